@@ -212,6 +212,52 @@ fn create_logical_plan(ctx: &mut ExecutionContext, query: usize) -> Result<Logic
                 l_linestatus;",
         ),
 
+        // original
+        // 2 => ctx.create_logical_plan(
+        //     "select
+        //         s_acctbal,
+        //         s_name,
+        //         n_name,
+        //         p_partkey,
+        //         p_mfgr,
+        //         s_address,
+        //         s_phone,
+        //         s_comment
+        //     from
+        //         part,
+        //         supplier,
+        //         partsupp,
+        //         nation,
+        //         region
+        //     where
+        //         p_partkey = ps_partkey
+        //         and s_suppkey = ps_suppkey
+        //         and p_size = 15
+        //         and p_type like '%BRASS'
+        //         and s_nationkey = n_nationkey
+        //         and n_regionkey = r_regionkey
+        //         and r_name = 'EUROPE'
+        //         and ps_supplycost = (
+        //             select
+        //                 min(ps_supplycost)
+        //             from
+        //                 partsupp,
+        //                 supplier,
+        //                 nation,
+        //                 region
+        //             where
+        //                 p_partkey = ps_partkey
+        //                 and s_suppkey = ps_suppkey
+        //                 and s_nationkey = n_nationkey
+        //                 and n_regionkey = r_regionkey
+        //                 and r_name = 'EUROPE'
+        //         )
+        //     order by
+        //         s_acctbal desc,
+        //         n_name,
+        //         s_name,
+        //         p_partkey;"
+        // ),
         2 => ctx.create_logical_plan(
             "select
                 s_acctbal,
@@ -224,8 +270,8 @@ fn create_logical_plan(ctx: &mut ExecutionContext, query: usize) -> Result<Logic
                 s_comment
             from
                 part,
-                supplier,
                 partsupp,
+                supplier,
                 nation,
                 region
             where
@@ -283,6 +329,30 @@ fn create_logical_plan(ctx: &mut ExecutionContext, query: usize) -> Result<Logic
                 o_orderdate;"
         ),
 
+        // original
+        // 4 => ctx.create_logical_plan(
+        //     "select
+        //         o_orderpriority,
+        //         count(*) as order_count
+        //     from
+        //         orders
+        //     where
+        //         o_orderdate >= date '1993-07-01'
+        //         and o_orderdate < date '1993-07-01' + interval '3' month
+        //         and exists (
+        //             select
+        //                 *
+        //             from
+        //                 lineitem
+        //             where
+        //                 l_orderkey = o_orderkey
+        //                 and l_commitdate < l_receiptdate
+        //         )
+        //     group by
+        //         o_orderpriority
+        //     order by
+        //         o_orderpriority;"
+        // ),
         4 => ctx.create_logical_plan(
             "select
                 o_orderpriority,
@@ -290,8 +360,8 @@ fn create_logical_plan(ctx: &mut ExecutionContext, query: usize) -> Result<Logic
             from
                 orders
             where
-                o_orderdate >= '1993-07-01'
-                and o_orderdate < date '1993-07-01' + interval '3' month
+                o_orderdate >= date '1993-07-01'
+                and o_orderdate < date '1993-10-01'
                 and exists (
                     select
                         *
@@ -864,6 +934,46 @@ fn create_logical_plan(ctx: &mut ExecutionContext, query: usize) -> Result<Logic
                 );"
         ),
 
+        // original
+        // 20 => ctx.create_logical_plan(
+        //     "select
+        //         s_name,
+        //         s_address
+        //     from
+        //         supplier,
+        //         nation
+        //     where
+        //         s_suppkey in (
+        //             select
+        //                 ps_suppkey
+        //             from
+        //                 partsupp
+        //             where
+        //                 ps_partkey in (
+        //                     select
+        //                         p_partkey
+        //                     from
+        //                         part
+        //                     where
+        //                         p_name like 'forest%'
+        //                 )
+        //                 and ps_availqty > (
+        //                     select
+        //                         0.5 * sum(l_quantity)
+        //                     from
+        //                         lineitem
+        //                     where
+        //                         l_partkey = ps_partkey
+        //                         and l_suppkey = ps_suppkey
+        //                         and l_shipdate >= date '1994-01-01'
+        //                         and l_shipdate < date '1994-01-01' + interval '1' year
+        //                 )
+        //         )
+        //         and s_nationkey = n_nationkey
+        //         and n_name = 'CANADA'
+        //     order by
+        //         s_name;"
+        // ),
         20 => ctx.create_logical_plan(
             "select
                 s_name,
@@ -895,7 +1005,7 @@ fn create_logical_plan(ctx: &mut ExecutionContext, query: usize) -> Result<Logic
                                 l_partkey = ps_partkey
                                 and l_suppkey = ps_suppkey
                                 and l_shipdate >= date '1994-01-01'
-                                and l_shipdate < 'date 1994-01-01' + interval '1' year
+                                and l_shipdate < date '1995-01-01'
                         )
                 )
                 and s_nationkey = n_nationkey
@@ -174,6 +174,43 @@ pub trait DataFrame {
         right_cols: &[&str],
     ) -> Result<Arc<dyn DataFrame>>;
 
+    /// Append the rows of another DataFrame to the rows of this DataFrame, keeping
+    /// duplicate rows (UNION ALL). Columns are matched by position and the result has
+    /// the column names of this DataFrame.
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new())?;
+    /// let d2 = df.clone();
+    /// let df = df.union(d2)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn union(&self, dataframe: Arc<dyn DataFrame>) -> Result<Arc<dyn DataFrame>>;
+
+    /// Append the rows of another DataFrame to the rows of this DataFrame, removing
+    /// duplicate rows (UNION). Columns are matched by position and the result has
+    /// the column names of this DataFrame.
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new())?;
+    /// let d2 = df.clone();
+    /// let df = df.union_distinct(d2)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn union_distinct(&self, dataframe: Arc<dyn DataFrame>)
+        -> Result<Arc<dyn DataFrame>>;
+
     /// Repartition a DataFrame based on a logical partitioning scheme.
     ///
     /// ```
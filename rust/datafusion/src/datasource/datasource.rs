@@ -41,6 +41,8 @@ pub struct Statistics {
 pub struct ColumnStatistics {
     /// Number of null values on column
     pub null_count: Option<usize>,
+    /// Number of distinct values on column
+    pub distinct_count: Option<usize>,
 }
 
 /// Indicates whether and how a filter expression can be handled by a
@@ -69,12 +71,15 @@ pub trait TableProvider {
     /// Get a reference to the schema for this table
     fn schema(&self) -> SchemaRef;
 
-    /// Create an ExecutionPlan that will scan the table.
+    /// Create an ExecutionPlan that will scan the table. The `limit` is a hint that
+    /// only the first `limit` rows are needed: the plan may stop reading once it has
+    /// produced them, but may also produce more rows.
     fn scan(
         &self,
         projection: &Option<Vec<usize>>,
         batch_size: usize,
         filters: &[Expr],
+        limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>>;
 
     /// Returns the table Statistics
@@ -0,0 +1,113 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Newline-delimited JSON data source
+//!
+//! This data source allows files with one JSON object per line to be used as input for
+//! queries.
+//!
+//! Example:
+//!
+//! ```
+//! use datafusion::datasource::TableProvider;
+//! use datafusion::datasource::json::{NdJsonTable, NdJsonReadOptions};
+//!
+//! let jsondata = NdJsonTable::try_new(
+//!     "tests/jsons",
+//!     NdJsonReadOptions::new().schema_infer_max_records(100),
+//! ).unwrap();
+//! let schema = jsondata.schema();
+//! ```
+
+use arrow::datatypes::SchemaRef;
+use std::any::Any;
+use std::string::String;
+use std::sync::Arc;
+
+use crate::datasource::datasource::Statistics;
+use crate::datasource::TableProvider;
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Expr;
+use crate::physical_plan::json::NdJsonExec;
+pub use crate::physical_plan::json::NdJsonReadOptions;
+use crate::physical_plan::{common, ExecutionPlan};
+
+/// Represents a newline-delimited JSON file, or a directory of them, with a provided
+/// or inferred schema
+pub struct NdJsonTable {
+    /// Path to a single JSON file or a directory containing one of more JSON files
+    path: String,
+    schema: SchemaRef,
+    file_extension: String,
+    statistics: Statistics,
+}
+
+impl NdJsonTable {
+    /// Attempt to initialize a new `NdJsonTable` from a file path
+    pub fn try_new(path: &str, options: NdJsonReadOptions) -> Result<Self> {
+        let schema = Arc::new(match options.schema {
+            Some(s) => s.clone(),
+            None => {
+                let mut filenames: Vec<String> = vec![];
+                common::build_file_list(path, &mut filenames, options.file_extension)?;
+                if filenames.is_empty() {
+                    return Err(DataFusionError::Plan("No files found".to_string()));
+                }
+                NdJsonExec::try_infer_schema(&filenames, &options)?
+            }
+        });
+
+        Ok(Self {
+            path: String::from(path),
+            schema,
+            file_extension: String::from(options.file_extension),
+            statistics: Statistics::default(),
+        })
+    }
+}
+
+impl TableProvider for NdJsonTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(NdJsonExec::try_new(
+            &self.path,
+            NdJsonReadOptions::new()
+                .schema(&self.schema)
+                .file_extension(self.file_extension.as_str()),
+            projection.clone(),
+            batch_size,
+            limit,
+        )?))
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.statistics.clone()
+    }
+}
@@ -0,0 +1,717 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Listing data source
+//!
+//! A listing table is made of all the files found recursively under a directory. The
+//! directories named `key=value` between the table directory and the files, as in the
+//! Hive layout `events/date=2020-12-01/region=us/part-0.parquet`, define partition
+//! columns. They are appended to the columns of the files, and the filters on them are
+//! used to skip the files of the partitions which cannot match. As written by Hive, the
+//! names and values of the partitions may escape characters as `%XX`, and the value
+//! `__HIVE_DEFAULT_PARTITION__` stands for NULL.
+//!
+//! Example:
+//!
+//! ```no_run
+//! use datafusion::datasource::TableProvider;
+//! use datafusion::datasource::listing::{ListingFileFormat, ListingTable};
+//!
+//! let table =
+//!     ListingTable::try_new("events", ListingFileFormat::Parquet, None).unwrap();
+//! // the columns of the files, followed by `date` and `region`
+//! let schema = table.schema();
+//! ```
+
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::string::String;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BooleanArray, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+
+use crate::datasource::datasource::{Statistics, TableProviderFilterPushDown};
+use crate::datasource::TableProvider;
+use crate::error::{DataFusionError, Result};
+use crate::execution::context::{ExecutionConfig, ExecutionContextState};
+use crate::logical_plan::Expr;
+use crate::optimizer::utils;
+use crate::physical_plan::csv::{CsvExec, CsvReadOptions};
+use crate::physical_plan::empty::EmptyExec;
+use crate::physical_plan::expressions::{col, lit};
+use crate::physical_plan::json::{NdJsonExec, NdJsonReadOptions};
+use crate::physical_plan::parquet::ParquetExec;
+use crate::physical_plan::planner::DefaultPhysicalPlanner;
+use crate::physical_plan::projection::ProjectionExec;
+use crate::physical_plan::union::UnionExec;
+use crate::physical_plan::{common, ColumnarValue, ExecutionPlan};
+use crate::scalar::ScalarValue;
+
+/// The format of the files of a [`ListingTable`]
+#[derive(Debug, Clone, Copy)]
+pub enum ListingFileFormat {
+    /// CSV files, with the given delimiter
+    Csv {
+        /// Whether the files have a header row
+        has_header: bool,
+        /// The column delimiter
+        delimiter: u8,
+    },
+    /// Parquet files
+    Parquet,
+    /// Newline-delimited JSON files
+    NdJson,
+}
+
+impl ListingFileFormat {
+    /// The extension of the files of this format, the other files are ignored
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            ListingFileFormat::Csv { .. } => ".csv",
+            ListingFileFormat::Parquet => ".parquet",
+            ListingFileFormat::NdJson => ".json",
+        }
+    }
+}
+
+/// The value of the partitions whose partition column is NULL
+const DEFAULT_PARTITION_VALUE: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// A file of a listing table, along with the values of the partition columns for it,
+/// `None` being NULL
+#[derive(Debug, Clone)]
+struct ListingFile {
+    path: String,
+    partition_values: Vec<Option<String>>,
+}
+
+/// Represents all the files of a given format under a directory, partitioned by the
+/// `key=value` directories containing them
+pub struct ListingTable {
+    format: ListingFileFormat,
+    /// The schema of the files
+    file_schema: SchemaRef,
+    /// The names of the partition columns, in the order of the directories
+    partition_columns: Vec<String>,
+    /// The schema of the files followed by the partition columns
+    schema: SchemaRef,
+    files: Vec<ListingFile>,
+    statistics: Statistics,
+}
+
+impl ListingTable {
+    /// Attempt to initialize a new `ListingTable` from the files found under `path`.
+    /// The schema of the files is inferred from them when `file_schema` is `None`.
+    pub fn try_new(
+        path: &str,
+        format: ListingFileFormat,
+        file_schema: Option<SchemaRef>,
+    ) -> Result<Self> {
+        let mut filenames: Vec<String> = vec![];
+        common::build_file_list(path, &mut filenames, format.file_extension())?;
+        if filenames.is_empty() {
+            return Err(DataFusionError::Plan("No files found".to_string()));
+        }
+        filenames.sort();
+
+        // all the files must be partitioned by the same columns
+        let mut partition_columns = None;
+        let mut files = Vec::with_capacity(filenames.len());
+        for filename in &filenames {
+            let (columns, values) = parse_partitions(path, filename)?;
+            match &partition_columns {
+                None => partition_columns = Some(columns),
+                Some(expected) if *expected != columns => {
+                    return Err(DataFusionError::Plan(format!(
+                        "The file {} is partitioned by {:?} instead of {:?}",
+                        filename, columns, expected
+                    )));
+                }
+                _ => {}
+            }
+            files.push(ListingFile {
+                path: filename.clone(),
+                partition_values: values,
+            });
+        }
+        let partition_columns = partition_columns.unwrap_or_default();
+
+        let file_schema = match file_schema {
+            Some(schema) => schema,
+            None => Arc::new(infer_schema(&filenames, format)?),
+        };
+        let mut fields = file_schema.fields().clone();
+        for (i, column) in partition_columns.iter().enumerate() {
+            if file_schema.field_with_name(column).is_ok() {
+                return Err(DataFusionError::Plan(format!(
+                    "The partition column {} is also a column of the files",
+                    column
+                )));
+            }
+            let nullable = files.iter().any(|file| file.partition_values[i].is_none());
+            fields.push(Field::new(column, DataType::Utf8, nullable));
+        }
+
+        Ok(Self {
+            format,
+            file_schema,
+            partition_columns,
+            schema: Arc::new(Schema::new(fields)),
+            files,
+            statistics: Statistics::default(),
+        })
+    }
+
+    /// The names of the partition columns, which are the last columns of the table
+    pub fn partition_columns(&self) -> &[String] {
+        &self.partition_columns
+    }
+
+    /// Whether `filter` only references partition columns and can be evaluated on
+    /// their values alone, in which case it is evaluated once per file
+    fn is_partition_filter(&self, filter: &Expr) -> Result<bool> {
+        let mut columns = HashSet::new();
+        utils::expr_to_column_names(filter, &mut columns)?;
+        Ok(!columns.is_empty()
+            && columns
+                .iter()
+                .all(|column| self.partition_columns.contains(column))
+            && is_context_free(filter)?)
+    }
+
+    /// Returns the files whose partition values match all the partition filters
+    fn prune_files(&self, filters: &[Expr]) -> Result<Vec<ListingFile>> {
+        let mut partition_filters = vec![];
+        for filter in filters {
+            if self.is_partition_filter(filter)? {
+                partition_filters.push(filter);
+            }
+        }
+        if partition_filters.is_empty() {
+            return Ok(self.files.clone());
+        }
+
+        // the filters are evaluated against a batch with one row of partition values per
+        // file
+        let schema =
+            Schema::new(self.schema.fields()[self.file_schema.fields().len()..].to_vec());
+        let columns = (0..self.partition_columns.len())
+            .map(|i| {
+                let values = self
+                    .files
+                    .iter()
+                    .map(|file| file.partition_values[i].as_deref())
+                    .collect::<Vec<_>>();
+                Arc::new(StringArray::from(values)) as ArrayRef
+            })
+            .collect();
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), columns)?;
+
+        let ctx_state = ExecutionContextState {
+            datasources: HashMap::new(),
+            scalar_functions: HashMap::new(),
+            var_provider: HashMap::new(),
+            aggregate_functions: HashMap::new(),
+            config: ExecutionConfig::new(),
+        };
+        let mut keep = vec![true; self.files.len()];
+        for filter in partition_filters {
+            let physical_expr = DefaultPhysicalPlanner::default()
+                .create_physical_expr(filter, &schema, &ctx_state)?;
+            let result = match physical_expr.evaluate(&batch)? {
+                ColumnarValue::Array(array) => array,
+                ColumnarValue::Scalar(value) => value.to_array_of_size(batch.num_rows()),
+            };
+            let result =
+                result
+                    .as_any()
+                    .downcast_ref::<BooleanArray>()
+                    .ok_or_else(|| {
+                        DataFusionError::Plan(format!(
+                            "The partition filter {:?} is not a boolean expression",
+                            filter
+                        ))
+                    })?;
+            // a null result does not match
+            for (i, keep) in keep.iter_mut().enumerate() {
+                *keep = *keep && result.is_valid(i) && result.value(i);
+            }
+        }
+
+        Ok(self
+            .files
+            .iter()
+            .zip(keep)
+            .filter_map(|(file, keep)| if keep { Some(file.clone()) } else { None })
+            .collect())
+    }
+
+    /// Creates the plan reading the columns `projection` of the file `path`
+    fn scan_file(
+        &self,
+        path: &str,
+        projection: Vec<usize>,
+        predicate: Option<Expr>,
+        batch_size: usize,
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(match self.format {
+            ListingFileFormat::Csv {
+                has_header,
+                delimiter,
+            } => Arc::new(CsvExec::try_new(
+                path,
+                CsvReadOptions::new()
+                    .schema(&self.file_schema)
+                    .has_header(has_header)
+                    .delimiter(delimiter),
+                Some(projection),
+                batch_size,
+                limit,
+            )?),
+            ListingFileFormat::Parquet => Arc::new(ParquetExec::try_from_files(
+                &[path],
+                Some(projection),
+                predicate,
+                batch_size,
+                limit,
+            )?),
+            ListingFileFormat::NdJson => Arc::new(NdJsonExec::try_new(
+                path,
+                NdJsonReadOptions::new().schema(&self.file_schema),
+                Some(projection),
+                batch_size,
+                limit,
+            )?),
+        })
+    }
+}
+
+/// Whether `expr` can be evaluated without the state of an execution context, i.e.
+/// without variables, subqueries, aggregates or window functions. The user-defined
+/// functions carry their implementation, so they do not need the context.
+fn is_context_free(expr: &Expr) -> Result<bool> {
+    match expr {
+        Expr::ScalarVariable(_)
+        | Expr::Exists { .. }
+        | Expr::InSubquery { .. }
+        | Expr::ScalarSubquery(_)
+        | Expr::AggregateFunction { .. }
+        | Expr::AggregateUDF { .. }
+        | Expr::WindowFunction { .. } => Ok(false),
+        _ => {
+            for expr in utils::expr_sub_expressions(expr)? {
+                if !is_context_free(&expr)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+    }
+}
+
+/// Decodes the characters escaped as `%XX` in the name or value of a partition, the
+/// other characters being kept as they are
+fn unescape_partition(escaped: &str) -> String {
+    let bytes = escaped.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = escaped
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.bytes().all(|byte| byte.is_ascii_hexdigit()));
+        match (bytes[i], hex) {
+            (b'%', Some(hex)) => {
+                // the two digits are hexadecimal
+                unescaped.push(u8::from_str_radix(hex, 16).unwrap());
+                i += 3;
+            }
+            (byte, _) => {
+                unescaped.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+/// Returns the names and values of the partition columns of the file `filename`, from
+/// the `key=value` directories between `path` and the file
+fn parse_partitions(
+    path: &str,
+    filename: &str,
+) -> Result<(Vec<String>, Vec<Option<String>>)> {
+    let relative = Path::new(filename).strip_prefix(path).map_err(|_| {
+        DataFusionError::Plan(format!("The file {} is not under {}", filename, path))
+    })?;
+    let mut columns = vec![];
+    let mut values = vec![];
+    // the last component is the file itself
+    if let Some(directories) = relative.parent() {
+        for directory in directories.iter() {
+            let directory = directory
+                .to_str()
+                .ok_or_else(|| DataFusionError::Plan("Invalid path".to_string()))?;
+            if let Some(position) = directory.find('=') {
+                columns.push(unescape_partition(&directory[..position]));
+                let value = &directory[position + 1..];
+                values.push(if value == DEFAULT_PARTITION_VALUE {
+                    None
+                } else {
+                    Some(unescape_partition(value))
+                });
+            }
+        }
+    }
+    Ok((columns, values))
+}
+
+/// Infers the schema of the files of a listing table
+fn infer_schema(filenames: &[String], format: ListingFileFormat) -> Result<Schema> {
+    match format {
+        ListingFileFormat::Csv {
+            has_header,
+            delimiter,
+        } => CsvExec::try_infer_schema(
+            filenames,
+            &CsvReadOptions::new()
+                .has_header(has_header)
+                .delimiter(delimiter),
+        ),
+        ListingFileFormat::Parquet => {
+            let filenames = filenames.iter().map(|f| f.as_str()).collect::<Vec<_>>();
+            let exec = ParquetExec::try_from_files(&filenames, None, None, 0, None)?;
+            Ok(exec.schema().as_ref().clone())
+        }
+        ListingFileFormat::NdJson => {
+            NdJsonExec::try_infer_schema(filenames, &NdJsonReadOptions::new())
+        }
+    }
+}
+
+impl TableProvider for ListingTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Scan the files of the partitions matching the filters, with one partition per
+    /// file. The partition columns are added to the columns read from each file.
+    fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let projection = projection
+            .clone()
+            .unwrap_or_else(|| (0..self.schema.fields().len()).collect());
+        let files = self.prune_files(filters)?;
+        if files.is_empty() {
+            let projected_schema = Schema::new(
+                projection
+                    .iter()
+                    .map(|i| self.schema.field(*i).clone())
+                    .collect(),
+            );
+            return Ok(Arc::new(EmptyExec::new(false, Arc::new(projected_schema))));
+        }
+
+        let num_file_columns = self.file_schema.fields().len();
+        let mut file_projection = projection
+            .iter()
+            .filter(|i| **i < num_file_columns)
+            .cloned()
+            .collect::<Vec<_>>();
+        // a column is still read to know the number of rows of the files
+        if file_projection.is_empty() {
+            file_projection.push(0);
+        }
+
+        // the filters on the columns of the files may be used to skip parts of them
+        let mut predicate: Option<Expr> = None;
+        if let ListingFileFormat::Parquet = self.format {
+            for filter in filters {
+                let mut columns = HashSet::new();
+                utils::expr_to_column_names(filter, &mut columns)?;
+                if columns
+                    .iter()
+                    .all(|column| self.file_schema.field_with_name(column).is_ok())
+                {
+                    predicate = Some(match predicate {
+                        Some(predicate) => predicate.and(filter.clone()),
+                        None => filter.clone(),
+                    });
+                }
+            }
+        }
+
+        let inputs = files
+            .iter()
+            .map(|file| {
+                let input = self.scan_file(
+                    &file.path,
+                    file_projection.clone(),
+                    predicate.clone(),
+                    batch_size,
+                    limit,
+                )?;
+                let exprs = projection
+                    .iter()
+                    .map(|i| {
+                        let name = self.schema.field(*i).name().clone();
+                        let expr = if *i < num_file_columns {
+                            col(&name)
+                        } else {
+                            let value = &file.partition_values[*i - num_file_columns];
+                            lit(ScalarValue::Utf8(value.clone()))
+                        };
+                        (expr, name)
+                    })
+                    .collect();
+                Ok(Arc::new(ProjectionExec::try_new(exprs, input)?)
+                    as Arc<dyn ExecutionPlan>)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(UnionExec::new(inputs)))
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.statistics.clone()
+    }
+
+    /// The filters on the partition columns that need no execution context are fully
+    /// applied by skipping files. The other filters are used to skip row groups of
+    /// Parquet files, and still need to be applied.
+    fn supports_filter_pushdown(
+        &self,
+        filter: &Expr,
+    ) -> Result<TableProviderFilterPushDown> {
+        if self.is_partition_filter(filter)? {
+            Ok(TableProviderFilterPushDown::Exact)
+        } else if let ListingFileFormat::Parquet = self.format {
+            Ok(TableProviderFilterPushDown::Inexact)
+        } else {
+            Ok(TableProviderFilterPushDown::Unsupported)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, create_udf, in_subquery, lit, LogicalPlanBuilder};
+    use crate::physical_plan::collect;
+    use crate::physical_plan::functions::ScalarFunctionImplementation;
+    use crate::test::format_batch;
+    use std::fs::{create_dir_all, File};
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Writes CSV files partitioned by date and region to `dir`
+    fn write_partitioned_csv(dir: &TempDir) -> Result<String> {
+        let files = vec![
+            ("date=2020-12-01/region=eu", "a,b\n1,x\n2,y\n"),
+            ("date=2020-12-01/region=us", "a,b\n3,z\n"),
+            ("date=2020-12-02/region=us", "a,b\n4,x\n5,y\n"),
+        ];
+        for (directory, content) in files {
+            let directory = dir.path().join(directory);
+            create_dir_all(&directory)?;
+            let mut file = File::create(directory.join("part-0.csv"))?;
+            file.write_all(content.as_bytes())?;
+        }
+        Ok(dir.path().to_str().unwrap().to_string())
+    }
+
+    fn csv_format() -> ListingFileFormat {
+        ListingFileFormat::Csv {
+            has_header: true,
+            delimiter: b',',
+        }
+    }
+
+    async fn scan(
+        table: &ListingTable,
+        projection: Option<Vec<usize>>,
+        filters: &[Expr],
+    ) -> Result<Vec<String>> {
+        let plan = table.scan(&projection, 1024, filters, None)?;
+        let batches = collect(plan).await?;
+        let mut rows = batches.iter().flat_map(format_batch).collect::<Vec<_>>();
+        rows.sort();
+        Ok(rows)
+    }
+
+    #[tokio::test]
+    async fn partition_columns() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = write_partitioned_csv(&dir)?;
+        let table = ListingTable::try_new(&path, csv_format(), None)?;
+
+        assert_eq!(table.partition_columns(), &["date", "region"]);
+        let schema = table.schema();
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| format!("{}: {:?}", f.name(), f.data_type()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            columns,
+            vec!["a: Int64", "b: Utf8", "date: Utf8", "region: Utf8"]
+        );
+
+        let rows = scan(&table, None, &[]).await?;
+        let expected = vec![
+            "1,x,2020-12-01,eu",
+            "2,y,2020-12-01,eu",
+            "3,z,2020-12-01,us",
+            "4,x,2020-12-02,us",
+            "5,y,2020-12-02,us",
+        ];
+        assert_eq!(rows, expected);
+
+        // the partition columns may be projected alone
+        let rows = scan(&table, Some(vec![3, 0]), &[]).await?;
+        let expected = vec!["eu,1", "eu,2", "us,3", "us,4", "us,5"];
+        assert_eq!(rows, expected);
+        let rows = scan(&table, Some(vec![2]), &[]).await?;
+        assert_eq!(rows.len(), 5);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn partition_pruning() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = write_partitioned_csv(&dir)?;
+        let table = ListingTable::try_new(&path, csv_format(), None)?;
+
+        let filter = col("region").eq(lit("us"));
+        assert!(matches!(
+            table.supports_filter_pushdown(&filter)?,
+            TableProviderFilterPushDown::Exact
+        ));
+        let plan = table.scan(&None, 1024, &[filter.clone()], None)?;
+        assert_eq!(plan.output_partitioning().partition_count(), 2);
+
+        let filters = vec![filter, col("date").gt(lit("2020-12-01"))];
+        let rows = scan(&table, Some(vec![0, 2]), &filters).await?;
+        assert_eq!(rows, vec!["4,2020-12-02", "5,2020-12-02"]);
+
+        // no partition matches
+        let rows = scan(&table, None, &[col("region").eq(lit("asia"))]).await?;
+        assert!(rows.is_empty());
+
+        // the filters on the columns of CSV files are not used
+        let filter = col("a").eq(lit(1i64));
+        assert!(matches!(
+            table.supports_filter_pushdown(&filter)?,
+            TableProviderFilterPushDown::Unsupported
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn partition_pruning_with_udf() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = write_partitioned_csv(&dir)?;
+        let table = ListingTable::try_new(&path, csv_format(), None)?;
+
+        // the function is not registered anywhere, it is part of the expression
+        let fun: ScalarFunctionImplementation =
+            Arc::new(|args: &[ArrayRef]| Ok(args[0].clone()));
+        let identity = create_udf(
+            "identity",
+            vec![DataType::Utf8],
+            Arc::new(DataType::Utf8),
+            fun,
+        );
+        let filter = identity.call(vec![col("region")]).eq(lit("eu"));
+        assert!(matches!(
+            table.supports_filter_pushdown(&filter)?,
+            TableProviderFilterPushDown::Exact
+        ));
+        let rows = scan(&table, Some(vec![0, 3]), &[filter]).await?;
+        assert_eq!(rows, vec!["1,eu", "2,eu"]);
+
+        // a subquery needs the context, so it is not used to skip files
+        let subquery = LogicalPlanBuilder::empty(true)
+            .project(vec![lit("eu").alias("region")])?
+            .build()?;
+        let filter = in_subquery(col("region"), subquery);
+        assert!(matches!(
+            table.supports_filter_pushdown(&filter)?,
+            TableProviderFilterPushDown::Unsupported
+        ));
+        let rows = scan(&table, Some(vec![0]), &[filter]).await?;
+        assert_eq!(rows.len(), 5);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn escaped_and_default_partitions() -> Result<()> {
+        let dir = TempDir::new()?;
+        let files = vec![
+            ("city=New%20York", "a\n1\n"),
+            ("city=a%3Db%2Fc", "a\n2\n"),
+            ("city=__HIVE_DEFAULT_PARTITION__", "a\n3\n"),
+            // an invalid escape is kept as it is
+            ("city=100%", "a\n4\n"),
+        ];
+        for (directory, content) in files {
+            let directory = dir.path().join(directory);
+            create_dir_all(&directory)?;
+            File::create(directory.join("part-0.csv"))?.write_all(content.as_bytes())?;
+        }
+        let path = dir.path().to_str().unwrap();
+        let table = ListingTable::try_new(path, csv_format(), None)?;
+        assert!(table.schema().field_with_name("city")?.is_nullable());
+
+        let rows = scan(&table, None, &[]).await?;
+        assert_eq!(rows, vec!["1,New York", "2,a=b/c", "3,NULL", "4,100%"]);
+
+        let rows = scan(&table, None, &[col("city").eq(lit("New York"))]).await?;
+        assert_eq!(rows, vec!["1,New York"]);
+        let rows = scan(&table, None, &[col("city").is_null()]).await?;
+        assert_eq!(rows, vec!["3,NULL"]);
+        Ok(())
+    }
+
+    #[test]
+    fn inconsistent_partitions() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = write_partitioned_csv(&dir)?;
+        let directory = dir.path().join("date=2020-12-03");
+        create_dir_all(&directory)?;
+        File::create(directory.join("part-0.csv"))?.write_all(b"a,b\n6,z\n")?;
+
+        match ListingTable::try_new(&path, csv_format(), None) {
+            Err(DataFusionError::Plan(message)) => {
+                assert!(message.contains("is partitioned by [\"date\"]"))
+            }
+            _ => panic!("The partitions should be inconsistent"),
+        }
+        Ok(())
+    }
+}
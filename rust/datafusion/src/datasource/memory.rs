@@ -21,6 +21,7 @@
 
 use log::debug;
 use std::any::Any;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use arrow::datatypes::{Field, Schema, SchemaRef};
@@ -31,6 +32,7 @@ use crate::datasource::TableProvider;
 use crate::error::{DataFusionError, Result};
 use crate::logical_plan::Expr;
 use crate::physical_plan::common;
+use crate::physical_plan::hash_join::create_key;
 use crate::physical_plan::memory::MemoryExec;
 use crate::physical_plan::ExecutionPlan;
 
@@ -65,8 +67,10 @@ fn calculate_statistics(
     let column_statistics = Some(
         null_count
             .iter()
-            .map(|null_count| ColumnStatistics {
+            .enumerate()
+            .map(|(i, null_count)| ColumnStatistics {
                 null_count: Some(*null_count),
+                distinct_count: distinct_count(partitions, i),
             })
             .collect(),
     );
@@ -78,6 +82,22 @@ fn calculate_statistics(
     }
 }
 
+// Counts the distinct non-null values of the column `i`, if its type can be hashed
+fn distinct_count(partitions: &[Vec<RecordBatch>], i: usize) -> Option<usize> {
+    let mut values = HashSet::new();
+    let mut key = Vec::new();
+    for batch in partitions.iter().flatten() {
+        let column = [batch.column(i).clone()];
+        for row in 0..batch.num_rows() {
+            if column[0].is_valid(row) {
+                create_key(&column, row, &mut key).ok()?;
+                values.insert(key.clone());
+            }
+        }
+    }
+    Some(values.len())
+}
+
 impl MemTable {
     /// Create a new in-memory table from the provided schema and record batches
     pub fn try_new(schema: SchemaRef, partitions: Vec<Vec<RecordBatch>>) -> Result<Self> {
@@ -104,7 +124,7 @@ impl MemTable {
     /// Create a mem table by reading from another data source
     pub async fn load(t: &dyn TableProvider, batch_size: usize) -> Result<Self> {
         let schema = t.schema();
-        let exec = t.scan(&None, batch_size, &[])?;
+        let exec = t.scan(&None, batch_size, &[], None)?;
         let partition_count = exec.output_partitioning().partition_count();
 
         let tasks = (0..partition_count)
@@ -144,6 +164,7 @@ impl TableProvider for MemTable {
         projection: &Option<Vec<usize>>,
         _batch_size: usize,
         _filters: &[Expr],
+        limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         let columns: Vec<usize> = match projection {
             Some(p) => p.clone(),
@@ -172,8 +193,29 @@ impl TableProvider for MemTable {
 
         let projected_schema = Arc::new(Schema::new(projected_columns?));
 
+        // only the first batches of each partition holding `limit` rows are read
+        let batches = match limit {
+            Some(limit) => self
+                .batches
+                .iter()
+                .map(|partition| {
+                    let mut num_rows = 0;
+                    partition
+                        .iter()
+                        .take_while(|batch| {
+                            let needed = num_rows < limit;
+                            num_rows += batch.num_rows();
+                            needed
+                        })
+                        .cloned()
+                        .collect()
+                })
+                .collect(),
+            None => self.batches.clone(),
+        };
+
         Ok(Arc::new(MemoryExec::try_new(
-            &self.batches.clone(),
+            &batches,
             projected_schema,
             projection.clone(),
         )?))
@@ -187,7 +229,7 @@ impl TableProvider for MemTable {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use arrow::array::Int32Array;
+    use arrow::array::{Int32Array, StringArray};
     use arrow::datatypes::{DataType, Field, Schema};
     use futures::StreamExt;
 
@@ -217,22 +259,26 @@ mod tests {
             provider.statistics().column_statistics,
             Some(vec![
                 ColumnStatistics {
-                    null_count: Some(0)
+                    null_count: Some(0),
+                    distinct_count: Some(3),
                 },
                 ColumnStatistics {
-                    null_count: Some(0)
+                    null_count: Some(0),
+                    distinct_count: Some(3),
                 },
                 ColumnStatistics {
-                    null_count: Some(0)
+                    null_count: Some(0),
+                    distinct_count: Some(3),
                 },
                 ColumnStatistics {
-                    null_count: Some(2)
+                    null_count: Some(2),
+                    distinct_count: Some(1),
                 },
             ])
         );
 
         // scan with projection
-        let exec = provider.scan(&Some(vec![2, 1]), 1024, &[])?;
+        let exec = provider.scan(&Some(vec![2, 1]), 1024, &[], None)?;
         let mut it = exec.execute(0).await?;
         let batch2 = it.next().await.unwrap()?;
         assert_eq!(2, batch2.schema().fields().len());
@@ -262,7 +308,7 @@ mod tests {
 
         let provider = MemTable::try_new(schema, vec![vec![batch]])?;
 
-        let exec = provider.scan(&None, 1024, &[])?;
+        let exec = provider.scan(&None, 1024, &[], None)?;
         let mut it = exec.execute(0).await?;
         let batch1 = it.next().await.unwrap()?;
         assert_eq!(3, batch1.schema().fields().len());
@@ -271,6 +317,75 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_distinct_count_of_partitions() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+
+        let batch1 = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(2)])),
+                Arc::new(StringArray::from(vec!["x", "y", "x"])),
+            ],
+        )?;
+        let batch2 = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(2), Some(3), None])),
+                Arc::new(StringArray::from(vec!["y", "y", "y"])),
+            ],
+        )?;
+
+        let provider = MemTable::try_new(schema, vec![vec![batch1], vec![batch2]])?;
+
+        // the values are counted once across partitions, and nulls are not counted
+        assert_eq!(
+            provider.statistics().column_statistics,
+            Some(vec![
+                ColumnStatistics {
+                    null_count: Some(2),
+                    distinct_count: Some(3),
+                },
+                ColumnStatistics {
+                    null_count: Some(0),
+                    distinct_count: Some(2),
+                },
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_with_limit() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )?;
+
+        let provider = MemTable::try_new(
+            schema,
+            vec![
+                vec![batch.clone(), batch.clone(), batch.clone()],
+                vec![batch],
+            ],
+        )?;
+
+        // the batches after the first 4 rows of each partition are not read
+        let exec = provider.scan(&None, 1024, &[], Some(4))?;
+        let batches = common::collect(exec.execute(0).await?).await?;
+        assert_eq!(batches.len(), 2);
+        let batches = common::collect(exec.execute(1).await?).await?;
+        assert_eq!(batches.len(), 1);
+
+        Ok(())
+    }
+
     #[test]
     fn test_invalid_projection() -> Result<()> {
         let schema = Arc::new(Schema::new(vec![
@@ -292,7 +407,7 @@ mod tests {
 
         let projection: Vec<usize> = vec![0, 4];
 
-        match provider.scan(&Some(projection), 1024, &[]) {
+        match provider.scan(&Some(projection), 1024, &[], None) {
             Err(DataFusionError::Internal(e)) => {
                 assert_eq!("\"Projection index out of range\"", format!("{:?}", e))
             }
@@ -20,9 +20,13 @@
 pub mod csv;
 pub mod datasource;
 pub mod empty;
+pub mod json;
+pub mod listing;
 pub mod memory;
 pub mod parquet;
 
 pub use self::csv::{CsvFile, CsvReadOptions};
 pub use self::datasource::TableProvider;
+pub use self::json::{NdJsonReadOptions, NdJsonTable};
+pub use self::listing::{ListingFileFormat, ListingTable};
 pub use self::memory::MemTable;
@@ -23,7 +23,7 @@ use std::sync::Arc;
 
 use arrow::datatypes::*;
 
-use crate::datasource::datasource::Statistics;
+use crate::datasource::datasource::{Statistics, TableProviderFilterPushDown};
 use crate::datasource::TableProvider;
 use crate::error::Result;
 use crate::logical_plan::Expr;
@@ -40,7 +40,7 @@ pub struct ParquetTable {
 impl ParquetTable {
     /// Attempt to initialize a new `ParquetTable` from a file path.
     pub fn try_new(path: &str) -> Result<Self> {
-        let parquet_exec = ParquetExec::try_from_path(path, None, 0)?;
+        let parquet_exec = ParquetExec::try_from_path(path, None, None, 0, None)?;
         let schema = parquet_exec.schema();
         Ok(Self {
             path: path.to_string(),
@@ -61,23 +61,38 @@ impl TableProvider for ParquetTable {
     }
 
     /// Scan the file(s), using the provided projection, and return one BatchIterator per
-    /// partition.
+    /// partition. The row groups which cannot match the filters are skipped.
     fn scan(
         &self,
         projection: &Option<Vec<usize>>,
         batch_size: usize,
-        _filters: &[Expr],
+        filters: &[Expr],
+        limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
+        let predicate = filters.split_first().map(|(first, rest)| {
+            rest.iter().fold(first.clone(), |acc, f| acc.and(f.clone()))
+        });
         Ok(Arc::new(ParquetExec::try_from_path(
             &self.path,
             projection.clone(),
+            predicate,
             batch_size,
+            limit,
         )?))
     }
 
     fn statistics(&self) -> Statistics {
         self.statistics.clone()
     }
+
+    /// The filters are used to skip row groups, the remaining rows still need to be
+    /// filtered
+    fn supports_filter_pushdown(
+        &self,
+        _filter: &Expr,
+    ) -> Result<TableProviderFilterPushDown> {
+        Ok(TableProviderFilterPushDown::Inexact)
+    }
 }
 
 #[cfg(test)]
@@ -94,7 +109,7 @@ mod tests {
     async fn read_small_batches() -> Result<()> {
         let table = load_table("alltypes_plain.parquet")?;
         let projection = None;
-        let exec = table.scan(&projection, 2, &[])?;
+        let exec = table.scan(&projection, 2, &[], None)?;
         let stream = exec.execute(0).await?;
 
         let count = stream
@@ -318,7 +333,7 @@ mod tests {
         table: Box<dyn TableProvider>,
         projection: &Option<Vec<usize>>,
     ) -> Result<RecordBatch> {
-        let exec = table.scan(projection, 1024, &[])?;
+        let exec = table.scan(projection, 1024, &[], None)?;
         let mut it = exec.execute(0).await?;
         it.next()
             .await
@@ -31,8 +31,10 @@ use futures::{StreamExt, TryStreamExt};
 use tokio::task::{self, JoinHandle};
 
 use arrow::csv;
+use arrow::datatypes::Schema;
 
 use crate::datasource::csv::CsvFile;
+use crate::datasource::json::NdJsonTable;
 use crate::datasource::parquet::ParquetTable;
 use crate::datasource::TableProvider;
 use crate::error::{DataFusionError, Result};
@@ -40,10 +42,17 @@ use crate::execution::dataframe_impl::DataFrameImpl;
 use crate::logical_plan::{
     FunctionRegistry, LogicalPlan, LogicalPlanBuilder, ToDFSchema,
 };
+use crate::optimizer::common_subexpr_eliminate::CommonSubexprEliminate;
+use crate::optimizer::constant_folding::ConstantFolding;
+use crate::optimizer::decorrelate_subquery::DecorrelateSubquery;
 use crate::optimizer::filter_push_down::FilterPushDown;
+use crate::optimizer::join_reorder::JoinReorder;
+use crate::optimizer::limit_push_down::LimitPushDown;
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::projection_push_down::ProjectionPushDown;
+use crate::optimizer::top_k::TopK;
 use crate::physical_plan::csv::CsvReadOptions;
+use crate::physical_plan::json::NdJsonReadOptions;
 use crate::physical_plan::planner::DefaultPhysicalPlanner;
 use crate::physical_plan::udf::ScalarUDF;
 use crate::physical_plan::ExecutionPlan;
@@ -146,10 +155,18 @@ impl ExecutionContext {
                     let plan = LogicalPlanBuilder::empty(false).build()?;
                     Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
                 }
-                _ => Err(DataFusionError::NotImplemented(format!(
-                    "Unsupported file type {:?}.",
-                    file_type
-                ))),
+                FileType::NdJson => {
+                    // the schema is inferred from the files when no columns are declared
+                    let schema: Schema = schema.as_ref().to_owned().into();
+                    let options = if schema.fields().is_empty() {
+                        NdJsonReadOptions::new()
+                    } else {
+                        NdJsonReadOptions::new().schema(&schema)
+                    };
+                    self.register_json(name, location, options)?;
+                    let plan = LogicalPlanBuilder::empty(false).build()?;
+                    Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
+                }
             },
 
             plan => Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan))),
@@ -216,6 +233,18 @@ impl ExecutionContext {
         )))
     }
 
+    /// Creates a DataFrame for reading a newline-delimited JSON data source.
+    pub fn read_json(
+        &mut self,
+        filename: &str,
+        options: NdJsonReadOptions,
+    ) -> Result<Arc<dyn DataFrame>> {
+        Ok(Arc::new(DataFrameImpl::new(
+            self.state.clone(),
+            &LogicalPlanBuilder::scan_json(&filename, options, None)?.build()?,
+        )))
+    }
+
     /// Creates a DataFrame for reading a Parquet data source.
     pub fn read_parquet(&mut self, filename: &str) -> Result<Arc<dyn DataFrame>> {
         Ok(Arc::new(DataFrameImpl::new(
@@ -236,6 +265,7 @@ impl ExecutionContext {
             projected_schema: schema.to_dfschema_ref()?,
             projection: None,
             filters: vec![],
+            limit: None,
         };
         Ok(Arc::new(DataFrameImpl::new(
             self.state.clone(),
@@ -255,6 +285,18 @@ impl ExecutionContext {
         Ok(())
     }
 
+    /// Register a newline-delimited JSON data source so that it can be referenced from
+    /// SQL statements executed against this context.
+    pub fn register_json(
+        &mut self,
+        name: &str,
+        filename: &str,
+        options: NdJsonReadOptions,
+    ) -> Result<()> {
+        self.register_table(name, Box::new(NdJsonTable::try_new(filename, options)?));
+        Ok(())
+    }
+
     /// Register a Parquet data source so that it can be referenced from SQL statements
     /// executed against this context.
     pub fn register_parquet(&mut self, name: &str, filename: &str) -> Result<()> {
@@ -290,6 +332,7 @@ impl ExecutionContext {
                     projected_schema: schema.to_dfschema_ref()?,
                     projection: None,
                     filters: vec![],
+                    limit: None,
                 };
                 Ok(Arc::new(DataFrameImpl::new(
                     self.state.clone(),
@@ -318,9 +361,15 @@ impl ExecutionContext {
     pub fn optimize(&self, plan: &LogicalPlan) -> Result<LogicalPlan> {
         // Apply standard rewrites and optimizations
         debug!("Logical plan:\n {:?}", plan);
-        let mut plan = ProjectionPushDown::new().optimize(&plan)?;
+        let mut plan = DecorrelateSubquery::new().optimize(&plan)?;
+        plan = ConstantFolding::new().optimize(&plan)?;
+        plan = CommonSubexprEliminate::new().optimize(&plan)?;
+        plan = ProjectionPushDown::new().optimize(&plan)?;
         plan = FilterPushDown::new().optimize(&plan)?;
+        plan = JoinReorder::new().optimize(&plan)?;
         plan = HashBuildProbeOrder::new().optimize(&plan)?;
+        plan = LimitPushDown::new().optimize(&plan)?;
+        plan = TopK::new().optimize(&plan)?;
         debug!("Optimized logical plan:\n {:?}", plan);
 
         self.state
@@ -485,6 +534,22 @@ pub struct ExecutionConfig {
     pub batch_size: usize,
     /// Responsible for planning `LogicalPlan`s, and `ExecutionPlan`
     query_planner: Arc<dyn QueryPlanner + Send + Sync>,
+    /// Whether joins are executed in parallel, by repartitioning both sides of the join
+    /// on the join keys into `concurrency` partitions
+    pub repartition_joins: bool,
+    /// Whether aggregates are executed in parallel, by repartitioning the partial
+    /// aggregates on the grouping keys into `concurrency` partitions
+    pub repartition_aggregations: bool,
+    /// Whether joins are executed by sorting both sides on the join keys and merging
+    /// them, instead of hashing the left side. Joins of sides already sorted on the
+    /// join keys are always merged.
+    pub sort_merge_joins: bool,
+    /// Number of bytes that each sort or aggregate keeps in memory before spilling to
+    /// disk, respectively its buffered input or the states of its groups. The limit
+    /// is not shared: it applies to each of these operators and each of their
+    /// partitions on its own, so that a query may use several times the limit. The
+    /// memory is not limited by default.
+    pub memory_limit: usize,
 }
 
 impl ExecutionConfig {
@@ -494,6 +559,10 @@ impl ExecutionConfig {
             concurrency: num_cpus::get(),
             batch_size: 4096,
             query_planner: Arc::new(DefaultQueryPlanner {}),
+            repartition_joins: true,
+            repartition_aggregations: true,
+            sort_merge_joins: false,
+            memory_limit: usize::MAX,
         }
     }
 
@@ -521,6 +590,32 @@ impl ExecutionConfig {
         self.query_planner = query_planner;
         self
     }
+
+    /// Enables or disables the repartitioning of joins on their keys
+    pub fn with_repartition_joins(mut self, enabled: bool) -> Self {
+        self.repartition_joins = enabled;
+        self
+    }
+
+    /// Enables or disables the repartitioning of aggregates on their grouping keys
+    pub fn with_repartition_aggregations(mut self, enabled: bool) -> Self {
+        self.repartition_aggregations = enabled;
+        self
+    }
+
+    /// Enables or disables sort-merge joins
+    pub fn with_sort_merge_joins(mut self, enabled: bool) -> Self {
+        self.sort_merge_joins = enabled;
+        self
+    }
+
+    /// Customize the memory limit, in bytes, past which operators spill to disk
+    pub fn with_memory_limit(mut self, n: usize) -> Self {
+        // memory limit must be greater than zero
+        assert!(n > 0);
+        self.memory_limit = n;
+        self
+    }
 }
 
 /// Execution context for registering data sources and executing queries
@@ -112,6 +112,26 @@ impl DataFrame for DataFrameImpl {
         Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
     }
 
+    /// Union with another DataFrame, keeping duplicate rows
+    fn union(&self, dataframe: Arc<dyn DataFrame>) -> Result<Arc<dyn DataFrame>> {
+        let plan = LogicalPlanBuilder::from(&self.plan)
+            .union(&dataframe.to_logical_plan())?
+            .build()?;
+        Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
+    }
+
+    /// Union with another DataFrame, removing duplicate rows
+    fn union_distinct(
+        &self,
+        dataframe: Arc<dyn DataFrame>,
+    ) -> Result<Arc<dyn DataFrame>> {
+        let plan = LogicalPlanBuilder::from(&self.plan)
+            .union(&dataframe.to_logical_plan())?
+            .distinct()?
+            .build()?;
+        Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
+    }
+
     fn repartition(
         &self,
         partitioning_scheme: Partitioning,
@@ -242,6 +262,21 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn union() -> Result<()> {
+        let df = test_table()?.select_columns(vec!["c1"])?;
+        let union = df.union(df.clone())?;
+        let rows = union.collect().await?;
+        let row_count: usize = rows.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(200, row_count);
+
+        let union_distinct = df.union_distinct(df.clone())?;
+        let rows = union_distinct.collect().await?;
+        let row_count: usize = rows.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(5, row_count);
+        Ok(())
+    }
+
     #[test]
     fn limit() -> Result<()> {
         // build query using Table API
@@ -140,7 +140,9 @@
 //! * Filter: [`FilterExec`](physical_plan::filter::FilterExec)
 //! * Hash and Grouped aggregations: [`HashAggregateExec`](physical_plan::hash_aggregate::HashAggregateExec)
 //! * Sort: [`SortExec`](physical_plan::sort::SortExec)
+//! * Sort and keep the first rows (ORDER BY ... LIMIT): [`TopKExec`](physical_plan::top_k::TopKExec)
 //! * Merge (partitions): [`MergeExec`](physical_plan::merge::MergeExec)
+//! * Merge (sorted partitions): [`SortPreservingMergeExec`](physical_plan::sort_preserving_merge::SortPreservingMergeExec)
 //! * Limit: [`LocalLimitExec`](physical_plan::limit::LocalLimitExec) and [`GlobalLimitExec`](physical_plan::limit::GlobalLimitExec)
 //! * Scan a CSV: [`CsvExec`](physical_plan::csv::CsvExec)
 //! * Scan a Parquet: [`ParquetExec`](physical_plan::parquet::ParquetExec)
@@ -27,15 +27,20 @@ use arrow::{
 use crate::datasource::TableProvider;
 use crate::error::{DataFusionError, Result};
 use crate::{
-    datasource::{empty::EmptyTable, parquet::ParquetTable, CsvFile, MemTable},
-    prelude::CsvReadOptions,
+    datasource::{
+        empty::EmptyTable, parquet::ParquetTable, CsvFile, MemTable, NdJsonTable,
+    },
+    prelude::{CsvReadOptions, NdJsonReadOptions},
 };
 
 use super::dfschema::ToDFSchema;
 use super::{
-    col, exprlist_to_fields, Expr, JoinType, LogicalPlan, PlanType, StringifiedPlan,
+    col, exprlist_to_fields, lit, max, min, Expr, JoinType, LogicalPlan, PlanType,
+    StringifiedPlan,
 };
 use crate::logical_plan::{DFField, DFSchema, DFSchemaRef, Partitioning};
+use crate::physical_plan::aggregates::AggregateFunction;
+use crate::physical_plan::expressions::union_coercion;
 use std::collections::HashSet;
 
 /// Builder for logical plans
@@ -79,6 +84,16 @@ impl LogicalPlanBuilder {
         Self::scan("", provider, projection)
     }
 
+    /// Scan a newline-delimited JSON data source
+    pub fn scan_json(
+        path: &str,
+        options: NdJsonReadOptions,
+        projection: Option<Vec<usize>>,
+    ) -> Result<Self> {
+        let provider = Arc::new(NdJsonTable::try_new(path, options)?);
+        Self::scan("", provider, projection)
+    }
+
     /// Scan a Parquet data source
     pub fn scan_parquet(path: &str, projection: Option<Vec<usize>>) -> Result<Self> {
         let provider = Arc::new(ParquetTable::try_new(path)?);
@@ -116,6 +131,7 @@ impl LogicalPlanBuilder {
             projected_schema,
             projection,
             filters: vec![],
+            limit: None,
         };
 
         Ok(Self::from(&table_scan))
@@ -165,11 +181,19 @@ impl LogicalPlanBuilder {
         }))
     }
 
+    /// Fail at execution when the plan produces more than one row
+    pub fn enforce_single_row(&self) -> Result<Self> {
+        Ok(Self::from(&LogicalPlan::EnforceSingleRow {
+            input: Arc::new(self.plan.clone()),
+        }))
+    }
+
     /// Apply a sort
     pub fn sort(&self, expr: Vec<Expr>) -> Result<Self> {
         Ok(Self::from(&LogicalPlan::Sort {
             expr,
             input: Arc::new(self.plan.clone()),
+            fetch: None,
         }))
     }
 
@@ -180,11 +204,41 @@ impl LogicalPlanBuilder {
         join_type: JoinType,
         left_keys: &[&str],
         right_keys: &[&str],
+    ) -> Result<Self> {
+        self.join_detailed(right, join_type, left_keys, right_keys, None)
+    }
+
+    /// Apply a join whose matching rows must also satisfy `filter`, a predicate on the
+    /// columns of both sides. Without join keys, the join is evaluated by comparing
+    /// every row of the left side with every row of the right side.
+    pub fn join_with_filter(
+        &self,
+        right: &LogicalPlan,
+        join_type: JoinType,
+        left_keys: &[&str],
+        right_keys: &[&str],
+        filter: Expr,
+    ) -> Result<Self> {
+        self.join_detailed(right, join_type, left_keys, right_keys, Some(filter))
+    }
+
+    fn join_detailed(
+        &self,
+        right: &LogicalPlan,
+        join_type: JoinType,
+        left_keys: &[&str],
+        right_keys: &[&str],
+        filter: Option<Expr>,
     ) -> Result<Self> {
         if left_keys.len() != right_keys.len() {
             Err(DataFusionError::Plan(
                 "left_keys and right_keys were not the same length".to_string(),
             ))
+        } else if left_keys.is_empty() && filter.is_none() {
+            Err(DataFusionError::Plan(
+                "A join requires join keys or a filter, use a cross join instead"
+                    .to_string(),
+            ))
         } else {
             let on: Vec<_> = left_keys
                 .iter()
@@ -196,17 +250,24 @@ impl LogicalPlanBuilder {
             Ok(Self::from(&LogicalPlan::Join {
                 left: Arc::new(self.plan.clone()),
                 right: Arc::new(right.clone()),
-                on: left_keys
-                    .iter()
-                    .zip(right_keys.iter())
-                    .map(|(l, r)| (l.to_string(), r.to_string()))
-                    .collect(),
+                on,
+                filter,
                 join_type,
                 schema: DFSchemaRef::new(join_schema),
             }))
         }
     }
 
+    /// Apply a cross join, combining every row of this plan with every row of `right`
+    pub fn cross_join(&self, right: &LogicalPlan) -> Result<Self> {
+        let schema = self.plan.schema().join(right.schema())?;
+        Ok(Self::from(&LogicalPlan::CrossJoin {
+            left: Arc::new(self.plan.clone()),
+            right: Arc::new(right.clone()),
+            schema: DFSchemaRef::new(schema),
+        }))
+    }
+
     /// Repartition
     pub fn repartition(&self, partitioning_scheme: Partitioning) -> Result<Self> {
         Ok(Self::from(&LogicalPlan::Repartition {
@@ -222,17 +283,207 @@ impl LogicalPlanBuilder {
 
         validate_unique_names("Aggregations", &all_expr, self.plan.schema())?;
 
+        validate_grouping_args(&group_expr, &aggr_expr)?;
+
         let aggr_schema =
             DFSchema::new(exprlist_to_fields(&all_expr, self.plan.schema())?)?;
 
         Ok(Self::from(&LogicalPlan::Aggregate {
             input: Arc::new(self.plan.clone()),
             group_expr,
+            grouping_sets: None,
             aggr_expr,
             schema: DFSchemaRef::new(aggr_schema),
         }))
     }
 
+    /// Apply an aggregate grouping the input by each of the `grouping_sets`
+    /// (GROUPING SETS), the grouping expressions missing from a set being NULL in
+    /// its groups. See [`rollup`] and [`cube`] for the sets of `ROLLUP` and `CUBE`.
+    pub fn aggregate_grouping_sets(
+        &self,
+        grouping_sets: Vec<Vec<Expr>>,
+        aggr_expr: Vec<Expr>,
+    ) -> Result<Self> {
+        if grouping_sets.is_empty() {
+            return Err(DataFusionError::Plan(
+                "An aggregate expects at least one grouping set".to_string(),
+            ));
+        }
+
+        // the distinct expressions of the sets, which refer to them by index
+        let mut group_expr: Vec<Expr> = vec![];
+        let grouping_sets = grouping_sets
+            .into_iter()
+            .map(|set| {
+                let mut indexes = vec![];
+                for expr in set {
+                    let index = match group_expr.iter().position(|e| *e == expr) {
+                        Some(index) => index,
+                        None => {
+                            group_expr.push(expr);
+                            group_expr.len() - 1
+                        }
+                    };
+                    if !indexes.contains(&index) {
+                        indexes.push(index);
+                    }
+                }
+                indexes
+            })
+            .collect::<Vec<_>>();
+
+        let mut all_expr: Vec<Expr> = group_expr.clone();
+        aggr_expr.iter().for_each(|x| all_expr.push(x.clone()));
+
+        validate_unique_names("Aggregations", &all_expr, self.plan.schema())?;
+        validate_grouping_args(&group_expr, &aggr_expr)?;
+
+        // the grouping expressions missing from a set are NULL in its groups
+        let fields = exprlist_to_fields(&all_expr, self.plan.schema())?
+            .into_iter()
+            .enumerate()
+            .map(|(i, field)| {
+                if i < group_expr.len()
+                    && grouping_sets.iter().any(|set| !set.contains(&i))
+                {
+                    DFField::new(
+                        field.qualifier().map(|q| q.as_str()),
+                        field.name(),
+                        field.data_type().clone(),
+                        true,
+                    )
+                } else {
+                    field
+                }
+            })
+            .collect();
+
+        Ok(Self::from(&LogicalPlan::Aggregate {
+            input: Arc::new(self.plan.clone()),
+            group_expr,
+            grouping_sets: Some(grouping_sets),
+            aggr_expr,
+            schema: DFSchemaRef::new(DFSchema::new(fields)?),
+        }))
+    }
+
+    /// Apply window functions to extend the schema: the output contains the
+    /// input columns followed by one column per window expression
+    pub fn window(&self, window_expr: Vec<Expr>) -> Result<Self> {
+        validate_unique_names("Windows", &window_expr, self.plan.schema())?;
+
+        let mut window_fields: Vec<DFField> = self.plan.schema().fields().clone();
+        window_fields
+            .extend_from_slice(&exprlist_to_fields(&window_expr, self.plan.schema())?);
+
+        Ok(Self::from(&LogicalPlan::Window {
+            input: Arc::new(self.plan.clone()),
+            window_expr,
+            schema: DFSchemaRef::new(DFSchema::new(window_fields)?),
+        }))
+    }
+
+    /// Apply a union, appending the rows of `plan` to the rows of this plan
+    /// (UNION ALL).
+    ///
+    /// Columns are matched by position: both plans must have the same number of
+    /// columns, and columns of different types are coerced to a common type. The
+    /// output has the column names of this plan.
+    pub fn union(&self, plan: &LogicalPlan) -> Result<Self> {
+        let schema = Arc::new(union_schema(self.plan.schema(), plan.schema())?);
+
+        // flatten chains of unions into a single node
+        let mut inputs = match &self.plan {
+            LogicalPlan::Union { inputs, .. } => inputs.clone(),
+            _ => vec![self.plan.clone()],
+        };
+        inputs.push(plan.clone());
+
+        let inputs = inputs
+            .iter()
+            .map(|input| coerce_plan(input, &schema))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self::from(&LogicalPlan::Union { inputs, schema }))
+    }
+
+    /// Remove the duplicate rows of this plan
+    pub fn distinct(&self) -> Result<Self> {
+        let group_expr = self
+            .plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| col(f.name()))
+            .collect();
+        self.aggregate(group_expr, vec![])
+    }
+
+    /// Apply an intersect, keeping the distinct rows of this plan that are also
+    /// rows of `plan` (INTERSECT).
+    ///
+    /// Columns are matched and coerced as in [`union`](Self::union). Like in
+    /// `GROUP BY`, nulls are equal to each other, so a row with nulls matches an
+    /// identical row of `plan`.
+    pub fn intersect(&self, plan: &LogicalPlan) -> Result<Self> {
+        self.set_operation(plan, true)
+    }
+
+    /// Apply an except, keeping the distinct rows of this plan that are not rows
+    /// of `plan` (EXCEPT).
+    ///
+    /// Columns are matched and coerced as in [`union`](Self::union). Like in
+    /// `GROUP BY`, nulls are equal to each other, so a row with nulls is removed
+    /// by an identical row of `plan`.
+    pub fn except(&self, plan: &LogicalPlan) -> Result<Self> {
+        self.set_operation(plan, false)
+    }
+
+    /// Plans INTERSECT and EXCEPT as an aggregate over the rows of both sides,
+    /// each tagged with its side, so that rows are matched with the null-safe
+    /// equality of grouping.
+    fn set_operation(&self, plan: &LogicalPlan, intersect: bool) -> Result<Self> {
+        let schema = union_schema(self.plan.schema(), plan.schema())?;
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| col(f.name()))
+            .collect::<Vec<_>>();
+
+        let tagged = |plan: &LogicalPlan, side: u8| -> Result<LogicalPlan> {
+            let mut expr = columns.clone();
+            expr.push(lit(side).alias(SET_SIDE_COLUMN));
+            Self::from(&coerce_plan(plan, &schema)?)
+                .project(expr)?
+                .build()
+        };
+
+        let side = col(SET_SIDE_COLUMN);
+        let (aggr_expr, predicate) = if intersect {
+            (
+                vec![
+                    min(side.clone()).alias(SET_MIN_SIDE_COLUMN),
+                    max(side).alias(SET_MAX_SIDE_COLUMN),
+                ],
+                col(SET_MIN_SIDE_COLUMN)
+                    .eq(lit(1u8))
+                    .and(col(SET_MAX_SIDE_COLUMN).eq(lit(2u8))),
+            )
+        } else {
+            (
+                vec![max(side).alias(SET_MAX_SIDE_COLUMN)],
+                col(SET_MAX_SIDE_COLUMN).eq(lit(1u8)),
+            )
+        };
+
+        Self::from(&tagged(&self.plan, 1)?)
+            .union(&tagged(plan, 2)?)?
+            .aggregate(columns.clone(), aggr_expr)?
+            .filter(predicate)?
+            .project(columns)
+    }
+
     /// Create an expression to represent the explanation of the plan
     pub fn explain(&self, verbose: bool) -> Result<Self> {
         let stringified_plans = vec![StringifiedPlan::new(
@@ -265,7 +516,7 @@ fn build_join_schema(
     join_type: &JoinType,
 ) -> Result<DFSchema> {
     let fields: Vec<DFField> = match join_type {
-        JoinType::Inner | JoinType::Left => {
+        JoinType::Inner | JoinType::Left | JoinType::Full => {
             // remove right-side join keys if they have the same names as the left-side
             let duplicate_keys = &on
                 .iter()
@@ -301,10 +552,154 @@ fn build_join_schema(
             // left then right
             left_fields.chain(right_fields).cloned().collect()
         }
+        JoinType::Semi | JoinType::Anti => {
+            // only the left side is part of the output
+            left.fields().clone()
+        }
     };
     DFSchema::new(fields)
 }
 
+/// Creates the schema of a set operation (e.g. a union) of two inputs, whose columns
+/// are matched by position.
+fn union_schema(left: &DFSchema, right: &DFSchema) -> Result<DFSchema> {
+    if left.fields().len() != right.fields().len() {
+        return Err(DataFusionError::Plan(format!(
+            "Set operations require the same number of columns on both sides, \
+             found {} and {}",
+            left.fields().len(),
+            right.fields().len()
+        )));
+    }
+
+    let fields = left
+        .fields()
+        .iter()
+        .zip(right.fields().iter())
+        .map(|(left_field, right_field)| {
+            let data_type =
+                union_coercion(left_field.data_type(), right_field.data_type())
+                    .ok_or_else(|| {
+                        DataFusionError::Plan(format!(
+                            "Column {} has incompatible types {:?} and {:?} \
+                             in set operation",
+                            left_field.name(),
+                            left_field.data_type(),
+                            right_field.data_type()
+                        ))
+                    })?;
+            Ok(DFField::new(
+                left_field.qualifier().map(|q| q.as_str()),
+                left_field.name(),
+                data_type,
+                left_field.is_nullable() || right_field.is_nullable(),
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    DFSchema::new(fields)
+}
+
+/// Column tagging the rows of INTERSECT and EXCEPT with the side they come from:
+/// 1 for the left side and 2 for the right one
+const SET_SIDE_COLUMN: &str = "__set_side";
+/// Smallest side of a distinct row of INTERSECT
+const SET_MIN_SIDE_COLUMN: &str = "__set_min_side";
+/// Largest side of a distinct row of INTERSECT and EXCEPT
+const SET_MAX_SIDE_COLUMN: &str = "__set_max_side";
+
+/// Projects `plan` so that its columns have the names and types of the columns of
+/// `schema` at the same position.
+fn coerce_plan(plan: &LogicalPlan, schema: &DFSchema) -> Result<LogicalPlan> {
+    let fields = plan.schema().fields().iter().zip(schema.fields().iter());
+
+    if fields.clone().all(|(f, target)| {
+        f.name() == target.name() && f.data_type() == target.data_type()
+    }) {
+        return Ok(plan.clone());
+    }
+
+    let expr = fields
+        .map(|(f, target)| {
+            if f.data_type() != target.data_type() {
+                Expr::Cast {
+                    expr: Box::new(col(f.name())),
+                    data_type: target.data_type().clone(),
+                }
+                .alias(target.name())
+            } else if f.name() != target.name() {
+                col(f.name()).alias(target.name())
+            } else {
+                col(f.name())
+            }
+        })
+        .collect();
+
+    LogicalPlanBuilder::from(plan).project(expr)?.build()
+}
+
+/// The grouping sets of `ROLLUP(exprs)`: all the prefixes of `exprs`, from the
+/// longest to the empty one.
+pub fn rollup(exprs: Vec<Expr>) -> Vec<Vec<Expr>> {
+    (0..=exprs.len())
+        .rev()
+        .map(|len| exprs[..len].to_vec())
+        .collect()
+}
+
+/// Maximum number of expressions of a `CUBE`, whose number of grouping sets grows
+/// exponentially with it
+const MAX_CUBE_EXPRS: usize = 12;
+
+/// The grouping sets of `CUBE(exprs)`: all the subsets of `exprs`, from the whole
+/// set to the empty one.
+pub fn cube(exprs: Vec<Expr>) -> Result<Vec<Vec<Expr>>> {
+    if exprs.len() > MAX_CUBE_EXPRS {
+        return Err(DataFusionError::Plan(format!(
+            "CUBE is limited to {} expressions, got {}",
+            MAX_CUBE_EXPRS,
+            exprs.len()
+        )));
+    }
+    // the first expression is the most significant bit of the masks of the subsets
+    Ok((0..1usize << exprs.len())
+        .rev()
+        .map(|mask| {
+            exprs
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << (exprs.len() - 1 - i)) != 0)
+                .map(|(_, expr)| expr.clone())
+                .collect()
+        })
+        .collect())
+}
+
+/// Errors if the arguments of a `GROUPING` aggregate of `aggr_expr` are not
+/// grouping expressions of the aggregate.
+fn validate_grouping_args(group_expr: &[Expr], aggr_expr: &[Expr]) -> Result<()> {
+    aggr_expr.iter().try_for_each(|expr| {
+        let expr = match expr {
+            Expr::Alias(expr, _) => expr.as_ref(),
+            _ => expr,
+        };
+        match expr {
+            Expr::AggregateFunction {
+                fun: AggregateFunction::Grouping,
+                args,
+                ..
+            } => match args.iter().find(|arg| !group_expr.contains(arg)) {
+                Some(arg) => Err(DataFusionError::Plan(format!(
+                    "The arguments of GROUPING must be grouping expressions, got {:?}",
+                    arg
+                ))),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    })
+}
+
 /// Errors if one or more expressions have equal names.
 fn validate_unique_names(
     node_name: &str,
@@ -338,6 +733,7 @@ mod tests {
 
     use super::super::{lit, sum};
     use super::*;
+    use crate::physical_plan::window_functions::WindowFunction;
 
     #[test]
     fn plan_builder_simple() -> Result<()> {
@@ -382,6 +778,91 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn plan_builder_aggregate_grouping_sets() -> Result<()> {
+        let plan = LogicalPlanBuilder::scan_empty(
+            "employee.csv",
+            &employee_schema(),
+            Some(vec![0, 3, 4]),
+        )?
+        .aggregate_grouping_sets(
+            vec![
+                vec![col("state"), col("id")],
+                vec![col("state")],
+                vec![col("state")],
+            ],
+            vec![sum(col("salary")).alias("total_salary")],
+        )?
+        .build()?;
+
+        let expected = "Aggregate: groupBy=[[#state, #id]], groupingSets=[[#state, #id], [#state], [#state]], aggr=[[SUM(#salary) AS total_salary]]\
+        \n  TableScan: employee.csv projection=Some([0, 3, 4])";
+
+        assert_eq!(expected, format!("{:?}", plan));
+
+        // the grouping expressions missing from a set are nullable
+        let nullable = plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.is_nullable())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![false, true, true], nullable);
+
+        Ok(())
+    }
+
+    #[test]
+    fn rollup_and_cube() -> Result<()> {
+        let sets = |sets: Vec<Vec<Expr>>| format!("{:?}", sets);
+
+        assert_eq!(
+            "[[#a, #b, #c], [#a, #b], [#a], []]",
+            sets(rollup(vec![col("a"), col("b"), col("c")]))
+        );
+        assert_eq!(
+            "[[#a, #b], [#a], [#b], []]",
+            sets(cube(vec![col("a"), col("b")])?)
+        );
+
+        let exprs = (0..13).map(|i| col(&format!("c{}", i))).collect();
+        assert!(cube(exprs).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn plan_builder_window() -> Result<()> {
+        let plan = LogicalPlanBuilder::scan_empty(
+            "employee.csv",
+            &employee_schema(),
+            Some(vec![3, 4]),
+        )?
+        .window(vec![Expr::WindowFunction {
+            fun: WindowFunction::AggregateFunction(AggregateFunction::Sum),
+            args: vec![col("salary")],
+            partition_by: vec![col("state")],
+            order_by: vec![col("salary").sort(false, false)],
+            window_frame: None,
+        }
+        .alias("running_salary")])?;
+
+        // the window output contains the input columns followed by the window columns
+        assert_eq!(3, plan.build()?.schema().fields().len());
+
+        let plan = plan
+            .project(vec![col("state"), col("running_salary")])?
+            .build()?;
+
+        let expected = "Projection: #state, #running_salary\
+        \n  Window: windowExpr=[[SUM(#salary) PARTITION BY [#state] ORDER BY [#salary DESC NULLS LAST] AS running_salary]]\
+        \n    TableScan: employee.csv projection=Some([3, 4])";
+
+        assert_eq!(expected, format!("{:?}", plan));
+
+        Ok(())
+    }
+
     #[test]
     fn plan_builder_sort() -> Result<()> {
         let plan = LogicalPlanBuilder::scan_empty(
@@ -457,6 +938,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn plan_builder_union_combined_single_union() -> Result<()> {
+        let plan = LogicalPlanBuilder::scan_empty(
+            "employee.csv",
+            &employee_schema(),
+            Some(vec![3, 4]),
+        )?;
+
+        let plan = plan
+            .union(&plan.build()?)?
+            .union(&plan.build()?)?
+            .union(&plan.build()?)?
+            .build()?;
+
+        // output has only one union
+        let expected = "Union\
+        \n  TableScan: employee.csv projection=Some([3, 4])\
+        \n  TableScan: employee.csv projection=Some([3, 4])\
+        \n  TableScan: employee.csv projection=Some([3, 4])\
+        \n  TableScan: employee.csv projection=Some([3, 4])";
+
+        assert_eq!(expected, format!("{:?}", plan));
+
+        Ok(())
+    }
+
     fn employee_schema() -> Schema {
         Schema::new(vec![
             Field::new("id", DataType::Int32, false),
@@ -26,9 +26,10 @@ use aggregates::{AccumulatorFunctionImplementation, StateTypeFunction};
 use arrow::{compute::can_cast_types, datatypes::DataType};
 
 use crate::error::{DataFusionError, Result};
-use crate::logical_plan::{DFField, DFSchema};
+use crate::logical_plan::{window_frames, DFField, DFSchema, LogicalPlan};
 use crate::physical_plan::{
     aggregates, expressions::binary_operator_data_type, functions, udf::ScalarUDF,
+    window_functions,
 };
 use crate::{physical_plan::udaf::AggregateUDF, scalar::ScalarValue};
 use functions::{ReturnTypeFunction, ScalarFunctionImplementation, Signature};
@@ -87,6 +88,16 @@ pub enum Expr {
         /// The high end of the range
         high: Box<Expr>,
     },
+    /// Whether an expression is equal to one of the expressions of a list, e.g.
+    /// `region IN ('us', 'eu', 'ap')`
+    InList {
+        /// The value to look for
+        expr: Box<Expr>,
+        /// The list of values
+        list: Vec<Expr>,
+        /// Whether the expression is negated
+        negated: bool,
+    },
     /// The CASE expression is similar to a series of nested if/else and there are two forms that
     /// can be used. The first form consists of a series of boolean "when" expressions with
     /// corresponding "then" expressions, and an optional "else" expression.
@@ -150,6 +161,9 @@ pub enum Expr {
         args: Vec<Expr>,
         /// Whether this is a DISTINCT aggregation or not
         distinct: bool,
+        /// The order of the values fed to the function, as sort expressions, for the
+        /// functions whose result depends on it
+        order_by: Vec<Expr>,
     },
     /// aggregate function
     AggregateUDF {
@@ -158,10 +172,78 @@ pub enum Expr {
         /// List of expressions to feed to the functions as arguments
         args: Vec<Expr>,
     },
+    /// Represents the call of a window function with arguments.
+    WindowFunction {
+        /// Name of the function
+        fun: window_functions::WindowFunction,
+        /// List of expressions to feed to the functions as arguments
+        args: Vec<Expr>,
+        /// List of partition by expressions
+        partition_by: Vec<Expr>,
+        /// List of order by expressions
+        order_by: Vec<Expr>,
+        /// Window frame
+        window_frame: Option<window_frames::WindowFrame>,
+    },
+    /// `[NOT] EXISTS (<subquery>)`: whether the subquery returns at least one row.
+    Exists {
+        /// The subquery
+        subquery: Subquery,
+        /// Whether the expression is negated
+        negated: bool,
+    },
+    /// `<expr> [NOT] IN (<subquery>)`: whether the value of the expression is
+    /// returned by the subquery, which must return a single column.
+    InSubquery {
+        /// The value to look for
+        expr: Box<Expr>,
+        /// The subquery
+        subquery: Subquery,
+        /// Whether the expression is negated
+        negated: bool,
+    },
+    /// A subquery that returns a single column and at most one row, used as a value.
+    ScalarSubquery(Subquery),
     /// Represents a reference to all fields in a schema.
     Wildcard,
 }
 
+/// A subquery used in an expression. Its plan may reference columns of the
+/// enclosing query (a correlated subquery): these are the columns that are not
+/// found in the schema of the plan node that uses them.
+///
+/// Subqueries are rewritten into joins by the
+/// [DecorrelateSubquery](crate::optimizer::decorrelate_subquery::DecorrelateSubquery)
+/// optimizer rule and cannot be executed otherwise.
+#[derive(Clone)]
+pub struct Subquery {
+    /// The plan of the subquery
+    pub plan: Arc<LogicalPlan>,
+}
+
+impl Subquery {
+    /// Create a new subquery from its plan
+    pub fn new(plan: LogicalPlan) -> Self {
+        Subquery {
+            plan: Arc::new(plan),
+        }
+    }
+}
+
+impl PartialEq for Subquery {
+    fn eq(&self, other: &Self) -> bool {
+        // logical plans cannot be compared, their textual representations can
+        Arc::ptr_eq(&self.plan, &other.plan)
+            || format!("{:?}", self.plan) == format!("{:?}", other.plan)
+    }
+}
+
+impl fmt::Debug for Subquery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<subquery>")
+    }
+}
+
 impl Expr {
     /// Returns the [arrow::datatypes::DataType] of the expression based on [arrow::datatypes::Schema].
     ///
@@ -202,6 +284,13 @@ impl Expr {
                     .collect::<Result<Vec<_>>>()?;
                 aggregates::return_type(fun, &data_types)
             }
+            Expr::WindowFunction { fun, args, .. } => {
+                let data_types = args
+                    .iter()
+                    .map(|e| e.get_type(schema))
+                    .collect::<Result<Vec<_>>>()?;
+                window_functions::return_type(fun, &data_types)
+            }
             Expr::AggregateUDF { fun, args, .. } => {
                 let data_types = args
                     .iter()
@@ -224,6 +313,12 @@ impl Expr {
             ),
             Expr::Sort { ref expr, .. } => expr.get_type(schema),
             Expr::Between { .. } => Ok(DataType::Boolean),
+            Expr::InList { .. } => Ok(DataType::Boolean),
+            Expr::Exists { .. } => Ok(DataType::Boolean),
+            Expr::InSubquery { .. } => Ok(DataType::Boolean),
+            Expr::ScalarSubquery(subquery) => {
+                Ok(subquery.plan.schema().field(0).data_type().clone())
+            }
             Expr::Wildcard => Err(DataFusionError::Internal(
                 "Wildcard expressions are not valid in a logical query plan".to_owned(),
             )),
@@ -265,8 +360,14 @@ impl Expr {
             Expr::Cast { expr, .. } => expr.nullable(input_schema),
             Expr::ScalarFunction { .. } => Ok(true),
             Expr::ScalarUDF { .. } => Ok(true),
+            // the bitmask of the grouping set of the row
+            Expr::AggregateFunction {
+                fun: aggregates::AggregateFunction::Grouping,
+                ..
+            } => Ok(false),
             Expr::AggregateFunction { .. } => Ok(true),
             Expr::AggregateUDF { .. } => Ok(true),
+            Expr::WindowFunction { .. } => Ok(true),
             Expr::Not(expr) => expr.nullable(input_schema),
             Expr::Negative(expr) => expr.nullable(input_schema),
             Expr::IsNull(_) => Ok(false),
@@ -278,6 +379,19 @@ impl Expr {
             } => Ok(left.nullable(input_schema)? || right.nullable(input_schema)?),
             Expr::Sort { ref expr, .. } => expr.nullable(input_schema),
             Expr::Between { ref expr, .. } => expr.nullable(input_schema),
+            Expr::InList { expr, list, .. } => {
+                // the result is null if the value is null, or if it is not found in a
+                // list with null values
+                let mut nullable = expr.nullable(input_schema)?;
+                for e in list {
+                    nullable = nullable || e.nullable(input_schema)?;
+                }
+                Ok(nullable)
+            }
+            Expr::Exists { .. } => Ok(false),
+            Expr::InSubquery { expr, .. } => expr.nullable(input_schema),
+            // the subquery may return no row
+            Expr::ScalarSubquery(_) => Ok(true),
             Expr::Wildcard => Err(DataFusionError::Internal(
                 "Wildcard expressions are not valid in a logical query plan".to_owned(),
             )),
@@ -525,12 +639,61 @@ pub fn col(name: &str) -> Expr {
     Expr::Column(name.to_owned())
 }
 
+/// Create an EXISTS subquery expression
+pub fn exists(subquery: LogicalPlan) -> Expr {
+    Expr::Exists {
+        subquery: Subquery::new(subquery),
+        negated: false,
+    }
+}
+
+/// Create a NOT EXISTS subquery expression
+pub fn not_exists(subquery: LogicalPlan) -> Expr {
+    Expr::Exists {
+        subquery: Subquery::new(subquery),
+        negated: true,
+    }
+}
+
+/// Create an IN list expression, or a NOT IN list expression if `negated`
+pub fn in_list(expr: Expr, list: Vec<Expr>, negated: bool) -> Expr {
+    Expr::InList {
+        expr: Box::new(expr),
+        list,
+        negated,
+    }
+}
+
+/// Create an IN subquery expression
+pub fn in_subquery(expr: Expr, subquery: LogicalPlan) -> Expr {
+    Expr::InSubquery {
+        expr: Box::new(expr),
+        subquery: Subquery::new(subquery),
+        negated: false,
+    }
+}
+
+/// Create a NOT IN subquery expression
+pub fn not_in_subquery(expr: Expr, subquery: LogicalPlan) -> Expr {
+    Expr::InSubquery {
+        expr: Box::new(expr),
+        subquery: Subquery::new(subquery),
+        negated: true,
+    }
+}
+
+/// Create a scalar subquery expression
+pub fn scalar_subquery(subquery: LogicalPlan) -> Expr {
+    Expr::ScalarSubquery(Subquery::new(subquery))
+}
+
 /// Create an expression to represent the min() aggregate function
 pub fn min(expr: Expr) -> Expr {
     Expr::AggregateFunction {
         fun: aggregates::AggregateFunction::Min,
         distinct: false,
         args: vec![expr],
+        order_by: vec![],
     }
 }
 
@@ -540,6 +703,7 @@ pub fn max(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::Max,
         distinct: false,
         args: vec![expr],
+        order_by: vec![],
     }
 }
 
@@ -549,6 +713,7 @@ pub fn sum(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::Sum,
         distinct: false,
         args: vec![expr],
+        order_by: vec![],
     }
 }
 
@@ -558,6 +723,7 @@ pub fn avg(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::Avg,
         distinct: false,
         args: vec![expr],
+        order_by: vec![],
     }
 }
 
@@ -567,6 +733,19 @@ pub fn count(expr: Expr) -> Expr {
         fun: aggregates::AggregateFunction::Count,
         distinct: false,
         args: vec![expr],
+        order_by: vec![],
+    }
+}
+
+/// Create an expression to represent the array_agg() aggregate function, whose values
+/// are in the order of the sort expressions `order_by`, or in no particular order when
+/// it is empty
+pub fn array_agg(expr: Expr, order_by: Vec<Expr>) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::ArrayAgg,
+        distinct: false,
+        args: vec![expr],
+        order_by,
     }
 }
 
@@ -786,11 +965,36 @@ impl fmt::Debug for Expr {
                 fun,
                 distinct,
                 ref args,
-                ..
-            } => fmt_function(f, &fun.to_string(), *distinct, args),
+                order_by,
+            } => {
+                fmt_function(f, &fun.to_string(), *distinct, args)?;
+                if !order_by.is_empty() {
+                    write!(f, " ORDER BY {:?}", order_by)?;
+                }
+                Ok(())
+            }
             Expr::AggregateUDF { fun, ref args, .. } => {
                 fmt_function(f, &fun.name, false, args)
             }
+            Expr::WindowFunction {
+                fun,
+                ref args,
+                partition_by,
+                order_by,
+                window_frame,
+            } => {
+                fmt_function(f, &fun.to_string(), false, args)?;
+                if !partition_by.is_empty() {
+                    write!(f, " PARTITION BY {:?}", partition_by)?;
+                }
+                if !order_by.is_empty() {
+                    write!(f, " ORDER BY {:?}", order_by)?;
+                }
+                if let Some(window_frame) = window_frame {
+                    write!(f, " {}", window_frame)?;
+                }
+                Ok(())
+            }
             Expr::Between {
                 expr,
                 negated,
@@ -803,6 +1007,41 @@ impl fmt::Debug for Expr {
                     write!(f, "{:?} BETWEEN {:?} AND {:?}", expr, low, high)
                 }
             }
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let list = list
+                    .iter()
+                    .map(|e| format!("{:?}", e))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if *negated {
+                    write!(f, "{:?} NOT IN ({})", expr, list)
+                } else {
+                    write!(f, "{:?} IN ({})", expr, list)
+                }
+            }
+            Expr::Exists { subquery, negated } => {
+                if *negated {
+                    write!(f, "NOT EXISTS ({:?})", subquery)
+                } else {
+                    write!(f, "EXISTS ({:?})", subquery)
+                }
+            }
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                if *negated {
+                    write!(f, "{:?} NOT IN ({:?})", expr, subquery)
+                } else {
+                    write!(f, "{:?} IN ({:?})", expr, subquery)
+                }
+            }
+            Expr::ScalarSubquery(subquery) => write!(f, "({:?})", subquery),
             Expr::Wildcard => write!(f, "*"),
         }
     }
@@ -876,6 +1115,20 @@ fn create_name(e: &Expr, input_schema: &DFSchema) -> Result<String> {
             let expr = create_name(expr, input_schema)?;
             Ok(format!("{} IS NOT NULL", expr))
         }
+        Expr::Sort {
+            expr,
+            asc,
+            nulls_first,
+        } => {
+            let expr = create_name(expr, input_schema)?;
+            let asc = if *asc { "ASC" } else { "DESC" };
+            let nulls = if *nulls_first {
+                "NULLS FIRST"
+            } else {
+                "NULLS LAST"
+            };
+            Ok(format!("{} {} {}", expr, asc, nulls))
+        }
         Expr::ScalarFunction { fun, args, .. } => {
             create_function_name(&fun.to_string(), false, args, input_schema)
         }
@@ -886,8 +1139,19 @@ fn create_name(e: &Expr, input_schema: &DFSchema) -> Result<String> {
             fun,
             distinct,
             args,
-            ..
-        } => create_function_name(&fun.to_string(), *distinct, args, input_schema),
+            order_by,
+        } => {
+            let mut name =
+                create_function_name(&fun.to_string(), *distinct, args, input_schema)?;
+            if !order_by.is_empty() {
+                let names = order_by
+                    .iter()
+                    .map(|e| create_name(e, input_schema))
+                    .collect::<Result<Vec<_>>>()?;
+                name += &format!(" ORDER BY [{}]", names.join(", "));
+            }
+            Ok(name)
+        }
         Expr::AggregateUDF { fun, args } => {
             let mut names = Vec::with_capacity(args.len());
             for e in args {
@@ -895,6 +1159,59 @@ fn create_name(e: &Expr, input_schema: &DFSchema) -> Result<String> {
             }
             Ok(format!("{}({})", fun.name, names.join(",")))
         }
+        Expr::WindowFunction {
+            fun,
+            args,
+            partition_by,
+            order_by,
+            window_frame,
+        } => {
+            let mut name =
+                create_function_name(&fun.to_string(), false, args, input_schema)?;
+            if !partition_by.is_empty() {
+                let names = partition_by
+                    .iter()
+                    .map(|e| create_name(e, input_schema))
+                    .collect::<Result<Vec<_>>>()?;
+                name += &format!(" PARTITION BY [{}]", names.join(", "));
+            }
+            if !order_by.is_empty() {
+                let names = order_by
+                    .iter()
+                    .map(|e| create_name(e, input_schema))
+                    .collect::<Result<Vec<_>>>()?;
+                name += &format!(" ORDER BY [{}]", names.join(", "));
+            }
+            if let Some(window_frame) = window_frame {
+                name += &format!(" {}", window_frame);
+            }
+            Ok(name)
+        }
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => {
+            let expr = create_name(expr, input_schema)?;
+            let list = list
+                .iter()
+                .map(|e| create_name(e, input_schema))
+                .collect::<Result<Vec<_>>>()?;
+            let not = if *negated { "NOT " } else { "" };
+            Ok(format!("{} {}IN ({})", expr, not, list.join(", ")))
+        }
+        Expr::Exists { negated, .. } => {
+            let not = if *negated { "NOT " } else { "" };
+            Ok(format!("{}EXISTS (<subquery>)", not))
+        }
+        Expr::InSubquery { expr, negated, .. } => {
+            let expr = create_name(expr, input_schema)?;
+            let not = if *negated { "NOT " } else { "" };
+            Ok(format!("{} {}IN (<subquery>)", expr, not))
+        }
+        Expr::ScalarSubquery(subquery) => {
+            Ok(format!("({})", subquery.plan.schema().field(0).name()))
+        }
         other => Err(DataFusionError::NotImplemented(format!(
             "Physical plan does not support logical expression {:?}",
             other
@@ -29,15 +29,17 @@ mod extension;
 mod operators;
 mod plan;
 mod registry;
+mod window_frames;
 
-pub use builder::LogicalPlanBuilder;
+pub use builder::{cube, rollup, LogicalPlanBuilder};
 pub use dfschema::{DFField, DFSchema, DFSchemaRef, ToDFSchema};
 pub use display::display_schema;
 pub use expr::{
-    abs, acos, and, array, asin, atan, avg, binary_expr, case, ceil, col, concat, cos,
-    count, create_udaf, create_udf, exp, exprlist_to_fields, floor, length, lit, ln,
-    log10, log2, lower, max, min, or, round, signum, sin, sqrt, sum, tan, trim, trunc,
-    upper, when, Expr, Literal,
+    abs, acos, and, array, array_agg, asin, atan, avg, binary_expr, case, ceil, col,
+    concat, cos, count, create_udaf, create_udf, exists, exp, exprlist_to_fields, floor,
+    in_list, in_subquery, length, lit, ln, log10, log2, lower, max, min, not_exists,
+    not_in_subquery, or, round, scalar_subquery, signum, sin, sqrt, sum, tan, trim,
+    trunc, upper, when, Expr, Literal, Subquery,
 };
 pub use extension::UserDefinedLogicalNode;
 pub use operators::Operator;
@@ -45,3 +47,4 @@ pub use plan::{
     JoinType, LogicalPlan, Partitioning, PlanType, PlanVisitor, StringifiedPlan,
 };
 pub use registry::FunctionRegistry;
+pub use window_frames::{WindowFrame, WindowFrameBound, WindowFrameUnits};
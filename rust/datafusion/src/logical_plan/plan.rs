@@ -34,7 +34,7 @@ use super::extension::UserDefinedLogicalNode;
 use crate::logical_plan::dfschema::DFSchemaRef;
 
 /// Join type
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum JoinType {
     /// Inner join
     Inner,
@@ -42,6 +42,12 @@ pub enum JoinType {
     Left,
     /// Right join
     Right,
+    /// Full join
+    Full,
+    /// Semi join, i.e. the rows of the left side with a match on the right side
+    Semi,
+    /// Anti join, i.e. the rows of the left side without a match on the right side
+    Anti,
 }
 
 /// A LogicalPlan represents the different types of relational
@@ -78,6 +84,19 @@ pub enum LogicalPlan {
         /// The incoming logical plan
         input: Arc<LogicalPlan>,
     },
+    /// Window its input based on a set of window specs and window
+    /// function expressions (e.g. `ROW_NUMBER() OVER (PARTITION BY a)`).
+    ///
+    /// The output contains all the columns of the input, followed by one
+    /// column per window expression.
+    Window {
+        /// The incoming logical plan
+        input: Arc<LogicalPlan>,
+        /// The window function expressions
+        window_expr: Vec<Expr>,
+        /// The schema description of the window output
+        schema: DFSchemaRef,
+    },
     /// Aggregates its input based on a set of grouping and aggregate
     /// expressions (e.g. SUM).
     Aggregate {
@@ -85,6 +104,9 @@ pub enum LogicalPlan {
         input: Arc<LogicalPlan>,
         /// Grouping expressions
         group_expr: Vec<Expr>,
+        /// The grouping sets the input is grouped by, the indexes of their grouping
+        /// expressions, `None` for a single set of all the grouping expressions
+        grouping_sets: Option<Vec<Vec<usize>>>,
         /// Aggregate expressions
         aggr_expr: Vec<Expr>,
         /// The schema description of the aggregate output
@@ -96,6 +118,8 @@ pub enum LogicalPlan {
         expr: Vec<Expr>,
         /// The incoming logical plan
         input: Arc<LogicalPlan>,
+        /// If set, only the first `fetch` rows of the sorted input are produced
+        fetch: Option<usize>,
     },
     /// Join two logical plans on one or more join columns
     Join {
@@ -105,11 +129,25 @@ pub enum LogicalPlan {
         right: Arc<LogicalPlan>,
         /// Equijoin clause expressed as pairs of (left, right) join columns
         on: Vec<(String, String)>,
+        /// Residual predicate on the columns of both inputs, that the pairs of rows
+        /// with equal join columns must also satisfy to match. A join without join
+        /// columns matches the pairs of rows satisfying the predicate.
+        filter: Option<Expr>,
         /// Join type
         join_type: JoinType,
         /// The output schema, containing fields from the left and right inputs
         schema: DFSchemaRef,
     },
+    /// Produces every combination of a row of the left input with a row of the
+    /// right input (the cartesian product of the inputs)
+    CrossJoin {
+        /// Left input
+        left: Arc<LogicalPlan>,
+        /// Right input
+        right: Arc<LogicalPlan>,
+        /// The output schema, containing fields from the left and right inputs
+        schema: DFSchemaRef,
+    },
     /// Repartition the plan based on a partitioning scheme.
     Repartition {
         /// The incoming logical plan
@@ -117,6 +155,14 @@ pub enum LogicalPlan {
         /// The partitioning scheme
         partitioning_scheme: Partitioning,
     },
+    /// Concatenates the rows of its inputs (UNION ALL). All inputs have the
+    /// same column names and types as the output schema.
+    Union {
+        /// The incoming logical plans
+        inputs: Vec<LogicalPlan>,
+        /// The schema description of the output
+        schema: DFSchemaRef,
+    },
     /// Produces rows from a table provider by reference or from the context
     TableScan {
         /// The name of the table
@@ -129,6 +175,8 @@ pub enum LogicalPlan {
         projected_schema: DFSchemaRef,
         /// Optional expressions to be used as filters by the table provider
         filters: Vec<Expr>,
+        /// Optional number of rows to read
+        limit: Option<usize>,
     },
     /// Produces no rows: An empty relation with an empty schema
     EmptyRelation {
@@ -144,6 +192,12 @@ pub enum LogicalPlan {
         /// The logical plan
         input: Arc<LogicalPlan>,
     },
+    /// Produces the rows of its input, and fails when there are more than one. The
+    /// value of an uncorrelated scalar subquery is the one of its single row.
+    EnforceSingleRow {
+        /// The logical plan
+        input: Arc<LogicalPlan>,
+    },
     /// Creates an external table.
     CreateExternalTable {
         /// The table schema
@@ -186,11 +240,15 @@ impl LogicalPlan {
             } => &projected_schema,
             LogicalPlan::Projection { schema, .. } => &schema,
             LogicalPlan::Filter { input, .. } => input.schema(),
+            LogicalPlan::Window { schema, .. } => &schema,
             LogicalPlan::Aggregate { schema, .. } => &schema,
             LogicalPlan::Sort { input, .. } => input.schema(),
             LogicalPlan::Join { schema, .. } => &schema,
+            LogicalPlan::CrossJoin { schema, .. } => &schema,
             LogicalPlan::Repartition { input, .. } => input.schema(),
+            LogicalPlan::Union { schema, .. } => &schema,
             LogicalPlan::Limit { input, .. } => input.schema(),
+            LogicalPlan::EnforceSingleRow { input } => input.schema(),
             LogicalPlan::CreateExternalTable { schema, .. } => &schema,
             LogicalPlan::Explain { schema, .. } => &schema,
             LogicalPlan::Extension { node } => &node.schema(),
@@ -213,7 +271,6 @@ pub enum Partitioning {
     RoundRobinBatch(usize),
     /// Allocate rows based on a hash of one of more expressions and the specified number
     /// of partitions.
-    /// This partitioning scheme is not yet fully supported. See https://issues.apache.org/jira/browse/ARROW-11011
     Hash(Vec<Expr>, usize),
 }
 
@@ -281,12 +338,23 @@ impl LogicalPlan {
             LogicalPlan::Projection { input, .. } => input.accept(visitor)?,
             LogicalPlan::Filter { input, .. } => input.accept(visitor)?,
             LogicalPlan::Repartition { input, .. } => input.accept(visitor)?,
+            LogicalPlan::Window { input, .. } => input.accept(visitor)?,
             LogicalPlan::Aggregate { input, .. } => input.accept(visitor)?,
             LogicalPlan::Sort { input, .. } => input.accept(visitor)?,
-            LogicalPlan::Join { left, right, .. } => {
+            LogicalPlan::Join { left, right, .. }
+            | LogicalPlan::CrossJoin { left, right, .. } => {
                 left.accept(visitor)? && right.accept(visitor)?
             }
             LogicalPlan::Limit { input, .. } => input.accept(visitor)?,
+            LogicalPlan::EnforceSingleRow { input } => input.accept(visitor)?,
+            LogicalPlan::Union { inputs, .. } => {
+                for input in inputs {
+                    if !input.accept(visitor)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
             LogicalPlan::Extension { node } => {
                 for input in node.inputs() {
                     if !input.accept(visitor)? {
@@ -490,6 +558,7 @@ impl LogicalPlan {
                         ref table_name,
                         ref projection,
                         ref filters,
+                        ref limit,
                         ..
                     } => {
                         let sep = " ".repeat(min(1, table_name.len()));
@@ -503,6 +572,10 @@ impl LogicalPlan {
                             write!(f, ", filters={:?}", filters)?;
                         }
 
+                        if let Some(n) = limit {
+                            write!(f, ", limit={}", n)?;
+                        }
+
                         Ok(())
                     }
                     LogicalPlan::Projection { ref expr, .. } => {
@@ -519,8 +592,12 @@ impl LogicalPlan {
                         predicate: ref expr,
                         ..
                     } => write!(f, "Filter: {:?}", expr),
+                    LogicalPlan::Window {
+                        ref window_expr, ..
+                    } => write!(f, "Window: windowExpr=[{:?}]", window_expr),
                     LogicalPlan::Aggregate {
                         ref group_expr,
+                        grouping_sets: None,
                         ref aggr_expr,
                         ..
                     } => write!(
@@ -528,7 +605,29 @@ impl LogicalPlan {
                         "Aggregate: groupBy=[{:?}], aggr=[{:?}]",
                         group_expr, aggr_expr
                     ),
-                    LogicalPlan::Sort { ref expr, .. } => {
+                    LogicalPlan::Aggregate {
+                        ref group_expr,
+                        grouping_sets: Some(ref grouping_sets),
+                        ref aggr_expr,
+                        ..
+                    } => {
+                        let grouping_sets = grouping_sets
+                            .iter()
+                            .map(|set| {
+                                set.iter().map(|i| &group_expr[*i]).collect::<Vec<_>>()
+                            })
+                            .collect::<Vec<_>>();
+                        write!(
+                            f,
+                            "Aggregate: groupBy=[{:?}], groupingSets={:?}, aggr=[{:?}]",
+                            group_expr, grouping_sets, aggr_expr
+                        )
+                    }
+                    LogicalPlan::Sort {
+                        ref expr,
+                        ref fetch,
+                        ..
+                    } => {
                         write!(f, "Sort: ")?;
                         for i in 0..expr.len() {
                             if i > 0 {
@@ -536,13 +635,28 @@ impl LogicalPlan {
                             }
                             write!(f, "{:?}", expr[i])?;
                         }
+                        if let Some(fetch) = fetch {
+                            write!(f, ", fetch={}", fetch)?;
+                        }
                         Ok(())
                     }
-                    LogicalPlan::Join { on: ref keys, .. } => {
+                    LogicalPlan::Join {
+                        on: ref keys,
+                        ref filter,
+                        ..
+                    } => {
                         let join_expr: Vec<String> =
                             keys.iter().map(|(l, r)| format!("{} = {}", l, r)).collect();
-                        write!(f, "Join: {}", join_expr.join(", "))
+                        write!(f, "Join: {}", join_expr.join(", "))?;
+                        match filter {
+                            Some(filter) if keys.is_empty() => {
+                                write!(f, "filter={:?}", filter)
+                            }
+                            Some(filter) => write!(f, ", filter={:?}", filter),
+                            None => Ok(()),
+                        }
                     }
+                    LogicalPlan::CrossJoin { .. } => write!(f, "CrossJoin"),
                     LogicalPlan::Repartition {
                         partitioning_scheme,
                         ..
@@ -565,7 +679,9 @@ impl LogicalPlan {
                             )
                         }
                     },
+                    LogicalPlan::Union { .. } => write!(f, "Union"),
                     LogicalPlan::Limit { ref n, .. } => write!(f, "Limit: {}", n),
+                    LogicalPlan::EnforceSingleRow { .. } => write!(f, "EnforceSingleRow"),
                     LogicalPlan::CreateExternalTable { ref name, .. } => {
                         write!(f, "CreateExternalTable: {:?}", name)
                     }
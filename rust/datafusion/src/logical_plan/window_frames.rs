@@ -0,0 +1,259 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Window frame
+//!
+//! The frame-spec determines which output rows are read by an aggregate window function.
+//! The frame-spec consists of:
+//! * the frame type, either ROWS or RANGE
+//! * the starting frame boundary
+//! * the ending frame boundary, which defaults to CURRENT ROW when omitted
+
+use crate::error::{DataFusionError, Result};
+use sqlparser::ast;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// The frame-spec of a window function call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowFrame {
+    /// A frame type - either ROWS or RANGE
+    pub units: WindowFrameUnits,
+    /// A starting frame boundary
+    pub start_bound: WindowFrameBound,
+    /// An ending frame boundary
+    pub end_bound: WindowFrameBound,
+}
+
+impl fmt::Display for WindowFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} BETWEEN {} AND {}",
+            self.units, self.start_bound, self.end_bound
+        )
+    }
+}
+
+impl TryFrom<ast::WindowFrame> for WindowFrame {
+    type Error = DataFusionError;
+
+    fn try_from(value: ast::WindowFrame) -> Result<Self> {
+        let start_bound = value.start_bound.into();
+        let end_bound = value
+            .end_bound
+            .map(WindowFrameBound::from)
+            .unwrap_or(WindowFrameBound::CurrentRow);
+
+        if let WindowFrameBound::Following(None) = start_bound {
+            return Err(DataFusionError::Plan(
+                "Invalid window frame: start bound cannot be unbounded following"
+                    .to_owned(),
+            ));
+        }
+        if let WindowFrameBound::Preceding(None) = end_bound {
+            return Err(DataFusionError::Plan(
+                "Invalid window frame: end bound cannot be unbounded preceding"
+                    .to_owned(),
+            ));
+        }
+        if start_bound.position() > end_bound.position() {
+            return Err(DataFusionError::Plan(format!(
+                "Invalid window frame: start bound ({}) cannot be larger than end bound ({})",
+                start_bound, end_bound
+            )));
+        }
+
+        let units = match value.units {
+            ast::WindowFrameUnits::Rows => WindowFrameUnits::Rows,
+            ast::WindowFrameUnits::Range => WindowFrameUnits::Range,
+            ast::WindowFrameUnits::Groups => {
+                return Err(DataFusionError::NotImplemented(
+                    "Window frame with GROUPS units is not supported".to_owned(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            units,
+            start_bound,
+            end_bound,
+        })
+    }
+}
+
+impl Default for WindowFrame {
+    /// The default frame, used when the frame-spec is omitted: all the rows from the start
+    /// of the partition up to the current row and its peers
+    fn default() -> Self {
+        WindowFrame {
+            units: WindowFrameUnits::Range,
+            start_bound: WindowFrameBound::Preceding(None),
+            end_bound: WindowFrameBound::CurrentRow,
+        }
+    }
+}
+
+/// There are three frame types: ROWS, GROUPS, and RANGE. The frame type determines how the
+/// starting and ending boundaries of the frame are measured. Only ROWS and RANGE are
+/// currently supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowFrameUnits {
+    /// The ROWS frame type means that the starting and ending boundaries for the frame are
+    /// determined by counting individual rows relative to the current row.
+    Rows,
+    /// The RANGE frame type requires that the ORDER BY clause of the window have exactly one
+    /// term when numeric offsets are used. The boundaries are determined by the difference
+    /// between the value of that term in the current row and in the other rows.
+    Range,
+}
+
+impl fmt::Display for WindowFrameUnits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            WindowFrameUnits::Rows => "ROWS",
+            WindowFrameUnits::Range => "RANGE",
+        })
+    }
+}
+
+/// A starting or ending boundary of a window frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowFrameBound {
+    /// `<N> PRECEDING` or `UNBOUNDED PRECEDING` (when `None`)
+    Preceding(Option<u64>),
+    /// `CURRENT ROW`
+    CurrentRow,
+    /// `<N> FOLLOWING` or `UNBOUNDED FOLLOWING` (when `None`)
+    Following(Option<u64>),
+}
+
+impl From<ast::WindowFrameBound> for WindowFrameBound {
+    fn from(value: ast::WindowFrameBound) -> Self {
+        match value {
+            ast::WindowFrameBound::Preceding(v) => Self::Preceding(v),
+            ast::WindowFrameBound::Following(v) => Self::Following(v),
+            ast::WindowFrameBound::CurrentRow => Self::CurrentRow,
+        }
+    }
+}
+
+impl fmt::Display for WindowFrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowFrameBound::CurrentRow => f.write_str("CURRENT ROW"),
+            WindowFrameBound::Preceding(None) => f.write_str("UNBOUNDED PRECEDING"),
+            WindowFrameBound::Following(None) => f.write_str("UNBOUNDED FOLLOWING"),
+            WindowFrameBound::Preceding(Some(n)) => write!(f, "{} PRECEDING", n),
+            WindowFrameBound::Following(Some(n)) => write!(f, "{} FOLLOWING", n),
+        }
+    }
+}
+
+impl WindowFrameBound {
+    /// the position of the bound relative to the current row, used to validate that a
+    /// frame does not start after it ends
+    fn position(&self) -> (i8, i128) {
+        match self {
+            WindowFrameBound::Preceding(None) => (0, 0),
+            WindowFrameBound::Preceding(Some(n)) => (1, -(*n as i128)),
+            WindowFrameBound::CurrentRow => (1, 0),
+            WindowFrameBound::Following(Some(n)) => (1, *n as i128),
+            WindowFrameBound::Following(None) => (2, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_frame_creation() -> Result<()> {
+        let window_frame = ast::WindowFrame {
+            units: ast::WindowFrameUnits::Range,
+            start_bound: ast::WindowFrameBound::Following(None),
+            end_bound: None,
+        };
+        let result = WindowFrame::try_from(window_frame);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Error during planning: Invalid window frame: start bound cannot be unbounded following".to_owned()
+        );
+
+        let window_frame = ast::WindowFrame {
+            units: ast::WindowFrameUnits::Range,
+            start_bound: ast::WindowFrameBound::Preceding(None),
+            end_bound: Some(ast::WindowFrameBound::Preceding(None)),
+        };
+        let result = WindowFrame::try_from(window_frame);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Error during planning: Invalid window frame: end bound cannot be unbounded preceding".to_owned()
+        );
+
+        let window_frame = ast::WindowFrame {
+            units: ast::WindowFrameUnits::Rows,
+            start_bound: ast::WindowFrameBound::Preceding(Some(1)),
+            end_bound: Some(ast::WindowFrameBound::Preceding(Some(2))),
+        };
+        let result = WindowFrame::try_from(window_frame);
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Error during planning: Invalid window frame: start bound (1 PRECEDING) cannot be larger than end bound (2 PRECEDING)".to_owned()
+        );
+
+        let window_frame = ast::WindowFrame {
+            units: ast::WindowFrameUnits::Groups,
+            start_bound: ast::WindowFrameBound::Preceding(Some(2)),
+            end_bound: None,
+        };
+        assert!(WindowFrame::try_from(window_frame).is_err());
+
+        let window_frame = ast::WindowFrame {
+            units: ast::WindowFrameUnits::Rows,
+            start_bound: ast::WindowFrameBound::Preceding(Some(2)),
+            end_bound: Some(ast::WindowFrameBound::Following(Some(1))),
+        };
+        let result = WindowFrame::try_from(window_frame)?;
+        assert_eq!(
+            "ROWS BETWEEN 2 PRECEDING AND 1 FOLLOWING",
+            result.to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_frame_bound_position() {
+        let bounds = vec![
+            WindowFrameBound::Preceding(None),
+            WindowFrameBound::Preceding(Some(5)),
+            WindowFrameBound::Preceding(Some(1)),
+            WindowFrameBound::CurrentRow,
+            WindowFrameBound::Following(Some(1)),
+            WindowFrameBound::Following(Some(5)),
+            WindowFrameBound::Following(None),
+        ];
+        for pair in bounds.windows(2) {
+            assert!(pair[0].position() < pair[1].position());
+        }
+        assert_eq!(
+            WindowFrameBound::Preceding(Some(0)).position(),
+            WindowFrameBound::CurrentRow.position()
+        );
+    }
+}
@@ -0,0 +1,348 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimizer rule eliminating the common sub-expressions of projections and aggregates,
+//! e.g. `a * b` in `SELECT SUM(a * b), MAX(a * b) FROM t`, by computing them once in a
+//! projection below.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::logical_plan::{col, DFSchema, Expr, LogicalPlan, LogicalPlanBuilder};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+
+/// Optimizer rule computing the sub-expressions which appear several times in the
+/// expressions of a projection or an aggregate once, in a projection inserted below it.
+/// The sub-expressions are replaced by columns named after them, so that the names of
+/// the expressions, and thus the schema of the plan, are unchanged.
+pub struct CommonSubexprEliminate {}
+
+impl OptimizerRule for CommonSubexprEliminate {
+    fn optimize(&mut self, plan: &LogicalPlan) -> Result<LogicalPlan> {
+        match plan {
+            LogicalPlan::Projection {
+                expr,
+                input,
+                schema,
+            } => {
+                let input = self.optimize(input)?;
+                let (expr, input) = eliminate_common_subexprs(expr, input)?;
+                Ok(LogicalPlan::Projection {
+                    expr,
+                    input: Arc::new(input),
+                    schema: schema.clone(),
+                })
+            }
+            LogicalPlan::Aggregate {
+                input,
+                group_expr,
+                grouping_sets,
+                aggr_expr,
+                schema,
+            } => {
+                let input = self.optimize(input)?;
+                let expr = group_expr
+                    .iter()
+                    .chain(aggr_expr.iter())
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let (mut expr, input) = eliminate_common_subexprs(&expr, input)?;
+                let aggr_expr = expr.split_off(group_expr.len());
+                Ok(LogicalPlan::Aggregate {
+                    input: Arc::new(input),
+                    group_expr: expr,
+                    grouping_sets: grouping_sets.clone(),
+                    aggr_expr,
+                    schema: schema.clone(),
+                })
+            }
+            LogicalPlan::Explain {
+                verbose,
+                plan,
+                stringified_plans,
+                schema,
+            } => utils::optimize_explain(
+                self,
+                *verbose,
+                &*plan,
+                stringified_plans,
+                &schema.as_ref().to_owned().into(),
+            ),
+            _ => self.optimize_children(plan),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "common_subexpr_eliminate"
+    }
+}
+
+impl CommonSubexprEliminate {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Rewrites `expr`, expressions on the columns of `input`, so that their common
+/// sub-expressions are computed once by a projection on top of `input`
+fn eliminate_common_subexprs(
+    expr: &[Expr],
+    input: LogicalPlan,
+) -> Result<(Vec<Expr>, LogicalPlan)> {
+    let schema = input.schema().clone();
+    // the columns of the input are projected by name, which must be unique
+    let mut names = HashSet::new();
+    if !schema
+        .fields()
+        .iter()
+        .all(|field| names.insert(field.name()))
+    {
+        return Ok((expr.to_vec(), input));
+    }
+
+    let mut counts = HashMap::new();
+    for e in expr {
+        count_subexprs(e, &schema, &mut counts)?;
+    }
+    let mut common = vec![];
+    let expr = expr
+        .iter()
+        .map(|e| replace_common_subexprs(e, &schema, &counts, &mut common))
+        .collect::<Result<Vec<_>>>()?;
+    if common.is_empty() {
+        return Ok((expr, input));
+    }
+
+    let projection = schema
+        .fields()
+        .iter()
+        .map(|field| col(field.name()))
+        .chain(common.iter().map(|c| c.expr.alias(&c.name)))
+        .collect::<Vec<_>>();
+    let input = LogicalPlanBuilder::from(&input)
+        .project(projection)?
+        .build()?;
+    Ok((expr, input))
+}
+
+/// Whether `expr` is worth computing once, and can be referenced by its name
+fn is_common_candidate(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::BinaryExpr { .. }
+            | Expr::Not(_)
+            | Expr::Negative(_)
+            | Expr::IsNull(_)
+            | Expr::IsNotNull(_)
+            | Expr::InList { .. }
+            | Expr::Case { .. }
+            | Expr::Cast { .. }
+            | Expr::ScalarFunction { .. }
+            | Expr::ScalarUDF { .. }
+    )
+}
+
+/// Whether the name of `expr` is built from the names of its sub-expressions, so
+/// that replacing them with columns named after them does not change it
+fn has_composite_name(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::BinaryExpr { .. }
+            | Expr::Not(_)
+            | Expr::Negative(_)
+            | Expr::IsNull(_)
+            | Expr::IsNotNull(_)
+            | Expr::InList { .. }
+            | Expr::Cast { .. }
+            | Expr::ScalarFunction { .. }
+            | Expr::ScalarUDF { .. }
+            | Expr::AggregateFunction { .. }
+            | Expr::AggregateUDF { .. }
+            | Expr::Alias(..)
+    )
+}
+
+/// Returns a string identifying `expr`. Unlike the name of `expr`, it tells apart
+/// expressions such as `(a + b) * c` and `a + (b * c)`.
+fn expr_id(expr: &Expr) -> Result<String> {
+    let sub_expressions = utils::expr_sub_expressions(expr)?;
+    if sub_expressions.is_empty() {
+        return Ok(format!("{:?}", expr));
+    }
+    // the expression without its sub-expressions
+    let placeholders = vec![col("?"); sub_expressions.len()];
+    let node = utils::rewrite_expression(expr, &placeholders)?;
+    let ids = sub_expressions
+        .iter()
+        .map(expr_id)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(format!("{:?}[{}]", node, ids.join(", ")))
+}
+
+/// Counts the occurrences of the candidate sub-expressions of `expr`
+fn count_subexprs(
+    expr: &Expr,
+    schema: &DFSchema,
+    counts: &mut HashMap<String, usize>,
+) -> Result<()> {
+    // some expressions, e.g. `BETWEEN`, have no name and cannot be referenced
+    if is_common_candidate(expr) && expr.name(schema).is_ok() {
+        *counts.entry(expr_id(expr)?).or_insert(0) += 1;
+    }
+    if has_composite_name(expr) {
+        for e in utils::expr_sub_expressions(expr)? {
+            count_subexprs(&e, schema, counts)?;
+        }
+    }
+    Ok(())
+}
+
+/// A sub-expression computed once, as the column `name`
+struct CommonSubexpr {
+    id: String,
+    name: String,
+    expr: Expr,
+}
+
+/// Replaces the largest sub-expressions of `expr` that occur several times by columns,
+/// adding the replaced sub-expressions to `common`
+fn replace_common_subexprs(
+    expr: &Expr,
+    schema: &DFSchema,
+    counts: &HashMap<String, usize>,
+    common: &mut Vec<CommonSubexpr>,
+) -> Result<Expr> {
+    if is_common_candidate(expr) {
+        if let Ok(name) = expr.name(schema) {
+            let id = expr_id(expr)?;
+            // the name must not be the one of a column of the input, or of another
+            // sub-expression
+            let available = schema.field_with_unqualified_name(&name).is_err()
+                && common.iter().all(|c| c.name != name || c.id == id);
+            if available && counts.get(&id).map_or(false, |count| *count > 1) {
+                if !common.iter().any(|c| c.id == id) {
+                    common.push(CommonSubexpr {
+                        id,
+                        name: name.clone(),
+                        expr: expr.clone(),
+                    });
+                }
+                return Ok(col(&name));
+            }
+        }
+    }
+    if has_composite_name(expr) {
+        let sub_expressions = utils::expr_sub_expressions(expr)?
+            .iter()
+            .map(|e| replace_common_subexprs(e, schema, counts, common))
+            .collect::<Result<Vec<_>>>()?;
+        utils::rewrite_expression(expr, &sub_expressions)
+    } else {
+        Ok(expr.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{avg, lit, max, sum};
+    use crate::test::*;
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = CommonSubexprEliminate::new()
+            .optimize(plan)
+            .expect("failed to optimize plan");
+        let formatted_plan = format!("{:?}", optimized_plan);
+        assert_eq!(formatted_plan, expected);
+    }
+
+    #[test]
+    fn aggregate_common_subexprs() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .aggregate(
+                vec![col("c")],
+                vec![
+                    sum(col("a") * col("b")),
+                    avg(col("a") * col("b")),
+                    max(col("a") * col("b")),
+                ],
+            )?
+            .build()?;
+
+        let expected = "Aggregate: groupBy=[[#c]], aggr=[[SUM(#a Multiply b), AVG(#a Multiply b), MAX(#a Multiply b)]]\
+        \n  Projection: #a, #b, #c, #a Multiply #b AS a Multiply b\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn projection_common_subexprs() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![
+                ((col("a") + col("b")) * col("c")).alias("x"),
+                (col("a") + col("b")) * col("c") + lit(1),
+                col("a") + col("b"),
+            ])?
+            .build()?;
+
+        // the largest common sub-expressions are computed
+        let expected = "Projection: #a Plus b Multiply c AS x, #a Plus b Multiply c Plus Int32(1), #a Plus b\
+        \n  Projection: #a, #b, #c, #a Plus #b Multiply #c AS a Plus b Multiply c, #a Plus #b AS a Plus b\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn same_name_different_subexprs() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![
+                ((col("a") + col("b")) * col("c")).alias("x1"),
+                ((col("a") + col("b")) * col("c")).alias("x2"),
+                (col("a") + col("b") * col("c")).alias("y1"),
+                (col("a") + col("b") * col("c")).alias("y2"),
+            ])?
+            .build()?;
+
+        // both `(a + b) * c` and `a + (b * c)` are named "a Plus b Multiply c"
+        let expected = "Projection: #a Plus b Multiply c AS x1, #a Plus b Multiply c AS x2, #a Plus #b Multiply c AS y1, #a Plus #b Multiply c AS y2\
+        \n  Projection: #a, #b, #c, #a Plus #b Multiply #c AS a Plus b Multiply c, #b Multiply #c AS b Multiply c\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn no_common_subexprs() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a") + col("b"), col("a") * col("b")])?
+            .build()?;
+
+        let expected = "Projection: #a Plus #b, #a Multiply #b\
+        \n  TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+}
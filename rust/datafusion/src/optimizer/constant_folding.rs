@@ -0,0 +1,358 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimizer rule evaluating the expressions which only depend on literals, e.g.
+//! `1 + 2` or `CAST('2020-01-01' AS DATE)`, and simplifying boolean expressions, so
+//! that the predicates pushed down to table providers are comparisons with literals.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, NullArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+
+use crate::error::Result;
+use crate::execution::context::{ExecutionConfig, ExecutionContextState};
+use crate::logical_plan::{DFSchema, Expr, LogicalPlan, Operator};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+use crate::physical_plan::planner::DefaultPhysicalPlanner;
+use crate::physical_plan::ColumnarValue;
+use crate::scalar::ScalarValue;
+
+/// Optimizer rule folding the constant expressions of projections and filters, and
+/// removing the filters which are always true:
+///
+/// * literal-only expressions are replaced by their value
+/// * `x AND true` and `x OR false` become `x`, `x AND false` becomes `false` and
+///   `x OR true` becomes `true`
+/// * `NOT NOT x` becomes `x`
+/// * `x = x` becomes `true` and `x != x` becomes `false` when `x` is a non-null column
+pub struct ConstantFolding {}
+
+impl OptimizerRule for ConstantFolding {
+    fn optimize(&mut self, plan: &LogicalPlan) -> Result<LogicalPlan> {
+        match plan {
+            LogicalPlan::Filter { predicate, input } => {
+                let input = self.optimize(input)?;
+                match simplify(predicate, input.schema())? {
+                    Expr::Literal(ScalarValue::Boolean(Some(true))) => Ok(input),
+                    predicate => Ok(LogicalPlan::Filter {
+                        predicate,
+                        input: Arc::new(input),
+                    }),
+                }
+            }
+            LogicalPlan::Projection {
+                expr,
+                input,
+                schema,
+            } => {
+                let input = self.optimize(input)?;
+                let expr = expr
+                    .iter()
+                    .map(|e| {
+                        // keep the name of the expression, as it is the name of the
+                        // column of the projection
+                        let name = e.name(input.schema())?;
+                        let simplified = simplify(e, input.schema())?;
+                        Ok(if simplified.name(input.schema())? == name {
+                            simplified
+                        } else {
+                            Expr::Alias(Box::new(simplified), name)
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(LogicalPlan::Projection {
+                    expr,
+                    input: Arc::new(input),
+                    schema: schema.clone(),
+                })
+            }
+            LogicalPlan::Explain {
+                verbose,
+                plan,
+                stringified_plans,
+                schema,
+            } => utils::optimize_explain(
+                self,
+                *verbose,
+                &*plan,
+                stringified_plans,
+                &schema.as_ref().to_owned().into(),
+            ),
+            _ => self.optimize_children(plan),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "constant_folding"
+    }
+}
+
+impl ConstantFolding {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Simplifies `expr`, an expression on the columns of `schema`, bottom-up
+fn simplify(expr: &Expr, schema: &DFSchema) -> Result<Expr> {
+    let expr = match expr {
+        // `BETWEEN` is kept as is instead of being rewritten with its sub expressions
+        Expr::Between {
+            expr,
+            negated,
+            low,
+            high,
+        } => Expr::Between {
+            expr: Box::new(simplify(expr, schema)?),
+            negated: *negated,
+            low: Box::new(simplify(low, schema)?),
+            high: Box::new(simplify(high, schema)?),
+        },
+        _ => {
+            let sub_expressions = utils::expr_sub_expressions(expr)?
+                .iter()
+                .map(|e| simplify(e, schema))
+                .collect::<Result<Vec<_>>>()?;
+            utils::rewrite_expression(expr, &sub_expressions)?
+        }
+    };
+
+    if is_constant(&expr)? {
+        // an expression which fails to evaluate is left for the execution to report
+        if let Ok(value) = evaluate(&expr) {
+            return Ok(Expr::Literal(value));
+        }
+    }
+
+    Ok(match expr {
+        Expr::BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        } => match (*left, *right) {
+            (Expr::Literal(ScalarValue::Boolean(Some(true))), other)
+            | (other, Expr::Literal(ScalarValue::Boolean(Some(true)))) => other,
+            (Expr::Literal(ScalarValue::Boolean(Some(false))), _)
+            | (_, Expr::Literal(ScalarValue::Boolean(Some(false)))) => {
+                Expr::Literal(ScalarValue::Boolean(Some(false)))
+            }
+            (left, right) => left.and(right),
+        },
+        Expr::BinaryExpr {
+            left,
+            op: Operator::Or,
+            right,
+        } => match (*left, *right) {
+            (Expr::Literal(ScalarValue::Boolean(Some(false))), other)
+            | (other, Expr::Literal(ScalarValue::Boolean(Some(false)))) => other,
+            (Expr::Literal(ScalarValue::Boolean(Some(true))), _)
+            | (_, Expr::Literal(ScalarValue::Boolean(Some(true)))) => {
+                Expr::Literal(ScalarValue::Boolean(Some(true)))
+            }
+            (left, right) => left.or(right),
+        },
+        Expr::BinaryExpr { left, op, right } => {
+            let same_column = match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(l), Expr::Column(r)) => {
+                    l == r && is_non_null_non_float(l, schema)
+                }
+                _ => false,
+            };
+            match op {
+                Operator::Eq | Operator::NotEq if same_column => {
+                    Expr::Literal(ScalarValue::Boolean(Some(op == Operator::Eq)))
+                }
+                _ => Expr::BinaryExpr { left, op, right },
+            }
+        }
+        Expr::Not(expr) => match *expr {
+            Expr::Not(expr) => *expr,
+            expr => Expr::Not(Box::new(expr)),
+        },
+        expr => expr,
+    })
+}
+
+/// Whether `expr` only depends on literals, and can be evaluated once for all the rows
+fn is_constant(expr: &Expr) -> Result<bool> {
+    let evaluable = matches!(
+        expr,
+        Expr::BinaryExpr { .. }
+            | Expr::Not(_)
+            | Expr::Negative(_)
+            | Expr::IsNull(_)
+            | Expr::IsNotNull(_)
+            | Expr::Between { .. }
+            | Expr::InList { .. }
+            | Expr::Case { .. }
+            | Expr::Cast { .. }
+            | Expr::ScalarFunction { .. }
+    );
+    Ok(evaluable
+        && utils::expr_sub_expressions(expr)?
+            .iter()
+            .all(|e| matches!(e, Expr::Literal(_))))
+}
+
+/// Evaluates a constant expression
+fn evaluate(expr: &Expr) -> Result<ScalarValue> {
+    // the expression is evaluated against a batch of a single row and column
+    let schema = Schema::new(vec![Field::new(".", DataType::Null, true)]);
+    let batch = RecordBatch::try_new(
+        Arc::new(schema.clone()),
+        vec![Arc::new(NullArray::new(1)) as ArrayRef],
+    )?;
+
+    let ctx_state = ExecutionContextState {
+        datasources: HashMap::new(),
+        scalar_functions: HashMap::new(),
+        var_provider: HashMap::new(),
+        aggregate_functions: HashMap::new(),
+        config: ExecutionConfig::new(),
+    };
+    let physical_expr = DefaultPhysicalPlanner::default()
+        .create_physical_expr(expr, &schema, &ctx_state)?;
+    match physical_expr.evaluate(&batch)? {
+        ColumnarValue::Scalar(value) => Ok(value),
+        ColumnarValue::Array(array) => ScalarValue::try_from_array(&array, 0),
+    }
+}
+
+/// Whether the column `name` of `schema` never contains nulls, and is not a float
+/// column which may contain NaN, for which `x = x` is false
+fn is_non_null_non_float(name: &str, schema: &DFSchema) -> bool {
+    match schema.field_with_unqualified_name(name) {
+        Ok(field) => {
+            !field.is_nullable()
+                && !matches!(field.data_type(), DataType::Float32 | DataType::Float64)
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, in_list, lit, LogicalPlanBuilder};
+    use crate::physical_plan::functions::BuiltinScalarFunction;
+    use crate::test::*;
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = ConstantFolding::new()
+            .optimize(plan)
+            .expect("failed to optimize plan");
+        let formatted_plan = format!("{:?}", optimized_plan);
+        assert_eq!(formatted_plan, expected);
+    }
+
+    #[test]
+    fn fold_literals() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .filter(col("a").gt(lit(1i64) + lit(2i64)).and(in_list(
+                lit(3i64),
+                vec![lit(1i64), lit(3i64)],
+                false,
+            )))?
+            .build()?;
+
+        let expected = "\
+            Filter: #a Gt Int64(3)\
+            \n  TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn fold_cast_and_function() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let to_timestamp = Expr::ScalarFunction {
+            fun: BuiltinScalarFunction::ToTimestamp,
+            args: vec![lit("2020-09-08T12:00:00Z")],
+        };
+        let to_date = Expr::Cast {
+            expr: Box::new(lit("2020-01-01")),
+            data_type: DataType::Date32(arrow::datatypes::DateUnit::Day),
+        };
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![to_timestamp, to_date, col("a")])?
+            .build()?;
+
+        // the folded expressions keep their names
+        let expected = "\
+            Projection: TimestampNanosecond(1599566400000000000) AS totimestamp(Utf8(\"2020-09-08T12:00:00Z\")), \
+            Date32(\"18262\") AS CAST(Utf8(\"2020-01-01\") AS Date32(Day)), #a\
+            \n  TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn simplify_boolean_expressions() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .filter(
+                col("a")
+                    .eq(lit(1u32))
+                    .and(lit(true))
+                    .and(col("b").lt(lit(2u32)).or(lit(false)))
+                    .and(col("c").gt(col("a")).not().not()),
+            )?
+            .build()?;
+
+        let expected = "\
+            Filter: #a Eq UInt32(1) And #b Lt UInt32(2) And #c Gt #a\
+            \n  TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn remove_always_true_filter() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .filter(col("a").eq(col("a")).or(col("b").gt(lit(1u32))))?
+            .project(vec![col("a")])?
+            .filter(lit(1i64).lt(lit(2i64)))?
+            .build()?;
+
+        let expected = "\
+            Projection: #a\
+            \n  TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_always_false_filter() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .filter(col("a").not_eq(col("a")).and(col("b").gt(lit(1u32))))?
+            .build()?;
+
+        let expected = "\
+            Filter: Boolean(false)\
+            \n  TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,1285 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Decorrelate Subquery rewrites the subqueries of expressions (`EXISTS`, `IN` and
+//! scalar subqueries) into joins, which is the only way they can be executed.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::{
+    and, col, lit, max, when, DFSchema, Expr, JoinType, LogicalPlan, LogicalPlanBuilder,
+    Operator, Subquery,
+};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+use crate::physical_plan::aggregates::AggregateFunction;
+
+/// Rewrites the subqueries of filters and projections into joins:
+///
+/// * `[NOT] EXISTS (<subquery>)` in a filter becomes a semi (anti) join with the
+///   subquery, which only joins the first row of an uncorrelated subquery
+/// * `<column> [NOT] IN (<subquery>)` in a filter becomes a semi (anti) join with the
+///   subquery, on the column and the one returned by the subquery
+/// * a scalar subquery becomes a left join with the subquery, and is replaced by the
+///   column it returns. An uncorrelated scalar subquery fails at execution when it
+///   returns more than one row.
+///
+/// The correlated predicates of the subqueries, on columns of the enclosing query
+/// in their filters, are pulled up through projections, aggregations, filters and
+/// sorts. The equalities between one of their columns and one of the enclosing
+/// query (`outer.a = inner.b`) become join keys, and grouping expressions of the
+/// aggregations. The other predicates become the filter of the join, and are not
+/// supported below aggregations.
+///
+/// When the column or the value of the subquery of a `NOT IN` can be null, the anti
+/// join is followed by a filter on the number of rows and of non null values of the
+/// subquery, so that it follows the SQL semantics of nulls: this is not supported
+/// on correlated subqueries. The value of a correlated scalar subquery without rows
+/// for a key is null, except for a `COUNT`, which is 0; other expressions on a
+/// `COUNT` are not supported.
+pub struct DecorrelateSubquery {
+    /// the number of names of columns generated so far, to generate unique ones
+    next_id: usize,
+}
+
+impl OptimizerRule for DecorrelateSubquery {
+    fn name(&self) -> &str {
+        "decorrelate_subquery"
+    }
+
+    fn optimize(&mut self, plan: &LogicalPlan) -> Result<LogicalPlan> {
+        match plan {
+            LogicalPlan::Explain {
+                verbose,
+                plan,
+                stringified_plans,
+                schema,
+            } => {
+                let schema = schema.as_ref().to_owned().into();
+                utils::optimize_explain(
+                    self,
+                    *verbose,
+                    &*plan,
+                    stringified_plans,
+                    &schema,
+                )
+            }
+            LogicalPlan::Filter { predicate, input } => {
+                let input = self.optimize(input)?;
+                if contains_subquery(predicate)? {
+                    self.decorrelate_filter(predicate, input)
+                } else {
+                    LogicalPlanBuilder::from(&input)
+                        .filter(predicate.clone())?
+                        .build()
+                }
+            }
+            LogicalPlan::Projection {
+                expr,
+                input,
+                schema,
+            } => {
+                let mut input = self.optimize(input)?;
+                let mut new_expr = Vec::with_capacity(expr.len());
+                for (e, field) in expr.iter().zip(schema.fields()) {
+                    if !contains_subquery(e)? {
+                        new_expr.push(e.clone());
+                        continue;
+                    }
+                    let (e, plan) = self.rewrite_scalar_subqueries(e, input)?;
+                    input = plan;
+                    // keep the name of the original expression
+                    new_expr.push(match e {
+                        Expr::Alias(..) => e,
+                        e => e.alias(field.name()),
+                    });
+                }
+                LogicalPlanBuilder::from(&input).project(new_expr)?.build()
+            }
+            _ => {
+                for e in utils::expressions(plan) {
+                    if contains_subquery(&e)? {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "Subqueries are only supported in filters and projections, found '{:?}'",
+                            e
+                        )));
+                    }
+                }
+                self.optimize_children(plan)
+            }
+        }
+    }
+}
+
+impl DecorrelateSubquery {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self { next_id: 0 }
+    }
+
+    /// Rewrites a filter whose predicate contains subqueries into joins of `input`
+    /// with the subqueries
+    fn decorrelate_filter(
+        &mut self,
+        predicate: &Expr,
+        input: LogicalPlan,
+    ) -> Result<LogicalPlan> {
+        let input_schema = input.schema().clone();
+
+        let mut conjuncts = vec![];
+        split_conjuncts(predicate, &mut conjuncts);
+
+        // the predicates that only use the columns of the input are evaluated before
+        // the joins, the others (on scalar subqueries or on the columns of an
+        // enclosing query) after them
+        let mut predicates = vec![];
+        let mut subquery_predicates = vec![];
+        let mut post_join_predicates = vec![];
+        for conjunct in conjuncts {
+            match conjunct {
+                Expr::Exists { .. } | Expr::InSubquery { .. } => {
+                    subquery_predicates.push(conjunct)
+                }
+                _ if contains_subquery(conjunct)? => {
+                    post_join_predicates.push(conjunct.clone())
+                }
+                _ if !outer_columns(conjunct, &input_schema)?.is_empty() => {
+                    post_join_predicates.push(conjunct.clone())
+                }
+                _ => predicates.push(conjunct.clone()),
+            }
+        }
+
+        let mut plan = match conjunction(predicates) {
+            Some(predicate) => LogicalPlanBuilder::from(&input)
+                .filter(predicate)?
+                .build()?,
+            None => input,
+        };
+
+        for predicate in subquery_predicates {
+            plan = match predicate {
+                Expr::Exists { subquery, negated } => {
+                    let (subquery_plan, correlation) =
+                        self.decorrelate_subquery(subquery)?;
+                    if correlation.is_empty() {
+                        // every row matches the first row of the subquery, if any
+                        let subquery_plan =
+                            LogicalPlanBuilder::from(&subquery_plan).limit(1)?.build()?;
+                        LogicalPlanBuilder::from(&plan)
+                            .join_with_filter(
+                                &subquery_plan,
+                                semi_join_type(*negated),
+                                &[],
+                                &[],
+                                lit(true),
+                            )?
+                            .build()?
+                    } else {
+                        self.join_correlated_subquery(
+                            &plan,
+                            &subquery_plan,
+                            semi_join_type(*negated),
+                            correlation,
+                        )?
+                    }
+                }
+                Expr::InSubquery {
+                    expr,
+                    subquery,
+                    negated,
+                } => {
+                    let column = match expr.as_ref() {
+                        Expr::Column(name) => name.clone(),
+                        other => {
+                            return Err(DataFusionError::NotImplemented(format!(
+                                "Unsupported expression '{:?}' in IN subquery, only columns are supported",
+                                other
+                            )))
+                        }
+                    };
+                    let value = subquery.plan.schema().field(0).name().clone();
+                    let (subquery_plan, mut correlation) =
+                        self.decorrelate_subquery(subquery)?;
+                    let null_aware = *negated
+                        && (expr.nullable(&input_schema)?
+                            || subquery.plan.schema().field(0).is_nullable());
+                    if null_aware && !correlation.is_empty() {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "NOT IN is not supported on correlated subqueries when '{:?}' or the value of the subquery can be null",
+                            expr
+                        )));
+                    }
+                    correlation.keys.insert(0, (column, value.clone()));
+                    let plan = self.join_correlated_subquery(
+                        &plan,
+                        &subquery_plan,
+                        semi_join_type(*negated),
+                        correlation,
+                    )?;
+                    if null_aware {
+                        // the anti join keeps the rows whose value is null or is not
+                        // in the subquery, for which NOT IN is only true when the
+                        // subquery is empty, or when neither their value nor the
+                        // values of the subquery are null
+                        let nulls = nulls_subquery(&subquery.plan, &value)?;
+                        let (plan, nulls) = self.join_scalar_subquery(plan, &nulls)?;
+                        post_join_predicates.push(
+                            Expr::IsNull(Box::new(nulls.clone()))
+                                .or(Expr::IsNotNull(Box::new(expr.as_ref().clone()))
+                                    .and(nulls.eq(lit(0u8)))),
+                        );
+                        plan
+                    } else {
+                        plan
+                    }
+                }
+                _ => unreachable!(),
+            };
+        }
+
+        let mut post_join_predicate = None;
+        for predicate in post_join_predicates {
+            let (predicate, new_plan) =
+                self.rewrite_scalar_subqueries(&predicate, plan)?;
+            plan = new_plan;
+            post_join_predicate = match post_join_predicate {
+                Some(acc) => Some(and(acc, predicate)),
+                None => Some(predicate),
+            };
+        }
+        if let Some(predicate) = post_join_predicate {
+            plan = LogicalPlanBuilder::from(&plan).filter(predicate)?.build()?;
+        }
+
+        // a filter does not change the schema of its input
+        if plan.schema().fields() != input_schema.fields() {
+            let columns = input_schema
+                .fields()
+                .iter()
+                .map(|field| col(field.name()))
+                .collect();
+            plan = LogicalPlanBuilder::from(&plan).project(columns)?.build()?;
+        }
+        Ok(plan)
+    }
+
+    /// Replaces the scalar subqueries of an expression by the column of a left join of
+    /// `plan` with each of them
+    fn rewrite_scalar_subqueries(
+        &mut self,
+        expr: &Expr,
+        plan: LogicalPlan,
+    ) -> Result<(Expr, LogicalPlan)> {
+        match expr {
+            Expr::ScalarSubquery(subquery) => {
+                let (plan, value) = self.join_scalar_subquery(plan, subquery)?;
+                Ok((value, plan))
+            }
+            Expr::Exists { .. } | Expr::InSubquery { .. } => {
+                Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported subquery expression '{:?}', EXISTS and IN subqueries are only supported as conjuncts of a filter",
+                    expr
+                )))
+            }
+            _ if !contains_subquery(expr)? => Ok((expr.clone(), plan)),
+            _ => {
+                let mut plan = plan;
+                let mut new_expressions = vec![];
+                for e in utils::expr_sub_expressions(expr)? {
+                    let (e, new_plan) = self.rewrite_scalar_subqueries(&e, plan)?;
+                    plan = new_plan;
+                    new_expressions.push(e);
+                }
+                Ok((utils::rewrite_expression(expr, &new_expressions)?, plan))
+            }
+        }
+    }
+
+    /// Left joins `plan` with a scalar subquery, returning the new plan and the
+    /// expression of the value of the subquery
+    fn join_scalar_subquery(
+        &mut self,
+        plan: LogicalPlan,
+        subquery: &Subquery,
+    ) -> Result<(LogicalPlan, Expr)> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let value = subquery.plan.schema().field(0).name().clone();
+        let (subquery_plan, correlation) = self.decorrelate_subquery(subquery)?;
+
+        // rename the columns of the subquery, so that they do not clash with the ones
+        // of `plan`
+        let value_alias = format!("__scalar_subquery_{}", id);
+        let mut exprs = vec![col(&value).alias(&value_alias)];
+        let mut join_keys = vec![];
+        if correlation.is_empty() {
+            // uncorrelated: every row of `plan` matches the single row of the subquery,
+            // which fails at execution when it has more than one
+            let subquery_plan = if at_most_one_row(&subquery_plan) {
+                subquery_plan
+            } else {
+                LogicalPlanBuilder::from(&subquery_plan)
+                    .enforce_single_row()?
+                    .build()?
+            };
+            let key = format!("__scalar_subquery_{}_key", id);
+            let outer_key = format!("__scalar_subquery_{}_outer_key", id);
+            exprs.push(lit(1i64).alias(&key));
+            let mut outer_exprs = plan
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| col(field.name()))
+                .collect::<Vec<_>>();
+            outer_exprs.push(lit(1i64).alias(&outer_key));
+            let plan = LogicalPlanBuilder::from(&plan)
+                .project(outer_exprs)?
+                .build()?;
+            let subquery_plan = LogicalPlanBuilder::from(&subquery_plan)
+                .project(exprs)?
+                .build()?;
+            let plan = LogicalPlanBuilder::from(&plan)
+                .join(&subquery_plan, JoinType::Left, &[&outer_key], &[&key])?
+                .build()?;
+            return Ok((plan, col(&value_alias)));
+        }
+        for (i, (outer, inner)) in correlation.keys.iter().enumerate() {
+            let key = format!("__scalar_subquery_{}_key_{}", id, i);
+            exprs.push(col(inner).alias(&key));
+            join_keys.push((outer.clone(), key));
+        }
+        let (subquery_plan, filter) =
+            self.project_filter_columns(&subquery_plan, exprs, &correlation.filters)?;
+        let plan =
+            join_subquery(&plan, &subquery_plan, JoinType::Left, &join_keys, filter)?;
+
+        // the keys without rows in the subquery are not joined, and their value is
+        // null, while a COUNT of no rows is 0
+        let value = if is_count(&subquery_plan, &value)? {
+            when(Expr::IsNull(Box::new(col(&value_alias))), lit(0u64))
+                .otherwise(col(&value_alias))?
+        } else {
+            col(&value_alias)
+        };
+        Ok((plan, value))
+    }
+
+    /// Optimizes the plan of a subquery and pulls its correlated predicates up,
+    /// returning the new plan and these predicates
+    fn decorrelate_subquery(
+        &mut self,
+        subquery: &Subquery,
+    ) -> Result<(LogicalPlan, Correlation)> {
+        let plan = self.optimize(&subquery.plan)?;
+        pull_up_correlated_predicates(&plan)
+    }
+
+    /// Joins `plan` with a decorrelated subquery on the keys of its correlation, the
+    /// joined rows also satisfying its filters
+    fn join_correlated_subquery(
+        &mut self,
+        plan: &LogicalPlan,
+        subquery: &LogicalPlan,
+        join_type: JoinType,
+        correlation: Correlation,
+    ) -> Result<LogicalPlan> {
+        if correlation.filters.is_empty() {
+            return join_subquery(plan, subquery, join_type, &correlation.keys, None);
+        }
+        let exprs = subquery
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| col(field.name()))
+            .collect();
+        let (subquery, filter) =
+            self.project_filter_columns(subquery, exprs, &correlation.filters)?;
+        join_subquery(plan, &subquery, join_type, &correlation.keys, filter)
+    }
+
+    /// Projects a decorrelated subquery on `exprs` and on the columns of the subquery
+    /// used by the filters of its correlation, renamed so that they do not clash
+    /// with the columns of the enclosing query. Returns the new plan and the
+    /// conjunction of the filters on the renamed columns.
+    fn project_filter_columns(
+        &mut self,
+        subquery: &LogicalPlan,
+        mut exprs: Vec<Expr>,
+        filters: &[Expr],
+    ) -> Result<(LogicalPlan, Option<Expr>)> {
+        let mut names = HashMap::new();
+        for column in inner_columns(filters, subquery.schema())? {
+            let alias = format!("__correlated_column_{}", self.next_id);
+            self.next_id += 1;
+            exprs.push(col(&column).alias(&alias));
+            names.insert(column, alias);
+        }
+        let filters = filters
+            .iter()
+            .map(|filter| rename_columns(filter, &names))
+            .collect::<Result<Vec<_>>>()?;
+        let plan = LogicalPlanBuilder::from(subquery).project(exprs)?.build()?;
+        Ok((plan, conjunction(filters)))
+    }
+}
+
+/// The correlated predicates of a subquery, pulled up from its filters
+#[derive(Debug, Default)]
+struct Correlation {
+    /// The pairs of (outer, inner) columns of its equalities of columns, the keys of
+    /// its join with the enclosing query
+    keys: Vec<(String, String)>,
+    /// Its other predicates, on the columns of the subquery and of the enclosing
+    /// query, that the joined rows must also satisfy
+    filters: Vec<Expr>,
+}
+
+impl Correlation {
+    fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.filters.is_empty()
+    }
+
+    /// The columns of `schema` used by the correlation, which the nodes of the
+    /// subquery must keep
+    fn inner_columns(&self, schema: &DFSchema) -> Result<Vec<String>> {
+        let mut columns = self
+            .keys
+            .iter()
+            .map(|(_, inner)| inner.clone())
+            .collect::<Vec<_>>();
+        for column in inner_columns(&self.filters, schema)? {
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+        Ok(columns)
+    }
+}
+
+/// Pulls the predicates on the columns of an enclosing query up from the filters of
+/// `plan`, returning the new plan, which outputs the columns these predicates use,
+/// and the predicates.
+fn pull_up_correlated_predicates(
+    plan: &LogicalPlan,
+) -> Result<(LogicalPlan, Correlation)> {
+    match plan {
+        LogicalPlan::Filter { predicate, input } => {
+            let (input, mut correlation) = pull_up_correlated_predicates(input)?;
+            let input_schema = input.schema().clone();
+
+            let mut conjuncts = vec![];
+            split_conjuncts(predicate, &mut conjuncts);
+            let mut predicates = vec![];
+            for conjunct in conjuncts {
+                let outer = outer_columns(conjunct, &input_schema)?;
+                if outer.is_empty() {
+                    predicates.push(conjunct.clone());
+                    continue;
+                }
+                // the equalities of an outer and an inner column are join keys, the
+                // other predicates filters of the join
+                match conjunct {
+                    Expr::BinaryExpr {
+                        left,
+                        op: Operator::Eq,
+                        right,
+                    } => match (left.as_ref(), right.as_ref()) {
+                        (Expr::Column(l), Expr::Column(r))
+                            if outer.contains(l) && !outer.contains(r) =>
+                        {
+                            correlation.keys.push((l.clone(), r.clone()))
+                        }
+                        (Expr::Column(l), Expr::Column(r))
+                            if outer.contains(r) && !outer.contains(l) =>
+                        {
+                            correlation.keys.push((r.clone(), l.clone()))
+                        }
+                        _ => correlation.filters.push(conjunct.clone()),
+                    },
+                    _ => correlation.filters.push(conjunct.clone()),
+                }
+            }
+
+            let plan = match conjunction(predicates) {
+                Some(predicate) => LogicalPlanBuilder::from(&input)
+                    .filter(predicate)?
+                    .build()?,
+                None => input,
+            };
+            Ok((plan, correlation))
+        }
+        LogicalPlan::Projection { expr, input, .. } => {
+            let (input, correlation) = pull_up_correlated_predicates(input)?;
+            let mut expr = expr.clone();
+            for inner in correlation.inner_columns(input.schema())? {
+                let column = col(&inner);
+                if !expr.contains(&column) {
+                    expr.push(column);
+                }
+            }
+            let plan = LogicalPlanBuilder::from(&input).project(expr)?.build()?;
+            Ok((plan, correlation))
+        }
+        LogicalPlan::Aggregate {
+            group_expr,
+            grouping_sets: None,
+            aggr_expr,
+            input,
+            ..
+        } => {
+            let (input, correlation) = pull_up_correlated_predicates(input)?;
+            if let Some(filter) = correlation.filters.first() {
+                return Err(unsupported_correlation(filter));
+            }
+            let mut group_expr = group_expr.clone();
+            for (_, inner) in &correlation.keys {
+                let column = col(inner);
+                if !group_expr.contains(&column) {
+                    group_expr.push(column);
+                }
+            }
+            let plan = LogicalPlanBuilder::from(&input)
+                .aggregate(group_expr, aggr_expr.clone())?
+                .build()?;
+            Ok((plan, correlation))
+        }
+        LogicalPlan::Aggregate {
+            group_expr,
+            grouping_sets: Some(grouping_sets),
+            aggr_expr,
+            input,
+            ..
+        } => {
+            // the keys are grouped by in every grouping set
+            let (input, correlation) = pull_up_correlated_predicates(input)?;
+            if let Some(filter) = correlation.filters.first() {
+                return Err(unsupported_correlation(filter));
+            }
+            let grouping_sets = grouping_sets
+                .iter()
+                .map(|set| {
+                    let mut set = set
+                        .iter()
+                        .map(|i| group_expr[*i].clone())
+                        .collect::<Vec<_>>();
+                    set.extend(correlation.keys.iter().map(|(_, inner)| col(inner)));
+                    set
+                })
+                .collect();
+            let plan = LogicalPlanBuilder::from(&input)
+                .aggregate_grouping_sets(grouping_sets, aggr_expr.clone())?
+                .build()?;
+            Ok((plan, correlation))
+        }
+        LogicalPlan::Sort {
+            expr,
+            input,
+            fetch: None,
+        } => {
+            let (input, correlation) = pull_up_correlated_predicates(input)?;
+            let plan = LogicalPlanBuilder::from(&input)
+                .sort(expr.clone())?
+                .build()?;
+            Ok((plan, correlation))
+        }
+        _ => {
+            if has_outer_columns(plan)? {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Correlated subqueries are only supported when their correlated predicates are in filters above their joins, found {:?}",
+                    plan
+                )));
+            }
+            Ok((plan.clone(), Correlation::default()))
+        }
+    }
+}
+
+/// Joins `plan` with a decorrelated subquery on the pairs of (outer, inner) columns,
+/// the joined rows also satisfying `filter`, if any
+fn join_subquery(
+    plan: &LogicalPlan,
+    subquery: &LogicalPlan,
+    join_type: JoinType,
+    keys: &[(String, String)],
+    filter: Option<Expr>,
+) -> Result<LogicalPlan> {
+    let mut outer = keys
+        .iter()
+        .map(|(outer, _)| outer.clone())
+        .collect::<Vec<_>>();
+    if let Some(filter) = &filter {
+        outer.extend(outer_columns(filter, subquery.schema())?);
+    }
+    for outer in outer {
+        if plan.schema().field_with_unqualified_name(&outer).is_err() {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Column '{}' is not a column of the query enclosing the subquery, only the columns of the enclosing query can be referenced",
+                outer
+            )));
+        }
+    }
+    let left_keys: Vec<&str> = keys.iter().map(|(l, _)| l.as_str()).collect();
+    let right_keys: Vec<&str> = keys.iter().map(|(_, r)| r.as_str()).collect();
+    let builder = LogicalPlanBuilder::from(plan);
+    match filter {
+        Some(filter) => builder.join_with_filter(
+            subquery,
+            join_type,
+            &left_keys,
+            &right_keys,
+            filter,
+        )?,
+        None => builder.join(subquery, join_type, &left_keys, &right_keys)?,
+    }
+    .build()
+}
+
+/// Whether `plan` is known to produce at most one row, e.g. an aggregation without
+/// grouping expressions
+fn at_most_one_row(plan: &LogicalPlan) -> bool {
+    match plan {
+        LogicalPlan::Aggregate {
+            group_expr,
+            grouping_sets: None,
+            ..
+        } => group_expr.is_empty(),
+        LogicalPlan::Limit { n, input } => *n <= 1 || at_most_one_row(input),
+        LogicalPlan::Projection { input, .. }
+        | LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::EnforceSingleRow { input } => at_most_one_row(input),
+        LogicalPlan::EmptyRelation { .. } => true,
+        _ => false,
+    }
+}
+
+/// The subquery of whether the values of the column `value` of `subquery` have
+/// nulls: null when it has no rows, else 1 if they have nulls and 0 otherwise
+fn nulls_subquery(subquery: &LogicalPlan, value: &str) -> Result<Subquery> {
+    let is_null =
+        when(Expr::IsNull(Box::new(col(value))), lit(1u8)).otherwise(lit(0u8))?;
+    Ok(Subquery::new(
+        LogicalPlanBuilder::from(subquery)
+            .aggregate(vec![], vec![max(is_null)])?
+            .build()?,
+    ))
+}
+
+/// Whether the column `name` of `plan` is a `COUNT`, which is 0 rather than null
+/// on no rows. Errors when the column is computed from a `COUNT` by another
+/// expression, as its value on no rows is not known.
+fn is_count(plan: &LogicalPlan, name: &str) -> Result<bool> {
+    match plan {
+        LogicalPlan::Projection {
+            expr,
+            input,
+            schema,
+        } => {
+            let expr = match schema.index_of(name) {
+                Ok(i) => &expr[i],
+                Err(_) => return Ok(false),
+            };
+            match unalias(expr) {
+                Expr::Column(name) => is_count(input, name),
+                other => {
+                    let mut columns = HashSet::new();
+                    utils::expr_to_column_names(other, &mut columns)?;
+                    for column in columns {
+                        if is_count(input, &column)? {
+                            return Err(unsupported_count(expr));
+                        }
+                    }
+                    Ok(false)
+                }
+            }
+        }
+        LogicalPlan::Aggregate {
+            group_expr,
+            aggr_expr,
+            schema,
+            ..
+        } => match schema.index_of(name) {
+            Ok(i) if i >= group_expr.len() => Ok(matches!(
+                unalias(&aggr_expr[i - group_expr.len()]),
+                Expr::AggregateFunction {
+                    fun: AggregateFunction::Count,
+                    ..
+                } | Expr::AggregateFunction {
+                    fun: AggregateFunction::ApproxDistinct,
+                    ..
+                }
+            )),
+            _ => Ok(false),
+        },
+        LogicalPlan::Filter { predicate, input } => {
+            if is_count(input, name)? {
+                return Err(unsupported_count(predicate));
+            }
+            Ok(false)
+        }
+        LogicalPlan::Sort { input, .. } | LogicalPlan::Limit { input, .. } => {
+            is_count(input, name)
+        }
+        _ => Ok(false),
+    }
+}
+
+fn unalias(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Alias(expr, _) => unalias(expr),
+        other => other,
+    }
+}
+
+fn unsupported_count(expr: &Expr) -> DataFusionError {
+    DataFusionError::NotImplemented(format!(
+        "Unsupported expression '{:?}' on a COUNT in a correlated scalar subquery, only the COUNT itself is supported",
+        expr
+    ))
+}
+
+fn semi_join_type(negated: bool) -> JoinType {
+    if negated {
+        JoinType::Anti
+    } else {
+        JoinType::Semi
+    }
+}
+
+fn unsupported_correlation(expr: &Expr) -> DataFusionError {
+    DataFusionError::NotImplemented(format!(
+        "Unsupported correlated predicate '{:?}' below an aggregation in subquery, only equalities of columns are supported there",
+        expr
+    ))
+}
+
+/// Whether an expression contains a subquery
+fn contains_subquery(expr: &Expr) -> Result<bool> {
+    match expr {
+        Expr::Exists { .. } | Expr::InSubquery { .. } | Expr::ScalarSubquery(_) => {
+            Ok(true)
+        }
+        _ => {
+            for e in utils::expr_sub_expressions(expr)? {
+                if contains_subquery(&e)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// The columns of an expression that are not columns of `schema`, i.e. that belong
+/// to an enclosing query
+fn outer_columns(expr: &Expr, schema: &DFSchema) -> Result<HashSet<String>> {
+    let mut columns = HashSet::new();
+    utils::expr_to_column_names(expr, &mut columns)?;
+    Ok(columns
+        .into_iter()
+        .filter(|name| schema.field_with_unqualified_name(name).is_err())
+        .collect())
+}
+
+/// The columns of `schema` used by `exprs`, i.e. that belong to the subquery of
+/// `schema`, sorted by name
+fn inner_columns(exprs: &[Expr], schema: &DFSchema) -> Result<Vec<String>> {
+    let mut columns = HashSet::new();
+    for expr in exprs {
+        utils::expr_to_column_names(expr, &mut columns)?;
+    }
+    let mut columns = columns
+        .into_iter()
+        .filter(|name| schema.field_with_unqualified_name(name).is_ok())
+        .collect::<Vec<_>>();
+    columns.sort();
+    Ok(columns)
+}
+
+/// Replaces the columns of an expression by the ones they are mapped to in `names`
+fn rename_columns(expr: &Expr, names: &HashMap<String, String>) -> Result<Expr> {
+    match expr {
+        Expr::Column(name) => Ok(names
+            .get(name)
+            .map_or_else(|| expr.clone(), |name| col(name))),
+        _ => {
+            let exprs = utils::expr_sub_expressions(expr)?
+                .iter()
+                .map(|e| rename_columns(e, names))
+                .collect::<Result<Vec<_>>>()?;
+            utils::rewrite_expression(expr, &exprs)
+        }
+    }
+}
+
+/// Whether a node of a plan uses columns that are not columns of its inputs
+fn has_outer_columns(plan: &LogicalPlan) -> Result<bool> {
+    let inputs = utils::inputs(plan);
+    if !inputs.is_empty() {
+        let mut fields = vec![];
+        for input in &inputs {
+            fields.extend(input.schema().fields().iter().cloned());
+        }
+        // inputs can have columns with the same name (e.g. unions)
+        let mut names = HashSet::new();
+        fields.retain(|field| names.insert(field.name().clone()));
+        let schema = DFSchema::new(fields)?;
+        for expr in utils::expressions(plan) {
+            if !outer_columns(&expr, &schema)?.is_empty() {
+                return Ok(true);
+            }
+        }
+    }
+    for input in inputs {
+        if has_outer_columns(input)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Splits a predicate into its conjuncts
+fn split_conjuncts<'a>(expr: &'a Expr, accum: &mut Vec<&'a Expr>) {
+    match expr {
+        Expr::BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        } => {
+            split_conjuncts(left, accum);
+            split_conjuncts(right, accum);
+        }
+        other => accum.push(other),
+    }
+}
+
+/// Combines predicates with `AND`, returning `None` if there are none
+fn conjunction(exprs: Vec<Expr>) -> Option<Expr> {
+    exprs.into_iter().fold(None, |acc, expr| match acc {
+        Some(acc) => Some(and(acc, expr)),
+        None => Some(expr),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{
+        count, exists, in_subquery, not_exists, not_in_subquery, scalar_subquery,
+    };
+    use crate::test::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    fn optimize(plan: &LogicalPlan) -> Result<LogicalPlan> {
+        let mut rule = DecorrelateSubquery::new();
+        rule.optimize(plan)
+    }
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = optimize(plan).expect("failed to optimize plan");
+        let formatted_plan = format!("{:?}", optimized_plan);
+        assert_eq!(formatted_plan, expected);
+    }
+
+    /// Returns the types of the joins of a plan, depth first
+    fn join_types(plan: &LogicalPlan) -> Vec<JoinType> {
+        let mut join_types = vec![];
+        if let LogicalPlan::Join { join_type, .. } = plan {
+            join_types.push(*join_type);
+        }
+        for input in utils::inputs(plan) {
+            join_types.extend(join_types(input));
+        }
+        join_types
+    }
+
+    /// a table with the columns `d`, `e` and `f`
+    fn subquery_table_scan() -> Result<LogicalPlan> {
+        let schema = Schema::new(vec![
+            Field::new("d", DataType::UInt32, false),
+            Field::new("e", DataType::UInt32, false),
+            Field::new("f", DataType::UInt32, false),
+        ]);
+        LogicalPlanBuilder::scan_empty("sq", &schema, None)?.build()
+    }
+
+    /// a table with the nullable columns `d`, `e` and `f`
+    fn nullable_subquery_table_scan() -> Result<LogicalPlan> {
+        let schema = Schema::new(vec![
+            Field::new("d", DataType::UInt32, true),
+            Field::new("e", DataType::UInt32, true),
+            Field::new("f", DataType::UInt32, true),
+        ]);
+        LogicalPlanBuilder::scan_empty("sq", &schema, None)?.build()
+    }
+
+    #[test]
+    fn in_subquery_to_semi_join() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .project(vec![col("d")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(and(col("b").eq(lit(1u32)), in_subquery(col("a"), subquery)))?
+            .project(vec![col("a")])?
+            .build()?;
+
+        let expected = "Projection: #a\
+        \n  Join: a = d\
+        \n    Filter: #b Eq UInt32(1)\
+        \n      TableScan: test projection=None\
+        \n    Projection: #d\
+        \n      TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(vec![JoinType::Semi], join_types(&optimize(&plan)?));
+        Ok(())
+    }
+
+    #[test]
+    fn not_in_subquery_to_anti_join() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .project(vec![col("d")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(not_in_subquery(col("a"), subquery))?
+            .build()?;
+
+        assert_eq!(vec![JoinType::Anti], join_types(&optimize(&plan)?));
+        Ok(())
+    }
+
+    #[test]
+    fn not_in_nullable_subquery() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&nullable_subquery_table_scan()?)
+            .project(vec![col("d")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(not_in_subquery(col("a"), subquery))?
+            .build()?;
+
+        // the rows kept by the anti join are filtered on whether the subquery is
+        // empty and has nulls
+        let expected = "Projection: #a, #b, #c\
+        \n  Filter: #__scalar_subquery_0 IS NULL Or #a IS NOT NULL And #__scalar_subquery_0 Eq UInt8(0)\
+        \n    Join: __scalar_subquery_0_outer_key = __scalar_subquery_0_key\
+        \n      Projection: #a, #b, #c, Int64(1) AS __scalar_subquery_0_outer_key\
+        \n        Join: a = d\
+        \n          TableScan: test projection=None\
+        \n          Projection: #d\
+        \n            TableScan: sq projection=None\
+        \n      Projection: #MAX(CASE WHEN #d IS NULL THEN UInt8(1) ELSE UInt8(0) END) AS __scalar_subquery_0, Int64(1) AS __scalar_subquery_0_key\
+        \n        Aggregate: groupBy=[[]], aggr=[[MAX(CASE WHEN #d IS NULL THEN UInt8(1) ELSE UInt8(0) END)]]\
+        \n          Projection: #d\
+        \n            TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(
+            vec![JoinType::Left, JoinType::Anti],
+            join_types(&optimize(&plan)?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn correlated_not_in_nullable_subquery() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&nullable_subquery_table_scan()?)
+            .filter(col("e").eq(col("b")))?
+            .project(vec![col("d")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(not_in_subquery(col("a"), subquery))?
+            .build()?;
+
+        let err = optimize(&plan).expect_err("optimization should have failed");
+        assert_eq!(
+            "NotImplemented(\"NOT IN is not supported on correlated subqueries when \\'#a\\' or the value of the subquery can be null\")",
+            format!("{:?}", err)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn correlated_in_subquery() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .filter(and(col("e").eq(col("b")), col("f").gt(lit(1u32))))?
+            .project(vec![col("d")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(in_subquery(col("a"), subquery))?
+            .build()?;
+
+        // the correlated predicate becomes a join key
+        let expected = "Join: a = d, b = e\
+        \n  TableScan: test projection=None\
+        \n  Projection: #d, #e\
+        \n    Filter: #f Gt UInt32(1)\
+        \n      TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn correlated_exists_to_semi_join() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .filter(and(col("a").eq(col("d")), col("e").gt(lit(1u32))))?
+            .project(vec![col("f")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(exists(subquery.clone()))?
+            .build()?;
+
+        let expected = "Join: a = d\
+        \n  TableScan: test projection=None\
+        \n  Projection: #f, #d\
+        \n    Filter: #e Gt UInt32(1)\
+        \n      TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(vec![JoinType::Semi], join_types(&optimize(&plan)?));
+
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(not_exists(subquery))?
+            .build()?;
+        assert_eq!(vec![JoinType::Anti], join_types(&optimize(&plan)?));
+        Ok(())
+    }
+
+    #[test]
+    fn uncorrelated_exists() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?).build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(exists(subquery.clone()))?
+            .build()?;
+
+        // every row matches the first row of the subquery, if any
+        let expected = "Join: filter=Boolean(true)\
+        \n  TableScan: test projection=None\
+        \n  Limit: 1\
+        \n    TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(vec![JoinType::Semi], join_types(&optimize(&plan)?));
+
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(not_exists(subquery))?
+            .build()?;
+        assert_eq!(vec![JoinType::Anti], join_types(&optimize(&plan)?));
+        Ok(())
+    }
+
+    #[test]
+    fn correlated_non_equality_predicates() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .filter(and(col("a").eq(col("d")), col("e").lt(col("b"))))?
+            .project(vec![col("f")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(not_exists(subquery))?
+            .build()?;
+
+        // the other correlated predicates become the filter of the join, on renamed
+        // columns of the subquery
+        let expected = "Join: a = d, filter=#__correlated_column_0 Lt #b\
+        \n  TableScan: test projection=None\
+        \n  Projection: #f, #d, #e, #e AS __correlated_column_0\
+        \n    Projection: #f, #d, #e\
+        \n      TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(vec![JoinType::Anti], join_types(&optimize(&plan)?));
+
+        // without equalities, the rows are only matched by the filter
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .filter(col("d").lt(col("a")))?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(exists(subquery))?
+            .build()?;
+        let expected = "Join: filter=#__correlated_column_0 Lt #a\
+        \n  TableScan: test projection=None\
+        \n  Projection: #d, #e, #f, #d AS __correlated_column_0\
+        \n    TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_correlated_predicate() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .filter(col("d").lt(col("a")))?
+            .aggregate(vec![], vec![max(col("e"))])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(col("b").gt(scalar_subquery(subquery)))?
+            .build()?;
+
+        let err = optimize(&plan).expect_err("optimization should have failed");
+        assert_eq!(
+            "NotImplemented(\"Unsupported correlated predicate \\'#d Lt #a\\' below an aggregation in subquery, only equalities of columns are supported there\")",
+            format!("{:?}", err)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn correlated_scalar_subquery_in_filter() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .filter(col("e").eq(col("b")))?
+            .aggregate(vec![], vec![max(col("d"))])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(col("a").gt(scalar_subquery(subquery)))?
+            .project(vec![col("a")])?
+            .build()?;
+
+        // the correlated column is added to the grouping expressions
+        let expected = "Projection: #a\
+        \n  Projection: #a, #b, #c\
+        \n    Filter: #a Gt #__scalar_subquery_0\
+        \n      Join: b = __scalar_subquery_0_key_0\
+        \n        TableScan: test projection=None\
+        \n        Projection: #MAX(d) AS __scalar_subquery_0, #e AS __scalar_subquery_0_key_0\
+        \n          Aggregate: groupBy=[[#e]], aggr=[[MAX(#d)]]\
+        \n            TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(vec![JoinType::Left], join_types(&optimize(&plan)?));
+        Ok(())
+    }
+
+    #[test]
+    fn correlated_count_subquery() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .filter(col("e").eq(col("b")))?
+            .aggregate(vec![], vec![count(col("d"))])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(col("a").gt(scalar_subquery(subquery)))?
+            .project(vec![col("a")])?
+            .build()?;
+
+        // the count of the keys without rows is 0
+        let expected = "Projection: #a\
+        \n  Projection: #a, #b, #c\
+        \n    Filter: #a Gt CASE WHEN #__scalar_subquery_0 IS NULL THEN UInt64(0) ELSE #__scalar_subquery_0 END\
+        \n      Join: b = __scalar_subquery_0_key_0\
+        \n        TableScan: test projection=None\
+        \n        Projection: #COUNT(d) AS __scalar_subquery_0, #e AS __scalar_subquery_0_key_0\
+        \n          Aggregate: groupBy=[[#e]], aggr=[[COUNT(#d)]]\
+        \n            TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn correlated_expression_on_count_subquery() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .filter(col("e").eq(col("b")))?
+            .aggregate(vec![], vec![count(col("d"))])?
+            .project(vec![col("COUNT(d)").eq(lit(0u64))])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(scalar_subquery(subquery))?
+            .build()?;
+
+        let err = optimize(&plan).expect_err("optimization should have failed");
+        assert_eq!(
+            "NotImplemented(\"Unsupported expression \\'#COUNT(d) Eq UInt64(0)\\' on a COUNT in a correlated scalar subquery, only the COUNT itself is supported\")",
+            format!("{:?}", err)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn uncorrelated_scalar_subquery_in_projection() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .aggregate(vec![], vec![max(col("d"))])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .project(vec![col("a"), scalar_subquery(subquery)])?
+            .build()?;
+
+        // the original name of the expression is kept
+        let expected = "Projection: #a, #__scalar_subquery_0 AS (MAX(d))\
+        \n  Join: __scalar_subquery_0_outer_key = __scalar_subquery_0_key\
+        \n    Projection: #a, #b, #c, Int64(1) AS __scalar_subquery_0_outer_key\
+        \n      TableScan: test projection=None\
+        \n    Projection: #MAX(d) AS __scalar_subquery_0, Int64(1) AS __scalar_subquery_0_key\
+        \n      Aggregate: groupBy=[[]], aggr=[[MAX(#d)]]\
+        \n        TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn uncorrelated_scalar_subquery_of_many_rows() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .project(vec![col("d")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(col("a").eq(scalar_subquery(subquery)))?
+            .build()?;
+
+        // the subquery fails at execution when it has more than one row
+        let expected = "Projection: #a, #b, #c\
+        \n  Filter: #a Eq #__scalar_subquery_0\
+        \n    Join: __scalar_subquery_0_outer_key = __scalar_subquery_0_key\
+        \n      Projection: #a, #b, #c, Int64(1) AS __scalar_subquery_0_outer_key\
+        \n        TableScan: test projection=None\
+        \n      Projection: #d AS __scalar_subquery_0, Int64(1) AS __scalar_subquery_0_key\
+        \n        EnforceSingleRow\
+        \n          Projection: #d\
+        \n            TableScan: sq projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn nested_subqueries() -> Result<()> {
+        let inner = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(col("f").eq(col("c")))?
+            .project(vec![col("a")])?
+            .build()?;
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .filter(in_subquery(col("e"), inner))?
+            .project(vec![col("d")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(in_subquery(col("a"), subquery))?
+            .build()?;
+
+        let expected = "Join: a = d\
+        \n  TableScan: test projection=None\
+        \n  Projection: #d\
+        \n    Join: e = a, f = c\
+        \n      TableScan: sq projection=None\
+        \n      Projection: #a, #c\
+        \n        TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn subquery_in_disjunction() -> Result<()> {
+        let subquery = LogicalPlanBuilder::from(&subquery_table_scan()?)
+            .project(vec![col("d")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&test_table_scan()?)
+            .filter(col("b").eq(lit(1u32)).or(in_subquery(col("a"), subquery)))?
+            .build()?;
+
+        assert!(optimize(&plan).is_err());
+        Ok(())
+    }
+}
@@ -16,7 +16,7 @@
 
 use crate::datasource::datasource::TableProviderFilterPushDown;
 use crate::logical_plan::{and, LogicalPlan};
-use crate::logical_plan::{DFSchema, Expr};
+use crate::logical_plan::{DFSchema, Expr, JoinType};
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::utils;
 use crate::{error::Result, logical_plan::Operator};
@@ -281,7 +281,11 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
             utils::from_plan(&plan, &expr, &vec![new_input])
         }
         LogicalPlan::Aggregate {
-            input, aggr_expr, ..
+            input,
+            group_expr,
+            grouping_sets,
+            aggr_expr,
+            ..
         } => {
             // An aggregate's aggreagate columns are _not_ filter-commutable => collect these:
             // * columns whose aggregation expression depends on
@@ -297,14 +301,29 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                 .collect::<Result<HashSet<_>>>()?;
             used_columns.extend(agg_columns);
 
+            // nor are the grouping columns missing from a grouping set, which are NULL
+            // in its groups
+            if let Some(grouping_sets) = grouping_sets {
+                for (i, expr) in group_expr.iter().enumerate() {
+                    if grouping_sets.iter().any(|set| !set.contains(&i)) {
+                        used_columns.insert(expr.name(input.schema())?);
+                    }
+                }
+            }
+
             issue_filters(state, used_columns, plan)
         }
-        LogicalPlan::Sort { .. } => {
+        LogicalPlan::Sort { fetch: None, .. } => {
             // sort is filter-commutable
             push_down(&state, plan)
         }
-        LogicalPlan::Limit { input, .. } => {
-            // limit is _not_ filter-commutable => collect all columns from its input
+        LogicalPlan::Union { .. } => {
+            // union is filter-commutable, as all its inputs share its column names
+            push_down(&state, plan)
+        }
+        LogicalPlan::Limit { input, .. } | LogicalPlan::Sort { input, .. } => {
+            // limit, and sort with a fetch, are _not_ filter-commutable => collect all
+            // columns from its input
             let used_columns = input
                 .schema()
                 .fields()
@@ -313,9 +332,26 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                 .collect::<HashSet<_>>();
             issue_filters(state, used_columns, plan)
         }
-        LogicalPlan::Join { left, right, .. } => {
+        LogicalPlan::Join { left, right, .. }
+        | LogicalPlan::CrossJoin { left, right, .. } => {
+            // a cross join is an inner join without join keys
+            let join_type = match plan {
+                LogicalPlan::Join { join_type, .. } => *join_type,
+                _ => JoinType::Inner,
+            };
+            // filters can only be pushed to the sides of the join whose rows are not
+            // padded with nulls, as they would otherwise filter out rows the join emits
+            let empty = DFSchema::empty();
+            let (left_schema, right_schema) = match join_type {
+                JoinType::Inner => (left.schema().as_ref(), right.schema().as_ref()),
+                JoinType::Left | JoinType::Semi | JoinType::Anti => {
+                    (left.schema().as_ref(), &empty)
+                }
+                JoinType::Right => (&empty, right.schema().as_ref()),
+                JoinType::Full => (&empty, &empty),
+            };
             let (pushable_to_left, pushable_to_right, keep) =
-                get_join_predicates(&state, &left.schema(), &right.schema());
+                get_join_predicates(&state, left_schema, right_schema);
 
             let mut left_state = state.clone();
             left_state.filters = keep_filters(&left_state.filters, &pushable_to_left);
@@ -345,6 +381,7 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
             filters,
             projection,
             table_name,
+            limit,
         } => {
             let mut used_columns = HashSet::new();
             let mut new_filters = filters.clone();
@@ -375,6 +412,7 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                     projected_schema: projected_schema.clone(),
                     table_name: table_name.clone(),
                     filters: new_filters,
+                    limit: *limit,
                 },
             )
         }
@@ -431,7 +469,9 @@ mod tests {
     use super::*;
     use crate::datasource::datasource::Statistics;
     use crate::datasource::TableProvider;
-    use crate::logical_plan::{lit, sum, DFSchema, Expr, LogicalPlanBuilder, Operator};
+    use crate::logical_plan::{
+        in_list, lit, sum, DFSchema, Expr, LogicalPlanBuilder, Operator,
+    };
     use crate::physical_plan::ExecutionPlan;
     use crate::test::*;
     use crate::{logical_plan::col, prelude::JoinType};
@@ -460,6 +500,22 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn filter_in_list_before_projection() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a"), col("b")])?
+            .filter(in_list(col("b"), vec![lit(1i64), lit(2i64)], true))?
+            .build()?;
+        // filter is before projection
+        let expected = "\
+            Projection: #a, #b\
+            \n  Filter: #b NOT IN (Int64(1), Int64(2))\
+            \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
     #[test]
     fn filter_after_limit() -> Result<()> {
         let table_scan = test_table_scan()?;
@@ -528,6 +584,38 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn filter_grouping_sets_agg() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let aggregate = LogicalPlanBuilder::from(&table_scan)
+            .aggregate_grouping_sets(
+                vec![vec![col("a"), col("b")], vec![col("a")]],
+                vec![sum(col("c")).alias("total")],
+            )?
+            .build()?;
+
+        // filter of a key of all the grouping sets is commutative
+        let plan = LogicalPlanBuilder::from(&aggregate)
+            .filter(col("a").gt(lit(10i64)))?
+            .build()?;
+        let expected = "\
+            Aggregate: groupBy=[[#a, #b]], groupingSets=[[#a, #b], [#a]], aggr=[[SUM(#c) AS total]]\
+            \n  Filter: #a Gt Int64(10)\
+            \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+
+        // filter of a key missing from a grouping set is not, as it is NULL in its groups
+        let plan = LogicalPlanBuilder::from(&aggregate)
+            .filter(col("b").gt(lit(10i64)))?
+            .build()?;
+        let expected = "\
+            Filter: #b Gt Int64(10)\
+            \n  Aggregate: groupBy=[[#a, #b]], groupingSets=[[#a, #b], [#a]], aggr=[[SUM(#c) AS total]]\
+            \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
     /// verifies that a filter is pushed to before a projection, the filter expression is correctly re-written
     #[test]
     fn alias() -> Result<()> {
@@ -919,6 +1007,83 @@ mod tests {
         Ok(())
     }
 
+    /// post-join predicates on the side of an outer join padded with nulls are not pushed
+    #[test]
+    fn filter_left_join_on_one_side() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let left = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a"), col("b")])?
+            .build()?;
+        let right = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a"), col("c")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&left)
+            .join(&right, JoinType::Left, &["a"], &["a"])?
+            .filter(and(col("b").lt_eq(lit(1i64)), col("c").lt_eq(lit(1i64))))?
+            .build()?;
+
+        // "b" is pushed to the left side, "c" must remain after the join
+        let expected = "\
+        Filter: #c LtEq Int64(1)\
+        \n  Join: a = a\
+        \n    Projection: #a, #b\
+        \n      Filter: #b LtEq Int64(1)\
+        \n        TableScan: test projection=None\
+        \n    Projection: #a, #c\
+        \n      TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    /// no post-join predicate is pushed through a full outer join
+    #[test]
+    fn filter_full_join() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let left = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a"), col("b")])?
+            .build()?;
+        let right = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a"), col("c")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&left)
+            .join(&right, JoinType::Full, &["a"], &["a"])?
+            .filter(col("b").lt_eq(lit(1i64)))?
+            .build()?;
+
+        // expected is equal: no push-down
+        let expected = &format!("{:?}", plan);
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    /// post-join predicates with columns from one side of a cross join are pushed to
+    /// that side, the others are kept above the join
+    #[test]
+    fn filter_cross_join() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let left = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a"), col("b")])?
+            .build()?;
+        let right = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("c")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(&left)
+            .cross_join(&right)?
+            .filter(and(col("b").lt_eq(lit(1i64)), col("a").lt(col("c"))))?
+            .build()?;
+
+        let expected = "\
+        Filter: #a Lt #c\
+        \n  CrossJoin\
+        \n    Projection: #a, #b\
+        \n      Filter: #b LtEq Int64(1)\
+        \n        TableScan: test projection=None\
+        \n    Projection: #c\
+        \n      TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
     struct PushDownProvider {
         pub filter_support: TableProviderFilterPushDown,
     }
@@ -939,6 +1104,7 @@ mod tests {
             _: &Option<Vec<usize>>,
             _: usize,
             _: &[Expr],
+            _: Option<usize>,
         ) -> Result<Arc<dyn ExecutionPlan>> {
             unimplemented!()
         }
@@ -967,6 +1133,7 @@ mod tests {
         let table_scan = LogicalPlan::TableScan {
             table_name: "".into(),
             filters: vec![],
+            limit: None,
             projected_schema: Arc::new(DFSchema::try_from_qualified(
                 "",
                 &*test_provider.schema(),
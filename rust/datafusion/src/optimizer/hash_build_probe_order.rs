@@ -40,7 +40,13 @@ pub struct HashBuildProbeOrder {}
 fn get_num_rows(logical_plan: &LogicalPlan) -> Option<usize> {
     match logical_plan {
         LogicalPlan::Projection { input, .. } => get_num_rows(input),
-        LogicalPlan::Sort { input, .. } => get_num_rows(input),
+        LogicalPlan::Sort { input, fetch, .. } => {
+            let num_rows_input = get_num_rows(input);
+            match fetch {
+                Some(fetch) => num_rows_input.map(|rows| std::cmp::min(*fetch, rows)),
+                None => num_rows_input,
+            }
+        }
         LogicalPlan::TableScan { source, .. } => source.statistics().num_rows,
         LogicalPlan::EmptyRelation {
             produce_one_row, ..
@@ -83,41 +89,51 @@ impl OptimizerRule for HashBuildProbeOrder {
                 left,
                 right,
                 on,
+                filter,
                 join_type,
                 schema,
             } => {
                 let left = self.optimize(left)?;
                 let right = self.optimize(right)?;
-                if should_swap_join_order(&left, &right) {
-                    // Swap left and right, change join type and (equi-)join key order
-                    Ok(LogicalPlan::Join {
-                        left: Arc::new(right),
-                        right: Arc::new(left),
-                        on: on
-                            .iter()
-                            .map(|(l, r)| (r.to_string(), l.to_string()))
-                            .collect(),
-                        join_type: swap_join_type(*join_type),
-                        schema: schema.clone(),
-                    })
-                } else {
-                    // Keep join as is
-                    Ok(LogicalPlan::Join {
-                        left: Arc::new(left),
-                        right: Arc::new(right),
-                        on: on.clone(),
-                        join_type: *join_type,
-                        schema: schema.clone(),
-                    })
+                match swap_join_type(*join_type) {
+                    Some(swapped_join_type) if should_swap_join_order(&left, &right) => {
+                        // Swap left and right, change join type and (equi-)join key order
+                        Ok(LogicalPlan::Join {
+                            left: Arc::new(right),
+                            right: Arc::new(left),
+                            on: on
+                                .iter()
+                                .map(|(l, r)| (r.to_string(), l.to_string()))
+                                .collect(),
+                            filter: filter.clone(),
+                            join_type: swapped_join_type,
+                            schema: schema.clone(),
+                        })
+                    }
+                    _ => {
+                        // Keep join as is
+                        Ok(LogicalPlan::Join {
+                            left: Arc::new(left),
+                            right: Arc::new(right),
+                            on: on.clone(),
+                            filter: filter.clone(),
+                            join_type: *join_type,
+                            schema: schema.clone(),
+                        })
+                    }
                 }
             }
             // Rest: recurse into plan, apply optimization where possible
             LogicalPlan::Projection { .. }
             | LogicalPlan::Aggregate { .. }
+            | LogicalPlan::Window { .. }
             | LogicalPlan::TableScan { .. }
             | LogicalPlan::Limit { .. }
+            | LogicalPlan::EnforceSingleRow { .. }
             | LogicalPlan::Filter { .. }
             | LogicalPlan::Repartition { .. }
+            | LogicalPlan::Union { .. }
+            | LogicalPlan::CrossJoin { .. }
             | LogicalPlan::EmptyRelation { .. }
             | LogicalPlan::Sort { .. }
             | LogicalPlan::CreateExternalTable { .. }
@@ -145,11 +161,15 @@ impl HashBuildProbeOrder {
     }
 }
 
-fn swap_join_type(join_type: JoinType) -> JoinType {
+// Returns the join type once its sides are swapped, if they can be.
+// Semi and anti joins only issue rows of their left side and cannot be swapped.
+fn swap_join_type(join_type: JoinType) -> Option<JoinType> {
     match join_type {
-        JoinType::Inner => JoinType::Inner,
-        JoinType::Left => JoinType::Right,
-        JoinType::Right => JoinType::Left,
+        JoinType::Inner => Some(JoinType::Inner),
+        JoinType::Left => Some(JoinType::Right),
+        JoinType::Right => Some(JoinType::Left),
+        JoinType::Full => Some(JoinType::Full),
+        JoinType::Semi | JoinType::Anti => None,
     }
 }
 
@@ -181,6 +201,7 @@ mod tests {
             _projection: &Option<Vec<usize>>,
             _batch_size: usize,
             _filters: &[Expr],
+            _limit: Option<usize>,
         ) -> Result<std::sync::Arc<dyn crate::physical_plan::ExecutionPlan>> {
             unimplemented!()
         }
@@ -210,6 +231,7 @@ mod tests {
             source: Arc::new(TestTableProvider { num_rows: 1000 }),
             projected_schema: Arc::new(DFSchema::empty()),
             filters: vec![],
+            limit: None,
         };
 
         let lp_right = LogicalPlan::TableScan {
@@ -218,6 +240,7 @@ mod tests {
             source: Arc::new(TestTableProvider { num_rows: 100 }),
             projected_schema: Arc::new(DFSchema::empty()),
             filters: vec![],
+            limit: None,
         };
 
         assert!(should_swap_join_order(&lp_left, &lp_right));
@@ -0,0 +1,814 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimizer rule reordering trees of inner joins based on the statistics of the joined
+//! tables. The number of rows of each relation is estimated from the row counts, the
+//! distinct counts and the null counts of the tables, and from the selectivity of the
+//! filters applied to them.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::datasource::datasource::{ColumnStatistics, TableProviderFilterPushDown};
+use crate::error::Result;
+use crate::logical_plan::{
+    col, DFSchema, Expr, JoinType, LogicalPlan, LogicalPlanBuilder, Operator,
+};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+use crate::scalar::ScalarValue;
+
+/// Selectivity of an equality predicate when the number of distinct values is unknown
+const EQUALITY_SELECTIVITY: f64 = 0.1;
+/// Selectivity of a range predicate, e.g. `a < 5`
+const RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+/// Selectivity of the predicates which cannot be estimated
+const DEFAULT_SELECTIVITY: f64 = 0.5;
+
+/// JoinReorder reorders trees of (at least three) inner joins, such as the joins of a
+/// fact table with its dimension tables.
+///
+/// The join order is chosen greedily: the smallest relation is joined first, then the
+/// relation connected to the joined ones by a join key that produces the smallest
+/// estimated result, and so on. The smaller side of each join is used as its left
+/// (build) side. A projection restores the original order of the columns.
+///
+/// The tree is left as is if the number of rows of any relation cannot be estimated,
+/// or if the relations have columns with the same names.
+pub struct JoinReorder {}
+
+impl JoinReorder {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for JoinReorder {
+    fn optimize(&mut self, plan: &LogicalPlan) -> Result<LogicalPlan> {
+        match plan {
+            LogicalPlan::Join {
+                join_type: JoinType::Inner,
+                ..
+            } => {
+                let mut relations = vec![];
+                let mut join_keys = vec![];
+                flatten_inner_joins(plan, &mut relations, &mut join_keys);
+                match JoinGraph::try_new(relations, &join_keys) {
+                    Some(graph) => graph.to_plan(self),
+                    None => self.optimize_children(plan),
+                }
+            }
+            LogicalPlan::Explain {
+                verbose,
+                plan,
+                stringified_plans,
+                schema,
+            } => utils::optimize_explain(
+                self,
+                *verbose,
+                &*plan,
+                stringified_plans,
+                &schema.as_ref().to_owned().into(),
+            ),
+            _ => self.optimize_children(plan),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "join_reorder"
+    }
+}
+
+/// Collects the relations joined by the tree of inner joins `plan`, and their join keys
+fn flatten_inner_joins(
+    plan: &LogicalPlan,
+    relations: &mut Vec<LogicalPlan>,
+    join_keys: &mut Vec<(String, String)>,
+) {
+    match plan {
+        LogicalPlan::Join {
+            left,
+            right,
+            on,
+            filter: None,
+            join_type: JoinType::Inner,
+            ..
+        } if !on.is_empty() => {
+            flatten_inner_joins(left, relations, join_keys);
+            flatten_inner_joins(right, relations, join_keys);
+            join_keys.extend(on.iter().cloned());
+        }
+        _ => relations.push(plan.clone()),
+    }
+}
+
+/// The relations of a tree of inner joins, in the order of the tree, and the order in
+/// which they are joined
+struct JoinGraph {
+    relations: Vec<LogicalPlan>,
+    /// The join keys, as `(relation, column)` pairs
+    edges: Vec<((usize, String), (usize, String))>,
+    /// The index of each relation, in join order, along with the estimated number of
+    /// rows of the relation and of the join of the relations up to it
+    order: Vec<(usize, f64, f64)>,
+}
+
+impl JoinGraph {
+    fn try_new(
+        relations: Vec<LogicalPlan>,
+        join_keys: &[(String, String)],
+    ) -> Option<Self> {
+        if relations.len() < 3 {
+            return None;
+        }
+
+        // the join keys are resolved by name, which must be unique
+        let mut column_relations = HashMap::new();
+        for (i, relation) in relations.iter().enumerate() {
+            for field in relation.schema().fields() {
+                if column_relations.insert(field.name().clone(), i).is_some() {
+                    return None;
+                }
+            }
+        }
+        let edges = join_keys
+            .iter()
+            .map(|(l, r)| {
+                let left = (*column_relations.get(l)?, l.clone());
+                let right = (*column_relations.get(r)?, r.clone());
+                Some((left, right))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let num_rows = relations
+            .iter()
+            .map(estimate_num_rows)
+            .collect::<Option<Vec<_>>>()?;
+        let distinct_counts = edges
+            .iter()
+            .flat_map(|(left, right)| vec![left, right])
+            .filter_map(|(i, column)| {
+                let distinct_count = estimate_distinct_count(&relations[*i], column)?;
+                Some((column.as_str(), distinct_count))
+            })
+            .collect::<HashMap<_, _>>();
+
+        // start with the smallest relation
+        let first = (0..relations.len()).fold(0, |first, i| {
+            if num_rows[i] < num_rows[first] {
+                i
+            } else {
+                first
+            }
+        });
+        let mut joined = HashSet::new();
+        joined.insert(first);
+        let mut order = vec![(first, num_rows[first], num_rows[first])];
+        let mut joined_num_rows = num_rows[first];
+        while joined.len() < relations.len() {
+            // the next relation is the one producing the smallest join
+            let mut next: Option<(usize, f64)> = None;
+            for i in (0..relations.len()).filter(|i| !joined.contains(i)) {
+                let keys = join_keys_between(&edges, &joined, i);
+                if keys.is_empty() {
+                    continue;
+                }
+                let key_distinct_counts = keys
+                    .iter()
+                    .map(|(l, r)| {
+                        (
+                            distinct_counts.get(l.as_str()).cloned(),
+                            distinct_counts.get(r.as_str()).cloned(),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let candidate_num_rows =
+                    join_num_rows(joined_num_rows, num_rows[i], &key_distinct_counts);
+                if next.map_or(true, |(_, num_rows)| candidate_num_rows < num_rows) {
+                    next = Some((i, candidate_num_rows));
+                }
+            }
+            // the relations which are not connected to the others cannot be joined
+            let (next, next_num_rows) = next?;
+            joined.insert(next);
+            order.push((next, num_rows[next], next_num_rows));
+            joined_num_rows = next_num_rows;
+        }
+
+        Some(Self {
+            relations,
+            edges,
+            order,
+        })
+    }
+
+    /// Joins the relations in order, optimizing each of them with `optimizer`
+    fn to_plan(&self, optimizer: &mut JoinReorder) -> Result<LogicalPlan> {
+        let (first, _, mut joined_num_rows) = self.order[0];
+        let mut plan = optimizer.optimize(&self.relations[first])?;
+        let mut joined = HashSet::new();
+        joined.insert(first);
+        for (i, num_rows, next_num_rows) in self.order.iter().skip(1) {
+            let relation = optimizer.optimize(&self.relations[*i])?;
+            let keys = join_keys_between(&self.edges, &joined, *i);
+            let joined_keys = keys.iter().map(|(l, _)| l.as_str()).collect::<Vec<_>>();
+            let keys = keys.iter().map(|(_, r)| r.as_str()).collect::<Vec<_>>();
+            // the smaller side is the build side of the join
+            let builder = if *num_rows < joined_num_rows {
+                LogicalPlanBuilder::from(&relation).join(
+                    &plan,
+                    JoinType::Inner,
+                    &keys,
+                    &joined_keys,
+                )?
+            } else {
+                LogicalPlanBuilder::from(&plan).join(
+                    &relation,
+                    JoinType::Inner,
+                    &joined_keys,
+                    &keys,
+                )?
+            };
+            plan = builder.build()?;
+            joined.insert(*i);
+            joined_num_rows = *next_num_rows;
+        }
+
+        // restore the order of the columns of the original tree
+        let fields = self
+            .relations
+            .iter()
+            .flat_map(|relation| relation.schema().fields().clone())
+            .collect::<Vec<_>>();
+        if plan.schema().fields() == &fields {
+            Ok(plan)
+        } else {
+            Ok(LogicalPlan::Projection {
+                expr: fields.iter().map(|field| col(field.name())).collect(),
+                input: Arc::new(plan),
+                schema: Arc::new(DFSchema::new(fields)?),
+            })
+        }
+    }
+}
+
+/// Returns the join keys between the `joined` relations and the relation `relation`,
+/// as `(joined column, relation column)` pairs
+fn join_keys_between(
+    edges: &[((usize, String), (usize, String))],
+    joined: &HashSet<usize>,
+    relation: usize,
+) -> Vec<(String, String)> {
+    edges
+        .iter()
+        .filter_map(|((l, left), (r, right))| {
+            if joined.contains(l) && *r == relation {
+                Some((left.clone(), right.clone()))
+            } else if joined.contains(r) && *l == relation {
+                Some((right.clone(), left.clone()))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Estimates the number of rows of an inner join, given the number of distinct values
+/// of each pair of join keys, if known
+fn join_num_rows(
+    left_num_rows: f64,
+    right_num_rows: f64,
+    key_distinct_counts: &[(Option<f64>, Option<f64>)],
+) -> f64 {
+    // the most selective key determines the number of rows
+    let distinct_count = key_distinct_counts
+        .iter()
+        .filter_map(|(left, right)| {
+            let left = left.map(|count| count.min(left_num_rows));
+            let right = right.map(|count| count.min(right_num_rows));
+            match (left, right) {
+                (Some(left), Some(right)) => Some(left.max(right)),
+                (Some(count), None) | (None, Some(count)) => Some(count),
+                (None, None) => None,
+            }
+        })
+        .fold(None, |max: Option<f64>, count| {
+            Some(max.map_or(count, |max| max.max(count)))
+        });
+    match distinct_count {
+        Some(distinct_count) => left_num_rows * right_num_rows / distinct_count.max(1.0),
+        // each row of the larger side is assumed to match a single row of the other side
+        None => left_num_rows
+            .max(right_num_rows)
+            .min(left_num_rows * right_num_rows),
+    }
+}
+
+/// Estimates the number of rows produced by `plan`
+fn estimate_num_rows(plan: &LogicalPlan) -> Option<f64> {
+    match plan {
+        LogicalPlan::TableScan {
+            source,
+            filters,
+            limit,
+            ..
+        } => {
+            let num_rows = source.statistics().num_rows? as f64;
+            // the filters that the source handles exactly are no longer in the plan
+            let filter_selectivity: f64 = filters
+                .iter()
+                .filter(|filter| {
+                    matches!(
+                        source.supports_filter_pushdown(filter),
+                        Ok(TableProviderFilterPushDown::Exact)
+                    )
+                })
+                .map(|filter| selectivity(filter, plan))
+                .product();
+            let num_rows = num_rows * filter_selectivity;
+            Some(limit.map_or(num_rows, |limit| num_rows.min(limit as f64)))
+        }
+        LogicalPlan::Filter { predicate, input } => {
+            Some(estimate_num_rows(input)? * selectivity(predicate, input))
+        }
+        LogicalPlan::Projection { input, .. }
+        | LogicalPlan::Window { input, .. }
+        | LogicalPlan::Repartition { input, .. } => estimate_num_rows(input),
+        LogicalPlan::Sort { input, fetch, .. } => {
+            let num_rows = estimate_num_rows(input)?;
+            Some(fetch.map_or(num_rows, |fetch| num_rows.min(fetch as f64)))
+        }
+        LogicalPlan::Limit { n, input } => Some(
+            estimate_num_rows(input)
+                .map_or(*n as f64, |num_rows| num_rows.min(*n as f64)),
+        ),
+        LogicalPlan::EmptyRelation {
+            produce_one_row, ..
+        } => Some(if *produce_one_row { 1.0 } else { 0.0 }),
+        LogicalPlan::Aggregate {
+            input,
+            group_expr,
+            grouping_sets,
+            ..
+        } => {
+            // each grouping set has its own groups
+            let num_sets = grouping_sets.as_ref().map_or(1, |sets| sets.len()) as f64;
+            if group_expr.is_empty() {
+                return Some(num_sets);
+            }
+            let num_rows = estimate_num_rows(input)?;
+            // there are at most as many groups as combinations of the grouped values
+            let num_groups = group_expr
+                .iter()
+                .map(|expr| distinct_count(expr, input))
+                .product::<Option<f64>>();
+            Some(
+                num_sets
+                    * num_groups.map_or(num_rows, |num_groups| num_groups.min(num_rows)),
+            )
+        }
+        LogicalPlan::Join {
+            left,
+            right,
+            on,
+            join_type,
+            ..
+        } => {
+            let left_num_rows = estimate_num_rows(left)?;
+            let right_num_rows = estimate_num_rows(right)?;
+            let key_distinct_counts = on
+                .iter()
+                .map(|(l, r)| {
+                    (
+                        estimate_distinct_count(left, l),
+                        estimate_distinct_count(right, r),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let num_rows =
+                join_num_rows(left_num_rows, right_num_rows, &key_distinct_counts);
+            Some(match join_type {
+                JoinType::Inner => num_rows,
+                JoinType::Left => num_rows.max(left_num_rows),
+                JoinType::Right => num_rows.max(right_num_rows),
+                JoinType::Full => num_rows.max(left_num_rows).max(right_num_rows),
+                JoinType::Semi => num_rows.min(left_num_rows),
+                JoinType::Anti => left_num_rows,
+            })
+        }
+        LogicalPlan::CrossJoin { left, right, .. } => {
+            Some(estimate_num_rows(left)? * estimate_num_rows(right)?)
+        }
+        LogicalPlan::Union { inputs, .. } => inputs.iter().map(estimate_num_rows).sum(),
+        _ => None,
+    }
+}
+
+/// Estimates the number of distinct values of the column `name` produced by `plan`
+fn estimate_distinct_count(plan: &LogicalPlan, name: &str) -> Option<f64> {
+    let distinct_count = distinct_count(&col(name), plan)?;
+    Some(
+        estimate_num_rows(plan)
+            .map_or(distinct_count, |num_rows| distinct_count.min(num_rows)),
+    )
+}
+
+/// Returns the statistics of the column `name` produced by `plan`, along with the
+/// number of rows of its table, if the column comes unchanged from a table scan
+fn column_statistics(plan: &LogicalPlan, name: &str) -> Option<(ColumnStatistics, f64)> {
+    match plan {
+        LogicalPlan::TableScan {
+            source,
+            projected_schema,
+            ..
+        } => {
+            projected_schema.field_with_unqualified_name(name).ok()?;
+            let index = source.schema().index_of(name).ok()?;
+            let statistics = source.statistics();
+            let column = statistics.column_statistics?.get(index)?.clone();
+            Some((column, statistics.num_rows? as f64))
+        }
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Repartition { input, .. } => column_statistics(input, name),
+        LogicalPlan::Projection { expr, input, .. } => {
+            expr.iter().find_map(|expr| match expr {
+                Expr::Column(column) if column == name => column_statistics(input, name),
+                Expr::Alias(expr, alias) if alias == name => match expr.as_ref() {
+                    Expr::Column(column) => column_statistics(input, column),
+                    _ => None,
+                },
+                _ => None,
+            })
+        }
+        LogicalPlan::Aggregate {
+            input, group_expr, ..
+        } => group_expr.iter().find_map(|expr| match expr {
+            Expr::Column(column) if column == name => column_statistics(input, name),
+            _ => None,
+        }),
+        LogicalPlan::Join { left, right, .. }
+        | LogicalPlan::CrossJoin { left, right, .. } => {
+            column_statistics(left, name).or_else(|| column_statistics(right, name))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the number of distinct values of the table column `expr`, if known
+fn distinct_count(expr: &Expr, input: &LogicalPlan) -> Option<f64> {
+    match expr {
+        Expr::Column(name) => {
+            let (column, _) = column_statistics(input, name)?;
+            column.distinct_count.map(|count| count as f64)
+        }
+        _ => None,
+    }
+}
+
+/// Estimates the fraction of the rows of `input` for which `predicate` is true
+fn selectivity(predicate: &Expr, input: &LogicalPlan) -> f64 {
+    match predicate {
+        Expr::BinaryExpr { left, op, right } => match op {
+            Operator::And => selectivity(left, input) * selectivity(right, input),
+            Operator::Or => {
+                let left = selectivity(left, input);
+                let right = selectivity(right, input);
+                left + right - left * right
+            }
+            Operator::Eq => equality_selectivity(left, right, input),
+            Operator::NotEq => 1.0 - equality_selectivity(left, right, input),
+            Operator::Lt | Operator::LtEq | Operator::Gt | Operator::GtEq => {
+                RANGE_SELECTIVITY
+            }
+            _ => DEFAULT_SELECTIVITY,
+        },
+        Expr::Not(expr) => 1.0 - selectivity(expr, input),
+        Expr::Between { negated, .. } => negate(RANGE_SELECTIVITY, *negated),
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => {
+            let list_selectivity =
+                list.len() as f64 * equality_selectivity(expr, expr, input);
+            negate(list_selectivity.min(1.0), *negated)
+        }
+        Expr::IsNull(expr) => null_fraction(expr, input),
+        Expr::IsNotNull(expr) => 1.0 - null_fraction(expr, input),
+        Expr::Literal(ScalarValue::Boolean(Some(value))) => {
+            if *value {
+                1.0
+            } else {
+                0.0
+            }
+        }
+        _ => DEFAULT_SELECTIVITY,
+    }
+}
+
+fn negate(selectivity: f64, negated: bool) -> f64 {
+    if negated {
+        1.0 - selectivity
+    } else {
+        selectivity
+    }
+}
+
+/// Estimates the fraction of the rows for which `left = right`, assuming that the
+/// values of the columns are uniformly distributed
+fn equality_selectivity(left: &Expr, right: &Expr, input: &LogicalPlan) -> f64 {
+    let distinct_count = match (distinct_count(left, input), distinct_count(right, input))
+    {
+        (Some(left), Some(right)) => Some(left.max(right)),
+        (Some(count), None) | (None, Some(count)) => Some(count),
+        (None, None) => None,
+    };
+    distinct_count.map_or(EQUALITY_SELECTIVITY, |count| 1.0 / count.max(1.0))
+}
+
+/// Estimates the fraction of null values of `expr`
+fn null_fraction(expr: &Expr, input: &LogicalPlan) -> f64 {
+    match expr {
+        Expr::Column(name) => match column_statistics(input, name) {
+            Some((
+                ColumnStatistics {
+                    null_count: Some(null_count),
+                    ..
+                },
+                num_rows,
+            )) if num_rows > 0.0 => (null_count as f64 / num_rows).min(1.0),
+            _ => EQUALITY_SELECTIVITY,
+        },
+        _ => EQUALITY_SELECTIVITY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::datasource::Statistics;
+    use crate::datasource::{MemTable, TableProvider};
+    use crate::logical_plan::lit;
+    use crate::physical_plan::ExecutionPlan;
+    use arrow::array::{ArrayRef, UInt32Array};
+    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+    use arrow::record_batch::RecordBatch;
+
+    /// A table provider with statistics, which cannot be scanned
+    struct TestTableProvider {
+        schema: SchemaRef,
+        num_rows: Option<usize>,
+        distinct_counts: Vec<Option<usize>>,
+    }
+
+    impl TableProvider for TestTableProvider {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn schema(&self) -> SchemaRef {
+            self.schema.clone()
+        }
+
+        fn scan(
+            &self,
+            _projection: &Option<Vec<usize>>,
+            _batch_size: usize,
+            _filters: &[Expr],
+            _limit: Option<usize>,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            unimplemented!()
+        }
+
+        fn statistics(&self) -> Statistics {
+            Statistics {
+                num_rows: self.num_rows,
+                total_byte_size: None,
+                column_statistics: Some(
+                    self.distinct_counts
+                        .iter()
+                        .map(|distinct_count| ColumnStatistics {
+                            null_count: Some(0),
+                            distinct_count: *distinct_count,
+                        })
+                        .collect(),
+                ),
+            }
+        }
+    }
+
+    /// Scans a table with the given columns and numbers of distinct values
+    fn scan(
+        name: &str,
+        num_rows: Option<usize>,
+        columns: Vec<(&str, Option<usize>)>,
+    ) -> Result<LogicalPlanBuilder> {
+        let schema = Schema::new(
+            columns
+                .iter()
+                .map(|(column, _)| Field::new(column, DataType::UInt32, false))
+                .collect(),
+        );
+        let provider = TestTableProvider {
+            schema: Arc::new(schema),
+            num_rows,
+            distinct_counts: columns.iter().map(|(_, count)| *count).collect(),
+        };
+        LogicalPlanBuilder::scan(name, Arc::new(provider), None)
+    }
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = JoinReorder::new()
+            .optimize(plan)
+            .expect("failed to optimize plan");
+        let formatted_plan = format!("{:?}", optimized_plan);
+        assert_eq!(formatted_plan, expected);
+    }
+
+    /// A fact table and two dimension tables
+    fn star_schema(
+    ) -> Result<(LogicalPlanBuilder, LogicalPlanBuilder, LogicalPlanBuilder)> {
+        let fact = scan(
+            "fact",
+            Some(10000),
+            vec![("f_a", Some(100)), ("f_b", Some(10)), ("f_value", None)],
+        )?;
+        let dim_a = scan(
+            "dim_a",
+            Some(100),
+            vec![("a_id", Some(100)), ("a_name", Some(50))],
+        )?;
+        let dim_b = scan(
+            "dim_b",
+            Some(10),
+            vec![("b_id", Some(10)), ("b_name", Some(10))],
+        )?;
+        Ok((fact, dim_a, dim_b))
+    }
+
+    #[test]
+    fn reorder_star_schema() -> Result<()> {
+        let (fact, dim_a, dim_b) = star_schema()?;
+        let plan = fact
+            .join(&dim_a.build()?, JoinType::Inner, &["f_a"], &["a_id"])?
+            .join(&dim_b.build()?, JoinType::Inner, &["f_b"], &["b_id"])?
+            .build()?;
+
+        // the smallest dimension is joined first, and the dimensions are build sides
+        let expected = "Projection: #f_a, #f_b, #f_value, #a_id, #a_name, #b_id, #b_name\
+        \n  Join: a_id = f_a\
+        \n    TableScan: dim_a projection=None\
+        \n    Join: b_id = f_b\
+        \n      TableScan: dim_b projection=None\
+        \n      TableScan: fact projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn reorder_with_filter_selectivity() -> Result<()> {
+        let (fact, dim_a, dim_b) = star_schema()?;
+        // 2 rows of dim_a are expected to match the filter
+        let dim_a = dim_a.filter(col("a_name").eq(lit(1u32)))?.build()?;
+        let plan = dim_b
+            .join(&fact.build()?, JoinType::Inner, &["b_id"], &["f_b"])?
+            .join(&dim_a, JoinType::Inner, &["f_a"], &["a_id"])?
+            .build()?;
+
+        let expected = "Projection: #b_id, #b_name, #f_a, #f_b, #f_value, #a_id, #a_name\
+        \n  Join: b_id = f_b\
+        \n    TableScan: dim_b projection=None\
+        \n    Join: a_id = f_a\
+        \n      Filter: #a_name Eq UInt32(1)\
+        \n        TableScan: dim_a projection=None\
+        \n      TableScan: fact projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    /// Scans an in-memory table with a single batch of the given columns
+    fn scan_memory(name: &str, columns: Vec<(&str, Vec<u32>)>) -> Result<LogicalPlan> {
+        let schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|(column, _)| Field::new(column, DataType::UInt32, false))
+                .collect(),
+        ));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            columns
+                .into_iter()
+                .map(|(_, values)| Arc::new(UInt32Array::from(values)) as ArrayRef)
+                .collect(),
+        )?;
+        let provider = MemTable::try_new(schema, vec![vec![batch]])?;
+        LogicalPlanBuilder::scan(name, Arc::new(provider), None)?.build()
+    }
+
+    #[test]
+    fn reorder_with_distinct_counts() -> Result<()> {
+        // without distinct counts, s and t are expected to produce joins of the same
+        // size, and the original order is kept
+        let r = scan("r", Some(2), vec![("r_a", None), ("r_b", None)])?.build()?;
+        let s = scan("s", Some(4), vec![("s_a", None)])?.build()?;
+        let t = scan("t", Some(4), vec![("t_b", None)])?.build()?;
+        let plan = LogicalPlanBuilder::from(&r)
+            .join(&t, JoinType::Inner, &["r_b"], &["t_b"])?
+            .join(&s, JoinType::Inner, &["r_a"], &["s_a"])?
+            .build()?;
+        let expected = "Join: r_a = s_a\
+        \n  Join: r_b = t_b\
+        \n    TableScan: r projection=None\
+        \n    TableScan: t projection=None\
+        \n  TableScan: s projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+
+        // each row of r matches a row of s, but all the rows of t
+        let r = scan_memory("r", vec![("r_a", vec![1, 2]), ("r_b", vec![1, 1])])?;
+        let s = scan_memory("s", vec![("s_a", vec![1, 2, 3, 4])])?;
+        let t = scan_memory("t", vec![("t_b", vec![1, 1, 1, 1])])?;
+        let plan = LogicalPlanBuilder::from(&r)
+            .join(&t, JoinType::Inner, &["r_b"], &["t_b"])?
+            .join(&s, JoinType::Inner, &["r_a"], &["s_a"])?
+            .build()?;
+        let expected = "Projection: #r_a, #r_b, #t_b, #s_a\
+        \n  Join: r_b = t_b\
+        \n    Join: r_a = s_a\
+        \n      TableScan: r projection=None\
+        \n      TableScan: s projection=None\
+        \n    TableScan: t projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_order_without_statistics() -> Result<()> {
+        let (fact, _, dim_b) = star_schema()?;
+        let dim_c = scan("dim_c", None, vec![("c_id", Some(10))])?.build()?;
+        let plan = fact
+            .join(&dim_c, JoinType::Inner, &["f_a"], &["c_id"])?
+            .join(&dim_b.build()?, JoinType::Inner, &["f_b"], &["b_id"])?
+            .build()?;
+
+        // the number of rows of dim_c is unknown
+        let expected = "Join: f_b = b_id\
+        \n  Join: f_a = c_id\
+        \n    TableScan: fact projection=None\
+        \n    TableScan: dim_c projection=None\
+        \n  TableScan: dim_b projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_order_of_two_relations() -> Result<()> {
+        let (fact, _, dim_b) = star_schema()?;
+        let plan = fact
+            .join(&dim_b.build()?, JoinType::Inner, &["f_b"], &["b_id"])?
+            .build()?;
+
+        // the build side of a single join is chosen by HashBuildProbeOrder
+        let expected = "Join: f_b = b_id\
+        \n  TableScan: fact projection=None\
+        \n  TableScan: dim_b projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn estimate_selectivity() -> Result<()> {
+        let (fact, _, _) = star_schema()?;
+        let fact = fact.build()?;
+        let estimate = |predicate: Expr| -> Result<Option<f64>> {
+            let plan = LogicalPlanBuilder::from(&fact).filter(predicate)?.build()?;
+            Ok(estimate_num_rows(&plan).map(|num_rows| num_rows.round()))
+        };
+
+        assert_eq!(estimate(col("f_b").eq(lit(1u32)))?, Some(1000.0));
+        assert_eq!(estimate(col("f_value").eq(lit(1u32)))?, Some(1000.0));
+        assert_eq!(estimate(col("f_b").not_eq(lit(1u32)))?, Some(9000.0));
+        assert_eq!(
+            estimate(col("f_a").eq(lit(1u32)).and(col("f_b").eq(lit(1u32))))?,
+            Some(10.0)
+        );
+        assert_eq!(estimate(Expr::IsNull(Box::new(col("f_a"))))?, Some(0.0));
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimizer rule pushing limits down through projections and unions, and into table
+//! scans, so that the table providers can stop reading once they produced enough rows.
+
+use std::cmp::min;
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::logical_plan::LogicalPlan;
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+
+/// Optimizer rule pushing a limit down the plan, e.g. `SELECT a FROM t LIMIT 5` only
+/// reads the first 5 rows of `t`. The limit is kept above the nodes it was pushed
+/// through, as the table scans may produce more rows than their limit.
+pub struct LimitPushDown {}
+
+impl LimitPushDown {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Pushes the number of rows `upper_limit` needed by the parent of `plan` down `plan`
+fn limit_push_down(
+    upper_limit: Option<usize>,
+    plan: &LogicalPlan,
+) -> Result<LogicalPlan> {
+    match (plan, upper_limit) {
+        (LogicalPlan::Limit { n, input }, upper_limit) => {
+            let limit = upper_limit.map_or(*n, |upper_limit| min(upper_limit, *n));
+            Ok(LogicalPlan::Limit {
+                n: limit,
+                input: Arc::new(limit_push_down(Some(limit), input)?),
+            })
+        }
+        (
+            LogicalPlan::TableScan {
+                table_name,
+                source,
+                projection,
+                projected_schema,
+                filters,
+                limit,
+            },
+            Some(upper_limit),
+        ) => Ok(LogicalPlan::TableScan {
+            table_name: table_name.clone(),
+            source: source.clone(),
+            projection: projection.clone(),
+            projected_schema: projected_schema.clone(),
+            filters: filters.clone(),
+            limit: Some(limit.map_or(upper_limit, |limit| min(limit, upper_limit))),
+        }),
+        (
+            LogicalPlan::Projection {
+                expr,
+                input,
+                schema,
+            },
+            upper_limit,
+        ) => Ok(LogicalPlan::Projection {
+            expr: expr.clone(),
+            input: Arc::new(limit_push_down(upper_limit, input)?),
+            schema: schema.clone(),
+        }),
+        (LogicalPlan::Union { inputs, schema }, Some(upper_limit)) => {
+            // each input is limited, as it may produce all the rows of the union
+            let inputs = inputs
+                .iter()
+                .map(|input| {
+                    Ok(LogicalPlan::Limit {
+                        n: upper_limit,
+                        input: Arc::new(limit_push_down(Some(upper_limit), input)?),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(LogicalPlan::Union {
+                inputs,
+                schema: schema.clone(),
+            })
+        }
+        // the other nodes may need all the rows of their inputs
+        _ => {
+            let expr = utils::expressions(plan);
+            let inputs = utils::inputs(plan)
+                .iter()
+                .map(|input| limit_push_down(None, input))
+                .collect::<Result<Vec<_>>>()?;
+            utils::from_plan(plan, &expr, &inputs)
+        }
+    }
+}
+
+impl OptimizerRule for LimitPushDown {
+    fn optimize(&mut self, plan: &LogicalPlan) -> Result<LogicalPlan> {
+        match plan {
+            LogicalPlan::Explain {
+                verbose,
+                plan,
+                stringified_plans,
+                schema,
+            } => utils::optimize_explain(
+                self,
+                *verbose,
+                &*plan,
+                stringified_plans,
+                &schema.as_ref().to_owned().into(),
+            ),
+            _ => limit_push_down(None, plan),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "limit_push_down"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, max, LogicalPlanBuilder};
+    use crate::test::*;
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = LimitPushDown::new()
+            .optimize(plan)
+            .expect("failed to optimize plan");
+        let formatted_plan = format!("{:?}", optimized_plan);
+        assert_eq!(formatted_plan, expected);
+    }
+
+    #[test]
+    fn limit_pushdown_projection_table_provider() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a")])?
+            .limit(1000)?
+            .build()?;
+
+        let expected = "Limit: 1000\
+        \n  Projection: #a\
+        \n    TableScan: test projection=None, limit=1000";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn limit_pushdown_nested_limits() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .limit(1000)?
+            .project(vec![col("a")])?
+            .limit(10)?
+            .build()?;
+
+        // the smallest limit is pushed down
+        let expected = "Limit: 10\
+        \n  Projection: #a\
+        \n    Limit: 10\
+        \n      TableScan: test projection=None, limit=10";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn limit_pushdown_union() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .union(&table_scan)?
+            .limit(100)?
+            .build()?;
+
+        let expected = "Limit: 100\
+        \n  Union\
+        \n    Limit: 100\
+        \n      TableScan: test projection=None, limit=100\
+        \n    Limit: 100\
+        \n      TableScan: test projection=None, limit=100";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn limit_not_pushed_through_aggregate() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .aggregate(vec![col("a")], vec![max(col("b"))])?
+            .limit(1000)?
+            .build()?;
+
+        let expected = "Limit: 1000\
+        \n  Aggregate: groupBy=[[#a]], aggr=[[MAX(#b)]]\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn limit_not_pushed_through_filter() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .filter(col("a").gt(col("b")))?
+            .limit(10)?
+            .build()?;
+
+        let expected = "Limit: 10\
+        \n  Filter: #a Gt #b\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+}
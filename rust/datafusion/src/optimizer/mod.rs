@@ -18,8 +18,14 @@
 //! This module contains a query optimizer that operates against a logical plan and applies
 //! some simple rules to a logical plan, such as "Projection Push Down" and "Type Coercion".
 
+pub mod common_subexpr_eliminate;
+pub mod constant_folding;
+pub mod decorrelate_subquery;
 pub mod filter_push_down;
 pub mod hash_build_probe_order;
+pub mod join_reorder;
+pub mod limit_push_down;
 pub mod optimizer;
 pub mod projection_push_down;
+pub mod top_k;
 pub mod utils;
@@ -19,7 +19,9 @@
 //! loaded into memory
 
 use crate::error::{DataFusionError, Result};
-use crate::logical_plan::{DFField, DFSchema, DFSchemaRef, LogicalPlan, ToDFSchema};
+use crate::logical_plan::{
+    DFField, DFSchema, DFSchemaRef, LogicalPlan, LogicalPlanBuilder, ToDFSchema,
+};
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::utils;
 use arrow::datatypes::Schema;
@@ -161,6 +163,7 @@ fn optimize_plan(
             left,
             right,
             on,
+            filter,
             join_type,
             schema,
         } => {
@@ -168,6 +171,9 @@ fn optimize_plan(
                 new_required_columns.insert(l.to_owned());
                 new_required_columns.insert(r.to_owned());
             }
+            if let Some(filter) = filter {
+                utils::expr_to_column_names(filter, &mut new_required_columns)?;
+            }
             Ok(LogicalPlan::Join {
                 left: Arc::new(optimize_plan(
                     optimizer,
@@ -184,15 +190,56 @@ fn optimize_plan(
 
                 join_type: *join_type,
                 on: on.clone(),
+                filter: filter.clone(),
                 schema: schema.clone(),
             })
         }
+        LogicalPlan::CrossJoin { left, right, .. } => {
+            let left = optimize_plan(optimizer, &left, &new_required_columns, true)?;
+            let right = optimize_plan(optimizer, &right, &new_required_columns, true)?;
+            LogicalPlanBuilder::from(&left).cross_join(&right)?.build()
+        }
+        LogicalPlan::Window {
+            input, window_expr, ..
+        } => {
+            // window:
+            // * remove any window expression that is not required
+            // * construct the new set of required columns
+
+            // Gather all columns needed for expressions in this Window
+            let mut new_window_expr = Vec::new();
+            window_expr.iter().try_for_each(|expr| {
+                let name = &expr.name(input.schema())?;
+
+                if required_columns.contains(name) {
+                    new_window_expr.push(expr.clone());
+                    new_required_columns.insert(name.clone());
+
+                    // add to the new set of required columns
+                    utils::expr_to_column_names(expr, &mut new_required_columns)
+                } else {
+                    Ok(())
+                }
+            })?;
+
+            let new_input =
+                optimize_plan(optimizer, &input, &new_required_columns, true)?;
+
+            if new_window_expr.is_empty() {
+                // no need for a window at all
+                Ok(new_input)
+            } else {
+                LogicalPlanBuilder::from(&new_input)
+                    .window(new_window_expr)?
+                    .build()
+            }
+        }
         LogicalPlan::Aggregate {
             schema,
             input,
             group_expr,
+            grouping_sets,
             aggr_expr,
-            ..
         } => {
             // aggregate:
             // * remove any aggregate expression that is not required
@@ -227,6 +274,7 @@ fn optimize_plan(
 
             Ok(LogicalPlan::Aggregate {
                 group_expr: group_expr.clone(),
+                grouping_sets: grouping_sets.clone(),
                 aggr_expr: new_aggr_expr,
                 input: Arc::new(optimize_plan(
                     optimizer,
@@ -237,6 +285,27 @@ fn optimize_plan(
                 schema: DFSchemaRef::new(new_schema),
             })
         }
+        LogicalPlan::Union { inputs, schema } => {
+            // union matches the columns of its inputs by position: they can only be
+            // removed from all the inputs at once, so keep all of them
+            let new_inputs = inputs
+                .iter()
+                .map(|input| {
+                    let input_columns = input
+                        .schema()
+                        .fields()
+                        .iter()
+                        .map(|f| f.name().clone())
+                        .collect::<HashSet<String>>();
+                    optimize_plan(optimizer, input, &input_columns, true)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(LogicalPlan::Union {
+                inputs: new_inputs,
+                schema: schema.clone(),
+            })
+        }
         // scans:
         // * remove un-used columns from the scan projection
         LogicalPlan::TableScan {
@@ -244,6 +313,7 @@ fn optimize_plan(
             source,
             projection,
             filters,
+            limit,
             ..
         } => {
             let (projection, projected_schema) = get_projected_schema(
@@ -260,6 +330,7 @@ fn optimize_plan(
                 projection: Some(projection),
                 projected_schema,
                 filters: filters.clone(),
+                limit: *limit,
             })
         }
         LogicalPlan::Explain {
@@ -274,6 +345,7 @@ fn optimize_plan(
         // all other nodes: Add any additional columns used by
         // expressions in this node to the list of required columns
         LogicalPlan::Limit { .. }
+        | LogicalPlan::EnforceSingleRow { .. }
         | LogicalPlan::Filter { .. }
         | LogicalPlan::Repartition { .. }
         | LogicalPlan::EmptyRelation { .. }
@@ -304,6 +376,8 @@ mod tests {
     use super::*;
     use crate::logical_plan::{col, lit};
     use crate::logical_plan::{max, min, Expr, LogicalPlanBuilder};
+    use crate::physical_plan::aggregates::AggregateFunction;
+    use crate::physical_plan::window_functions::WindowFunction;
     use crate::test::*;
     use arrow::datatypes::DataType;
 
@@ -518,6 +592,46 @@ mod tests {
         Ok(())
     }
 
+    /// tests that it removes a window expression that is never used downstream
+    #[test]
+    fn table_unused_window() -> Result<()> {
+        let table_scan = test_table_scan()?;
+
+        // we never use "min_c" => remove it, together with the column c
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .window(vec![
+                Expr::WindowFunction {
+                    fun: WindowFunction::AggregateFunction(AggregateFunction::Max),
+                    args: vec![col("b")],
+                    partition_by: vec![col("a")],
+                    order_by: vec![],
+                    window_frame: None,
+                }
+                .alias("max_b"),
+                Expr::WindowFunction {
+                    fun: WindowFunction::AggregateFunction(AggregateFunction::Min),
+                    args: vec![col("c")],
+                    partition_by: vec![],
+                    order_by: vec![],
+                    window_frame: None,
+                }
+                .alias("min_c"),
+            ])?
+            .project(vec![col("a"), col("max_b")])?
+            .build()?;
+
+        assert_fields_eq(&plan, vec!["a", "max_b"]);
+
+        let expected = "\
+        Projection: #a, #max_b\
+        \n  Window: windowExpr=[[MAX(#b) PARTITION BY [#a] AS max_b]]\
+        \n    TableScan: test projection=Some([0, 1])";
+
+        assert_optimized_plan_eq(&plan, expected);
+
+        Ok(())
+    }
+
     fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
         let optimized_plan = optimize(plan).expect("failed to optimize plan");
         let formatted_plan = format!("{:?}", optimized_plan);
@@ -0,0 +1,147 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimizer rule fusing a limit with the sort below it into a "Top K", a sort that only
+//! produces its first rows. The physical plan of a Top K keeps the first rows of each
+//! input partition in a bounded heap instead of sorting the whole input.
+
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::logical_plan::LogicalPlan;
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+
+/// Optimizer rule setting the `fetch` of a sort followed by a limit, e.g. for
+/// `SELECT * FROM events ORDER BY ts DESC LIMIT 10`. The limit is kept as the sort
+/// does not guarantee to produce only `fetch` rows.
+pub struct TopK {}
+
+impl OptimizerRule for TopK {
+    fn optimize(&mut self, plan: &LogicalPlan) -> Result<LogicalPlan> {
+        match plan {
+            LogicalPlan::Limit { n, input } => match input.as_ref() {
+                LogicalPlan::Sort {
+                    expr,
+                    input: sort_input,
+                    fetch,
+                } => {
+                    let fetch = match fetch {
+                        Some(fetch) => std::cmp::min(*fetch, *n),
+                        None => *n,
+                    };
+                    Ok(LogicalPlan::Limit {
+                        n: *n,
+                        input: Arc::new(LogicalPlan::Sort {
+                            expr: expr.clone(),
+                            input: Arc::new(self.optimize(sort_input)?),
+                            fetch: Some(fetch),
+                        }),
+                    })
+                }
+                _ => self.optimize_children(plan),
+            },
+            LogicalPlan::Explain {
+                verbose,
+                plan,
+                stringified_plans,
+                schema,
+            } => utils::optimize_explain(
+                self,
+                *verbose,
+                &*plan,
+                stringified_plans,
+                &schema.as_ref().to_owned().into(),
+            ),
+            _ => self.optimize_children(plan),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "top_k"
+    }
+}
+
+impl TopK {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, LogicalPlanBuilder};
+    use crate::test::*;
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = TopK::new().optimize(plan).expect("failed to optimize plan");
+        let formatted_plan = format!("{:?}", optimized_plan);
+        assert_eq!(formatted_plan, expected);
+    }
+
+    #[test]
+    fn limit_sort() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .project(vec![col("a"), col("b")])?
+            .sort(vec![col("a").sort(false, true)])?
+            .limit(10)?
+            .build()?;
+
+        let expected = "Limit: 10\
+        \n  Sort: #a DESC NULLS FIRST, fetch=10\
+        \n    Projection: #a, #b\
+        \n      TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn nested_limits() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .sort(vec![col("a").sort(true, false)])?
+            .limit(10)?
+            .limit(5)?
+            .build()?;
+
+        // only the limit right above the sort is fused
+        let expected = "Limit: 5\
+        \n  Limit: 10\
+        \n    Sort: #a ASC NULLS LAST, fetch=10\
+        \n      TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn limit_without_sort() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(&table_scan)
+            .limit(10)?
+            .sort(vec![col("a").sort(true, false)])?
+            .build()?;
+
+        let expected = "Sort: #a ASC NULLS LAST\
+        \n  Limit: 10\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+}
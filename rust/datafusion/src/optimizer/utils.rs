@@ -90,8 +90,21 @@ pub fn expr_to_column_names(expr: &Expr, accum: &mut HashSet<String>) -> Result<
         }
         Expr::Cast { expr, .. } => expr_to_column_names(expr, accum),
         Expr::Sort { expr, .. } => expr_to_column_names(expr, accum),
-        Expr::AggregateFunction { args, .. } => exprlist_to_column_names(args, accum),
+        Expr::AggregateFunction { args, order_by, .. } => {
+            exprlist_to_column_names(args, accum)?;
+            exprlist_to_column_names(order_by, accum)
+        }
         Expr::AggregateUDF { args, .. } => exprlist_to_column_names(args, accum),
+        Expr::WindowFunction {
+            args,
+            partition_by,
+            order_by,
+            ..
+        } => {
+            exprlist_to_column_names(args, accum)?;
+            exprlist_to_column_names(partition_by, accum)?;
+            exprlist_to_column_names(order_by, accum)
+        }
         Expr::ScalarFunction { args, .. } => exprlist_to_column_names(args, accum),
         Expr::ScalarUDF { args, .. } => exprlist_to_column_names(args, accum),
         Expr::Between {
@@ -102,6 +115,14 @@ pub fn expr_to_column_names(expr: &Expr, accum: &mut HashSet<String>) -> Result<
             expr_to_column_names(high, accum)?;
             Ok(())
         }
+        Expr::InList { expr, list, .. } => {
+            expr_to_column_names(expr, accum)?;
+            exprlist_to_column_names(list, accum)
+        }
+        // the columns of a subquery are not columns of the input of the expression
+        Expr::Exists { .. } => Ok(()),
+        Expr::InSubquery { expr, .. } => expr_to_column_names(expr, accum),
+        Expr::ScalarSubquery(_) => Ok(()),
         Expr::Wildcard => Err(DataFusionError::Internal(
             "Wildcard expressions are not valid in a logical query plan".to_owned(),
         )),
@@ -147,6 +168,7 @@ pub fn expressions(plan: &LogicalPlan) -> Vec<Expr> {
             Partitioning::Hash(expr, _) => expr.clone(),
             _ => vec![],
         },
+        LogicalPlan::Window { window_expr, .. } => window_expr.clone(),
         LogicalPlan::Aggregate {
             group_expr,
             aggr_expr,
@@ -156,15 +178,20 @@ pub fn expressions(plan: &LogicalPlan) -> Vec<Expr> {
             result.extend(aggr_expr.clone());
             result
         }
-        LogicalPlan::Join { on, .. } => {
-            on.iter().flat_map(|(l, r)| vec![col(l), col(r)]).collect()
-        }
+        LogicalPlan::Join { on, filter, .. } => on
+            .iter()
+            .flat_map(|(l, r)| vec![col(l), col(r)])
+            .chain(filter.clone())
+            .collect(),
         LogicalPlan::Sort { expr, .. } => expr.clone(),
         LogicalPlan::Extension { node } => node.expressions(),
         // plans without expressions
         LogicalPlan::TableScan { .. }
         | LogicalPlan::EmptyRelation { .. }
         | LogicalPlan::Limit { .. }
+        | LogicalPlan::EnforceSingleRow { .. }
+        | LogicalPlan::Union { .. }
+        | LogicalPlan::CrossJoin { .. }
         | LogicalPlan::CreateExternalTable { .. }
         | LogicalPlan::Explain { .. } => vec![],
     }
@@ -176,10 +203,14 @@ pub fn inputs(plan: &LogicalPlan) -> Vec<&LogicalPlan> {
         LogicalPlan::Projection { input, .. } => vec![input],
         LogicalPlan::Filter { input, .. } => vec![input],
         LogicalPlan::Repartition { input, .. } => vec![input],
+        LogicalPlan::Window { input, .. } => vec![input],
         LogicalPlan::Aggregate { input, .. } => vec![input],
         LogicalPlan::Sort { input, .. } => vec![input],
-        LogicalPlan::Join { left, right, .. } => vec![left, right],
+        LogicalPlan::Join { left, right, .. }
+        | LogicalPlan::CrossJoin { left, right, .. } => vec![left, right],
         LogicalPlan::Limit { input, .. } => vec![input],
+        LogicalPlan::EnforceSingleRow { input } => vec![input],
+        LogicalPlan::Union { inputs, .. } => inputs.iter().collect(),
         LogicalPlan::Extension { node } => node.inputs(),
         // plans without inputs
         LogicalPlan::TableScan { .. }
@@ -218,17 +249,27 @@ pub fn from_plan(
                 input: Arc::new(inputs[0].clone()),
             }),
         },
+        LogicalPlan::Window { schema, .. } => Ok(LogicalPlan::Window {
+            input: Arc::new(inputs[0].clone()),
+            window_expr: expr.clone(),
+            schema: schema.clone(),
+        }),
         LogicalPlan::Aggregate {
-            group_expr, schema, ..
+            group_expr,
+            grouping_sets,
+            schema,
+            ..
         } => Ok(LogicalPlan::Aggregate {
             group_expr: expr[0..group_expr.len()].to_vec(),
+            grouping_sets: grouping_sets.clone(),
             aggr_expr: expr[group_expr.len()..].to_vec(),
             input: Arc::new(inputs[0].clone()),
             schema: schema.clone(),
         }),
-        LogicalPlan::Sort { .. } => Ok(LogicalPlan::Sort {
+        LogicalPlan::Sort { fetch, .. } => Ok(LogicalPlan::Sort {
             expr: expr.clone(),
             input: Arc::new(inputs[0].clone()),
+            fetch: *fetch,
         }),
         LogicalPlan::Join {
             join_type,
@@ -240,12 +281,26 @@ pub fn from_plan(
             right: Arc::new(inputs[1].clone()),
             join_type: *join_type,
             on: on.clone(),
+            // the filter follows the join columns
+            filter: expr.get(on.len() * 2).cloned(),
+            schema: schema.clone(),
+        }),
+        LogicalPlan::CrossJoin { schema, .. } => Ok(LogicalPlan::CrossJoin {
+            left: Arc::new(inputs[0].clone()),
+            right: Arc::new(inputs[1].clone()),
             schema: schema.clone(),
         }),
         LogicalPlan::Limit { n, .. } => Ok(LogicalPlan::Limit {
             n: *n,
             input: Arc::new(inputs[0].clone()),
         }),
+        LogicalPlan::EnforceSingleRow { .. } => Ok(LogicalPlan::EnforceSingleRow {
+            input: Arc::new(inputs[0].clone()),
+        }),
+        LogicalPlan::Union { schema, .. } => Ok(LogicalPlan::Union {
+            inputs: inputs.clone(),
+            schema: schema.clone(),
+        }),
         LogicalPlan::Extension { node } => Ok(LogicalPlan::Extension {
             node: node.from_template(expr, inputs),
         }),
@@ -267,8 +322,23 @@ pub fn expr_sub_expressions(expr: &Expr) -> Result<Vec<Expr>> {
         Expr::IsNotNull(e) => Ok(vec![e.as_ref().to_owned()]),
         Expr::ScalarFunction { args, .. } => Ok(args.clone()),
         Expr::ScalarUDF { args, .. } => Ok(args.clone()),
-        Expr::AggregateFunction { args, .. } => Ok(args.clone()),
+        Expr::AggregateFunction { args, order_by, .. } => {
+            let mut expr_list = args.clone();
+            expr_list.extend(order_by.clone());
+            Ok(expr_list)
+        }
         Expr::AggregateUDF { args, .. } => Ok(args.clone()),
+        Expr::WindowFunction {
+            args,
+            partition_by,
+            order_by,
+            ..
+        } => {
+            let mut expr_list = args.clone();
+            expr_list.extend(partition_by.clone());
+            expr_list.extend(order_by.clone());
+            Ok(expr_list)
+        }
         Expr::Case {
             expr,
             when_then_expr,
@@ -305,6 +375,14 @@ pub fn expr_sub_expressions(expr: &Expr) -> Result<Vec<Expr>> {
             low.as_ref().to_owned(),
             high.as_ref().to_owned(),
         ]),
+        Expr::InList { expr, list, .. } => {
+            let mut expr_list = vec![expr.as_ref().to_owned()];
+            expr_list.extend(list.iter().cloned());
+            Ok(expr_list)
+        }
+        Expr::Exists { .. } => Ok(vec![]),
+        Expr::InSubquery { expr, .. } => Ok(vec![expr.as_ref().to_owned()]),
+        Expr::ScalarSubquery(_) => Ok(vec![]),
         Expr::Wildcard { .. } => Err(DataFusionError::Internal(
             "Wildcard expressions are not valid in a logical query plan".to_owned(),
         )),
@@ -331,15 +409,38 @@ pub fn rewrite_expression(expr: &Expr, expressions: &Vec<Expr>) -> Result<Expr>
             fun: fun.clone(),
             args: expressions.clone(),
         }),
-        Expr::AggregateFunction { fun, distinct, .. } => Ok(Expr::AggregateFunction {
+        Expr::AggregateFunction {
+            fun,
+            distinct,
+            args,
+            ..
+        } => Ok(Expr::AggregateFunction {
             fun: fun.clone(),
-            args: expressions.clone(),
+            args: expressions[..args.len()].to_vec(),
             distinct: *distinct,
+            order_by: expressions[args.len()..].to_vec(),
         }),
         Expr::AggregateUDF { fun, .. } => Ok(Expr::AggregateUDF {
             fun: fun.clone(),
             args: expressions.clone(),
         }),
+        Expr::WindowFunction {
+            fun,
+            args,
+            partition_by,
+            window_frame,
+            ..
+        } => {
+            let partition_index = args.len();
+            let order_index = partition_index + partition_by.len();
+            Ok(Expr::WindowFunction {
+                fun: fun.clone(),
+                args: expressions[..partition_index].to_vec(),
+                partition_by: expressions[partition_index..order_index].to_vec(),
+                order_by: expressions[order_index..].to_vec(),
+                window_frame: *window_frame,
+            })
+        }
         Expr::Case { .. } => {
             let mut base_expr: Option<Box<Expr>> = None;
             let mut when_then: Vec<(Box<Expr>, Box<Expr>)> = vec![];
@@ -416,6 +517,20 @@ pub fn rewrite_expression(expr: &Expr, expressions: &Vec<Expr>) -> Result<Expr>
                 Ok(expr)
             }
         }
+        Expr::InList { negated, .. } => Ok(Expr::InList {
+            expr: Box::new(expressions[0].clone()),
+            list: expressions[1..].to_vec(),
+            negated: *negated,
+        }),
+        Expr::Exists { .. } => Ok(expr.clone()),
+        Expr::InSubquery {
+            subquery, negated, ..
+        } => Ok(Expr::InSubquery {
+            expr: Box::new(expressions[0].clone()),
+            subquery: subquery.clone(),
+            negated: *negated,
+        }),
+        Expr::ScalarSubquery(_) => Ok(expr.clone()),
         Expr::Wildcard { .. } => Err(DataFusionError::Internal(
             "Wildcard expressions are not valid in a logical query plan".to_owned(),
         )),
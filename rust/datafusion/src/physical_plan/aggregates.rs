@@ -29,12 +29,21 @@
 use super::{
     functions::Signature,
     type_coercion::{coerce, data_types},
-    Accumulator, AggregateExpr, PhysicalExpr,
+    Accumulator, AggregateExpr, ColumnarValue, PhysicalExpr,
 };
 use crate::error::{DataFusionError, Result};
+use crate::physical_plan::approx_expressions::{ApproxDistinct, ApproxPercentileCont};
+use crate::physical_plan::collection_expressions::{ArrayAgg, StringAgg};
 use crate::physical_plan::distinct_expressions;
-use crate::physical_plan::expressions;
-use arrow::datatypes::{DataType, Schema};
+use crate::physical_plan::expressions::{self, PhysicalSortExpr};
+use crate::physical_plan::statistical_expressions::{
+    percentile_value, Correlation, Covariance, PercentileCont, StatsType, Stddev,
+    Variance,
+};
+use crate::scalar::ScalarValue;
+use arrow::array::{ArrayRef, NullArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
 use expressions::{avg_return_type, sum_return_type};
 use std::{fmt, str::FromStr, sync::Arc};
 
@@ -60,12 +69,55 @@ pub enum AggregateFunction {
     Max,
     /// avg
     Avg,
+    /// sample standard deviation
+    Stddev,
+    /// population standard deviation
+    StddevPop,
+    /// sample variance
+    Variance,
+    /// population variance
+    VariancePop,
+    /// sample covariance
+    Covariance,
+    /// population covariance
+    CovariancePop,
+    /// correlation
+    Correlation,
+    /// median
+    Median,
+    /// continuous percentile
+    PercentileCont,
+    /// approximate number of distinct values
+    ApproxDistinct,
+    /// approximate continuous percentile
+    ApproxPercentileCont,
+    /// the values as a list
+    ArrayAgg,
+    /// the concatenation of the values, with a separator
+    StringAgg,
+    /// whether its arguments are aggregated in the grouping set of the row, as a bitmask
+    Grouping,
 }
 
 impl fmt::Display for AggregateFunction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // uppercase of the debug.
-        write!(f, "{}", format!("{:?}", self).to_uppercase())
+        let name = match self {
+            AggregateFunction::StddevPop => "STDDEV_POP".to_string(),
+            AggregateFunction::VariancePop => "VAR_POP".to_string(),
+            AggregateFunction::Covariance => "COVAR".to_string(),
+            AggregateFunction::CovariancePop => "COVAR_POP".to_string(),
+            AggregateFunction::Correlation => "CORR".to_string(),
+            AggregateFunction::PercentileCont => "PERCENTILE_CONT".to_string(),
+            AggregateFunction::ApproxDistinct => "APPROX_DISTINCT".to_string(),
+            AggregateFunction::ApproxPercentileCont => {
+                "APPROX_PERCENTILE_CONT".to_string()
+            }
+            AggregateFunction::ArrayAgg => "ARRAY_AGG".to_string(),
+            AggregateFunction::StringAgg => "STRING_AGG".to_string(),
+            // uppercase of the debug.
+            _ => format!("{:?}", self).to_uppercase(),
+        };
+        write!(f, "{}", name)
     }
 }
 
@@ -78,6 +130,22 @@ impl FromStr for AggregateFunction {
             "COUNT" => AggregateFunction::Count,
             "AVG" => AggregateFunction::Avg,
             "SUM" => AggregateFunction::Sum,
+            "STDDEV" | "STDDEV_SAMP" => AggregateFunction::Stddev,
+            "STDDEV_POP" => AggregateFunction::StddevPop,
+            "VARIANCE" | "VAR" | "VAR_SAMP" => AggregateFunction::Variance,
+            "VAR_POP" => AggregateFunction::VariancePop,
+            "COVAR" | "COVAR_SAMP" => AggregateFunction::Covariance,
+            "COVAR_POP" => AggregateFunction::CovariancePop,
+            "CORR" => AggregateFunction::Correlation,
+            "MEDIAN" => AggregateFunction::Median,
+            "PERCENTILE_CONT" => AggregateFunction::PercentileCont,
+            "APPROX_DISTINCT" => AggregateFunction::ApproxDistinct,
+            "APPROX_PERCENTILE_CONT" | "APPROX_PERCENTILE" => {
+                AggregateFunction::ApproxPercentileCont
+            }
+            "ARRAY_AGG" => AggregateFunction::ArrayAgg,
+            "STRING_AGG" => AggregateFunction::StringAgg,
+            "GROUPING" => AggregateFunction::Grouping,
             _ => {
                 return Err(DataFusionError::Plan(format!(
                     "There is no built-in function named {}",
@@ -100,10 +168,30 @@ pub fn return_type(
     data_types(arg_types, &signature(fun))?;
 
     match fun {
-        AggregateFunction::Count => Ok(DataType::UInt64),
+        AggregateFunction::Count | AggregateFunction::ApproxDistinct => {
+            Ok(DataType::UInt64)
+        }
         AggregateFunction::Max | AggregateFunction::Min => Ok(arg_types[0].clone()),
         AggregateFunction::Sum => sum_return_type(&arg_types[0]),
         AggregateFunction::Avg => avg_return_type(&arg_types[0]),
+        // the statistical aggregates are computed on f64
+        AggregateFunction::Stddev
+        | AggregateFunction::StddevPop
+        | AggregateFunction::Variance
+        | AggregateFunction::VariancePop
+        | AggregateFunction::Covariance
+        | AggregateFunction::CovariancePop
+        | AggregateFunction::Correlation
+        | AggregateFunction::Median
+        | AggregateFunction::PercentileCont
+        | AggregateFunction::ApproxPercentileCont => Ok(DataType::Float64),
+        AggregateFunction::ArrayAgg => Ok(DataType::List(Box::new(Field::new(
+            "item",
+            arg_types[0].clone(),
+            true,
+        )))),
+        AggregateFunction::StringAgg => Ok(DataType::Utf8),
+        AggregateFunction::Grouping => Ok(DataType::Int32),
     }
 }
 
@@ -116,8 +204,35 @@ pub fn create_aggregate_expr(
     input_schema: &Schema,
     name: String,
 ) -> Result<Arc<dyn AggregateExpr>> {
+    create_ordered_aggregate_expr(fun, distinct, args, &[], input_schema, name)
+}
+
+/// Create a physical (function) expression, whose values are fed in the order of
+/// `order_by`. Only the functions whose result depends on the order of the values
+/// accept an ordering.
+pub fn create_ordered_aggregate_expr(
+    fun: &AggregateFunction,
+    distinct: bool,
+    args: &Vec<Arc<dyn PhysicalExpr>>,
+    order_by: &[PhysicalSortExpr],
+    input_schema: &Schema,
+    name: String,
+) -> Result<Arc<dyn AggregateExpr>> {
+    let ordered = matches!(
+        fun,
+        AggregateFunction::ArrayAgg | AggregateFunction::StringAgg
+    );
+    if !order_by.is_empty() && (!ordered || distinct) {
+        return Err(DataFusionError::NotImplemented(format!(
+            "ORDER BY is not supported in {}{} aggregations",
+            fun,
+            if distinct { "(DISTINCT)" } else { "" }
+        )));
+    }
+
     // coerce
-    let arg = coerce(args, input_schema, &signature(fun))?[0].clone();
+    let coerced_args = coerce(args, input_schema, &signature(fun))?;
+    let arg = coerced_args[0].clone();
 
     let arg_types = args
         .iter()
@@ -160,9 +275,108 @@ pub fn create_aggregate_expr(
                 "AVG(DISTINCT) aggregations are not available".to_string(),
             ));
         }
+        (AggregateFunction::ArrayAgg, true) => Arc::new(
+            distinct_expressions::DistinctArrayAgg::new(arg, name, arg_types[0].clone()),
+        ),
+        (_, true) => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "{}(DISTINCT) aggregations are not available",
+                fun
+            )));
+        }
+        (AggregateFunction::Stddev, false) => {
+            Arc::new(Stddev::new(arg, name, StatsType::Sample))
+        }
+        (AggregateFunction::StddevPop, false) => {
+            Arc::new(Stddev::new(arg, name, StatsType::Population))
+        }
+        (AggregateFunction::Variance, false) => {
+            Arc::new(Variance::new(arg, name, StatsType::Sample))
+        }
+        (AggregateFunction::VariancePop, false) => {
+            Arc::new(Variance::new(arg, name, StatsType::Population))
+        }
+        (AggregateFunction::Covariance, false) => Arc::new(Covariance::new(
+            arg,
+            coerced_args[1].clone(),
+            name,
+            StatsType::Sample,
+        )),
+        (AggregateFunction::CovariancePop, false) => Arc::new(Covariance::new(
+            arg,
+            coerced_args[1].clone(),
+            name,
+            StatsType::Population,
+        )),
+        (AggregateFunction::Correlation, false) => {
+            Arc::new(Correlation::new(arg, coerced_args[1].clone(), name))
+        }
+        (AggregateFunction::Median, false) => {
+            Arc::new(PercentileCont::new(arg, name, 0.5))
+        }
+        (AggregateFunction::PercentileCont, false) => {
+            let percentile = percentile_value(&coerced_args[1])?;
+            Arc::new(PercentileCont::new(arg, name, percentile))
+        }
+        (AggregateFunction::ApproxDistinct, false) => {
+            Arc::new(ApproxDistinct::new(arg, name))
+        }
+        (AggregateFunction::ApproxPercentileCont, false) => {
+            let percentile = percentile_value(&coerced_args[1])?;
+            Arc::new(ApproxPercentileCont::new(arg, name, percentile))
+        }
+        (AggregateFunction::ArrayAgg, false) => Arc::new(ArrayAgg::try_new(
+            arg,
+            name,
+            arg_types[0].clone(),
+            order_by.to_vec(),
+            input_schema,
+        )?),
+        (AggregateFunction::StringAgg, false) => {
+            let separator = match constant_argument(&coerced_args[1]) {
+                Some(ScalarValue::Utf8(Some(separator))) => separator,
+                _ => {
+                    return Err(DataFusionError::Plan(format!(
+                        "STRING_AGG expects a constant separator, got {}",
+                        coerced_args[1]
+                    )))
+                }
+            };
+            Arc::new(StringAgg::try_new(
+                arg,
+                name,
+                separator,
+                order_by.to_vec(),
+                input_schema,
+            )?)
+        }
+        (AggregateFunction::Grouping, false) => {
+            // GROUPING does not aggregate values but is computed from the grouping
+            // sets of its aggregate
+            return Err(DataFusionError::Internal(
+                "GROUPING is planned with the grouping sets of its aggregate".to_string(),
+            ));
+        }
     })
 }
 
+/// Returns the value of an argument of an aggregate function that must be a constant,
+/// or `None` when it is not one
+pub(crate) fn constant_argument(expr: &Arc<dyn PhysicalExpr>) -> Option<ScalarValue> {
+    // the expression is evaluated against a batch without the input columns, so that
+    // it fails when it references one
+    let schema = Schema::new(vec![Field::new(".", DataType::Null, true)]);
+    let batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![Arc::new(NullArray::new(1)) as ArrayRef],
+    )
+    .ok()?;
+    match expr.evaluate(&batch).ok()? {
+        ColumnarValue::Scalar(value) => Some(value),
+        ColumnarValue::Array(array) => ScalarValue::try_from_array(&array, 0).ok(),
+    }
+}
+
 static NUMERICS: &[DataType] = &[
     DataType::Int8,
     DataType::Int16,
@@ -181,6 +395,12 @@ fn signature(fun: &AggregateFunction) -> Signature {
     // note: the physical expression must accept the type returned by this function or the execution panics.
     match fun {
         AggregateFunction::Count => Signature::Any(1),
+        // the values are hashed
+        AggregateFunction::ApproxDistinct => {
+            let mut valid = vec![DataType::Boolean, DataType::Utf8, DataType::LargeUtf8];
+            valid.extend_from_slice(&NUMERICS[..8]);
+            Signature::Uniform(1, valid)
+        }
         AggregateFunction::Min | AggregateFunction::Max => {
             let mut valid = vec![DataType::Utf8, DataType::LargeUtf8];
             valid.extend_from_slice(NUMERICS);
@@ -189,6 +409,27 @@ fn signature(fun: &AggregateFunction) -> Signature {
         AggregateFunction::Avg | AggregateFunction::Sum => {
             Signature::Uniform(1, NUMERICS.to_vec())
         }
+        AggregateFunction::ArrayAgg => {
+            let mut valid = vec![DataType::Boolean, DataType::Utf8];
+            valid.extend_from_slice(NUMERICS);
+            Signature::Uniform(1, valid)
+        }
+        AggregateFunction::StringAgg => {
+            Signature::Exact(vec![DataType::Utf8, DataType::Utf8])
+        }
+        AggregateFunction::Grouping => Signature::VariadicAny,
+        AggregateFunction::Stddev
+        | AggregateFunction::StddevPop
+        | AggregateFunction::Variance
+        | AggregateFunction::VariancePop
+        | AggregateFunction::Median => Signature::Exact(vec![DataType::Float64]),
+        AggregateFunction::Covariance
+        | AggregateFunction::CovariancePop
+        | AggregateFunction::Correlation
+        | AggregateFunction::PercentileCont
+        | AggregateFunction::ApproxPercentileCont => {
+            Signature::Exact(vec![DataType::Float64, DataType::Float64])
+        }
     }
 }
 
@@ -241,6 +482,97 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_statistical_return_type() -> Result<()> {
+        let observed = return_type(&AggregateFunction::Stddev, &vec![DataType::Int32])?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(
+            &AggregateFunction::Correlation,
+            &vec![DataType::Float32, DataType::Int64],
+        )?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(&AggregateFunction::Median, &vec![DataType::Utf8]);
+        assert!(observed.is_err());
+        let observed =
+            return_type(&AggregateFunction::Covariance, &vec![DataType::Float64]);
+        assert!(observed.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_statistical_names() -> Result<()> {
+        for fun in vec![
+            AggregateFunction::Stddev,
+            AggregateFunction::StddevPop,
+            AggregateFunction::Variance,
+            AggregateFunction::VariancePop,
+            AggregateFunction::Covariance,
+            AggregateFunction::CovariancePop,
+            AggregateFunction::Correlation,
+            AggregateFunction::Median,
+            AggregateFunction::PercentileCont,
+            AggregateFunction::ApproxDistinct,
+            AggregateFunction::ApproxPercentileCont,
+        ] {
+            assert_eq!(fun, AggregateFunction::from_str(&fun.to_string())?);
+        }
+        assert_eq!(
+            AggregateFunction::Variance,
+            AggregateFunction::from_str("var_samp")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_approx_return_type() -> Result<()> {
+        let observed =
+            return_type(&AggregateFunction::ApproxDistinct, &vec![DataType::Utf8])?;
+        assert_eq!(DataType::UInt64, observed);
+
+        let observed = return_type(
+            &AggregateFunction::ApproxPercentileCont,
+            &vec![DataType::Int32, DataType::Float64],
+        )?;
+        assert_eq!(DataType::Float64, observed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_collection_return_type() -> Result<()> {
+        let observed = return_type(&AggregateFunction::ArrayAgg, &vec![DataType::Int32])?;
+        assert_eq!(
+            DataType::List(Box::new(Field::new("item", DataType::Int32, true))),
+            observed
+        );
+
+        let observed = return_type(
+            &AggregateFunction::StringAgg,
+            &vec![DataType::Utf8, DataType::Utf8],
+        )?;
+        assert_eq!(DataType::Utf8, observed);
+        assert_eq!(
+            AggregateFunction::StringAgg,
+            AggregateFunction::from_str("string_agg")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_grouping_return_type() -> Result<()> {
+        let observed = return_type(
+            &AggregateFunction::Grouping,
+            &vec![DataType::Utf8, DataType::Int64],
+        )?;
+        assert_eq!(DataType::Int32, observed);
+
+        // GROUPING expects at least one argument
+        let observed = return_type(&AggregateFunction::Grouping, &vec![]);
+        assert!(observed.is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_avg_no_utf8() -> Result<()> {
         let observed = return_type(&AggregateFunction::Avg, &vec![DataType::Utf8]);
@@ -0,0 +1,654 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Approximate aggregate expressions, whose memory does not grow with the number of
+//! values: `approx_distinct`, which estimates the number of distinct values with a
+//! HyperLogLog sketch, and `approx_percentile_cont`, which estimates a percentile with
+//! a t-digest.
+//!
+//! The sketches are serialized into a binary state, and sketches of different
+//! partitions are merged into the sketch of their union.
+
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use ahash::RandomState;
+use arrow::array::{Array, ArrayRef, BinaryArray, Float64Array};
+use arrow::datatypes::{DataType, Field};
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::format_state_name;
+use crate::physical_plan::hash_utils::create_hashes;
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+
+/// Returns the binary values of `array`, which must be a `BinaryArray`
+fn as_binary_array(array: &ArrayRef) -> Result<&BinaryArray> {
+    array.as_any().downcast_ref::<BinaryArray>().ok_or_else(|| {
+        DataFusionError::Internal(format!(
+            "Expected a BinaryArray, got {:?}",
+            array.data_type()
+        ))
+    })
+}
+
+/// Returns the bytes of a binary state, `None` when it is null
+fn as_bytes(value: &ScalarValue) -> Result<Option<&[u8]>> {
+    match value {
+        ScalarValue::Binary(value) => Ok(value.as_deref()),
+        other => Err(DataFusionError::Internal(format!(
+            "Expected a Binary value, got {:?}",
+            other
+        ))),
+    }
+}
+
+/// The number of bits of the hash of a value that select its register. The relative
+/// standard error of the estimates is `1.04 / sqrt(2^HLL_PRECISION)`, about 1.6%.
+const HLL_PRECISION: u32 = 12;
+
+/// The number of registers of a [HyperLogLog]
+const HLL_REGISTERS: usize = 1 << HLL_PRECISION;
+
+/// A HyperLogLog sketch, as described by Flajolet et al., which estimates the number
+/// of distinct hashes added to it
+#[derive(Debug, Clone)]
+struct HyperLogLog {
+    /// For each register, the maximum rank of the hashes assigned to it
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    fn new() -> Self {
+        Self {
+            registers: vec![0; HLL_REGISTERS],
+        }
+    }
+
+    fn add_hash(&mut self, hash: u64) {
+        let index = (hash >> (64 - HLL_PRECISION)) as usize;
+        // the rank is the position of the first set bit of the remaining bits, which
+        // are terminated by a set bit so that it is bounded
+        let remaining = (hash << HLL_PRECISION) | (1 << (HLL_PRECISION - 1));
+        let rank = remaining.leading_zeros() as u8 + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Merges the serialized registers of another sketch into this one
+    fn merge_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() != HLL_REGISTERS {
+            return Err(DataFusionError::Internal(format!(
+                "Invalid HyperLogLog state of {} bytes",
+                bytes.len()
+            )));
+        }
+        for (register, other) in self.registers.iter_mut().zip(bytes) {
+            *register = (*register).max(*other);
+        }
+        Ok(())
+    }
+
+    fn count(&self) -> u64 {
+        let m = HLL_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|rank| 2f64.powi(-(*rank as i32)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+
+        // the estimate is biased for small cardinalities, which are better estimated
+        // by linear counting of the empty registers
+        let zeros = self.registers.iter().filter(|rank| **rank == 0).count();
+        let estimate = if estimate <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        };
+        estimate.round() as u64
+    }
+}
+
+/// APPROX_DISTINCT aggregate expression, an estimate of the number of distinct
+/// non-null values of its argument
+#[derive(Debug)]
+pub struct ApproxDistinct {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl ApproxDistinct {
+    /// Create a new APPROX_DISTINCT aggregate function
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String) -> Self {
+        Self { name, expr }
+    }
+}
+
+impl AggregateExpr for ApproxDistinct {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::UInt64, false))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "hll_registers"),
+            DataType::Binary,
+            false,
+        )])
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ApproxDistinctAccumulator {
+            hll: HyperLogLog::new(),
+            // the hashes must be equal across accumulators for their sketches to merge
+            random_state: RandomState::with_seeds(0, 0, 0, 0),
+            hashes_buffer: vec![],
+        }))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+}
+
+#[derive(Debug)]
+struct ApproxDistinctAccumulator {
+    hll: HyperLogLog,
+    random_state: RandomState,
+    hashes_buffer: Vec<u64>,
+}
+
+impl Accumulator for ApproxDistinctAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![ScalarValue::Binary(Some(self.hll.registers.clone()))])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        self.update_batch(&vec![values[0].to_array()])
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let array = &values[0];
+        self.hashes_buffer.clear();
+        self.hashes_buffer.resize(array.len(), 0);
+        create_hashes(&values[..1], &self.random_state, &mut self.hashes_buffer)?;
+        for (row, hash) in self.hashes_buffer.iter().enumerate() {
+            if array.is_valid(row) {
+                self.hll.add_hash(*hash);
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        match as_bytes(&states[0])? {
+            Some(bytes) => self.hll.merge_bytes(bytes),
+            None => Ok(()),
+        }
+    }
+
+    fn merge_batch(&mut self, states: &Vec<ArrayRef>) -> Result<()> {
+        let states = as_binary_array(&states[0])?;
+        for i in 0..states.len() {
+            if states.is_valid(i) {
+                self.hll.merge_bytes(states.value(i))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(ScalarValue::UInt64(Some(self.hll.count())))
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self.hll.registers.capacity()
+            + self.hashes_buffer.capacity() * std::mem::size_of::<u64>()
+    }
+}
+
+/// The compression of a [TDigest], which bounds its number of centroids. Larger values
+/// trade memory for accuracy.
+const TDIGEST_COMPRESSION: f64 = 100.0;
+
+/// The number of values buffered by a [TDigest] before they are merged into centroids
+const TDIGEST_BUFFER_SIZE: usize = 500;
+
+/// A centroid of a [TDigest], the mean of `weight` adjacent values
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// A merging t-digest, as described by Dunning and Ertl, which summarizes a
+/// distribution with centroids that are smaller near the extreme percentiles
+#[derive(Debug, Clone)]
+struct TDigest {
+    /// The centroids, sorted by mean
+    centroids: Vec<Centroid>,
+    /// The centroids not yet merged into `centroids`
+    buffer: Vec<Centroid>,
+    min: f64,
+    max: f64,
+}
+
+/// The scale function of the t-digest, which maps a quantile to the index of its
+/// centroid. A centroid may span at most one unit of the scale.
+fn tdigest_scale(quantile: f64) -> f64 {
+    let x = (2.0 * quantile - 1.0).max(-1.0).min(1.0);
+    TDIGEST_COMPRESSION / (2.0 * PI) * x.asin()
+}
+
+impl TDigest {
+    fn new() -> Self {
+        Self {
+            centroids: vec![],
+            buffer: vec![],
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        // NaN can't be ordered and is ignored
+        if value.is_nan() {
+            return;
+        }
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.add_centroid(Centroid {
+            mean: value,
+            weight: 1.0,
+        });
+    }
+
+    fn add_centroid(&mut self, centroid: Centroid) {
+        self.buffer.push(centroid);
+        if self.buffer.len() >= TDIGEST_BUFFER_SIZE {
+            self.compress();
+        }
+    }
+
+    /// Merges the buffered centroids into the centroids
+    fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        let mut centroids = std::mem::take(&mut self.centroids);
+        centroids.append(&mut self.buffer);
+        centroids.sort_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap_or(Ordering::Equal));
+        let total: f64 = centroids.iter().map(|c| c.weight).sum();
+
+        let mut centroids = centroids.into_iter();
+        // the buffer was not empty
+        let mut current = centroids.next().unwrap();
+        let mut weight_before = 0.0;
+        for centroid in centroids {
+            let weight = current.weight + centroid.weight;
+            let size = tdigest_scale((weight_before + weight) / total)
+                - tdigest_scale(weight_before / total);
+            if size <= 1.0 {
+                current.mean += (centroid.mean - current.mean) * centroid.weight / weight;
+                current.weight = weight;
+            } else {
+                weight_before += current.weight;
+                self.centroids.push(current);
+                current = centroid;
+            }
+        }
+        self.centroids.push(current);
+    }
+
+    /// Returns the estimated value at `percentile`, interpolated as by
+    /// `PERCENTILE_CONT`, or `None` when the digest is empty. The digest must be
+    /// compressed.
+    fn percentile(&self, percentile: f64) -> Option<f64> {
+        if self.centroids.is_empty() {
+            return None;
+        }
+        let total: f64 = self.centroids.iter().map(|c| c.weight).sum();
+        // the position of the value in the sorted values
+        let position = percentile * (total - 1.0);
+        let interpolate = |(p0, v0): (f64, f64), (p1, v1): (f64, f64)| {
+            if p1 > p0 {
+                v0 + (v1 - v0) * (position - p0) / (p1 - p0)
+            } else {
+                v1
+            }
+        };
+
+        // each centroid is placed at the middle position of the values it summarizes,
+        // between the minimum at the first position and the maximum at the last one
+        let mut previous = (0.0, self.min);
+        let mut weight_before = 0.0;
+        for centroid in &self.centroids {
+            let center = (weight_before + (centroid.weight - 1.0) / 2.0).max(previous.0);
+            if position <= center {
+                return Some(interpolate(previous, (center, centroid.mean)));
+            }
+            previous = (center, centroid.mean);
+            weight_before += centroid.weight;
+        }
+        Some(interpolate(previous, (total - 1.0, self.max)))
+    }
+
+    /// Serializes the digest, which must be compressed, as the little-endian f64 of its
+    /// minimum, its maximum and the mean and weight of each centroid
+    fn to_bytes(&self) -> Vec<u8> {
+        if self.centroids.is_empty() {
+            return vec![];
+        }
+        let mut bytes = Vec::with_capacity(16 * (self.centroids.len() + 1));
+        bytes.extend_from_slice(&self.min.to_le_bytes());
+        bytes.extend_from_slice(&self.max.to_le_bytes());
+        for centroid in &self.centroids {
+            bytes.extend_from_slice(&centroid.mean.to_le_bytes());
+            bytes.extend_from_slice(&centroid.weight.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Merges a digest serialized by [TDigest::to_bytes] into this one
+    fn merge_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        if bytes.len() % 16 != 0 {
+            return Err(DataFusionError::Internal(format!(
+                "Invalid t-digest state of {} bytes",
+                bytes.len()
+            )));
+        }
+        let mut values = bytes.chunks_exact(8).map(|chunk| {
+            let mut value = [0u8; 8];
+            value.copy_from_slice(chunk);
+            f64::from_le_bytes(value)
+        });
+        if let (Some(min), Some(max)) = (values.next(), values.next()) {
+            self.min = self.min.min(min);
+            self.max = self.max.max(max);
+            while let (Some(mean), Some(weight)) = (values.next(), values.next()) {
+                self.add_centroid(Centroid { mean, weight });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a compressed copy of this digest
+    fn compressed(&self) -> Self {
+        let mut digest = self.clone();
+        digest.compress();
+        digest
+    }
+}
+
+/// APPROX_PERCENTILE_CONT aggregate expression, an estimate of the value at a
+/// percentile of the sorted values of its argument
+#[derive(Debug)]
+pub struct ApproxPercentileCont {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    /// The percentile, between 0 and 1
+    percentile: f64,
+}
+
+impl ApproxPercentileCont {
+    /// Create a new APPROX_PERCENTILE_CONT aggregate function, whose argument must be
+    /// of type f64
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String, percentile: f64) -> Self {
+        Self {
+            name,
+            expr,
+            percentile,
+        }
+    }
+}
+
+impl AggregateExpr for ApproxPercentileCont {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "tdigest"),
+            DataType::Binary,
+            false,
+        )])
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ApproxPercentileContAccumulator {
+            digest: TDigest::new(),
+            percentile: self.percentile,
+        }))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+}
+
+#[derive(Debug)]
+struct ApproxPercentileContAccumulator {
+    digest: TDigest,
+    percentile: f64,
+}
+
+impl Accumulator for ApproxPercentileContAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let bytes = self.digest.compressed().to_bytes();
+        Ok(vec![ScalarValue::Binary(Some(bytes))])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        match &values[0] {
+            ScalarValue::Float64(Some(value)) => self.digest.add(*value),
+            ScalarValue::Float64(None) => {}
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Expected a Float64 value, got {:?}",
+                    other
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let values = values[0]
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "Expected a Float64Array, got {:?}",
+                    values[0].data_type()
+                ))
+            })?;
+        for i in 0..values.len() {
+            if values.is_valid(i) {
+                self.digest.add(values.value(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        match as_bytes(&states[0])? {
+            Some(bytes) => self.digest.merge_bytes(bytes),
+            None => Ok(()),
+        }
+    }
+
+    fn merge_batch(&mut self, states: &Vec<ArrayRef>) -> Result<()> {
+        let states = as_binary_array(&states[0])?;
+        for i in 0..states.len() {
+            if states.is_valid(i) {
+                self.digest.merge_bytes(states.value(i))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let value = self.digest.compressed().percentile(self.percentile);
+        Ok(ScalarValue::Float64(value))
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + (self.digest.centroids.capacity() + self.digest.buffer.capacity())
+                * std::mem::size_of::<Centroid>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use arrow::array::{Int64Array, StringArray};
+
+    /// Aggregates each of `partitions` in its own accumulator, whose states are merged
+    fn aggregate(
+        agg: &dyn AggregateExpr,
+        partitions: Vec<ArrayRef>,
+    ) -> Result<ScalarValue> {
+        let mut states = vec![];
+        for values in partitions {
+            let mut accumulator = agg.create_accumulator()?;
+            accumulator.update_batch(&vec![values])?;
+            states.push(accumulator.state()?[0].to_array());
+        }
+        let states = states.iter().map(|a| a.as_ref()).collect::<Vec<_>>();
+        let states = arrow::compute::concat(&states)?;
+
+        let mut accumulator = agg.create_accumulator()?;
+        accumulator.merge_batch(&vec![states])?;
+        accumulator.evaluate()
+    }
+
+    fn int64s(range: std::ops::Range<i64>) -> ArrayRef {
+        Arc::new(Int64Array::from(range.collect::<Vec<_>>()))
+    }
+
+    fn approx_distinct(partitions: Vec<ArrayRef>) -> Result<u64> {
+        let agg = ApproxDistinct::new(col("a"), "d".to_string());
+        match aggregate(&agg, partitions)? {
+            ScalarValue::UInt64(Some(count)) => Ok(count),
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected result {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn approx_percentile(values: Vec<f64>, percentile: f64) -> Result<Option<f64>> {
+        let agg = ApproxPercentileCont::new(col("a"), "p".to_string(), percentile);
+        // the values are split in two partitions
+        let middle = values.len() / 2;
+        let partitions = vec![
+            Arc::new(Float64Array::from(values[..middle].to_vec())) as ArrayRef,
+            Arc::new(Float64Array::from(values[middle..].to_vec())),
+        ];
+        match aggregate(&agg, partitions)? {
+            ScalarValue::Float64(value) => Ok(value),
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected result {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn assert_relative_error(expected: f64, actual: f64, error: f64) {
+        assert!(
+            ((actual - expected) / expected).abs() <= error,
+            "expected {} within {}, got {}",
+            expected,
+            error,
+            actual
+        );
+    }
+
+    #[test]
+    fn approx_distinct_small() -> Result<()> {
+        let values = Arc::new(StringArray::from(vec![
+            Some("a"),
+            Some("b"),
+            None,
+            Some("a"),
+            Some("c"),
+        ]));
+        assert_eq!(3, approx_distinct(vec![values])?);
+        assert_eq!(0, approx_distinct(vec![int64s(0..0)])?);
+        Ok(())
+    }
+
+    #[test]
+    fn approx_distinct_merges_partitions() -> Result<()> {
+        // the partitions overlap, so that only their union is counted
+        let partitions = vec![int64s(0..60_000), int64s(40_000..100_000)];
+        let count = approx_distinct(partitions)?;
+        assert_relative_error(100_000.0, count as f64, 0.05);
+        Ok(())
+    }
+
+    #[test]
+    fn approx_percentile_small() -> Result<()> {
+        // with few values, the estimates are exact
+        let values = vec![5.0, 1.0, 3.0, 2.0];
+        assert_eq!(Some(2.5), approx_percentile(values.clone(), 0.5)?);
+        assert_eq!(Some(1.0), approx_percentile(values.clone(), 0.0)?);
+        assert_eq!(Some(5.0), approx_percentile(values, 1.0)?);
+        assert_eq!(None, approx_percentile(vec![], 0.5)?);
+        Ok(())
+    }
+
+    #[test]
+    fn approx_percentile_large() -> Result<()> {
+        // a shuffled permutation of 0..100_000
+        let values = (0..100_000u64)
+            .map(|i| ((i * 7919) % 100_000) as f64)
+            .collect::<Vec<_>>();
+        for percentile in vec![0.01, 0.25, 0.5, 0.9, 0.99] {
+            let value = approx_percentile(values.clone(), percentile)?.unwrap();
+            // the values are their rank, whose error is bounded
+            let expected = percentile * 99_999.0;
+            assert!(
+                (value - expected).abs() <= 500.0,
+                "expected {}, got {}",
+                expected,
+                value
+            );
+        }
+        assert_eq!(Some(0.0), approx_percentile(values.clone(), 0.0)?);
+        assert_eq!(Some(99_999.0), approx_percentile(values, 1.0)?);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_state() -> Result<()> {
+        let agg = ApproxDistinct::new(col("a"), "d".to_string());
+        let mut accumulator = agg.create_accumulator()?;
+        assert!(accumulator
+            .merge(&vec![ScalarValue::Binary(Some(vec![1, 2, 3]))])
+            .is_err());
+        Ok(())
+    }
+}
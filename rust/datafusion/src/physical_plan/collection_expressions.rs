@@ -0,0 +1,577 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Collection aggregate expressions, which gather all the values of a group:
+//! `array_agg`, which returns them as a list, and `string_agg`, which concatenates them.
+//!
+//! The values are in no particular order, unless the aggregate is given sort
+//! expressions. Their values are then gathered along with the values of the aggregate,
+//! so that the values of all the partitions are sorted once merged.
+
+use std::sync::Arc;
+
+use arrow::compute::{lexsort_to_indices, SortColumn, SortOptions};
+use arrow::datatypes::{DataType, DateUnit, Field, Schema, TimeUnit};
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::{format_state_name, PhysicalSortExpr};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+
+/// Returns the data types of the sort expressions `ordering`, which must be types that
+/// a list of the state can hold
+fn ordering_types(
+    ordering: &[PhysicalSortExpr],
+    input_schema: &Schema,
+) -> Result<Vec<DataType>> {
+    ordering
+        .iter()
+        .map(|sort_expr| {
+            let data_type = sort_expr.expr.data_type(input_schema)?;
+            match data_type {
+                DataType::Boolean
+                | DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::UInt8
+                | DataType::UInt16
+                | DataType::UInt32
+                | DataType::UInt64
+                | DataType::Float32
+                | DataType::Float64
+                | DataType::Utf8
+                | DataType::Date32(DateUnit::Day)
+                | DataType::Timestamp(TimeUnit::Nanosecond, None) => Ok(data_type),
+                other => Err(DataFusionError::NotImplemented(format!(
+                    "Ordering aggregated values by {:?} is not supported",
+                    other
+                ))),
+            }
+        })
+        .collect()
+}
+
+/// The fields of the state of a [CollectAccumulator]: the list of the values, followed
+/// by the list of the values of each sort expression
+fn collect_state_fields(
+    name: &str,
+    data_type: &DataType,
+    ordering_types: &[DataType],
+) -> Vec<Field> {
+    let list = |data_type: &DataType| {
+        DataType::List(Box::new(Field::new("item", data_type.clone(), true)))
+    };
+    let mut fields = vec![Field::new(
+        &format_state_name(name, "values"),
+        list(data_type),
+        true,
+    )];
+    fields.extend(ordering_types.iter().enumerate().map(|(i, data_type)| {
+        Field::new(
+            &format_state_name(name, &format!("ordering_{}", i)),
+            list(data_type),
+            true,
+        )
+    }));
+    fields
+}
+
+/// The expressions evaluated for a [CollectAccumulator]: the argument of the
+/// aggregate, followed by the sort expressions
+fn collect_expressions(
+    expr: &Arc<dyn PhysicalExpr>,
+    ordering: &[PhysicalSortExpr],
+) -> Vec<Arc<dyn PhysicalExpr>> {
+    let mut exprs = vec![expr.clone()];
+    exprs.extend(ordering.iter().map(|sort_expr| sort_expr.expr.clone()));
+    exprs
+}
+
+/// ARRAY_AGG aggregate expression, the list of the values of its argument, including
+/// the nulls
+#[derive(Debug)]
+pub struct ArrayAgg {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    data_type: DataType,
+    ordering: Vec<PhysicalSortExpr>,
+    ordering_types: Vec<DataType>,
+}
+
+impl ArrayAgg {
+    /// Create a new ARRAY_AGG aggregate function of values of type `data_type`, which
+    /// are sorted by `ordering` when it is not empty
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpr>,
+        name: String,
+        data_type: DataType,
+        ordering: Vec<PhysicalSortExpr>,
+        input_schema: &Schema,
+    ) -> Result<Self> {
+        let ordering_types = ordering_types(&ordering, input_schema)?;
+        Ok(Self {
+            name,
+            expr,
+            data_type,
+            ordering,
+            ordering_types,
+        })
+    }
+}
+
+impl AggregateExpr for ArrayAgg {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(
+            &self.name,
+            DataType::List(Box::new(Field::new("item", self.data_type.clone(), true))),
+            true,
+        ))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(collect_state_fields(
+            &self.name,
+            &self.data_type,
+            &self.ordering_types,
+        ))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ArrayAggAccumulator(CollectAccumulator::new(
+            &self.data_type,
+            &self.ordering,
+            &self.ordering_types,
+        ))))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        collect_expressions(&self.expr, &self.ordering)
+    }
+}
+
+/// STRING_AGG aggregate expression, the concatenation of the non-null values of its
+/// argument, separated by a constant separator
+#[derive(Debug)]
+pub struct StringAgg {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    separator: String,
+    ordering: Vec<PhysicalSortExpr>,
+    ordering_types: Vec<DataType>,
+}
+
+impl StringAgg {
+    /// Create a new STRING_AGG aggregate function, whose argument must be of type utf8,
+    /// and whose values are sorted by `ordering` when it is not empty
+    pub fn try_new(
+        expr: Arc<dyn PhysicalExpr>,
+        name: String,
+        separator: String,
+        ordering: Vec<PhysicalSortExpr>,
+        input_schema: &Schema,
+    ) -> Result<Self> {
+        let ordering_types = ordering_types(&ordering, input_schema)?;
+        Ok(Self {
+            name,
+            expr,
+            separator,
+            ordering,
+            ordering_types,
+        })
+    }
+}
+
+impl AggregateExpr for StringAgg {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Utf8, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(collect_state_fields(
+            &self.name,
+            &DataType::Utf8,
+            &self.ordering_types,
+        ))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(StringAggAccumulator {
+            values: CollectAccumulator::new(
+                &DataType::Utf8,
+                &self.ordering,
+                &self.ordering_types,
+            ),
+            separator: self.separator.clone(),
+        }))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        collect_expressions(&self.expr, &self.ordering)
+    }
+}
+
+/// Gathers the values of an aggregate along with the values of its sort expressions
+#[derive(Debug)]
+struct CollectAccumulator {
+    values: Vec<ScalarValue>,
+    /// The values of each sort expression, one for each value of `values`
+    ordering_values: Vec<Vec<ScalarValue>>,
+    /// Estimated number of bytes used by the gathered values, kept up to date as they
+    /// are gathered
+    values_size: usize,
+    data_type: DataType,
+    ordering_types: Vec<DataType>,
+    sort_options: Vec<SortOptions>,
+}
+
+impl CollectAccumulator {
+    fn new(
+        data_type: &DataType,
+        ordering: &[PhysicalSortExpr],
+        ordering_types: &[DataType],
+    ) -> Self {
+        Self {
+            values: vec![],
+            ordering_values: vec![vec![]; ordering.len()],
+            values_size: 0,
+            data_type: data_type.clone(),
+            ordering_types: ordering_types.to_vec(),
+            sort_options: ordering.iter().map(|sort_expr| sort_expr.options).collect(),
+        }
+    }
+
+    /// Gathers a row of the expressions of the aggregate
+    fn update(&mut self, values: &[ScalarValue]) {
+        self.values_size += values[0].size();
+        self.values.push(values[0].clone());
+        for (ordering_values, value) in self.ordering_values.iter_mut().zip(&values[1..])
+        {
+            self.values_size += value.size();
+            ordering_values.push(value.clone());
+        }
+    }
+
+    fn state(&self) -> Vec<ScalarValue> {
+        let mut state = vec![ScalarValue::List(
+            Some(self.values.clone()),
+            self.data_type.clone(),
+        )];
+        state.extend(self.ordering_values.iter().zip(&self.ordering_types).map(
+            |(values, data_type)| {
+                ScalarValue::List(Some(values.clone()), data_type.clone())
+            },
+        ));
+        state
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        let mut lists = states.iter().map(|state| match state {
+            ScalarValue::List(values, _) => Ok(values.as_deref().unwrap_or(&[])),
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected state {:?} of a collection aggregate",
+                other
+            ))),
+        });
+        if let Some(values) = lists.next() {
+            let values = values?;
+            self.values_size += values.iter().map(ScalarValue::size).sum::<usize>();
+            self.values.extend_from_slice(values);
+        }
+        for (ordering_values, values) in self.ordering_values.iter_mut().zip(lists) {
+            let values = values?;
+            self.values_size += values.iter().map(ScalarValue::size).sum::<usize>();
+            ordering_values.extend_from_slice(values);
+        }
+        Ok(())
+    }
+
+    /// Estimated number of bytes of memory allocated by the gathered values, their
+    /// unused capacity included
+    fn allocated_size(&self) -> usize {
+        let unused = (self.values.capacity() - self.values.len())
+            + self
+                .ordering_values
+                .iter()
+                .map(|values| values.capacity() - values.len())
+                .sum::<usize>();
+        self.values_size + unused * std::mem::size_of::<ScalarValue>()
+    }
+
+    /// Returns the gathered values, sorted by the sort expressions
+    fn sorted_values(&self) -> Result<Vec<ScalarValue>> {
+        if self.ordering_values.is_empty() || self.values.is_empty() {
+            return Ok(self.values.clone());
+        }
+        let columns = self
+            .ordering_values
+            .iter()
+            .zip(&self.sort_options)
+            .map(|(values, options)| {
+                Ok(SortColumn {
+                    values: ScalarValue::iter_to_array(values)?,
+                    options: Some(*options),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let indices = lexsort_to_indices(&columns)?;
+        Ok((0..indices.len())
+            .map(|i| self.values[indices.value(i) as usize].clone())
+            .collect())
+    }
+}
+
+#[derive(Debug)]
+struct ArrayAggAccumulator(CollectAccumulator);
+
+impl Accumulator for ArrayAggAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(self.0.state())
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        self.0.update(values);
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        self.0.merge(states)
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        // the list of no values is null
+        let values = Some(self.0.sorted_values()?).filter(|values| !values.is_empty());
+        Ok(ScalarValue::List(values, self.0.data_type.clone()))
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.0.allocated_size()
+    }
+}
+
+#[derive(Debug)]
+struct StringAggAccumulator {
+    values: CollectAccumulator,
+    separator: String,
+}
+
+impl Accumulator for StringAggAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(self.values.state())
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        // the nulls are not concatenated, and neither gathered
+        if !values[0].is_null() {
+            self.values.update(values);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        self.values.merge(states)
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let values = self
+            .values
+            .sorted_values()?
+            .into_iter()
+            .map(|value| match value {
+                ScalarValue::Utf8(Some(value)) => Ok(value),
+                other => Err(DataFusionError::Internal(format!(
+                    "Unexpected value {:?} of STRING_AGG",
+                    other
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if values.is_empty() {
+            return Ok(ScalarValue::Utf8(None));
+        }
+        Ok(ScalarValue::Utf8(Some(values.join(&self.separator))))
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self.values.allocated_size()
+            + self.separator.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+
+    /// Aggregates each of `partitions` in its own accumulator, whose states are merged
+    fn aggregate(
+        agg: &dyn AggregateExpr,
+        partitions: Vec<Vec<ArrayRef>>,
+    ) -> Result<ScalarValue> {
+        let mut states = vec![];
+        for columns in partitions {
+            let mut accumulator = agg.create_accumulator()?;
+            accumulator.update_batch(&columns)?;
+            states.push(accumulator.state()?);
+        }
+        // the states are merged as arrays
+        let states = (0..states[0].len())
+            .map(|i| {
+                let values = states.iter().map(|s| s[i].to_array()).collect::<Vec<_>>();
+                let values = values.iter().map(|a| a.as_ref()).collect::<Vec<_>>();
+                Ok(arrow::compute::concat(&values)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut accumulator = agg.create_accumulator()?;
+        accumulator.merge_batch(&states)?;
+        accumulator.evaluate()
+    }
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", DataType::Int32, true),
+        ])
+    }
+
+    fn partitions() -> Vec<Vec<ArrayRef>> {
+        vec![
+            vec![
+                Arc::new(StringArray::from(vec![Some("x"), None])),
+                Arc::new(Int32Array::from(vec![Some(3), Some(1)])),
+            ],
+            vec![
+                Arc::new(StringArray::from(vec![Some("y"), Some("z")])),
+                Arc::new(Int32Array::from(vec![Some(2), None])),
+            ],
+        ]
+    }
+
+    fn utf8(value: Option<&str>) -> ScalarValue {
+        ScalarValue::Utf8(value.map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn array_agg() -> Result<()> {
+        let agg = ArrayAgg::try_new(
+            col("a"),
+            "a".to_string(),
+            DataType::Utf8,
+            vec![],
+            &schema(),
+        )?;
+        // the partitions are merged in order
+        let partitions = partitions()
+            .into_iter()
+            .map(|columns| vec![columns[0].clone()])
+            .collect();
+        let expected = ScalarValue::List(
+            Some(vec![
+                utf8(Some("x")),
+                utf8(None),
+                utf8(Some("y")),
+                utf8(Some("z")),
+            ]),
+            DataType::Utf8,
+        );
+        assert_eq!(expected, aggregate(&agg, partitions)?);
+        Ok(())
+    }
+
+    #[test]
+    fn array_agg_size() -> Result<()> {
+        let agg = ArrayAgg::try_new(
+            col("a"),
+            "a".to_string(),
+            DataType::Utf8,
+            vec![],
+            &schema(),
+        )?;
+        let mut accumulator = agg.create_accumulator()?;
+        let empty_size = accumulator.size();
+        // the gathered values are part of the memory of the accumulator
+        for columns in partitions() {
+            let size = accumulator.size();
+            accumulator.update_batch(&vec![columns[0].clone()])?;
+            assert!(accumulator.size() > size);
+        }
+        assert!(
+            accumulator.size() >= empty_size + 4 * std::mem::size_of::<ScalarValue>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn array_agg_ordered() -> Result<()> {
+        let ordering = vec![PhysicalSortExpr {
+            expr: col("b"),
+            options: SortOptions {
+                descending: true,
+                nulls_first: false,
+            },
+        }];
+        let agg = ArrayAgg::try_new(
+            col("a"),
+            "a".to_string(),
+            DataType::Utf8,
+            ordering,
+            &schema(),
+        )?;
+        assert_eq!(agg.state_fields()?.len(), 2);
+        let expected = ScalarValue::List(
+            Some(vec![
+                utf8(Some("x")),
+                utf8(Some("y")),
+                utf8(None),
+                utf8(Some("z")),
+            ]),
+            DataType::Utf8,
+        );
+        assert_eq!(expected, aggregate(&agg, partitions())?);
+
+        // the list of no values is null
+        let empty = vec![vec![
+            Arc::new(StringArray::from(Vec::<Option<&str>>::new())) as ArrayRef,
+            Arc::new(Int32Array::from(Vec::<Option<i32>>::new())),
+        ]];
+        assert_eq!(
+            ScalarValue::List(None, DataType::Utf8),
+            aggregate(&agg, empty)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn string_agg() -> Result<()> {
+        let ordering = vec![PhysicalSortExpr {
+            expr: col("b"),
+            options: SortOptions::default(),
+        }];
+        let agg = StringAgg::try_new(
+            col("a"),
+            "a".to_string(),
+            ", ".to_string(),
+            ordering,
+            &schema(),
+        )?;
+        // the nulls are skipped
+        assert_eq!(utf8(Some("z, y, x")), aggregate(&agg, partitions())?);
+        Ok(())
+    }
+}
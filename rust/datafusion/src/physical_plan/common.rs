@@ -32,12 +32,17 @@ use array::{
 };
 use arrow::datatypes::{DataType, SchemaRef};
 use arrow::error::Result as ArrowResult;
+use arrow::ipc::{reader::FileReader, writer::FileWriter};
 use arrow::record_batch::RecordBatch;
 use arrow::{
     array::{self, ArrayRef},
     datatypes::Schema,
 };
-use futures::{Stream, TryStreamExt};
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::{SinkExt, Stream, TryStreamExt};
+use tempfile::NamedTempFile;
+use tokio::task;
 
 /// Stream of record batches
 pub struct SizedRecordBatchStream {
@@ -87,6 +92,58 @@ pub async fn collect(stream: SendableRecordBatchStream) -> Result<Vec<RecordBatc
         .map_err(DataFusionError::from)
 }
 
+/// Returns the number of bytes of memory used by the columns of a record batch
+pub fn batch_memory_size(batch: &RecordBatch) -> usize {
+    batch
+        .columns()
+        .iter()
+        .map(|column| column.get_array_memory_size())
+        .sum()
+}
+
+/// Writes record batches to a temporary Arrow IPC file, which is deleted when the
+/// returned handle is dropped. The file is written on a blocking thread, so that the
+/// I/O does not block the asynchronous tasks of the runtime.
+pub async fn spill_batches(
+    batches: Vec<RecordBatch>,
+    schema: SchemaRef,
+) -> Result<NamedTempFile> {
+    task::spawn_blocking(move || -> Result<NamedTempFile> {
+        let file = NamedTempFile::new()?;
+        let mut writer = FileWriter::try_new(file.reopen()?, &schema)?;
+        for batch in &batches {
+            writer.write(batch)?;
+        }
+        writer.finish()?;
+        Ok(file)
+    })
+    .await
+    .map_err(|e| DataFusionError::Execution(format!("Failed to spill batches: {}", e)))?
+}
+
+/// Reads the record batches of a file written by [`spill_batches`]. The file is read
+/// on a blocking thread, a couple of batches ahead of the returned stream.
+pub fn read_spilled_batches(
+    file: &NamedTempFile,
+) -> Result<mpsc::Receiver<ArrowResult<RecordBatch>>> {
+    let file = file.reopen()?;
+    let (mut sender, receiver) = mpsc::channel(2);
+    task::spawn_blocking(move || match FileReader::try_new(file) {
+        Ok(reader) => {
+            for batch in reader {
+                if block_on(sender.send(batch)).is_err() {
+                    // the stream was dropped
+                    break;
+                }
+            }
+        }
+        Err(e) => {
+            block_on(sender.send(Err(e))).ok();
+        }
+    });
+    Ok(receiver)
+}
+
 /// Recursively build a list of files in a directory with a given extension
 pub fn build_file_list(dir: &str, filenames: &mut Vec<String>, ext: &str) -> Result<()> {
     let metadata = metadata(dir)?;
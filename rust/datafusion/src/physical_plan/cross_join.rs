@@ -0,0 +1,314 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the cross join plan, which produces the cartesian product of its inputs.
+//! The left side is collected into memory, and joined with every partition of the right.
+
+use std::any::Any;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::{Stream, StreamExt};
+use tokio::sync::Mutex;
+
+use arrow::array::UInt32Array;
+use arrow::compute::take;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use async_trait::async_trait;
+
+use super::common;
+use super::merge::MergeExec;
+use super::{
+    Distribution, ExecutionPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream,
+};
+use crate::error::{DataFusionError, Result};
+
+/// The batches of the left side, once collected
+type CrossJoinLeftData = Arc<Vec<RecordBatch>>;
+
+/// Execution plan of a cross join, combining every row of the left side with every row
+/// of the right side. The columns of the left side are followed by the ones of the
+/// right side.
+#[derive(Debug)]
+pub struct CrossJoinExec {
+    /// left side, which is collected
+    left: Arc<dyn ExecutionPlan>,
+    /// right side, whose batches are joined with the left side as they arrive
+    right: Arc<dyn ExecutionPlan>,
+    /// The schema once the join is applied
+    schema: SchemaRef,
+    /// The collected left side, shared by the partitions of the right side
+    left_data: Arc<Mutex<Option<CrossJoinLeftData>>>,
+}
+
+impl CrossJoinExec {
+    /// Creates a new [CrossJoinExec].
+    pub fn new(left: Arc<dyn ExecutionPlan>, right: Arc<dyn ExecutionPlan>) -> Self {
+        let fields = left
+            .schema()
+            .fields()
+            .iter()
+            .chain(right.schema().fields().iter())
+            .cloned()
+            .collect();
+        CrossJoinExec {
+            left,
+            right,
+            schema: Arc::new(Schema::new(fields)),
+            left_data: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// left (collected) side of the join
+    pub fn left(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.left
+    }
+
+    /// right side of the join
+    pub fn right(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.right
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for CrossJoinExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.left.clone(), self.right.clone()]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            2 => Ok(Arc::new(CrossJoinExec::new(
+                children[0].clone(),
+                children[1].clone(),
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "CrossJoinExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.right.output_partitioning()
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::UnspecifiedDistribution
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        // the left side is only collected once
+        let left_data = {
+            let mut left_data = self.left_data.lock().await;
+            match left_data.as_ref() {
+                Some(batches) => batches.clone(),
+                None => {
+                    let merge = MergeExec::new(self.left.clone());
+                    let batches =
+                        Arc::new(common::collect(merge.execute(0).await?).await?);
+                    *left_data = Some(batches.clone());
+                    batches
+                }
+            }
+        };
+
+        let right = self.right.execute(partition).await?;
+        Ok(Box::pin(CrossJoinStream {
+            schema: self.schema.clone(),
+            left_data,
+            right,
+            right_batch: None,
+        }))
+    }
+}
+
+/// Returns the batch combining every row of `left` with every row of `right`, the rows
+/// of `right` varying the fastest
+fn build_batch(
+    schema: &SchemaRef,
+    left: &RecordBatch,
+    right: &RecordBatch,
+) -> ArrowResult<RecordBatch> {
+    let left_rows = left.num_rows() as u32;
+    let right_rows = right.num_rows() as u32;
+    let left_indices = UInt32Array::from(
+        (0..left_rows)
+            .flat_map(|row| std::iter::repeat(row).take(right_rows as usize))
+            .collect::<Vec<_>>(),
+    );
+    let right_indices = UInt32Array::from(
+        (0..left_rows)
+            .flat_map(|_| 0..right_rows)
+            .collect::<Vec<_>>(),
+    );
+
+    let left_columns = left
+        .columns()
+        .iter()
+        .map(|column| take(column.as_ref(), &left_indices, None));
+    let right_columns = right
+        .columns()
+        .iter()
+        .map(|column| take(column.as_ref(), &right_indices, None));
+    let columns = left_columns
+        .chain(right_columns)
+        .collect::<ArrowResult<Vec<_>>>()?;
+    RecordBatch::try_new(schema.clone(), columns)
+}
+
+/// A stream joining the batches of the right side, as they arrive, with the batches of
+/// the left side
+struct CrossJoinStream {
+    /// Output schema
+    schema: SchemaRef,
+    /// the batches of the left side
+    left_data: CrossJoinLeftData,
+    /// right
+    right: SendableRecordBatchStream,
+    /// the batch of the right side being joined, along with the index of the next
+    /// batch of the left side to join it with
+    right_batch: Option<(RecordBatch, usize)>,
+}
+
+impl RecordBatchStream for CrossJoinStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl Stream for CrossJoinStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            // a batch is issued for each batch of the left side
+            if let Some((right_batch, left_index)) = &mut this.right_batch {
+                if let Some(left_batch) = this.left_data.get(*left_index) {
+                    *left_index += 1;
+                    return Poll::Ready(Some(build_batch(
+                        &this.schema,
+                        left_batch,
+                        right_batch,
+                    )));
+                }
+                this.right_batch = None;
+            }
+
+            match this.right.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(batch))) => this.right_batch = Some((batch, 0)),
+                other => return other,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::test::{build_table_i32, columns, format_batch};
+
+    #[tokio::test]
+    async fn cross_join() -> Result<()> {
+        // two batches on the left side
+        let left = vec![
+            build_table_i32(("a1", &vec![1]), ("b1", &vec![3]), ("c1", &vec![5])),
+            build_table_i32(("a1", &vec![2]), ("b1", &vec![4]), ("c1", &vec![6])),
+        ];
+        let left_schema = left[0].schema();
+        let left = Arc::new(MemoryExec::try_new(&vec![left], left_schema, None)?);
+        // two partitions on the right side
+        let right = vec![
+            vec![build_table_i32(
+                ("a2", &vec![10, 20]),
+                ("b2", &vec![40, 50]),
+                ("c2", &vec![70, 80]),
+            )],
+            vec![build_table_i32(
+                ("a2", &vec![30]),
+                ("b2", &vec![60]),
+                ("c2", &vec![90]),
+            )],
+        ];
+        let right_schema = right[0][0].schema();
+        let right = Arc::new(MemoryExec::try_new(&right, right_schema, None)?);
+
+        let join = CrossJoinExec::new(left, right);
+        assert_eq!(
+            columns(&join.schema()),
+            vec!["a1", "b1", "c1", "a2", "b2", "c2"]
+        );
+        assert_eq!(join.output_partitioning().partition_count(), 2);
+
+        let mut result = vec![];
+        for partition in 0..2 {
+            let batches = common::collect(join.execute(partition).await?).await?;
+            result.extend(batches.iter().flat_map(format_batch));
+        }
+
+        let expected = vec![
+            "1,3,5,10,40,70",
+            "1,3,5,20,50,80",
+            "2,4,6,10,40,70",
+            "2,4,6,20,50,80",
+            "1,3,5,30,60,90",
+            "2,4,6,30,60,90",
+        ];
+        assert_eq!(result, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cross_join_empty_left() -> Result<()> {
+        let left = build_table_i32(("a1", &vec![]), ("b1", &vec![]), ("c1", &vec![]));
+        let left_schema = left.schema();
+        let left = Arc::new(MemoryExec::try_new(&vec![vec![]], left_schema, None)?);
+        let right = build_table_i32(
+            ("a2", &vec![10, 20]),
+            ("b2", &vec![40, 50]),
+            ("c2", &vec![70, 80]),
+        );
+        let right_schema = right.schema();
+        let right =
+            Arc::new(MemoryExec::try_new(&vec![vec![right]], right_schema, None)?);
+
+        let join = CrossJoinExec::new(left, right);
+        let batches = common::collect(join.execute(0).await?).await?;
+        assert!(batches.is_empty());
+        Ok(())
+    }
+}
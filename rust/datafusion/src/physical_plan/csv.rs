@@ -127,15 +127,19 @@ pub struct CsvExec {
     projected_schema: SchemaRef,
     /// Batch size
     batch_size: usize,
+    /// Optional number of rows to read from each file
+    limit: Option<usize>,
 }
 
 impl CsvExec {
-    /// Create a new execution plan for reading a set of CSV files
+    /// Create a new execution plan for reading a set of CSV files, stopping after the
+    /// first `limit` rows of each file if specified
     pub fn try_new(
         path: &str,
         options: CsvReadOptions,
         projection: Option<Vec<usize>>,
         batch_size: usize,
+        limit: Option<usize>,
     ) -> Result<Self> {
         let file_extension = String::from(options.file_extension);
 
@@ -165,6 +169,7 @@ impl CsvExec {
             projection,
             projected_schema: Arc::new(projected_schema),
             batch_size,
+            limit,
         })
     }
 
@@ -226,6 +231,7 @@ impl ExecutionPlan for CsvExec {
             self.delimiter,
             &self.projection,
             self.batch_size,
+            self.limit,
         )?))
     }
 }
@@ -245,15 +251,18 @@ impl CsvStream {
         delimiter: Option<u8>,
         projection: &Option<Vec<usize>>,
         batch_size: usize,
+        limit: Option<usize>,
     ) -> Result<Self> {
         let file = File::open(filename)?;
+        // the line numbers of the bounds of the reader count the header
+        let bounds = limit.map(|limit| (0, limit + has_header as usize));
         let reader = csv::Reader::new(
             file,
             schema,
             has_header,
             delimiter,
             batch_size,
-            None,
+            bounds,
             projection.clone(),
         );
 
@@ -296,6 +305,7 @@ mod tests {
             CsvReadOptions::new().schema(&schema),
             Some(vec![0, 2, 4]),
             1024,
+            None,
         )?;
         assert_eq!(13, csv.schema.fields().len());
         assert_eq!(3, csv.projected_schema.fields().len());
@@ -311,14 +321,39 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn csv_exec_with_limit() -> Result<()> {
+        let schema = aggr_test_schema();
+        let testdata = arrow::util::test_util::arrow_test_data();
+        let filename = "aggregate_test_100.csv";
+        let path = format!("{}/csv/{}", testdata, filename);
+        let csv = CsvExec::try_new(
+            &path,
+            CsvReadOptions::new().schema(&schema),
+            None,
+            2,
+            Some(5),
+        )?;
+        let batches = common::collect(csv.execute(0).await?).await?;
+        // the reader stops after the first 5 rows of the file
+        let num_rows: Vec<usize> = batches.iter().map(|batch| batch.num_rows()).collect();
+        assert_eq!(vec![2, 2, 1], num_rows);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn csv_exec_without_projection() -> Result<()> {
         let schema = aggr_test_schema();
         let testdata = arrow::util::test_util::arrow_test_data();
         let filename = "aggregate_test_100.csv";
         let path = format!("{}/csv/{}", testdata, filename);
-        let csv =
-            CsvExec::try_new(&path, CsvReadOptions::new().schema(&schema), None, 1024)?;
+        let csv = CsvExec::try_new(
+            &path,
+            CsvReadOptions::new().schema(&schema),
+            None,
+            1024,
+            None,
+        )?;
         assert_eq!(13, csv.schema.fields().len());
         assert_eq!(13, csv.projected_schema.fields().len());
         assert_eq!(13, csv.schema().fields().len());
@@ -15,13 +15,15 @@
 // specific language governing permissions and limitations
 // under the License.
 
-//! Implementations for DISTINCT expressions, e.g. `COUNT(DISTINCT c)`
+//! Implementations for DISTINCT expressions, e.g. `COUNT(DISTINCT c)` and
+//! `ARRAY_AGG(DISTINCT c)`
 
 use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::sync::Arc;
 
+use arrow::compute::{sort_to_indices, take};
 use arrow::datatypes::{DataType, Field};
 
 use ahash::RandomState;
@@ -95,6 +97,7 @@ impl AggregateExpr for DistinctCount {
     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
         Ok(Box::new(DistinctCountAccumulator {
             values: HashSet::default(),
+            values_size: 0,
             data_types: self.input_data_types.clone(),
             count_data_type: self.data_type.clone(),
         }))
@@ -104,6 +107,9 @@ impl AggregateExpr for DistinctCount {
 #[derive(Debug)]
 struct DistinctCountAccumulator {
     values: HashSet<DistinctScalarValues, RandomState>,
+    /// Estimated number of bytes used by the values of `values`, kept up to date as
+    /// they are inserted
+    values_size: usize,
     data_types: Vec<DataType>,
     count_data_type: DataType,
 }
@@ -112,12 +118,14 @@ impl Accumulator for DistinctCountAccumulator {
     fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
         // If a row has a NULL, it is not included in the final count.
         if !values.iter().any(|v| v.is_null()) {
-            self.values.insert(DistinctScalarValues(
-                values
-                    .iter()
-                    .map(GroupByScalar::try_from)
-                    .collect::<Result<Vec<_>>>()?,
-            ));
+            let values = values
+                .iter()
+                .map(GroupByScalar::try_from)
+                .collect::<Result<Vec<_>>>()?;
+            let size = values.iter().map(GroupByScalar::size).sum::<usize>();
+            if self.values.insert(DistinctScalarValues(values)) {
+                self.values_size += size;
+            }
         }
 
         Ok(())
@@ -162,13 +170,11 @@ impl Accumulator for DistinctCountAccumulator {
             })
             .collect::<Vec<_>>();
 
-        self.values.iter().for_each(|distinct_values| {
-            distinct_values.0.iter().enumerate().for_each(
-                |(col_index, distinct_value)| {
-                    cols_vec[col_index].push(ScalarValue::from(distinct_value));
-                },
-            )
-        });
+        for distinct_values in &self.values {
+            for (col_index, distinct_value) in distinct_values.0.iter().enumerate() {
+                cols_vec[col_index].push(ScalarValue::try_from(distinct_value)?);
+            }
+        }
 
         Ok(cols_out)
     }
@@ -182,6 +188,148 @@ impl Accumulator for DistinctCountAccumulator {
             ))),
         }
     }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self.values.capacity() * std::mem::size_of::<DistinctScalarValues>()
+            + self.values_size
+    }
+}
+
+/// Expression for an ARRAY_AGG(DISTINCT) aggregation, the list of the distinct non-null
+/// values of its argument, in ascending order.
+#[derive(Debug)]
+pub struct DistinctArrayAgg {
+    /// Column name
+    name: String,
+    /// The DataType of the input argument
+    input_data_type: DataType,
+    /// The input argument
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl DistinctArrayAgg {
+    /// Create a new ARRAY_AGG(DISTINCT) aggregate function.
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        name: String,
+        input_data_type: DataType,
+    ) -> Self {
+        Self {
+            name,
+            input_data_type,
+            expr,
+        }
+    }
+}
+
+impl AggregateExpr for DistinctArrayAgg {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(
+            &self.name,
+            DataType::List(Box::new(Field::new(
+                "item",
+                self.input_data_type.clone(),
+                true,
+            ))),
+            true,
+        ))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "distinct_array_agg"),
+            DataType::List(Box::new(Field::new(
+                "item",
+                self.input_data_type.clone(),
+                true,
+            ))),
+            false,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(DistinctArrayAggAccumulator {
+            values: HashSet::default(),
+            values_size: 0,
+            data_type: self.input_data_type.clone(),
+        }))
+    }
+}
+
+#[derive(Debug)]
+struct DistinctArrayAggAccumulator {
+    values: HashSet<GroupByScalar, RandomState>,
+    /// Estimated number of bytes used by the values of `values`, kept up to date as
+    /// they are inserted
+    values_size: usize,
+    data_type: DataType,
+}
+
+impl Accumulator for DistinctArrayAggAccumulator {
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        // NULLs are not part of the list
+        if !values[0].is_null() {
+            let value = GroupByScalar::try_from(&values[0])?;
+            let size = value.size();
+            if self.values.insert(value) {
+                self.values_size += size;
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        match &states[0] {
+            ScalarValue::List(Some(values), _) => values
+                .iter()
+                .try_for_each(|value| self.update(&vec![value.clone()])),
+            ScalarValue::List(None, _) => Ok(()),
+            _ => Err(DataFusionError::Internal(
+                "Unexpected accumulator state".to_string(),
+            )),
+        }
+    }
+
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let values = self
+            .values
+            .iter()
+            .map(ScalarValue::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(vec![ScalarValue::List(
+            Some(values),
+            self.data_type.clone(),
+        )])
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        if self.values.is_empty() {
+            return Ok(ScalarValue::List(None, self.data_type.clone()));
+        }
+        let values = self
+            .values
+            .iter()
+            .map(ScalarValue::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let array = ScalarValue::iter_to_array(&values)?;
+        let sorted = take(array.as_ref(), &sort_to_indices(&array, None)?, None)?;
+        let values = (0..sorted.len())
+            .map(|i| ScalarValue::try_from_array(&sorted, i))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ScalarValue::List(Some(values), self.data_type.clone()))
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + (self.values.capacity() - self.values.len())
+                * std::mem::size_of::<GroupByScalar>()
+            + self.values_size
+    }
 }
 
 #[cfg(test)]
@@ -196,6 +344,8 @@ mod tests {
     use arrow::array::{Int32Builder, ListBuilder, UInt64Builder};
     use arrow::datatypes::DataType;
 
+    use crate::physical_plan::expressions::col;
+
     macro_rules! build_list {
         ($LISTS:expr, $BUILDER_TYPE:ident) => {{
             let mut builder = ListBuilder::new($BUILDER_TYPE::new(0));
@@ -551,4 +701,41 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn distinct_array_agg() -> Result<()> {
+        let agg = DistinctArrayAgg::new(
+            col("a"),
+            String::from("__col_name__"),
+            DataType::Int32,
+        );
+        let mut accumulator = agg.create_accumulator()?;
+        accumulator.update_batch(&vec![Arc::new(Int32Array::from(vec![
+            Some(3),
+            None,
+            Some(1),
+            Some(3),
+        ])) as ArrayRef])?;
+
+        // the state of another partition, with a value in common
+        let state = ScalarValue::List(
+            Some(vec![
+                ScalarValue::Int32(Some(2)),
+                ScalarValue::Int32(Some(1)),
+            ]),
+            DataType::Int32,
+        );
+        accumulator.merge(&vec![state])?;
+
+        let expected = ScalarValue::List(
+            Some(vec![
+                ScalarValue::Int32(Some(1)),
+                ScalarValue::Int32(Some(2)),
+                ScalarValue::Int32(Some(3)),
+            ]),
+            DataType::Int32,
+        );
+        assert_eq!(expected, accumulator.evaluate()?);
+        Ok(())
+    }
 }
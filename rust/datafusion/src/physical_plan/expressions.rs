@@ -17,9 +17,10 @@
 
 //! Defines physical expressions that can evaluated at runtime during query execution
 
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use super::ColumnarValue;
 use crate::error::{DataFusionError, Result};
@@ -1295,6 +1296,19 @@ fn order_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType>
         .or_else(|| temporal_coercion(lhs_type, rhs_type))
 }
 
+/// Coercion rules for the columns of set operations (e.g. UNION): the type that the
+/// values of both lhs and rhs can be casted to without losing information
+pub fn union_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    if lhs_type == rhs_type {
+        // same type => all good
+        return Some(lhs_type.clone());
+    }
+
+    numerical_coercion(lhs_type, rhs_type)
+        .or_else(|| string_coercion(lhs_type, rhs_type))
+        .or_else(|| dictionary_coercion(lhs_type, rhs_type))
+}
+
 /// Coercion rules for all binary operators. Returns the output type
 /// of applying `op` to an argument of `lhs_type` and `rhs_type`.
 fn common_binary_type(
@@ -1851,6 +1865,383 @@ pub fn is_not_null(arg: Arc<dyn PhysicalExpr>) -> Result<Arc<dyn PhysicalExpr>>
     Ok(Arc::new(IsNotNullExpr::new(arg)))
 }
 
+/// GROUPING expression: the bitmask of the grouping expressions aggregated in the
+/// grouping set of each row, computed from the index of the set
+#[derive(Debug)]
+pub struct GroupingExpr {
+    /// The index of the grouping set of the rows
+    grouping_id: Arc<dyn PhysicalExpr>,
+    /// The value of the expression in each grouping set
+    values: Vec<i32>,
+}
+
+impl GroupingExpr {
+    /// Create a new GROUPING expression, whose value is `values[i]` in the rows of the
+    /// grouping set of index `i`
+    pub fn new(grouping_id: Arc<dyn PhysicalExpr>, values: Vec<i32>) -> Self {
+        Self {
+            grouping_id,
+            values,
+        }
+    }
+}
+
+impl fmt::Display for GroupingExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GROUPING({}, {:?})", self.grouping_id, self.values)
+    }
+}
+
+impl PhysicalExpr for GroupingExpr {
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Int32)
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let grouping_id = self
+            .grouping_id
+            .evaluate(batch)?
+            .into_array(batch.num_rows());
+        let grouping_id = grouping_id
+            .as_any()
+            .downcast_ref::<UInt32Array>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(
+                    "GROUPING expects the index of a grouping set".to_string(),
+                )
+            })?;
+        let values = grouping_id
+            .iter()
+            .map(|id| id.and_then(|id| self.values.get(id as usize).copied()))
+            .collect::<Int32Array>();
+        Ok(ColumnarValue::Array(Arc::new(values)))
+    }
+}
+
+/// Collects the non null values of a list of literals into a hash set
+macro_rules! collect_set {
+    ($LIST:expr, $SCALAR:ident, $VALUE:ident => $KEY:expr) => {{
+        $LIST
+            .iter()
+            .filter_map(|value| match value {
+                ScalarValue::$SCALAR(Some($VALUE)) => $KEY,
+                _ => None,
+            })
+            .collect()
+    }};
+}
+
+/// Searches the values of an array in a hash set
+macro_rules! set_contains {
+    ($ARRAY:expr, $SET:expr, $ARRAY_TYPE:ident, $VALUE:ident => $KEY:expr) => {{
+        let array = $ARRAY.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+        array
+            .iter()
+            .map(|value| value.map(|$VALUE| $SET.contains($KEY)))
+            .collect::<Vec<_>>()
+    }};
+}
+
+/// Returns the bits of a float to hash it by, with `-0.0` equal to `0.0`
+macro_rules! float_bits {
+    ($VALUE:expr) => {
+        (if $VALUE == 0.0 { 0.0 } else { $VALUE }).to_bits()
+    };
+}
+
+/// The non null values of a list of literals, in a hash set of the type of the input
+/// of an IN list expression
+#[derive(Debug)]
+enum InSet {
+    Boolean(HashSet<bool>),
+    Int8(HashSet<i8>),
+    Int16(HashSet<i16>),
+    Int32(HashSet<i32>),
+    Int64(HashSet<i64>),
+    UInt8(HashSet<u8>),
+    UInt16(HashSet<u16>),
+    UInt32(HashSet<u32>),
+    UInt64(HashSet<u64>),
+    /// Floats are hashed by their bits, with `-0.0` equal to `0.0` and NaN equal to
+    /// nothing as for the comparison kernels
+    Float32(HashSet<u32>),
+    Float64(HashSet<u64>),
+    Utf8(HashSet<String>),
+    LargeUtf8(HashSet<String>),
+    Date32(HashSet<i32>),
+}
+
+impl InSet {
+    /// Creates the set of the values of `list` for an input of type `data_type`, or
+    /// `None` when the type is not supported
+    fn try_new(data_type: &DataType, list: &[ScalarValue]) -> Option<Self> {
+        Some(match data_type {
+            DataType::Boolean => {
+                InSet::Boolean(collect_set!(list, Boolean, value => Some(*value)))
+            }
+            DataType::Int8 => {
+                InSet::Int8(collect_set!(list, Int8, value => Some(*value)))
+            }
+            DataType::Int16 => {
+                InSet::Int16(collect_set!(list, Int16, value => Some(*value)))
+            }
+            DataType::Int32 => {
+                InSet::Int32(collect_set!(list, Int32, value => Some(*value)))
+            }
+            DataType::Int64 => {
+                InSet::Int64(collect_set!(list, Int64, value => Some(*value)))
+            }
+            DataType::UInt8 => {
+                InSet::UInt8(collect_set!(list, UInt8, value => Some(*value)))
+            }
+            DataType::UInt16 => {
+                InSet::UInt16(collect_set!(list, UInt16, value => Some(*value)))
+            }
+            DataType::UInt32 => {
+                InSet::UInt32(collect_set!(list, UInt32, value => Some(*value)))
+            }
+            DataType::UInt64 => {
+                InSet::UInt64(collect_set!(list, UInt64, value => Some(*value)))
+            }
+            DataType::Float32 => InSet::Float32(collect_set!(
+                list, Float32, value => Some(float_bits!(*value)).filter(|_| !value.is_nan())
+            )),
+            DataType::Float64 => InSet::Float64(collect_set!(
+                list, Float64, value => Some(float_bits!(*value)).filter(|_| !value.is_nan())
+            )),
+            DataType::Utf8 => {
+                InSet::Utf8(collect_set!(list, Utf8, value => Some(value.clone())))
+            }
+            DataType::LargeUtf8 => InSet::LargeUtf8(
+                collect_set!(list, LargeUtf8, value => Some(value.clone())),
+            ),
+            DataType::Date32(DateUnit::Day) => {
+                InSet::Date32(collect_set!(list, Date32, value => Some(*value)))
+            }
+            _ => return None,
+        })
+    }
+
+    /// Searches every row of `array` in the set
+    fn contains(&self, array: &ArrayRef) -> Vec<Option<bool>> {
+        match self {
+            InSet::Boolean(set) => set_contains!(array, set, BooleanArray, v => &v),
+            InSet::Int8(set) => set_contains!(array, set, Int8Array, v => &v),
+            InSet::Int16(set) => set_contains!(array, set, Int16Array, v => &v),
+            InSet::Int32(set) => set_contains!(array, set, Int32Array, v => &v),
+            InSet::Int64(set) => set_contains!(array, set, Int64Array, v => &v),
+            InSet::UInt8(set) => set_contains!(array, set, UInt8Array, v => &v),
+            InSet::UInt16(set) => set_contains!(array, set, UInt16Array, v => &v),
+            InSet::UInt32(set) => set_contains!(array, set, UInt32Array, v => &v),
+            InSet::UInt64(set) => set_contains!(array, set, UInt64Array, v => &v),
+            InSet::Float32(set) => {
+                set_contains!(array, set, Float32Array, v => &float_bits!(v))
+            }
+            InSet::Float64(set) => {
+                set_contains!(array, set, Float64Array, v => &float_bits!(v))
+            }
+            InSet::Utf8(set) => set_contains!(array, set, StringArray, v => v),
+            InSet::LargeUtf8(set) => set_contains!(array, set, LargeStringArray, v => v),
+            InSet::Date32(set) => set_contains!(array, set, Date32Array, v => &v),
+        }
+    }
+}
+
+/// How the list of an IN list expression is searched, decided on its first evaluation
+#[derive(Debug)]
+enum InListSearch {
+    /// A list of literals is searched in the hash set of its values, the result being
+    /// null instead of false when the list has a null
+    Set { set: InSet, has_null: bool },
+    /// Any other list is compared expression by expression
+    CompareEach,
+}
+
+/// IN list expression, e.g. `region IN ('us', 'eu')`. Follows the SQL semantics for
+/// nulls: the result is null when the value is null, or when the value is not found
+/// in a list that contains a null.
+#[derive(Debug)]
+pub struct InListExpr {
+    expr: Arc<dyn PhysicalExpr>,
+    list: Vec<Arc<dyn PhysicalExpr>>,
+    negated: bool,
+    /// How the list is searched, once it has been evaluated
+    search: Mutex<Option<Arc<InListSearch>>>,
+}
+
+impl InListExpr {
+    /// Create a new IN list expression
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        list: Vec<Arc<dyn PhysicalExpr>>,
+        negated: bool,
+    ) -> Self {
+        Self {
+            expr,
+            list,
+            negated,
+            search: Mutex::new(None),
+        }
+    }
+
+    /// Input expression
+    pub fn expr(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.expr
+    }
+
+    /// List to search in
+    pub fn list(&self) -> &[Arc<dyn PhysicalExpr>] {
+        &self.list
+    }
+
+    /// Whether this is a NOT IN expression
+    pub fn negated(&self) -> bool {
+        self.negated
+    }
+
+    /// Returns how the list is searched, deciding it on the first evaluation: a list
+    /// whose expressions all evaluate to scalars is a list of literals, whose values
+    /// are the same for every batch
+    fn search(
+        &self,
+        batch: &RecordBatch,
+        data_type: &DataType,
+    ) -> Result<Arc<InListSearch>> {
+        let mut cached = self.search.lock().unwrap();
+        if let Some(search) = cached.as_ref() {
+            return Ok(search.clone());
+        }
+        let literals = self
+            .list
+            .iter()
+            .map(|item| {
+                Ok(match item.evaluate(batch)? {
+                    ColumnarValue::Scalar(scalar) => Some(scalar),
+                    ColumnarValue::Array(_) => None,
+                })
+            })
+            .collect::<Result<Option<Vec<_>>>>()?;
+        let search = literals
+            .and_then(|literals| {
+                let has_null = literals.iter().any(|value| value.is_null());
+                InSet::try_new(data_type, &literals)
+                    .map(|set| InListSearch::Set { set, has_null })
+            })
+            .unwrap_or(InListSearch::CompareEach);
+        let search = Arc::new(search);
+        *cached = Some(search.clone());
+        Ok(search)
+    }
+
+    /// Compares every row of the input with every expression of the list, combining
+    /// the comparisons with a three-valued OR.
+    fn compare_each(&self, batch: &RecordBatch) -> Result<Vec<Option<bool>>> {
+        let mut found = vec![Some(false); batch.num_rows()];
+        for item in &self.list {
+            let eq = BinaryExpr::new(self.expr.clone(), Operator::Eq, item.clone())
+                .evaluate(batch)?
+                .into_array(batch.num_rows());
+            let eq = eq.as_any().downcast_ref::<BooleanArray>().unwrap();
+            found.iter_mut().zip(eq.iter()).for_each(|(found, eq)| {
+                *found = match (*found, eq) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (None, _) | (_, None) => None,
+                    _ => Some(false),
+                }
+            });
+        }
+        Ok(found)
+    }
+}
+
+impl fmt::Display for InListExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = self
+            .list
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.negated {
+            write!(f, "{} NOT IN ({})", self.expr, list)
+        } else {
+            write!(f, "{} IN ({})", self.expr, list)
+        }
+    }
+}
+
+impl PhysicalExpr for InListExpr {
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        if self.expr.nullable(input_schema)? {
+            return Ok(true);
+        }
+        for item in &self.list {
+            if item.nullable(input_schema)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let array = self.expr.evaluate(batch)?.into_array(batch.num_rows());
+
+        let found = match self.search(batch, array.data_type())?.as_ref() {
+            InListSearch::Set { set, has_null } => set
+                .contains(&array)
+                .into_iter()
+                .map(|found| match found {
+                    Some(false) if *has_null => None,
+                    found => found,
+                })
+                .collect(),
+            InListSearch::CompareEach => self.compare_each(batch)?,
+        };
+
+        let result = found
+            .into_iter()
+            .map(|found| found.map(|found| found != self.negated))
+            .collect::<Vec<_>>();
+        Ok(ColumnarValue::Array(Arc::new(BooleanArray::from(result))))
+    }
+}
+
+/// Create an IN list expression, coercing the input and the expressions of the list
+/// to a common type
+pub fn in_list(
+    expr: Arc<dyn PhysicalExpr>,
+    list: Vec<Arc<dyn PhysicalExpr>>,
+    negated: bool,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let expr_type = expr.data_type(input_schema)?;
+    let mut common_type = expr_type.clone();
+    for item in &list {
+        let item_type = item.data_type(input_schema)?;
+        common_type = order_coercion(&common_type, &item_type).ok_or_else(|| {
+            DataFusionError::Plan(format!(
+                "'{:?} IN ({:?})' can't be evaluated because there isn't a common type to coerce the types to",
+                expr_type, item_type
+            ))
+        })?;
+    }
+
+    let expr = cast(expr, input_schema, common_type.clone())?;
+    let list = list
+        .into_iter()
+        .map(|item| cast(item, input_schema, common_type.clone()))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Arc::new(InListExpr::new(expr, list, negated)))
+}
+
 /// The CASE expression is similar to a series of nested if/else and there are two forms that
 /// can be used. The first form consists of a series of boolean "when" expressions with
 /// corresponding "then" expressions, and an optional "else" expression.
@@ -3639,6 +4030,179 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn grouping_op() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("id", DataType::UInt32, false)]);
+        let id = UInt32Array::from(vec![2, 0, 1, 2]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(id)])?;
+
+        // the bitmasks of `GROUPING(a, b)` in the grouping sets of `ROLLUP(a, b)`
+        let expr = GroupingExpr::new(col("id"), vec![0, 1, 3]);
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .expect("failed to downcast to Int32Array");
+
+        let expected = &Int32Array::from(vec![3, 0, 1, 3]);
+
+        assert_eq!(expected, result);
+
+        Ok(())
+    }
+
+    // evaluates `a [NOT] IN (list)` on a single column batch
+    fn in_list_eval(
+        array: ArrayRef,
+        list: Vec<Arc<dyn PhysicalExpr>>,
+        negated: bool,
+    ) -> Result<Vec<Option<bool>>> {
+        let schema = Schema::new(vec![Field::new("a", array.data_type().clone(), true)]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![array])?;
+        let expr = in_list(col("a"), list, negated, &schema)?;
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        Ok(result
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .expect("failed to downcast to BooleanArray")
+            .iter()
+            .collect())
+    }
+
+    #[test]
+    fn in_list_int() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int64Array::from(vec![Some(1), Some(2), None]));
+
+        // expression: "a IN (1, 3)"
+        let list = vec![
+            lit(ScalarValue::Int64(Some(1))),
+            lit(ScalarValue::Int64(Some(3))),
+        ];
+        let result = in_list_eval(a.clone(), list.clone(), false)?;
+        assert_eq!(result, vec![Some(true), Some(false), None]);
+
+        // expression: "a NOT IN (1, 3)"
+        let result = in_list_eval(a, list, true)?;
+        assert_eq!(result, vec![Some(false), Some(true), None]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn in_list_coerced() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+
+        // expression: "a IN (2.0, 3.5)"
+        let list = vec![
+            lit(ScalarValue::Float64(Some(2.0))),
+            lit(ScalarValue::Float64(Some(3.5))),
+        ];
+        let result = in_list_eval(a, list, false)?;
+        assert_eq!(result, vec![Some(false), Some(true), Some(false)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn in_list_utf8_with_null() -> Result<()> {
+        let a: ArrayRef = Arc::new(StringArray::from(vec![Some("a"), Some("b"), None]));
+
+        // expression: "a IN ('a', NULL)"
+        let list = vec![
+            lit(ScalarValue::Utf8(Some("a".to_string()))),
+            lit(ScalarValue::Utf8(None)),
+        ];
+        let result = in_list_eval(a.clone(), list.clone(), false)?;
+        assert_eq!(result, vec![Some(true), None, None]);
+
+        // expression: "a NOT IN ('a', NULL)"
+        let result = in_list_eval(a, list, true)?;
+        assert_eq!(result, vec![Some(false), None, None]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn in_list_float() -> Result<()> {
+        let a: ArrayRef = Arc::new(Float64Array::from(vec![-0.0, 1.5, f64::NAN]));
+
+        // expression: "a IN (0.0, NaN)"
+        let list = vec![
+            lit(ScalarValue::Float64(Some(0.0))),
+            lit(ScalarValue::Float64(Some(f64::NAN))),
+        ];
+        let result = in_list_eval(a, list, false)?;
+        assert_eq!(result, vec![Some(true), Some(false), Some(false)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn in_list_set_is_built_once() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+        let expr = InListExpr::new(
+            col("a"),
+            vec![
+                lit(ScalarValue::Int64(Some(1))),
+                lit(ScalarValue::Int64(Some(3))),
+            ],
+            false,
+        );
+        for values in vec![vec![1, 2], vec![3, 4]] {
+            let batch = RecordBatch::try_new(
+                Arc::new(schema.clone()),
+                vec![Arc::new(Int64Array::from(values))],
+            )?;
+            let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            let result = result
+                .as_any()
+                .downcast_ref::<BooleanArray>()
+                .expect("failed to downcast to BooleanArray");
+            assert_eq!(result, &BooleanArray::from(vec![true, false]));
+        }
+        // the hash set built on the first evaluation is kept for the next ones
+        let search = expr.search.lock().unwrap().clone();
+        assert!(matches!(
+            search.as_deref(),
+            Some(InListSearch::Set {
+                has_null: false,
+                ..
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn in_list_columns() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Int64, true),
+        ]);
+        let a = Int64Array::from(vec![Some(1), Some(2), Some(3)]);
+        let b = Int64Array::from(vec![Some(1), Some(5), None]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(a), Arc::new(b)],
+        )?;
+
+        // expression: "a IN (b, 2)"
+        let list = vec![col("b"), lit(ScalarValue::Int64(Some(2)))];
+        let expr = in_list(col("a"), list, false, &schema)?;
+        assert_eq!(expr.to_string(), "a IN (b, 2)");
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .expect("failed to downcast to BooleanArray");
+        assert_eq!(
+            result,
+            &BooleanArray::from(vec![Some(true), Some(true), None])
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn case_with_expr() -> Result<()> {
         let batch = case_test_batch()?;
@@ -67,6 +67,12 @@ pub enum Signature {
     Exact(Vec<DataType>),
     /// fixed number of arguments of arbitrary types
     Any(usize),
+    /// arbitrary but non-zero number of arguments of arbitrary types
+    // A function such as `grouping` is `VariadicAny`
+    VariadicAny,
+    /// one of several signatures, the first one the arguments can be coerced to
+    // A function such as `substr`, of 2 or 3 arguments, is `OneOf`
+    OneOf(Vec<Signature>),
 }
 
 /// Scalar function
@@ -124,6 +130,8 @@ pub enum BuiltinScalarFunction {
     Upper,
     /// trim
     Trim,
+    /// substr, also known as substring
+    Substr,
     /// to_timestamp
     ToTimestamp,
     /// construct an array from columns
@@ -166,6 +174,8 @@ impl FromStr for BuiltinScalarFunction {
             "concat" => BuiltinScalarFunction::Concat,
             "lower" => BuiltinScalarFunction::Lower,
             "trim" => BuiltinScalarFunction::Trim,
+            "substr" => BuiltinScalarFunction::Substr,
+            "substring" => BuiltinScalarFunction::Substr,
             "upper" => BuiltinScalarFunction::Upper,
             "to_timestamp" => BuiltinScalarFunction::ToTimestamp,
             "array" => BuiltinScalarFunction::Array,
@@ -234,6 +244,16 @@ pub fn return_type(
                 ));
             }
         }),
+        BuiltinScalarFunction::Substr => Ok(match arg_types[0] {
+            DataType::LargeUtf8 => DataType::LargeUtf8,
+            DataType::Utf8 => DataType::Utf8,
+            _ => {
+                // this error is internal as `data_types` should have captured this.
+                return Err(DataFusionError::Internal(
+                    "The substr function can only accept strings.".to_string(),
+                ));
+            }
+        }),
         BuiltinScalarFunction::Upper => Ok(match arg_types[0] {
             DataType::LargeUtf8 => DataType::LargeUtf8,
             DataType::Utf8 => DataType::Utf8,
@@ -306,6 +326,14 @@ pub fn create_physical_expr(
                 other,
             ))),
         },
+        BuiltinScalarFunction::Substr => |args| match args[0].data_type() {
+            DataType::Utf8 => Ok(Arc::new(string_expressions::substr::<i32>(args)?)),
+            DataType::LargeUtf8 => Ok(Arc::new(string_expressions::substr::<i64>(args)?)),
+            other => Err(DataFusionError::Internal(format!(
+                "Unsupported data type {:?} for function substr",
+                other,
+            ))),
+        },
         BuiltinScalarFunction::Upper => |args| match args[0].data_type() {
             DataType::Utf8 => Ok(Arc::new(string_expressions::upper::<i32>(args)?)),
             DataType::LargeUtf8 => Ok(Arc::new(string_expressions::upper::<i64>(args)?)),
@@ -354,6 +382,22 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
         BuiltinScalarFunction::Trim => {
             Signature::Uniform(1, vec![DataType::Utf8, DataType::LargeUtf8])
         }
+        // substr(string, start [, length])
+        BuiltinScalarFunction::Substr => Signature::OneOf(
+            [DataType::Utf8, DataType::LargeUtf8]
+                .iter()
+                .flat_map(|string| {
+                    vec![
+                        Signature::Exact(vec![string.clone(), DataType::Int64]),
+                        Signature::Exact(vec![
+                            string.clone(),
+                            DataType::Int64,
+                            DataType::Int64,
+                        ]),
+                    ]
+                })
+                .collect(),
+        ),
         BuiltinScalarFunction::ToTimestamp => Signature::Uniform(1, vec![DataType::Utf8]),
         BuiltinScalarFunction::Array => {
             Signature::Variadic(array_expressions::SUPPORTED_ARRAY_TYPES.to_vec())
@@ -17,15 +17,77 @@
 
 //! Defines scalars used to construct groups, ex. in GROUP BY clauses.
 
-use std::convert::{From, TryFrom};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
 
 use crate::error::{DataFusionError, Result};
 use crate::scalar::ScalarValue;
 
-/// Enumeration of types that can be used in a GROUP BY expression (all primitives except
-/// for floating point numerics)
+macro_rules! ordered_float {
+    ($NAME:ident, $FLOAT:ident, $BITS:ident) => {
+        /// A float with a total order, so that it can be used as a key: all the NaNs
+        /// are equal to each other and greater than any other value, and -0.0 equals 0.0
+        #[derive(Debug, Clone, Copy)]
+        pub(crate) struct $NAME(pub $FLOAT);
+
+        impl $NAME {
+            /// The bits of the value, equal for values that are equal
+            pub fn to_bits(self) -> $BITS {
+                if self.0.is_nan() {
+                    $FLOAT::NAN.to_bits()
+                } else if self.0 == 0.0 {
+                    0
+                } else {
+                    self.0.to_bits()
+                }
+            }
+        }
+
+        impl PartialEq for $NAME {
+            fn eq(&self, other: &Self) -> bool {
+                self.to_bits() == other.to_bits()
+            }
+        }
+
+        impl Eq for $NAME {}
+
+        impl Hash for $NAME {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.to_bits().hash(state)
+            }
+        }
+
+        impl PartialOrd for $NAME {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $NAME {
+            fn cmp(&self, other: &Self) -> Ordering {
+                match (self.0.is_nan(), other.0.is_nan()) {
+                    (true, true) => Ordering::Equal,
+                    (true, false) => Ordering::Greater,
+                    (false, true) => Ordering::Less,
+                    // neither is NaN
+                    (false, false) => self.0.partial_cmp(&other.0).unwrap(),
+                }
+            }
+        }
+    };
+}
+
+ordered_float!(OrderedFloat32, f32, u32);
+ordered_float!(OrderedFloat64, f64, u64);
+
+/// Enumeration of types that can be used in a GROUP BY expression. A NULL key is
+/// represented by the absence of a `GroupByScalar`, i.e. `None`.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub(crate) enum GroupByScalar {
+    Boolean(bool),
+    Float32(OrderedFloat32),
+    Float64(OrderedFloat64),
     UInt8(u8),
     UInt16(u16),
     UInt32(u32),
@@ -35,6 +97,30 @@ pub(crate) enum GroupByScalar {
     Int32(i32),
     Int64(i64),
     Utf8(Box<String>),
+    LargeUtf8(Box<String>),
+    Date32(i32),
+    Date64(i64),
+    TimestampSecond(i64),
+    TimestampMillisecond(i64),
+    TimestampMicrosecond(i64),
+    TimestampNanosecond(i64),
+    /// The value, precision and scale of a decimal, boxed to keep the keys small
+    Decimal(Box<(i128, usize, usize)>),
+}
+
+impl GroupByScalar {
+    /// Estimated number of bytes of memory used by the value, its heap allocations
+    /// included
+    pub fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + match self {
+                GroupByScalar::Utf8(v) | GroupByScalar::LargeUtf8(v) => {
+                    std::mem::size_of::<String>() + v.capacity()
+                }
+                GroupByScalar::Decimal(_) => std::mem::size_of::<(i128, usize, usize)>(),
+                _ => 0,
+            }
+    }
 }
 
 impl TryFrom<&ScalarValue> for GroupByScalar {
@@ -42,6 +128,9 @@ impl TryFrom<&ScalarValue> for GroupByScalar {
 
     fn try_from(scalar_value: &ScalarValue) -> Result<Self> {
         Ok(match scalar_value {
+            ScalarValue::Boolean(Some(v)) => GroupByScalar::Boolean(*v),
+            ScalarValue::Float32(Some(v)) => GroupByScalar::Float32(OrderedFloat32(*v)),
+            ScalarValue::Float64(Some(v)) => GroupByScalar::Float64(OrderedFloat64(*v)),
             ScalarValue::Int8(Some(v)) => GroupByScalar::Int8(*v),
             ScalarValue::Int16(Some(v)) => GroupByScalar::Int16(*v),
             ScalarValue::Int32(Some(v)) => GroupByScalar::Int32(*v),
@@ -51,7 +140,25 @@ impl TryFrom<&ScalarValue> for GroupByScalar {
             ScalarValue::UInt32(Some(v)) => GroupByScalar::UInt32(*v),
             ScalarValue::UInt64(Some(v)) => GroupByScalar::UInt64(*v),
             ScalarValue::Utf8(Some(v)) => GroupByScalar::Utf8(Box::new(v.clone())),
-            ScalarValue::Int8(None)
+            ScalarValue::LargeUtf8(Some(v)) => {
+                GroupByScalar::LargeUtf8(Box::new(v.clone()))
+            }
+            ScalarValue::Date32(Some(v)) => GroupByScalar::Date32(*v),
+            ScalarValue::Date64(Some(v)) => GroupByScalar::Date64(*v),
+            ScalarValue::TimestampSecond(Some(v)) => GroupByScalar::TimestampSecond(*v),
+            ScalarValue::TimestampMillisecond(Some(v)) => {
+                GroupByScalar::TimestampMillisecond(*v)
+            }
+            ScalarValue::TimestampMicrosecond(Some(v)) => {
+                GroupByScalar::TimestampMicrosecond(*v)
+            }
+            ScalarValue::TimestampNanosecond(Some(v)) => {
+                GroupByScalar::TimestampNanosecond(*v)
+            }
+            ScalarValue::Boolean(None)
+            | ScalarValue::Float32(None)
+            | ScalarValue::Float64(None)
+            | ScalarValue::Int8(None)
             | ScalarValue::Int16(None)
             | ScalarValue::Int32(None)
             | ScalarValue::Int64(None)
@@ -59,7 +166,14 @@ impl TryFrom<&ScalarValue> for GroupByScalar {
             | ScalarValue::UInt16(None)
             | ScalarValue::UInt32(None)
             | ScalarValue::UInt64(None)
-            | ScalarValue::Utf8(None) => {
+            | ScalarValue::Utf8(None)
+            | ScalarValue::LargeUtf8(None)
+            | ScalarValue::Date32(None)
+            | ScalarValue::Date64(None)
+            | ScalarValue::TimestampSecond(None)
+            | ScalarValue::TimestampMillisecond(None)
+            | ScalarValue::TimestampMicrosecond(None)
+            | ScalarValue::TimestampNanosecond(None) => {
                 return Err(DataFusionError::Internal(format!(
                     "Cannot convert a ScalarValue holding NULL ({:?})",
                     scalar_value
@@ -75,9 +189,14 @@ impl TryFrom<&ScalarValue> for GroupByScalar {
     }
 }
 
-impl From<&GroupByScalar> for ScalarValue {
-    fn from(group_by_scalar: &GroupByScalar) -> Self {
-        match group_by_scalar {
+impl TryFrom<&GroupByScalar> for ScalarValue {
+    type Error = DataFusionError;
+
+    fn try_from(group_by_scalar: &GroupByScalar) -> Result<Self> {
+        Ok(match group_by_scalar {
+            GroupByScalar::Boolean(v) => ScalarValue::Boolean(Some(*v)),
+            GroupByScalar::Float32(v) => ScalarValue::Float32(Some(v.0)),
+            GroupByScalar::Float64(v) => ScalarValue::Float64(Some(v.0)),
             GroupByScalar::Int8(v) => ScalarValue::Int8(Some(*v)),
             GroupByScalar::Int16(v) => ScalarValue::Int16(Some(*v)),
             GroupByScalar::Int32(v) => ScalarValue::Int32(Some(*v)),
@@ -87,7 +206,25 @@ impl From<&GroupByScalar> for ScalarValue {
             GroupByScalar::UInt32(v) => ScalarValue::UInt32(Some(*v)),
             GroupByScalar::UInt64(v) => ScalarValue::UInt64(Some(*v)),
             GroupByScalar::Utf8(v) => ScalarValue::Utf8(Some(v.to_string())),
-        }
+            GroupByScalar::LargeUtf8(v) => ScalarValue::LargeUtf8(Some(v.to_string())),
+            GroupByScalar::Date32(v) => ScalarValue::Date32(Some(*v)),
+            GroupByScalar::Date64(v) => ScalarValue::Date64(Some(*v)),
+            GroupByScalar::TimestampSecond(v) => ScalarValue::TimestampSecond(Some(*v)),
+            GroupByScalar::TimestampMillisecond(v) => {
+                ScalarValue::TimestampMillisecond(Some(*v))
+            }
+            GroupByScalar::TimestampMicrosecond(v) => {
+                ScalarValue::TimestampMicrosecond(Some(*v))
+            }
+            GroupByScalar::TimestampNanosecond(v) => {
+                ScalarValue::TimestampNanosecond(Some(*v))
+            }
+            GroupByScalar::Decimal(_) => {
+                return Err(DataFusionError::NotImplemented(
+                    "Decimals cannot be converted to a ScalarValue".to_string(),
+                ))
+            }
+        })
     }
 }
 
@@ -116,14 +253,14 @@ mod tests {
     #[test]
     fn from_scalar_unsupported() -> Result<()> {
         // Use any ScalarValue type not supported by GroupByScalar.
-        let scalar_value = ScalarValue::Float32(Some(1.1));
+        let scalar_value = ScalarValue::Binary(Some(vec![1]));
         let result = GroupByScalar::try_from(&scalar_value);
 
         match result {
             Err(DataFusionError::Internal(error_message)) => assert_eq!(
                 error_message,
                 String::from(
-                    "Cannot convert a ScalarValue with associated DataType Float32"
+                    "Cannot convert a ScalarValue with associated DataType Binary"
                 )
             ),
             _ => panic!("Unexpected result"),
@@ -132,8 +269,32 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn ordered_floats() {
+        assert_eq!(OrderedFloat64(0.0), OrderedFloat64(-0.0));
+        assert_eq!(OrderedFloat64(f64::NAN), OrderedFloat64(-f64::NAN));
+        assert_ne!(OrderedFloat64(1.0), OrderedFloat64(-1.0));
+        assert!(OrderedFloat64(f64::NAN) > OrderedFloat64(f64::INFINITY));
+        assert!(OrderedFloat32(-1.0) < OrderedFloat32(-0.0));
+        assert_eq!(
+            OrderedFloat32(f32::NAN).to_bits(),
+            OrderedFloat32(0.0 / 0.0).to_bits()
+        );
+    }
+
+    #[test]
+    fn decimal_to_scalar_unsupported() {
+        let decimal = GroupByScalar::Decimal(Box::new((12345, 10, 2)));
+        match ScalarValue::try_from(&decimal) {
+            Err(DataFusionError::NotImplemented(_)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
     #[test]
     fn size_of_group_by_scalar() {
         assert_eq!(std::mem::size_of::<GroupByScalar>(), 16);
+        // a NULL key costs no more than any other
+        assert_eq!(std::mem::size_of::<Option<GroupByScalar>>(), 16);
     }
 }
@@ -16,26 +16,44 @@
 // under the License.
 
 //! Defines the execution plan for the hash aggregate operation
+//!
+//! The groups of an aggregate with a `GROUP BY` are kept in memory up to a memory
+//! limit. Past that limit, the states of their accumulators are spilled to disk,
+//! partitioned by group, and each partition is merged once the whole input has been
+//! consumed.
+//!
+//! An aggregate can also group its input by several grouping sets, i.e. subsets of
+//! its group expressions, in a single pass: each row is aggregated into one group per
+//! set, the expressions missing from the set being NULL, and the groups are tagged
+//! with the index of their set in the [`GROUPING_ID_COLUMN`] column.
 
 use std::any::Any;
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::mem::size_of;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use futures::{
+    channel::mpsc,
     stream::{Stream, StreamExt},
-    Future,
+    Future, SinkExt,
 };
 
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{Accumulator, AggregateExpr};
 use crate::physical_plan::{Distribution, ExecutionPlan, Partitioning, PhysicalExpr};
+use crate::scalar::ScalarValue;
 
-use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
 use arrow::error::{ArrowError, Result as ArrowResult};
 use arrow::record_batch::RecordBatch;
 use arrow::{
     array::{
-        ArrayRef, Int16Array, Int32Array, Int64Array, Int8Array, StringArray,
+        ArrayRef, BooleanArray, Date32Array, Date64Array, DecimalArray, DecimalBuilder,
+        Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
+        LargeStringArray, StringArray, TimestampMicrosecondArray,
+        TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray,
         UInt16Array, UInt32Array, UInt64Array, UInt8Array,
     },
     compute,
@@ -43,11 +61,15 @@ use arrow::{
 use pin_project_lite::pin_project;
 
 use super::{
-    common, expressions::Column, group_scalar::GroupByScalar, hash_join::create_key,
+    common,
+    expressions::Column,
+    group_scalar::{GroupByScalar, OrderedFloat32, OrderedFloat64},
+    hash_join::{create_key, decode_dictionaries},
     RecordBatchStream, SendableRecordBatchStream,
 };
 use ahash::RandomState;
 use hashbrown::HashMap;
+use tempfile::NamedTempFile;
 
 use async_trait::async_trait;
 
@@ -58,32 +80,52 @@ pub enum AggregateMode {
     Partial,
     /// Final aggregate that produces a single partition of output
     Final,
+    /// Final aggregate that works on pre-partitioned data, all the rows of a group
+    /// being in the same partition. It produces one partition of output per input
+    /// partition.
+    FinalPartitioned,
 }
 
+/// Name of the column with the index of the grouping set of each group of an
+/// aggregate with grouping sets
+pub const GROUPING_ID_COLUMN: &str = "__grouping_id";
+
 /// Hash aggregate execution plan
 #[derive(Debug)]
 pub struct HashAggregateExec {
     mode: AggregateMode,
     group_expr: Vec<(Arc<dyn PhysicalExpr>, String)>,
+    /// The grouping sets, whether each group expression is part of the set, `None`
+    /// for a single set of all the group expressions
+    grouping_sets: Option<Vec<Vec<bool>>>,
     aggr_expr: Vec<Arc<dyn AggregateExpr>>,
     input: Arc<dyn ExecutionPlan>,
     schema: SchemaRef,
+    /// Number of bytes of groups kept in memory before their states are spilled to disk
+    memory_limit: usize,
 }
 
 fn create_schema(
     input_schema: &Schema,
     group_expr: &Vec<(Arc<dyn PhysicalExpr>, String)>,
+    grouping_sets: Option<&Vec<Vec<bool>>>,
     aggr_expr: &Vec<Arc<dyn AggregateExpr>>,
     mode: AggregateMode,
 ) -> Result<Schema> {
-    let mut fields = Vec::with_capacity(group_expr.len() + aggr_expr.len());
-    for (expr, name) in group_expr {
+    let mut fields = Vec::with_capacity(group_expr.len() + aggr_expr.len() + 1);
+    for (i, (expr, name)) in group_expr.iter().enumerate() {
+        // the group expressions missing from a grouping set are NULL in its groups
+        let missing_from_a_set =
+            grouping_sets.map_or(false, |sets| sets.iter().any(|set| !set[i]));
         fields.push(Field::new(
             name,
             expr.data_type(&input_schema)?,
-            expr.nullable(&input_schema)?,
+            expr.nullable(&input_schema)? || missing_from_a_set,
         ))
     }
+    if grouping_sets.is_some() {
+        fields.push(Field::new(GROUPING_ID_COLUMN, DataType::UInt32, false))
+    }
 
     match mode {
         AggregateMode::Partial => {
@@ -92,7 +134,7 @@ fn create_schema(
                 fields.extend(expr.state_fields()?.iter().cloned())
             }
         }
-        AggregateMode::Final => {
+        AggregateMode::Final | AggregateMode::FinalPartitioned => {
             // in final mode, the field with the final result of the accumulator
             for expr in aggr_expr {
                 fields.push(expr.field()?)
@@ -111,18 +153,68 @@ impl HashAggregateExec {
         aggr_expr: Vec<Arc<dyn AggregateExpr>>,
         input: Arc<dyn ExecutionPlan>,
     ) -> Result<Self> {
-        let schema = create_schema(&input.schema(), &group_expr, &aggr_expr, mode)?;
+        Self::try_new_impl(mode, group_expr, None, aggr_expr, input)
+    }
+
+    /// Create a new hash aggregate execution plan grouping its input by each of the
+    /// `grouping_sets`, whether each group expression is part of the set. Its output
+    /// has a [`GROUPING_ID_COLUMN`] column after the group columns with the index of
+    /// the set of each group.
+    pub fn try_new_with_grouping_sets(
+        mode: AggregateMode,
+        group_expr: Vec<(Arc<dyn PhysicalExpr>, String)>,
+        grouping_sets: Vec<Vec<bool>>,
+        aggr_expr: Vec<Arc<dyn AggregateExpr>>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Result<Self> {
+        if grouping_sets.is_empty()
+            || grouping_sets
+                .iter()
+                .any(|set| set.len() != group_expr.len())
+        {
+            return Err(DataFusionError::Internal(format!(
+                "HashAggregateExec expects grouping sets of {} group expressions",
+                group_expr.len()
+            )));
+        }
+        Self::try_new_impl(mode, group_expr, Some(grouping_sets), aggr_expr, input)
+    }
+
+    fn try_new_impl(
+        mode: AggregateMode,
+        group_expr: Vec<(Arc<dyn PhysicalExpr>, String)>,
+        grouping_sets: Option<Vec<Vec<bool>>>,
+        aggr_expr: Vec<Arc<dyn AggregateExpr>>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Result<Self> {
+        let schema = create_schema(
+            &input.schema(),
+            &group_expr,
+            grouping_sets.as_ref(),
+            &aggr_expr,
+            mode,
+        )?;
 
         let schema = Arc::new(schema);
 
         Ok(HashAggregateExec {
             mode,
             group_expr,
+            grouping_sets,
             aggr_expr,
             input,
             schema,
+            memory_limit: usize::MAX,
         })
     }
+
+    /// Limits the memory used to keep the groups of the aggregate, whose states are
+    /// spilled to disk past `memory_limit` bytes. The limit applies to each partition
+    /// of the aggregate on its own. The memory is not limited by default.
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
 }
 
 #[async_trait]
@@ -142,7 +234,9 @@ impl ExecutionPlan for HashAggregateExec {
 
     fn required_child_distribution(&self) -> Distribution {
         match &self.mode {
-            AggregateMode::Partial => Distribution::UnspecifiedDistribution,
+            AggregateMode::Partial | AggregateMode::FinalPartitioned => {
+                Distribution::UnspecifiedDistribution
+            }
             AggregateMode::Final => Distribution::SinglePartition,
         }
     }
@@ -156,7 +250,7 @@ impl ExecutionPlan for HashAggregateExec {
         let input = self.input.execute(partition).await?;
         let group_expr = self.group_expr.iter().map(|x| x.0.clone()).collect();
 
-        if self.group_expr.is_empty() {
+        if self.group_expr.is_empty() && self.grouping_sets.is_none() {
             Ok(Box::pin(HashAggregateStream::new(
                 self.mode,
                 self.schema.clone(),
@@ -164,12 +258,23 @@ impl ExecutionPlan for HashAggregateExec {
                 input,
             )))
         } else {
+            // the states of the groups are spilled along with their group values
+            let spill_schema = create_schema(
+                &self.input.schema(),
+                &self.group_expr,
+                self.grouping_sets.as_ref(),
+                &self.aggr_expr,
+                AggregateMode::Partial,
+            )?;
             Ok(Box::pin(GroupedHashAggregateStream::new(
                 self.mode,
                 self.schema.clone(),
                 group_expr,
+                self.grouping_sets.clone(),
                 self.aggr_expr.clone(),
                 input,
+                self.memory_limit,
+                Arc::new(spill_schema),
             )))
         }
     }
@@ -179,12 +284,16 @@ impl ExecutionPlan for HashAggregateExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(HashAggregateExec::try_new(
-                self.mode,
-                self.group_expr.clone(),
-                self.aggr_expr.clone(),
-                children[0].clone(),
-            )?)),
+            1 => Ok(Arc::new(
+                HashAggregateExec::try_new_impl(
+                    self.mode,
+                    self.group_expr.clone(),
+                    self.grouping_sets.clone(),
+                    self.aggr_expr.clone(),
+                    children[0].clone(),
+                )?
+                .with_memory_limit(self.memory_limit),
+            )),
             _ => Err(DataFusionError::Internal(
                 "HashAggregateExec wrong number of children".to_string(),
             )),
@@ -221,21 +330,69 @@ pin_project! {
     struct GroupedHashAggregateStream {
         schema: SchemaRef,
         #[pin]
-        output: futures::channel::oneshot::Receiver<ArrowResult<RecordBatch>>,
-        finished: bool,
+        output: mpsc::Receiver<ArrowResult<RecordBatch>>,
     }
 }
 
+/// Number of partitions the spilled states are split into by group, each partition
+/// being merged separately
+const SPILL_PARTITIONS: usize = 16;
+
+/// Number of times the states of a partition are spilled again while being merged,
+/// past which they are merged regardless of the memory limit, e.g. when the states
+/// of a single group exceed it
+const MAX_SPILL_DEPTH: usize = 4;
+
+/// Estimated number of bytes of memory used by a new group with the key `key`: the
+/// key, the group values built from it and its accumulators
+fn group_memory_size(key: &[u8], accumulator_set: &AccumulatorSet) -> usize {
+    2 * key.len()
+        + size_of::<(
+            Vec<u8>,
+            (Box<[Option<GroupByScalar>]>, AccumulatorSet, Vec<u32>),
+        )>()
+        + accumulators_size(accumulator_set)
+}
+
+/// Estimated number of bytes of memory used by the accumulators of a group, whose
+/// states may grow with the rows of the group
+fn accumulators_size(accumulator_set: &AccumulatorSet) -> usize {
+    accumulator_set
+        .iter()
+        .map(|accumulator| accumulator.size())
+        .sum()
+}
+
+#[allow(clippy::too_many_arguments)]
 fn group_aggregate_batch(
     mode: &AggregateMode,
     group_expr: &Vec<Arc<dyn PhysicalExpr>>,
+    grouping_sets: Option<&Vec<Vec<bool>>>,
     aggr_expr: &Vec<Arc<dyn AggregateExpr>>,
     batch: RecordBatch,
     mut accumulators: Accumulators,
     aggregate_expressions: &Vec<Vec<Arc<dyn PhysicalExpr>>>,
+    mut memory_size: Option<&mut usize>,
 ) -> Result<Accumulators> {
-    // evaluate the grouping expressions
-    let group_values = evaluate(group_expr, &batch)?;
+    // evaluate the grouping expressions, the dictionaries being grouped by the values
+    // they encode
+    let group_values = decode_dictionaries(evaluate(group_expr, &batch)?)?;
+
+    // the values of the group expressions of each grouping set
+    let set_values = match grouping_sets {
+        Some(grouping_sets) => grouping_sets
+            .iter()
+            .map(|set| {
+                group_values
+                    .iter()
+                    .zip(set)
+                    .filter(|(_, grouped)| **grouped)
+                    .map(|(values, _)| values.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>(),
+        None => vec![group_values.clone()],
+    };
 
     // evaluate the aggregation expressions.
     // We could evaluate them after the `take`, but since we need to evaluate all
@@ -245,16 +402,18 @@ fn group_aggregate_batch(
     // create vector large enough to hold the grouping key
     // this is an optimization to avoid allocating `key` on every row.
     // it will be overwritten on every iteration of the loop below
-    let mut group_by_values = Vec::with_capacity(group_values.len());
-    for _ in 0..group_values.len() {
-        group_by_values.push(GroupByScalar::UInt32(0));
+    // the groups of grouping sets also have the index of their set as last value
+    let num_group_values = group_values.len() + grouping_sets.is_some() as usize;
+    let mut group_by_values = Vec::with_capacity(num_group_values);
+    for _ in 0..num_group_values {
+        group_by_values.push(None);
     }
 
     let mut group_by_values = group_by_values.into_boxed_slice();
 
     let mut key = Vec::with_capacity(group_values.len());
 
-    // 1.1 construct the key from the group values
+    // 1.1 construct the key from the group values of each grouping set
     // 1.2 construct the mapping key if it does not exist
     // 1.3 add the row' index to `indices`
 
@@ -262,35 +421,60 @@ fn group_aggregate_batch(
     create_accumulators(aggr_expr).map_err(DataFusionError::into_arrow_external_error)?;
 
     for row in 0..batch.num_rows() {
-        // 1.1
-        create_key(&group_values, row, &mut key)
-            .map_err(DataFusionError::into_arrow_external_error)?;
+        for (set_index, values) in set_values.iter().enumerate() {
+            // 1.1
+            create_key(values, row, &mut key)
+                .map_err(DataFusionError::into_arrow_external_error)?;
+            if grouping_sets.is_some() {
+                // the index keeps apart the groups of sets with the same values
+                key.extend_from_slice(&(set_index as u32).to_le_bytes());
+            }
 
-        accumulators
-            .raw_entry_mut()
-            .from_key(&key)
-            // 1.3
-            .and_modify(|_, (_, _, v)| v.push(row as u32))
-            // 1.2
-            .or_insert_with(|| {
-                // We can safely unwrap here as we checked we can create an accumulator before
-                let accumulator_set = create_accumulators(aggr_expr).unwrap();
-                let _ = create_group_by_values(&group_values, row, &mut group_by_values);
-                (
-                    key.clone(),
-                    (group_by_values.clone(), accumulator_set, vec![row as u32]),
-                )
-            });
+            accumulators
+                .raw_entry_mut()
+                .from_key(&key)
+                // 1.3
+                .and_modify(|_, (_, _, v)| v.push(row as u32))
+                // 1.2
+                .or_insert_with(|| {
+                    // We can safely unwrap here as we checked we can create an accumulator before
+                    let accumulator_set = create_accumulators(aggr_expr).unwrap();
+                    let _ =
+                        create_group_by_values(&group_values, row, &mut group_by_values);
+                    if let Some(grouping_sets) = grouping_sets {
+                        let set = &grouping_sets[set_index];
+                        for (value, grouped) in group_by_values.iter_mut().zip(set) {
+                            if !grouped {
+                                *value = None;
+                            }
+                        }
+                        group_by_values[group_values.len()] =
+                            Some(GroupByScalar::UInt32(set_index as u32));
+                    }
+                    if let Some(memory_size) = memory_size.as_deref_mut() {
+                        *memory_size += group_memory_size(&key, &accumulator_set);
+                    }
+                    (
+                        key.clone(),
+                        (group_by_values.clone(), accumulator_set, vec![row as u32]),
+                    )
+                });
+        }
     }
 
-    // 2.1 for each key
+    // 2.1 for each key with rows in the batch
     // 2.2 for each aggregation
     // 2.3 `take` from each of its arrays the keys' values
     // 2.4 update / merge the accumulator with the values
     // 2.5 clear indices
+    // 2.6 add the growth of the accumulators to the memory of the groups
     accumulators
         .iter_mut()
+        .filter(|(_, (_, _, indices))| !indices.is_empty())
         .try_for_each(|(_, (_, accumulator_set, indices))| {
+            let size_before = memory_size
+                .as_ref()
+                .map(|_| accumulators_size(accumulator_set));
             // 2.2
             accumulator_set
                 .iter_mut()
@@ -314,7 +498,7 @@ fn group_aggregate_batch(
                 })
                 .try_for_each(|(accumulator, values)| match mode {
                     AggregateMode::Partial => accumulator.update_batch(&values),
-                    AggregateMode::Final => {
+                    AggregateMode::Final | AggregateMode::FinalPartitioned => {
                         // note: the aggregation here is over states, not values, thus the merge
                         accumulator.merge_batch(&values)
                     }
@@ -323,81 +507,313 @@ fn group_aggregate_batch(
                 .and({
                     indices.clear();
                     Ok(())
-                })
+                })?;
+            // 2.6
+            if let (Some(memory_size), Some(size_before)) =
+                (memory_size.as_deref_mut(), size_before)
+            {
+                *memory_size =
+                    *memory_size + accumulators_size(accumulator_set) - size_before;
+            }
+            Ok(())
         })?;
     Ok(accumulators)
 }
 
+/// The groups of an aggregate, kept in memory up to `memory_limit` bytes past which
+/// their states are spilled to disk, partitioned by group
+struct SpillableAccumulators {
+    accumulators: Accumulators,
+    /// Estimated number of bytes used by the groups in memory
+    memory_size: usize,
+    memory_limit: usize,
+    /// Schema of the spilled states, the group values followed by the state fields
+    spill_schema: SchemaRef,
+    /// Number of group values of the groups, their grouping set included
+    num_group_columns: usize,
+    /// Hashes the keys of the groups to their spill partitions
+    random_state: RandomState,
+    /// Spill files of each partition
+    spills: Vec<Vec<NamedTempFile>>,
+}
+
+impl SpillableAccumulators {
+    fn new(
+        memory_limit: usize,
+        spill_schema: SchemaRef,
+        num_group_columns: usize,
+    ) -> Self {
+        Self {
+            accumulators: Accumulators::default(),
+            memory_size: 0,
+            memory_limit,
+            spill_schema,
+            num_group_columns,
+            random_state: RandomState::new(),
+            spills: (0..SPILL_PARTITIONS).map(|_| vec![]).collect(),
+        }
+    }
+
+    /// Whether some states were spilled to disk
+    fn has_spilled(&self) -> bool {
+        self.spills.iter().any(|spills| !spills.is_empty())
+    }
+
+    /// Updates or merges the groups with `batch`, spilling them if they exceed the
+    /// memory limit
+    async fn aggregate_batch(
+        &mut self,
+        mode: &AggregateMode,
+        group_expr: &Vec<Arc<dyn PhysicalExpr>>,
+        grouping_sets: Option<&Vec<Vec<bool>>>,
+        aggr_expr: &Vec<Arc<dyn AggregateExpr>>,
+        batch: RecordBatch,
+        aggregate_expressions: &Vec<Vec<Arc<dyn PhysicalExpr>>>,
+    ) -> Result<()> {
+        self.accumulators = group_aggregate_batch(
+            mode,
+            group_expr,
+            grouping_sets,
+            aggr_expr,
+            batch,
+            std::mem::take(&mut self.accumulators),
+            aggregate_expressions,
+            // the memory is only estimated when it is limited
+            if self.memory_limit < usize::MAX {
+                Some(&mut self.memory_size)
+            } else {
+                None
+            },
+        )?;
+        if self.memory_size > self.memory_limit {
+            self.spill().await?;
+        }
+        Ok(())
+    }
+
+    /// Adds the group of each empty grouping set, the grand totals, that is not in
+    /// memory, with the initial states of the accumulators, so that they have a row
+    /// even without input rows
+    fn add_empty_set_groups(
+        &mut self,
+        grouping_sets: &[Vec<bool>],
+        num_group_expr: usize,
+        aggr_expr: &Vec<Arc<dyn AggregateExpr>>,
+    ) -> Result<()> {
+        for (set_index, set) in grouping_sets.iter().enumerate() {
+            if set.iter().any(|grouped| *grouped) {
+                continue;
+            }
+            // the key of a group without group values is the index of its set
+            let key = (set_index as u32).to_le_bytes().to_vec();
+            if !self.accumulators.contains_key(&key) {
+                let mut group_by_values = vec![None; num_group_expr];
+                group_by_values.push(Some(GroupByScalar::UInt32(set_index as u32)));
+                let accumulator_set = create_accumulators(aggr_expr)?;
+                self.memory_size += group_memory_size(&key, &accumulator_set);
+                self.accumulators.insert(
+                    key,
+                    (group_by_values.into_boxed_slice(), accumulator_set, vec![]),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Spills the states of the groups in memory to one file per partition
+    async fn spill(&mut self) -> Result<()> {
+        let mut partitions = (0..SPILL_PARTITIONS)
+            .map(|_| Accumulators::default())
+            .collect::<Vec<_>>();
+        for (key, group) in self.accumulators.drain() {
+            let mut hasher = self.random_state.build_hasher();
+            key.hash(&mut hasher);
+            let partition = (hasher.finish() % SPILL_PARTITIONS as u64) as usize;
+            partitions[partition].insert(key, group);
+        }
+        for (partition, accumulators) in partitions.iter().enumerate() {
+            if !accumulators.is_empty() {
+                let batch = create_batch_from_map(
+                    &AggregateMode::Partial,
+                    accumulators,
+                    self.num_group_columns,
+                    &self.spill_schema,
+                )?;
+                self.spills[partition].push(
+                    common::spill_batches(vec![batch], self.spill_schema.clone()).await?,
+                );
+            }
+        }
+        self.memory_size = 0;
+        Ok(())
+    }
+}
+
+/// Aggregates the batches of `input` and sends the groups to `output`, in a single
+/// batch unless some groups were spilled to disk, in which case one batch is sent per
+/// spill partition
+#[allow(clippy::too_many_arguments)]
 async fn compute_grouped_hash_aggregate(
     mode: AggregateMode,
     schema: SchemaRef,
     group_expr: Vec<Arc<dyn PhysicalExpr>>,
+    grouping_sets: Option<Vec<Vec<bool>>>,
     aggr_expr: Vec<Arc<dyn AggregateExpr>>,
     mut input: SendableRecordBatchStream,
-) -> ArrowResult<RecordBatch> {
+    memory_limit: usize,
+    spill_schema: SchemaRef,
+    output: &mut mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
     // the expressions to evaluate the batch, one vec of expressions per aggregation
-    let aggregate_expressions = aggregate_expressions(&aggr_expr, &mode)
-        .map_err(DataFusionError::into_arrow_external_error)?;
+    let aggregate_expressions = aggregate_expressions(&aggr_expr, &mode)?;
 
     // mapping key -> (set of accumulators, indices of the key in the batch)
     // * the indexes are updated at each row
     // * the accumulators are updated at the end of each batch
     // * the indexes are `clear`ed at the end of each batch
-    //let mut accumulators: Accumulators = FnvHashMap::default();
+    // * the accumulators are spilled when they exceed the memory limit
+
+    // the groups of grouping sets have the index of their set as last group value
+    let num_group_columns = group_expr.len() + grouping_sets.is_some() as usize;
 
     // iterate over all input batches and update the accumulators
-    let mut accumulators = Accumulators::default();
+    let mut accumulators =
+        SpillableAccumulators::new(memory_limit, spill_schema.clone(), num_group_columns);
     while let Some(batch) = input.next().await {
-        let batch = batch?;
-        accumulators = group_aggregate_batch(
-            &mode,
-            &group_expr,
-            &aggr_expr,
-            batch,
-            accumulators,
-            &aggregate_expressions,
-        )
-        .map_err(DataFusionError::into_arrow_external_error)?;
+        accumulators
+            .aggregate_batch(
+                &mode,
+                &group_expr,
+                grouping_sets.as_ref(),
+                &aggr_expr,
+                batch?,
+                &aggregate_expressions,
+            )
+            .await?;
+    }
+    if let (AggregateMode::Partial, Some(grouping_sets)) = (&mode, &grouping_sets) {
+        // merged in the final aggregate, the empty states of the partitions give the
+        // grand totals of an empty input
+        accumulators.add_empty_set_groups(grouping_sets, group_expr.len(), &aggr_expr)?;
     }
 
-    create_batch_from_map(&mode, &accumulators, group_expr.len(), &schema)
+    // the spilled states are merged by the group columns of the spill schema, the
+    // index of the grouping set included
+    let merge_group_expr = spill_schema.fields()[..num_group_columns]
+        .iter()
+        .map(|field| Arc::new(Column::new(field.name())) as Arc<dyn PhysicalExpr>)
+        .collect::<Vec<_>>();
+    let merge_expressions = aggregate_expressions(&aggr_expr, &AggregateMode::Final)?;
+
+    // the spill files of the partitions left to merge, with their number of spills
+    let mut partitions = vec![];
+    let mut depth = 0;
+    loop {
+        if accumulators.has_spilled() {
+            accumulators.spill().await?;
+            partitions.extend(
+                accumulators
+                    .spills
+                    .into_iter()
+                    .filter(|spills| !spills.is_empty())
+                    .map(|spills| (spills, depth + 1)),
+            );
+        } else {
+            let batch = create_batch_from_map(
+                &mode,
+                &accumulators.accumulators,
+                num_group_columns,
+                &schema,
+            );
+            if output.send(batch).await.is_err() {
+                // the plan is being torn down
+                break;
+            }
+        }
+
+        let (spills, partition_depth) = match partitions.pop() {
+            Some(partition) => partition,
+            None => break,
+        };
+        depth = partition_depth;
+        let memory_limit = if depth < MAX_SPILL_DEPTH {
+            memory_limit
+        } else {
+            usize::MAX
+        };
+        accumulators = SpillableAccumulators::new(
+            memory_limit,
+            spill_schema.clone(),
+            num_group_columns,
+        );
+        for spill in &spills {
+            let mut batches = common::read_spilled_batches(spill)?;
+            while let Some(batch) = batches.next().await {
+                // note: the spilled batches are states, thus the merge
+                accumulators
+                    .aggregate_batch(
+                        &AggregateMode::Final,
+                        &merge_group_expr,
+                        None,
+                        &aggr_expr,
+                        batch?,
+                        &merge_expressions,
+                    )
+                    .await?;
+            }
+        }
+    }
+    Ok(())
 }
 
 impl GroupedHashAggregateStream {
     /// Create a new HashAggregateStream
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         mode: AggregateMode,
         schema: SchemaRef,
         group_expr: Vec<Arc<dyn PhysicalExpr>>,
+        grouping_sets: Option<Vec<Vec<bool>>>,
         aggr_expr: Vec<Arc<dyn AggregateExpr>>,
         input: SendableRecordBatchStream,
+        memory_limit: usize,
+        spill_schema: SchemaRef,
     ) -> Self {
-        let (tx, rx) = futures::channel::oneshot::channel();
+        let (mut sender, receiver) = mpsc::channel(1);
 
         let schema_clone = schema.clone();
         tokio::spawn(async move {
-            let result = compute_grouped_hash_aggregate(
+            if let Err(e) = compute_grouped_hash_aggregate(
                 mode,
                 schema_clone,
                 group_expr,
+                grouping_sets,
                 aggr_expr,
                 input,
+                memory_limit,
+                spill_schema,
+                &mut sender,
             )
-            .await;
-            tx.send(result)
+            .await
+            {
+                // If send fails, plan being torn down, no place to send the error
+                sender.send(Err(e.into_arrow_external_error())).await.ok();
+            }
         });
 
         GroupedHashAggregateStream {
             schema,
-            output: rx,
-            finished: false,
+            output: receiver,
         }
     }
 }
 
 type AccumulatorSet = Vec<Box<dyn Accumulator>>;
-type Accumulators =
-    HashMap<Vec<u8>, (Box<[GroupByScalar]>, AccumulatorSet, Vec<u32>), RandomState>;
+type Accumulators = HashMap<
+    Vec<u8>,
+    (Box<[Option<GroupByScalar>]>, AccumulatorSet, Vec<u32>),
+    RandomState,
+>;
 
 impl Stream for GroupedHashAggregateStream {
     type Item = ArrowResult<RecordBatch>;
@@ -406,27 +822,7 @@ impl Stream for GroupedHashAggregateStream {
         self: std::pin::Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        if self.finished {
-            return Poll::Ready(None);
-        }
-
-        // is the output ready?
-        let this = self.project();
-        let output_poll = this.output.poll(cx);
-
-        match output_poll {
-            Poll::Ready(result) => {
-                *this.finished = true;
-
-                // check for error in receiving channel and unwrap actual result
-                let result = match result {
-                    Err(e) => Err(ArrowError::ExternalError(Box::new(e))), // error receiving
-                    Ok(result) => result,
-                };
-                Poll::Ready(Some(result))
-            }
-            Poll::Pending => Poll::Pending,
-        }
+        self.project().output.poll_next(cx)
     }
 }
 
@@ -484,7 +880,7 @@ fn aggregate_expressions(
             Ok(aggr_expr.iter().map(|agg| agg.expressions()).collect())
         }
         // in this mode, we build the merge expressions of the aggregation
-        AggregateMode::Final => Ok(aggr_expr
+        AggregateMode::Final | AggregateMode::FinalPartitioned => Ok(aggr_expr
             .iter()
             .map(|agg| merge_expressions(agg))
             .collect::<Result<Vec<_>>>()?),
@@ -580,7 +976,7 @@ fn aggregate_batch(
                 AggregateMode::Partial => {
                     accum.update_batch(values)?;
                 }
-                AggregateMode::Final => {
+                AggregateMode::Final | AggregateMode::FinalPartitioned => {
                     accum.merge_batch(values)?;
                 }
             }
@@ -648,52 +1044,83 @@ fn create_batch_from_map(
     num_group_expr: usize,
     output_schema: &Schema,
 ) -> ArrowResult<RecordBatch> {
-    // 1. for each key
-    // 2. create single-row ArrayRef with all group expressions
-    // 3. create single-row ArrayRef with all aggregate states or values
-    // 4. collect all in a vector per key of vec<ArrayRef>, vec[i][j]
-    // 5. concatenate the arrays over the second index [j] into a single vec<ArrayRef>.
+    if accumulators.is_empty() {
+        return common::create_batch_empty(output_schema);
+    }
+
+    // 1. for each group expression, create the array of the values of all keys
+    // 2. for each key, create single-row ArrayRef with all aggregate states or values
+    // 3. collect all in a vector per key of vec<ArrayRef>, vec[i][j]
+    // 4. concatenate the arrays over the second index [j] into a single vec<ArrayRef>.
+    let mut columns = (0..num_group_expr)
+        .map(|i| {
+            let values = accumulators
+                .iter()
+                .map(|(_, (group_by_values, _, _))| &group_by_values[i]);
+            create_group_by_array(values, output_schema.field(i).data_type())
+        })
+        .collect::<Result<Vec<_>>>()
+        .map_err(DataFusionError::into_arrow_external_error)?;
+
     let arrays = accumulators
         .iter()
-        .map(|(_, (group_by_values, accumulator_set, _))| {
-            // 2.
-            let mut groups = (0..num_group_expr)
-                .map(|i| match &group_by_values[i] {
-                    GroupByScalar::Int8(n) => {
-                        Arc::new(Int8Array::from(vec![*n])) as ArrayRef
-                    }
-                    GroupByScalar::Int16(n) => Arc::new(Int16Array::from(vec![*n])),
-                    GroupByScalar::Int32(n) => Arc::new(Int32Array::from(vec![*n])),
-                    GroupByScalar::Int64(n) => Arc::new(Int64Array::from(vec![*n])),
-                    GroupByScalar::UInt8(n) => Arc::new(UInt8Array::from(vec![*n])),
-                    GroupByScalar::UInt16(n) => Arc::new(UInt16Array::from(vec![*n])),
-                    GroupByScalar::UInt32(n) => Arc::new(UInt32Array::from(vec![*n])),
-                    GroupByScalar::UInt64(n) => Arc::new(UInt64Array::from(vec![*n])),
-                    GroupByScalar::Utf8(str) => {
-                        Arc::new(StringArray::from(vec![&***str]))
-                    }
-                })
-                .collect::<Vec<ArrayRef>>();
+        // 2.
+        .map(|(_, (_, accumulator_set, _))| finalize_aggregation(accumulator_set, mode))
+        // 3.
+        .collect::<Result<Vec<Vec<ArrayRef>>>>()
+        .map_err(DataFusionError::into_arrow_external_error)?;
 
-            // 3.
-            groups.extend(
-                finalize_aggregation(accumulator_set, mode)
-                    .map_err(DataFusionError::into_arrow_external_error)?,
-            );
+    // 4.
+    columns.extend(concatenate(arrays)?);
+    RecordBatch::try_new(Arc::new(output_schema.to_owned()), columns)
+}
 
-            Ok(groups)
-        })
-        // 4.
-        .collect::<ArrowResult<Vec<Vec<ArrayRef>>>>()?;
+/// Creates the array of type `data_type` of the group by values `values`, `None`
+/// being NULL
+fn create_group_by_array<'a>(
+    values: impl Iterator<Item = &'a Option<GroupByScalar>>,
+    data_type: &DataType,
+) -> Result<ArrayRef> {
+    // there is no decimal `ScalarValue`, so decimals are built directly
+    if let DataType::Decimal(precision, scale) = data_type {
+        let mut builder = DecimalBuilder::new(0, *precision, *scale);
+        for value in values {
+            match value {
+                Some(GroupByScalar::Decimal(decimal)) => {
+                    builder.append_value(decimal.0)?
+                }
+                None => builder.append_null()?,
+                Some(other) => {
+                    return Err(DataFusionError::Internal(format!(
+                        "Unexpected group by value {:?} for a decimal column",
+                        other
+                    )))
+                }
+            }
+        }
+        return Ok(Arc::new(builder.finish()));
+    }
 
-    let batch = if !arrays.is_empty() {
-        // 5.
-        let columns = concatenate(arrays)?;
-        RecordBatch::try_new(Arc::new(output_schema.to_owned()), columns)?
-    } else {
-        common::create_batch_empty(output_schema)?
+    // the values of dictionaries are grouped by the values they encode, and the
+    // timestamps are grouped without their timezone
+    let scalar_type = match data_type {
+        DataType::Dictionary(_, value_type) => value_type.as_ref().clone(),
+        DataType::Timestamp(unit, _) => DataType::Timestamp(unit.clone(), None),
+        other => other.clone(),
     };
-    Ok(batch)
+    let null = ScalarValue::try_from(&scalar_type)?;
+    let values = values
+        .map(|value| match value {
+            Some(value) => ScalarValue::try_from(value),
+            None => Ok(null.clone()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let array = ScalarValue::iter_to_array(&values)?;
+    if array.data_type() == data_type {
+        Ok(array)
+    } else {
+        Ok(compute::cast(&array, data_type)?)
+    }
 }
 
 fn create_accumulators(
@@ -725,7 +1152,7 @@ fn finalize_aggregation(
                 .collect::<Result<Vec<_>>>()?;
             Ok(a.iter().flatten().cloned().collect::<Vec<_>>())
         }
-        AggregateMode::Final => {
+        AggregateMode::Final | AggregateMode::FinalPartitioned => {
             // merge the state to the final value
             accumulators
                 .iter()
@@ -735,50 +1162,107 @@ fn finalize_aggregation(
     }
 }
 
-/// Create a Box<[GroupByScalar]> for the group by values
+/// Create a Box<[Option<GroupByScalar>]> for the group by values, `None` being NULL
 pub(crate) fn create_group_by_values(
     group_by_keys: &[ArrayRef],
     row: usize,
-    vec: &mut Box<[GroupByScalar]>,
+    vec: &mut Box<[Option<GroupByScalar>]>,
 ) -> Result<()> {
     for i in 0..group_by_keys.len() {
         let col = &group_by_keys[i];
-        match col.data_type() {
+        if col.is_null(row) {
+            vec[i] = None;
+            continue;
+        }
+        vec[i] = Some(match col.data_type() {
+            DataType::Boolean => {
+                let array = col.as_any().downcast_ref::<BooleanArray>().unwrap();
+                GroupByScalar::Boolean(array.value(row))
+            }
+            DataType::Float32 => {
+                let array = col.as_any().downcast_ref::<Float32Array>().unwrap();
+                GroupByScalar::Float32(OrderedFloat32(array.value(row)))
+            }
+            DataType::Float64 => {
+                let array = col.as_any().downcast_ref::<Float64Array>().unwrap();
+                GroupByScalar::Float64(OrderedFloat64(array.value(row)))
+            }
             DataType::UInt8 => {
                 let array = col.as_any().downcast_ref::<UInt8Array>().unwrap();
-                vec[i] = GroupByScalar::UInt8(array.value(row))
+                GroupByScalar::UInt8(array.value(row))
             }
             DataType::UInt16 => {
                 let array = col.as_any().downcast_ref::<UInt16Array>().unwrap();
-                vec[i] = GroupByScalar::UInt16(array.value(row))
+                GroupByScalar::UInt16(array.value(row))
             }
             DataType::UInt32 => {
                 let array = col.as_any().downcast_ref::<UInt32Array>().unwrap();
-                vec[i] = GroupByScalar::UInt32(array.value(row))
+                GroupByScalar::UInt32(array.value(row))
             }
             DataType::UInt64 => {
                 let array = col.as_any().downcast_ref::<UInt64Array>().unwrap();
-                vec[i] = GroupByScalar::UInt64(array.value(row))
+                GroupByScalar::UInt64(array.value(row))
             }
             DataType::Int8 => {
                 let array = col.as_any().downcast_ref::<Int8Array>().unwrap();
-                vec[i] = GroupByScalar::Int8(array.value(row))
+                GroupByScalar::Int8(array.value(row))
             }
             DataType::Int16 => {
                 let array = col.as_any().downcast_ref::<Int16Array>().unwrap();
-                vec[i] = GroupByScalar::Int16(array.value(row))
+                GroupByScalar::Int16(array.value(row))
             }
             DataType::Int32 => {
                 let array = col.as_any().downcast_ref::<Int32Array>().unwrap();
-                vec[i] = GroupByScalar::Int32(array.value(row))
+                GroupByScalar::Int32(array.value(row))
             }
             DataType::Int64 => {
                 let array = col.as_any().downcast_ref::<Int64Array>().unwrap();
-                vec[i] = GroupByScalar::Int64(array.value(row))
+                GroupByScalar::Int64(array.value(row))
             }
             DataType::Utf8 => {
                 let array = col.as_any().downcast_ref::<StringArray>().unwrap();
-                vec[i] = GroupByScalar::Utf8(Box::new(array.value(row).into()))
+                GroupByScalar::Utf8(Box::new(array.value(row).into()))
+            }
+            DataType::LargeUtf8 => {
+                let array = col.as_any().downcast_ref::<LargeStringArray>().unwrap();
+                GroupByScalar::LargeUtf8(Box::new(array.value(row).into()))
+            }
+            DataType::Date32(_) => {
+                let array = col.as_any().downcast_ref::<Date32Array>().unwrap();
+                GroupByScalar::Date32(array.value(row))
+            }
+            DataType::Date64(_) => {
+                let array = col.as_any().downcast_ref::<Date64Array>().unwrap();
+                GroupByScalar::Date64(array.value(row))
+            }
+            DataType::Timestamp(TimeUnit::Second, _) => {
+                let array = col.as_any().downcast_ref::<TimestampSecondArray>().unwrap();
+                GroupByScalar::TimestampSecond(array.value(row))
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
+                let array = col
+                    .as_any()
+                    .downcast_ref::<TimestampMillisecondArray>()
+                    .unwrap();
+                GroupByScalar::TimestampMillisecond(array.value(row))
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                let array = col
+                    .as_any()
+                    .downcast_ref::<TimestampMicrosecondArray>()
+                    .unwrap();
+                GroupByScalar::TimestampMicrosecond(array.value(row))
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+                let array = col
+                    .as_any()
+                    .downcast_ref::<TimestampNanosecondArray>()
+                    .unwrap();
+                GroupByScalar::TimestampNanosecond(array.value(row))
+            }
+            DataType::Decimal(precision, scale) => {
+                let array = col.as_any().downcast_ref::<DecimalArray>().unwrap();
+                GroupByScalar::Decimal(Box::new((array.value(row), *precision, *scale)))
             }
             _ => {
                 // This is internal because we should have caught this before.
@@ -786,7 +1270,7 @@ pub(crate) fn create_group_by_values(
                     "Unsupported GROUP BY data type".to_string(),
                 ));
             }
-        }
+        })
     }
     Ok(())
 }
@@ -794,13 +1278,15 @@ pub(crate) fn create_group_by_values(
 #[cfg(test)]
 mod tests {
 
-    use arrow::array::Float64Array;
+    use arrow::array::{Array, Float64Array};
 
     use super::*;
     use crate::physical_plan::common;
     use crate::physical_plan::expressions::{col, Avg};
 
+    use crate::physical_plan::memory::MemoryExec;
     use crate::physical_plan::merge::MergeExec;
+    use crate::physical_plan::repartition::RepartitionExec;
 
     /// some mock data to aggregates
     fn some_data() -> (Arc<Schema>, Vec<RecordBatch>) {
@@ -895,6 +1381,273 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn aggregate_final_partitioned() -> Result<()> {
+        let (schema, batches) = some_data();
+        let input = Arc::new(MemoryExec::try_new(&vec![batches], schema, None)?);
+
+        let groups: Vec<(Arc<dyn PhysicalExpr>, String)> =
+            vec![(col("a"), "a".to_string())];
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Avg::new(
+            col("b"),
+            "AVG(b)".to_string(),
+            DataType::Float64,
+        ))];
+
+        let partial_aggregate = Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            groups.clone(),
+            aggregates.clone(),
+            input,
+        )?);
+
+        // all the partial states of a group are in the same partition
+        let repartition = Arc::new(RepartitionExec::try_new(
+            partial_aggregate,
+            Partitioning::Hash(vec![col("a")], 3),
+        )?);
+
+        let final_aggregate = HashAggregateExec::try_new(
+            AggregateMode::FinalPartitioned,
+            vec![(col("a"), "a".to_string())],
+            aggregates,
+            repartition,
+        )?;
+        assert_eq!(3, final_aggregate.output_partitioning().partition_count());
+
+        let mut rows = vec![];
+        for partition in 0..3 {
+            let stream = final_aggregate.execute(partition).await?;
+            for batch in common::collect(stream).await? {
+                rows.extend(crate::test::format_batch(&batch));
+            }
+        }
+        rows.sort();
+
+        assert_eq!(
+            rows,
+            vec![
+                "2,1.0",
+                "3,2.3333333333333335", // 3, (2 + 3 + 2) / 3
+                "4,3.6666666666666665"  // 4, (3 + 4 + 4) / 3
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn aggregate_with_memory_limit() -> Result<()> {
+        let (schema, batches) = some_data();
+        let input = Arc::new(MemoryExec::try_new(&vec![batches], schema, None)?);
+
+        let groups: Vec<(Arc<dyn PhysicalExpr>, String)> =
+            vec![(col("a"), "a".to_string())];
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Avg::new(
+            col("b"),
+            "AVG(b)".to_string(),
+            DataType::Float64,
+        ))];
+
+        // the groups are spilled after each batch, and merged partition by partition
+        let partial_aggregate = Arc::new(
+            HashAggregateExec::try_new(
+                AggregateMode::Partial,
+                groups.clone(),
+                aggregates.clone(),
+                input,
+            )?
+            .with_memory_limit(1),
+        );
+
+        let mut rows = vec![];
+        for batch in common::collect(partial_aggregate.execute(0).await?).await? {
+            rows.extend(crate::test::format_batch(&batch));
+        }
+        rows.sort();
+        assert_eq!(rows, vec!["2,2,2.0", "3,3,7.0", "4,3,11.0"]);
+
+        let final_aggregate = HashAggregateExec::try_new(
+            AggregateMode::Final,
+            vec![(col("a"), "a".to_string())],
+            aggregates,
+            Arc::new(MergeExec::new(partial_aggregate)),
+        )?
+        .with_memory_limit(1);
+
+        let mut rows = vec![];
+        for batch in common::collect(final_aggregate.execute(0).await?).await? {
+            rows.extend(crate::test::format_batch(&batch));
+        }
+        rows.sort();
+
+        assert_eq!(
+            rows,
+            vec![
+                "2,1.0",
+                "3,2.3333333333333335", // 3, (2 + 3 + 2) / 3
+                "4,3.6666666666666665"  // 4, (3 + 4 + 4) / 3
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn aggregate_by_decimal() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Decimal(10, 2), true),
+            Field::new("b", DataType::Float64, false),
+        ]));
+        let decimals = |values: Vec<Option<i128>>| -> Result<ArrayRef> {
+            let mut builder = DecimalBuilder::new(values.len(), 10, 2);
+            for value in values {
+                match value {
+                    Some(value) => builder.append_value(value)?,
+                    None => builder.append_null()?,
+                }
+            }
+            Ok(Arc::new(builder.finish()))
+        };
+        let batches = vec![
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    decimals(vec![Some(123), Some(456), None, Some(123)])?,
+                    Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0, 4.0])),
+                ],
+            )?,
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    decimals(vec![Some(456), Some(123), None, Some(789)])?,
+                    Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0, 4.0])),
+                ],
+            )?,
+        ];
+        let input = Arc::new(MemoryExec::try_new(&vec![batches], schema, None)?);
+
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Avg::new(
+            col("b"),
+            "AVG(b)".to_string(),
+            DataType::Float64,
+        ))];
+        let partial_aggregate = Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            vec![(col("a"), "a".to_string())],
+            aggregates.clone(),
+            input,
+        )?);
+        let final_aggregate = HashAggregateExec::try_new(
+            AggregateMode::Final,
+            vec![(col("a"), "a".to_string())],
+            aggregates,
+            Arc::new(MergeExec::new(partial_aggregate)),
+        )?;
+
+        let result = common::collect(final_aggregate.execute(0).await?).await?;
+        let batch = &result[0];
+        assert_eq!(
+            &DataType::Decimal(10, 2),
+            batch.schema().field(0).data_type()
+        );
+        let keys = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<DecimalArray>()
+            .unwrap();
+        let averages = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        let mut rows = (0..batch.num_rows())
+            .map(|row| {
+                let key = if keys.is_null(row) {
+                    None
+                } else {
+                    Some(keys.value(row))
+                };
+                (key, averages.value(row))
+            })
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|(key, _)| *key);
+        assert_eq!(
+            rows,
+            vec![
+                (None, 3.0),
+                (Some(123), 7.0 / 3.0),
+                (Some(456), 1.5),
+                (Some(789), 4.0)
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test(threaded_scheduler)]
+    async fn aggregate_grouping_sets() -> Result<()> {
+        let (schema, batches) = some_data();
+        let input = Arc::new(MemoryExec::try_new(&vec![batches], schema, None)?);
+
+        let groups: Vec<(Arc<dyn PhysicalExpr>, String)> =
+            vec![(col("a"), "a".to_string())];
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Avg::new(
+            col("b"),
+            "AVG(b)".to_string(),
+            DataType::Float64,
+        ))];
+
+        // the groups of `a` and the grand total, in one pass over the input and
+        // spilled after each batch
+        let partial_aggregate = Arc::new(
+            HashAggregateExec::try_new_with_grouping_sets(
+                AggregateMode::Partial,
+                groups.clone(),
+                vec![vec![true], vec![false]],
+                aggregates.clone(),
+                input,
+            )?
+            .with_memory_limit(1),
+        );
+        let partial_schema = partial_aggregate.schema();
+        assert!(partial_schema.field(0).is_nullable());
+        assert_eq!(GROUPING_ID_COLUMN, partial_schema.field(1).name());
+
+        let mut rows = vec![];
+        for batch in common::collect(partial_aggregate.execute(0).await?).await? {
+            rows.extend(crate::test::format_batch(&batch));
+        }
+        rows.sort();
+        assert_eq!(
+            rows,
+            vec!["2,0,2,2.0", "3,0,3,7.0", "4,0,3,11.0", "NULL,1,8,20.0"]
+        );
+
+        // the states are merged by group and grouping set
+        let final_aggregate = HashAggregateExec::try_new(
+            AggregateMode::Final,
+            vec![
+                (col("a"), "a".to_string()),
+                (col(GROUPING_ID_COLUMN), GROUPING_ID_COLUMN.to_string()),
+            ],
+            aggregates,
+            Arc::new(MergeExec::new(partial_aggregate)),
+        )?;
+
+        let result = common::collect(final_aggregate.execute(0).await?).await?;
+        let mut rows = crate::test::format_batch(&result[0]);
+        rows.sort();
+
+        assert_eq!(
+            rows,
+            vec![
+                "2,0,1.0",
+                "3,0,2.3333333333333335", // 3, (2 + 3 + 2) / 3
+                "4,0,3.6666666666666665", // 4, (3 + 4 + 4) / 3
+                "NULL,1,2.5"              // the average of all the values
+            ]
+        );
+        Ok(())
+    }
+
     /// Define a test source that can yield back to runtime before returning its first item ///
 
     #[derive(Debug)]
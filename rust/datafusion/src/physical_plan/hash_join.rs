@@ -19,33 +19,45 @@
 //! into a set of partitions.
 
 use arrow::array::ArrayRef;
+use std::convert::TryFrom;
 use std::sync::Arc;
-use std::{any::Any, collections::HashSet};
+use std::task::{Context, Poll};
+use std::{any::Any, collections::HashSet, pin::Pin};
 
 use async_trait::async_trait;
 use futures::{Stream, StreamExt, TryStreamExt};
 use hashbrown::HashMap;
 use tokio::sync::Mutex;
 
-use arrow::array::{make_array, Array, MutableArrayData};
-use arrow::datatypes::DataType;
+use arrow::array::{make_array, Array, BooleanArray, MutableArrayData};
+use arrow::compute::{cast, take};
+use arrow::datatypes::{DataType, TimeUnit};
 use arrow::datatypes::{Schema, SchemaRef};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 
 use arrow::array::{
-    Int16Array, Int32Array, Int64Array, Int8Array, StringArray, UInt16Array, UInt32Array,
-    UInt64Array, UInt8Array,
+    Date32Array, Date64Array, DecimalArray, Float32Array, Float64Array, Int16Array,
+    Int32Array, Int64Array, Int8Array, LargeStringArray, StringArray,
+    TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+    TimestampSecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
 };
 
 use super::expressions::col;
+use super::group_scalar::{OrderedFloat32, OrderedFloat64};
 use super::{
-    hash_utils::{build_join_schema, check_join_is_valid, JoinOn, JoinType},
+    hash_utils::{
+        build_filter_schema, build_join_schema, check_join_is_valid, JoinOn, JoinType,
+    },
     merge::MergeExec,
 };
 use crate::error::{DataFusionError, Result};
+use crate::scalar::ScalarValue;
 
-use super::{ExecutionPlan, Partitioning, RecordBatchStream, SendableRecordBatchStream};
+use super::{
+    Distribution, ExecutionPlan, Partitioning, PhysicalExpr, RecordBatchStream,
+    SendableRecordBatchStream,
+};
 use ahash::RandomState;
 
 // An index of (batch, row) uniquely identifying a row in a part.
@@ -63,8 +75,32 @@ type RightIndex = Option<usize>;
 type JoinHashMap = HashMap<Vec<u8>, Vec<Index>, RandomState>;
 type JoinLeftData = Arc<(JoinHashMap, Vec<RecordBatch>)>;
 
+/// Partitioning mode to use for the hash join
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PartitionMode {
+    /// The left and right sides are partitioned by the hash of their join keys, and
+    /// every partition of the left side is joined with the same partition of the right
+    Partitioned,
+    /// The left side is collected into a single partition, that is joined with every
+    /// partition of the right side
+    CollectLeft,
+}
+
+/// The residual predicate of a join, that the pairs of rows with equal keys must also
+/// satisfy to match
+#[derive(Debug, Clone)]
+struct JoinFilter {
+    /// The predicate, on the columns of both sides
+    expr: Arc<dyn PhysicalExpr>,
+    /// The schema of the batches the predicate is evaluated against
+    schema: SchemaRef,
+}
+
 /// join execution plan executes partitions in parallel and combines them into a set of
 /// partitions.
+///
+/// A join without keys compares every row of the left side with every row of the right
+/// side (a nested-loop join), and matches the pairs of rows satisfying its filter.
 #[derive(Debug)]
 pub struct HashJoinExec {
     /// left (build) side which gets hashed
@@ -77,8 +113,12 @@ pub struct HashJoinExec {
     join_type: JoinType,
     /// The schema once the join is applied
     schema: SchemaRef,
-    /// Build-side
+    /// Build-side, when the left side is collected
     build_side: Arc<Mutex<Option<JoinLeftData>>>,
+    /// How the sides of the join are partitioned
+    mode: PartitionMode,
+    /// The predicate the pairs of rows with equal keys must also satisfy, if any
+    filter: Option<JoinFilter>,
 }
 
 impl HashJoinExec {
@@ -90,10 +130,16 @@ impl HashJoinExec {
         right: Arc<dyn ExecutionPlan>,
         on: &JoinOn,
         join_type: &JoinType,
+        mode: PartitionMode,
     ) -> Result<Self> {
         let left_schema = left.schema();
         let right_schema = right.schema();
-        check_join_is_valid(&left_schema, &right_schema, &on)?;
+        check_join_is_valid(&left_schema, &right_schema, &on, join_type)?;
+        if on.is_empty() && mode == PartitionMode::Partitioned {
+            return Err(DataFusionError::Plan(
+                "A join without keys cannot be partitioned".to_string(),
+            ));
+        }
 
         let schema = Arc::new(build_join_schema(
             &left_schema,
@@ -114,8 +160,63 @@ impl HashJoinExec {
             join_type: *join_type,
             schema,
             build_side: Arc::new(Mutex::new(None)),
+            mode,
+            filter: None,
         })
     }
+
+    /// Sets the predicate that the pairs of rows with equal keys must also satisfy to
+    /// match. It is evaluated against batches of the schema returned by
+    /// [build_filter_schema], with the columns of both sides.
+    pub fn with_filter(mut self, filter: Arc<dyn PhysicalExpr>) -> Self {
+        let schema = build_filter_schema(&self.left.schema(), &self.right.schema());
+        self.filter = Some(JoinFilter {
+            expr: filter,
+            schema: Arc::new(schema),
+        });
+        self
+    }
+
+    /// The predicate that the pairs of rows with equal keys must also satisfy, if any
+    pub fn filter(&self) -> Option<&Arc<dyn PhysicalExpr>> {
+        self.filter.as_ref().map(|filter| &filter.expr)
+    }
+
+    /// Creates the hash map of the keys of the left side and collects its batches
+    async fn collect_left(
+        &self,
+        stream: SendableRecordBatchStream,
+    ) -> Result<JoinLeftData> {
+        let on_left = self
+            .on
+            .iter()
+            .map(|on| on.0.clone())
+            .collect::<HashSet<_>>();
+
+        // This operation performs 2 steps at once:
+        // 1. creates a [JoinHashMap] of all batches from the stream
+        // 2. stores the batches in a vector.
+        let initial = (JoinHashMap::default(), Vec::new(), 0);
+        let left_data = stream
+            .try_fold(initial, |mut acc, batch| async {
+                let hash = &mut acc.0;
+                let values = &mut acc.1;
+                let index = acc.2;
+                update_hash(&on_left, &batch, hash, index).unwrap();
+                values.push(batch);
+                acc.2 += 1;
+                Ok(acc)
+            })
+            .await?;
+
+        let mut batches = left_data.1;
+        if batches.is_empty() {
+            // an empty batch to build the columns of the left side from
+            batches.push(empty_batch(&self.left.schema())?);
+        }
+
+        Ok(Arc::new((left_data.0, batches)))
+    }
 }
 
 #[async_trait]
@@ -137,12 +238,19 @@ impl ExecutionPlan for HashJoinExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            2 => Ok(Arc::new(HashJoinExec::try_new(
-                children[0].clone(),
-                children[1].clone(),
-                &self.on,
-                &self.join_type,
-            )?)),
+            2 => {
+                let join = HashJoinExec::try_new(
+                    children[0].clone(),
+                    children[1].clone(),
+                    &self.on,
+                    &self.join_type,
+                    self.mode,
+                )?;
+                Ok(Arc::new(match self.filter() {
+                    Some(filter) => join.with_filter(filter.clone()),
+                    None => join,
+                }))
+            }
             _ => Err(DataFusionError::Internal(
                 "HashJoinExec wrong number of children".to_string(),
             )),
@@ -153,44 +261,46 @@ impl ExecutionPlan for HashJoinExec {
         self.right.output_partitioning()
     }
 
+    fn required_child_distribution(&self) -> Distribution {
+        match self.mode {
+            // matching rows are in the same partition of both sides
+            PartitionMode::Partitioned => Distribution::UnspecifiedDistribution,
+            PartitionMode::CollectLeft => match self.join_type {
+                JoinType::Inner | JoinType::Right => {
+                    Distribution::UnspecifiedDistribution
+                }
+                // the rows of the left side issued after the right side is exhausted
+                // depend on all the rows of the right side
+                JoinType::Left | JoinType::Full | JoinType::Semi | JoinType::Anti => {
+                    Distribution::SinglePartition
+                }
+            },
+        }
+    }
+
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        // we only want to compute the build side once
-        let left_data = {
-            let mut build_side = self.build_side.lock().await;
-            match build_side.as_ref() {
-                Some(stream) => stream.clone(),
-                None => {
-                    // merge all left parts into a single stream
-                    let merge = MergeExec::new(self.left.clone());
-                    let stream = merge.execute(0).await?;
-
-                    let on_left = self
-                        .on
-                        .iter()
-                        .map(|on| on.0.clone())
-                        .collect::<HashSet<_>>();
-
-                    // This operation performs 2 steps at once:
-                    // 1. creates a [JoinHashMap] of all batches from the stream
-                    // 2. stores the batches in a vector.
-                    let initial = (JoinHashMap::default(), Vec::new(), 0);
-                    let left_data = stream
-                        .try_fold(initial, |mut acc, batch| async {
-                            let hash = &mut acc.0;
-                            let values = &mut acc.1;
-                            let index = acc.2;
-                            update_hash(&on_left, &batch, hash, index).unwrap();
-                            values.push(batch);
-                            acc.2 += 1;
-                            Ok(acc)
-                        })
-                        .await?;
-
-                    let left_side = Arc::new((left_data.0, left_data.1));
-                    *build_side = Some(left_side.clone());
-                    left_side
+        let left_data = match self.mode {
+            PartitionMode::CollectLeft => {
+                // we only want to compute the build side once
+                let mut build_side = self.build_side.lock().await;
+                match build_side.as_ref() {
+                    Some(stream) => stream.clone(),
+                    None => {
+                        // merge all left parts into a single stream
+                        let merge = MergeExec::new(self.left.clone());
+                        let left_side =
+                            self.collect_left(merge.execute(0).await?).await?;
+                        *build_side = Some(left_side.clone());
+                        left_side
+                    }
                 }
             }
+            // only the same partition of the left side can match the rows of this
+            // partition of the right side
+            PartitionMode::Partitioned => {
+                self.collect_left(self.left.execute(partition).await?)
+                    .await?
+            }
         };
 
         // we have the batches and the hash map with their keys. We can how create a stream
@@ -202,12 +312,22 @@ impl ExecutionPlan for HashJoinExec {
             .iter()
             .map(|on| on.1.clone())
             .collect::<HashSet<_>>();
+        let visited_left_side = left_data
+            .1
+            .iter()
+            .map(|batch| vec![false; batch.num_rows()])
+            .collect();
         Ok(Box::pin(HashJoinStream {
             schema: self.schema.clone(),
             on_right,
             join_type: self.join_type,
+            filter: self.filter.clone(),
             left_data,
             right: stream,
+            right_schema: self.right.schema(),
+            right_batch: None,
+            visited_left_side,
+            is_exhausted: false,
         }))
     }
 }
@@ -225,11 +345,16 @@ fn update_hash(
         .iter()
         .map(|name| Ok(col(name).evaluate(batch)?.into_array(batch.num_rows())))
         .collect::<Result<Vec<_>>>()?;
+    let keys_values = decode_dictionaries(keys_values)?;
 
     let mut key = Vec::with_capacity(keys_values.len());
 
     // update the hash map
     for row in 0..batch.num_rows() {
+        // a NULL key equals no other key
+        if keys_values.iter().any(|array| array.is_null(row)) {
+            continue;
+        }
         create_key(&keys_values, row, &mut key)?;
 
         hash.raw_entry_mut()
@@ -248,10 +373,20 @@ struct HashJoinStream {
     on_right: HashSet<String>,
     /// type of the join
     join_type: JoinType,
+    /// the predicate the pairs of rows with equal keys must also satisfy, if any
+    filter: Option<JoinFilter>,
     /// information from the left
     left_data: JoinLeftData,
     /// right
     right: SendableRecordBatchStream,
+    /// schema of the right side
+    right_schema: SchemaRef,
+    /// a batch of the right side, to build the (null) columns of the right side from
+    right_batch: Option<RecordBatch>,
+    /// for each batch and row of the left side, whether it matched a row of the right
+    visited_left_side: Vec<Vec<bool>>,
+    /// whether the right side is exhausted and the remaining rows were issued
+    is_exhausted: bool,
 }
 
 impl RecordBatchStream for HashJoinStream {
@@ -263,79 +398,113 @@ impl RecordBatchStream for HashJoinStream {
 /// Returns a new [RecordBatch] by combining the `left` and `right` according to `indices`.
 /// The resulting batch has [Schema] `schema`.
 /// # Error
-/// This function errors when:
-/// *
+/// This function errors when a column of `schema` is in neither `left` nor `right`.
 fn build_batch_from_indices(
     schema: &Schema,
-    left: &Vec<RecordBatch>,
+    left: &[RecordBatch],
     right: &RecordBatch,
     join_type: &JoinType,
     indices: &[(JoinIndex, RightIndex)],
 ) -> ArrowResult<RecordBatch> {
-    if left.is_empty() {
-        todo!("Create empty record batch");
-    }
-    // this is just for symmetry of the code below.
-    let right = vec![right.clone()];
-
-    let (primary_is_left, primary, secondary) = match join_type {
-        JoinType::Inner | JoinType::Left => (true, left, &right),
-        JoinType::Right => (false, &right, left),
-    };
-
     // build the columns of the new [RecordBatch]:
-    // 1. pick whether the column is from the left or right
+    // 1. pick whether the column is from the left, the right or both
     // 2. based on the pick, `take` items from the different recordBatches
     let mut columns: Vec<Arc<dyn Array>> = Vec::with_capacity(schema.fields().len());
     for field in schema.fields() {
-        // pick the column (left or right) based on the field name.
-        // Note that we take `.data()` to gather the [ArrayData] of each array.
-        let (is_primary, arrays) = match primary[0].schema().index_of(field.name()) {
-            Ok(i) => Ok((true, primary.iter().map(|batch| batch.column(i).data()).collect::<Vec<_>>())),
-            Err(_) => {
-                match secondary[0].schema().index_of(field.name()) {
-                    Ok(i) => Ok((false, secondary.iter().map(|batch| batch.column(i).data()).collect::<Vec<_>>())),
-                    _ => Err(DataFusionError::Internal(
-                        format!("During execution, the column {} was not found in neither the left or right side of the join", field.name()).to_string()
-                    ))
-                }
-            }
-        }.map_err(DataFusionError::into_arrow_external_error)?;
-
-        // create a vector of references to be passed to [MutableArrayData]
+        let (left_column, right_column) = join_column_indices(
+            field.name(),
+            &left[0].schema(),
+            &right.schema(),
+            join_type,
+        )?;
+
+        // gather the [ArrayData] of the left batches followed by the one of the right
+        // batch, as references to be passed to [MutableArrayData]
+        let mut arrays = vec![];
+        if let Some(i) = left_column {
+            arrays.extend(left.iter().map(|batch| batch.column(i).data()));
+        }
+        if let Some(i) = right_column {
+            arrays.push(right.column(i).data());
+        }
+        let right_array = if left_column.is_some() { left.len() } else { 0 };
         let arrays = arrays
             .iter()
             .map(|array| array.as_ref())
             .collect::<Vec<_>>();
-        let capacity = arrays.iter().map(|array| array.len()).sum();
-        let mut mutable = MutableArrayData::new(arrays, true, capacity);
-
-        let is_left =
-            (is_primary && primary_is_left) || (!is_primary && !primary_is_left);
-        if is_left {
-            // use the left indices
-            for (join_index, _) in indices {
-                match join_index {
-                    Some((batch, row)) => mutable.extend(*batch, *row, *row + 1),
-                    None => mutable.extend_nulls(1),
-                }
-            }
-        } else {
-            // use the right indices
-            for (_, join_index) in indices {
-                match join_index {
-                    Some(row) => mutable.extend(0, *row, *row + 1),
-                    None => mutable.extend_nulls(1),
+        let mut mutable = MutableArrayData::new(arrays, true, indices.len());
+
+        for (left_index, right_index) in indices {
+            match (left_column, left_index, right_column, right_index) {
+                (Some(_), Some((batch, row)), _, _) => {
+                    mutable.extend(*batch, *row, *row + 1)
                 }
+                (_, _, Some(_), Some(row)) => mutable.extend(right_array, *row, *row + 1),
+                _ => mutable.extend_nulls(1),
             }
-        };
+        }
         let array = make_array(Arc::new(mutable.freeze()));
         columns.push(array);
     }
     Ok(RecordBatch::try_new(Arc::new(schema.clone()), columns)?)
 }
 
-/// Create a key `Vec<u8>` that is used as key for the hashmap
+/// Returns the indices of the columns of the left and right sides of a join to build the
+/// output column `name` from.
+/// # Error
+/// This function errors when `name` is in neither `left` nor `right`.
+pub(crate) fn join_column_indices(
+    name: &str,
+    left: &Schema,
+    right: &Schema,
+    join_type: &JoinType,
+) -> ArrowResult<(Option<usize>, Option<usize>)> {
+    // pick the column (left or right) based on the field name. A join key with the
+    // same name on both sides is taken from the left side, except on a right join,
+    // where it is taken from the right side, and on a full join, where it is taken
+    // from whichever side has a row.
+    let left_column = left.index_of(name).ok();
+    let right_column = right.index_of(name).ok();
+    match (left_column, right_column) {
+        (Some(l), Some(r)) => match join_type {
+            JoinType::Full => Ok((Some(l), Some(r))),
+            JoinType::Right => Ok((None, Some(r))),
+            _ => Ok((Some(l), None)),
+        },
+        (None, None) => Err(DataFusionError::Internal(format!(
+            "During execution, the column {} was not found in neither the left or right side of the join",
+            name
+        ))
+        .into_arrow_external_error()),
+        other => Ok(other),
+    }
+}
+
+/// Returns an empty [RecordBatch] with [Schema] `schema`
+pub(crate) fn empty_batch(schema: &SchemaRef) -> Result<RecordBatch> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| Ok(ScalarValue::try_from(field.data_type())?.to_array_of_size(0)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// Decodes the dictionary-encoded arrays of `arrays` into arrays of the values they
+/// encode, so that the keys of their rows can be created with [create_key]
+pub(crate) fn decode_dictionaries(arrays: Vec<ArrayRef>) -> Result<Vec<ArrayRef>> {
+    arrays
+        .into_iter()
+        .map(|array| match array.data_type() {
+            DataType::Dictionary(_, value_type) => Ok(cast(&array, value_type)?),
+            _ => Ok(array),
+        })
+        .collect()
+}
+
+/// Create a key `Vec<u8>` that is used as key for the hashmap. A NULL value is written
+/// as a single byte distinct from the one preceding any other value, so that the rows
+/// with a NULL form their own key.
 pub(crate) fn create_key(
     group_by_keys: &[ArrayRef],
     row: usize,
@@ -344,7 +513,26 @@ pub(crate) fn create_key(
     vec.clear();
     for i in 0..group_by_keys.len() {
         let col = &group_by_keys[i];
+        if col.is_null(row) {
+            vec.push(0);
+            continue;
+        }
+        vec.push(1);
         match col.data_type() {
+            DataType::Boolean => {
+                let array = col.as_any().downcast_ref::<BooleanArray>().unwrap();
+                vec.push(array.value(row) as u8);
+            }
+            DataType::Float32 => {
+                let array = col.as_any().downcast_ref::<Float32Array>().unwrap();
+                let value = OrderedFloat32(array.value(row));
+                vec.extend(value.to_bits().to_le_bytes().iter());
+            }
+            DataType::Float64 => {
+                let array = col.as_any().downcast_ref::<Float64Array>().unwrap();
+                let value = OrderedFloat64(array.value(row));
+                vec.extend(value.to_bits().to_le_bytes().iter());
+            }
             DataType::UInt8 => {
                 let array = col.as_any().downcast_ref::<UInt8Array>().unwrap();
                 vec.extend(array.value(row).to_le_bytes().iter());
@@ -385,6 +573,49 @@ pub(crate) fn create_key(
                 // store the string value
                 vec.extend(array.value(row).as_bytes().iter());
             }
+            DataType::LargeUtf8 => {
+                let array = col.as_any().downcast_ref::<LargeStringArray>().unwrap();
+                let value = array.value(row);
+                vec.extend(value.len().to_le_bytes().iter());
+                vec.extend(value.as_bytes().iter());
+            }
+            DataType::Date32(_) => {
+                let array = col.as_any().downcast_ref::<Date32Array>().unwrap();
+                vec.extend(array.value(row).to_le_bytes().iter());
+            }
+            DataType::Date64(_) => {
+                let array = col.as_any().downcast_ref::<Date64Array>().unwrap();
+                vec.extend(array.value(row).to_le_bytes().iter());
+            }
+            DataType::Timestamp(TimeUnit::Second, _) => {
+                let array = col.as_any().downcast_ref::<TimestampSecondArray>().unwrap();
+                vec.extend(array.value(row).to_le_bytes().iter());
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
+                let array = col
+                    .as_any()
+                    .downcast_ref::<TimestampMillisecondArray>()
+                    .unwrap();
+                vec.extend(array.value(row).to_le_bytes().iter());
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                let array = col
+                    .as_any()
+                    .downcast_ref::<TimestampMicrosecondArray>()
+                    .unwrap();
+                vec.extend(array.value(row).to_le_bytes().iter());
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+                let array = col
+                    .as_any()
+                    .downcast_ref::<TimestampNanosecondArray>()
+                    .unwrap();
+                vec.extend(array.value(row).to_le_bytes().iter());
+            }
+            DataType::Decimal(_, _) => {
+                let array = col.as_any().downcast_ref::<DecimalArray>().unwrap();
+                vec.extend(array.value(row).to_le_bytes().iter());
+            }
             _ => {
                 // This is internal because we should have caught this before.
                 return Err(DataFusionError::Internal(
@@ -401,9 +632,19 @@ fn build_batch(
     left_data: &JoinLeftData,
     on_right: &HashSet<String>,
     join_type: &JoinType,
+    filter: Option<&JoinFilter>,
     schema: &Schema,
+    visited_left_side: &mut [Vec<bool>],
 ) -> ArrowResult<RecordBatch> {
-    let indices = build_join_indexes(&left_data.0, &batch, join_type, on_right).unwrap();
+    let indices = build_join_indexes(
+        left_data,
+        &batch,
+        join_type,
+        on_right,
+        filter,
+        visited_left_side,
+    )
+    .map_err(DataFusionError::into_arrow_external_error)?;
 
     build_batch_from_indices(schema, &left_data.1, &batch, join_type, &indices)
 }
@@ -435,89 +676,199 @@ fn build_batch(
 // (0, 0)     (1, 2)
 // (1, 1)     (1, 1)
 // (1, 0)     (1, 2)
+//
+// The pairs of rows that do not satisfy the filter of the join, if any, do not match.
+// The rows of the left side that matched a row of the right side are marked in
+// `visited_left_side`, so that the rows of the left side issued once the right side is
+// exhausted can be found (see `build_unmatched_left_indexes`).
 fn build_join_indexes(
-    left: &JoinHashMap,
+    left_data: &JoinLeftData,
     right: &RecordBatch,
     join_type: &JoinType,
     right_on: &HashSet<String>,
+    filter: Option<&JoinFilter>,
+    visited_left_side: &mut [Vec<bool>],
 ) -> Result<Vec<(JoinIndex, RightIndex)>> {
     let keys_values = right_on
         .iter()
         .map(|name| Ok(col(name).evaluate(right)?.into_array(right.num_rows())))
         .collect::<Result<Vec<_>>>()?;
+    let keys_values = decode_dictionaries(keys_values)?;
 
     let mut key = Vec::with_capacity(keys_values.len());
 
-    match join_type {
-        JoinType::Inner => {
-            let mut indexes = Vec::new(); // unknown a prior size
+    // the pairs of rows with equal keys, ordered by the row of the right side
+    let mut matches = Vec::new(); // unknown a prior size
+    for row in 0..right.num_rows() {
+        // a NULL key matches no row
+        if keys_values.iter().any(|array| array.is_null(row)) {
+            continue;
+        }
+        create_key(&keys_values, row, &mut key)?;
+        if let Some(indices) = left_data.0.get(&key) {
+            matches.extend(indices.iter().map(|index| (*index, row)));
+        }
+    }
+    if let Some(filter) = filter {
+        matches = filter.apply(&left_data.1, right, matches)?;
+    }
 
-            // Visit all of the right rows
-            for row in 0..right.num_rows() {
-                // Get the key and find it in the build index
-                create_key(&keys_values, row, &mut key)?;
-                let left_indexes = left.get(&key);
-
-                // for every item on the left and right with this key, add the respective pair
-                left_indexes.unwrap_or(&vec![]).iter().for_each(|x| {
-                    // on an inner join, left and right indices are present
-                    indexes.push((Some(*x), Some(row)));
+    match join_type {
+        JoinType::Inner => Ok(matches
+            .into_iter()
+            .map(|(left_index, row)| (Some(left_index), Some(row)))
+            .collect()),
+        JoinType::Left => {
+            // the left rows without a match are only added once the right side is
+            // exhausted
+            Ok(matches
+                .into_iter()
+                .map(|(left_index, row)| {
+                    visited_left_side[left_index.0][left_index.1] = true;
+                    (Some(left_index), Some(row))
                 })
+                .collect())
+        }
+        JoinType::Semi | JoinType::Anti => {
+            // Only mark the left rows with a match: the rows of the left side are added
+            // once the right side is exhausted.
+            for (left_index, _) in matches {
+                visited_left_side[left_index.0][left_index.1] = true;
             }
-            Ok(indexes)
+            Ok(vec![])
         }
-        JoinType::Left => {
-            let mut indexes = Vec::new(); // unknown a prior size
-
-            // Keep track of which item is visited in the build input
-            // TODO: this can be stored more efficiently with a marker
-            let mut is_visited = HashSet::new();
-
-            // First visit all of the rows
+        JoinType::Right | JoinType::Full => {
+            let mut indexes = Vec::with_capacity(matches.len());
+            let mut i = 0;
             for row in 0..right.num_rows() {
-                create_key(&keys_values, row, &mut key)?;
-                let left_indexes = left.get(&key);
-
-                if let Some(indices) = left_indexes {
-                    is_visited.insert(key.clone());
-
-                    indices.iter().for_each(|x| {
-                        indexes.push((Some(*x), Some(row)));
-                    })
-                };
-            }
-            // Add the remaining left rows to the result set with None on the right side
-            for (key, indices) in left {
-                if !is_visited.contains(key) {
-                    indices.iter().for_each(|x| {
-                        indexes.push((Some(*x), None));
-                    });
+                let first_match = i;
+                while i < matches.len() && matches[i].1 == row {
+                    let left_index = matches[i].0;
+                    if *join_type == JoinType::Full {
+                        visited_left_side[left_index.0][left_index.1] = true;
+                    }
+                    indexes.push((Some(left_index), Some(row)));
+                    i += 1;
+                }
+                if i == first_match {
+                    // when no match, add the row with None for the left side
+                    indexes.push((None, Some(row)));
                 }
             }
-
             Ok(indexes)
         }
-        JoinType::Right => {
-            let mut indexes = Vec::new(); // unknown a prior size
-            for row in 0..right.num_rows() {
-                create_key(&keys_values, row, &mut key)?;
+    }
+}
 
-                let left_indices = left.get(&key);
+impl JoinFilter {
+    /// Returns the pairs of rows of `matches`, rows of `left` and `right`, that satisfy
+    /// the predicate
+    fn apply(
+        &self,
+        left: &[RecordBatch],
+        right: &RecordBatch,
+        matches: Vec<(Index, usize)>,
+    ) -> Result<Vec<(Index, usize)>> {
+        if matches.is_empty() {
+            return Ok(matches);
+        }
 
-                match left_indices {
-                    Some(indices) => {
-                        indices.iter().for_each(|x| {
-                            indexes.push((Some(*x), Some(row)));
-                        });
-                    }
-                    None => {
-                        // when no match, add the row with None for the left side
-                        indexes.push((None, Some(row)));
-                    }
-                }
+        // the batch of the pairs of rows, with the columns of both sides
+        let mut columns: Vec<ArrayRef> = Vec::with_capacity(self.schema.fields().len());
+        for i in 0..left[0].num_columns() {
+            let arrays = left
+                .iter()
+                .map(|batch| batch.column(i).data())
+                .collect::<Vec<_>>();
+            let arrays = arrays
+                .iter()
+                .map(|array| array.as_ref())
+                .collect::<Vec<_>>();
+            let mut mutable = MutableArrayData::new(arrays, false, matches.len());
+            for ((batch, row), _) in &matches {
+                mutable.extend(*batch, *row, *row + 1);
             }
-            Ok(indexes)
+            columns.push(make_array(Arc::new(mutable.freeze())));
+        }
+        let right_indices = UInt32Array::from(
+            matches
+                .iter()
+                .map(|(_, row)| *row as u32)
+                .collect::<Vec<_>>(),
+        );
+        for column in right.columns() {
+            columns.push(take(column.as_ref(), &right_indices, None)?);
+        }
+        let batch = RecordBatch::try_new(self.schema.clone(), columns)?;
+
+        let mask = self.expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let mask = mask
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .ok_or_else(|| {
+                DataFusionError::Plan(
+                    "The filter of a join must be a boolean expression".to_string(),
+                )
+            })?;
+        // rows for which the predicate is null do not match
+        Ok(matches
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| mask.is_valid(*i) && mask.value(*i))
+            .map(|(_, pair)| pair)
+            .collect())
+    }
+}
+
+/// returns a vector with the indices of the rows of the left side issued once the right
+/// side is exhausted, paired with no index from the right
+fn build_unmatched_left_indexes(
+    join_type: &JoinType,
+    visited_left_side: &[Vec<bool>],
+) -> Vec<(JoinIndex, RightIndex)> {
+    // a semi join issues the rows with a match, the other joins the rows without one
+    let visited = match join_type {
+        JoinType::Inner | JoinType::Right => return vec![],
+        JoinType::Semi => true,
+        JoinType::Left | JoinType::Full | JoinType::Anti => false,
+    };
+
+    visited_left_side
+        .iter()
+        .enumerate()
+        .flat_map(|(batch, rows)| {
+            rows.iter()
+                .enumerate()
+                .filter(move |(_, is_visited)| **is_visited == visited)
+                .map(move |(row, _)| (Some((batch, row)), None))
+        })
+        .collect()
+}
+
+impl HashJoinStream {
+    /// Builds the batch with the rows of the left side that are issued once the right
+    /// side is exhausted, if any
+    fn build_unmatched_batch(&self) -> ArrowResult<Option<RecordBatch>> {
+        let indices =
+            build_unmatched_left_indexes(&self.join_type, &self.visited_left_side);
+        if indices.is_empty() {
+            return Ok(None);
         }
+
+        // the columns of the right side are all null
+        let right = match &self.right_batch {
+            Some(batch) => batch.clone(),
+            None => empty_batch(&self.right_schema)
+                .map_err(DataFusionError::into_arrow_external_error)?,
+        };
+        build_batch_from_indices(
+            &self.schema,
+            &self.left_data.1,
+            &right,
+            &self.join_type,
+            &indices,
+        )
+        .map(Some)
     }
 }
 
@@ -525,21 +876,43 @@ impl Stream for HashJoinStream {
     type Item = ArrowResult<RecordBatch>;
 
     fn poll_next(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Option<Self::Item>> {
-        self.right
-            .poll_next_unpin(cx)
-            .map(|maybe_batch| match maybe_batch {
-                Some(Ok(batch)) => Some(build_batch(
-                    &batch,
-                    &self.left_data,
-                    &self.on_right,
-                    &self.join_type,
-                    &self.schema,
-                )),
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.is_exhausted {
+                return Poll::Ready(None);
+            }
+
+            let this = &mut *self;
+            return match this.right.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(batch))) => {
+                    if this.right_batch.is_none() {
+                        this.right_batch = Some(batch.clone());
+                    }
+                    let result = build_batch(
+                        &batch,
+                        &this.left_data,
+                        &this.on_right,
+                        &this.join_type,
+                        this.filter.as_ref(),
+                        &this.schema,
+                        &mut this.visited_left_side,
+                    );
+                    match (this.join_type, result) {
+                        // semi and anti joins only issue rows once the right side is
+                        // exhausted
+                        (JoinType::Semi, Ok(_)) | (JoinType::Anti, Ok(_)) => continue,
+                        (_, result) => Poll::Ready(Some(result)),
+                    }
+                }
+                Poll::Ready(None) => {
+                    this.is_exhausted = true;
+                    Poll::Ready(this.build_unmatched_batch().transpose())
+                }
                 other => other,
-            })
+            };
+        }
     }
 }
 
@@ -547,11 +920,15 @@ impl Stream for HashJoinStream {
 mod tests {
 
     use crate::{
-        physical_plan::{common, memory::MemoryExec},
+        logical_plan::Operator,
+        physical_plan::{
+            common, expressions::binary, memory::MemoryExec, repartition::RepartitionExec,
+        },
         test::{build_table_i32, columns, format_batch},
     };
 
     use super::*;
+    use arrow::datatypes::Field;
     use std::collections::HashSet;
     use std::sync::Arc;
 
@@ -575,7 +952,11 @@ mod tests {
             .iter()
             .map(|(l, r)| (l.to_string(), r.to_string()))
             .collect();
-        HashJoinExec::try_new(left, right, &on, join_type)
+        HashJoinExec::try_new(left, right, &on, join_type, PartitionMode::CollectLeft)
+    }
+
+    fn format_batches(batches: &[RecordBatch]) -> Vec<String> {
+        batches.iter().flat_map(format_batch).collect()
     }
 
     /// Asserts that the rows are the same, taking into account that their order
@@ -621,6 +1002,48 @@ mod tests {
         Ok(())
     }
 
+    /// Builds a table of an Int32 column `a` and a nullable Int32 column `b`
+    fn build_table_with_nulls(
+        a: (&str, Vec<i32>),
+        b: (&str, Vec<Option<i32>>),
+    ) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(a.0, DataType::Int32, false),
+            Field::new(b.0, DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(a.1)),
+                Arc::new(Int32Array::from(b.1)),
+            ],
+        )
+        .unwrap();
+        Arc::new(MemoryExec::try_new(&vec![vec![batch]], schema, None).unwrap())
+    }
+
+    #[tokio::test]
+    async fn join_left_null_keys() -> Result<()> {
+        let left =
+            build_table_with_nulls(("a1", vec![1, 2]), ("b1", vec![None, Some(4)]));
+        let right =
+            build_table_with_nulls(("a2", vec![10, 20]), ("b1", vec![None, Some(4)]));
+        let on = &[("b1", "b1")];
+
+        let join = join(left, right, on, &JoinType::Left)?;
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        // a NULL key matches no row, not even one with a NULL key
+        let result = format_batches(&batches);
+        let expected = vec!["1,NULL,NULL", "2,4,20"];
+
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn join_inner_one_no_shared_column_names() -> Result<()> {
         let left = build_table(
@@ -792,7 +1215,8 @@ mod tests {
         let stream = join.execute(0).await?;
         let batches = common::collect(stream).await?;
 
-        let result = format_batch(&batches[0]);
+        // the rows without a match are issued once the right side is exhausted
+        let result = format_batches(&batches);
         let expected = vec!["1,4,7,10,70", "2,5,8,20,80", "3,7,9,NULL,NULL"];
 
         assert_same_rows(&result, &expected);
@@ -800,6 +1224,56 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn join_full_partitioned() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 7]), // 7 does not exist on the right
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]), // 6 does not exist on the left
+            ("c2", &vec![70, 80, 90]),
+        );
+        let left = Arc::new(RepartitionExec::try_new(
+            left,
+            Partitioning::Hash(vec![col("b1")], 2),
+        )?);
+        let right = Arc::new(RepartitionExec::try_new(
+            right,
+            Partitioning::Hash(vec![col("b1")], 2),
+        )?);
+        let on = vec![("b1".to_string(), "b1".to_string())];
+
+        let join = HashJoinExec::try_new(
+            left,
+            right,
+            &on,
+            &JoinType::Full,
+            PartitionMode::Partitioned,
+        )?;
+        assert_eq!(2, join.output_partitioning().partition_count());
+
+        let mut result = vec![];
+        for partition in 0..2 {
+            let stream = join.execute(partition).await?;
+            let batches = common::collect(stream).await?;
+            result.extend(format_batches(&batches));
+        }
+        let expected = vec![
+            "1,4,7,10,70",
+            "2,5,8,20,80",
+            "3,7,9,NULL,NULL",
+            "NULL,6,NULL,30,90",
+        ];
+
+        assert_eq!(result.len(), expected.len());
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn join_right_one() -> Result<()> {
         let left = build_table(
@@ -829,4 +1303,249 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn join_left_two_right_batches() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 7]), // 7 does not exist on the right
+            ("c1", &vec![7, 8, 9]),
+        );
+        // the left rows matched by the second batch must not be issued as unmatched
+        let batch1 =
+            build_table_i32(("a2", &vec![10]), ("b1", &vec![4]), ("c2", &vec![70]));
+        let batch2 =
+            build_table_i32(("a2", &vec![20]), ("b1", &vec![5]), ("c2", &vec![80]));
+        let schema = batch1.schema();
+        let right = Arc::new(
+            MemoryExec::try_new(&vec![vec![batch1, batch2]], schema, None).unwrap(),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join(left, right, on, &JoinType::Left)?;
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        let result = format_batches(&batches);
+        let expected = vec!["1,4,7,10,70", "2,5,8,20,80", "3,7,9,NULL,NULL"];
+        assert_eq!(result.len(), expected.len());
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_full_one() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 7]), // 7 does not exist on the right
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]), // 6 does not exist on the left
+            ("c2", &vec![70, 80, 90]),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join(left, right, on, &JoinType::Full)?;
+
+        let columns = columns(&join.schema());
+        assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "c2"]);
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        // the join key is taken from whichever side has a row
+        let result = format_batches(&batches);
+        let expected = vec![
+            "1,4,7,10,70",
+            "2,5,8,20,80",
+            "3,7,9,NULL,NULL",
+            "NULL,6,NULL,30,90",
+        ];
+
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_semi() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3, 4]),
+            ("b1", &vec![4, 5, 5, 7]), // 7 does not exist on the right
+            ("c1", &vec![7, 8, 9, 10]),
+        );
+        let right = build_table(
+            ("a1", &vec![10, 20, 30]), // same name as the left side
+            ("b1", &vec![4, 5, 5]),    // 5 matches twice
+            ("c2", &vec![70, 80, 90]),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join(left, right, on, &JoinType::Semi)?;
+
+        let columns = columns(&join.schema());
+        assert_eq!(columns, vec!["a1", "b1", "c1"]);
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+        assert_eq!(batches.len(), 1);
+
+        // every row of the left side is issued at most once
+        let result = format_batches(&batches);
+        let expected = vec!["1,4,7", "2,5,8", "3,5,9"];
+        assert_eq!(result.len(), expected.len());
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_anti() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3, 4]),
+            ("b1", &vec![4, 5, 5, 7]), // 7 does not exist on the right
+            ("c1", &vec![7, 8, 9, 10]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join(left, right, on, &JoinType::Anti)?;
+
+        let columns = columns(&join.schema());
+        assert_eq!(columns, vec!["a1", "b1", "c1"]);
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        let result = format_batches(&batches);
+        let expected = vec!["4,7,10"];
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    /// Creates a join on `on` whose matching rows must also satisfy `left Gt right`
+    fn join_with_filter(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        on: &[(&str, &str)],
+        filter: (&str, &str),
+        join_type: &JoinType,
+    ) -> Result<HashJoinExec> {
+        let schema = build_filter_schema(&left.schema(), &right.schema());
+        let filter = binary(col(filter.0), Operator::Gt, col(filter.1), &schema)?;
+        Ok(join(left, right, on, join_type)?.with_filter(filter))
+    }
+
+    #[tokio::test]
+    async fn join_inner_with_filter() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 5]),
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 8, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join_with_filter(left, right, on, ("a2", "c1"), &JoinType::Inner)?;
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        // the rows with b1 = 5 do not satisfy the filter
+        let result = format_batches(&batches);
+        let expected = vec!["1,4,7,10,70"];
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_left_with_filter() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 5]),
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 8, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+        let on = &[("b1", "b1")];
+
+        let join = join_with_filter(left, right, on, ("a2", "c1"), &JoinType::Left)?;
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        // the rows of the left side whose matches do not satisfy the filter are
+        // padded with nulls
+        let result = format_batches(&batches);
+        let expected = vec!["1,4,7,10,70", "2,5,8,NULL,NULL", "3,5,9,NULL,NULL"];
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_without_keys() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 6]),
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![2, 3]),
+            ("b2", &vec![40, 50]),
+            ("c2", &vec![70, 80]),
+        );
+
+        // every row of the left side is compared with every row of the right side
+        let join = join_with_filter(left, right, &[], ("a2", "a1"), &JoinType::Full)?;
+
+        let columns = columns(&join.schema());
+        assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "b2", "c2"]);
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        let result = format_batches(&batches);
+        let expected = vec![
+            "1,4,7,2,40,70",
+            "1,4,7,3,50,80",
+            "2,5,8,3,50,80",
+            "3,6,9,NULL,NULL,NULL",
+        ];
+        assert_same_rows(&result, &expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn join_without_keys_partitioned() {
+        let left = build_table(("a1", &vec![1]), ("b1", &vec![4]), ("c1", &vec![7]));
+        let right = build_table(("a2", &vec![2]), ("b2", &vec![40]), ("c2", &vec![70]));
+
+        let result = HashJoinExec::try_new(
+            left,
+            right,
+            &[],
+            &JoinType::Inner,
+            PartitionMode::Partitioned,
+        );
+        assert!(result.is_err());
+    }
 }
@@ -18,11 +18,22 @@
 //! Functionality used both on logical and physical plans
 
 use crate::error::{DataFusionError, Result};
-use arrow::datatypes::{Field, Schema};
+use crate::physical_plan::group_scalar::{OrderedFloat32, OrderedFloat64};
+use ahash::RandomState;
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, Date32Array, Date64Array, DecimalArray, Float32Array,
+    Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, LargeStringArray,
+    StringArray, TimestampMicrosecondArray, TimestampMillisecondArray,
+    TimestampNanosecondArray, TimestampSecondArray, UInt16Array, UInt32Array,
+    UInt64Array, UInt8Array,
+};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
 use std::collections::HashSet;
+use std::hash::{BuildHasher, Hash, Hasher};
 
 /// All valid types of joins.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum JoinType {
     /// Inner join
     Inner,
@@ -30,19 +41,32 @@ pub enum JoinType {
     Left,
     /// Right
     Right,
+    /// Full
+    Full,
+    /// Semi, i.e. the rows of the left side with at least one match on the right side
+    Semi,
+    /// Anti, i.e. the rows of the left side without any match on the right side
+    Anti,
 }
 
 /// The on clause of the join, as vector of (left, right) columns.
 pub type JoinOn = [(String, String)];
 
 /// Checks whether the schemas "left" and "right" and columns "on" represent a valid join.
-/// They are valid whenever their columns' intersection equals the set `on`
-pub fn check_join_is_valid(left: &Schema, right: &Schema, on: &JoinOn) -> Result<()> {
+/// They are valid whenever their columns' intersection equals the set `on`, or, for
+/// semi and anti joins, whose output only contains the left side, whenever the columns
+/// `on` exist. `on` may be empty for a join evaluated on a filter only.
+pub fn check_join_is_valid(
+    left: &Schema,
+    right: &Schema,
+    on: &JoinOn,
+    join_type: &JoinType,
+) -> Result<()> {
     let left: HashSet<String> = left.fields().iter().map(|f| f.name().clone()).collect();
     let right: HashSet<String> =
         right.fields().iter().map(|f| f.name().clone()).collect();
 
-    check_join_set_is_valid(&left, &right, on)
+    check_join_set_is_valid(&left, &right, on, join_type)
 }
 
 /// Checks whether the sets left, right and on compose a valid join.
@@ -51,12 +75,8 @@ fn check_join_set_is_valid(
     left: &HashSet<String>,
     right: &HashSet<String>,
     on: &JoinOn,
+    join_type: &JoinType,
 ) -> Result<()> {
-    if on.is_empty() {
-        return Err(DataFusionError::Plan(
-            "The 'on' clause of a join cannot be empty".to_string(),
-        ));
-    }
     let on_left = &on.iter().map(|on| on.0.to_string()).collect::<HashSet<_>>();
     let left_missing = on_left.difference(left).collect::<HashSet<_>>();
 
@@ -71,6 +91,11 @@ fn check_join_set_is_valid(
             )));
     };
 
+    if let JoinType::Semi | JoinType::Anti = join_type {
+        // the columns of the right side are not part of the output
+        return Ok(());
+    }
+
     let remaining = right
         .difference(on_right)
         .cloned()
@@ -97,7 +122,7 @@ pub fn build_join_schema(
     join_type: &JoinType,
 ) -> Schema {
     let fields: Vec<Field> = match join_type {
-        JoinType::Inner | JoinType::Left => {
+        JoinType::Inner | JoinType::Left | JoinType::Full => {
             // remove right-side join keys if they have the same names as the left-side
             let duplicate_keys = &on
                 .iter()
@@ -133,23 +158,191 @@ pub fn build_join_schema(
             // left then right
             left_fields.chain(right_fields).cloned().collect()
         }
+        JoinType::Semi | JoinType::Anti => {
+            // only the left side is part of the output
+            left.fields().clone()
+        }
     };
     Schema::new(fields)
 }
 
+/// Creates the schema of the batches the filter of a join is evaluated against, the
+/// fields of the left side followed by the fields of the right side
+pub fn build_filter_schema(left: &Schema, right: &Schema) -> Schema {
+    let fields = left
+        .fields()
+        .iter()
+        .chain(right.fields().iter())
+        .cloned()
+        .collect();
+    Schema::new(fields)
+}
+
+// Combines two hashes into one hash
+fn combine_hashes(l: u64, r: u64) -> u64 {
+    let hash = (17 * 37u64).wrapping_add(l);
+    hash.wrapping_mul(37).wrapping_add(r)
+}
+
+fn hash_value<T: Hash + ?Sized>(value: &T, random_state: &RandomState) -> u64 {
+    let mut hasher = random_state.build_hasher();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+macro_rules! hash_array {
+    ($array_type:ident, $column: ident, $random_state: ident, $hashes: ident) => {{
+        hash_array!($array_type, $column, $random_state, $hashes, v => v)
+    }};
+    // `$VALUE` converts the value `$V` of the array into the value that is hashed
+    (
+        $array_type:ident,
+        $column: ident,
+        $random_state: ident,
+        $hashes: ident,
+        $V:ident => $VALUE:expr
+    ) => {{
+        let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
+        for (row, hash) in $hashes.iter_mut().enumerate() {
+            // null values leave the hash of the row unchanged
+            if array.is_valid(row) {
+                let $V = array.value(row);
+                *hash = combine_hashes(hash_value(&$VALUE, $random_state), *hash);
+            }
+        }
+    }};
+}
+
+/// Creates the hash values of the rows of `arrays` in `hashes_buffer`, whose length must
+/// be the number of rows. Rows with equal values have equal hashes for a given
+/// `random_state`, so that they can be assigned to the same partition.
+pub fn create_hashes<'a>(
+    arrays: &[ArrayRef],
+    random_state: &RandomState,
+    hashes_buffer: &'a mut Vec<u64>,
+) -> Result<&'a mut Vec<u64>> {
+    hashes_buffer.iter_mut().for_each(|hash| *hash = 0);
+    for column in arrays {
+        // dictionary-encoded values are hashed as the values they encode, so that equal
+        // values have equal hashes whatever their dictionaries
+        let column = &match column.data_type() {
+            DataType::Dictionary(_, value_type) => cast(column, value_type)?,
+            _ => column.clone(),
+        };
+        match column.data_type() {
+            DataType::Boolean => {
+                hash_array!(BooleanArray, column, random_state, hashes_buffer)
+            }
+            DataType::Float32 => hash_array!(
+                Float32Array,
+                column,
+                random_state,
+                hashes_buffer,
+                v => OrderedFloat32(v)
+            ),
+            DataType::Float64 => hash_array!(
+                Float64Array,
+                column,
+                random_state,
+                hashes_buffer,
+                v => OrderedFloat64(v)
+            ),
+            DataType::UInt8 => {
+                hash_array!(UInt8Array, column, random_state, hashes_buffer)
+            }
+            DataType::UInt16 => {
+                hash_array!(UInt16Array, column, random_state, hashes_buffer)
+            }
+            DataType::UInt32 => {
+                hash_array!(UInt32Array, column, random_state, hashes_buffer)
+            }
+            DataType::UInt64 => {
+                hash_array!(UInt64Array, column, random_state, hashes_buffer)
+            }
+            DataType::Int8 => {
+                hash_array!(Int8Array, column, random_state, hashes_buffer)
+            }
+            DataType::Int16 => {
+                hash_array!(Int16Array, column, random_state, hashes_buffer)
+            }
+            DataType::Int32 => {
+                hash_array!(Int32Array, column, random_state, hashes_buffer)
+            }
+            DataType::Int64 => {
+                hash_array!(Int64Array, column, random_state, hashes_buffer)
+            }
+            DataType::Utf8 => {
+                hash_array!(StringArray, column, random_state, hashes_buffer)
+            }
+            DataType::LargeUtf8 => {
+                hash_array!(LargeStringArray, column, random_state, hashes_buffer)
+            }
+            DataType::Date32(_) => {
+                hash_array!(Date32Array, column, random_state, hashes_buffer)
+            }
+            DataType::Date64(_) => {
+                hash_array!(Date64Array, column, random_state, hashes_buffer)
+            }
+            DataType::Timestamp(TimeUnit::Second, _) => {
+                hash_array!(TimestampSecondArray, column, random_state, hashes_buffer)
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, _) => hash_array!(
+                TimestampMillisecondArray,
+                column,
+                random_state,
+                hashes_buffer
+            ),
+            DataType::Timestamp(TimeUnit::Microsecond, _) => hash_array!(
+                TimestampMicrosecondArray,
+                column,
+                random_state,
+                hashes_buffer
+            ),
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => hash_array!(
+                TimestampNanosecondArray,
+                column,
+                random_state,
+                hashes_buffer
+            ),
+            DataType::Decimal(_, _) => {
+                hash_array!(DecimalArray, column, random_state, hashes_buffer)
+            }
+            other => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Hashing of columns of type {:?} is not supported",
+                    other
+                )))
+            }
+        }
+    }
+    Ok(hashes_buffer)
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use arrow::array::DictionaryArray;
+    use arrow::datatypes::Int8Type;
+    use std::sync::Arc;
 
     fn check(left: &[&str], right: &[&str], on: &[(&str, &str)]) -> Result<()> {
+        check_with_join_type(left, right, on, &JoinType::Inner)
+    }
+
+    fn check_with_join_type(
+        left: &[&str],
+        right: &[&str],
+        on: &[(&str, &str)],
+        join_type: &JoinType,
+    ) -> Result<()> {
         let left = left.iter().map(|x| x.to_string()).collect::<HashSet<_>>();
         let right = right.iter().map(|x| x.to_string()).collect::<HashSet<_>>();
         let on: Vec<_> = on
             .iter()
             .map(|(l, r)| (l.to_string(), r.to_string()))
             .collect();
-        check_join_set_is_valid(&left, &right, &on)
+        check_join_set_is_valid(&left, &right, &on, join_type)
     }
 
     #[test]
@@ -190,6 +383,19 @@ mod tests {
         assert!(check(&left, &right, on).is_err());
     }
 
+    #[test]
+    fn check_collision_semi_anti() -> Result<()> {
+        // column "c" is only part of the output on the left side
+        let left = vec!["a", "c"];
+        let right = vec!["b", "c"];
+        let on = &[("a", "b")];
+
+        check_with_join_type(&left, &right, on, &JoinType::Semi)?;
+        check_with_join_type(&left, &right, on, &JoinType::Anti)?;
+        assert!(check_with_join_type(&left, &right, on, &JoinType::Full).is_err());
+        Ok(())
+    }
+
     #[test]
     fn check_in_right() {
         let left = vec!["a", "c"];
@@ -198,4 +404,56 @@ mod tests {
 
         assert!(check(&left, &right, on).is_ok());
     }
+
+    #[test]
+    fn create_hashes_of_equal_rows() -> Result<()> {
+        let random_state = RandomState::with_seeds(0, 0, 0, 0);
+        let ints: ArrayRef =
+            Arc::new(Int32Array::from(vec![Some(1), Some(2), None, Some(1)]));
+        let strings: ArrayRef = Arc::new(StringArray::from(vec!["a", "b", "c", "a"]));
+
+        let mut hashes = vec![0; 4];
+        create_hashes(&[ints, strings], &random_state, &mut hashes)?;
+        assert_eq!(hashes[0], hashes[3]);
+        assert_ne!(hashes[0], hashes[1]);
+        assert_ne!(hashes[0], hashes[2]);
+        Ok(())
+    }
+
+    #[test]
+    fn create_hashes_of_floats_and_dictionaries() -> Result<()> {
+        let random_state = RandomState::with_seeds(0, 0, 0, 0);
+        let floats: ArrayRef = Arc::new(Float64Array::from(vec![
+            0.0,
+            -0.0,
+            f64::NAN,
+            -f64::NAN,
+            1.0,
+        ]));
+        let mut hashes = vec![0; 5];
+        create_hashes(&[floats], &random_state, &mut hashes)?;
+        assert_eq!(hashes[0], hashes[1]);
+        assert_eq!(hashes[2], hashes[3]);
+        assert_ne!(hashes[0], hashes[4]);
+
+        // dictionary-encoded values are hashed as the values they encode
+        let strings: ArrayRef = Arc::new(StringArray::from(vec!["a", "b"]));
+        let mut expected = vec![0; 2];
+        create_hashes(&[strings], &random_state, &mut expected)?;
+        let dictionary: DictionaryArray<Int8Type> = vec!["b", "a"].into_iter().collect();
+        let dictionary: ArrayRef = Arc::new(dictionary);
+        let mut hashes = vec![0; 2];
+        create_hashes(&[dictionary], &random_state, &mut hashes)?;
+        assert_eq!(hashes, vec![expected[1], expected[0]]);
+        Ok(())
+    }
+
+    #[test]
+    fn create_hashes_unsupported_type() {
+        let random_state = RandomState::with_seeds(0, 0, 0, 0);
+        let binary: ArrayRef =
+            Arc::new(arrow::array::BinaryArray::from(vec![b"a".as_ref()]));
+        let mut hashes = vec![0; 1];
+        assert!(create_hashes(&[binary], &random_state, &mut hashes).is_err());
+    }
 }
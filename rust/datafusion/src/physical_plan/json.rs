@@ -0,0 +1,406 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Execution plan for reading newline-delimited JSON files, with one JSON object per line
+
+use std::any::Any;
+use std::fs::File;
+use std::io::BufReader;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::ExecutionPlan;
+use crate::physical_plan::{common, Partitioning};
+use arrow::array::ArrayRef;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::json;
+use arrow::json::reader::ValueIter;
+use arrow::record_batch::RecordBatch;
+use futures::Stream;
+
+use super::{RecordBatchStream, SendableRecordBatchStream};
+use async_trait::async_trait;
+
+/// Newline-delimited JSON file read option
+#[derive(Copy, Clone)]
+pub struct NdJsonReadOptions<'a> {
+    /// An optional schema representing the JSON files. If None, the JSON reader will try
+    /// to infer it based on data in the files.
+    pub schema: Option<&'a Schema>,
+    /// Max number of rows to read from JSON files for schema inference if needed.
+    /// Defaults to 1000.
+    pub schema_infer_max_records: usize,
+    /// File extension; only files with this extension are selected for data input.
+    /// Defaults to ".json".
+    pub file_extension: &'a str,
+}
+
+impl<'a> NdJsonReadOptions<'a> {
+    /// Create a JSON read option with default presets
+    pub fn new() -> Self {
+        Self {
+            schema: None,
+            schema_infer_max_records: 1000,
+            file_extension: ".json",
+        }
+    }
+
+    /// Specify the file extension for JSON file selection
+    pub fn file_extension(mut self, file_extension: &'a str) -> Self {
+        self.file_extension = file_extension;
+        self
+    }
+
+    /// Specify schema to use for JSON read
+    pub fn schema(mut self, schema: &'a Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Configure number of max records to read for schema inference
+    pub fn schema_infer_max_records(mut self, max_records: usize) -> Self {
+        self.schema_infer_max_records = max_records;
+        self
+    }
+}
+
+/// Execution plan for scanning newline-delimited JSON files
+#[derive(Debug, Clone)]
+pub struct NdJsonExec {
+    /// Path to a single JSON file or a directory containing JSON files with the same
+    /// schema
+    path: String,
+    /// The individual files under path
+    filenames: Vec<String>,
+    /// Schema representing the JSON files
+    schema: SchemaRef,
+    /// File extension
+    file_extension: String,
+    /// Optional projection for which columns to load
+    projection: Option<Vec<usize>>,
+    /// Schema after the projection has been applied
+    projected_schema: SchemaRef,
+    /// Batch size
+    batch_size: usize,
+    /// Optional number of rows to read from each file
+    limit: Option<usize>,
+}
+
+impl NdJsonExec {
+    /// Create a new execution plan for reading a set of JSON files, stopping after the
+    /// first `limit` rows of each file if specified
+    pub fn try_new(
+        path: &str,
+        options: NdJsonReadOptions,
+        projection: Option<Vec<usize>>,
+        batch_size: usize,
+        limit: Option<usize>,
+    ) -> Result<Self> {
+        let file_extension = String::from(options.file_extension);
+
+        let mut filenames: Vec<String> = vec![];
+        common::build_file_list(path, &mut filenames, file_extension.as_str())?;
+        if filenames.is_empty() {
+            return Err(DataFusionError::Execution("No files found".to_string()));
+        }
+
+        let schema = match options.schema {
+            Some(s) => s.clone(),
+            None => NdJsonExec::try_infer_schema(&filenames, &options)?,
+        };
+
+        let projected_schema = match &projection {
+            None => schema.clone(),
+            Some(p) => Schema::new(p.iter().map(|i| schema.field(*i).clone()).collect()),
+        };
+
+        Ok(Self {
+            path: path.to_string(),
+            filenames,
+            schema: Arc::new(schema),
+            file_extension,
+            projection,
+            projected_schema: Arc::new(projected_schema),
+            batch_size,
+            limit,
+        })
+    }
+
+    /// Infer schema for given JSON dataset, from the first records of its files
+    pub fn try_infer_schema(
+        filenames: &[String],
+        options: &NdJsonReadOptions,
+    ) -> Result<Schema> {
+        let mut readers = filenames
+            .iter()
+            .map(|filename| Ok(BufReader::new(File::open(filename)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let values = readers
+            .iter_mut()
+            .flat_map(|reader| ValueIter::new(reader, None))
+            .take(options.schema_infer_max_records);
+        let schema = json::reader::infer_json_schema_from_iterator(values)?;
+        Ok(schema.as_ref().clone())
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for NdJsonExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// Get the schema for this execution plan
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.filenames.len())
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        // this is a leaf node and has no children
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if children.is_empty() {
+            Ok(Arc::new(self.clone()))
+        } else {
+            Err(DataFusionError::Internal(format!(
+                "Children cannot be replaced in {:?}",
+                self
+            )))
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        Ok(Box::pin(NdJsonStream::try_new(
+            &self.filenames[partition],
+            self.schema.clone(),
+            &self.projection,
+            self.projected_schema.clone(),
+            self.batch_size,
+            self.limit,
+        )?))
+    }
+}
+
+/// Iterator over batches
+struct NdJsonStream {
+    /// Arrow JSON reader
+    reader: json::Reader<File>,
+    /// Schema after the projection has been applied
+    projected_schema: SchemaRef,
+    /// The index in the batches of the reader, which have the columns in the order of
+    /// the schema, of each projected column, if they are in a different order
+    column_indices: Option<Vec<usize>>,
+    /// The number of rows left to read, if limited
+    remaining: Option<usize>,
+}
+
+impl NdJsonStream {
+    /// Create an iterator for a JSON file
+    pub fn try_new(
+        filename: &str,
+        schema: SchemaRef,
+        projection: &Option<Vec<usize>>,
+        projected_schema: SchemaRef,
+        batch_size: usize,
+        limit: Option<usize>,
+    ) -> Result<Self> {
+        let file = File::open(filename)?;
+        // the reader selects the projected columns by name, and returns them in the
+        // order of the schema, so they are requested in that order
+        let mut sorted_projection = projection.clone();
+        if let Some(sorted) = &mut sorted_projection {
+            sorted.sort_unstable();
+        }
+        let column_names = sorted_projection.as_ref().map(|sorted| {
+            sorted
+                .iter()
+                .map(|i| schema.field(*i).name().clone())
+                .collect()
+        });
+        let reader = json::Reader::new(file, schema, batch_size, column_names);
+
+        let column_indices = match (projection, &sorted_projection) {
+            (Some(projection), Some(sorted)) if projection != sorted => Some(
+                projection
+                    .iter()
+                    .map(|i| sorted.iter().position(|j| i == j).unwrap())
+                    .collect(),
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            reader,
+            projected_schema,
+            column_indices,
+            remaining: limit,
+        })
+    }
+
+    /// Reads the next batch, with the projected columns in the order of the projection
+    fn next_batch(&mut self) -> ArrowResult<Option<RecordBatch>> {
+        if self.remaining == Some(0) {
+            return Ok(None);
+        }
+        let batch = match self.reader.next()? {
+            Some(batch) => batch,
+            None => return Ok(None),
+        };
+
+        let num_rows = match &mut self.remaining {
+            Some(remaining) => {
+                let num_rows = batch.num_rows().min(*remaining);
+                *remaining -= num_rows;
+                num_rows
+            }
+            None => batch.num_rows(),
+        };
+        let columns: Vec<ArrayRef> = match &self.column_indices {
+            Some(indices) => indices.iter().map(|i| batch.column(*i).clone()).collect(),
+            None => batch.columns().to_vec(),
+        };
+        let columns = columns
+            .into_iter()
+            .map(|column| column.slice(0, num_rows))
+            .collect();
+        RecordBatch::try_new(self.projected_schema.clone(), columns).map(Some)
+    }
+}
+
+impl Stream for NdJsonStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.next_batch().transpose())
+    }
+}
+
+impl RecordBatchStream for NdJsonStream {
+    /// Get the schema
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::{DataType, Field};
+    use futures::StreamExt;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    /// Writes two JSON files to `dir`, whose rows have different fields
+    fn write_json_files(dir: &TempDir) -> Result<String> {
+        let files = vec![
+            (
+                "1.json",
+                "{\"a\":1, \"b\":2.0, \"c\":\"x\"}\n{\"a\":-10, \"b\":-3.5}\n",
+            ),
+            (
+                "2.json",
+                "{\"a\":2, \"c\":\"y\"}\n{\"a\":3, \"b\":1.5, \"c\":\"z\"}\n",
+            ),
+            // files without the extension are ignored
+            ("3.txt", "{\"a\":4}\n"),
+        ];
+        for (name, content) in files {
+            let mut file = File::create(dir.path().join(name))?;
+            file.write_all(content.as_bytes())?;
+        }
+        Ok(dir.path().to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn json_infer_schema() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = write_json_files(&dir)?;
+        let json =
+            NdJsonExec::try_new(&path, NdJsonReadOptions::new(), None, 1024, None)?;
+
+        let schema = json.schema();
+        assert_eq!(3, schema.fields().len());
+        assert_eq!(&DataType::Int64, schema.field_with_name("a")?.data_type());
+        assert_eq!(&DataType::Float64, schema.field_with_name("b")?.data_type());
+        assert_eq!(&DataType::Utf8, schema.field_with_name("c")?.data_type());
+        assert_eq!(2, json.output_partitioning().partition_count());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn json_exec_with_projection() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = write_json_files(&dir)?;
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Float64, true),
+            Field::new("c", DataType::Utf8, true),
+        ]);
+        let json = NdJsonExec::try_new(
+            &path,
+            NdJsonReadOptions::new().schema(&schema),
+            Some(vec![2, 0]),
+            1024,
+            None,
+        )?;
+        assert_eq!(2, json.schema().fields().len());
+
+        let mut num_rows = 0;
+        for partition in 0..2 {
+            let mut stream = json.execute(partition).await?;
+            let batch = stream.next().await.unwrap()?;
+            // the columns are in the order of the projection
+            let batch_schema = batch.schema();
+            assert_eq!("c", batch_schema.field(0).name());
+            assert_eq!("a", batch_schema.field(1).name());
+            assert_eq!(&DataType::Utf8, batch.column(0).data_type());
+            num_rows += batch.num_rows();
+        }
+        assert_eq!(4, num_rows);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn json_exec_with_limit() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = write_json_files(&dir)?;
+        let json =
+            NdJsonExec::try_new(&path, NdJsonReadOptions::new(), None, 1024, Some(1))?;
+        let batches = common::collect(json.execute(0).await?).await?;
+        // the reader stops after the first row of the file
+        let num_rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(1, num_rows);
+        Ok(())
+    }
+}
@@ -174,6 +174,76 @@ impl ExecutionPlan for LocalLimitExec {
     }
 }
 
+/// Produces the rows of its input, and fails when there are more than one, as the
+/// input of an uncorrelated scalar subquery
+#[derive(Debug)]
+pub struct EnforceSingleRowExec {
+    input: Arc<dyn ExecutionPlan>,
+}
+
+impl EnforceSingleRowExec {
+    /// Create a new EnforceSingleRowExec
+    pub fn new(input: Arc<dyn ExecutionPlan>) -> Self {
+        Self { input }
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for EnforceSingleRowExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::SinglePartition
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(EnforceSingleRowExec::new(children[0].clone()))),
+            _ => Err(DataFusionError::Internal(
+                "EnforceSingleRowExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "EnforceSingleRowExec invalid partition {}",
+                partition
+            )));
+        }
+        if 1 != self.input.output_partitioning().partition_count() {
+            return Err(DataFusionError::Internal(
+                "EnforceSingleRowExec requires a single input partition".to_owned(),
+            ));
+        }
+
+        let stream = self.input.execute(0).await?;
+        Ok(Box::pin(EnforceSingleRowStream {
+            input: stream,
+            num_rows: 0,
+        }))
+    }
+}
+
 /// Truncate a RecordBatch to maximum of n rows
 pub fn truncate_batch(batch: &RecordBatch, n: usize) -> RecordBatch {
     let limited_columns: Vec<ArrayRef> = (0..batch.num_columns())
@@ -235,12 +305,52 @@ impl RecordBatchStream for LimitStream {
     }
 }
 
+/// A stream of the rows of its input that fails when there are more than one
+struct EnforceSingleRowStream {
+    input: SendableRecordBatchStream,
+    // the number of rows so far
+    num_rows: usize,
+}
+
+impl Stream for EnforceSingleRowStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.input.poll_next_unpin(cx).map(|x| match x {
+            Some(Ok(batch)) => {
+                self.num_rows += batch.num_rows();
+                if self.num_rows > 1 {
+                    Some(Err(DataFusionError::Execution(
+                        "More than one row returned by a subquery used as an expression"
+                            .to_string(),
+                    )
+                    .into_arrow_external_error()))
+                } else {
+                    Some(Ok(batch))
+                }
+            }
+            other => other,
+        })
+    }
+}
+
+impl RecordBatchStream for EnforceSingleRowStream {
+    /// Get the schema
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
     use crate::physical_plan::common;
     use crate::physical_plan::csv::{CsvExec, CsvReadOptions};
+    use crate::physical_plan::memory::MemoryExec;
     use crate::physical_plan::merge::MergeExec;
     use crate::test;
 
@@ -252,8 +362,13 @@ mod tests {
         let path =
             test::create_partitioned_csv("aggregate_test_100.csv", num_partitions)?;
 
-        let csv =
-            CsvExec::try_new(&path, CsvReadOptions::new().schema(&schema), None, 1024)?;
+        let csv = CsvExec::try_new(
+            &path,
+            CsvReadOptions::new().schema(&schema),
+            None,
+            1024,
+            None,
+        )?;
 
         // input should have 4 partitions
         assert_eq!(csv.output_partitioning().partition_count(), num_partitions);
@@ -270,4 +385,27 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn enforce_single_row() -> Result<()> {
+        let batch =
+            test::build_table_i32(("a", &vec![1]), ("b", &vec![2]), ("c", &vec![3]));
+        let schema = batch.schema();
+        let input =
+            MemoryExec::try_new(&vec![vec![batch.clone()]], schema.clone(), None)?;
+        let plan = EnforceSingleRowExec::new(Arc::new(input));
+        let batches = common::collect(plan.execute(0).await?).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+
+        // the rows of different batches are counted together
+        let input = MemoryExec::try_new(&vec![vec![batch.clone(), batch]], schema, None)?;
+        let plan = EnforceSingleRowExec::new(Arc::new(input));
+        let err = common::collect(plan.execute(0).await?)
+            .await
+            .expect_err("more than one row should have failed");
+        assert!(err
+            .to_string()
+            .contains("More than one row returned by a subquery used as an expression"));
+        Ok(())
+    }
 }
@@ -111,7 +111,6 @@ pub enum Partitioning {
     RoundRobinBatch(usize),
     /// Allocate rows based on a hash of one of more expressions and the specified
     /// number of partitions
-    /// This partitioning scheme is not yet fully supported. See https://issues.apache.org/jira/browse/ARROW-11011
     Hash(Vec<Arc<dyn PhysicalExpr>>, usize),
     /// Unknown partitioning scheme with a known number of partitions
     UnknownPartitioning(usize),
@@ -244,11 +243,46 @@ pub trait Accumulator: Send + Sync + Debug {
 
     /// returns its value based on its current state.
     fn evaluate(&self) -> Result<ScalarValue>;
+
+    /// Estimated number of bytes of memory used by the accumulator, its heap
+    /// allocations included. Accumulators whose state grows with their input, e.g.
+    /// by gathering values, must override it for their memory to be limited.
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
+}
+
+/// A window expression that:
+/// * knows its resulting field
+/// * knows how to partition and order its input
+/// * knows how to evaluate its window function over all the rows of a batch
+pub trait WindowExpr: Send + Sync + Debug {
+    /// the field of the final result of this window function.
+    fn field(&self) -> Result<Field>;
+
+    /// human readable name such as `"ROW_NUMBER()"` or `"SUM(c1)"`
+    fn name(&self) -> &str;
+
+    /// expressions that are passed to the window function.
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>>;
+
+    /// expressions from the PARTITION BY clause
+    fn partition_by(&self) -> &[Arc<dyn PhysicalExpr>];
+
+    /// expressions from the ORDER BY clause
+    fn order_by(&self) -> &[expressions::PhysicalSortExpr];
+
+    /// evaluates the window function over all the rows of `batch`, returning one
+    /// value per row, in the same order as the rows of `batch`.
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef>;
 }
 
 pub mod aggregates;
+pub mod approx_expressions;
 pub mod array_expressions;
+pub mod collection_expressions;
 pub mod common;
+pub mod cross_join;
 pub mod csv;
 pub mod datetime_expressions;
 pub mod distinct_expressions;
@@ -261,6 +295,7 @@ pub mod group_scalar;
 pub mod hash_aggregate;
 pub mod hash_join;
 pub mod hash_utils;
+pub mod json;
 pub mod limit;
 pub mod math_expressions;
 pub mod memory;
@@ -270,7 +305,14 @@ pub mod planner;
 pub mod projection;
 pub mod repartition;
 pub mod sort;
+pub mod sort_merge_join;
+pub mod sort_preserving_merge;
+pub mod statistical_expressions;
 pub mod string_expressions;
+pub mod top_k;
 pub mod type_coercion;
 pub mod udaf;
 pub mod udf;
+pub mod union;
+pub mod window_functions;
+pub mod windows;
@@ -16,8 +16,13 @@
 // under the License.
 
 //! Execution plan for reading Parquet files
+//!
+//! The row groups of the files can be pruned with a predicate: the row groups whose
+//! column statistics (min and max values, null counts) prove that none of their rows can
+//! match it are not read.
 
 use std::any::Any;
+use std::cmp::Ordering;
 use std::fs::File;
 use std::sync::Arc;
 use std::task::{Context, Poll};
@@ -25,18 +30,22 @@ use std::{fmt, thread};
 
 use super::{RecordBatchStream, SendableRecordBatchStream};
 use crate::error::{DataFusionError, Result};
+use crate::logical_plan::{Expr, Operator};
 use crate::physical_plan::ExecutionPlan;
 use crate::physical_plan::{common, Partitioning};
-use arrow::datatypes::{Schema, SchemaRef};
+use crate::scalar::ScalarValue;
+use arrow::datatypes::{DataType, Schema, SchemaRef};
 use arrow::error::{ArrowError, Result as ArrowResult};
 use arrow::record_batch::RecordBatch;
-use parquet::file::reader::SerializedFileReader;
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::statistics::Statistics as ParquetStatistics;
 
 use crossbeam::channel::{bounded, Receiver, RecvError, Sender};
 use fmt::Debug;
 use parquet::arrow::{ArrowReader, ParquetFileArrowReader};
 
-use crate::datasource::datasource::Statistics;
+use crate::datasource::datasource::{ColumnStatistics, Statistics};
 use async_trait::async_trait;
 use futures::stream::Stream;
 
@@ -51,6 +60,8 @@ pub struct ParquetExec {
     projection: Vec<usize>,
     /// Batch size
     batch_size: usize,
+    /// Optional number of rows to read from each partition
+    limit: Option<usize>,
     /// Statistics for the data set (sum of statistics for all partitions)
     statistics: Statistics,
 }
@@ -68,17 +79,22 @@ pub struct ParquetExec {
 pub struct ParquetPartition {
     /// The Parquet filename for this partition
     filename: String,
+    /// Indices of the row groups of the file to read
+    row_groups: Vec<usize>,
     /// Statistics for this partition
     statistics: Statistics,
 }
 
 impl ParquetExec {
     /// Create a new Parquet reader execution plan based on the specified Parquet filename or
-    /// directory containing Parquet files
+    /// directory containing Parquet files. The row groups which cannot match `predicate`
+    /// are skipped, and the reading of a file stops after its first `limit` rows.
     pub fn try_from_path(
         path: &str,
         projection: Option<Vec<usize>>,
+        predicate: Option<Expr>,
         batch_size: usize,
+        limit: Option<usize>,
     ) -> Result<Self> {
         // build a list of filenames from the specified path, which could be a single file or
         // a directory containing one or more parquet files
@@ -94,16 +110,19 @@ impl ParquetExec {
                 .iter()
                 .map(|filename| filename.as_str())
                 .collect::<Vec<&str>>();
-            Self::try_from_files(&filenames, projection, batch_size)
+            Self::try_from_files(&filenames, projection, predicate, batch_size, limit)
         }
     }
 
     /// Create a new Parquet reader execution plan based on the specified list of Parquet
-    /// files
+    /// files. The row groups which cannot match `predicate` are skipped, and the reading
+    /// of a file stops after its first `limit` rows.
     pub fn try_from_files(
         filenames: &[&str],
         projection: Option<Vec<usize>>,
+        predicate: Option<Expr>,
         batch_size: usize,
+        limit: Option<usize>,
     ) -> Result<Self> {
         // build a list of Parquet partitions with statistics and gather all unique schemas
         // used in this data set
@@ -114,25 +133,63 @@ impl ParquetExec {
             let file_reader = Arc::new(SerializedFileReader::new(file)?);
             let mut arrow_reader = ParquetFileArrowReader::new(file_reader);
             let meta_data = arrow_reader.get_metadata();
-            // collect all the unique schemas in this data set
             let schema = arrow_reader.get_schema()?;
-            if schemas.is_empty() || schema != schemas[0] {
-                schemas.push(schema);
-            }
+            let num_fields = schema.fields().len();
+            let predicate = predicate
+                .as_ref()
+                .map(|expr| RowGroupPredicate::new(expr, &schema));
+            let mut row_groups = vec![];
             let mut num_rows = 0;
             let mut total_byte_size = 0;
+            // the statistics of the columns are only mapped to the fields of flat schemas
+            let mut column_statistics = Some(vec![EMPTY_COLUMN_STATISTICS; num_fields])
+                .filter(|_| {
+                    meta_data.file_metadata().schema_descr().num_columns() == num_fields
+                });
             for i in 0..meta_data.num_row_groups() {
+                // the next row groups are not needed once there are enough rows
+                if limit.map_or(false, |limit| num_rows as usize >= limit) {
+                    break;
+                }
                 let row_group_meta = meta_data.row_group(i);
+                if let Some(predicate) = &predicate {
+                    if !predicate.may_match(row_group_meta) {
+                        continue;
+                    }
+                }
+                row_groups.push(i);
                 num_rows += row_group_meta.num_rows();
                 total_byte_size += row_group_meta.total_byte_size();
+                column_statistics = column_statistics.and_then(|columns| {
+                    columns
+                        .iter()
+                        .enumerate()
+                        .map(|(column, merged)| {
+                            let statistics =
+                                row_group_meta.column(column).statistics()?;
+                            let row_group = ColumnStatistics {
+                                null_count: Some(statistics.null_count() as usize),
+                                distinct_count: statistics
+                                    .distinct_count()
+                                    .map(|count| count as usize),
+                            };
+                            Some(merge_column_statistics(merged, &row_group))
+                        })
+                        .collect()
+                });
             }
             let statistics = Statistics {
                 num_rows: Some(num_rows as usize),
                 total_byte_size: Some(total_byte_size as usize),
-                column_statistics: None,
+                column_statistics,
             };
+            // collect all the unique schemas in this data set
+            if schemas.is_empty() || schema != schemas[0] {
+                schemas.push(schema);
+            }
             partitions.push(ParquetPartition {
                 filename: filename.to_owned().to_string(),
+                row_groups,
                 statistics,
             });
         }
@@ -149,7 +206,7 @@ impl ParquetExec {
         }
         let schema = schemas[0].clone();
 
-        Ok(Self::new(partitions, schema, projection, batch_size))
+        Ok(Self::new(partitions, schema, projection, batch_size, limit))
     }
 
     /// Create a new Parquet reader execution plan with provided partitions and schema
@@ -158,6 +215,7 @@ impl ParquetExec {
         schema: Schema,
         projection: Option<Vec<usize>>,
         batch_size: usize,
+        limit: Option<usize>,
     ) -> Self {
         let projection = match projection {
             Some(p) => p,
@@ -174,6 +232,8 @@ impl ParquetExec {
         // sum the statistics
         let mut num_rows: Option<usize> = None;
         let mut total_byte_size: Option<usize> = None;
+        let mut column_statistics =
+            Some(vec![EMPTY_COLUMN_STATISTICS; schema.fields().len()]);
         for part in &partitions {
             if let Some(n) = part.statistics.num_rows {
                 num_rows = Some(num_rows.unwrap_or(0) + n)
@@ -181,17 +241,32 @@ impl ParquetExec {
             if let Some(n) = part.statistics.total_byte_size {
                 total_byte_size = Some(total_byte_size.unwrap_or(0) + n)
             }
+            // the column statistics are only known if they are known for all partitions
+            column_statistics =
+                match (column_statistics, &part.statistics.column_statistics) {
+                    (Some(merged), Some(columns)) => Some(
+                        merged
+                            .iter()
+                            .zip(columns)
+                            .map(|(merged, column)| {
+                                merge_column_statistics(merged, column)
+                            })
+                            .collect(),
+                    ),
+                    _ => None,
+                };
         }
         let statistics = Statistics {
             num_rows,
             total_byte_size,
-            column_statistics: None,
+            column_statistics,
         };
         Self {
             partitions,
             schema: Arc::new(projected_schema),
             projection,
             batch_size,
+            limit,
             statistics,
         }
     }
@@ -246,11 +321,20 @@ impl ExecutionPlan for ParquetExec {
         ) = bounded(2);
 
         let filename = self.partitions[partition].filename.clone();
+        let row_groups = self.partitions[partition].row_groups.clone();
         let projection = self.projection.clone();
         let batch_size = self.batch_size;
+        let limit = self.limit;
 
         thread::spawn(move || {
-            if let Err(e) = read_file(&filename, projection, batch_size, response_tx) {
+            if let Err(e) = read_file(
+                &filename,
+                row_groups,
+                projection,
+                batch_size,
+                limit,
+                response_tx,
+            ) {
                 println!("Parquet reader thread terminated due to error: {:?}", e);
             }
         });
@@ -274,18 +358,37 @@ fn send_result(
 
 fn read_file(
     filename: &str,
+    row_groups: Vec<usize>,
     projection: Vec<usize>,
     batch_size: usize,
+    limit: Option<usize>,
     response_tx: Sender<Option<ArrowResult<RecordBatch>>>,
 ) -> Result<()> {
+    if row_groups.is_empty() {
+        // all the row groups of the file were pruned
+        return send_result(&response_tx, None);
+    }
     let file = File::open(&filename)?;
-    let file_reader = Arc::new(SerializedFileReader::new(file)?);
+    let mut file_reader = SerializedFileReader::new(file)?;
+    if row_groups.len() < file_reader.num_row_groups() {
+        file_reader.filter_row_groups(&|_, i| row_groups.contains(&i));
+    }
+    let file_reader = Arc::new(file_reader);
     let mut arrow_reader = ParquetFileArrowReader::new(file_reader);
     let mut batch_reader =
         arrow_reader.get_record_reader_by_columns(projection, batch_size)?;
+    let mut num_rows = 0;
     loop {
+        if limit.map_or(false, |limit| num_rows >= limit) {
+            // the rows after the limit are not needed
+            send_result(&response_tx, None)?;
+            break;
+        }
         match batch_reader.next() {
-            Some(Ok(batch)) => send_result(&response_tx, Some(Ok(batch)))?,
+            Some(Ok(batch)) => {
+                num_rows += batch.num_rows();
+                send_result(&response_tx, Some(Ok(batch)))?
+            }
             None => {
                 // finished reading file
                 send_result(&response_tx, None)?;
@@ -333,17 +436,294 @@ impl RecordBatchStream for ParquetStream {
     }
 }
 
+/// The statistics of a column without any rows
+const EMPTY_COLUMN_STATISTICS: ColumnStatistics = ColumnStatistics {
+    null_count: Some(0),
+    distinct_count: Some(0),
+};
+
+/// Merges the statistics of a column in two row groups or partitions. The null counts
+/// are added up, while the distinct count is estimated as the largest of the two, since
+/// the values of different row groups may overlap.
+fn merge_column_statistics(
+    left: &ColumnStatistics,
+    right: &ColumnStatistics,
+) -> ColumnStatistics {
+    ColumnStatistics {
+        null_count: left
+            .null_count
+            .and_then(|left| Some(left + right.null_count?)),
+        distinct_count: left
+            .distinct_count
+            .and_then(|left| Some(left.max(right.distinct_count?))),
+    }
+}
+
+/// Predicate on the rows of a Parquet file, evaluated against the column statistics of
+/// its row groups. Only the conjunctions and disjunctions of comparisons of a column with
+/// a literal, `BETWEEN`, `IN` with a list of literals and `IS [NOT] NULL` on a column are
+/// used, any other expression may match any row group.
+#[derive(Debug)]
+struct RowGroupPredicate<'a> {
+    /// The predicate
+    expr: &'a Expr,
+    /// Schema of the file
+    schema: &'a Schema,
+}
+
+impl<'a> RowGroupPredicate<'a> {
+    fn new(expr: &'a Expr, schema: &'a Schema) -> Self {
+        Self { expr, schema }
+    }
+
+    /// Whether some rows of the row group `row_group` may match the predicate
+    fn may_match(&self, row_group: &RowGroupMetaData) -> bool {
+        self.expr_may_match(self.expr, row_group)
+    }
+
+    fn expr_may_match(&self, expr: &Expr, row_group: &RowGroupMetaData) -> bool {
+        match expr {
+            Expr::BinaryExpr {
+                left,
+                op: Operator::And,
+                right,
+            } => {
+                self.expr_may_match(left, row_group)
+                    && self.expr_may_match(right, row_group)
+            }
+            Expr::BinaryExpr {
+                left,
+                op: Operator::Or,
+                right,
+            } => {
+                self.expr_may_match(left, row_group)
+                    || self.expr_may_match(right, row_group)
+            }
+            Expr::BinaryExpr { left, op, right } => match (left.as_ref(), right.as_ref())
+            {
+                (Expr::Column(name), Expr::Literal(value)) => {
+                    self.comparison_may_match(name, op, value, row_group)
+                }
+                (Expr::Literal(value), Expr::Column(name)) => match swap_operator(op) {
+                    Some(op) => self.comparison_may_match(name, &op, value, row_group),
+                    None => true,
+                },
+                _ => true,
+            },
+            Expr::Between {
+                expr,
+                negated: false,
+                low,
+                high,
+            } => match (expr.as_ref(), low.as_ref(), high.as_ref()) {
+                (Expr::Column(name), Expr::Literal(low), Expr::Literal(high)) => {
+                    self.comparison_may_match(name, &Operator::GtEq, low, row_group)
+                        && self.comparison_may_match(
+                            name,
+                            &Operator::LtEq,
+                            high,
+                            row_group,
+                        )
+                }
+                _ => true,
+            },
+            Expr::InList {
+                expr,
+                list,
+                negated: false,
+            } => match expr.as_ref() {
+                Expr::Column(name) => list.iter().any(|item| match item {
+                    Expr::Literal(value) => {
+                        self.comparison_may_match(name, &Operator::Eq, value, row_group)
+                    }
+                    _ => true,
+                }),
+                _ => true,
+            },
+            Expr::IsNull(expr) => match expr.as_ref() {
+                Expr::Column(name) => self
+                    .column_statistics(name, row_group)
+                    .map_or(true, |statistics| statistics.null_count() > 0),
+                _ => true,
+            },
+            Expr::IsNotNull(expr) => match expr.as_ref() {
+                Expr::Column(name) => self
+                    .column_statistics(name, row_group)
+                    .map_or(true, |statistics| {
+                        (statistics.null_count() as i64) < row_group.num_rows()
+                    }),
+                _ => true,
+            },
+            _ => true,
+        }
+    }
+
+    /// Whether some values of the column `name` in `row_group` may match `name op value`
+    fn comparison_may_match(
+        &self,
+        name: &str,
+        op: &Operator,
+        value: &ScalarValue,
+        row_group: &RowGroupMetaData,
+    ) -> bool {
+        let (min, max) = match self.min_max(name, row_group) {
+            Some(min_max) => min_max,
+            None => return true,
+        };
+        let (min, max) =
+            match (compare_scalars(&min, value), compare_scalars(&max, value)) {
+                (Some(min), Some(max)) => (min, max),
+                _ => return true,
+            };
+        match op {
+            Operator::Eq => min != Ordering::Greater && max != Ordering::Less,
+            Operator::NotEq => !(min == Ordering::Equal && max == Ordering::Equal),
+            Operator::Lt => min == Ordering::Less,
+            Operator::LtEq => min != Ordering::Greater,
+            Operator::Gt => max == Ordering::Greater,
+            Operator::GtEq => max != Ordering::Less,
+            _ => true,
+        }
+    }
+
+    /// The statistics of the column `name` in `row_group`, if any
+    fn column_statistics<'b>(
+        &self,
+        name: &str,
+        row_group: &'b RowGroupMetaData,
+    ) -> Option<&'b ParquetStatistics> {
+        // the columns of the row group are only the fields of flat schemas
+        if row_group.num_columns() != self.schema.fields().len() {
+            return None;
+        }
+        let index = self.schema.index_of(name).ok()?;
+        row_group.column(index).statistics()
+    }
+
+    /// The min and max values of the column `name` in `row_group`, if they are known and
+    /// ordered as the values of the column
+    fn min_max(
+        &self,
+        name: &str,
+        row_group: &RowGroupMetaData,
+    ) -> Option<(ScalarValue, ScalarValue)> {
+        let statistics = self.column_statistics(name, row_group)?;
+        if !statistics.has_min_max_set() {
+            return None;
+        }
+        let data_type = self.schema.field_with_name(name).ok()?.data_type();
+        match (statistics, data_type) {
+            (ParquetStatistics::Boolean(s), DataType::Boolean) => Some((
+                ScalarValue::Boolean(Some(*s.min())),
+                ScalarValue::Boolean(Some(*s.max())),
+            )),
+            (ParquetStatistics::Int32(s), DataType::Int8)
+            | (ParquetStatistics::Int32(s), DataType::Int16)
+            | (ParquetStatistics::Int32(s), DataType::Int32) => Some((
+                ScalarValue::Int32(Some(*s.min())),
+                ScalarValue::Int32(Some(*s.max())),
+            )),
+            (ParquetStatistics::Int64(s), DataType::Int64) => Some((
+                ScalarValue::Int64(Some(*s.min())),
+                ScalarValue::Int64(Some(*s.max())),
+            )),
+            (ParquetStatistics::Float(s), DataType::Float32) => Some((
+                ScalarValue::Float32(Some(*s.min())),
+                ScalarValue::Float32(Some(*s.max())),
+            )),
+            (ParquetStatistics::Double(s), DataType::Float64) => Some((
+                ScalarValue::Float64(Some(*s.min())),
+                ScalarValue::Float64(Some(*s.max())),
+            )),
+            // the deprecated min and max of byte arrays were ordered as signed bytes
+            (ParquetStatistics::ByteArray(s), DataType::Utf8)
+                if !s.is_min_max_deprecated() =>
+            {
+                Some((
+                    ScalarValue::Utf8(Some(s.min().as_utf8().ok()?.to_owned())),
+                    ScalarValue::Utf8(Some(s.max().as_utf8().ok()?.to_owned())),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The operator `swapped` such that `a op b` is `b swapped a`
+fn swap_operator(op: &Operator) -> Option<Operator> {
+    match op {
+        Operator::Eq => Some(Operator::Eq),
+        Operator::NotEq => Some(Operator::NotEq),
+        Operator::Lt => Some(Operator::Gt),
+        Operator::LtEq => Some(Operator::GtEq),
+        Operator::Gt => Some(Operator::Lt),
+        Operator::GtEq => Some(Operator::LtEq),
+        _ => None,
+    }
+}
+
+/// Compares two non null boolean, string or numeric values, `None` if they cannot be
+/// compared
+fn compare_scalars(left: &ScalarValue, right: &ScalarValue) -> Option<Ordering> {
+    match (left, right) {
+        (ScalarValue::Boolean(Some(left)), ScalarValue::Boolean(Some(right))) => {
+            Some(left.cmp(right))
+        }
+        (ScalarValue::Utf8(Some(left)), ScalarValue::Utf8(Some(right))) => {
+            Some(left.cmp(right))
+        }
+        _ => match (scalar_to_i128(left), scalar_to_i128(right)) {
+            (Some(left), Some(right)) => Some(left.cmp(&right)),
+            _ => scalar_to_f64(left)?.partial_cmp(&scalar_to_f64(right)?),
+        },
+    }
+}
+
+/// The value of a non null integer
+fn scalar_to_i128(value: &ScalarValue) -> Option<i128> {
+    match value {
+        ScalarValue::Int8(Some(v)) => Some(*v as i128),
+        ScalarValue::Int16(Some(v)) => Some(*v as i128),
+        ScalarValue::Int32(Some(v)) => Some(*v as i128),
+        ScalarValue::Int64(Some(v)) => Some(*v as i128),
+        ScalarValue::UInt8(Some(v)) => Some(*v as i128),
+        ScalarValue::UInt16(Some(v)) => Some(*v as i128),
+        ScalarValue::UInt32(Some(v)) => Some(*v as i128),
+        ScalarValue::UInt64(Some(v)) => Some(*v as i128),
+        _ => None,
+    }
+}
+
+/// The value of a non null number
+fn scalar_to_f64(value: &ScalarValue) -> Option<f64> {
+    match value {
+        ScalarValue::Float32(Some(v)) => Some(*v as f64),
+        ScalarValue::Float64(Some(v)) => Some(*v),
+        _ => scalar_to_i128(value).map(|v| v as f64),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::logical_plan::{col, in_list, lit};
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::Field;
     use futures::StreamExt;
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::{Repetition, Type as PhysicalType};
+    use parquet::column::writer::ColumnWriter;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::{FileWriter, RowGroupWriter, SerializedFileWriter};
+    use parquet::schema::types::Type;
+    use tempfile::TempDir;
 
     #[tokio::test]
     async fn test() -> Result<()> {
         let testdata = arrow::util::test_util::parquet_test_data();
         let filename = format!("{}/alltypes_plain.parquet", testdata);
         let parquet_exec =
-            ParquetExec::try_from_path(&filename, Some(vec![0, 1, 2]), 1024)?;
+            ParquetExec::try_from_path(&filename, Some(vec![0, 1, 2]), None, 1024, None)?;
         assert_eq!(parquet_exec.output_partitioning().partition_count(), 1);
 
         let mut results = parquet_exec.execute(0).await?;
@@ -368,4 +748,197 @@ mod tests {
 
         Ok(())
     }
+
+    /// Writes a Parquet file with a row group of 3 rows for each of the values of `a`
+    fn write_row_groups(dir: &TempDir, a: Vec<Vec<Option<i32>>>) -> Result<String> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+        let filename = dir.path().join("row_groups.parquet");
+        let mut writer =
+            ArrowWriter::try_new(File::create(&filename)?, schema.clone(), None)?;
+        for (i, a) in a.into_iter().enumerate() {
+            let b = vec![format!("b{}", i); a.len()];
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from(a)),
+                    Arc::new(StringArray::from(
+                        b.iter().map(|b| b.as_str()).collect::<Vec<_>>(),
+                    )),
+                ],
+            )?;
+            // each batch is written in its own row group
+            writer.write(&batch)?;
+        }
+        writer.close()?;
+        Ok(filename.to_str().unwrap().to_owned())
+    }
+
+    async fn read_column_a(exec: &ParquetExec) -> Result<Vec<Option<i32>>> {
+        let batches = common::collect(exec.execute(0).await?).await?;
+        Ok(batches
+            .iter()
+            .flat_map(|batch| {
+                let a = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int32Array>()
+                    .unwrap();
+                a.iter().collect::<Vec<_>>()
+            })
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn prune_row_groups() -> Result<()> {
+        let dir = TempDir::new()?;
+        let filename = write_row_groups(
+            &dir,
+            vec![
+                vec![Some(1), Some(2), Some(3)],
+                vec![Some(4), None, Some(6)],
+                vec![Some(7), Some(8), Some(9)],
+            ],
+        )?;
+
+        let exec = ParquetExec::try_from_path(&filename, None, None, 1024, None)?;
+        assert_eq!(exec.statistics().num_rows, Some(9));
+        assert_eq!(
+            exec.statistics().column_statistics,
+            Some(vec![
+                ColumnStatistics {
+                    null_count: Some(1),
+                    distinct_count: None,
+                },
+                ColumnStatistics {
+                    null_count: Some(0),
+                    distinct_count: None,
+                },
+            ])
+        );
+
+        let predicate = col("a").gt(lit(5i64)).and(col("a").lt_eq(lit(7)));
+        let exec =
+            ParquetExec::try_from_path(&filename, None, Some(predicate), 1024, None)?;
+        assert_eq!(exec.statistics().num_rows, Some(6));
+        // the rows of the row groups which may match are all read
+        assert_eq!(
+            read_column_a(&exec).await?,
+            vec![Some(4), None, Some(6), Some(7), Some(8), Some(9)]
+        );
+
+        let predicate = lit(3).gt_eq(col("a")).or(Expr::IsNull(Box::new(col("a"))));
+        let exec =
+            ParquetExec::try_from_path(&filename, None, Some(predicate), 1024, None)?;
+        assert_eq!(exec.statistics().num_rows, Some(6));
+
+        let predicate = col("b").eq(lit("b2"));
+        let exec =
+            ParquetExec::try_from_path(&filename, None, Some(predicate), 1024, None)?;
+        assert_eq!(exec.statistics().num_rows, Some(3));
+        assert_eq!(read_column_a(&exec).await?, vec![Some(7), Some(8), Some(9)]);
+
+        let predicate = in_list(col("a"), vec![lit(2), lit(9)], false);
+        let exec =
+            ParquetExec::try_from_path(&filename, None, Some(predicate), 1024, None)?;
+        assert_eq!(exec.statistics().num_rows, Some(6));
+
+        // NOT IN is not checked against the statistics
+        let predicate = in_list(col("a"), vec![lit(2), lit(9)], true);
+        let exec =
+            ParquetExec::try_from_path(&filename, None, Some(predicate), 1024, None)?;
+        assert_eq!(exec.statistics().num_rows, Some(9));
+
+        let predicate = col("a").gt(lit(10));
+        let exec =
+            ParquetExec::try_from_path(&filename, None, Some(predicate), 1024, None)?;
+        assert_eq!(exec.statistics().num_rows, Some(0));
+        assert!(read_column_a(&exec).await?.is_empty());
+
+        // expressions which cannot be checked against the statistics match all row groups
+        let predicate = (col("a") + lit(1)).gt(lit(10));
+        let exec =
+            ParquetExec::try_from_path(&filename, None, Some(predicate), 1024, None)?;
+        assert_eq!(exec.statistics().num_rows, Some(9));
+
+        Ok(())
+    }
+
+    /// Writes a Parquet file with a row group for each of the values of the column `a`,
+    /// along with their distinct counts, which are not written by the ArrowWriter
+    fn write_distinct_counts(dir: &TempDir, a: Vec<(Vec<i32>, u64)>) -> Result<String> {
+        let schema = Arc::new(
+            Type::group_type_builder("schema")
+                .with_fields(&mut vec![Arc::new(
+                    Type::primitive_type_builder("a", PhysicalType::INT32)
+                        .with_repetition(Repetition::REQUIRED)
+                        .build()?,
+                )])
+                .build()?,
+        );
+        let filename = dir.path().join("distinct_counts.parquet");
+        let props = Arc::new(WriterProperties::builder().build());
+        let mut writer =
+            SerializedFileWriter::new(File::create(&filename)?, schema, props)?;
+        for (values, distinct_count) in a {
+            let mut row_group_writer = writer.next_row_group()?;
+            if let Some(mut column_writer) = row_group_writer.next_column()? {
+                if let ColumnWriter::Int32ColumnWriter(ref mut typed) = column_writer {
+                    let min = values.iter().min().cloned();
+                    let max = values.iter().max().cloned();
+                    typed.write_batch_with_statistics(
+                        &values,
+                        None,
+                        None,
+                        &min,
+                        &max,
+                        Some(0),
+                        Some(distinct_count),
+                    )?;
+                }
+                row_group_writer.close_column(column_writer)?;
+            }
+            writer.close_row_group(row_group_writer)?;
+        }
+        writer.close()?;
+        Ok(filename.to_str().unwrap().to_owned())
+    }
+
+    #[test]
+    fn distinct_counts_of_row_groups() -> Result<()> {
+        let dir = TempDir::new()?;
+        let filename = write_distinct_counts(
+            &dir,
+            vec![(vec![1, 2, 3, 3], 3), (vec![2, 4, 5, 6, 7], 5)],
+        )?;
+
+        // the values of the row groups may overlap, so their largest distinct count is
+        // kept as an estimate
+        let exec = ParquetExec::try_from_path(&filename, None, None, 1024, None)?;
+        assert_eq!(exec.statistics().num_rows, Some(9));
+        assert_eq!(
+            exec.statistics().column_statistics,
+            Some(vec![ColumnStatistics {
+                null_count: Some(0),
+                distinct_count: Some(5),
+            }])
+        );
+
+        // the statistics of the pruned row groups are left out
+        let predicate = col("a").lt(lit(2));
+        let exec =
+            ParquetExec::try_from_path(&filename, None, Some(predicate), 1024, None)?;
+        assert_eq!(exec.statistics().num_rows, Some(4));
+        assert_eq!(
+            exec.statistics().column_statistics,
+            Some(vec![ColumnStatistics {
+                null_count: Some(0),
+                distinct_count: Some(3),
+            }])
+        );
+
+        Ok(())
+    }
 }
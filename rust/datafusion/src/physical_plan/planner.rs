@@ -17,6 +17,7 @@
 
 //! Physical query planner
 
+use std::convert::TryFrom;
 use std::sync::Arc;
 
 use super::{aggregates, empty::EmptyExec, expressions::binary, functions, udaf};
@@ -26,21 +27,36 @@ use crate::logical_plan::{
     DFSchema, Expr, LogicalPlan, Operator, Partitioning as LogicalPartitioning, PlanType,
     StringifiedPlan, UserDefinedLogicalNode,
 };
+use crate::physical_plan::cross_join::CrossJoinExec;
 use crate::physical_plan::explain::ExplainExec;
-use crate::physical_plan::expressions::{CaseExpr, Column, Literal, PhysicalSortExpr};
+use crate::physical_plan::expressions::{
+    CaseExpr, Column, GroupingExpr, Literal, PhysicalSortExpr,
+};
 use crate::physical_plan::filter::FilterExec;
-use crate::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
-use crate::physical_plan::hash_join::HashJoinExec;
-use crate::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
+use crate::physical_plan::hash_aggregate::{
+    AggregateMode, HashAggregateExec, GROUPING_ID_COLUMN,
+};
+use crate::physical_plan::hash_join::{HashJoinExec, PartitionMode};
+use crate::physical_plan::limit::{
+    EnforceSingleRowExec, GlobalLimitExec, LocalLimitExec,
+};
 use crate::physical_plan::merge::MergeExec;
 use crate::physical_plan::projection::ProjectionExec;
 use crate::physical_plan::repartition::RepartitionExec;
 use crate::physical_plan::sort::SortExec;
+use crate::physical_plan::sort_merge_join::SortMergeJoinExec;
+use crate::physical_plan::sort_preserving_merge::SortPreservingMergeExec;
+use crate::physical_plan::top_k::TopKExec;
 use crate::physical_plan::udf;
+use crate::physical_plan::union::UnionExec;
+use crate::physical_plan::windows::{self, WindowAggExec};
 use crate::physical_plan::{expressions, Distribution};
 use crate::physical_plan::{hash_utils, Partitioning};
-use crate::physical_plan::{AggregateExpr, ExecutionPlan, PhysicalExpr, PhysicalPlanner};
+use crate::physical_plan::{
+    AggregateExpr, ExecutionPlan, PhysicalExpr, PhysicalPlanner, WindowExpr,
+};
 use crate::prelude::JoinType;
+use crate::scalar::ScalarValue;
 use crate::variable::VarType;
 use arrow::compute::SortOptions;
 use arrow::datatypes::{Schema, SchemaRef};
@@ -141,11 +157,34 @@ impl DefaultPhysicalPlanner {
                 source,
                 projection,
                 filters,
+                limit,
                 ..
-            } => source.scan(projection, batch_size, filters),
+            } => source.scan(projection, batch_size, filters, *limit),
+            LogicalPlan::Window {
+                input, window_expr, ..
+            } => {
+                let input_exec = self.create_physical_plan(input, ctx_state)?;
+                let physical_input_schema = input_exec.as_ref().schema();
+                let logical_input_schema = input.as_ref().schema();
+
+                let window_expr = window_expr
+                    .iter()
+                    .map(|e| {
+                        self.create_window_expr(
+                            e,
+                            &logical_input_schema,
+                            &physical_input_schema,
+                            ctx_state,
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok(Arc::new(WindowAggExec::try_new(window_expr, input_exec)?))
+            }
             LogicalPlan::Aggregate {
                 input,
                 group_expr,
+                grouping_sets,
                 aggr_expr,
                 ..
             } => {
@@ -167,9 +206,14 @@ impl DefaultPhysicalPlanner {
                         ))
                     })
                     .collect::<Result<Vec<_>>>()?;
+                // GROUPING is not aggregated but computed from the grouping sets
+                let grouping_args =
+                    aggr_expr.iter().map(grouping_args).collect::<Vec<_>>();
                 let aggregates = aggr_expr
                     .iter()
-                    .map(|e| {
+                    .zip(&grouping_args)
+                    .filter(|(_, args)| args.is_none())
+                    .map(|(e, _)| {
                         self.create_aggregate_expr(
                             e,
                             &logical_input_schema,
@@ -179,28 +223,115 @@ impl DefaultPhysicalPlanner {
                     })
                     .collect::<Result<Vec<_>>>()?;
 
-                let initial_aggr = Arc::new(HashAggregateExec::try_new(
-                    AggregateMode::Partial,
-                    groups.clone(),
-                    aggregates.clone(),
-                    input_exec,
-                )?);
+                // the groups of grouping sets are tagged with the index of their set, a
+                // GROUP BY being a single set when GROUPING is used
+                let grouping_sets = match grouping_sets {
+                    Some(grouping_sets) => Some(grouping_sets.clone()),
+                    None if grouping_args.iter().any(Option::is_some) => {
+                        Some(vec![(0..groups.len()).collect::<Vec<_>>()])
+                    }
+                    None => None,
+                };
 
+                let initial_aggr = match &grouping_sets {
+                    Some(grouping_sets) => HashAggregateExec::try_new_with_grouping_sets(
+                        AggregateMode::Partial,
+                        groups.clone(),
+                        grouping_sets
+                            .iter()
+                            .map(|set| {
+                                (0..groups.len()).map(|i| set.contains(&i)).collect()
+                            })
+                            .collect(),
+                        aggregates.clone(),
+                        input_exec,
+                    )?,
+                    None => HashAggregateExec::try_new(
+                        AggregateMode::Partial,
+                        groups.clone(),
+                        aggregates.clone(),
+                        input_exec,
+                    )?,
+                };
+                let initial_aggr = Arc::new(
+                    initial_aggr.with_memory_limit(ctx_state.config.memory_limit),
+                );
+
+                // the groups of grouping sets are merged by group and grouping set
+                let mut final_group_names = groups
+                    .iter()
+                    .map(|(_, name)| name.clone())
+                    .collect::<Vec<_>>();
+                if grouping_sets.is_some() {
+                    final_group_names.push(GROUPING_ID_COLUMN.to_string());
+                }
                 let final_group: Vec<Arc<dyn PhysicalExpr>> =
-                    (0..groups.len()).map(|i| col(&groups[i].1)).collect();
+                    final_group_names.iter().map(|name| col(name)).collect();
+
+                // the partial aggregates of a group can be merged in parallel once they
+                // are repartitioned on the grouping keys
+                let (initial_aggr, final_mode) = if !final_group.is_empty()
+                    && ctx_state.config.concurrency > 1
+                    && ctx_state.config.repartition_aggregations
+                {
+                    let repartition = Arc::new(RepartitionExec::try_new(
+                        initial_aggr,
+                        Partitioning::Hash(
+                            final_group.clone(),
+                            ctx_state.config.concurrency,
+                        ),
+                    )?);
+                    (
+                        repartition as Arc<dyn ExecutionPlan>,
+                        AggregateMode::FinalPartitioned,
+                    )
+                } else {
+                    (initial_aggr as Arc<dyn ExecutionPlan>, AggregateMode::Final)
+                };
 
                 // construct a second aggregation, keeping the final column name equal to the first aggregation
                 // and the expressions corresponding to the respective aggregate
-                Ok(Arc::new(HashAggregateExec::try_new(
-                    AggregateMode::Final,
-                    final_group
-                        .iter()
-                        .enumerate()
-                        .map(|(i, expr)| (expr.clone(), groups[i].1.clone()))
-                        .collect(),
-                    aggregates,
-                    initial_aggr,
-                )?))
+                let final_aggr = Arc::new(
+                    HashAggregateExec::try_new(
+                        final_mode,
+                        final_group
+                            .iter()
+                            .zip(final_group_names)
+                            .map(|(expr, name)| (expr.clone(), name))
+                            .collect(),
+                        aggregates,
+                        initial_aggr,
+                    )?
+                    .with_memory_limit(ctx_state.config.memory_limit),
+                );
+
+                let grouping_sets = match grouping_sets {
+                    Some(grouping_sets) => grouping_sets,
+                    None => return Ok(final_aggr),
+                };
+
+                // the index of the grouping set of the groups is replaced by the values
+                // of GROUPING
+                let mut projection = groups
+                    .iter()
+                    .map(|(_, name)| (col(name), name.clone()))
+                    .collect::<Vec<_>>();
+                for (e, args) in aggr_expr.iter().zip(&grouping_args) {
+                    let name = e.name(&logical_input_schema)?;
+                    let expr = match args {
+                        Some(args) => {
+                            let values = grouping_sets
+                                .iter()
+                                .map(|set| grouping_value(args, group_expr, set))
+                                .collect::<Result<Vec<_>>>()?;
+                            Arc::new(GroupingExpr::new(col(GROUPING_ID_COLUMN), values))
+                                as Arc<dyn PhysicalExpr>
+                        }
+                        None => col(&name),
+                    };
+                    projection.push((expr, name));
+                }
+                Ok(Arc::new(ProjectionExec::try_new(projection, final_aggr)?))
             }
             LogicalPlan::Projection { input, expr, .. } => {
                 let input_exec = self.create_physical_plan(input, ctx_state)?;
@@ -254,7 +385,7 @@ impl DefaultPhysicalPlanner {
                     physical_partitioning,
                 )?))
             }
-            LogicalPlan::Sort { expr, input, .. } => {
+            LogicalPlan::Sort { expr, input, fetch } => {
                 let input = self.create_physical_plan(input, ctx_state)?;
                 let input_schema = input.as_ref().schema();
 
@@ -280,16 +411,39 @@ impl DefaultPhysicalPlanner {
                     })
                     .collect::<Result<Vec<_>>>()?;
 
-                Ok(Arc::new(SortExec::try_new(
-                    sort_expr,
-                    input,
-                    ctx_state.config.concurrency,
-                )?))
+                let input_partitions = input.output_partitioning().partition_count();
+                let sort: Arc<dyn ExecutionPlan> = match fetch {
+                    // only the first rows of each partition are needed
+                    Some(fetch) => {
+                        Arc::new(TopKExec::new(sort_expr.clone(), input, *fetch))
+                    }
+                    None => {
+                        let sort = SortExec::try_new(
+                            sort_expr.clone(),
+                            input,
+                            ctx_state.config.concurrency,
+                        )?
+                        .with_memory_limit(ctx_state.config.memory_limit);
+                        if input_partitions == 1 {
+                            return Ok(Arc::new(sort));
+                        }
+                        Arc::new(sort.with_preserve_partitioning(true))
+                    }
+                };
+                if input_partitions == 1 {
+                    Ok(sort)
+                } else {
+                    // sort the partitions in parallel, then merge them
+                    Ok(Arc::new(SortPreservingMergeExec::new(
+                        sort_expr, sort, batch_size,
+                    )))
+                }
             }
             LogicalPlan::Join {
                 left,
                 right,
                 on: keys,
+                filter,
                 join_type,
                 ..
             } => {
@@ -299,14 +453,98 @@ impl DefaultPhysicalPlanner {
                     JoinType::Inner => hash_utils::JoinType::Inner,
                     JoinType::Left => hash_utils::JoinType::Left,
                     JoinType::Right => hash_utils::JoinType::Right,
+                    JoinType::Full => hash_utils::JoinType::Full,
+                    JoinType::Semi => hash_utils::JoinType::Semi,
+                    JoinType::Anti => hash_utils::JoinType::Anti,
                 };
 
-                Ok(Arc::new(HashJoinExec::try_new(
-                    left,
-                    right,
-                    &keys,
-                    &physical_join_type,
-                )?))
+                let left_keys = keys.iter().map(|(l, _)| l.clone()).collect::<Vec<_>>();
+                let right_keys = keys.iter().map(|(_, r)| r.clone()).collect::<Vec<_>>();
+
+                // the filter is evaluated by a hash join, against the columns of both
+                // sides
+                let filter = filter
+                    .as_ref()
+                    .map(|filter| {
+                        let schema = hash_utils::build_filter_schema(
+                            &left.schema(),
+                            &right.schema(),
+                        );
+                        self.create_physical_expr(filter, &schema, ctx_state)
+                    })
+                    .transpose()?;
+
+                // sides that are already sorted on the join keys are merged as they are
+                if let (None, Some(left_options), Some(right_options)) = (
+                    &filter,
+                    join_key_sort_options(left.as_ref(), &left_keys),
+                    join_key_sort_options(right.as_ref(), &right_keys),
+                ) {
+                    let same_options =
+                        left_options.iter().zip(right_options.iter()).all(|(l, r)| {
+                            l.descending == r.descending && l.nulls_first == r.nulls_first
+                        });
+                    if same_options {
+                        return Ok(Arc::new(SortMergeJoinExec::try_new(
+                            left,
+                            right,
+                            &keys,
+                            &physical_join_type,
+                            left_options,
+                            batch_size,
+                        )?));
+                    }
+                }
+
+                // a join without keys compares every row of the collected left side
+                // with every row of the right side
+                let partitioned = ctx_state.config.concurrency > 1
+                    && ctx_state.config.repartition_joins
+                    && !keys.is_empty();
+                let (left, right) = if partitioned {
+                    repartition_join_sides(left, right, keys, ctx_state)?
+                } else {
+                    (left, right)
+                };
+
+                if ctx_state.config.sort_merge_joins && filter.is_none() {
+                    // each partition of both sides is sorted on the join keys
+                    let left = sort_join_side(left, &left_keys, partitioned, ctx_state)?;
+                    let right =
+                        sort_join_side(right, &right_keys, partitioned, ctx_state)?;
+                    return Ok(Arc::new(SortMergeJoinExec::try_new(
+                        left,
+                        right,
+                        &keys,
+                        &physical_join_type,
+                        vec![SortOptions::default(); keys.len()],
+                        batch_size,
+                    )?));
+                }
+
+                let mode = if partitioned {
+                    PartitionMode::Partitioned
+                } else {
+                    PartitionMode::CollectLeft
+                };
+                let join =
+                    HashJoinExec::try_new(left, right, &keys, &physical_join_type, mode)?;
+                Ok(Arc::new(match filter {
+                    Some(filter) => join.with_filter(filter),
+                    None => join,
+                }))
+            }
+            LogicalPlan::CrossJoin { left, right, .. } => {
+                let left = self.create_physical_plan(left, ctx_state)?;
+                let right = self.create_physical_plan(right, ctx_state)?;
+                Ok(Arc::new(CrossJoinExec::new(left, right)))
+            }
+            LogicalPlan::Union { inputs, .. } => {
+                let physical_plans = inputs
+                    .iter()
+                    .map(|input| self.create_physical_plan(input, ctx_state))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Arc::new(UnionExec::new(physical_plans)))
             }
             LogicalPlan::EmptyRelation {
                 produce_one_row,
@@ -320,7 +558,18 @@ impl DefaultPhysicalPlanner {
                 let input = self.create_physical_plan(input, ctx_state)?;
 
                 // GlobalLimitExec requires a single partition for input
-                let input = if input.output_partitioning().partition_count() == 1 {
+                let input: Arc<dyn ExecutionPlan> = if let Some(merge) = input
+                    .as_any()
+                    .downcast_ref::<SortPreservingMergeExec>()
+                    .filter(|merge| merge.input().as_any().is::<SortExec>())
+                {
+                    // only the first rows of each sorted partition need to be merged
+                    Arc::new(SortPreservingMergeExec::new(
+                        merge.expr().to_vec(),
+                        Arc::new(LocalLimitExec::new(merge.input().clone(), limit)),
+                        batch_size,
+                    ))
+                } else if input.output_partitioning().partition_count() == 1 {
                     input
                 } else {
                     // Apply a LocalLimitExec to each partition. The optimizer will also insert
@@ -334,6 +583,10 @@ impl DefaultPhysicalPlanner {
                     ctx_state.config.concurrency,
                 )))
             }
+            LogicalPlan::EnforceSingleRow { input } => {
+                let input = self.create_physical_plan(input, ctx_state)?;
+                Ok(Arc::new(EnforceSingleRowExec::new(input)))
+            }
             LogicalPlan::CreateExternalTable { .. } => {
                 // There is no default plan for "CREATE EXTERNAL
                 // TABLE" -- it must be handled at a higher level (so
@@ -564,6 +817,26 @@ impl DefaultPhysicalPlanner {
                     binary_expr
                 }
             }
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let value_expr =
+                    self.create_physical_expr(expr, input_schema, ctx_state)?;
+                let value_type = value_expr.data_type(input_schema)?;
+                let list_exprs = list
+                    .iter()
+                    .map(|item| match item {
+                        // a NULL of the list takes the type of the value
+                        Expr::Literal(value) if value.is_null() => {
+                            Ok(expressions::lit(ScalarValue::try_from(&value_type)?))
+                        }
+                        _ => self.create_physical_expr(item, input_schema, ctx_state),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                expressions::in_list(value_expr, list_exprs, *negated, input_schema)
+            }
             other => Err(DataFusionError::NotImplemented(format!(
                 "Physical plan does not support logical expression {:?}",
                 other
@@ -571,6 +844,63 @@ impl DefaultPhysicalPlanner {
         }
     }
 
+    /// Create a window expression from a logical expression
+    pub fn create_window_expr(
+        &self,
+        e: &Expr,
+        logical_input_schema: &DFSchema,
+        physical_input_schema: &Schema,
+        ctx_state: &ExecutionContextState,
+    ) -> Result<Arc<dyn WindowExpr>> {
+        // unpack aliased logical expressions, e.g. "sum(col) over () as total"
+        let (name, e) = match e {
+            Expr::Alias(sub_expr, alias) => (alias.clone(), sub_expr.as_ref()),
+            _ => (e.name(logical_input_schema)?, e),
+        };
+
+        match e {
+            Expr::WindowFunction {
+                fun,
+                args,
+                partition_by,
+                order_by,
+                window_frame,
+            } => {
+                let args = args
+                    .iter()
+                    .map(|e| {
+                        self.create_physical_expr(e, physical_input_schema, ctx_state)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let partition_by = partition_by
+                    .iter()
+                    .map(|e| {
+                        self.create_physical_expr(e, physical_input_schema, ctx_state)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let order_by = self.create_physical_sort_exprs(
+                    order_by,
+                    physical_input_schema,
+                    ctx_state,
+                )?;
+
+                windows::create_window_expr(
+                    fun,
+                    name,
+                    &args,
+                    &partition_by,
+                    &order_by,
+                    *window_frame,
+                    physical_input_schema,
+                )
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "Invalid window expression '{:?}'",
+                other
+            ))),
+        }
+    }
+
     /// Create an aggregate expression from a logical expression
     pub fn create_aggregate_expr(
         &self,
@@ -590,7 +920,7 @@ impl DefaultPhysicalPlanner {
                 fun,
                 distinct,
                 args,
-                ..
+                order_by,
             } => {
                 let args = args
                     .iter()
@@ -598,10 +928,16 @@ impl DefaultPhysicalPlanner {
                         self.create_physical_expr(e, physical_input_schema, ctx_state)
                     })
                     .collect::<Result<Vec<_>>>()?;
-                aggregates::create_aggregate_expr(
+                let order_by = self.create_physical_sort_exprs(
+                    order_by,
+                    physical_input_schema,
+                    ctx_state,
+                )?;
+                aggregates::create_ordered_aggregate_expr(
                     fun,
                     *distinct,
                     &args,
+                    &order_by,
                     physical_input_schema,
                     name,
                 )
@@ -623,6 +959,36 @@ impl DefaultPhysicalPlanner {
         }
     }
 
+    /// Create the physical sort expressions of the logical sort expressions `order_by`
+    fn create_physical_sort_exprs(
+        &self,
+        order_by: &[Expr],
+        input_schema: &Schema,
+        ctx_state: &ExecutionContextState,
+    ) -> Result<Vec<PhysicalSortExpr>> {
+        order_by
+            .iter()
+            .map(|e| match e {
+                Expr::Sort {
+                    expr,
+                    asc,
+                    nulls_first,
+                } => self.create_physical_sort_expr(
+                    expr,
+                    input_schema,
+                    SortOptions {
+                        descending: !*asc,
+                        nulls_first: *nulls_first,
+                    },
+                    ctx_state,
+                ),
+                _ => Err(DataFusionError::Plan(
+                    "Sort only accepts sort expressions".to_string(),
+                )),
+            })
+            .collect()
+    }
+
     /// Create an aggregate expression from a logical expression
     pub fn create_physical_sort_expr(
         &self,
@@ -638,6 +1004,113 @@ impl DefaultPhysicalPlanner {
     }
 }
 
+/// Returns the sort options of the join keys `keys` if `plan` is a single partition
+/// sorted on them, e.g. the output of a sort whose first sort expressions are `keys`
+fn join_key_sort_options(
+    plan: &dyn ExecutionPlan,
+    keys: &[String],
+) -> Option<Vec<SortOptions>> {
+    let expr = if let Some(sort) = plan.as_any().downcast_ref::<SortExec>() {
+        if plan.output_partitioning().partition_count() != 1 {
+            return None;
+        }
+        sort.expr()
+    } else if let Some(merge) = plan.as_any().downcast_ref::<SortPreservingMergeExec>() {
+        merge.expr()
+    } else {
+        return None;
+    };
+    if expr.len() < keys.len() {
+        return None;
+    }
+    keys.iter()
+        .zip(expr.iter())
+        .map(|(key, expr)| {
+            if expr.expr.to_string() == *key {
+                Some(expr.options)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Repartitions both sides of a join by the hash of their join keys `keys`, so that
+/// the rows with equal keys are in the same partition of both sides
+fn repartition_join_sides(
+    left: Arc<dyn ExecutionPlan>,
+    right: Arc<dyn ExecutionPlan>,
+    keys: &[(String, String)],
+    ctx_state: &ExecutionContextState,
+) -> Result<(Arc<dyn ExecutionPlan>, Arc<dyn ExecutionPlan>)> {
+    let left_expr = keys.iter().map(|(l, _)| col(l)).collect();
+    let right_expr = keys.iter().map(|(_, r)| col(r)).collect();
+    Ok((
+        Arc::new(RepartitionExec::try_new(
+            left,
+            Partitioning::Hash(left_expr, ctx_state.config.concurrency),
+        )?),
+        Arc::new(RepartitionExec::try_new(
+            right,
+            Partitioning::Hash(right_expr, ctx_state.config.concurrency),
+        )?),
+    ))
+}
+
+/// Sorts a side of a sort-merge join on its join keys `keys`, each partition
+/// separately when the join is `partitioned`, or all the partitions together
+fn sort_join_side(
+    input: Arc<dyn ExecutionPlan>,
+    keys: &[String],
+    partitioned: bool,
+    ctx_state: &ExecutionContextState,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let expr = keys
+        .iter()
+        .map(|key| PhysicalSortExpr {
+            expr: col(key),
+            options: SortOptions::default(),
+        })
+        .collect();
+    Ok(Arc::new(
+        SortExec::try_new(expr, input, ctx_state.config.concurrency)?
+            .with_memory_limit(ctx_state.config.memory_limit)
+            .with_preserve_partitioning(partitioned),
+    ))
+}
+
+/// Returns the arguments of `expr` if it is a GROUPING aggregate
+fn grouping_args(expr: &Expr) -> Option<&Vec<Expr>> {
+    match expr {
+        Expr::Alias(expr, _) => grouping_args(expr),
+        Expr::AggregateFunction {
+            fun: aggregates::AggregateFunction::Grouping,
+            args,
+            ..
+        } => Some(args),
+        _ => None,
+    }
+}
+
+/// Returns the value of `GROUPING(args)` in the groups of the grouping set `set`, the
+/// indexes of its expressions in `group_expr`: the bitmask of the arguments missing
+/// from the set, the first argument being the most significant bit
+fn grouping_value(args: &[Expr], group_expr: &[Expr], set: &[usize]) -> Result<i32> {
+    if args.len() > 31 {
+        return Err(DataFusionError::Plan(format!(
+            "GROUPING is limited to 31 arguments, got {}",
+            args.len()
+        )));
+    }
+    Ok(args.iter().fold(0, |value, arg| {
+        let grouped = group_expr
+            .iter()
+            .position(|expr| expr == arg)
+            .map_or(false, |i| set.contains(&i));
+        (value << 1) | (!grouped as i32)
+    }))
+}
+
 fn tuple_err<T, R>(value: (Result<T>, Result<R>)) -> Result<(T, R)> {
     match value {
         (Ok(e), Ok(e1)) => Ok((e, e1)),
@@ -668,10 +1141,11 @@ impl ExtensionPlanner for DefaultExtensionPlanner {
 mod tests {
     use super::*;
     use crate::logical_plan::{DFField, DFSchema, DFSchemaRef};
+    use crate::optimizer::{optimizer::OptimizerRule, top_k::TopK};
     use crate::physical_plan::{csv::CsvReadOptions, expressions, Partitioning};
     use crate::prelude::ExecutionConfig;
     use crate::{
-        logical_plan::{col, lit, sum, LogicalPlanBuilder},
+        logical_plan::{col, lit, rollup, sum, LogicalPlanBuilder},
         physical_plan::SendableRecordBatchStream,
     };
     use arrow::datatypes::{DataType, Field, SchemaRef};
@@ -719,6 +1193,180 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_aggregate_repartitioned() -> Result<()> {
+        let testdata = arrow::util::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let logical_plan = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .aggregate(vec![col("c1")], vec![sum(col("c2"))])?
+            .build()?;
+
+        let planner = DefaultPhysicalPlanner::default();
+        let mut ctx_state = make_ctx_state();
+        ctx_state.config = ExecutionConfig::new().with_concurrency(4);
+
+        // the final aggregate is computed in parallel over the partitions of the groups
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+        assert!(format!("{:?}", plan).contains("mode: FinalPartitioned"));
+        assert_eq!(4, plan.output_partitioning().partition_count());
+
+        ctx_state.config = ctx_state.config.with_repartition_aggregations(false);
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+        assert!(format!("{:?}", plan).contains("mode: Final,"));
+        assert_eq!(1, plan.output_partitioning().partition_count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregate_grouping_sets() -> Result<()> {
+        let testdata = arrow::util::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let grouping = Expr::AggregateFunction {
+            fun: aggregates::AggregateFunction::Grouping,
+            args: vec![col("c1"), col("c2")],
+            distinct: false,
+            order_by: vec![],
+        };
+        let logical_plan = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .aggregate_grouping_sets(
+                rollup(vec![col("c1"), col("c2")]),
+                vec![sum(col("c3")), grouping.alias("g")],
+            )?
+            .build()?;
+
+        let plan = plan(&logical_plan)?;
+
+        // GROUPING is computed from the index of the grouping set of the groups
+        let expected = "GroupingExpr { grouping_id: Column { name: \"__grouping_id\" }, values: [0, 1, 3] }";
+        assert!(format!("{:?}", plan).contains(expected));
+
+        // the plan has the schema of the logical plan, without the grouping set index
+        let fields = plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| (f.name().clone(), f.is_nullable()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("c1".to_string(), true),
+                ("c2".to_string(), true),
+                ("SUM(c3)".to_string(), true),
+                ("g".to_string(), false),
+            ],
+            fields
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_partitions_in_parallel() -> Result<()> {
+        let testdata = arrow::util::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let logical_plan = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .aggregate(vec![col("c1")], vec![sum(col("c2"))])?
+            .sort(vec![col("c1").sort(true, true)])?
+            .limit(3)?
+            .build()?;
+
+        let planner = DefaultPhysicalPlanner::default();
+        let mut ctx_state = make_ctx_state();
+        ctx_state.config = ExecutionConfig::new().with_concurrency(4);
+
+        // the partitions of the aggregate are sorted and limited separately, then merged
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+        let plan = format!("{:?}", plan);
+        assert!(plan.contains("SortPreservingMergeExec"));
+        assert!(plan.contains("LocalLimitExec"));
+        assert!(plan.contains("preserve_partitioning: true"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_merge_join() -> Result<()> {
+        let testdata = arrow::util::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let left = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .project(vec![col("c1"), col("c2")])?;
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let right = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .project(vec![col("c1").alias("r1"), col("c3")])?;
+        let logical_plan = left
+            .join(&right.build()?, JoinType::Inner, &["c1"], &["r1"])?
+            .build()?;
+
+        let planner = DefaultPhysicalPlanner::default();
+        let mut ctx_state = make_ctx_state();
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+        assert!(format!("{:?}", plan).contains("HashJoinExec"));
+
+        // both sides are sorted on the join keys, then merged
+        ctx_state.config = ExecutionConfig::new()
+            .with_concurrency(4)
+            .with_sort_merge_joins(true);
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+        let plan = format!("{:?}", plan);
+        assert!(plan.starts_with("SortMergeJoinExec"));
+        assert!(plan.contains("RepartitionExec"));
+        assert!(plan.contains("preserve_partitioning: true"));
+
+        // sides that are already sorted on the join keys are merged as they are
+        let logical_plan = left
+            .sort(vec![col("c1").sort(true, true)])?
+            .join(
+                &right.sort(vec![col("r1").sort(true, true)])?.build()?,
+                JoinType::Left,
+                &["c1"],
+                &["r1"],
+            )?
+            .build()?;
+        let plan = planner.create_physical_plan(&logical_plan, &make_ctx_state())?;
+        let plan = format!("{:?}", plan);
+        assert!(plan.starts_with("SortMergeJoinExec"));
+        assert!(!plan.contains("RepartitionExec"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_k_partitions() -> Result<()> {
+        let testdata = arrow::util::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let logical_plan = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .aggregate(vec![col("c1")], vec![sum(col("c2"))])?
+            .sort(vec![col("c1").sort(true, true)])?
+            .limit(3)?
+            .build()?;
+        let logical_plan = TopK::new().optimize(&logical_plan)?;
+
+        let planner = DefaultPhysicalPlanner::default();
+        let mut ctx_state = make_ctx_state();
+        ctx_state.config = ExecutionConfig::new().with_concurrency(4);
+
+        // the first rows of each partition of the aggregate are merged
+        let plan = planner.create_physical_plan(&logical_plan, &ctx_state)?;
+        let plan = format!("{:?}", plan);
+        assert!(plan.contains("SortPreservingMergeExec"));
+        assert!(plan.contains("TopKExec"));
+        assert!(!plan.contains("SortExec"));
+        assert!(!plan.contains("LocalLimitExec"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_create_not() -> Result<()> {
         let schema = Schema::new(vec![Field::new("a", DataType::Boolean, true)]);
@@ -24,15 +24,18 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use crate::error::{DataFusionError, Result};
-use crate::physical_plan::{ExecutionPlan, Partitioning};
+use crate::physical_plan::{ExecutionPlan, Partitioning, PhysicalExpr};
+use arrow::array::UInt32Array;
+use arrow::compute::take;
 use arrow::datatypes::SchemaRef;
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 
-use super::{RecordBatchStream, SendableRecordBatchStream};
+use super::{hash_utils::create_hashes, RecordBatchStream, SendableRecordBatchStream};
+use ahash::RandomState;
 use async_trait::async_trait;
 
-use crossbeam::channel::{unbounded, Receiver, Sender};
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
 use futures::stream::Stream;
 use futures::StreamExt;
 use tokio::sync::Mutex;
@@ -49,8 +52,16 @@ pub struct RepartitionExec {
     /// Partitioning scheme to use
     partitioning: Partitioning,
     /// Channels for sending batches from input partitions to output partitions
-    /// there is one entry in this Vec for each output partition
-    channels: Arc<Mutex<Vec<(Sender<MaybeBatch>, Receiver<MaybeBatch>)>>>,
+    /// there is one entry in this Vec for each output partition, whose receiver is
+    /// taken once the output partition is executed
+    channels: Arc<
+        Mutex<
+            Vec<(
+                UnboundedSender<MaybeBatch>,
+                Option<UnboundedReceiver<MaybeBatch>>,
+            )>,
+        >,
+    >,
 }
 
 #[async_trait]
@@ -106,24 +117,48 @@ impl ExecutionPlan for RepartitionExec {
                 // reading output partitions in order rather than concurrently. One workaround
                 // for this would be to add spill-to-disk capabilities.
                 let (sender, receiver) = unbounded::<Option<ArrowResult<RecordBatch>>>();
-                channels.push((sender, receiver));
+                channels.push((sender, Some(receiver)));
             }
             // launch one async task per *input* partition
             for i in 0..num_input_partitions {
                 let input = self.input.clone();
-                let mut channels = channels.clone();
+                let senders: Vec<_> =
+                    channels.iter().map(|(sender, _)| sender.clone()).collect();
                 let partitioning = self.partitioning.clone();
                 let _: JoinHandle<Result<()>> = tokio::spawn(async move {
+                    // all the input partitions must assign equal keys to the same output
+                    // partition, hence the fixed seeds
+                    let random_state = RandomState::with_seeds(0, 0, 0, 0);
                     let mut stream = input.execute(i).await?;
                     let mut counter = 0;
                     while let Some(result) = stream.next().await {
-                        match partitioning {
+                        match &partitioning {
                             Partitioning::RoundRobinBatch(_) => {
                                 let output_partition = counter % num_output_partitions;
-                                let tx = &mut channels[output_partition].0;
-                                tx.send(Some(result)).map_err(|e| {
-                                    DataFusionError::Execution(e.to_string())
-                                })?;
+                                send_to_output(&senders[output_partition], Some(result));
+                            }
+                            Partitioning::Hash(exprs, _) => {
+                                let batches = result.and_then(|batch| {
+                                    hash_partition(
+                                        &batch,
+                                        exprs,
+                                        &random_state,
+                                        num_output_partitions,
+                                    )
+                                    .map_err(DataFusionError::into_arrow_external_error)
+                                });
+                                match batches {
+                                    Ok(batches) => {
+                                        for (output_partition, batch) in batches {
+                                            send_to_output(
+                                                &senders[output_partition],
+                                                Some(Ok(batch)),
+                                            );
+                                        }
+                                    }
+                                    // the error is reported by the first output partition
+                                    Err(e) => send_to_output(&senders[0], Some(Err(e))),
+                                }
                             }
                             other => {
                                 // this should be unreachable as long as the validation logic
@@ -138,10 +173,8 @@ impl ExecutionPlan for RepartitionExec {
                     }
 
                     // notify each output partition that this input partition has no more data
-                    for i in 0..num_output_partitions {
-                        let tx = &mut channels[i].0;
-                        tx.send(None)
-                            .map_err(|e| DataFusionError::Execution(e.to_string()))?;
+                    for tx in &senders {
+                        send_to_output(tx, None);
                     }
                     Ok(())
                 });
@@ -150,11 +183,17 @@ impl ExecutionPlan for RepartitionExec {
 
         // now return stream for the specified *output* partition which will
         // read from the channel
+        let input = channels[partition].1.take().ok_or_else(|| {
+            DataFusionError::Execution(format!(
+                "RepartitionExec partition {} has already been executed",
+                partition
+            ))
+        })?;
         Ok(Box::pin(RepartitionStream {
             num_input_partitions,
             num_input_partitions_processed: 0,
             schema: self.input.schema(),
-            input: channels[partition].1.clone(),
+            input,
         }))
     }
 }
@@ -166,11 +205,13 @@ impl RepartitionExec {
         partitioning: Partitioning,
     ) -> Result<Self> {
         match &partitioning {
-            Partitioning::RoundRobinBatch(_) => Ok(RepartitionExec {
-                input,
-                partitioning,
-                channels: Arc::new(Mutex::new(vec![])),
-            }),
+            Partitioning::RoundRobinBatch(_) | Partitioning::Hash(_, _) => {
+                Ok(RepartitionExec {
+                    input,
+                    partitioning,
+                    channels: Arc::new(Mutex::new(vec![])),
+                })
+            }
             other => Err(DataFusionError::NotImplemented(format!(
                 "Partitioning scheme not supported yet: {:?}",
                 other
@@ -179,6 +220,52 @@ impl RepartitionExec {
     }
 }
 
+/// Sends a batch to an output partition. The receiver of an output partition is dropped
+/// once the partition is no longer read, in which case the batch is discarded.
+fn send_to_output(sender: &UnboundedSender<MaybeBatch>, batch: MaybeBatch) {
+    sender.unbounded_send(batch).ok();
+}
+
+/// Splits `batch` into one batch per output partition, assigning each row to the
+/// partition given by the hash of the values of `exprs` for that row. The output
+/// partitions without any row are omitted.
+fn hash_partition(
+    batch: &RecordBatch,
+    exprs: &[Arc<dyn PhysicalExpr>],
+    random_state: &RandomState,
+    num_output_partitions: usize,
+) -> Result<Vec<(usize, RecordBatch)>> {
+    let arrays = exprs
+        .iter()
+        .map(|expr| Ok(expr.evaluate(batch)?.into_array(batch.num_rows())))
+        .collect::<Result<Vec<_>>>()?;
+    let mut hashes = vec![0; batch.num_rows()];
+    create_hashes(&arrays, random_state, &mut hashes)?;
+
+    let mut indices = vec![vec![]; num_output_partitions];
+    for (row, hash) in hashes.iter().enumerate() {
+        indices[(*hash % num_output_partitions as u64) as usize].push(row as u32);
+    }
+
+    indices
+        .into_iter()
+        .enumerate()
+        .filter(|(_, indices)| !indices.is_empty())
+        .map(|(output_partition, indices)| {
+            let indices = UInt32Array::from(indices);
+            let columns = batch
+                .columns()
+                .iter()
+                .map(|column| take(column.as_ref(), &indices, None))
+                .collect::<ArrowResult<Vec<_>>>()?;
+            Ok((
+                output_partition,
+                RecordBatch::try_new(batch.schema(), columns)?,
+            ))
+        })
+        .collect()
+}
+
 struct RepartitionStream {
     /// Number of input partitions that will be sending batches to this output channel
     num_input_partitions: usize,
@@ -187,7 +274,7 @@ struct RepartitionStream {
     /// Schema
     schema: SchemaRef,
     /// channel containing the repartitioned batches
-    input: Receiver<Option<ArrowResult<RecordBatch>>>,
+    input: UnboundedReceiver<MaybeBatch>,
 }
 
 impl Stream for RepartitionStream {
@@ -197,10 +284,10 @@ impl Stream for RepartitionStream {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        match self.input.recv() {
-            Ok(Some(batch)) => Poll::Ready(Some(batch)),
+        match self.input.poll_next_unpin(cx) {
+            Poll::Ready(Some(Some(batch))) => Poll::Ready(Some(batch)),
             // End of results from one input partition
-            Ok(None) => {
+            Poll::Ready(Some(None)) => {
                 self.num_input_partitions_processed += 1;
                 if self.num_input_partitions == self.num_input_partitions_processed {
                     // all input partitions have finished sending batches
@@ -210,8 +297,9 @@ impl Stream for RepartitionStream {
                     self.poll_next(cx)
                 }
             }
-            // RecvError means receiver has exited and closed the channel
-            Err(_) => Poll::Ready(None),
+            // all the senders have been dropped and closed the channel
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
         }
     }
 }
@@ -226,10 +314,12 @@ impl RecordBatchStream for RepartitionStream {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::physical_plan::expressions::col;
     use crate::physical_plan::memory::MemoryExec;
     use arrow::array::UInt32Array;
     use arrow::datatypes::{DataType, Field, Schema};
     use arrow::record_batch::RecordBatch;
+    use std::collections::HashMap;
 
     #[tokio::test(threaded_scheduler)]
     async fn one_to_many_round_robin() -> Result<()> {
@@ -289,6 +379,47 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test(threaded_scheduler)]
+    async fn many_to_many_hash_partition() -> Result<()> {
+        // define input partitions
+        let schema = test_schema();
+        let partition = create_vec_batches(&schema, 50)?;
+        let partitions = vec![partition.clone(), partition.clone(), partition.clone()];
+
+        // repartition from 3 input to 5 output
+        let output_partitions =
+            repartition(&schema, partitions, Partitioning::Hash(vec![col("c0")], 5))
+                .await?;
+
+        let total_rows: usize = output_partitions
+            .iter()
+            .flatten()
+            .map(|batch| batch.num_rows())
+            .sum();
+        assert_eq!(5, output_partitions.len());
+        assert_eq!(3 * 50 * 8, total_rows);
+
+        // all the rows with the same value are in the same output partition
+        let mut partition_of_value = HashMap::new();
+        for (i, batches) in output_partitions.iter().enumerate() {
+            for batch in batches {
+                let values = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<UInt32Array>()
+                    .unwrap();
+                for row in 0..values.len() {
+                    let partition =
+                        partition_of_value.entry(values.value(row)).or_insert(i);
+                    assert_eq!(i, *partition);
+                }
+            }
+        }
+        assert_eq!(8, partition_of_value.len());
+
+        Ok(())
+    }
+
     fn test_schema() -> Arc<Schema> {
         Arc::new(Schema::new(vec![Field::new("c0", DataType::UInt32, false)]))
     }
@@ -16,23 +16,32 @@
 // under the License.
 
 //! Defines the SORT plan
+//!
+//! The input of a sort is buffered in memory up to a memory limit. Past that limit,
+//! the buffered batches are sorted and spilled to a temporary file, and the sorted
+//! runs are merged once the whole input has been consumed.
 
 use std::any::Any;
+use std::cmp::Ordering;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
-use futures::stream::Stream;
-use futures::Future;
+use futures::channel::mpsc;
+use futures::stream::{self, Stream};
+use futures::{SinkExt, StreamExt};
 
 use pin_project_lite::pin_project;
 
+use arrow::array::{
+    build_compare, make_array, Array, ArrayRef, DynComparator, MutableArrayData,
+    UInt32Array,
+};
 pub use arrow::compute::SortOptions;
 use arrow::compute::{concat, lexsort_to_indices, take, SortColumn, TakeOptions};
 use arrow::datatypes::SchemaRef;
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
-use arrow::{array::ArrayRef, error::ArrowError};
 
 use super::{RecordBatchStream, SendableRecordBatchStream};
 use crate::error::{DataFusionError, Result};
@@ -50,6 +59,11 @@ pub struct SortExec {
     expr: Vec<PhysicalSortExpr>,
     /// Number of threads to execute input partitions on before combining into a single partition
     concurrency: usize,
+    /// Number of bytes of input buffered in memory before being spilled to disk
+    memory_limit: usize,
+    /// Whether each partition of the input is sorted separately, instead of the
+    /// whole input being sorted in a single partition
+    preserve_partitioning: bool,
 }
 
 impl SortExec {
@@ -63,8 +77,30 @@ impl SortExec {
             expr,
             input,
             concurrency,
+            memory_limit: usize::MAX,
+            preserve_partitioning: false,
         })
     }
+
+    /// Limits the memory used to buffer the input, which is spilled to disk past
+    /// `memory_limit` bytes. The limit applies to each partition of the sort on its
+    /// own. The memory is not limited by default.
+    pub fn with_memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Sort expressions
+    pub fn expr(&self) -> &[PhysicalSortExpr] {
+        &self.expr
+    }
+
+    /// Sorts each partition of the input separately when `preserve_partitioning` is
+    /// true, e.g. to merge the sorted partitions with a `SortPreservingMergeExec`
+    pub fn with_preserve_partitioning(mut self, preserve_partitioning: bool) -> Self {
+        self.preserve_partitioning = preserve_partitioning;
+        self
+    }
 }
 
 #[async_trait]
@@ -84,11 +120,19 @@ impl ExecutionPlan for SortExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(1)
+        if self.preserve_partitioning {
+            self.input.output_partitioning()
+        } else {
+            Partitioning::UnknownPartitioning(1)
+        }
     }
 
     fn required_child_distribution(&self) -> Distribution {
-        Distribution::SinglePartition
+        if self.preserve_partitioning {
+            Distribution::UnspecifiedDistribution
+        } else {
+            Distribution::SinglePartition
+        }
     }
 
     fn with_new_children(
@@ -96,11 +140,15 @@ impl ExecutionPlan for SortExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(SortExec::try_new(
-                self.expr.clone(),
-                children[0].clone(),
-                self.concurrency,
-            )?)),
+            1 => Ok(Arc::new(
+                SortExec::try_new(
+                    self.expr.clone(),
+                    children[0].clone(),
+                    self.concurrency,
+                )?
+                .with_memory_limit(self.memory_limit)
+                .with_preserve_partitioning(self.preserve_partitioning),
+            )),
             _ => Err(DataFusionError::Internal(
                 "SortExec wrong number of children".to_string(),
             )),
@@ -108,30 +156,37 @@ impl ExecutionPlan for SortExec {
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        if 0 != partition {
-            return Err(DataFusionError::Internal(format!(
-                "SortExec invalid partition {}",
-                partition
-            )));
-        }
+        if !self.preserve_partitioning {
+            if 0 != partition {
+                return Err(DataFusionError::Internal(format!(
+                    "SortExec invalid partition {}",
+                    partition
+                )));
+            }
 
-        // sort needs to operate on a single partition currently
-        if 1 != self.input.output_partitioning().partition_count() {
-            return Err(DataFusionError::Internal(
-                "SortExec requires a single input partition".to_owned(),
-            ));
+            // sort needs to operate on a single partition currently
+            if 1 != self.input.output_partitioning().partition_count() {
+                return Err(DataFusionError::Internal(
+                    "SortExec requires a single input partition".to_owned(),
+                ));
+            }
         }
-        let input = self.input.execute(0).await?;
+        let input = self.input.execute(partition).await?;
 
-        Ok(Box::pin(SortStream::new(input, self.expr.clone())))
+        Ok(Box::pin(SortStream::new(
+            input,
+            self.expr.clone(),
+            self.memory_limit,
+        )))
     }
 }
 
-fn sort_batches(
-    batches: &Vec<RecordBatch>,
+/// Sorts the rows of `batches` and returns them with the indices of their sorted order
+fn lexsort_batches(
+    batches: &[RecordBatch],
     schema: &SchemaRef,
     expr: &[PhysicalSortExpr],
-) -> ArrowResult<RecordBatch> {
+) -> ArrowResult<(RecordBatch, UInt32Array)> {
     // combine all record batches into one for each column
     let combined_batch = RecordBatch::try_new(
         schema.clone(),
@@ -159,16 +214,20 @@ fn sort_batches(
             .map_err(DataFusionError::into_arrow_external_error)?,
     )?;
 
-    // reorder all rows based on sorted indices
+    Ok((combined_batch, indices))
+}
+
+/// Takes the rows of `batch` at `indices`
+fn take_rows(batch: &RecordBatch, indices: &UInt32Array) -> ArrowResult<RecordBatch> {
     RecordBatch::try_new(
-        schema.clone(),
-        combined_batch
+        batch.schema(),
+        batch
             .columns()
             .iter()
             .map(|column| {
                 take(
                     column.as_ref(),
-                    &indices,
+                    indices,
                     // disable bound check overhead since indices are already generated from
                     // the same record batch
                     Some(TakeOptions {
@@ -180,33 +239,458 @@ fn sort_batches(
     )
 }
 
+fn sort_batches(
+    batches: &[RecordBatch],
+    schema: &SchemaRef,
+    expr: &[PhysicalSortExpr],
+) -> ArrowResult<RecordBatch> {
+    let (combined_batch, indices) = lexsort_batches(batches, schema, expr)?;
+
+    // reorder all rows based on sorted indices
+    take_rows(&combined_batch, &indices)
+}
+
+/// Sorts the rows of `batches` into a sorted run of batches of `batch_size` rows
+fn sort_batches_to_run(
+    batches: &[RecordBatch],
+    schema: &SchemaRef,
+    expr: &[PhysicalSortExpr],
+    batch_size: usize,
+) -> ArrowResult<Vec<RecordBatch>> {
+    let (combined_batch, indices) = lexsort_batches(batches, schema, expr)?;
+
+    (0..indices.len())
+        .step_by(batch_size)
+        .map(|offset| {
+            let end = indices.len().min(offset + batch_size);
+            let chunk = (offset..end)
+                .map(|i| indices.value(i))
+                .collect::<Vec<u32>>();
+            take_rows(&combined_batch, &UInt32Array::from(chunk))
+        })
+        .collect()
+}
+
+/// Compares the rows of two sets of sort key columns, following the sort options of
+/// each column
+pub(crate) struct RowComparator<'a> {
+    left: &'a [ArrayRef],
+    right: &'a [ArrayRef],
+    options: &'a [SortOptions],
+    comparators: Vec<DynComparator<'a>>,
+}
+
+impl<'a> RowComparator<'a> {
+    /// Create a comparator of the rows of `left` with the rows of `right`
+    pub(crate) fn try_new(
+        left: &'a [ArrayRef],
+        right: &'a [ArrayRef],
+        options: &'a [SortOptions],
+    ) -> Result<Self> {
+        let comparators = left
+            .iter()
+            .zip(right.iter())
+            .map(|(left, right)| build_compare(left.as_ref(), right.as_ref()))
+            .collect::<ArrowResult<Vec<_>>>()?;
+        Ok(Self {
+            left,
+            right,
+            options,
+            comparators,
+        })
+    }
+
+    /// Compares the row `left_row` of the left columns with the row `right_row` of
+    /// the right columns
+    pub(crate) fn compare(&self, left_row: usize, right_row: usize) -> Ordering {
+        let columns = self.left.iter().zip(self.right.iter());
+        for ((left, right), (options, comparator)) in
+            columns.zip(self.options.iter().zip(self.comparators.iter()))
+        {
+            let ordering = match (left.is_valid(left_row), right.is_valid(right_row)) {
+                (false, false) => Ordering::Equal,
+                (false, true) if options.nulls_first => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (true, false) if options.nulls_first => Ordering::Greater,
+                (true, false) => Ordering::Less,
+                (true, true) => {
+                    let ordering = comparator(left_row, right_row);
+                    if options.descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                }
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+/// Compares the row `left_row` of the sort key columns `left` with the row `right_row`
+/// of the sort key columns `right`, following the sort `options` of each column
+pub(crate) fn compare_rows(
+    left: &[ArrayRef],
+    left_row: usize,
+    right: &[ArrayRef],
+    right_row: usize,
+    options: &[SortOptions],
+) -> Result<Ordering> {
+    Ok(RowComparator::try_new(left, right, options)?.compare(left_row, right_row))
+}
+
+/// Evaluates the sort key columns of a batch
+pub(crate) fn sort_key_columns(
+    expr: &[PhysicalSortExpr],
+    batch: &RecordBatch,
+) -> Result<Vec<ArrayRef>> {
+    expr.iter()
+        .map(|e| Ok(e.evaluate_to_sort_column(batch)?.values))
+        .collect()
+}
+
+/// Builds a batch from rows of `batches`, given as an index in `batches` and a row
+pub(crate) fn interleave_batches(
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+    indices: &[(usize, usize)],
+) -> Result<RecordBatch> {
+    // copy the consecutive rows of a same batch at once
+    let mut ranges: Vec<(usize, usize, usize)> = vec![];
+    for &(batch, row) in indices {
+        match ranges.last_mut() {
+            Some((last_batch, _, end)) if *last_batch == batch && *end == row => {
+                *end += 1
+            }
+            _ => ranges.push((batch, row, row + 1)),
+        }
+    }
+
+    let columns = (0..schema.fields().len())
+        .map(|i| {
+            let arrays = batches
+                .iter()
+                .map(|batch| batch.column(i).data_ref().as_ref())
+                .collect::<Vec<_>>();
+            let mut array_data = MutableArrayData::new(arrays, false, indices.len());
+            for &(batch, start, end) in &ranges {
+                array_data.extend(batch, start, end);
+            }
+            make_array(Arc::new(array_data.freeze()))
+        })
+        .collect::<Vec<_>>();
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// The state of a sorted run being merged by a [`SortedRunsMerger`]
+enum RunState {
+    /// The next batch of the run is needed to continue merging
+    Pending,
+    /// The run has a batch with rows left to merge
+    Active(RunCursor),
+    /// All the rows of the run have been merged
+    Finished,
+}
+
+/// The position of a run in its current batch
+struct RunCursor {
+    /// Index of the batch in the batches of the merger
+    batch: usize,
+    /// The sort key columns of the batch
+    sort_columns: Vec<ArrayRef>,
+    /// The next row of the batch to merge
+    row: usize,
+}
+
+/// The comparators of the sort keys of the current batches of each pair of runs,
+/// built when first used
+struct RunComparators<'a> {
+    /// The sort key columns of the current batch of each run
+    sort_columns: &'a [Vec<ArrayRef>],
+    options: &'a [SortOptions],
+    /// The comparator of each pair of runs, by `left * number of runs + right`
+    comparators: Vec<Option<RowComparator<'a>>>,
+}
+
+impl<'a> RunComparators<'a> {
+    fn new(sort_columns: &'a [Vec<ArrayRef>], options: &'a [SortOptions]) -> Self {
+        let num_runs = sort_columns.len();
+        Self {
+            sort_columns,
+            options,
+            comparators: (0..num_runs * num_runs).map(|_| None).collect(),
+        }
+    }
+
+    /// Compares the row `left_row` of the run `left` with the row `right_row` of the
+    /// run `right`
+    fn compare(
+        &mut self,
+        left: usize,
+        left_row: usize,
+        right: usize,
+        right_row: usize,
+    ) -> Result<Ordering> {
+        let sort_columns = self.sort_columns;
+        let comparator = &mut self.comparators[left * sort_columns.len() + right];
+        if comparator.is_none() {
+            *comparator = Some(RowComparator::try_new(
+                &sort_columns[left],
+                &sort_columns[right],
+                self.options,
+            )?);
+        }
+        Ok(comparator.as_ref().unwrap().compare(left_row, right_row))
+    }
+}
+
+/// Merges sorted runs of record batches into sorted batches of `batch_size` rows.
+///
+/// The merger does not read its runs: whenever [`SortedRunsMerger::pending_run`]
+/// returns a run, its next batch must be given to [`SortedRunsMerger::push_batch`]
+/// before more rows are merged. Rows that compare equal are output in the order of
+/// their runs.
+pub(crate) struct SortedRunsMerger {
+    schema: SchemaRef,
+    expr: Vec<PhysicalSortExpr>,
+    options: Vec<SortOptions>,
+    batch_size: usize,
+    runs: Vec<RunState>,
+    /// The batches the rows of the next output batch are taken from
+    batches: Vec<RecordBatch>,
+    /// The rows of the next output batch, as an index in `batches` and a row
+    indices: Vec<(usize, usize)>,
+}
+
+impl SortedRunsMerger {
+    /// Create a merger of `num_runs` runs sorted by `expr`
+    pub(crate) fn new(
+        schema: SchemaRef,
+        expr: Vec<PhysicalSortExpr>,
+        num_runs: usize,
+        batch_size: usize,
+    ) -> Self {
+        let options = expr.iter().map(|e| e.options).collect();
+        Self {
+            schema,
+            expr,
+            options,
+            batch_size,
+            runs: (0..num_runs).map(|_| RunState::Pending).collect(),
+            batches: vec![],
+            indices: vec![],
+        }
+    }
+
+    /// Returns a run whose next batch is needed to continue merging, if any
+    pub(crate) fn pending_run(&self) -> Option<usize> {
+        self.runs
+            .iter()
+            .position(|run| matches!(run, RunState::Pending))
+    }
+
+    /// Gives the next batch of a pending run, or `None` when the run is exhausted
+    pub(crate) fn push_batch(
+        &mut self,
+        run: usize,
+        batch: Option<RecordBatch>,
+    ) -> Result<()> {
+        self.runs[run] = match batch {
+            None => RunState::Finished,
+            Some(batch) if batch.num_rows() == 0 => RunState::Pending,
+            Some(batch) => {
+                let sort_columns = sort_key_columns(&self.expr, &batch)?;
+                self.batches.push(batch);
+                RunState::Active(RunCursor {
+                    batch: self.batches.len() - 1,
+                    sort_columns,
+                    row: 0,
+                })
+            }
+        };
+        Ok(())
+    }
+
+    /// Merges the next batch of rows. Returns `None` when a run is pending, or when all
+    /// the rows have been merged.
+    pub(crate) fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        // the current batches of the runs do not change until a run is pending, which
+        // ends the merge, so the comparators of their sort keys are built once
+        let sort_columns = self
+            .runs
+            .iter()
+            .map(|run| match run {
+                RunState::Active(cursor) => cursor.sort_columns.clone(),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>();
+        let options = self.options.clone();
+        let mut comparators = RunComparators::new(&sort_columns, &options);
+
+        while self.indices.len() < self.batch_size {
+            if self.pending_run().is_some() {
+                return Ok(None);
+            }
+            let run = match self.min_run(&mut comparators)? {
+                Some(run) => run,
+                // all the runs are finished
+                None => break,
+            };
+            if let RunState::Active(cursor) = &mut self.runs[run] {
+                self.indices.push((cursor.batch, cursor.row));
+                cursor.row += 1;
+                if cursor.row == self.batches[cursor.batch].num_rows() {
+                    self.runs[run] = RunState::Pending;
+                }
+            }
+        }
+
+        if self.indices.is_empty() {
+            Ok(None)
+        } else {
+            self.build_batch().map(Some)
+        }
+    }
+
+    /// Returns whether all the rows of all the runs have been merged
+    pub(crate) fn is_finished(&self) -> bool {
+        self.indices.is_empty()
+            && self
+                .runs
+                .iter()
+                .all(|run| matches!(run, RunState::Finished))
+    }
+
+    /// Returns the active run with the smallest current row
+    fn min_run(&self, comparators: &mut RunComparators) -> Result<Option<usize>> {
+        let mut min: Option<(usize, usize)> = None;
+        for (run, state) in self.runs.iter().enumerate() {
+            if let RunState::Active(cursor) = state {
+                min = match min {
+                    Some((min_run, min_row))
+                        if comparators.compare(min_run, min_row, run, cursor.row)?
+                            != Ordering::Greater =>
+                    {
+                        min
+                    }
+                    _ => Some((run, cursor.row)),
+                };
+            }
+        }
+        Ok(min.map(|(run, _)| run))
+    }
+
+    /// Builds a batch from the merged rows, and releases the batches that are no longer
+    /// needed
+    fn build_batch(&mut self) -> Result<RecordBatch> {
+        let batch = interleave_batches(&self.schema, &self.batches, &self.indices)?;
+
+        self.indices.clear();
+        let mut batches = vec![];
+        for run in self.runs.iter_mut() {
+            if let RunState::Active(cursor) = run {
+                batches.push(self.batches[cursor.batch].clone());
+                cursor.batch = batches.len() - 1;
+            }
+        }
+        self.batches = batches;
+
+        Ok(batch)
+    }
+}
+
+/// Sorts the batches of `input` and sends them to `output`
+async fn sort_input(
+    mut input: SendableRecordBatchStream,
+    expr: Vec<PhysicalSortExpr>,
+    memory_limit: usize,
+    output: &mut mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
+    let schema = input.schema();
+    let mut batches = vec![];
+    let mut memory_size = 0;
+    let mut spills = vec![];
+    // the merged batches are as large as the largest input batch
+    let mut batch_size = 1;
+    while let Some(batch) = input.next().await {
+        let batch = batch?;
+        batch_size = batch_size.max(batch.num_rows());
+        memory_size += common::batch_memory_size(&batch);
+        batches.push(batch);
+
+        if memory_size > memory_limit {
+            let run = sort_batches_to_run(&batches, &schema, &expr, batch_size)?;
+            spills.push(common::spill_batches(run, schema.clone()).await?);
+            batches.clear();
+            memory_size = 0;
+        }
+    }
+
+    if spills.is_empty() {
+        // the whole input fits in memory
+        if !batches.is_empty() {
+            // If send fails, plan being torn down, there is no place to send the batch
+            output
+                .send(sort_batches(&batches, &schema, &expr))
+                .await
+                .ok();
+        }
+        return Ok(());
+    }
+
+    let mut runs = vec![];
+    for spill in &spills {
+        runs.push(common::read_spilled_batches(spill)?.boxed());
+    }
+    if !batches.is_empty() {
+        let run = sort_batches_to_run(&batches, &schema, &expr, batch_size)?;
+        runs.push(stream::iter(run.into_iter().map(Ok)).boxed());
+    }
+
+    let mut merger = SortedRunsMerger::new(schema, expr, runs.len(), batch_size);
+    while !merger.is_finished() {
+        if let Some(run) = merger.pending_run() {
+            let batch = runs[run].next().await.transpose()?;
+            merger.push_batch(run, batch)?;
+        } else if let Some(batch) = merger.next_batch()? {
+            if output.send(Ok(batch)).await.is_err() {
+                // the plan is being torn down
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
 pin_project! {
     struct SortStream {
         #[pin]
-        output: futures::channel::oneshot::Receiver<ArrowResult<RecordBatch>>,
-        finished: bool,
+        output: mpsc::Receiver<ArrowResult<RecordBatch>>,
         schema: SchemaRef,
     }
 }
 
 impl SortStream {
-    fn new(input: SendableRecordBatchStream, expr: Vec<PhysicalSortExpr>) -> Self {
-        let (tx, rx) = futures::channel::oneshot::channel();
+    fn new(
+        input: SendableRecordBatchStream,
+        expr: Vec<PhysicalSortExpr>,
+        memory_limit: usize,
+    ) -> Self {
+        let (mut sender, receiver) = mpsc::channel(1);
 
         let schema = input.schema();
         tokio::spawn(async move {
-            let schema = input.schema();
-            let sorted_batch = common::collect(input)
-                .await
-                .map_err(DataFusionError::into_arrow_external_error)
-                .and_then(move |batches| sort_batches(&batches, &schema, &expr));
-
-            tx.send(sorted_batch)
+            if let Err(e) = sort_input(input, expr, memory_limit, &mut sender).await {
+                // If send fails, plan being torn down, no place to send the error
+                sender.send(Err(e.into_arrow_external_error())).await.ok();
+            }
         });
 
         Self {
-            output: rx,
-            finished: false,
+            output: receiver,
             schema,
         }
     }
@@ -216,27 +700,7 @@ impl Stream for SortStream {
     type Item = ArrowResult<RecordBatch>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.finished {
-            return Poll::Ready(None);
-        }
-
-        // is the output ready?
-        let this = self.project();
-        let output_poll = this.output.poll(cx);
-
-        match output_poll {
-            Poll::Ready(result) => {
-                *this.finished = true;
-
-                // check for error in receiving channel and unwrap actual result
-                let result = match result {
-                    Err(e) => Err(ArrowError::ExternalError(Box::new(e))), // error receiving
-                    Ok(result) => result,
-                };
-                Poll::Ready(Some(result))
-            }
-            Poll::Pending => Poll::Pending,
-        }
+        self.project().output.poll_next(cx)
     }
 }
 
@@ -259,14 +723,20 @@ mod tests {
     use crate::test;
     use arrow::array::*;
     use arrow::datatypes::*;
+    use arrow::util::display::array_value_to_string;
 
     #[tokio::test]
     async fn test_sort() -> Result<()> {
         let schema = test::aggr_test_schema();
         let partitions = 4;
         let path = test::create_partitioned_csv("aggregate_test_100.csv", partitions)?;
-        let csv =
-            CsvExec::try_new(&path, CsvReadOptions::new().schema(&schema), None, 1024)?;
+        let csv = CsvExec::try_new(
+            &path,
+            CsvReadOptions::new().schema(&schema),
+            None,
+            1024,
+            None,
+        )?;
 
         let sort_exec = Arc::new(SortExec::try_new(
             vec![
@@ -310,6 +780,116 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_sort_spill() -> Result<()> {
+        let schema = test::aggr_test_schema();
+        let partitions = 4;
+        let path = test::create_partitioned_csv("aggregate_test_100.csv", partitions)?;
+        let csv = CsvExec::try_new(
+            &path,
+            CsvReadOptions::new().schema(&schema),
+            None,
+            1024,
+            None,
+        )?;
+        let input = Arc::new(MergeExec::new(Arc::new(csv)));
+        let sort_expr = vec![
+            PhysicalSortExpr {
+                expr: col("c1"),
+                options: SortOptions::default(),
+            },
+            PhysicalSortExpr {
+                expr: col("c2"),
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: false,
+                },
+            },
+            PhysicalSortExpr {
+                expr: col("c7"),
+                options: SortOptions::default(),
+            },
+        ];
+
+        let sort_exec = SortExec::try_new(sort_expr.clone(), input.clone(), 2)?;
+        let expected = sort_keys(&collect(Arc::new(sort_exec)).await?)?;
+
+        // every input batch is spilled to disk
+        let sort_exec = SortExec::try_new(sort_expr, input, 2)?.with_memory_limit(1);
+        let result = collect(Arc::new(sort_exec)).await?;
+        assert_eq!(result.len(), partitions);
+        assert_eq!(sort_keys(&result)?, expected);
+
+        Ok(())
+    }
+
+    /// the values of the columns c1, c2 and c7 of `batches`
+    fn sort_keys(batches: &[RecordBatch]) -> Result<Vec<String>> {
+        let mut keys = vec![];
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                keys.push(format!(
+                    "{},{},{}",
+                    array_value_to_string(batch.column(0), row)?,
+                    array_value_to_string(batch.column(1), row)?,
+                    array_value_to_string(batch.column(6), row)?,
+                ));
+            }
+        }
+        Ok(keys)
+    }
+
+    #[test]
+    fn test_merge_sorted_runs() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = |values: Vec<Option<i32>>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))])
+        };
+        let expr = vec![PhysicalSortExpr {
+            expr: col("a"),
+            options: SortOptions {
+                descending: true,
+                nulls_first: true,
+            },
+        }];
+
+        let mut runs = vec![
+            vec![batch(vec![None, Some(9)])?, batch(vec![Some(5), Some(1)])?],
+            vec![batch(vec![Some(8), Some(5), Some(2)])?],
+            vec![],
+        ]
+        .into_iter()
+        .map(|run| run.into_iter())
+        .collect::<Vec<_>>();
+
+        let mut merger = SortedRunsMerger::new(schema.clone(), expr, runs.len(), 3);
+        let mut result = vec![];
+        while !merger.is_finished() {
+            if let Some(run) = merger.pending_run() {
+                merger.push_batch(run, runs[run].next())?;
+            } else if let Some(batch) = merger.next_batch()? {
+                result.push(batch);
+            }
+        }
+
+        let result = result
+            .iter()
+            .map(|batch| {
+                as_primitive_array::<Int32Type>(batch.column(0))
+                    .iter()
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let expected = vec![
+            vec![None, Some(9), Some(8)],
+            vec![Some(5), Some(5), Some(2)],
+            vec![Some(1)],
+        ];
+        assert_eq!(expected, result);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_lex_sort_by_float() -> Result<()> {
         let schema = Arc::new(Schema::new(vec![
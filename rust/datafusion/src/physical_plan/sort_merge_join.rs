@@ -0,0 +1,1066 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the sort-merge join plan, which joins two inputs sorted on their join keys by
+//! merging them as they are streamed. Unlike a hash join, only the rows of the current
+//! join key of each side are kept in memory.
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc;
+use futures::stream::Stream;
+use futures::{SinkExt, StreamExt};
+
+use pin_project_lite::pin_project;
+
+use arrow::array::{make_array, ArrayRef, MutableArrayData};
+use arrow::compute::SortOptions;
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use async_trait::async_trait;
+
+use super::expressions::col;
+use super::hash_join::{empty_batch, join_column_indices};
+use super::hash_utils::{build_join_schema, check_join_is_valid, JoinOn, JoinType};
+use super::sort::{compare_rows, RowComparator};
+use super::{
+    Distribution, ExecutionPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream,
+};
+use crate::error::{DataFusionError, Result};
+
+/// Sort-merge join execution plan. Both sides must be sorted on their join keys,
+/// following `sort_options`, and have the same number of partitions: every partition of
+/// the left side is joined with the same partition of the right side.
+#[derive(Debug)]
+pub struct SortMergeJoinExec {
+    /// Left sorted side
+    left: Arc<dyn ExecutionPlan>,
+    /// Right sorted side
+    right: Arc<dyn ExecutionPlan>,
+    /// Set of common columns used to join on
+    on: Vec<(String, String)>,
+    /// How the join is performed
+    join_type: JoinType,
+    /// How the join keys of both sides are sorted
+    sort_options: Vec<SortOptions>,
+    /// The schema once the join is applied
+    schema: SchemaRef,
+    /// Number of rows of the output batches
+    batch_size: usize,
+}
+
+impl SortMergeJoinExec {
+    /// Tries to create a new [SortMergeJoinExec].
+    /// # Error
+    /// This function errors when it is not possible to join the left and right sides on
+    /// keys `on`, or when the sides have different numbers of partitions.
+    pub fn try_new(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        on: &JoinOn,
+        join_type: &JoinType,
+        sort_options: Vec<SortOptions>,
+        batch_size: usize,
+    ) -> Result<Self> {
+        let left_schema = left.schema();
+        let right_schema = right.schema();
+        check_join_is_valid(&left_schema, &right_schema, &on, join_type)?;
+
+        if sort_options.len() != on.len() {
+            return Err(DataFusionError::Plan(format!(
+                "SortMergeJoinExec requires the sort options of its {} join keys, got {}",
+                on.len(),
+                sort_options.len()
+            )));
+        }
+        let left_partitions = left.output_partitioning().partition_count();
+        let right_partitions = right.output_partitioning().partition_count();
+        if left_partitions != right_partitions {
+            return Err(DataFusionError::Plan(format!(
+                "SortMergeJoinExec requires sides with the same number of partitions, got {} and {}",
+                left_partitions, right_partitions
+            )));
+        }
+
+        let schema = Arc::new(build_join_schema(
+            &left_schema,
+            &right_schema,
+            on,
+            &join_type,
+        ));
+
+        let on = on
+            .iter()
+            .map(|(l, r)| (l.to_string(), r.to_string()))
+            .collect();
+
+        Ok(SortMergeJoinExec {
+            left,
+            right,
+            on,
+            join_type: *join_type,
+            sort_options,
+            schema,
+            batch_size,
+        })
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for SortMergeJoinExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.left.clone(), self.right.clone()]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            2 => Ok(Arc::new(SortMergeJoinExec::try_new(
+                children[0].clone(),
+                children[1].clone(),
+                &self.on,
+                &self.join_type,
+                self.sort_options.clone(),
+                self.batch_size,
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "SortMergeJoinExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.left.output_partitioning()
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        // matching rows are in the same partition of both sides, which must not be
+        // merged as that would not preserve their order
+        Distribution::UnspecifiedDistribution
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        let left = SortedInput::new(
+            self.left.execute(partition).await?,
+            self.on.iter().map(|on| on.0.clone()).collect(),
+        );
+        let right = SortedInput::new(
+            self.right.execute(partition).await?,
+            self.on.iter().map(|on| on.1.clone()).collect(),
+        );
+        let joined = JoinedRows::try_new(
+            self.schema.clone(),
+            self.left.schema(),
+            self.right.schema(),
+            self.join_type,
+        )?;
+
+        Ok(Box::pin(SortMergeJoinStream::new(
+            left,
+            right,
+            joined,
+            self.sort_options.clone(),
+            self.batch_size,
+        )))
+    }
+}
+
+/// A batch of a side of the join, with the values of its join keys
+struct KeyedBatch {
+    /// Position of the batch in its side
+    id: usize,
+    batch: RecordBatch,
+    keys: Vec<ArrayRef>,
+}
+
+/// The consecutive rows of a side of the join with equal join keys, which may span
+/// several batches
+struct KeyGroup {
+    /// The batches of the rows, with the range of the rows in each batch
+    ranges: Vec<(Arc<KeyedBatch>, usize, usize)>,
+}
+
+impl KeyGroup {
+    /// The batch and row of the first row of the group
+    fn first(&self) -> (&KeyedBatch, usize) {
+        let (batch, start, _) = &self.ranges[0];
+        (batch.as_ref(), *start)
+    }
+
+    /// Whether a join key of the group is null, in which case it matches no other row
+    fn has_null_key(&self) -> bool {
+        let (batch, row) = self.first();
+        batch.keys.iter().any(|key| key.is_null(row))
+    }
+
+    /// Compares the join keys of the group with the ones of `other`
+    fn compare(&self, other: &KeyGroup, options: &[SortOptions]) -> Result<Ordering> {
+        let (batch, row) = self.first();
+        let (other_batch, other_row) = other.first();
+        compare_rows(&batch.keys, row, &other_batch.keys, other_row, options)
+    }
+}
+
+/// A side of the join, sorted on its join keys, read one group of rows with equal join
+/// keys at a time
+struct SortedInput {
+    stream: SendableRecordBatchStream,
+    /// The join keys of the side
+    on: Vec<String>,
+    /// The current batch and the next row to read from it
+    current: Option<(Arc<KeyedBatch>, usize)>,
+    /// Number of batches read so far
+    num_batches: usize,
+}
+
+impl SortedInput {
+    fn new(stream: SendableRecordBatchStream, on: Vec<String>) -> Self {
+        Self {
+            stream,
+            on,
+            current: None,
+            num_batches: 0,
+        }
+    }
+
+    /// Reads the next batch with rows into `current`, returning false once the side is
+    /// exhausted
+    async fn next_batch(&mut self) -> Result<bool> {
+        while let Some(batch) = self.stream.next().await {
+            let batch = batch?;
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            let keys = self
+                .on
+                .iter()
+                .map(|name| Ok(col(name).evaluate(&batch)?.into_array(batch.num_rows())))
+                .collect::<Result<Vec<_>>>()?;
+            let batch = KeyedBatch {
+                id: self.num_batches,
+                batch,
+                keys,
+            };
+            self.num_batches += 1;
+            self.current = Some((Arc::new(batch), 0));
+            return Ok(true);
+        }
+        self.current = None;
+        Ok(false)
+    }
+
+    /// Reads the next group of rows with equal join keys, if any
+    async fn next_group(&mut self, options: &[SortOptions]) -> Result<Option<KeyGroup>> {
+        let has_row = match &self.current {
+            Some((batch, row)) => *row < batch.batch.num_rows(),
+            None => false,
+        };
+        if !has_row && !self.next_batch().await? {
+            return Ok(None);
+        }
+
+        let (first, start) = self.current.clone().unwrap();
+        let mut ranges = vec![];
+        let (mut batch, mut row) = (first.clone(), start);
+        loop {
+            let end = group_end(&first.keys, start, &batch.keys, row, options)?;
+            if end > row {
+                ranges.push((batch.clone(), row, end));
+            }
+            if end < batch.batch.num_rows() {
+                self.current = Some((batch, end));
+                break;
+            }
+            // the group may continue in the next batch
+            if !self.next_batch().await? {
+                break;
+            }
+            batch = self.current.as_ref().unwrap().0.clone();
+            row = 0;
+        }
+        Ok(Some(KeyGroup { ranges }))
+    }
+}
+
+/// Returns the end of the rows of `keys` from `start` whose join keys are equal to the
+/// ones of the row `group_row` of `group_keys`
+fn group_end(
+    group_keys: &[ArrayRef],
+    group_row: usize,
+    keys: &[ArrayRef],
+    start: usize,
+    options: &[SortOptions],
+) -> Result<usize> {
+    let num_rows = keys[0].len();
+    let comparator = RowComparator::try_new(group_keys, keys, options)?;
+    Ok((start..num_rows)
+        .find(|row| comparator.compare(group_row, *row) != Ordering::Equal)
+        .unwrap_or(num_rows))
+}
+
+/// A row of a side of [JoinedRows], as the id of its batch and its row in the batch
+type RowIndex = (usize, usize);
+
+/// Rows to join, whose pairs of left and right rows are generated as the output
+/// batches are built
+enum JoinedGroup {
+    /// Every pair of rows of a left and a right group with matching join keys, the
+    /// right rows varying fastest
+    Matched(Vec<RowIndex>, Vec<RowIndex>),
+    /// Rows of the left side, with nulls for the right side
+    Left(Vec<RowIndex>),
+    /// Rows of the right side, with nulls for the left side
+    Right(Vec<RowIndex>),
+}
+
+impl JoinedGroup {
+    fn len(&self) -> usize {
+        match self {
+            JoinedGroup::Matched(left, right) => left.len() * right.len(),
+            JoinedGroup::Left(rows) | JoinedGroup::Right(rows) => rows.len(),
+        }
+    }
+
+    /// The pair of rows at `position`, None being a row of nulls
+    fn get(&self, position: usize) -> (Option<RowIndex>, Option<RowIndex>) {
+        match self {
+            JoinedGroup::Matched(left, right) => (
+                Some(left[position / right.len()]),
+                Some(right[position % right.len()]),
+            ),
+            JoinedGroup::Left(rows) => (Some(rows[position]), None),
+            JoinedGroup::Right(rows) => (None, Some(rows[position])),
+        }
+    }
+
+    /// The rows of each side that the pairs from `position` on are taken from
+    fn rows_from(&self, position: usize) -> (&[RowIndex], &[RowIndex]) {
+        match self {
+            JoinedGroup::Matched(left, right) => (&left[position / right.len()..], right),
+            JoinedGroup::Left(rows) => (&rows[position..], &[]),
+            JoinedGroup::Right(rows) => (&[], &rows[position..]),
+        }
+    }
+}
+
+/// The joined rows of the next output batches
+struct JoinedRows {
+    schema: SchemaRef,
+    left_schema: SchemaRef,
+    right_schema: SchemaRef,
+    join_type: JoinType,
+    /// The batches of the left side the rows are taken from, the first one being
+    /// empty, to build the columns from when no row of the side is taken
+    left: Vec<RecordBatch>,
+    right: Vec<RecordBatch>,
+    /// The position of the batches of each side in `left` and `right`, by id
+    left_ids: HashMap<usize, usize>,
+    right_ids: HashMap<usize, usize>,
+    /// The groups of rows to join, the first one from its pair at `cursor` on
+    groups: VecDeque<JoinedGroup>,
+    cursor: usize,
+    /// The number of pairs of rows left to join
+    num_rows: usize,
+}
+
+impl JoinedRows {
+    fn try_new(
+        schema: SchemaRef,
+        left_schema: SchemaRef,
+        right_schema: SchemaRef,
+        join_type: JoinType,
+    ) -> Result<Self> {
+        Ok(Self {
+            left: vec![empty_batch(&left_schema)?],
+            right: vec![empty_batch(&right_schema)?],
+            schema,
+            left_schema,
+            right_schema,
+            join_type,
+            left_ids: HashMap::new(),
+            right_ids: HashMap::new(),
+            groups: VecDeque::new(),
+            cursor: 0,
+            num_rows: 0,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.num_rows
+    }
+
+    /// The rows of `group` of the left side, keeping their batches
+    fn left_rows(&mut self, group: &KeyGroup) -> Vec<RowIndex> {
+        group_rows(group, &mut self.left, &mut self.left_ids)
+    }
+
+    /// The rows of `group` of the right side, keeping their batches
+    fn right_rows(&mut self, group: &KeyGroup) -> Vec<RowIndex> {
+        group_rows(group, &mut self.right, &mut self.right_ids)
+    }
+
+    fn push(&mut self, group: JoinedGroup) {
+        self.num_rows += group.len();
+        self.groups.push_back(group);
+    }
+
+    /// Adds the rows of two groups of rows with matching join keys
+    fn push_matched(&mut self, left: &KeyGroup, right: &KeyGroup) {
+        match self.join_type {
+            JoinType::Inner | JoinType::Left | JoinType::Right | JoinType::Full => {
+                let left_rows = self.left_rows(left);
+                let right_rows = self.right_rows(right);
+                self.push(JoinedGroup::Matched(left_rows, right_rows));
+            }
+            // each row of the left side is issued once
+            JoinType::Semi => {
+                let left_rows = self.left_rows(left);
+                self.push(JoinedGroup::Left(left_rows));
+            }
+            JoinType::Anti => {}
+        }
+    }
+
+    /// Adds the rows of a group of the left side without a match on the right side
+    fn push_unmatched_left(&mut self, group: &KeyGroup) {
+        if let JoinType::Left | JoinType::Full | JoinType::Anti = self.join_type {
+            let left_rows = self.left_rows(group);
+            self.push(JoinedGroup::Left(left_rows));
+        }
+    }
+
+    /// Adds the rows of a group of the right side without a match on the left side
+    fn push_unmatched_right(&mut self, group: &KeyGroup) {
+        if let JoinType::Right | JoinType::Full = self.join_type {
+            let right_rows = self.right_rows(group);
+            self.push(JoinedGroup::Right(right_rows));
+        }
+    }
+
+    /// Builds a batch from the next `num_rows` pairs of rows, moving the cursor past
+    /// them
+    fn build_batch(&mut self, num_rows: usize) -> Result<RecordBatch> {
+        // the (batch, row) pairs of the rows, by the position of the batches
+        let mut indices = Vec::with_capacity(num_rows);
+        while indices.len() < num_rows {
+            let group = &self.groups[0];
+            let end = group.len().min(self.cursor + num_rows - indices.len());
+            let (left_ids, right_ids) = (&self.left_ids, &self.right_ids);
+            indices.extend((self.cursor..end).map(|position| {
+                let (left, right) = group.get(position);
+                (
+                    left.map(|(id, row)| (left_ids[&id], row)),
+                    right.map(|(id, row)| (right_ids[&id], row)),
+                )
+            }));
+            if end == group.len() {
+                self.groups.pop_front();
+                self.cursor = 0;
+            } else {
+                self.cursor = end;
+            }
+        }
+        self.num_rows -= num_rows;
+
+        let mut columns = Vec::with_capacity(self.schema.fields().len());
+        for field in self.schema.fields() {
+            let (left_column, right_column) = join_column_indices(
+                field.name(),
+                &self.left_schema,
+                &self.right_schema,
+                &self.join_type,
+            )?;
+
+            // gather the [ArrayData] of the left batches followed by the ones of the
+            // right batches, as references to be passed to [MutableArrayData]
+            let mut arrays = vec![];
+            if let Some(i) = left_column {
+                arrays.extend(self.left.iter().map(|batch| batch.column(i).data()));
+            }
+            let right_offset = arrays.len();
+            if let Some(i) = right_column {
+                arrays.extend(self.right.iter().map(|batch| batch.column(i).data()));
+            }
+            let arrays = arrays
+                .iter()
+                .map(|array| array.as_ref())
+                .collect::<Vec<_>>();
+            let mut mutable = MutableArrayData::new(arrays, true, num_rows);
+
+            for (left_index, right_index) in &indices {
+                match (left_column, left_index, right_column, right_index) {
+                    (Some(_), Some((batch, row)), _, _) => {
+                        mutable.extend(*batch, *row, *row + 1)
+                    }
+                    (_, _, Some(_), Some((batch, row))) => {
+                        mutable.extend(right_offset + *batch, *row, *row + 1)
+                    }
+                    _ => mutable.extend_nulls(1),
+                }
+            }
+            columns.push(make_array(Arc::new(mutable.freeze())));
+        }
+        let batch = RecordBatch::try_new(self.schema.clone(), columns)?;
+
+        self.release_batches();
+        Ok(batch)
+    }
+
+    /// Removes the batches that no pair of rows after the cursor is taken from, so
+    /// that only the batches of the rows of the next output batches are kept in memory
+    fn release_batches(&mut self) {
+        let mut left_used = HashSet::new();
+        let mut right_used = HashSet::new();
+        for (i, group) in self.groups.iter().enumerate() {
+            let position = if i == 0 { self.cursor } else { 0 };
+            let (left, right) = group.rows_from(position);
+            left_used.extend(left.iter().map(|(id, _)| *id));
+            right_used.extend(right.iter().map(|(id, _)| *id));
+        }
+        release_unused_batches(&mut self.left, &mut self.left_ids, &left_used);
+        release_unused_batches(&mut self.right, &mut self.right_ids, &right_used);
+    }
+}
+
+/// Removes the batches whose id is not `used` from `batches`, except the first empty
+/// one, and from `ids`, updating the positions of the remaining ones
+fn release_unused_batches(
+    batches: &mut Vec<RecordBatch>,
+    ids: &mut HashMap<usize, usize>,
+    used: &HashSet<usize>,
+) {
+    ids.retain(|id, _| used.contains(id));
+    let mut released = batches.drain(..).map(Some).collect::<Vec<_>>();
+    batches.push(released[0].take().unwrap());
+    for position in ids.values_mut() {
+        batches.push(released[*position].take().unwrap());
+        *position = batches.len() - 1;
+    }
+}
+
+/// Returns the rows of `group`, adding the batches of the group that are not part of
+/// `batches` yet
+fn group_rows(
+    group: &KeyGroup,
+    batches: &mut Vec<RecordBatch>,
+    ids: &mut HashMap<usize, usize>,
+) -> Vec<RowIndex> {
+    let mut rows = vec![];
+    for (batch, start, end) in &group.ranges {
+        ids.entry(batch.id).or_insert_with(|| {
+            batches.push(batch.batch.clone());
+            batches.len() - 1
+        });
+        rows.extend((*start..*end).map(|row| (batch.id, row)));
+    }
+    rows
+}
+
+/// Joins the groups of rows of `left` and `right` and sends the joined rows to `output`,
+/// in batches of `batch_size` rows
+async fn sort_merge_join(
+    mut left: SortedInput,
+    mut right: SortedInput,
+    mut joined: JoinedRows,
+    options: Vec<SortOptions>,
+    batch_size: usize,
+    output: &mut mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
+    let mut left_group = left.next_group(&options).await?;
+    let mut right_group = right.next_group(&options).await?;
+    loop {
+        let ordering = match (&left_group, &right_group) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(left_group), Some(right_group)) => {
+                left_group.compare(right_group, &options)?
+            }
+        };
+        match ordering {
+            Ordering::Less => {
+                let group = left_group.take().unwrap();
+                joined.push_unmatched_left(&group);
+                left_group = left.next_group(&options).await?;
+            }
+            Ordering::Greater => {
+                let group = right_group.take().unwrap();
+                joined.push_unmatched_right(&group);
+                right_group = right.next_group(&options).await?;
+            }
+            Ordering::Equal => {
+                let left_rows = left_group.take().unwrap();
+                let right_rows = right_group.take().unwrap();
+                // null join keys do not match each other
+                if left_rows.has_null_key() {
+                    joined.push_unmatched_left(&left_rows);
+                    joined.push_unmatched_right(&right_rows);
+                } else {
+                    joined.push_matched(&left_rows, &right_rows);
+                }
+                left_group = left.next_group(&options).await?;
+                right_group = right.next_group(&options).await?;
+            }
+        }
+
+        while joined.len() >= batch_size {
+            let batch = joined.build_batch(batch_size)?;
+            if output.send(Ok(batch)).await.is_err() {
+                // the plan is being torn down
+                return Ok(());
+            }
+        }
+    }
+
+    while joined.len() > 0 {
+        let batch = joined.build_batch(joined.len().min(batch_size))?;
+        if output.send(Ok(batch)).await.is_err() {
+            // the plan is being torn down
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+pin_project! {
+    struct SortMergeJoinStream {
+        #[pin]
+        output: mpsc::Receiver<ArrowResult<RecordBatch>>,
+        schema: SchemaRef,
+    }
+}
+
+impl SortMergeJoinStream {
+    fn new(
+        left: SortedInput,
+        right: SortedInput,
+        joined: JoinedRows,
+        options: Vec<SortOptions>,
+        batch_size: usize,
+    ) -> Self {
+        let (mut sender, receiver) = mpsc::channel(1);
+
+        let schema = joined.schema.clone();
+        tokio::spawn(async move {
+            if let Err(e) =
+                sort_merge_join(left, right, joined, options, batch_size, &mut sender)
+                    .await
+            {
+                // If send fails, plan being torn down, no place to send the error
+                sender.send(Err(e.into_arrow_external_error())).await.ok();
+            }
+        });
+
+        Self {
+            output: receiver,
+            schema,
+        }
+    }
+}
+
+impl Stream for SortMergeJoinStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().output.poll_next(cx)
+    }
+}
+
+impl RecordBatchStream for SortMergeJoinStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        physical_plan::{common, memory::MemoryExec},
+        test::{build_table_i32, columns, format_batch},
+    };
+
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    /// Builds a table whose partition is split in batches of `batch_size` rows
+    fn build_table(
+        a: (&str, &Vec<i32>),
+        b: (&str, &Vec<i32>),
+        c: (&str, &Vec<i32>),
+        batch_size: usize,
+    ) -> Arc<dyn ExecutionPlan> {
+        let batch = build_table_i32(a, b, c);
+        let schema = batch.schema();
+        let batches = (0..batch.num_rows())
+            .step_by(batch_size)
+            .map(|offset| {
+                let length = batch_size.min(batch.num_rows() - offset);
+                let columns = batch
+                    .columns()
+                    .iter()
+                    .map(|column| column.slice(offset, length))
+                    .collect();
+                RecordBatch::try_new(schema.clone(), columns).unwrap()
+            })
+            .collect();
+        Arc::new(MemoryExec::try_new(&vec![batches], schema, None).unwrap())
+    }
+
+    async fn join_collect(
+        left: Arc<dyn ExecutionPlan>,
+        right: Arc<dyn ExecutionPlan>,
+        on: &[(&str, &str)],
+        join_type: &JoinType,
+    ) -> Result<(Vec<String>, Vec<String>)> {
+        let on: Vec<_> = on
+            .iter()
+            .map(|(l, r)| (l.to_string(), r.to_string()))
+            .collect();
+        let sort_options = vec![SortOptions::default(); on.len()];
+        let join =
+            SortMergeJoinExec::try_new(left, right, &on, join_type, sort_options, 2)?;
+        let columns = columns(&join.schema());
+        let batches = common::collect(join.execute(0).await?).await?;
+        // the joined rows are issued in batches of at most 2 rows
+        assert!(batches.iter().all(|batch| batch.num_rows() <= 2));
+        let rows = batches.iter().flat_map(format_batch).collect();
+        Ok((columns, rows))
+    }
+
+    #[tokio::test]
+    async fn join_inner_one() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 5]), // this has a repetition
+            ("c1", &vec![7, 8, 9]),
+            3,
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+            3,
+        );
+
+        let (columns, rows) =
+            join_collect(left, right, &[("b1", "b1")], &JoinType::Inner).await?;
+        assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "c2"]);
+        // the rows are issued in the order of the join keys
+        assert_eq!(rows, vec!["1,4,7,10,70", "2,5,8,20,80", "3,5,9,20,80"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_inner_groups_across_batches() -> Result<()> {
+        // the rows with the same join key span several batches on both sides
+        let left = build_table(
+            ("a1", &vec![1, 2, 3, 4, 5]),
+            ("b1", &vec![4, 5, 5, 5, 7]),
+            ("c1", &vec![7, 8, 9, 10, 11]),
+            2,
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30, 40]),
+            ("b2", &vec![5, 5, 5, 7]),
+            ("c2", &vec![70, 80, 90, 100]),
+            1,
+        );
+
+        let (columns, rows) =
+            join_collect(left, right, &[("b1", "b2")], &JoinType::Inner).await?;
+        assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "b2", "c2"]);
+        assert_eq!(
+            rows,
+            vec![
+                "2,5,8,10,5,70",
+                "2,5,8,20,5,80",
+                "2,5,8,30,5,90",
+                "3,5,9,10,5,70",
+                "3,5,9,20,5,80",
+                "3,5,9,30,5,90",
+                "4,5,10,10,5,70",
+                "4,5,10,20,5,80",
+                "4,5,10,30,5,90",
+                "5,7,11,40,7,100",
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_releases_batches() -> Result<()> {
+        let keys = (0..100).collect::<Vec<_>>();
+        let left = build_table(("a1", &keys), ("b1", &keys), ("c1", &keys), 1);
+        let right = build_table(("a2", &keys), ("b1", &keys), ("c2", &keys), 1);
+        let on = vec![("b1".to_string(), "b1".to_string())];
+        let options = vec![SortOptions::default()];
+        let join = SortMergeJoinExec::try_new(
+            left.clone(),
+            right.clone(),
+            &on,
+            &JoinType::Inner,
+            options.clone(),
+            2,
+        )?;
+
+        let mut joined = JoinedRows::try_new(
+            join.schema(),
+            left.schema(),
+            right.schema(),
+            JoinType::Inner,
+        )?;
+        let mut left = SortedInput::new(left.execute(0).await?, vec!["b1".to_string()]);
+        let mut right = SortedInput::new(right.execute(0).await?, vec!["b1".to_string()]);
+        let mut num_rows = 0;
+        while let (Some(left_group), Some(right_group)) = (
+            left.next_group(&options).await?,
+            right.next_group(&options).await?,
+        ) {
+            joined.push_matched(&left_group, &right_group);
+            // a row of one batch of each side always remains to be issued
+            if joined.len() > 2 {
+                num_rows += joined.build_batch(2)?.num_rows();
+            }
+            // the empty batch and the batches of the at most 2 remaining rows
+            assert!(joined.left.len() <= 3);
+            assert!(joined.right.len() <= 3);
+        }
+        num_rows += joined.build_batch(joined.len())?.num_rows();
+        assert_eq!(100, num_rows);
+        assert_eq!(1, joined.left.len());
+        assert_eq!(1, joined.right.len());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_generates_cross_product_lazily() -> Result<()> {
+        // every row of each side has the same join key
+        let keys = vec![1; 10];
+        let left = build_table(("a1", &keys), ("b1", &keys), ("c1", &keys), 1);
+        let right = build_table(("a2", &keys), ("b1", &keys), ("c2", &keys), 1);
+        let options = vec![SortOptions::default()];
+        let schema = build_join_schema(
+            &left.schema(),
+            &right.schema(),
+            &[("b1".to_string(), "b1".to_string())],
+            &JoinType::Inner,
+        );
+
+        let mut joined = JoinedRows::try_new(
+            Arc::new(schema),
+            left.schema(),
+            right.schema(),
+            JoinType::Inner,
+        )?;
+        let mut left = SortedInput::new(left.execute(0).await?, vec!["b1".to_string()]);
+        let mut right = SortedInput::new(right.execute(0).await?, vec!["b1".to_string()]);
+        let left_group = left.next_group(&options).await?.unwrap();
+        let right_group = right.next_group(&options).await?.unwrap();
+        joined.push_matched(&left_group, &right_group);
+        assert_eq!(100, joined.len());
+
+        for i in 1..50 {
+            assert_eq!(2, joined.build_batch(2)?.num_rows());
+            // the batch of a left row is released once joined with every right row
+            assert_eq!(1 + 10 - (2 * i) / 10, joined.left.len());
+            assert_eq!(1 + 10, joined.right.len());
+        }
+        assert_eq!(2, joined.build_batch(2)?.num_rows());
+        assert_eq!(0, joined.len());
+        assert_eq!(1, joined.left.len());
+        assert_eq!(1, joined.right.len());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_inner_two() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 2]),
+            ("b2", &vec![1, 2, 2]),
+            ("c1", &vec![7, 8, 9]),
+            2,
+        );
+        let right = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b2", &vec![1, 2, 2]),
+            ("c2", &vec![70, 80, 90]),
+            2,
+        );
+
+        let (columns, rows) =
+            join_collect(left, right, &[("a1", "a1"), ("b2", "b2")], &JoinType::Inner)
+                .await?;
+        assert_eq!(columns, vec!["a1", "b2", "c1", "c2"]);
+        assert_eq!(rows, vec!["1,1,7,70", "2,2,8,80", "2,2,9,80"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_outer() -> Result<()> {
+        let left = || {
+            build_table(
+                ("a1", &vec![1, 2, 3]),
+                ("b1", &vec![4, 5, 7]), // 7 does not exist on the right
+                ("c1", &vec![7, 8, 9]),
+                2,
+            )
+        };
+        let right = || {
+            build_table(
+                ("a2", &vec![10, 20, 30]),
+                ("b1", &vec![4, 5, 6]), // 6 does not exist on the left
+                ("c2", &vec![70, 80, 90]),
+                2,
+            )
+        };
+        let on = &[("b1", "b1")];
+
+        let (columns, rows) = join_collect(left(), right(), on, &JoinType::Left).await?;
+        assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "c2"]);
+        assert_eq!(rows, vec!["1,4,7,10,70", "2,5,8,20,80", "3,7,9,NULL,NULL"]);
+
+        let (columns, rows) = join_collect(left(), right(), on, &JoinType::Right).await?;
+        assert_eq!(columns, vec!["a1", "c1", "a2", "b1", "c2"]);
+        assert_eq!(
+            rows,
+            vec!["1,7,10,4,70", "2,8,20,5,80", "NULL,NULL,30,6,90"]
+        );
+
+        // the join key is taken from whichever side has a row
+        let (columns, rows) = join_collect(left(), right(), on, &JoinType::Full).await?;
+        assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "c2"]);
+        assert_eq!(
+            rows,
+            vec![
+                "1,4,7,10,70",
+                "2,5,8,20,80",
+                "NULL,6,NULL,30,90",
+                "3,7,9,NULL,NULL"
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_semi_anti() -> Result<()> {
+        let left = || {
+            build_table(
+                ("a1", &vec![1, 2, 3, 4]),
+                ("b1", &vec![4, 5, 5, 7]),
+                ("c1", &vec![7, 8, 9, 10]),
+                3,
+            )
+        };
+        let right = || {
+            build_table(
+                ("a2", &vec![10, 20, 30]),
+                ("b1", &vec![5, 5, 6]), // 5 matches twice
+                ("c2", &vec![70, 80, 90]),
+                1,
+            )
+        };
+        let on = &[("b1", "b1")];
+
+        // the rows of the left side are issued at most once
+        let (columns, rows) = join_collect(left(), right(), on, &JoinType::Semi).await?;
+        assert_eq!(columns, vec!["a1", "b1", "c1"]);
+        assert_eq!(rows, vec!["2,5,8", "3,5,9"]);
+
+        let (columns, rows) = join_collect(left(), right(), on, &JoinType::Anti).await?;
+        assert_eq!(columns, vec!["a1", "b1", "c1"]);
+        assert_eq!(rows, vec!["1,4,7", "4,7,10"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn join_null_keys() -> Result<()> {
+        let table = |name: &str, keys: Vec<Option<i32>>, values: Vec<i32>| {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new(&format!("{}_key", name), DataType::Int32, true),
+                Field::new(&format!("{}_value", name), DataType::Int32, false),
+            ]));
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from(keys)),
+                    Arc::new(Int32Array::from(values)),
+                ],
+            )
+            .unwrap();
+            Arc::new(MemoryExec::try_new(&vec![vec![batch]], schema, None).unwrap())
+                as Arc<dyn ExecutionPlan>
+        };
+        // the null keys are sorted first
+        let left = table("l", vec![None, Some(1), Some(2)], vec![1, 2, 3]);
+        let right = table("r", vec![None, Some(2)], vec![10, 20]);
+
+        // null keys do not match each other
+        let (_, rows) =
+            join_collect(left, right, &[("l_key", "r_key")], &JoinType::Full).await?;
+        assert_eq!(
+            rows,
+            vec![
+                "NULL,1,NULL,NULL",
+                "NULL,NULL,NULL,10",
+                "1,2,NULL,NULL",
+                "2,3,2,20"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn join_partition_count_mismatch() {
+        let left = build_table(("a1", &vec![1]), ("b1", &vec![4]), ("c1", &vec![7]), 1);
+        let batch = build_table_i32(("a2", &vec![1]), ("b1", &vec![4]), ("c2", &vec![7]));
+        let schema = batch.schema();
+        let right = Arc::new(
+            MemoryExec::try_new(&vec![vec![batch.clone()], vec![batch]], schema, None)
+                .unwrap(),
+        );
+        let on = vec![("b1".to_string(), "b1".to_string())];
+
+        let join = SortMergeJoinExec::try_new(
+            left,
+            right,
+            &on,
+            &JoinType::Inner,
+            vec![SortOptions::default()],
+            2,
+        );
+        assert!(join.is_err());
+    }
+}
@@ -0,0 +1,371 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the sort preserving merge plan, which merges sorted partitions into a single
+//! sorted partition
+
+use std::any::Any;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc;
+use futures::sink::SinkExt;
+use futures::stream::StreamExt;
+use futures::Stream;
+
+use async_trait::async_trait;
+
+use arrow::record_batch::RecordBatch;
+use arrow::{
+    datatypes::SchemaRef,
+    error::{ArrowError, Result as ArrowResult},
+};
+
+use pin_project_lite::pin_project;
+
+use super::{RecordBatchStream, SendableRecordBatchStream};
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::physical_plan::sort::SortedRunsMerger;
+use crate::physical_plan::{Distribution, ExecutionPlan, Partitioning};
+
+/// Sort preserving merge execution plan. Its input partitions must be sorted by `expr`,
+/// and are merged in a single partition sorted by `expr`, as they are streamed.
+#[derive(Debug)]
+pub struct SortPreservingMergeExec {
+    /// Input execution plan
+    input: Arc<dyn ExecutionPlan>,
+    /// Sort expressions of the input partitions
+    expr: Vec<PhysicalSortExpr>,
+    /// Number of rows of the output batches
+    batch_size: usize,
+}
+
+impl SortPreservingMergeExec {
+    /// Create a new SortPreservingMergeExec
+    pub fn new(
+        expr: Vec<PhysicalSortExpr>,
+        input: Arc<dyn ExecutionPlan>,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            input,
+            expr,
+            batch_size,
+        }
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// Sort expressions
+    pub fn expr(&self) -> &[PhysicalSortExpr] {
+        &self.expr
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for SortPreservingMergeExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::UnspecifiedDistribution
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(SortPreservingMergeExec::new(
+                self.expr.clone(),
+                children[0].clone(),
+                self.batch_size,
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "SortPreservingMergeExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        // SortPreservingMergeExec produces a single partition
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "SortPreservingMergeExec invalid partition {}",
+                partition
+            )));
+        }
+
+        let input_partitions = self.input.output_partitioning().partition_count();
+        match input_partitions {
+            0 => Err(DataFusionError::Internal(
+                "SortPreservingMergeExec requires at least one input partition"
+                    .to_owned(),
+            )),
+            1 => {
+                // a single sorted partition is already merged
+                self.input.execute(0).await
+            }
+            _ => {
+                // execute each partition in its own task, whose batches are sent to a
+                // channel read by the merge
+                let mut inputs = vec![];
+                for part_i in 0..input_partitions {
+                    let (mut sender, receiver) =
+                        mpsc::channel::<ArrowResult<RecordBatch>>(1);
+                    let input = self.input.clone();
+                    tokio::spawn(async move {
+                        let mut stream = match input.execute(part_i).await {
+                            Err(e) => {
+                                // If send fails, plan being torn
+                                // down, no place to send the error
+                                let arrow_error = ArrowError::ExternalError(Box::new(e));
+                                sender.send(Err(arrow_error)).await.ok();
+                                return;
+                            }
+                            Ok(stream) => stream,
+                        };
+
+                        while let Some(item) = stream.next().await {
+                            if sender.send(item).await.is_err() {
+                                // the merge is being torn down
+                                return;
+                            }
+                        }
+                    });
+                    inputs.push(receiver);
+                }
+
+                let merger = SortedRunsMerger::new(
+                    self.schema(),
+                    self.expr.clone(),
+                    input_partitions,
+                    self.batch_size,
+                );
+                let (mut sender, receiver) = mpsc::channel(1);
+                tokio::spawn(async move {
+                    if let Err(e) = merge_partitions(inputs, merger, &mut sender).await {
+                        // If send fails, plan being torn down, no place to send the error
+                        sender.send(Err(e.into_arrow_external_error())).await.ok();
+                    }
+                });
+
+                Ok(Box::pin(SortPreservingMergeStream {
+                    output: receiver,
+                    schema: self.schema(),
+                }))
+            }
+        }
+    }
+}
+
+/// Merges the sorted batches of `inputs` and sends them to `output`
+async fn merge_partitions(
+    mut inputs: Vec<mpsc::Receiver<ArrowResult<RecordBatch>>>,
+    mut merger: SortedRunsMerger,
+    output: &mut mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
+    while !merger.is_finished() {
+        if let Some(partition) = merger.pending_run() {
+            let batch = inputs[partition].next().await.transpose()?;
+            merger.push_batch(partition, batch)?;
+        } else if let Some(batch) = merger.next_batch()? {
+            if output.send(Ok(batch)).await.is_err() {
+                // the plan is being torn down
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+pin_project! {
+    struct SortPreservingMergeStream {
+        #[pin]
+        output: mpsc::Receiver<ArrowResult<RecordBatch>>,
+        schema: SchemaRef,
+    }
+}
+
+impl Stream for SortPreservingMergeStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().output.poll_next(cx)
+    }
+}
+
+impl RecordBatchStream for SortPreservingMergeStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::csv::{CsvExec, CsvReadOptions};
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::merge::MergeExec;
+    use crate::physical_plan::sort::{SortExec, SortOptions};
+    use crate::test;
+    use arrow::array::*;
+    use arrow::datatypes::*;
+    use arrow::util::display::array_value_to_string;
+
+    #[tokio::test]
+    async fn merge_sorted_partitions() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+        let batch = |a: Vec<Option<i32>>, b: Vec<&str>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from(a)),
+                    Arc::new(StringArray::from(b)),
+                ],
+            )
+        };
+        let partitions = vec![
+            vec![
+                batch(vec![Some(1), Some(4)], vec!["a", "b"])?,
+                batch(vec![Some(7), None], vec!["c", "d"])?,
+            ],
+            vec![],
+            vec![batch(vec![Some(2), Some(4), Some(9)], vec!["e", "f", "g"])?],
+        ];
+        let input = Arc::new(MemoryExec::try_new(&partitions, schema.clone(), None)?);
+        let merge = Arc::new(SortPreservingMergeExec::new(
+            vec![PhysicalSortExpr {
+                expr: col("a"),
+                options: SortOptions {
+                    descending: false,
+                    nulls_first: false,
+                },
+            }],
+            input,
+            2,
+        ));
+        assert_eq!(merge.output_partitioning().partition_count(), 1);
+
+        let result = collect(merge).await?;
+        let result = result
+            .iter()
+            .map(|batch| {
+                let a = as_primitive_array::<Int32Type>(batch.column(0));
+                let b = as_string_array(batch.column(1));
+                a.iter()
+                    .enumerate()
+                    .map(|(i, a)| (a, b.value(i).to_owned()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let expected = vec![
+            vec![(Some(1), "a".to_owned()), (Some(2), "e".to_owned())],
+            vec![(Some(4), "b".to_owned()), (Some(4), "f".to_owned())],
+            vec![(Some(7), "c".to_owned()), (Some(9), "g".to_owned())],
+            vec![(None, "d".to_owned())],
+        ];
+        assert_eq!(expected, result);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_sorted_csv_partitions() -> Result<()> {
+        let schema = test::aggr_test_schema();
+        let partitions = 4;
+        let path = test::create_partitioned_csv("aggregate_test_100.csv", partitions)?;
+        let csv = Arc::new(CsvExec::try_new(
+            &path,
+            CsvReadOptions::new().schema(&schema),
+            None,
+            1024,
+            None,
+        )?);
+        let sort_expr = vec![
+            PhysicalSortExpr {
+                expr: col("c1"),
+                options: SortOptions::default(),
+            },
+            PhysicalSortExpr {
+                expr: col("c7"),
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: true,
+                },
+            },
+        ];
+
+        let sort = SortExec::try_new(
+            sort_expr.clone(),
+            Arc::new(MergeExec::new(csv.clone())),
+            1,
+        )?;
+        let expected = sort_keys(&collect(Arc::new(sort)).await?)?;
+
+        // each partition is sorted separately before being merged
+        let sort = SortExec::try_new(sort_expr.clone(), csv, 1)?
+            .with_preserve_partitioning(true);
+        assert_eq!(sort.output_partitioning().partition_count(), partitions);
+        let merge = SortPreservingMergeExec::new(sort_expr, Arc::new(sort), 30);
+        let result = collect(Arc::new(merge)).await?;
+        assert_eq!(result.len(), 4);
+        assert_eq!(sort_keys(&result)?, expected);
+
+        Ok(())
+    }
+
+    /// the values of the columns c1 and c7 of `batches`
+    fn sort_keys(batches: &[RecordBatch]) -> Result<Vec<String>> {
+        let mut keys = vec![];
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                keys.push(format!(
+                    "{},{}",
+                    array_value_to_string(batch.column(0), row)?,
+                    array_value_to_string(batch.column(6), row)?,
+                ));
+            }
+        }
+        Ok(keys)
+    }
+}
@@ -0,0 +1,817 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Statistical aggregate expressions: variance, standard deviation, covariance and
+//! correlation, which are computed in a single pass with Welford's algorithm, and the
+//! ordered-set aggregates median and `percentile_cont`.
+//!
+//! The partial states of the moments-based aggregates are merged with the formulas of
+//! Chan et al., so that they do not lose precision when aggregated in two phases.
+
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, Float64Array, ListArray, UInt64Array};
+use arrow::datatypes::{DataType, Field};
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::aggregates::constant_argument;
+use crate::physical_plan::expressions::format_state_name;
+use crate::physical_plan::group_scalar::OrderedFloat64;
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+
+/// Whether a statistic is computed for a sample, with Bessel's correction, or for the
+/// whole population
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsType {
+    /// The statistic of a sample of the population
+    Sample,
+    /// The statistic of the whole population
+    Population,
+}
+
+/// Returns the f64 values of `array`, which must be a `Float64Array`
+fn as_f64_array(array: &ArrayRef) -> Result<&Float64Array> {
+    array
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "Expected a Float64Array, got {:?}",
+                array.data_type()
+            ))
+        })
+}
+
+/// Returns the u64 values of `array`, which must be a `UInt64Array`
+fn as_u64_array(array: &ArrayRef) -> Result<&UInt64Array> {
+    array.as_any().downcast_ref::<UInt64Array>().ok_or_else(|| {
+        DataFusionError::Internal(format!(
+            "Expected a UInt64Array, got {:?}",
+            array.data_type()
+        ))
+    })
+}
+
+/// Returns the f64 value of a scalar, `None` when it is null
+fn as_f64(value: &ScalarValue) -> Result<Option<f64>> {
+    match value {
+        ScalarValue::Float64(value) => Ok(*value),
+        other => Err(DataFusionError::Internal(format!(
+            "Expected a Float64 value, got {:?}",
+            other
+        ))),
+    }
+}
+
+/// Returns the u64 value of a scalar, `0` when it is null
+fn as_u64(value: &ScalarValue) -> Result<u64> {
+    match value {
+        ScalarValue::UInt64(value) => Ok(value.unwrap_or(0)),
+        other => Err(DataFusionError::Internal(format!(
+            "Expected a UInt64 value, got {:?}",
+            other
+        ))),
+    }
+}
+
+/// VAR_SAMP and VAR_POP aggregate expression
+#[derive(Debug)]
+pub struct Variance {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    stats_type: StatsType,
+}
+
+impl Variance {
+    /// Create a new variance aggregate function, whose argument must be of type f64
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String, stats_type: StatsType) -> Self {
+        Self {
+            name,
+            expr,
+            stats_type,
+        }
+    }
+}
+
+/// The fields of the state of a [VarianceAccumulator]
+fn variance_state_fields(name: &str) -> Vec<Field> {
+    vec![
+        Field::new(&format_state_name(name, "count"), DataType::UInt64, true),
+        Field::new(&format_state_name(name, "mean"), DataType::Float64, true),
+        Field::new(&format_state_name(name, "m2"), DataType::Float64, true),
+    ]
+}
+
+impl AggregateExpr for Variance {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(variance_state_fields(&self.name))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(VarianceAccumulator::new(self.stats_type, false)))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+}
+
+/// STDDEV and STDDEV_POP aggregate expression
+#[derive(Debug)]
+pub struct Stddev {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    stats_type: StatsType,
+}
+
+impl Stddev {
+    /// Create a new standard deviation aggregate function, whose argument must be of
+    /// type f64
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String, stats_type: StatsType) -> Self {
+        Self {
+            name,
+            expr,
+            stats_type,
+        }
+    }
+}
+
+impl AggregateExpr for Stddev {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(variance_state_fields(&self.name))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(VarianceAccumulator::new(self.stats_type, true)))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+}
+
+/// An accumulator of the count, mean and sum of squared differences from the mean of
+/// its values, from which the variance and the standard deviation are computed
+#[derive(Debug)]
+struct VarianceAccumulator {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    stats_type: StatsType,
+    /// Whether the square root of the variance is returned
+    stddev: bool,
+}
+
+impl VarianceAccumulator {
+    fn new(stats_type: StatsType, stddev: bool) -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            stats_type,
+            stddev,
+        }
+    }
+
+    fn update_value(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn merge_state(&mut self, count: u64, mean: f64, m2: f64) {
+        if count == 0 {
+            return;
+        }
+        let total = self.count + count;
+        let delta = mean - self.mean;
+        self.mean += delta * count as f64 / total as f64;
+        self.m2 += m2 + delta * delta * self.count as f64 * count as f64 / total as f64;
+        self.count = total;
+    }
+}
+
+impl Accumulator for VarianceAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![
+            ScalarValue::from(self.count),
+            ScalarValue::from(self.mean),
+            ScalarValue::from(self.m2),
+        ])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        if let Some(value) = as_f64(&values[0])? {
+            self.update_value(value);
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let values = as_f64_array(&values[0])?;
+        for i in 0..values.len() {
+            if values.is_valid(i) {
+                self.update_value(values.value(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        let count = as_u64(&states[0])?;
+        let mean = as_f64(&states[1])?.unwrap_or(0.0);
+        let m2 = as_f64(&states[2])?.unwrap_or(0.0);
+        self.merge_state(count, mean, m2);
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &Vec<ArrayRef>) -> Result<()> {
+        let counts = as_u64_array(&states[0])?;
+        let means = as_f64_array(&states[1])?;
+        let m2s = as_f64_array(&states[2])?;
+        for i in 0..counts.len() {
+            if counts.is_valid(i) {
+                self.merge_state(counts.value(i), means.value(i), m2s.value(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let divisor = match self.stats_type {
+            StatsType::Sample => self.count.saturating_sub(1),
+            StatsType::Population => self.count,
+        };
+        if divisor == 0 {
+            return Ok(ScalarValue::Float64(None));
+        }
+        let variance = self.m2 / divisor as f64;
+        Ok(ScalarValue::from(if self.stddev {
+            variance.sqrt()
+        } else {
+            variance
+        }))
+    }
+}
+
+/// COVAR_SAMP and COVAR_POP aggregate expression
+#[derive(Debug)]
+pub struct Covariance {
+    name: String,
+    expr1: Arc<dyn PhysicalExpr>,
+    expr2: Arc<dyn PhysicalExpr>,
+    stats_type: StatsType,
+}
+
+impl Covariance {
+    /// Create a new covariance aggregate function, whose arguments must be of type f64
+    pub fn new(
+        expr1: Arc<dyn PhysicalExpr>,
+        expr2: Arc<dyn PhysicalExpr>,
+        name: String,
+        stats_type: StatsType,
+    ) -> Self {
+        Self {
+            name,
+            expr1,
+            expr2,
+            stats_type,
+        }
+    }
+}
+
+impl AggregateExpr for Covariance {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(moments_state_fields(&self.name))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(MomentsAccumulator::new(Some(self.stats_type))))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr1.clone(), self.expr2.clone()]
+    }
+}
+
+/// CORR aggregate expression, the Pearson correlation coefficient of its arguments
+#[derive(Debug)]
+pub struct Correlation {
+    name: String,
+    expr1: Arc<dyn PhysicalExpr>,
+    expr2: Arc<dyn PhysicalExpr>,
+}
+
+impl Correlation {
+    /// Create a new correlation aggregate function, whose arguments must be of type f64
+    pub fn new(
+        expr1: Arc<dyn PhysicalExpr>,
+        expr2: Arc<dyn PhysicalExpr>,
+        name: String,
+    ) -> Self {
+        Self { name, expr1, expr2 }
+    }
+}
+
+impl AggregateExpr for Correlation {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(moments_state_fields(&self.name))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(MomentsAccumulator::new(None)))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr1.clone(), self.expr2.clone()]
+    }
+}
+
+/// The fields of the state of a [MomentsAccumulator]
+fn moments_state_fields(name: &str) -> Vec<Field> {
+    vec![
+        Field::new(&format_state_name(name, "count"), DataType::UInt64, true),
+        Field::new(&format_state_name(name, "mean1"), DataType::Float64, true),
+        Field::new(&format_state_name(name, "mean2"), DataType::Float64, true),
+        Field::new(&format_state_name(name, "m2_1"), DataType::Float64, true),
+        Field::new(&format_state_name(name, "m2_2"), DataType::Float64, true),
+        Field::new(
+            &format_state_name(name, "co_moment"),
+            DataType::Float64,
+            true,
+        ),
+    ]
+}
+
+/// An accumulator of the count, means, sums of squared differences from the means and
+/// sum of the products of the differences from the means of pairs of values, from which
+/// their covariance and their correlation are computed. The pairs with a null value are
+/// ignored.
+#[derive(Debug)]
+struct MomentsAccumulator {
+    count: u64,
+    mean1: f64,
+    mean2: f64,
+    m2_1: f64,
+    m2_2: f64,
+    co_moment: f64,
+    /// The kind of covariance returned, or `None` for the correlation
+    covariance: Option<StatsType>,
+}
+
+impl MomentsAccumulator {
+    fn new(covariance: Option<StatsType>) -> Self {
+        Self {
+            count: 0,
+            mean1: 0.0,
+            mean2: 0.0,
+            m2_1: 0.0,
+            m2_2: 0.0,
+            co_moment: 0.0,
+            covariance,
+        }
+    }
+
+    fn update_values(&mut self, value1: f64, value2: f64) {
+        self.count += 1;
+        let delta1 = value1 - self.mean1;
+        let delta2 = value2 - self.mean2;
+        self.mean1 += delta1 / self.count as f64;
+        self.mean2 += delta2 / self.count as f64;
+        self.m2_1 += delta1 * (value1 - self.mean1);
+        self.m2_2 += delta2 * (value2 - self.mean2);
+        self.co_moment += delta1 * (value2 - self.mean2);
+    }
+
+    fn merge_state(&mut self, other: &MomentsAccumulator) {
+        if other.count == 0 {
+            return;
+        }
+        let total = self.count + other.count;
+        let delta1 = other.mean1 - self.mean1;
+        let delta2 = other.mean2 - self.mean2;
+        // the weight of the product of the differences between the means
+        let weight = self.count as f64 * other.count as f64 / total as f64;
+        self.mean1 += delta1 * other.count as f64 / total as f64;
+        self.mean2 += delta2 * other.count as f64 / total as f64;
+        self.m2_1 += other.m2_1 + delta1 * delta1 * weight;
+        self.m2_2 += other.m2_2 + delta2 * delta2 * weight;
+        self.co_moment += other.co_moment + delta1 * delta2 * weight;
+        self.count = total;
+    }
+}
+
+impl Accumulator for MomentsAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![
+            ScalarValue::from(self.count),
+            ScalarValue::from(self.mean1),
+            ScalarValue::from(self.mean2),
+            ScalarValue::from(self.m2_1),
+            ScalarValue::from(self.m2_2),
+            ScalarValue::from(self.co_moment),
+        ])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        if let (Some(value1), Some(value2)) = (as_f64(&values[0])?, as_f64(&values[1])?) {
+            self.update_values(value1, value2);
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let values1 = as_f64_array(&values[0])?;
+        let values2 = as_f64_array(&values[1])?;
+        for i in 0..values1.len() {
+            if values1.is_valid(i) && values2.is_valid(i) {
+                self.update_values(values1.value(i), values2.value(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        let mut other = MomentsAccumulator::new(self.covariance);
+        other.count = as_u64(&states[0])?;
+        other.mean1 = as_f64(&states[1])?.unwrap_or(0.0);
+        other.mean2 = as_f64(&states[2])?.unwrap_or(0.0);
+        other.m2_1 = as_f64(&states[3])?.unwrap_or(0.0);
+        other.m2_2 = as_f64(&states[4])?.unwrap_or(0.0);
+        other.co_moment = as_f64(&states[5])?.unwrap_or(0.0);
+        self.merge_state(&other);
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &Vec<ArrayRef>) -> Result<()> {
+        let counts = as_u64_array(&states[0])?;
+        let columns = states[1..]
+            .iter()
+            .map(as_f64_array)
+            .collect::<Result<Vec<_>>>()?;
+        for i in 0..counts.len() {
+            if counts.is_valid(i) {
+                self.merge_state(&MomentsAccumulator {
+                    count: counts.value(i),
+                    mean1: columns[0].value(i),
+                    mean2: columns[1].value(i),
+                    m2_1: columns[2].value(i),
+                    m2_2: columns[3].value(i),
+                    co_moment: columns[4].value(i),
+                    covariance: self.covariance,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let value = match self.covariance {
+            Some(stats_type) => {
+                let divisor = match stats_type {
+                    StatsType::Sample => self.count.saturating_sub(1),
+                    StatsType::Population => self.count,
+                };
+                Some(self.co_moment / divisor as f64).filter(|_| divisor > 0)
+            }
+            // the correlation is undefined when a variance is zero
+            None => Some(self.co_moment / (self.m2_1 * self.m2_2).sqrt())
+                .filter(|_| self.count > 0 && self.m2_1 != 0.0 && self.m2_2 != 0.0),
+        };
+        Ok(ScalarValue::Float64(value))
+    }
+}
+
+/// PERCENTILE_CONT and MEDIAN aggregate expression, the value at a percentile of the
+/// sorted values of its argument, interpolated between the two closest values
+#[derive(Debug)]
+pub struct PercentileCont {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    /// The percentile, between 0 and 1
+    percentile: f64,
+}
+
+impl PercentileCont {
+    /// Create a new percentile aggregate function, whose argument must be of type f64
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: String, percentile: f64) -> Self {
+        Self {
+            name,
+            expr,
+            percentile,
+        }
+    }
+}
+
+/// Returns the value of the percentile argument of `PERCENTILE_CONT`, which must be a
+/// constant between 0 and 1
+pub fn percentile_value(expr: &Arc<dyn PhysicalExpr>) -> Result<f64> {
+    let value = constant_argument(expr).and_then(|value| as_f64(&value).ok().flatten());
+    match value {
+        Some(percentile) if (0.0..=1.0).contains(&percentile) => Ok(percentile),
+        _ => Err(DataFusionError::Plan(format!(
+            "PERCENTILE_CONT expects a constant percentile between 0 and 1, got {}",
+            expr
+        ))),
+    }
+}
+
+impl AggregateExpr for PercentileCont {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "values"),
+            DataType::List(Box::new(Field::new("item", DataType::Float64, true))),
+            false,
+        )])
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(PercentileContAccumulator {
+            values: vec![],
+            percentile: self.percentile,
+        }))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+}
+
+/// An accumulator of all the non-null values, which are sorted once all accumulated
+#[derive(Debug)]
+struct PercentileContAccumulator {
+    values: Vec<f64>,
+    percentile: f64,
+}
+
+impl Accumulator for PercentileContAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let values = self.values.iter().map(|v| ScalarValue::from(*v)).collect();
+        Ok(vec![ScalarValue::List(Some(values), DataType::Float64)])
+    }
+
+    fn update(&mut self, values: &Vec<ScalarValue>) -> Result<()> {
+        if let Some(value) = as_f64(&values[0])? {
+            self.values.push(value);
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &Vec<ArrayRef>) -> Result<()> {
+        let values = as_f64_array(&values[0])?;
+        self.values.reserve(values.len() - values.null_count());
+        for i in 0..values.len() {
+            if values.is_valid(i) {
+                self.values.push(values.value(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &Vec<ScalarValue>) -> Result<()> {
+        match &states[0] {
+            ScalarValue::List(Some(values), _) => {
+                for value in values {
+                    self.update(&vec![value.clone()])?;
+                }
+                Ok(())
+            }
+            ScalarValue::List(None, _) => Ok(()),
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected state {:?} of PERCENTILE_CONT",
+                other
+            ))),
+        }
+    }
+
+    fn merge_batch(&mut self, states: &Vec<ArrayRef>) -> Result<()> {
+        let lists = states[0]
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(
+                    "Unexpected state of PERCENTILE_CONT".to_string(),
+                )
+            })?;
+        for i in 0..lists.len() {
+            if lists.is_valid(i) {
+                self.update_batch(&vec![lists.value(i)])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        if self.values.is_empty() {
+            return Ok(ScalarValue::Float64(None));
+        }
+        // the NaNs are sorted last, as greater than any other value
+        let mut values = self.values.clone();
+        values.sort_by_key(|value| OrderedFloat64(*value));
+
+        let position = self.percentile * (values.len() - 1) as f64;
+        let lower = position.floor() as usize;
+        let upper = position.ceil() as usize;
+        let value =
+            values[lower] + (values[upper] - values[lower]) * (position - lower as f64);
+        Ok(ScalarValue::from(value))
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.values.capacity() * std::mem::size_of::<f64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{cast, col, lit};
+
+    /// Aggregates `values` in two partial accumulators, whose states are merged
+    fn aggregate(agg: &dyn AggregateExpr, columns: Vec<Vec<Option<f64>>>) -> Result<f64> {
+        let columns = columns
+            .into_iter()
+            .map(|values| Arc::new(Float64Array::from(values)) as ArrayRef)
+            .collect::<Vec<_>>();
+        let num_rows = columns[0].len();
+        let middle = num_rows / 2;
+
+        let mut states = vec![];
+        for (offset, length) in vec![(0, middle), (middle, num_rows - middle)] {
+            let mut accumulator = agg.create_accumulator()?;
+            let values = columns
+                .iter()
+                .map(|column| column.slice(offset, length))
+                .collect();
+            accumulator.update_batch(&values)?;
+            states.push(accumulator.state()?);
+        }
+        // the states are merged as arrays
+        let states = (0..states[0].len())
+            .map(|i| {
+                let values = states.iter().map(|s| s[i].to_array()).collect::<Vec<_>>();
+                let values = values.iter().map(|a| a.as_ref()).collect::<Vec<_>>();
+                Ok(arrow::compute::concat(&values)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut accumulator = agg.create_accumulator()?;
+        accumulator.merge_batch(&states)?;
+        match accumulator.evaluate()? {
+            ScalarValue::Float64(Some(value)) => Ok(value),
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected result {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn values(values: &[f64]) -> Vec<Option<f64>> {
+        values.iter().map(|v| Some(*v)).collect()
+    }
+
+    fn assert_close(expected: f64, actual: f64) {
+        assert!(
+            (expected - actual).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn variance_and_stddev() -> Result<()> {
+        let data = values(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        let agg = Variance::new(col("a"), "v".to_string(), StatsType::Population);
+        assert_close(4.0, aggregate(&agg, vec![data.clone()])?);
+        let agg = Variance::new(col("a"), "v".to_string(), StatsType::Sample);
+        assert_close(32.0 / 7.0, aggregate(&agg, vec![data.clone()])?);
+        let agg = Stddev::new(col("a"), "s".to_string(), StatsType::Population);
+        assert_close(2.0, aggregate(&agg, vec![data])?);
+        Ok(())
+    }
+
+    #[test]
+    fn variance_is_stable() -> Result<()> {
+        // a naive sum of squares loses all the precision with such an offset
+        let data = values(&[1e9 + 4.0, 1e9 + 7.0, 1e9 + 13.0, 1e9 + 16.0]);
+        let agg = Variance::new(col("a"), "v".to_string(), StatsType::Sample);
+        assert_close(30.0, aggregate(&agg, vec![data])?);
+        Ok(())
+    }
+
+    #[test]
+    fn variance_with_nulls() -> Result<()> {
+        let data = vec![Some(1.0), None, Some(3.0), None];
+        let agg = Variance::new(col("a"), "v".to_string(), StatsType::Sample);
+        assert_close(2.0, aggregate(&agg, vec![data])?);
+
+        // the sample variance of a single value is null
+        let mut accumulator = agg.create_accumulator()?;
+        accumulator.update(&vec![ScalarValue::from(1.0)])?;
+        assert_eq!(ScalarValue::Float64(None), accumulator.evaluate()?);
+        Ok(())
+    }
+
+    #[test]
+    fn covariance_and_correlation() -> Result<()> {
+        let data1 = vec![Some(1.0), Some(2.0), Some(3.0), Some(4.0), None];
+        let data2 = vec![Some(2.0), Some(4.0), Some(5.0), Some(9.0), Some(1.0)];
+        let agg = Covariance::new(col("a"), col("b"), "c".to_string(), StatsType::Sample);
+        assert_close(
+            11.0 / 3.0,
+            aggregate(&agg, vec![data1.clone(), data2.clone()])?,
+        );
+        let agg =
+            Covariance::new(col("a"), col("b"), "c".to_string(), StatsType::Population);
+        assert_close(2.75, aggregate(&agg, vec![data1.clone(), data2.clone()])?);
+        let agg = Correlation::new(col("a"), col("b"), "c".to_string());
+        // the population covariance divided by the population standard deviations
+        assert_close(
+            2.75 / (1.25f64 * 6.5).sqrt(),
+            aggregate(&agg, vec![data1, data2])?,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn median_and_percentile() -> Result<()> {
+        let data = vec![Some(5.0), Some(1.0), None, Some(3.0), Some(2.0)];
+        let agg = PercentileCont::new(col("a"), "m".to_string(), 0.5);
+        assert_close(2.5, aggregate(&agg, vec![data.clone()])?);
+        let agg = PercentileCont::new(col("a"), "p".to_string(), 0.9);
+        assert_close(4.4, aggregate(&agg, vec![data.clone()])?);
+        let agg = PercentileCont::new(col("a"), "p".to_string(), 0.0);
+        assert_close(1.0, aggregate(&agg, vec![data])?);
+        Ok(())
+    }
+
+    #[test]
+    fn percentile_with_nan() -> Result<()> {
+        // the NaNs are greater than any other value, wherever they are in the input
+        for data in vec![
+            vec![Some(f64::NAN), Some(1.0), None, Some(3.0), Some(2.0)],
+            vec![Some(3.0), Some(1.0), None, Some(f64::NAN), Some(2.0)],
+            vec![Some(2.0), Some(3.0), Some(f64::NAN), None, Some(1.0)],
+        ] {
+            let agg = PercentileCont::new(col("a"), "m".to_string(), 0.5);
+            assert_close(2.5, aggregate(&agg, vec![data.clone()])?);
+            let agg = PercentileCont::new(col("a"), "p".to_string(), 0.0);
+            assert_close(1.0, aggregate(&agg, vec![data.clone()])?);
+            let agg = PercentileCont::new(col("a"), "p".to_string(), 1.0);
+            assert!(aggregate(&agg, vec![data])?.is_nan());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn percentile_must_be_constant() -> Result<()> {
+        assert_close(0.25, percentile_value(&lit(ScalarValue::from(0.25)))?);
+        assert!(percentile_value(&lit(ScalarValue::from(1.5))).is_err());
+        assert!(percentile_value(&col("a")).is_err());
+        // a cast constant is also accepted
+        let one = cast(
+            lit(ScalarValue::from(1i64)),
+            &Schema::empty(),
+            DataType::Float64,
+        )?;
+        assert_close(1.0, percentile_value(&one)?);
+        Ok(())
+    }
+}
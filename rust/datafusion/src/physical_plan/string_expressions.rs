@@ -19,7 +19,7 @@
 
 use crate::error::{DataFusionError, Result};
 use arrow::array::{
-    Array, ArrayRef, GenericStringArray, StringArray, StringBuilder,
+    Array, ArrayRef, GenericStringArray, Int64Array, StringArray, StringBuilder,
     StringOffsetSizeTrait,
 };
 
@@ -89,3 +89,105 @@ macro_rules! string_unary_function {
 string_unary_function!(lower, to_ascii_lowercase);
 string_unary_function!(upper, to_ascii_uppercase);
 string_unary_function!(trim, trim);
+
+/// SQL `SUBSTRING(<string> FROM <start> [FOR <length>])`: the characters of the
+/// strings from the (1-based) position `start`, `length` of them if any. The
+/// positions before the first character count in the length.
+pub fn substr<T: StringOffsetSizeTrait>(
+    args: &[ArrayRef],
+) -> Result<GenericStringArray<T>> {
+    let strings = args[0]
+        .as_any()
+        .downcast_ref::<GenericStringArray<T>>()
+        .ok_or_else(|| DataFusionError::Internal("failed to downcast".to_string()))?;
+    let ints = downcast_vec!(args[1..], Int64Array).collect::<Result<Vec<_>>>()?;
+    let (starts, lengths) = (ints[0], ints.get(1));
+
+    (0..strings.len())
+        .map(|i| {
+            if strings.is_null(i)
+                || starts.is_null(i)
+                || lengths.map_or(false, |lengths| lengths.is_null(i))
+            {
+                return Ok(None);
+            }
+            let chars = strings.value(i).chars();
+            let start = starts.value(i);
+            let skip = (start.max(1) - 1) as usize;
+            Ok(Some(match lengths {
+                Some(lengths) => {
+                    let length = lengths.value(i);
+                    if length < 0 {
+                        return Err(DataFusionError::Execution(format!(
+                            "negative substring length {} is not allowed",
+                            length
+                        )));
+                    }
+                    let end = start.saturating_add(length);
+                    let take = (end - start.max(1)).max(0) as usize;
+                    chars.skip(skip).take(take).collect::<String>()
+                }
+                None => chars.skip(skip).collect::<String>(),
+            }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn substr_positions() -> Result<()> {
+        let strings: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("alphabet"),
+            Some("alphabet"),
+            Some("alphabet"),
+            Some("éàü"),
+            None,
+            Some("alphabet"),
+        ]));
+        let starts: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(3),
+            Some(0),
+            Some(-1),
+            Some(2),
+            Some(1),
+            None,
+        ]));
+        let lengths: ArrayRef = Arc::new(Int64Array::from(vec![2, 2, 10, 5, 1, 1]));
+
+        let result = substr::<i32>(&[strings.clone(), starts.clone(), lengths])?;
+        // the positions before the first character count in the length
+        let expected = StringArray::from(vec![
+            Some("ph"),
+            Some("a"),
+            Some("alphabet"),
+            Some("àü"),
+            None,
+            None,
+        ]);
+        assert_eq!(result, expected);
+
+        let result = substr::<i32>(&[strings, starts])?;
+        let expected = StringArray::from(vec![
+            Some("phabet"),
+            Some("alphabet"),
+            Some("alphabet"),
+            Some("àü"),
+            None,
+            None,
+        ]);
+        assert_eq!(result, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn substr_negative_length() {
+        let strings: ArrayRef = Arc::new(StringArray::from(vec!["alphabet"]));
+        let starts: ArrayRef = Arc::new(Int64Array::from(vec![1]));
+        let lengths: ArrayRef = Arc::new(Int64Array::from(vec![-1]));
+        assert!(substr::<i32>(&[strings, starts, lengths]).is_err());
+    }
+}
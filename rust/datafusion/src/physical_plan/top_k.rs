@@ -0,0 +1,467 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the Top K plan, which produces the first `k` rows of each of its input
+//! partitions in sorted order, without sorting the whole partitions.
+//!
+//! The first rows seen so far are kept in a bounded max-heap of `k` rows, whose root is
+//! the last of them: a new row only enters the heap if it sorts before the root, which it
+//! then replaces. Only `k` rows, plus the batch being read, are kept in memory.
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc;
+use futures::stream::Stream;
+use futures::{SinkExt, StreamExt};
+
+use pin_project_lite::pin_project;
+
+use arrow::compute::SortOptions;
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use super::{RecordBatchStream, SendableRecordBatchStream};
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::physical_plan::sort::{interleave_batches, sort_key_columns, RowComparator};
+use crate::physical_plan::{Distribution, ExecutionPlan, Partitioning};
+
+use async_trait::async_trait;
+
+/// Top K execution plan: sorts each of its input partitions by `expr` and produces only
+/// their first `k` rows, as a single batch per partition. Use a
+/// `SortPreservingMergeExec` to merge the partitions, and a limit to keep the first `k`
+/// rows overall.
+#[derive(Debug)]
+pub struct TopKExec {
+    /// Input execution plan
+    input: Arc<dyn ExecutionPlan>,
+    /// Sort expressions
+    expr: Vec<PhysicalSortExpr>,
+    /// Number of rows to produce per partition
+    k: usize,
+}
+
+impl TopKExec {
+    /// Create a new TopKExec
+    pub fn new(
+        expr: Vec<PhysicalSortExpr>,
+        input: Arc<dyn ExecutionPlan>,
+        k: usize,
+    ) -> Self {
+        Self { input, expr, k }
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// Sort expressions
+    pub fn expr(&self) -> &[PhysicalSortExpr] {
+        &self.expr
+    }
+
+    /// Number of rows produced per partition
+    pub fn k(&self) -> usize {
+        self.k
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for TopKExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::UnspecifiedDistribution
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(TopKExec::new(
+                self.expr.clone(),
+                children[0].clone(),
+                self.k,
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "TopKExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        let input = self.input.execute(partition).await?;
+
+        Ok(Box::pin(TopKStream::new(input, self.expr.clone(), self.k)))
+    }
+}
+
+/// Keeps the first `k` rows of `input` in the order of `expr`, and sends them to `output`
+async fn top_k_input(
+    mut input: SendableRecordBatchStream,
+    expr: Vec<PhysicalSortExpr>,
+    k: usize,
+    output: &mut mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<()> {
+    if k == 0 {
+        return Ok(());
+    }
+
+    let schema = input.schema();
+    let options = expr.iter().map(|e| e.options).collect::<Vec<_>>();
+    let mut top = None;
+    while let Some(batch) = input.next().await {
+        let batch = batch?;
+        if batch.num_rows() > 0 {
+            top = Some(top_k_rows(&schema, &expr, &options, top, batch, k)?);
+        }
+    }
+
+    if let Some(top) = top {
+        // If send fails, plan being torn down, there is no place to send the batch
+        output.send(Ok(top)).await.ok();
+    }
+    Ok(())
+}
+
+/// Returns the first `k` rows, in sorted order, of the rows of `top`, the sorted first
+/// rows of the previous batches, and of the rows of `batch`
+fn top_k_rows(
+    schema: &SchemaRef,
+    expr: &[PhysicalSortExpr],
+    options: &[SortOptions],
+    top: Option<RecordBatch>,
+    batch: RecordBatch,
+    k: usize,
+) -> Result<RecordBatch> {
+    let batches = top.into_iter().chain(Some(batch)).collect::<Vec<_>>();
+    let sort_columns = batches
+        .iter()
+        .map(|batch| sort_key_columns(expr, batch))
+        .collect::<Result<Vec<_>>>()?;
+    let comparators = sort_columns
+        .iter()
+        .map(|left| {
+            sort_columns
+                .iter()
+                .map(|right| RowComparator::try_new(left, right, options))
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let compare = |(left_batch, left_row): (usize, usize),
+                   (right_batch, right_row): (usize, usize)| {
+        comparators[left_batch][right_batch].compare(left_row, right_row)
+    };
+
+    // the rows of `top` in reverse order are a valid max-heap
+    let new_batch = batches.len() - 1;
+    let mut heap = (0..new_batch)
+        .flat_map(|batch| {
+            (0..batches[batch].num_rows())
+                .rev()
+                .map(move |row| (batch, row))
+        })
+        .collect::<Vec<_>>();
+    for row in 0..batches[new_batch].num_rows() {
+        if heap.len() < k {
+            heap.push((new_batch, row));
+            sift_up(&mut heap, &compare);
+        } else if compare((new_batch, row), heap[0]) == Ordering::Less {
+            heap[0] = (new_batch, row);
+            sift_down(&mut heap, &compare);
+        }
+    }
+
+    heap.sort_by(|left, right| compare(*left, *right));
+    interleave_batches(schema, &batches, &heap)
+}
+
+/// Moves the last row of the max-heap `heap` up to its position
+fn sift_up<F>(heap: &mut [(usize, usize)], compare: &F)
+where
+    F: Fn((usize, usize), (usize, usize)) -> Ordering,
+{
+    let mut i = heap.len() - 1;
+    while i > 0 {
+        let parent = (i - 1) / 2;
+        if compare(heap[i], heap[parent]) != Ordering::Greater {
+            break;
+        }
+        heap.swap(i, parent);
+        i = parent;
+    }
+}
+
+/// Moves the root of the max-heap `heap` down to its position
+fn sift_down<F>(heap: &mut [(usize, usize)], compare: &F)
+where
+    F: Fn((usize, usize), (usize, usize)) -> Ordering,
+{
+    let mut i = 0;
+    loop {
+        let mut largest = i;
+        for child in &[2 * i + 1, 2 * i + 2] {
+            if *child < heap.len()
+                && compare(heap[*child], heap[largest]) == Ordering::Greater
+            {
+                largest = *child;
+            }
+        }
+        if largest == i {
+            break;
+        }
+        heap.swap(i, largest);
+        i = largest;
+    }
+}
+
+pin_project! {
+    struct TopKStream {
+        #[pin]
+        output: mpsc::Receiver<ArrowResult<RecordBatch>>,
+        schema: SchemaRef,
+    }
+}
+
+impl TopKStream {
+    fn new(
+        input: SendableRecordBatchStream,
+        expr: Vec<PhysicalSortExpr>,
+        k: usize,
+    ) -> Self {
+        let (mut sender, receiver) = mpsc::channel(1);
+
+        let schema = input.schema();
+        tokio::spawn(async move {
+            if let Err(e) = top_k_input(input, expr, k, &mut sender).await {
+                // If send fails, plan being torn down, no place to send the error
+                sender.send(Err(e.into_arrow_external_error())).await.ok();
+            }
+        });
+
+        Self {
+            output: receiver,
+            schema,
+        }
+    }
+}
+
+impl Stream for TopKStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().output.poll_next(cx)
+    }
+}
+
+impl RecordBatchStream for TopKStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::csv::{CsvExec, CsvReadOptions};
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::merge::MergeExec;
+    use crate::physical_plan::sort::SortExec;
+    use crate::test;
+    use arrow::array::*;
+    use arrow::datatypes::*;
+    use arrow::util::display::array_value_to_string;
+
+    #[tokio::test]
+    async fn top_k_partitions() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+        let batch = |a: Vec<Option<i32>>, b: Vec<&str>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int32Array::from(a)),
+                    Arc::new(StringArray::from(b)),
+                ],
+            )
+        };
+        let partitions = vec![
+            vec![
+                batch(vec![Some(3), None, Some(8)], vec!["a", "b", "c"])?,
+                batch(vec![], vec![])?,
+                batch(vec![Some(5), Some(9), Some(1)], vec!["d", "e", "f"])?,
+                batch(vec![Some(7)], vec!["g"])?,
+            ],
+            vec![],
+            vec![batch(vec![Some(2), Some(4)], vec!["h", "i"])?],
+        ];
+        let input = Arc::new(MemoryExec::try_new(&partitions, schema.clone(), None)?);
+        let top_k = Arc::new(TopKExec::new(
+            vec![PhysicalSortExpr {
+                expr: col("a"),
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: true,
+                },
+            }],
+            input,
+            3,
+        ));
+        assert_eq!(top_k.output_partitioning().partition_count(), 3);
+
+        let mut result = vec![];
+        for partition in 0..3 {
+            let stream = top_k.execute(partition).await?;
+            let batches = crate::physical_plan::common::collect(stream).await?;
+            let rows = batches
+                .iter()
+                .map(|batch| {
+                    let a = as_primitive_array::<Int32Type>(batch.column(0));
+                    let b = as_string_array(batch.column(1));
+                    a.iter()
+                        .enumerate()
+                        .map(|(i, a)| (a, b.value(i).to_owned()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            result.push(rows);
+        }
+        let expected = vec![
+            vec![vec![
+                (None, "b".to_owned()),
+                (Some(9), "e".to_owned()),
+                (Some(8), "c".to_owned()),
+            ]],
+            vec![],
+            vec![vec![(Some(4), "i".to_owned()), (Some(2), "h".to_owned())]],
+        ];
+        assert_eq!(expected, result);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn top_k_zero() -> Result<()> {
+        let schema = test::aggr_test_schema();
+        let path = test::create_partitioned_csv("aggregate_test_100.csv", 1)?;
+        let csv = CsvExec::try_new(
+            &path,
+            CsvReadOptions::new().schema(&schema),
+            None,
+            1024,
+            None,
+        )?;
+        let top_k = TopKExec::new(
+            vec![PhysicalSortExpr {
+                expr: col("c2"),
+                options: SortOptions::default(),
+            }],
+            Arc::new(csv),
+            0,
+        );
+
+        let result = collect(Arc::new(top_k)).await?;
+        assert!(result.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn top_k_csv() -> Result<()> {
+        let schema = test::aggr_test_schema();
+        let path = test::create_partitioned_csv("aggregate_test_100.csv", 1)?;
+        let csv = Arc::new(CsvExec::try_new(
+            &path,
+            CsvReadOptions::new().schema(&schema),
+            None,
+            // small batches to update the top rows several times
+            7,
+            None,
+        )?);
+        let sort_expr = vec![
+            PhysicalSortExpr {
+                expr: col("c1"),
+                options: SortOptions::default(),
+            },
+            PhysicalSortExpr {
+                expr: col("c7"),
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: true,
+                },
+            },
+        ];
+
+        let sort = SortExec::try_new(
+            sort_expr.clone(),
+            Arc::new(MergeExec::new(csv.clone())),
+            1,
+        )?;
+        let mut expected = sort_keys(&collect(Arc::new(sort)).await?)?;
+        expected.truncate(15);
+
+        let top_k = TopKExec::new(sort_expr, csv, 15);
+        let result = collect(Arc::new(top_k)).await?;
+        assert_eq!(result.len(), 1);
+        assert_eq!(sort_keys(&result)?, expected);
+
+        Ok(())
+    }
+
+    /// the values of the columns c1 and c7 of `batches`
+    fn sort_keys(batches: &[RecordBatch]) -> Result<Vec<String>> {
+        let mut keys = vec![];
+        for batch in batches {
+            for row in 0..batch.num_rows() {
+                keys.push(format!(
+                    "{},{}",
+                    array_value_to_string(batch.column(0), row)?,
+                    array_value_to_string(batch.column(6), row)?,
+                ));
+            }
+        }
+        Ok(keys)
+    }
+}
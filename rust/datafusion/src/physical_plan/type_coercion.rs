@@ -95,6 +95,23 @@ pub fn data_types(
             }
             vec![(0..*number).map(|i| current_types[i].clone()).collect()]
         }
+        Signature::VariadicAny => {
+            if current_types.is_empty() {
+                return Err(DataFusionError::Plan(
+                    "The function expected at least one argument".to_string(),
+                ));
+            }
+            vec![current_types.clone()]
+        }
+        Signature::OneOf(signatures) => {
+            if let Some(types) = signatures
+                .iter()
+                .find_map(|signature| data_types(current_types, signature).ok())
+            {
+                return Ok(types);
+            }
+            vec![]
+        }
     };
 
     if valid_types.contains(current_types) {
@@ -296,6 +313,21 @@ mod tests {
                 Signature::Any(1),
                 vec![DataType::Float32],
             )?,
+            // u32 and bool -> u32 and bool
+            case(
+                vec![DataType::UInt32, DataType::Boolean],
+                Signature::VariadicAny,
+                vec![DataType::UInt32, DataType::Boolean],
+            )?,
+            // the signature with two arguments: u32 -> f64
+            case(
+                vec![DataType::UInt32, DataType::UInt32],
+                Signature::OneOf(vec![
+                    Signature::Exact(vec![DataType::Float64]),
+                    Signature::Exact(vec![DataType::Float64, DataType::Float64]),
+                ]),
+                vec![DataType::Float64, DataType::Float64],
+            )?,
         ];
 
         for case in cases {
@@ -326,6 +358,17 @@ mod tests {
             )?,
             // expected two arguments
             case(vec![DataType::UInt32], Signature::Any(2), vec![])?,
+            // expected at least one argument
+            case(vec![], Signature::VariadicAny, vec![])?,
+            // no signature with three arguments
+            case(
+                vec![DataType::UInt32, DataType::UInt32, DataType::UInt32],
+                Signature::OneOf(vec![
+                    Signature::Exact(vec![DataType::Float64]),
+                    Signature::Exact(vec![DataType::Float64, DataType::Float64]),
+                ]),
+                vec![],
+            )?,
         ];
 
         for case in cases {
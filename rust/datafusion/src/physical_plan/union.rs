@@ -0,0 +1,255 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the union plan, which concatenates the partitions of its inputs (UNION ALL)
+
+use std::any::Any;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use futures::stream::{Stream, StreamExt};
+
+use super::{ExecutionPlan, Partitioning, RecordBatchStream, SendableRecordBatchStream};
+use crate::error::{DataFusionError, Result};
+use async_trait::async_trait;
+
+/// Union execution plan. The partitions of all its inputs become the partitions of
+/// its output, in order: partition `i` of the second input follows the last
+/// partition of the first input, and so on. All inputs must have the same column
+/// types, while a column of the output is nullable if it is nullable in any input.
+#[derive(Debug)]
+pub struct UnionExec {
+    /// Input execution plans
+    inputs: Vec<Arc<dyn ExecutionPlan>>,
+    /// The schema of the output
+    schema: SchemaRef,
+}
+
+impl UnionExec {
+    /// Create a new UnionExec
+    pub fn new(inputs: Vec<Arc<dyn ExecutionPlan>>) -> Self {
+        let schema = union_schema(&inputs);
+        UnionExec { inputs, schema }
+    }
+}
+
+/// The schema of the first input, with the columns that are nullable in any input
+fn union_schema(inputs: &[Arc<dyn ExecutionPlan>]) -> SchemaRef {
+    let schema = inputs[0].schema();
+    let fields = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let nullable = inputs
+                .iter()
+                .any(|input| input.schema().field(i).is_nullable());
+            if nullable == field.is_nullable() {
+                field.clone()
+            } else {
+                Field::new(field.name(), field.data_type().clone(), nullable)
+            }
+        })
+        .collect();
+    Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+#[async_trait]
+impl ExecutionPlan for UnionExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        self.inputs.clone()
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        let num_partitions = self
+            .inputs
+            .iter()
+            .map(|plan| plan.output_partitioning().partition_count())
+            .sum();
+        Partitioning::UnknownPartitioning(num_partitions)
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(UnionExec::new(children)))
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        // find the input owning the requested partition
+        let mut input_partition = partition;
+        for input in self.inputs.iter() {
+            let input_partitions = input.output_partitioning().partition_count();
+            if input_partition < input_partitions {
+                let stream = input.execute(input_partition).await?;
+                // the batches of the inputs with other nullabilities take the schema
+                // of the union
+                return if input.schema() == self.schema {
+                    Ok(stream)
+                } else {
+                    Ok(Box::pin(UnionStream {
+                        schema: self.schema.clone(),
+                        input: stream,
+                    }))
+                };
+            }
+            input_partition -= input_partitions;
+        }
+
+        Err(DataFusionError::Internal(format!(
+            "UnionExec invalid partition {}",
+            partition
+        )))
+    }
+}
+
+/// A stream of the batches of an input of the union, with the schema of the union
+struct UnionStream {
+    schema: SchemaRef,
+    input: SendableRecordBatchStream,
+}
+
+impl Stream for UnionStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.input.poll_next_unpin(cx).map(|x| match x {
+            Some(Ok(batch)) => Some(RecordBatch::try_new(
+                self.schema.clone(),
+                batch.columns().to_vec(),
+            )),
+            other => other,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // same number of record batches
+        self.input.size_hint()
+    }
+}
+
+impl RecordBatchStream for UnionStream {
+    /// Get the schema
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::{
+        collect,
+        csv::{CsvExec, CsvReadOptions},
+        memory::MemoryExec,
+    };
+    use crate::test;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::DataType;
+
+    #[tokio::test]
+    async fn union_partitions() -> Result<()> {
+        let schema = test::aggr_test_schema();
+
+        // Create csv's with different partitioning
+        let path = test::create_partitioned_csv("aggregate_test_100.csv", 4)?;
+        let path2 = test::create_partitioned_csv("aggregate_test_100.csv", 5)?;
+
+        let csv = CsvExec::try_new(
+            &path,
+            CsvReadOptions::new().schema(&schema),
+            None,
+            1024,
+            None,
+        )?;
+        let csv2 = CsvExec::try_new(
+            &path2,
+            CsvReadOptions::new().schema(&schema),
+            None,
+            1024,
+            None,
+        )?;
+
+        let union_exec = Arc::new(UnionExec::new(vec![Arc::new(csv), Arc::new(csv2)]));
+
+        // Should have 9 partitions and 9 output batches
+        assert_eq!(union_exec.output_partitioning().partition_count(), 9);
+
+        let result = collect(union_exec).await?;
+        assert_eq!(result.len(), 9);
+
+        // there should be a total of 200 rows
+        let row_count: usize = result.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(row_count, 200);
+
+        Ok(())
+    }
+
+    /// Scans a batch of the column `a` of the given values
+    fn scan_column_a(values: Vec<Option<i32>>, nullable: bool) -> Result<MemoryExec> {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "a",
+            DataType::Int32,
+            nullable,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(values))],
+        )?;
+        MemoryExec::try_new(&vec![vec![batch]], schema, None)
+    }
+
+    #[tokio::test]
+    async fn union_nullability() -> Result<()> {
+        let not_null = Arc::new(scan_column_a(vec![Some(1)], false)?);
+        let nullable = Arc::new(scan_column_a(vec![None], true)?);
+
+        // a column is nullable if it is nullable in any of the inputs
+        let union_exec = Arc::new(UnionExec::new(vec![not_null.clone(), nullable]));
+        assert!(union_exec.schema().field(0).is_nullable());
+
+        // the batches of all the inputs have the schema of the union
+        let result = collect(union_exec.clone()).await?;
+        assert_eq!(result.len(), 2);
+        for batch in result {
+            assert_eq!(batch.schema(), union_exec.schema());
+        }
+
+        let union_exec = UnionExec::new(vec![not_null.clone(), not_null]);
+        assert!(!union_exec.schema().field(0).is_nullable());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Declaration of window functions.
+//! A window function is either one of the built-in aggregate functions evaluated over
+//! a window frame, or one of the built-in window functions such as `ROW_NUMBER` or `LAG`.
+//!
+//! As with aggregates, a window function has:
+//! * a return type, that is a function of the incoming argument's types
+//! * the computation, that must accept each valid set of arguments
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::aggregates;
+use crate::physical_plan::aggregates::AggregateFunction;
+use arrow::datatypes::DataType;
+use std::{fmt, str::FromStr};
+
+/// Enum of all window functions: either an aggregate function evaluated over a window
+/// frame, or a built-in window function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowFunction {
+    /// an aggregate function evaluated over a window frame
+    AggregateFunction(AggregateFunction),
+    /// a built-in window function
+    BuiltInWindowFunction(BuiltInWindowFunction),
+}
+
+impl FromStr for WindowFunction {
+    type Err = DataFusionError;
+    fn from_str(name: &str) -> Result<WindowFunction> {
+        if let Ok(fun) = AggregateFunction::from_str(name) {
+            Ok(WindowFunction::AggregateFunction(fun))
+        } else if let Ok(fun) = BuiltInWindowFunction::from_str(name) {
+            Ok(WindowFunction::BuiltInWindowFunction(fun))
+        } else {
+            Err(DataFusionError::Plan(format!(
+                "There is no window function named {}",
+                name
+            )))
+        }
+    }
+}
+
+impl fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowFunction::AggregateFunction(fun) => fun.fmt(f),
+            WindowFunction::BuiltInWindowFunction(fun) => fun.fmt(f),
+        }
+    }
+}
+
+/// Enum of all built-in window functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltInWindowFunction {
+    /// number of the current row within its partition, counting from 1
+    RowNumber,
+    /// rank of the current row with gaps
+    Rank,
+    /// rank of the current row without gaps
+    DenseRank,
+    /// relative rank of the current row: (rank - 1) / (total rows - 1)
+    PercentRank,
+    /// relative rank of the current row: (number of rows preceding or peer with current row) / (total rows)
+    CumeDist,
+    /// integer ranging from 1 to the argument value, dividing the partition as equally as possible
+    Ntile,
+    /// value evaluated at the row that is offset rows before the current row within the partition
+    Lag,
+    /// value evaluated at the row that is offset rows after the current row within the partition
+    Lead,
+    /// value evaluated at the row that is the first row of the window frame
+    FirstValue,
+    /// value evaluated at the row that is the last row of the window frame
+    LastValue,
+    /// value evaluated at the row that is the nth row of the window frame (counting from 1)
+    NthValue,
+}
+
+impl fmt::Display for BuiltInWindowFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            BuiltInWindowFunction::RowNumber => "ROW_NUMBER",
+            BuiltInWindowFunction::Rank => "RANK",
+            BuiltInWindowFunction::DenseRank => "DENSE_RANK",
+            BuiltInWindowFunction::PercentRank => "PERCENT_RANK",
+            BuiltInWindowFunction::CumeDist => "CUME_DIST",
+            BuiltInWindowFunction::Ntile => "NTILE",
+            BuiltInWindowFunction::Lag => "LAG",
+            BuiltInWindowFunction::Lead => "LEAD",
+            BuiltInWindowFunction::FirstValue => "FIRST_VALUE",
+            BuiltInWindowFunction::LastValue => "LAST_VALUE",
+            BuiltInWindowFunction::NthValue => "NTH_VALUE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for BuiltInWindowFunction {
+    type Err = DataFusionError;
+    fn from_str(name: &str) -> Result<BuiltInWindowFunction> {
+        Ok(match &*name.to_uppercase() {
+            "ROW_NUMBER" => BuiltInWindowFunction::RowNumber,
+            "RANK" => BuiltInWindowFunction::Rank,
+            "DENSE_RANK" => BuiltInWindowFunction::DenseRank,
+            "PERCENT_RANK" => BuiltInWindowFunction::PercentRank,
+            "CUME_DIST" => BuiltInWindowFunction::CumeDist,
+            "NTILE" => BuiltInWindowFunction::Ntile,
+            "LAG" => BuiltInWindowFunction::Lag,
+            "LEAD" => BuiltInWindowFunction::Lead,
+            "FIRST_VALUE" => BuiltInWindowFunction::FirstValue,
+            "LAST_VALUE" => BuiltInWindowFunction::LastValue,
+            "NTH_VALUE" => BuiltInWindowFunction::NthValue,
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "There is no built-in window function named {}",
+                    name
+                )))
+            }
+        })
+    }
+}
+
+/// Returns the datatype of the window function
+pub fn return_type(fun: &WindowFunction, arg_types: &[DataType]) -> Result<DataType> {
+    match fun {
+        WindowFunction::AggregateFunction(fun) => {
+            aggregates::return_type(fun, &arg_types.to_vec())
+        }
+        WindowFunction::BuiltInWindowFunction(fun) => {
+            built_in_return_type(fun, arg_types)
+        }
+    }
+}
+
+fn built_in_return_type(
+    fun: &BuiltInWindowFunction,
+    arg_types: &[DataType],
+) -> Result<DataType> {
+    // verify that this is a valid number of arguments for this function
+    let (min_args, max_args) = arity(fun);
+    if arg_types.len() < min_args || arg_types.len() > max_args {
+        return Err(DataFusionError::Plan(format!(
+            "The function {} expects between {} and {} arguments, got {}",
+            fun,
+            min_args,
+            max_args,
+            arg_types.len()
+        )));
+    }
+
+    match fun {
+        BuiltInWindowFunction::RowNumber
+        | BuiltInWindowFunction::Rank
+        | BuiltInWindowFunction::DenseRank
+        | BuiltInWindowFunction::Ntile => Ok(DataType::UInt64),
+        BuiltInWindowFunction::PercentRank | BuiltInWindowFunction::CumeDist => {
+            Ok(DataType::Float64)
+        }
+        BuiltInWindowFunction::Lag
+        | BuiltInWindowFunction::Lead
+        | BuiltInWindowFunction::FirstValue
+        | BuiltInWindowFunction::LastValue
+        | BuiltInWindowFunction::NthValue => Ok(arg_types[0].clone()),
+    }
+}
+
+/// the minimum and maximum number of arguments accepted by `fun`.
+fn arity(fun: &BuiltInWindowFunction) -> (usize, usize) {
+    match fun {
+        BuiltInWindowFunction::RowNumber
+        | BuiltInWindowFunction::Rank
+        | BuiltInWindowFunction::DenseRank
+        | BuiltInWindowFunction::PercentRank
+        | BuiltInWindowFunction::CumeDist => (0, 0),
+        BuiltInWindowFunction::Ntile
+        | BuiltInWindowFunction::FirstValue
+        | BuiltInWindowFunction::LastValue => (1, 1),
+        BuiltInWindowFunction::NthValue => (2, 2),
+        BuiltInWindowFunction::Lag | BuiltInWindowFunction::Lead => (1, 3),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_function_from_str() -> Result<()> {
+        assert_eq!(
+            WindowFunction::from_str("max")?,
+            WindowFunction::AggregateFunction(AggregateFunction::Max)
+        );
+        assert_eq!(
+            WindowFunction::from_str("row_number")?,
+            WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::RowNumber)
+        );
+        assert!(WindowFunction::from_str("not_a_function").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_function_display() {
+        assert_eq!(
+            "DENSE_RANK",
+            WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::DenseRank)
+                .to_string()
+        );
+        assert_eq!(
+            "SUM",
+            WindowFunction::AggregateFunction(AggregateFunction::Sum).to_string()
+        );
+    }
+
+    #[test]
+    fn test_ranking_return_type() -> Result<()> {
+        let fun = WindowFunction::from_str("rank")?;
+        assert_eq!(DataType::UInt64, return_type(&fun, &[])?);
+
+        let fun = WindowFunction::from_str("cume_dist")?;
+        assert_eq!(DataType::Float64, return_type(&fun, &[])?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lag_return_type() -> Result<()> {
+        let fun = WindowFunction::from_str("lag")?;
+        let observed = return_type(&fun, &[DataType::Utf8, DataType::Int64])?;
+        assert_eq!(DataType::Utf8, observed);
+
+        assert!(return_type(&fun, &[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregate_return_type() -> Result<()> {
+        let fun = WindowFunction::from_str("sum")?;
+        let observed = return_type(&fun, &[DataType::UInt32])?;
+        assert_eq!(DataType::UInt64, observed);
+        Ok(())
+    }
+}
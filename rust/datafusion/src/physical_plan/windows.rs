@@ -0,0 +1,1207 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the execution plan for window functions
+//!
+//! Each window expression sorts the input by its PARTITION BY and ORDER BY clauses,
+//! evaluates its window function partition by partition and returns its values in the
+//! original order of the input rows.
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::stream::Stream;
+use futures::Future;
+
+use pin_project_lite::pin_project;
+
+use arrow::array::{build_compare, Array, ArrayRef, Float64Array, UInt32Array};
+use arrow::compute::{cast, concat, lexsort_to_indices, take, SortColumn};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::record_batch::RecordBatch;
+
+use super::{RecordBatchStream, SendableRecordBatchStream};
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::{WindowFrame, WindowFrameBound, WindowFrameUnits};
+use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::physical_plan::window_functions::{
+    return_type, BuiltInWindowFunction, WindowFunction,
+};
+use crate::physical_plan::{
+    aggregates, common, AggregateExpr, ColumnarValue, Distribution, ExecutionPlan,
+    Partitioning, PhysicalExpr, WindowExpr,
+};
+use crate::scalar::ScalarValue;
+
+use async_trait::async_trait;
+
+/// Create a physical expression for a window function
+pub fn create_window_expr(
+    fun: &WindowFunction,
+    name: String,
+    args: &[Arc<dyn PhysicalExpr>],
+    partition_by: &[Arc<dyn PhysicalExpr>],
+    order_by: &[PhysicalSortExpr],
+    window_frame: Option<WindowFrame>,
+    input_schema: &Schema,
+) -> Result<Arc<dyn WindowExpr>> {
+    validate_window_frame(&window_frame, order_by, input_schema)?;
+
+    Ok(match fun {
+        WindowFunction::AggregateFunction(fun) => Arc::new(AggregateWindowExpr {
+            aggregate: aggregates::create_aggregate_expr(
+                fun,
+                false,
+                &args.to_vec(),
+                input_schema,
+                name.clone(),
+            )?,
+            name,
+            partition_by: partition_by.to_vec(),
+            order_by: order_by.to_vec(),
+            window_frame,
+        }),
+        WindowFunction::BuiltInWindowFunction(built_in) => {
+            let arg_types = args
+                .iter()
+                .map(|e| e.data_type(input_schema))
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(BuiltInWindowExpr {
+                fun: *built_in,
+                data_type: return_type(fun, &arg_types)?,
+                name,
+                args: args.to_vec(),
+                partition_by: partition_by.to_vec(),
+                order_by: order_by.to_vec(),
+                window_frame,
+            })
+        }
+    })
+}
+
+/// RANGE frames with `<N> PRECEDING` or `<N> FOLLOWING` bounds compare the values of
+/// the ORDER BY expression, which must therefore be a single numeric expression
+fn validate_window_frame(
+    window_frame: &Option<WindowFrame>,
+    order_by: &[PhysicalSortExpr],
+    input_schema: &Schema,
+) -> Result<()> {
+    let window_frame = match window_frame {
+        Some(window_frame) if has_range_offset(window_frame) => window_frame,
+        _ => return Ok(()),
+    };
+
+    if order_by.len() != 1 {
+        return Err(DataFusionError::Plan(format!(
+            "Window frame {} requires exactly one ORDER BY expression",
+            window_frame
+        )));
+    }
+    match order_by[0].expr.data_type(input_schema)? {
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64
+        | DataType::Float32
+        | DataType::Float64 => Ok(()),
+        other => Err(DataFusionError::Plan(format!(
+            "Window frame {} requires a numeric ORDER BY expression, got {:?}",
+            window_frame, other
+        ))),
+    }
+}
+
+/// whether `window_frame` is a RANGE frame with a `<N> PRECEDING` or `<N> FOLLOWING` bound
+fn has_range_offset(window_frame: &WindowFrame) -> bool {
+    let is_offset = |bound: &WindowFrameBound| {
+        matches!(
+            bound,
+            WindowFrameBound::Preceding(Some(_)) | WindowFrameBound::Following(Some(_))
+        )
+    };
+    window_frame.units == WindowFrameUnits::Range
+        && (is_offset(&window_frame.start_bound) || is_offset(&window_frame.end_bound))
+}
+
+/// A window expr that evaluates an aggregate function over a window frame
+#[derive(Debug)]
+pub struct AggregateWindowExpr {
+    aggregate: Arc<dyn AggregateExpr>,
+    name: String,
+    partition_by: Vec<Arc<dyn PhysicalExpr>>,
+    order_by: Vec<PhysicalSortExpr>,
+    window_frame: Option<WindowFrame>,
+}
+
+impl WindowExpr for AggregateWindowExpr {
+    fn field(&self) -> Result<Field> {
+        let field = self.aggregate.field()?;
+        Ok(Field::new(&self.name, field.data_type().clone(), true))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        self.aggregate.expressions()
+    }
+
+    fn partition_by(&self) -> &[Arc<dyn PhysicalExpr>] {
+        &self.partition_by
+    }
+
+    fn order_by(&self) -> &[PhysicalSortExpr] {
+        &self.order_by
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let sorted = SortedBatch::try_new(batch, &self.partition_by, &self.order_by)?;
+        let args = sorted.evaluate_args(batch, &self.expressions())?;
+        let window_frame = self.window_frame.unwrap_or_default();
+        let range_values = sorted.range_values(&window_frame)?;
+
+        let mut values = Vec::with_capacity(batch.num_rows());
+        for partition in &sorted.partitions {
+            if window_frame.start_bound == WindowFrameBound::Preceding(None) {
+                // all the frames start at the beginning of the partition and their ends
+                // never decrease: accumulate the rows incrementally.
+                let mut accumulator = self.aggregate.create_accumulator()?;
+                let mut accumulated_end = partition.start;
+                for idx in partition.clone() {
+                    let frame = sorted.frame(
+                        &window_frame,
+                        idx,
+                        partition,
+                        range_values.as_deref(),
+                    );
+                    if frame.end > accumulated_end {
+                        accumulator.update_batch(&slice_args(
+                            &args,
+                            accumulated_end..frame.end,
+                        ))?;
+                        accumulated_end = frame.end;
+                    }
+                    values.push(accumulator.evaluate()?);
+                }
+            } else {
+                let mut last: Option<(Range<usize>, ScalarValue)> = None;
+                for idx in partition.clone() {
+                    let frame = sorted.frame(
+                        &window_frame,
+                        idx,
+                        partition,
+                        range_values.as_deref(),
+                    );
+                    let value = match last {
+                        // peers share the same frame in RANGE mode
+                        Some((ref last_frame, ref value)) if *last_frame == frame => {
+                            value.clone()
+                        }
+                        _ => {
+                            let mut accumulator = self.aggregate.create_accumulator()?;
+                            accumulator
+                                .update_batch(&slice_args(&args, frame.clone()))?;
+                            accumulator.evaluate()?
+                        }
+                    };
+                    values.push(value.clone());
+                    last = Some((frame, value));
+                }
+            }
+        }
+
+        sorted.to_original_order(&values)
+    }
+}
+
+/// A window expr that evaluates one of the built-in window functions
+#[derive(Debug)]
+pub struct BuiltInWindowExpr {
+    fun: BuiltInWindowFunction,
+    data_type: DataType,
+    name: String,
+    args: Vec<Arc<dyn PhysicalExpr>>,
+    partition_by: Vec<Arc<dyn PhysicalExpr>>,
+    order_by: Vec<PhysicalSortExpr>,
+    window_frame: Option<WindowFrame>,
+}
+
+impl BuiltInWindowExpr {
+    /// evaluates the argument at position `index`, which must be a constant
+    fn const_arg(&self, batch: &RecordBatch, index: usize) -> Result<ScalarValue> {
+        match self.args[index].evaluate(batch)? {
+            ColumnarValue::Scalar(value) => Ok(value),
+            ColumnarValue::Array(_) => Err(DataFusionError::Plan(format!(
+                "The argument {} of the window function {} must be a constant",
+                index + 1,
+                self.fun
+            ))),
+        }
+    }
+
+    /// evaluates the argument at position `index` as a constant integer
+    fn const_int_arg(&self, batch: &RecordBatch, index: usize) -> Result<Option<i64>> {
+        match cast_scalar(&self.const_arg(batch, index)?, &DataType::Int64)? {
+            ScalarValue::Int64(value) => Ok(value),
+            other => Err(DataFusionError::Internal(format!(
+                "Unexpected value {:?} when casting to Int64",
+                other
+            ))),
+        }
+    }
+
+    /// the offset and default value of `LAG` and `LEAD`
+    fn offset_args(&self, batch: &RecordBatch) -> Result<(i64, ScalarValue)> {
+        let offset = if self.args.len() > 1 {
+            self.const_int_arg(batch, 1)?.ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "The offset of the window function {} cannot be NULL",
+                    self.fun
+                ))
+            })?
+        } else {
+            1
+        };
+        let default_value = if self.args.len() > 2 {
+            cast_scalar(&self.const_arg(batch, 2)?, &self.data_type)?
+        } else {
+            ScalarValue::try_from(&self.data_type)?
+        };
+        Ok((offset, default_value))
+    }
+
+    /// the positive integer argument of `NTILE` and `NTH_VALUE`
+    fn positive_int_arg(&self, batch: &RecordBatch, index: usize) -> Result<usize> {
+        match self.const_int_arg(batch, index)? {
+            Some(n) if n > 0 => Ok(n as usize),
+            _ => Err(DataFusionError::Plan(format!(
+                "The argument {} of the window function {} must be a positive integer",
+                index + 1,
+                self.fun
+            ))),
+        }
+    }
+}
+
+impl WindowExpr for BuiltInWindowExpr {
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        self.args.clone()
+    }
+
+    fn partition_by(&self) -> &[Arc<dyn PhysicalExpr>] {
+        &self.partition_by
+    }
+
+    fn order_by(&self) -> &[PhysicalSortExpr] {
+        &self.order_by
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let sorted = SortedBatch::try_new(batch, &self.partition_by, &self.order_by)?;
+        let window_frame = self.window_frame.unwrap_or_default();
+        let range_values = sorted.range_values(&window_frame)?;
+
+        // the values of the first argument, in sorted order
+        let values_arg = match self.fun {
+            BuiltInWindowFunction::Lag
+            | BuiltInWindowFunction::Lead
+            | BuiltInWindowFunction::FirstValue
+            | BuiltInWindowFunction::LastValue
+            | BuiltInWindowFunction::NthValue => {
+                Some(sorted.evaluate_args(batch, &self.args[..1])?.remove(0))
+            }
+            _ => None,
+        };
+        let value_at = |idx: usize| match &values_arg {
+            Some(array) => ScalarValue::try_from_array(array, idx),
+            None => Err(DataFusionError::Internal(format!(
+                "The window function {} has no argument",
+                self.fun
+            ))),
+        };
+        let null_value = ScalarValue::try_from(&self.data_type)?;
+
+        // the constant arguments of the function
+        let buckets = match self.fun {
+            BuiltInWindowFunction::Ntile => self.positive_int_arg(batch, 0)?,
+            _ => 1,
+        };
+        let nth = match self.fun {
+            BuiltInWindowFunction::NthValue => self.positive_int_arg(batch, 1)?,
+            _ => 1,
+        };
+        let (offset, default_value) = match self.fun {
+            BuiltInWindowFunction::Lag => {
+                let (offset, default_value) = self.offset_args(batch)?;
+                (-offset, default_value)
+            }
+            BuiltInWindowFunction::Lead => self.offset_args(batch)?,
+            _ => (0, null_value.clone()),
+        };
+
+        let mut values = Vec::with_capacity(batch.num_rows());
+        for partition in &sorted.partitions {
+            let num_rows = partition.len();
+            let mut dense_rank = 0;
+            for idx in partition.clone() {
+                let peers = &sorted.peers[idx];
+                let value = match self.fun {
+                    BuiltInWindowFunction::RowNumber => {
+                        ScalarValue::UInt64(Some((idx - partition.start + 1) as u64))
+                    }
+                    BuiltInWindowFunction::Rank => ScalarValue::UInt64(Some(
+                        (peers.start - partition.start + 1) as u64,
+                    )),
+                    BuiltInWindowFunction::DenseRank => {
+                        if peers.start == idx {
+                            dense_rank += 1;
+                        }
+                        ScalarValue::UInt64(Some(dense_rank))
+                    }
+                    BuiltInWindowFunction::PercentRank => {
+                        let rank = peers.start - partition.start;
+                        ScalarValue::Float64(Some(if num_rows > 1 {
+                            rank as f64 / (num_rows - 1) as f64
+                        } else {
+                            0.0
+                        }))
+                    }
+                    BuiltInWindowFunction::CumeDist => ScalarValue::Float64(Some(
+                        (peers.end - partition.start) as f64 / num_rows as f64,
+                    )),
+                    BuiltInWindowFunction::Ntile => ScalarValue::UInt64(Some(ntile(
+                        idx - partition.start,
+                        num_rows,
+                        buckets,
+                    ))),
+                    BuiltInWindowFunction::Lag | BuiltInWindowFunction::Lead => {
+                        let target = idx as i64 + offset;
+                        if target >= partition.start as i64
+                            && target < partition.end as i64
+                        {
+                            value_at(target as usize)?
+                        } else {
+                            default_value.clone()
+                        }
+                    }
+                    BuiltInWindowFunction::FirstValue
+                    | BuiltInWindowFunction::LastValue
+                    | BuiltInWindowFunction::NthValue => {
+                        let frame = sorted.frame(
+                            &window_frame,
+                            idx,
+                            partition,
+                            range_values.as_deref(),
+                        );
+                        let position = match self.fun {
+                            BuiltInWindowFunction::FirstValue => Some(frame.start),
+                            BuiltInWindowFunction::LastValue => frame.end.checked_sub(1),
+                            _ => Some(frame.start + nth - 1),
+                        };
+                        match position {
+                            Some(position) if frame.contains(&position) => {
+                                value_at(position)?
+                            }
+                            _ => null_value.clone(),
+                        }
+                    }
+                };
+                values.push(value);
+            }
+        }
+
+        sorted.to_original_order(&values)
+    }
+}
+
+/// the (1-based) bucket of the row at position `row` of a partition of `num_rows` rows
+/// divided in `buckets` buckets whose sizes differ by at most 1, larger buckets first
+fn ntile(row: usize, num_rows: usize, buckets: usize) -> u64 {
+    let size = num_rows / buckets;
+    let larger_buckets = num_rows % buckets;
+    let larger_rows = larger_buckets * (size + 1);
+    let bucket = if row < larger_rows {
+        row / (size + 1)
+    } else {
+        larger_buckets + (row - larger_rows) / size
+    };
+    bucket as u64 + 1
+}
+
+fn cast_scalar(value: &ScalarValue, data_type: &DataType) -> Result<ScalarValue> {
+    if value.get_datatype() == *data_type {
+        return Ok(value.clone());
+    }
+    let array = cast(&value.to_array(), data_type)?;
+    ScalarValue::try_from_array(&array, 0)
+}
+
+fn slice_args(args: &[ArrayRef], range: Range<usize>) -> Vec<ArrayRef> {
+    args.iter()
+        .map(|array| array.slice(range.start, range.len()))
+        .collect()
+}
+
+/// The rows of a batch, sorted by the PARTITION BY and ORDER BY clauses of a window
+/// expression. All the positions are positions in the sorted order.
+struct SortedBatch {
+    /// for each sorted position, the position of the row in the original batch
+    indices: UInt32Array,
+    /// the range of positions of each partition
+    partitions: Vec<Range<usize>>,
+    /// for each position, the range of positions of its peers, i.e. the rows of the
+    /// same partition with equal ORDER BY values
+    peers: Vec<Range<usize>>,
+    /// the sorted ORDER BY columns
+    order_by: Vec<SortColumn>,
+}
+
+impl SortedBatch {
+    fn try_new(
+        batch: &RecordBatch,
+        partition_by: &[Arc<dyn PhysicalExpr>],
+        order_by: &[PhysicalSortExpr],
+    ) -> Result<Self> {
+        let num_rows = batch.num_rows();
+
+        let partition_columns = partition_by
+            .iter()
+            .map(|e| {
+                Ok(SortColumn {
+                    values: e.evaluate(batch)?.into_array(num_rows),
+                    options: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let order_by_columns = order_by
+            .iter()
+            .map(|e| e.evaluate_to_sort_column(batch))
+            .collect::<Result<Vec<_>>>()?;
+
+        let sort_columns = partition_columns
+            .iter()
+            .chain(order_by_columns.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        let indices = if sort_columns.is_empty() {
+            UInt32Array::from((0..num_rows as u32).collect::<Vec<_>>())
+        } else {
+            lexsort_to_indices(&sort_columns)?
+        };
+
+        let take_sorted = |column: &SortColumn| -> Result<SortColumn> {
+            Ok(SortColumn {
+                values: take(column.values.as_ref(), &indices, None)?,
+                options: column.options,
+            })
+        };
+        let partition_columns = partition_columns
+            .iter()
+            .map(take_sorted)
+            .collect::<Result<Vec<_>>>()?;
+        let order_by_columns = order_by_columns
+            .iter()
+            .map(take_sorted)
+            .collect::<Result<Vec<_>>>()?;
+
+        let partitions = find_ranges_of_equal_values(&partition_columns, 0..num_rows)?;
+        let mut peers = Vec::with_capacity(num_rows);
+        for partition in &partitions {
+            for peer_range in
+                find_ranges_of_equal_values(&order_by_columns, partition.clone())?
+            {
+                peers.extend(peer_range.clone().map(|_| peer_range.clone()));
+            }
+        }
+
+        Ok(Self {
+            indices,
+            partitions,
+            peers,
+            order_by: order_by_columns,
+        })
+    }
+
+    /// evaluates `exprs` against `batch`, in sorted order
+    fn evaluate_args(
+        &self,
+        batch: &RecordBatch,
+        exprs: &[Arc<dyn PhysicalExpr>],
+    ) -> Result<Vec<ArrayRef>> {
+        exprs
+            .iter()
+            .map(|e| {
+                let array = e.evaluate(batch)?.into_array(batch.num_rows());
+                Ok(take(array.as_ref(), &self.indices, None)?)
+            })
+            .collect()
+    }
+
+    /// the values compared by a RANGE frame with offsets, as `f64` that are increasing
+    /// in sorted order
+    fn range_values(&self, window_frame: &WindowFrame) -> Result<Option<Vec<f64>>> {
+        if !has_range_offset(window_frame) {
+            return Ok(None);
+        }
+
+        let column = &self.order_by[0];
+        let values = cast(&column.values, &DataType::Float64)?;
+        let values = values
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .ok_or_else(|| {
+                DataFusionError::Internal("Failed to downcast Float64Array".to_string())
+            })?;
+        let descending = column.options.map(|o| o.descending).unwrap_or(false);
+        Ok(Some(
+            (0..values.len())
+                .map(|i| {
+                    if descending {
+                        -values.value(i)
+                    } else {
+                        values.value(i)
+                    }
+                })
+                .collect(),
+        ))
+    }
+
+    /// the frame of the row at position `idx`, as a (possibly empty) range of positions
+    fn frame(
+        &self,
+        window_frame: &WindowFrame,
+        idx: usize,
+        partition: &Range<usize>,
+        range_values: Option<&[f64]>,
+    ) -> Range<usize> {
+        let start = self.frame_bound(
+            &window_frame.start_bound,
+            true,
+            window_frame.units,
+            idx,
+            partition,
+            range_values,
+        );
+        let end = self.frame_bound(
+            &window_frame.end_bound,
+            false,
+            window_frame.units,
+            idx,
+            partition,
+            range_values,
+        );
+        start..end.max(start)
+    }
+
+    /// the position of the first row of the frame (when `is_start`) or the position
+    /// after the last row of the frame
+    fn frame_bound(
+        &self,
+        bound: &WindowFrameBound,
+        is_start: bool,
+        units: WindowFrameUnits,
+        idx: usize,
+        partition: &Range<usize>,
+        range_values: Option<&[f64]>,
+    ) -> usize {
+        match (bound, units) {
+            (WindowFrameBound::Preceding(None), _) => partition.start,
+            (WindowFrameBound::Following(None), _) => partition.end,
+            (WindowFrameBound::CurrentRow, WindowFrameUnits::Rows) => {
+                if is_start {
+                    idx
+                } else {
+                    idx + 1
+                }
+            }
+            (WindowFrameBound::CurrentRow, WindowFrameUnits::Range) => {
+                let peers = &self.peers[idx];
+                if is_start {
+                    peers.start
+                } else {
+                    peers.end
+                }
+            }
+            (WindowFrameBound::Preceding(Some(n)), WindowFrameUnits::Rows) => {
+                // the row `n` rows before the current row, if it is in the partition
+                let row = (idx - partition.start)
+                    .checked_sub(*n as usize)
+                    .map(|offset| partition.start + offset);
+                match (row, is_start) {
+                    (Some(row), true) => row,
+                    (Some(row), false) => row + 1,
+                    (None, _) => partition.start,
+                }
+            }
+            (WindowFrameBound::Following(Some(n)), WindowFrameUnits::Rows) => {
+                let last = idx.saturating_add(*n as usize);
+                if is_start {
+                    last.min(partition.end)
+                } else {
+                    last.saturating_add(1).min(partition.end)
+                }
+            }
+            (WindowFrameBound::Preceding(Some(n)), WindowFrameUnits::Range)
+            | (WindowFrameBound::Following(Some(n)), WindowFrameUnits::Range) => {
+                let peers = &self.peers[idx];
+                let values = match range_values {
+                    Some(values) => values,
+                    None => return if is_start { peers.start } else { peers.end },
+                };
+                let column = &self.order_by[0].values;
+                if column.is_null(idx) {
+                    // the frame of a NULL value is made of its (NULL) peers
+                    return if is_start { peers.start } else { peers.end };
+                }
+
+                // the NULL values are either at the start or at the end of the partition
+                let nulls_first = self.order_by[0]
+                    .options
+                    .map(|o| o.nulls_first)
+                    .unwrap_or(true);
+                let non_null = if nulls_first {
+                    first_position(partition.clone(), |i| column.is_null(i))
+                        ..partition.end
+                } else {
+                    partition.start..first_position(partition.clone(), |i| {
+                        column.is_valid(i)
+                    })
+                };
+
+                let target = if let WindowFrameBound::Preceding(_) = bound {
+                    values[idx] - *n as f64
+                } else {
+                    values[idx] + *n as f64
+                };
+                if is_start {
+                    first_position(non_null, |i| values[i] < target)
+                } else {
+                    first_position(non_null, |i| values[i] <= target)
+                }
+            }
+        }
+    }
+
+    /// converts the values of the window function, in sorted order, to an array in the
+    /// order of the original batch
+    fn to_original_order(&self, values: &[ScalarValue]) -> Result<ArrayRef> {
+        let sorted = ScalarValue::iter_to_array(values)?;
+        let mut inverse = vec![0_u32; self.indices.len()];
+        for (position, original) in self.indices.values().iter().enumerate() {
+            inverse[*original as usize] = position as u32;
+        }
+        Ok(take(sorted.as_ref(), &UInt32Array::from(inverse), None)?)
+    }
+}
+
+/// the first position in `range` for which `predicate` is false, given that `predicate`
+/// is true for a (possibly empty) prefix of `range` and false afterwards
+fn first_position<F>(range: Range<usize>, predicate: F) -> usize
+where
+    F: Fn(usize) -> bool,
+{
+    let (mut low, mut high) = (range.start, range.end);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if predicate(mid) {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// splits `range` into the ranges of consecutive positions with equal values in
+/// `columns`. NULL values are equal to each other.
+fn find_ranges_of_equal_values(
+    columns: &[SortColumn],
+    range: Range<usize>,
+) -> Result<Vec<Range<usize>>> {
+    let mut ranges = vec![];
+    if range.is_empty() {
+        return Ok(ranges);
+    }
+
+    let comparators = columns
+        .iter()
+        .map(|column| build_compare(column.values.as_ref(), column.values.as_ref()))
+        .collect::<ArrowResult<Vec<_>>>()?;
+    let is_equal = |left: usize, right: usize| {
+        columns.iter().zip(comparators.iter()).all(|(column, cmp)| {
+            match (column.values.is_valid(left), column.values.is_valid(right)) {
+                (true, true) => cmp(left, right) == Ordering::Equal,
+                (false, false) => true,
+                _ => false,
+            }
+        })
+    };
+
+    let mut start = range.start;
+    for idx in range.start + 1..range.end {
+        if !is_equal(idx - 1, idx) {
+            ranges.push(start..idx);
+            start = idx;
+        }
+    }
+    ranges.push(start..range.end);
+    Ok(ranges)
+}
+
+/// Window execution plan
+#[derive(Debug)]
+pub struct WindowAggExec {
+    /// Input plan
+    input: Arc<dyn ExecutionPlan>,
+    /// Window function expressions
+    window_expr: Vec<Arc<dyn WindowExpr>>,
+    /// Schema after the window is run
+    schema: SchemaRef,
+}
+
+impl WindowAggExec {
+    /// Create a new execution plan for window functions
+    pub fn try_new(
+        window_expr: Vec<Arc<dyn WindowExpr>>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Result<Self> {
+        let input_schema = input.schema();
+        let mut fields = input_schema.fields().clone();
+        for expr in &window_expr {
+            fields.push(expr.field()?);
+        }
+        let schema = Arc::new(Schema::new(fields));
+
+        Ok(Self {
+            input,
+            window_expr,
+            schema,
+        })
+    }
+
+    /// Window expressions
+    pub fn window_expr(&self) -> &[Arc<dyn WindowExpr>] {
+        &self.window_expr
+    }
+
+    /// Input plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for WindowAggExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::SinglePartition
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(WindowAggExec::try_new(
+                self.window_expr.clone(),
+                children[0].clone(),
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "WindowAggExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "WindowAggExec invalid partition {}",
+                partition
+            )));
+        }
+
+        // window needs to operate on a single partition currently
+        if 1 != self.input.output_partitioning().partition_count() {
+            return Err(DataFusionError::Internal(
+                "WindowAggExec requires a single input partition".to_owned(),
+            ));
+        }
+        let input = self.input.execute(0).await?;
+
+        Ok(Box::pin(WindowAggStream::new(
+            self.schema.clone(),
+            self.window_expr.clone(),
+            input,
+        )))
+    }
+}
+
+/// Combines all the input batches and appends the values of the window expressions
+fn compute_window_aggregates(
+    batches: &[RecordBatch],
+    schema: &SchemaRef,
+    window_expr: &[Arc<dyn WindowExpr>],
+) -> ArrowResult<Option<RecordBatch>> {
+    if batches.iter().all(|batch| batch.num_rows() == 0) {
+        return Ok(None);
+    }
+
+    // combine all record batches into one for each column
+    let input_schema = batches[0].schema();
+    let mut columns = (0..input_schema.fields().len())
+        .map(|i| {
+            concat(
+                &batches
+                    .iter()
+                    .map(|batch| batch.column(i).as_ref())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<ArrowResult<Vec<ArrayRef>>>()?;
+    let combined_batch = RecordBatch::try_new(input_schema, columns.clone())?;
+
+    for expr in window_expr {
+        columns.push(
+            expr.evaluate(&combined_batch)
+                .map_err(DataFusionError::into_arrow_external_error)?,
+        );
+    }
+
+    Ok(Some(RecordBatch::try_new(schema.clone(), columns)?))
+}
+
+pin_project! {
+    struct WindowAggStream {
+        #[pin]
+        output: futures::channel::oneshot::Receiver<ArrowResult<Option<RecordBatch>>>,
+        finished: bool,
+        schema: SchemaRef,
+    }
+}
+
+impl WindowAggStream {
+    fn new(
+        schema: SchemaRef,
+        window_expr: Vec<Arc<dyn WindowExpr>>,
+        input: SendableRecordBatchStream,
+    ) -> Self {
+        let (tx, rx) = futures::channel::oneshot::channel();
+
+        let output_schema = schema.clone();
+        tokio::spawn(async move {
+            let result = common::collect(input)
+                .await
+                .map_err(DataFusionError::into_arrow_external_error)
+                .and_then(|batches| {
+                    compute_window_aggregates(&batches, &output_schema, &window_expr)
+                });
+
+            tx.send(result)
+        });
+
+        Self {
+            output: rx,
+            finished: false,
+            schema,
+        }
+    }
+}
+
+impl Stream for WindowAggStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.finished {
+            return Poll::Ready(None);
+        }
+
+        // is the output ready?
+        let this = self.project();
+        let output_poll = this.output.poll(cx);
+
+        match output_poll {
+            Poll::Ready(result) => {
+                *this.finished = true;
+
+                // check for error in receiving channel and unwrap actual result
+                match result {
+                    Err(e) => {
+                        Poll::Ready(Some(Err(ArrowError::ExternalError(Box::new(e)))))
+                    } // error receiving
+                    Ok(Ok(Some(batch))) => Poll::Ready(Some(Ok(batch))),
+                    Ok(Ok(None)) => Poll::Ready(None),
+                    Ok(Err(e)) => Poll::Ready(Some(Err(e))),
+                }
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl RecordBatchStream for WindowAggStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::aggregates::AggregateFunction;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::memory::MemoryExec;
+    use arrow::array::*;
+    use arrow::compute::SortOptions;
+
+    fn create_input() -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Utf8, false),
+            Field::new("b", DataType::Int32, false),
+            Field::new("c", DataType::Int32, false),
+        ]));
+
+        // two batches, to verify that the window is computed over all of them
+        let batch1 = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["b", "a", "b"])),
+                Arc::new(Int32Array::from(vec![3, 1, 1])),
+                Arc::new(Int32Array::from(vec![5, 1, 4])),
+            ],
+        )?;
+        let batch2 = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(Int32Array::from(vec![2, 1])),
+                Arc::new(Int32Array::from(vec![2, 3])),
+            ],
+        )?;
+
+        Ok(Arc::new(MemoryExec::try_new(
+            &vec![vec![batch1, batch2]],
+            schema,
+            None,
+        )?))
+    }
+
+    fn sort_expr(name: &str) -> PhysicalSortExpr {
+        PhysicalSortExpr {
+            expr: col(name),
+            options: SortOptions::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn window_ranking_functions() -> Result<()> {
+        let input = create_input()?;
+        let schema = input.schema();
+
+        let window_expr = vec![
+            create_window_expr(
+                &WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::Rank),
+                "rank".to_owned(),
+                &[],
+                &[col("a")],
+                &[sort_expr("b")],
+                None,
+                &schema,
+            )?,
+            create_window_expr(
+                &WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::DenseRank),
+                "dense_rank".to_owned(),
+                &[],
+                &[col("a")],
+                &[sort_expr("b")],
+                None,
+                &schema,
+            )?,
+            create_window_expr(
+                &WindowFunction::AggregateFunction(AggregateFunction::Sum),
+                "sum".to_owned(),
+                &[col("b")],
+                &[col("a")],
+                &[sort_expr("b")],
+                None,
+                &schema,
+            )?,
+        ];
+        let window = Arc::new(WindowAggExec::try_new(window_expr, input)?);
+
+        let result: Vec<RecordBatch> = collect(window).await?;
+        assert_eq!(result.len(), 1);
+        let batch = &result[0];
+        assert_eq!(batch.num_columns(), 6);
+
+        // the peers of a row are part of its (default) frame
+        assert_eq!(
+            batch.column(3).as_ref(),
+            &UInt64Array::from(vec![3, 1, 1, 2, 1])
+        );
+        assert_eq!(
+            batch.column(4).as_ref(),
+            &UInt64Array::from(vec![2, 1, 1, 2, 1])
+        );
+        assert_eq!(
+            batch.column(5).as_ref(),
+            &Int64Array::from(vec![5, 1, 2, 3, 2])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn window_frames() -> Result<()> {
+        let input = create_input()?;
+        let schema = input.schema();
+
+        let window_expr = vec![
+            create_window_expr(
+                &WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::RowNumber),
+                "row_number".to_owned(),
+                &[],
+                &[],
+                &[sort_expr("c")],
+                None,
+                &schema,
+            )?,
+            create_window_expr(
+                &WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::Lag),
+                "lag".to_owned(),
+                &[col("c")],
+                &[],
+                &[sort_expr("c")],
+                None,
+                &schema,
+            )?,
+            create_window_expr(
+                &WindowFunction::AggregateFunction(AggregateFunction::Sum),
+                "sum_rows".to_owned(),
+                &[col("c")],
+                &[],
+                &[sort_expr("c")],
+                Some(WindowFrame {
+                    units: WindowFrameUnits::Rows,
+                    start_bound: WindowFrameBound::Preceding(Some(1)),
+                    end_bound: WindowFrameBound::Following(Some(1)),
+                }),
+                &schema,
+            )?,
+            create_window_expr(
+                &WindowFunction::AggregateFunction(AggregateFunction::Sum),
+                "sum_range".to_owned(),
+                &[col("c")],
+                &[],
+                &[sort_expr("c")],
+                Some(WindowFrame {
+                    units: WindowFrameUnits::Range,
+                    start_bound: WindowFrameBound::Preceding(Some(1)),
+                    end_bound: WindowFrameBound::CurrentRow,
+                }),
+                &schema,
+            )?,
+        ];
+        let window = Arc::new(WindowAggExec::try_new(window_expr, input)?);
+
+        let result: Vec<RecordBatch> = collect(window).await?;
+        assert_eq!(result.len(), 1);
+        let batch = &result[0];
+
+        assert_eq!(
+            batch.column(3).as_ref(),
+            &UInt64Array::from(vec![5, 1, 4, 2, 3])
+        );
+        assert_eq!(
+            batch.column(4).as_ref(),
+            &Int32Array::from(vec![Some(4), None, Some(3), Some(1), Some(2)])
+        );
+        assert_eq!(
+            batch.column(5).as_ref(),
+            &Int64Array::from(vec![9, 3, 12, 6, 9])
+        );
+        assert_eq!(
+            batch.column(6).as_ref(),
+            &Int64Array::from(vec![9, 1, 7, 3, 5])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn window_range_frame_requires_numeric_order_by() -> Result<()> {
+        let schema = create_input()?.schema();
+        let result = create_window_expr(
+            &WindowFunction::AggregateFunction(AggregateFunction::Sum),
+            "sum".to_owned(),
+            &[col("c")],
+            &[],
+            &[sort_expr("a")],
+            Some(WindowFrame {
+                units: WindowFrameUnits::Range,
+                start_bound: WindowFrameBound::Preceding(Some(1)),
+                end_bound: WindowFrameBound::CurrentRow,
+            }),
+            &schema,
+        );
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn ntile_buckets() {
+        // 10 rows in 4 buckets: 3, 3, 2, 2
+        let buckets = (0..10).map(|row| ntile(row, 10, 4)).collect::<Vec<_>>();
+        assert_eq!(buckets, vec![1, 1, 1, 2, 2, 2, 3, 3, 4, 4]);
+    }
+}
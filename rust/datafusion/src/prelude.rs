@@ -28,7 +28,8 @@
 pub use crate::dataframe::DataFrame;
 pub use crate::execution::context::{ExecutionConfig, ExecutionContext};
 pub use crate::logical_plan::{
-    array, avg, col, concat, count, create_udf, length, lit, lower, max, min, sum, trim,
-    upper, JoinType, Partitioning,
+    array, array_agg, avg, col, concat, count, create_udf, length, lit, lower, max, min,
+    sum, trim, upper, JoinType, Partitioning,
 };
 pub use crate::physical_plan::csv::CsvReadOptions;
+pub use crate::physical_plan::json::NdJsonReadOptions;
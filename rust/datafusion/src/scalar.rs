@@ -20,8 +20,10 @@
 use std::{convert::TryFrom, fmt, sync::Arc};
 
 use arrow::array::{
-    Int16Builder, Int32Builder, Int64Builder, Int8Builder, ListBuilder, UInt16Builder,
-    UInt32Builder, UInt64Builder, UInt8Builder,
+    BooleanBuilder, Date32Builder, Float32Builder, Float64Builder, Int16Builder,
+    Int32Builder, Int64Builder, Int8Builder, ListBuilder, StringBuilder,
+    TimestampNanosecondBuilder, UInt16Builder, UInt32Builder, UInt64Builder,
+    UInt8Builder,
 };
 use arrow::{
     array::ArrayRef,
@@ -29,11 +31,13 @@ use arrow::{
 };
 use arrow::{
     array::{
-        Array, BooleanArray, Date32Array, Float32Array, Float64Array, Int16Array,
-        Int32Array, Int64Array, Int8Array, LargeStringArray, ListArray, StringArray,
-        UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+        Array, BinaryArray, BooleanArray, Date32Array, Date64Array, Float32Array,
+        Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, LargeStringArray,
+        ListArray, StringArray, TimestampMicrosecondArray, TimestampMillisecondArray,
+        TimestampNanosecondArray, TimestampSecondArray, UInt16Array, UInt32Array,
+        UInt64Array, UInt8Array,
     },
-    datatypes::DateUnit,
+    datatypes::{DateUnit, TimeUnit},
 };
 
 use crate::error::{DataFusionError, Result};
@@ -68,10 +72,22 @@ pub enum ScalarValue {
     Utf8(Option<String>),
     /// utf-8 encoded string representing a LargeString's arrow type.
     LargeUtf8(Option<String>),
+    /// binary data, such as the serialized state of a sketch
+    Binary(Option<Vec<u8>>),
     /// list of nested ScalarValue
     List(Option<Vec<ScalarValue>>, DataType),
     /// Date stored as a signed 32bit int
     Date32(Option<i32>),
+    /// Date stored as a signed 64bit int, in milliseconds since the epoch
+    Date64(Option<i64>),
+    /// Timestamp in seconds since the epoch, without timezone
+    TimestampSecond(Option<i64>),
+    /// Timestamp in milliseconds since the epoch, without timezone
+    TimestampMillisecond(Option<i64>),
+    /// Timestamp in microseconds since the epoch, without timezone
+    TimestampMicrosecond(Option<i64>),
+    /// Timestamp in nanoseconds since the epoch, without timezone
+    TimestampNanosecond(Option<i64>),
 }
 
 macro_rules! typed_cast {
@@ -86,6 +102,16 @@ macro_rules! typed_cast {
 
 macro_rules! build_list {
     ($VALUE_BUILDER_TY:ident, $SCALAR_TY:ident, $VALUES:expr, $SIZE:expr) => {{
+        build_list!($VALUE_BUILDER_TY, $SCALAR_TY, $VALUES, $SIZE, v => *v)
+    }};
+    // `$VALUE` converts the value `$V` of a scalar into the type of the builder
+    (
+        $VALUE_BUILDER_TY:ident,
+        $SCALAR_TY:ident,
+        $VALUES:expr,
+        $SIZE:expr,
+        $V:ident => $VALUE:expr
+    ) => {{
         match $VALUES {
             None => {
                 let mut builder = ListBuilder::new($VALUE_BUILDER_TY::new(0));
@@ -100,8 +126,8 @@ macro_rules! build_list {
                 for _ in 0..$SIZE {
                     for scalar_value in values {
                         match scalar_value {
-                            ScalarValue::$SCALAR_TY(Some(v)) => {
-                                builder.values().append_value(*v).unwrap()
+                            ScalarValue::$SCALAR_TY(Some($V)) => {
+                                builder.values().append_value($VALUE).unwrap()
                             }
                             ScalarValue::$SCALAR_TY(None) => {
                                 builder.values().append_null().unwrap();
@@ -135,10 +161,24 @@ impl ScalarValue {
             ScalarValue::Float64(_) => DataType::Float64,
             ScalarValue::Utf8(_) => DataType::Utf8,
             ScalarValue::LargeUtf8(_) => DataType::LargeUtf8,
+            ScalarValue::Binary(_) => DataType::Binary,
             ScalarValue::List(_, data_type) => {
                 DataType::List(Box::new(Field::new("item", data_type.clone(), true)))
             }
             ScalarValue::Date32(_) => DataType::Date32(DateUnit::Day),
+            ScalarValue::Date64(_) => DataType::Date64(DateUnit::Millisecond),
+            ScalarValue::TimestampSecond(_) => {
+                DataType::Timestamp(TimeUnit::Second, None)
+            }
+            ScalarValue::TimestampMillisecond(_) => {
+                DataType::Timestamp(TimeUnit::Millisecond, None)
+            }
+            ScalarValue::TimestampMicrosecond(_) => {
+                DataType::Timestamp(TimeUnit::Microsecond, None)
+            }
+            ScalarValue::TimestampNanosecond(_) => {
+                DataType::Timestamp(TimeUnit::Nanosecond, None)
+            }
         }
     }
 
@@ -161,6 +201,27 @@ impl ScalarValue {
         }
     }
 
+    /// Estimated number of bytes of memory used by the value, its heap allocations
+    /// included
+    pub fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + match self {
+                ScalarValue::Utf8(Some(v)) | ScalarValue::LargeUtf8(Some(v)) => {
+                    v.capacity()
+                }
+                ScalarValue::Binary(Some(v)) => v.capacity(),
+                ScalarValue::List(Some(values), _) => ScalarValue::size_of_vec(values),
+                _ => 0,
+            }
+    }
+
+    /// Estimated number of bytes of memory allocated by `values`, the heap
+    /// allocations of the values included
+    pub fn size_of_vec(values: &Vec<ScalarValue>) -> usize {
+        values.iter().map(|value| value.size()).sum::<usize>()
+            + (values.capacity() - values.len()) * std::mem::size_of::<Self>()
+    }
+
     /// whether this value is null or not.
     pub fn is_null(&self) -> bool {
         matches!(
@@ -178,7 +239,14 @@ impl ScalarValue {
                 | ScalarValue::Float64(None)
                 | ScalarValue::Utf8(None)
                 | ScalarValue::LargeUtf8(None)
+                | ScalarValue::Binary(None)
                 | ScalarValue::List(None, _)
+                | ScalarValue::Date32(None)
+                | ScalarValue::Date64(None)
+                | ScalarValue::TimestampSecond(None)
+                | ScalarValue::TimestampMillisecond(None)
+                | ScalarValue::TimestampMicrosecond(None)
+                | ScalarValue::TimestampNanosecond(None)
         )
     }
 
@@ -209,7 +277,11 @@ impl ScalarValue {
             ScalarValue::LargeUtf8(e) => {
                 Arc::new(LargeStringArray::from(vec![e.as_deref(); size]))
             }
+            ScalarValue::Binary(e) => {
+                Arc::new(BinaryArray::from(vec![e.as_deref(); size]))
+            }
             ScalarValue::List(values, data_type) => Arc::new(match data_type {
+                DataType::Boolean => build_list!(BooleanBuilder, Boolean, values, size),
                 DataType::Int8 => build_list!(Int8Builder, Int8, values, size),
                 DataType::Int16 => build_list!(Int16Builder, Int16, values, size),
                 DataType::Int32 => build_list!(Int32Builder, Int32, values, size),
@@ -218,10 +290,121 @@ impl ScalarValue {
                 DataType::UInt16 => build_list!(UInt16Builder, UInt16, values, size),
                 DataType::UInt32 => build_list!(UInt32Builder, UInt32, values, size),
                 DataType::UInt64 => build_list!(UInt64Builder, UInt64, values, size),
+                DataType::Float32 => build_list!(Float32Builder, Float32, values, size),
+                DataType::Float64 => build_list!(Float64Builder, Float64, values, size),
+                DataType::Utf8 => {
+                    build_list!(StringBuilder, Utf8, values, size, v => v.as_str())
+                }
+                DataType::Date32(DateUnit::Day) => {
+                    build_list!(Date32Builder, Date32, values, size)
+                }
+                DataType::Timestamp(TimeUnit::Nanosecond, None) => build_list!(
+                    TimestampNanosecondBuilder,
+                    TimestampNanosecond,
+                    values,
+                    size
+                ),
                 _ => panic!("Unexpected DataType for list"),
             }),
             ScalarValue::Date32(e) => Arc::new(Date32Array::from(vec![*e; size])),
+            ScalarValue::Date64(e) => Arc::new(Date64Array::from(vec![*e; size])),
+            ScalarValue::TimestampSecond(e) => {
+                Arc::new(TimestampSecondArray::from(vec![*e; size]))
+            }
+            ScalarValue::TimestampMillisecond(e) => {
+                Arc::new(TimestampMillisecondArray::from(vec![*e; size]))
+            }
+            ScalarValue::TimestampMicrosecond(e) => {
+                Arc::new(TimestampMicrosecondArray::from(vec![*e; size]))
+            }
+            ScalarValue::TimestampNanosecond(e) => {
+                Arc::new(TimestampNanosecondArray::from(vec![*e; size]))
+            }
+        }
+    }
+
+    /// Converts a slice of scalar values into an array. All the values must be of the
+    /// same type, which becomes the type of the resulting array.
+    pub fn iter_to_array(scalars: &[ScalarValue]) -> Result<ArrayRef> {
+        let data_type = match scalars.first() {
+            Some(scalar) => scalar.get_datatype(),
+            None => {
+                return Err(DataFusionError::Internal(
+                    "Empty slice passed to ScalarValue::iter_to_array".to_owned(),
+                ))
+            }
+        };
+
+        macro_rules! build_array {
+            ($ARRAY_TY:ident, $SCALAR_TY:ident) => {{
+                let values = scalars
+                    .iter()
+                    .map(|scalar| match scalar {
+                        ScalarValue::$SCALAR_TY(v) => Ok(*v),
+                        other => Err(DataFusionError::Internal(format!(
+                            "Inconsistent types in ScalarValue::iter_to_array. \
+                             Expected {:?}, got {:?}",
+                            data_type, other
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Arc::new($ARRAY_TY::from(values)) as ArrayRef
+            }};
+        }
+
+        macro_rules! build_string_array {
+            ($ARRAY_TY:ident, $SCALAR_TY:ident) => {{
+                let values = scalars
+                    .iter()
+                    .map(|scalar| match scalar {
+                        ScalarValue::$SCALAR_TY(v) => Ok(v.as_deref()),
+                        other => Err(DataFusionError::Internal(format!(
+                            "Inconsistent types in ScalarValue::iter_to_array. \
+                             Expected {:?}, got {:?}",
+                            data_type, other
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Arc::new($ARRAY_TY::from(values)) as ArrayRef
+            }};
         }
+
+        Ok(match data_type {
+            DataType::Boolean => build_array!(BooleanArray, Boolean),
+            DataType::Float32 => build_array!(Float32Array, Float32),
+            DataType::Float64 => build_array!(Float64Array, Float64),
+            DataType::Int8 => build_array!(Int8Array, Int8),
+            DataType::Int16 => build_array!(Int16Array, Int16),
+            DataType::Int32 => build_array!(Int32Array, Int32),
+            DataType::Int64 => build_array!(Int64Array, Int64),
+            DataType::UInt8 => build_array!(UInt8Array, UInt8),
+            DataType::UInt16 => build_array!(UInt16Array, UInt16),
+            DataType::UInt32 => build_array!(UInt32Array, UInt32),
+            DataType::UInt64 => build_array!(UInt64Array, UInt64),
+            DataType::Utf8 => build_string_array!(StringArray, Utf8),
+            DataType::LargeUtf8 => build_string_array!(LargeStringArray, LargeUtf8),
+            DataType::Binary => build_string_array!(BinaryArray, Binary),
+            DataType::Date32(DateUnit::Day) => build_array!(Date32Array, Date32),
+            DataType::Date64(DateUnit::Millisecond) => build_array!(Date64Array, Date64),
+            DataType::Timestamp(TimeUnit::Second, None) => {
+                build_array!(TimestampSecondArray, TimestampSecond)
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, None) => {
+                build_array!(TimestampMillisecondArray, TimestampMillisecond)
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+                build_array!(TimestampMicrosecondArray, TimestampMicrosecond)
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+                build_array!(TimestampNanosecondArray, TimestampNanosecond)
+            }
+            other => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Can't create an array of type \"{:?}\" from scalar values",
+                    other
+                )))
+            }
+        })
     }
 
     /// Converts a value in `array` at `index` into a ScalarValue
@@ -240,6 +423,7 @@ impl ScalarValue {
             DataType::Int8 => typed_cast!(array, index, Int8Array, Int8),
             DataType::Utf8 => typed_cast!(array, index, StringArray, Utf8),
             DataType::LargeUtf8 => typed_cast!(array, index, LargeStringArray, LargeUtf8),
+            DataType::Binary => typed_cast!(array, index, BinaryArray, Binary),
             DataType::List(nested_type) => {
                 let list_array =
                     array.as_any().downcast_ref::<ListArray>().ok_or_else(|| {
@@ -262,6 +446,31 @@ impl ScalarValue {
             DataType::Date32(DateUnit::Day) => {
                 typed_cast!(array, index, Date32Array, Date32)
             }
+            DataType::Date64(DateUnit::Millisecond) => {
+                typed_cast!(array, index, Date64Array, Date64)
+            }
+            DataType::Timestamp(TimeUnit::Second, None) => {
+                typed_cast!(array, index, TimestampSecondArray, TimestampSecond)
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, None) => {
+                typed_cast!(
+                    array,
+                    index,
+                    TimestampMillisecondArray,
+                    TimestampMillisecond
+                )
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+                typed_cast!(
+                    array,
+                    index,
+                    TimestampMicrosecondArray,
+                    TimestampMicrosecond
+                )
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+                typed_cast!(array, index, TimestampNanosecondArray, TimestampNanosecond)
+            }
             other => {
                 return Err(DataFusionError::NotImplemented(format!(
                     "Can't create a scalar of array of type \"{:?}\"",
@@ -377,7 +586,27 @@ impl TryFrom<ScalarValue> for i32 {
     }
 }
 
-impl_try_from!(Int64, i64);
+// special implementation for i64 because of Date64 and the timestamps
+impl TryFrom<ScalarValue> for i64 {
+    type Error = DataFusionError;
+
+    fn try_from(value: ScalarValue) -> Result<Self> {
+        match value {
+            ScalarValue::Int64(Some(inner_value))
+            | ScalarValue::Date64(Some(inner_value))
+            | ScalarValue::TimestampSecond(Some(inner_value))
+            | ScalarValue::TimestampMillisecond(Some(inner_value))
+            | ScalarValue::TimestampMicrosecond(Some(inner_value))
+            | ScalarValue::TimestampNanosecond(Some(inner_value)) => Ok(inner_value),
+            _ => Err(DataFusionError::Internal(format!(
+                "Cannot convert {:?} to {}",
+                value,
+                std::any::type_name::<Self>()
+            ))),
+        }
+    }
+}
+
 impl_try_from!(UInt8, u8);
 impl_try_from!(UInt16, u16);
 impl_try_from!(UInt32, u32);
@@ -404,9 +633,24 @@ impl TryFrom<&DataType> for ScalarValue {
             DataType::UInt64 => ScalarValue::UInt64(None),
             DataType::Utf8 => ScalarValue::Utf8(None),
             DataType::LargeUtf8 => ScalarValue::LargeUtf8(None),
+            DataType::Binary => ScalarValue::Binary(None),
             DataType::List(ref nested_type) => {
                 ScalarValue::List(None, nested_type.data_type().clone())
             }
+            DataType::Date32(DateUnit::Day) => ScalarValue::Date32(None),
+            DataType::Date64(DateUnit::Millisecond) => ScalarValue::Date64(None),
+            DataType::Timestamp(TimeUnit::Second, None) => {
+                ScalarValue::TimestampSecond(None)
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, None) => {
+                ScalarValue::TimestampMillisecond(None)
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+                ScalarValue::TimestampMicrosecond(None)
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+                ScalarValue::TimestampNanosecond(None)
+            }
             _ => {
                 return Err(DataFusionError::NotImplemented(format!(
                     "Can't create a scalar of type \"{:?}\"",
@@ -442,6 +686,14 @@ impl fmt::Display for ScalarValue {
             ScalarValue::UInt64(e) => format_option!(f, e)?,
             ScalarValue::Utf8(e) => format_option!(f, e)?,
             ScalarValue::LargeUtf8(e) => format_option!(f, e)?,
+            ScalarValue::Binary(e) => match e {
+                Some(bytes) => {
+                    for byte in bytes {
+                        write!(f, "{:02x}", byte)?;
+                    }
+                }
+                None => write!(f, "NULL")?,
+            },
             ScalarValue::List(e, _) => match e {
                 Some(l) => write!(
                     f,
@@ -454,6 +706,11 @@ impl fmt::Display for ScalarValue {
                 None => write!(f, "NULL")?,
             },
             ScalarValue::Date32(e) => format_option!(f, e)?,
+            ScalarValue::Date64(e) => format_option!(f, e)?,
+            ScalarValue::TimestampSecond(e) => format_option!(f, e)?,
+            ScalarValue::TimestampMillisecond(e) => format_option!(f, e)?,
+            ScalarValue::TimestampMicrosecond(e) => format_option!(f, e)?,
+            ScalarValue::TimestampNanosecond(e) => format_option!(f, e)?,
         };
         Ok(())
     }
@@ -475,8 +732,20 @@ impl fmt::Debug for ScalarValue {
             ScalarValue::UInt64(_) => write!(f, "UInt64({})", self),
             ScalarValue::Utf8(_) => write!(f, "Utf8(\"{}\")", self),
             ScalarValue::LargeUtf8(_) => write!(f, "LargeUtf8(\"{}\")", self),
+            ScalarValue::Binary(_) => write!(f, "Binary(\"{}\")", self),
             ScalarValue::List(_, _) => write!(f, "List([{}])", self),
             ScalarValue::Date32(_) => write!(f, "Date32(\"{}\")", self),
+            ScalarValue::Date64(_) => write!(f, "Date64(\"{}\")", self),
+            ScalarValue::TimestampSecond(_) => write!(f, "TimestampSecond({})", self),
+            ScalarValue::TimestampMillisecond(_) => {
+                write!(f, "TimestampMillisecond({})", self)
+            }
+            ScalarValue::TimestampMicrosecond(_) => {
+                write!(f, "TimestampMicrosecond({})", self)
+            }
+            ScalarValue::TimestampNanosecond(_) => {
+                write!(f, "TimestampNanosecond({})", self)
+            }
         }
     }
 }
@@ -485,6 +754,47 @@ impl fmt::Debug for ScalarValue {
 mod tests {
     use super::*;
 
+    #[test]
+    fn scalar_iter_to_array() -> Result<()> {
+        let scalars = vec![
+            ScalarValue::Int32(Some(1)),
+            ScalarValue::Int32(None),
+            ScalarValue::Int32(Some(3)),
+        ];
+        let array = ScalarValue::iter_to_array(&scalars)?;
+        let array = array.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(array, &Int32Array::from(vec![Some(1), None, Some(3)]));
+
+        let scalars = vec![
+            ScalarValue::Utf8(Some("a".to_string())),
+            ScalarValue::Utf8(None),
+        ];
+        let array = ScalarValue::iter_to_array(&scalars)?;
+        let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(array, &StringArray::from(vec![Some("a"), None]));
+
+        // mixed types are an error
+        let scalars = vec![ScalarValue::Int32(Some(1)), ScalarValue::Int64(Some(1))];
+        assert!(ScalarValue::iter_to_array(&scalars).is_err());
+
+        assert!(ScalarValue::iter_to_array(&[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn scalar_binary_round_trip() -> Result<()> {
+        let scalars = vec![
+            ScalarValue::Binary(Some(vec![0, 1, 255])),
+            ScalarValue::Binary(None),
+        ];
+        let array = ScalarValue::iter_to_array(&scalars)?;
+        assert_eq!(array.data_type(), &DataType::Binary);
+        assert_eq!(ScalarValue::try_from_array(&array, 0)?, scalars[0]);
+        assert_eq!(ScalarValue::try_from_array(&array, 1)?, scalars[1]);
+        assert_eq!(format!("{:?}", scalars[0]), "Binary(\"0001ff\")");
+        Ok(())
+    }
+
     #[test]
     fn scalar_list_null_to_array() -> Result<()> {
         let list_array_ref = ScalarValue::List(None, DataType::UInt64).to_array();
@@ -525,4 +835,22 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn scalar_string_list_to_array() -> Result<()> {
+        let scalar = ScalarValue::List(
+            Some(vec![
+                ScalarValue::Utf8(Some("a".to_string())),
+                ScalarValue::Utf8(None),
+            ]),
+            DataType::Utf8,
+        );
+        let list_array_ref = scalar.to_array_of_size(2);
+        let list_array = list_array_ref.as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(list_array.len(), 2);
+        assert_eq!(list_array.values().len(), 4);
+
+        assert_eq!(ScalarValue::try_from_array(&list_array_ref, 1)?, scalar);
+        Ok(())
+    }
 }
@@ -86,6 +86,50 @@ pub struct DFParser {
     parser: Parser,
 }
 
+/// Rewrites the tokens of `SUBSTRING(<string> FROM <start> [FOR <length>])`, which
+/// sqlparser does not parse, into the ones of the function call
+/// `SUBSTRING(<string>, <start> [, <length>])`
+fn rewrite_substring(tokens: Vec<Token>) -> Vec<Token> {
+    // the depths of the parentheses of the enclosing SUBSTRING calls
+    let mut calls = vec![];
+    let mut depth = 0;
+    let mut after_substring = false;
+    tokens
+        .into_iter()
+        .map(|token| {
+            let token = match token {
+                Token::LParen => {
+                    depth += 1;
+                    if after_substring {
+                        calls.push(depth);
+                    }
+                    token
+                }
+                Token::RParen => {
+                    if calls.last() == Some(&depth) {
+                        calls.pop();
+                    }
+                    depth -= 1;
+                    token
+                }
+                Token::Word(w)
+                    if (w.keyword == Keyword::FROM || w.keyword == Keyword::FOR)
+                        && calls.last() == Some(&depth) =>
+                {
+                    Token::Comma
+                }
+                token => token,
+            };
+            match &token {
+                Token::Whitespace(_) => {}
+                Token::Word(w) => after_substring = w.value.to_uppercase() == "SUBSTRING",
+                _ => after_substring = false,
+            }
+            token
+        })
+        .collect()
+}
+
 impl DFParser {
     /// Parse the specified tokens
     pub fn new(sql: &str) -> Result<Self, ParserError> {
@@ -101,7 +145,7 @@ impl DFParser {
         let mut tokenizer = Tokenizer::new(dialect, sql);
         let tokens = tokenizer.tokenize()?;
         Ok(DFParser {
-            parser: Parser::new(tokens),
+            parser: Parser::new(rewrite_substring(tokens)),
         })
     }
 
@@ -401,4 +445,19 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn substring_from_for() -> Result<(), ParserError> {
+        let sql = "SELECT SUBSTRING(a FROM 1 FOR 2), substring (b from 3) FROM t";
+        let expected =
+            DFParser::parse_sql("SELECT SUBSTRING(a, 1, 2), substring (b, 3) FROM t")?;
+        assert_eq!(DFParser::parse_sql(sql)?, expected);
+
+        // only the arguments of SUBSTRING are rewritten
+        let sql = "SELECT SUBSTRING(a FROM (SELECT MAX(b) FROM u)) FROM t";
+        let expected =
+            DFParser::parse_sql("SELECT SUBSTRING(a, (SELECT MAX(b) FROM u)) FROM t")?;
+        assert_eq!(DFParser::parse_sql(sql)?, expected);
+        Ok(())
+    }
 }
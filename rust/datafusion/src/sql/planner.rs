@@ -17,14 +17,15 @@
 
 //! SQL Query Planner (produces logical plan from SQL AST)
 
+use std::convert::TryInto;
 use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::datasource::TableProvider;
 use crate::logical_plan::Expr::Alias;
 use crate::logical_plan::{
-    and, lit, DFSchema, Expr, LogicalPlan, LogicalPlanBuilder, Operator, PlanType,
-    StringifiedPlan, ToDFSchema,
+    and, col, cube, lit, rollup, DFSchema, Expr, LogicalPlan, LogicalPlanBuilder,
+    Operator, PlanType, StringifiedPlan, Subquery, ToDFSchema, WindowFrame,
 };
 use crate::scalar::ScalarValue;
 use crate::{
@@ -33,7 +34,7 @@ use crate::{
 };
 use crate::{
     physical_plan::udf::ScalarUDF,
-    physical_plan::{aggregates, functions},
+    physical_plan::{aggregates, functions, window_functions},
     sql::parser::{CreateExternalTable, FileType, Statement as DFStatement},
 };
 
@@ -42,9 +43,9 @@ use arrow::datatypes::*;
 use super::parser::ExplainPlan;
 use crate::prelude::JoinType;
 use sqlparser::ast::{
-    BinaryOperator, DataType as SQLDataType, Expr as SQLExpr, Join, JoinConstraint,
-    JoinOperator, Query, Select, SelectItem, SetExpr, TableFactor, TableWithJoins,
-    UnaryOperator, Value,
+    BinaryOperator, DataType as SQLDataType, Expr as SQLExpr, Function, Ident, Join,
+    JoinConstraint, JoinOperator, Query, Select, SelectItem, SetExpr, SetOperator,
+    TableFactor, TableWithJoins, UnaryOperator, Value, WindowSpec,
 };
 use sqlparser::ast::{ColumnDef as SQLColumnDef, ColumnOption};
 use sqlparser::ast::{OrderByExpr, Statement};
@@ -52,7 +53,7 @@ use sqlparser::parser::ParserError::ParserError;
 
 use super::utils::{
     can_columns_satisfy_exprs, expand_wildcard, expr_as_column_expr,
-    find_aggregate_exprs, find_column_exprs, rebase_expr,
+    find_aggregate_exprs, find_column_exprs, find_window_exprs, rebase_expr,
 };
 
 /// The ContextProvider trait allows the query planner to obtain meta-data about tables and
@@ -72,12 +73,74 @@ pub trait ContextProvider {
 /// SQL query planner
 pub struct SqlToRel<'a, S: ContextProvider> {
     schema_provider: &'a S,
+    /// The columns of the enclosing queries that a subquery can reference
+    outer_query_schema: Option<DFSchema>,
+    /// The relations of the FROM clause of the query whose expressions are planned
+    relations: Vec<Relation>,
+    /// The relations of the FROM clauses of the enclosing queries, innermost first
+    outer_relations: Vec<Relation>,
+}
+
+/// A relation of the FROM clause of a query, that qualified column names reference
+#[derive(Clone, Debug)]
+struct Relation {
+    /// The alias of the relation, or the name of its table
+    name: Option<String>,
+    /// The names of the columns of the relation, with the ones of the columns of the
+    /// plan holding them
+    columns: Vec<(String, String)>,
 }
 
 impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     /// Create a new query planner
     pub fn new(schema_provider: &'a S) -> Self {
-        SqlToRel { schema_provider }
+        SqlToRel {
+            schema_provider,
+            outer_query_schema: None,
+            relations: vec![],
+            outer_relations: vec![],
+        }
+    }
+
+    /// Create a planner of the expressions of a query over the given relations
+    fn with_relations(&self, relations: Vec<Relation>) -> Self {
+        SqlToRel {
+            schema_provider: self.schema_provider,
+            outer_query_schema: self.outer_query_schema.clone(),
+            relations,
+            outer_relations: self.outer_relations.clone(),
+        }
+    }
+
+    /// Generate a logical plan from a subquery of an expression evaluated against
+    /// `schema`. The subquery can reference the columns of `schema` and the ones of
+    /// the enclosing queries, its own columns shadowing them.
+    fn subquery_to_plan(
+        &self,
+        subquery: &Query,
+        schema: &DFSchema,
+    ) -> Result<LogicalPlan> {
+        let mut fields = schema.fields().clone();
+        if let Some(outer_query_schema) = &self.outer_query_schema {
+            fields.extend(
+                outer_query_schema
+                    .fields()
+                    .iter()
+                    .filter(|field| {
+                        schema.field_with_unqualified_name(field.name()).is_err()
+                    })
+                    .cloned(),
+            );
+        }
+        let mut outer_relations = self.relations.clone();
+        outer_relations.extend(self.outer_relations.iter().cloned());
+        let planner = SqlToRel {
+            schema_provider: self.schema_provider,
+            outer_query_schema: Some(DFSchema::new(fields)?),
+            relations: vec![],
+            outer_relations,
+        };
+        planner.query_to_plan(subquery)
     }
 
     /// Generate a logical plan from an DataFusion SQL statement
@@ -101,19 +164,50 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
     /// Generate a logic plan from an SQL query
     pub fn query_to_plan(&self, query: &Query) -> Result<LogicalPlan> {
-        let plan = match &query.body {
-            SetExpr::Select(s) => self.select_to_plan(s.as_ref()),
-            _ => Err(DataFusionError::NotImplemented(format!(
-                "Query {} not implemented yet",
-                query.body
-            ))),
-        }?;
+        let plan = self.set_expr_to_plan(&query.body)?;
 
         let plan = self.order_by(&plan, &query.order_by)?;
 
         self.limit(&plan, &query.limit)
     }
 
+    /// Generate a logical plan from a SELECT or from a set operation (e.g. UNION)
+    /// of queries
+    fn set_expr_to_plan(&self, set_expr: &SetExpr) -> Result<LogicalPlan> {
+        match set_expr {
+            SetExpr::Select(s) => self.select_to_plan(s.as_ref()),
+            SetExpr::Query(q) => self.query_to_plan(q.as_ref()),
+            SetExpr::SetOperation {
+                op,
+                all,
+                left,
+                right,
+            } => {
+                let left_plan = self.set_expr_to_plan(left.as_ref())?;
+                let right_plan = self.set_expr_to_plan(right.as_ref())?;
+                let builder = LogicalPlanBuilder::from(&left_plan);
+                match (op, all) {
+                    (SetOperator::Union, true) => builder.union(&right_plan)?.build(),
+                    (SetOperator::Union, false) => {
+                        builder.union(&right_plan)?.distinct()?.build()
+                    }
+                    (SetOperator::Intersect, false) => {
+                        builder.intersect(&right_plan)?.build()
+                    }
+                    (SetOperator::Except, false) => builder.except(&right_plan)?.build(),
+                    (op, true) => Err(DataFusionError::NotImplemented(format!(
+                        "{} ALL is not supported",
+                        op
+                    ))),
+                }
+            }
+            _ => Err(DataFusionError::NotImplemented(format!(
+                "Query {} not implemented yet",
+                set_expr
+            ))),
+        }
+    }
+
     /// Generate a logical plan from a CREATE EXTERNAL TABLE statement
     pub fn external_table_to_plan(
         &self,
@@ -144,6 +238,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                     ));
                 }
             }
+            // the schema of JSON files is inferred when no columns are defined
             FileType::NdJson => {}
         };
 
@@ -221,24 +316,32 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         }
     }
 
-    fn plan_from_tables(&self, from: &Vec<TableWithJoins>) -> Result<Vec<LogicalPlan>> {
+    fn plan_from_tables(
+        &self,
+        from: &Vec<TableWithJoins>,
+        relations: &mut Vec<Relation>,
+    ) -> Result<Vec<LogicalPlan>> {
         match from.len() {
             0 => Ok(vec![LogicalPlanBuilder::empty(true).build()?]),
             _ => from
                 .iter()
-                .map(|t| self.plan_table_with_joins(t))
+                .map(|t| self.plan_table_with_joins(t, relations))
                 .collect::<Result<Vec<_>>>(),
         }
     }
 
-    fn plan_table_with_joins(&self, t: &TableWithJoins) -> Result<LogicalPlan> {
-        let left = self.create_relation(&t.relation)?;
+    fn plan_table_with_joins(
+        &self,
+        t: &TableWithJoins,
+        relations: &mut Vec<Relation>,
+    ) -> Result<LogicalPlan> {
+        let left = self.create_relation(&t.relation, relations)?;
         match t.joins.len() {
             0 => Ok(left),
             n => {
-                let mut left = self.parse_relation_join(&left, &t.joins[0])?;
+                let mut left = self.parse_relation_join(&left, &t.joins[0], relations)?;
                 for i in 1..n {
-                    left = self.parse_relation_join(&left, &t.joins[i])?;
+                    left = self.parse_relation_join(&left, &t.joins[i], relations)?;
                 }
                 Ok(left)
             }
@@ -249,17 +352,26 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         &self,
         left: &LogicalPlan,
         join: &Join,
+        relations: &mut Vec<Relation>,
     ) -> Result<LogicalPlan> {
-        let right = self.create_relation(&join.relation)?;
+        let right = self.create_relation(&join.relation, relations)?;
+        // the join condition references the columns of the relations joined so far
+        let planner = self.with_relations(relations.clone());
         match &join.join_operator {
             JoinOperator::LeftOuter(constraint) => {
-                self.parse_join(left, &right, constraint, JoinType::Left)
+                planner.parse_join(left, &right, constraint, JoinType::Left)
             }
             JoinOperator::RightOuter(constraint) => {
-                self.parse_join(left, &right, constraint, JoinType::Right)
+                planner.parse_join(left, &right, constraint, JoinType::Right)
             }
             JoinOperator::Inner(constraint) => {
-                self.parse_join(left, &right, constraint, JoinType::Inner)
+                planner.parse_join(left, &right, constraint, JoinType::Inner)
+            }
+            JoinOperator::FullOuter(constraint) => {
+                planner.parse_join(left, &right, constraint, JoinType::Full)
+            }
+            JoinOperator::CrossJoin => {
+                LogicalPlanBuilder::from(left).cross_join(&right)?.build()
             }
             other => Err(DataFusionError::NotImplemented(format!(
                 "Unsupported JOIN operator {:?}",
@@ -278,22 +390,47 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         match constraint {
             JoinConstraint::On(sql_expr) => {
                 let mut keys: Vec<(String, String)> = vec![];
+                let mut filters = vec![];
                 let join_schema = left.schema().join(&right.schema())?;
 
                 // parse ON expression
                 let expr = self.sql_to_rex(sql_expr, &join_schema)?;
 
-                // extract join keys
-                extract_join_keys(&expr, &mut keys)?;
+                // extract join keys, and the other predicates of the join
+                split_join_condition(
+                    &expr,
+                    left.schema(),
+                    right.schema(),
+                    &mut keys,
+                    &mut filters,
+                );
                 let left_keys: Vec<&str> =
                     keys.iter().map(|pair| pair.0.as_str()).collect();
                 let right_keys: Vec<&str> =
                     keys.iter().map(|pair| pair.1.as_str()).collect();
+                let filter = filters.into_iter().fold(None, |acc, filter| match acc {
+                    Some(acc) => Some(and(acc, filter)),
+                    None => Some(filter),
+                });
 
                 // return the logical plan representing the join
-                LogicalPlanBuilder::from(&left)
-                    .join(&right, join_type, &left_keys, &right_keys)?
-                    .build()
+                let builder = LogicalPlanBuilder::from(&left);
+                match filter {
+                    None => builder.join(&right, join_type, &left_keys, &right_keys)?,
+                    // an inner join without keys filters the cartesian product of
+                    // its inputs
+                    Some(filter) if keys.is_empty() && join_type == JoinType::Inner => {
+                        builder.cross_join(&right)?.filter(filter)?
+                    }
+                    Some(filter) => builder.join_with_filter(
+                        &right,
+                        join_type,
+                        &left_keys,
+                        &right_keys,
+                        filter,
+                    )?,
+                }
+                .build()
             }
             JoinConstraint::Using(idents) => {
                 let keys: Vec<&str> = idents.iter().map(|x| x.value.as_str()).collect();
@@ -310,11 +447,15 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         }
     }
 
-    fn create_relation(&self, relation: &TableFactor) -> Result<LogicalPlan> {
-        match relation {
-            TableFactor::Table { name, .. } => {
+    fn create_relation(
+        &self,
+        relation: &TableFactor,
+        relations: &mut Vec<Relation>,
+    ) -> Result<LogicalPlan> {
+        let (plan, name) = match relation {
+            TableFactor::Table { name, alias, .. } => {
                 let table_name = name.to_string();
-                match self.schema_provider.get_table_provider(&table_name) {
+                let plan = match self.schema_provider.get_table_provider(&table_name) {
                     Some(provider) => {
                         LogicalPlanBuilder::scan(&table_name, provider, None)?.build()
                     }
@@ -322,25 +463,158 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                         "no provider found for table {}",
                         name
                     ))),
-                }
+                }?;
+                let name = match alias {
+                    Some(alias) => alias.name.value.clone(),
+                    None => table_name,
+                };
+                (plan, Some(name))
+            }
+            TableFactor::Derived {
+                subquery, alias, ..
+            } => {
+                let plan = self.query_to_plan(subquery)?;
+                let plan = match alias {
+                    Some(alias) if !alias.columns.is_empty() => {
+                        self.apply_column_aliases(&plan, &alias.columns)
+                    }
+                    _ => Ok(plan),
+                }?;
+                (plan, alias.as_ref().map(|alias| alias.name.value.clone()))
             }
-            TableFactor::Derived { subquery, .. } => self.query_to_plan(subquery),
             TableFactor::NestedJoin(table_with_joins) => {
-                self.plan_table_with_joins(table_with_joins)
+                return self.plan_table_with_joins(table_with_joins, relations);
+            }
+        };
+        self.add_relation(plan, name, relations)
+    }
+
+    /// Add a relation to the relations of a FROM clause. The columns of a relation
+    /// that are also columns of a differently named relation of an enclosing query
+    /// are renamed to their qualified name, so that a subquery can reference both.
+    fn add_relation(
+        &self,
+        plan: LogicalPlan,
+        name: Option<String>,
+        relations: &mut Vec<Relation>,
+    ) -> Result<LogicalPlan> {
+        let columns = plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| {
+                let column = field.name().clone();
+                let planned = match &name {
+                    Some(name)
+                        if self.outer_relations.iter().any(|relation| {
+                            relation.name.as_ref() != Some(name)
+                                && relation.columns.iter().any(|(c, _)| *c == column)
+                        }) =>
+                    {
+                        format!("{}.{}", name, column)
+                    }
+                    _ => column.clone(),
+                };
+                (column, planned)
+            })
+            .collect::<Vec<_>>();
+        let plan = if columns.iter().any(|(column, planned)| column != planned) {
+            let exprs = columns
+                .iter()
+                .map(|(column, planned)| {
+                    if column == planned {
+                        col(column)
+                    } else {
+                        col(column).alias(planned)
+                    }
+                })
+                .collect::<Vec<_>>();
+            LogicalPlanBuilder::from(&plan).project(exprs)?.build()?
+        } else {
+            plan
+        };
+        relations.push(Relation { name, columns });
+        Ok(plan)
+    }
+
+    /// Returns the name of the column of the plan that a column name of the query,
+    /// possibly qualified by the name of a relation, references. An unqualified name
+    /// of no relation of the query is a column of an enclosing query.
+    fn column_name(&self, relation: Option<&str>, name: &str) -> Result<String> {
+        match relation {
+            Some(relation) => self
+                .relations
+                .iter()
+                .chain(self.outer_relations.iter())
+                .find(|r| r.name.as_deref() == Some(relation))
+                .ok_or_else(|| {
+                    DataFusionError::Plan(format!("Invalid qualifier '{}'", relation))
+                })?
+                .columns
+                .iter()
+                .find(|(column, _)| column == name)
+                .map(|(_, planned)| planned.clone())
+                .ok_or_else(|| {
+                    DataFusionError::Plan(format!(
+                        "Invalid identifier '{}.{}'",
+                        relation, name
+                    ))
+                }),
+            None => {
+                let mut planned = self
+                    .relations
+                    .iter()
+                    .flat_map(|r| r.columns.iter())
+                    .filter(|(column, _)| column == name)
+                    .map(|(_, planned)| planned)
+                    .collect::<Vec<_>>();
+                planned.sort();
+                planned.dedup();
+                match planned.as_slice() {
+                    [] => Ok(name.to_string()),
+                    [planned] => Ok(planned.to_string()),
+                    _ => Err(DataFusionError::Plan(format!(
+                        "Ambiguous reference to column '{}'",
+                        name
+                    ))),
+                }
             }
         }
     }
 
-    /// Generate a logic plan from an SQL select
-    fn select_to_plan(&self, select: &Select) -> Result<LogicalPlan> {
-        if select.having.is_some() {
-            return Err(DataFusionError::NotImplemented(
-                "HAVING is not implemented yet".to_string(),
-            ));
+    /// Name a column renamed to its qualified name after the column of its relation,
+    /// e.g. for the output of a subquery
+    fn unqualify_column(&self, expr: Expr) -> Expr {
+        let column = match &expr {
+            Expr::Column(name) => self
+                .relations
+                .iter()
+                .flat_map(|r| r.columns.iter())
+                .find(|(column, planned)| planned == name && column != planned)
+                .map(|(column, _)| column.clone()),
+            _ => None,
+        };
+        match column {
+            Some(column) => expr.alias(&column),
+            None => expr,
         }
+    }
 
-        let plans = self.plan_from_tables(&select.from)?;
+    /// Generate a logic plan from an SQL select
+    fn select_to_plan(&self, select: &Select) -> Result<LogicalPlan> {
+        let mut relations = vec![];
+        let plans = self.plan_from_tables(&select.from, &mut relations)?;
+        self.with_relations(relations)
+            .select_exprs_to_plan(select, plans)
+    }
 
+    /// Generate a logic plan from the expressions of an SQL select over the plans of
+    /// the relations of its FROM clause
+    fn select_exprs_to_plan(
+        &self,
+        select: &Select,
+        plans: Vec<LogicalPlan>,
+    ) -> Result<LogicalPlan> {
         let plan = match &select.selection {
             Some(predicate_expr) => {
                 // build join schema
@@ -375,9 +649,8 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                         }
                     }
                     if join_keys.is_empty() {
-                        return Err(DataFusionError::NotImplemented(
-                            "Cartesian joins are not supported".to_string(),
-                        ));
+                        left =
+                            LogicalPlanBuilder::from(&left).cross_join(right)?.build()?;
                     } else {
                         let left_keys: Vec<_> =
                             join_keys.iter().map(|(l, _)| *l).collect();
@@ -400,13 +673,11 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 }
             }
             None => {
-                if plans.len() == 1 {
-                    Ok(plans[0].clone())
-                } else {
-                    Err(DataFusionError::NotImplemented(
-                        "Cartesian joins are not supported".to_string(),
-                    ))
+                let mut left = plans[0].clone();
+                for right in &plans[1..] {
+                    left = LogicalPlanBuilder::from(&left).cross_join(right)?.build()?;
                 }
+                Ok(left)
             }
         };
         let plan = plan?;
@@ -414,17 +685,78 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         // The SELECT expressions, with wildcards expanded.
         let select_exprs = self.prepare_select_exprs(&plan, &select.projection)?;
 
-        // All of the aggregate expressions (deduplicated).
-        let aggr_exprs = find_aggregate_exprs(&select_exprs);
-
-        let (plan, select_exprs_post_aggr) =
-            if !select.group_by.is_empty() || !aggr_exprs.is_empty() {
-                self.aggregate(&plan, &select_exprs, &select.group_by, &aggr_exprs)?
-            } else {
-                (plan, select_exprs)
+        // Optionally the HAVING expression.
+        let having_expr = select
+            .having
+            .as_ref()
+            .map(|having_expr| self.sql_to_rex(having_expr, &plan.schema()))
+            .transpose()?;
+
+        // All of the aggregate expressions (deduplicated), including the ones only
+        // referenced by the HAVING expression.
+        let mut aggr_expr_haystack = select_exprs.clone();
+        aggr_expr_haystack.extend(having_expr.iter().cloned());
+        let aggr_exprs = find_aggregate_exprs(&aggr_expr_haystack);
+
+        let (plan, select_exprs_post_aggr) = if !select.group_by.is_empty()
+            || !aggr_exprs.is_empty()
+        {
+            let (plan, select_exprs_post_aggr, having_expr_post_aggr) = self.aggregate(
+                &plan,
+                &select_exprs,
+                &having_expr,
+                &select.group_by,
+                &aggr_exprs,
+            )?;
+            let plan = match having_expr_post_aggr {
+                Some(having_expr) => LogicalPlanBuilder::from(&plan)
+                    .filter(having_expr)?
+                    .build()?,
+                None => plan,
             };
+            (plan, select_exprs_post_aggr)
+        } else {
+            if having_expr.is_some() {
+                return Err(DataFusionError::Plan(
+                    "HAVING clause requires a GROUP BY clause or an aggregate function"
+                        .to_owned(),
+                ));
+            }
+            (plan, select_exprs)
+        };
+
+        // All of the window expressions (deduplicated).
+        let window_exprs = find_window_exprs(&select_exprs_post_aggr);
+
+        let (plan, select_exprs_post_window) = if !window_exprs.is_empty() {
+            self.window(&plan, window_exprs, &select_exprs_post_aggr)?
+        } else {
+            (plan, select_exprs_post_aggr)
+        };
 
-        self.project(&plan, select_exprs_post_aggr, false)
+        self.project(&plan, select_exprs_post_window, false)
+    }
+
+    /// Renames the columns of a plan, e.g. for a derived table `(...) AS t (a, b)`
+    fn apply_column_aliases(
+        &self,
+        plan: &LogicalPlan,
+        aliases: &[Ident],
+    ) -> Result<LogicalPlan> {
+        let fields = plan.schema().fields().clone();
+        if fields.len() != aliases.len() {
+            return Err(DataFusionError::Plan(format!(
+                "Derived table returns {} columns but {} column aliases are given",
+                fields.len(),
+                aliases.len(),
+            )));
+        }
+        let exprs = fields
+            .iter()
+            .zip(aliases.iter())
+            .map(|(field, alias)| col(field.name()).alias(&alias.value))
+            .collect::<Vec<_>>();
+        LogicalPlanBuilder::from(plan).project(exprs)?.build()
     }
 
     /// Returns the `Expr`'s corresponding to a SQL query's SELECT expressions.
@@ -443,6 +775,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             .collect::<Result<Vec<Expr>>>()?
             .iter()
             .flat_map(|expr| expand_wildcard(&expr, &input_schema))
+            .map(|expr| self.unqualify_column(expr))
             .collect::<Vec<Expr>>())
     }
 
@@ -478,13 +811,48 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         &self,
         input: &LogicalPlan,
         select_exprs: &Vec<Expr>,
+        having_expr: &Option<Expr>,
         group_by: &Vec<SQLExpr>,
         aggr_exprs: &Vec<Expr>,
-    ) -> Result<(LogicalPlan, Vec<Expr>)> {
-        let group_by_exprs = group_by
-            .iter()
-            .map(|e| self.sql_to_rex(e, &input.schema()))
-            .collect::<Result<Vec<Expr>>>()?;
+    ) -> Result<(LogicalPlan, Vec<Expr>, Option<Expr>)> {
+        // the grouping sets of the GROUP BY, the cross product of the sets of its
+        // items. Note that `GROUPING SETS (...)` is not supported by the SQL parser.
+        let mut grouping_sets: Vec<Vec<Expr>> = vec![vec![]];
+        let mut has_grouping_sets = false;
+        for e in group_by {
+            let item_sets = match e {
+                SQLExpr::Function(function) if function.over.is_none() => {
+                    match function.name.to_string().to_uppercase().as_str() {
+                        "ROLLUP" => {
+                            has_grouping_sets = true;
+                            rollup(self.grouping_set_exprs(&function.args, input)?)
+                        }
+                        "CUBE" => {
+                            has_grouping_sets = true;
+                            cube(self.grouping_set_exprs(&function.args, input)?)?
+                        }
+                        _ => vec![vec![self.sql_to_rex(e, &input.schema())?]],
+                    }
+                }
+                _ => vec![vec![self.sql_to_rex(e, &input.schema())?]],
+            };
+            grouping_sets = grouping_sets
+                .iter()
+                .flat_map(|set| {
+                    item_sets
+                        .iter()
+                        .map(move |item| set.iter().chain(item).cloned().collect())
+                })
+                .collect();
+        }
+
+        // the distinct grouping expressions of the sets
+        let mut group_by_exprs: Vec<Expr> = vec![];
+        for expr in grouping_sets.iter().flatten() {
+            if !has_grouping_sets || !group_by_exprs.contains(expr) {
+                group_by_exprs.push(expr.clone());
+            }
+        }
 
         let aggr_projection_exprs = group_by_exprs
             .iter()
@@ -492,9 +860,15 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             .cloned()
             .collect::<Vec<Expr>>();
 
-        let plan = LogicalPlanBuilder::from(&input)
-            .aggregate(group_by_exprs, aggr_exprs.clone())?
-            .build()?;
+        let plan = if has_grouping_sets {
+            LogicalPlanBuilder::from(&input)
+                .aggregate_grouping_sets(grouping_sets, aggr_exprs.clone())?
+                .build()?
+        } else {
+            LogicalPlanBuilder::from(&input)
+                .aggregate(group_by_exprs, aggr_exprs.clone())?
+                .build()?
+        };
 
         // After aggregation, these are all of the columns that will be
         // available to next phases of planning.
@@ -516,7 +890,58 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             ));
         }
 
-        Ok((plan, select_exprs_post_aggr))
+        // Rewrite the HAVING expression to use the columns produced by the
+        // aggregation.
+        let having_expr_post_aggr = match having_expr {
+            Some(having_expr) => {
+                let having_expr_post_aggr =
+                    rebase_expr(having_expr, &aggr_projection_exprs, input)?;
+                if !can_columns_satisfy_exprs(
+                    &column_exprs_post_aggr,
+                    &vec![having_expr_post_aggr.clone()],
+                )? {
+                    return Err(DataFusionError::Plan(
+                        "Having references non-aggregate values".to_owned(),
+                    ));
+                }
+                Some(having_expr_post_aggr)
+            }
+            None => None,
+        };
+
+        Ok((plan, select_exprs_post_aggr, having_expr_post_aggr))
+    }
+
+    /// The grouping expressions of a `ROLLUP` or `CUBE` of the GROUP BY
+    fn grouping_set_exprs(
+        &self,
+        args: &[SQLExpr],
+        input: &LogicalPlan,
+    ) -> Result<Vec<Expr>> {
+        args.iter()
+            .map(|e| self.sql_to_rex(e, &input.schema()))
+            .collect()
+    }
+
+    /// Wrap a plan in a window
+    fn window(
+        &self,
+        input: &LogicalPlan,
+        window_exprs: Vec<Expr>,
+        select_exprs: &Vec<Expr>,
+    ) -> Result<(LogicalPlan, Vec<Expr>)> {
+        let plan = LogicalPlanBuilder::from(&input)
+            .window(window_exprs.clone())?
+            .build()?;
+
+        // Rewrite the SELECT expression to use the columns produced by the
+        // window.
+        let select_exprs_post_window = select_exprs
+            .iter()
+            .map(|expr| rebase_expr(expr, &window_exprs, input))
+            .collect::<Result<Vec<Expr>>>()?;
+
+        Ok((plan, select_exprs_post_window))
     }
 
     /// Wrap a plan in a limit
@@ -546,24 +971,30 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             return Ok(plan.clone());
         }
 
-        let input_schema = plan.schema();
-        let order_by_rex: Result<Vec<Expr>> = order_by
+        let schema = plan.schema();
+        let order_by_rex = order_by
             .iter()
-            .map(|e| {
-                Ok(Expr::Sort {
-                    expr: Box::new(self.sql_to_rex(&e.expr, &input_schema).unwrap()),
-                    // by default asc
-                    asc: e.asc.unwrap_or(true),
-                    // by default nulls first to be consistent with spark
-                    nulls_first: e.nulls_first.unwrap_or(true),
-                })
-            })
-            .collect();
+            .map(|e| self.order_by_to_sort_expr(e, &schema))
+            .collect::<Result<Vec<Expr>>>()?;
+        self.validate_schema_satisfies_exprs(&schema, &order_by_rex)?;
 
-        LogicalPlanBuilder::from(&plan).sort(order_by_rex?)?.build()
+        LogicalPlanBuilder::from(&plan).sort(order_by_rex)?.build()
+    }
+
+    /// convert sql OrderByExpr to Expr::Sort
+    fn order_by_to_sort_expr(&self, e: &OrderByExpr, schema: &DFSchema) -> Result<Expr> {
+        Ok(Expr::Sort {
+            expr: Box::new(self.sql_expr_to_logical_expr(&e.expr, schema)?),
+            // by default asc
+            asc: e.asc.unwrap_or(true),
+            // by default nulls first to be consistent with spark
+            nulls_first: e.nulls_first.unwrap_or(true),
+        })
     }
 
     /// Validate the schema provides all of the columns referenced in the expressions.
+    /// The expressions of a subquery can also reference the columns of the enclosing
+    /// queries.
     fn validate_schema_satisfies_exprs(
         &self,
         schema: &DFSchema,
@@ -573,6 +1004,13 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             .iter()
             .try_for_each(|col| match col {
                 Expr::Column(name) => {
+                    let is_outer_column = match &self.outer_query_schema {
+                        Some(outer) => outer.field_with_unqualified_name(&name).is_ok(),
+                        None => false,
+                    };
+                    if is_outer_column {
+                        return Ok(());
+                    }
                     schema.field_with_unqualified_name(&name).map_err(|_| {
                         DataFusionError::Plan(format!(
                             "Invalid identifier '{}' for schema {}",
@@ -603,12 +1041,12 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
     /// Generate a relational expression from a SQL expression
     pub fn sql_to_rex(&self, sql: &SQLExpr, schema: &DFSchema) -> Result<Expr> {
-        let expr = self.sql_expr_to_logical_expr(sql)?;
+        let expr = self.sql_expr_to_logical_expr(sql, schema)?;
         self.validate_schema_satisfies_exprs(schema, &vec![expr.clone()])?;
         Ok(expr)
     }
 
-    fn sql_expr_to_logical_expr(&self, sql: &SQLExpr) -> Result<Expr> {
+    fn sql_expr_to_logical_expr(&self, sql: &SQLExpr, schema: &DFSchema) -> Result<Expr> {
         match sql {
             SQLExpr::Value(Value::Number(n)) => match n.parse::<i64>() {
                 Ok(n) => Ok(lit(n)),
@@ -623,7 +1061,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                     let var_names = vec![id.value.clone()];
                     Ok(Expr::ScalarVariable(var_names))
                 } else {
-                    Ok(Expr::Column(id.value.to_string()))
+                    Ok(Expr::Column(self.column_name(None, &id.value)?))
                 }
             }
 
@@ -635,6 +1073,10 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 }
                 if &var_names[0][0..1] == "@" {
                     Ok(Expr::ScalarVariable(var_names))
+                } else if var_names.len() == 2 {
+                    Ok(Expr::Column(
+                        self.column_name(Some(&var_names[0]), &var_names[1])?,
+                    ))
                 } else {
                     Err(DataFusionError::NotImplemented(format!(
                         "Unsupported compound identifier '{:?}'",
@@ -652,20 +1094,20 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 else_result,
             } => {
                 let expr = if let Some(e) = operand {
-                    Some(Box::new(self.sql_expr_to_logical_expr(e)?))
+                    Some(Box::new(self.sql_expr_to_logical_expr(e, schema)?))
                 } else {
                     None
                 };
                 let when_expr = conditions
                     .iter()
-                    .map(|e| self.sql_expr_to_logical_expr(e))
+                    .map(|e| self.sql_expr_to_logical_expr(e, schema))
                     .collect::<Result<Vec<_>>>()?;
                 let then_expr = results
                     .iter()
-                    .map(|e| self.sql_expr_to_logical_expr(e))
+                    .map(|e| self.sql_expr_to_logical_expr(e, schema))
                     .collect::<Result<Vec<_>>>()?;
                 let else_expr = if let Some(e) = else_result {
-                    Some(Box::new(self.sql_expr_to_logical_expr(e)?))
+                    Some(Box::new(self.sql_expr_to_logical_expr(e, schema)?))
                 } else {
                     None
                 };
@@ -685,7 +1127,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 ref expr,
                 ref data_type,
             } => Ok(Expr::Cast {
-                expr: Box::new(self.sql_expr_to_logical_expr(&expr)?),
+                expr: Box::new(self.sql_expr_to_logical_expr(&expr, schema)?),
                 data_type: convert_data_type(data_type)?,
             }),
 
@@ -697,19 +1139,25 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 data_type: convert_data_type(data_type)?,
             }),
 
-            SQLExpr::IsNull(ref expr) => {
-                Ok(Expr::IsNull(Box::new(self.sql_expr_to_logical_expr(expr)?)))
-            }
+            SQLExpr::IsNull(ref expr) => Ok(Expr::IsNull(Box::new(
+                self.sql_expr_to_logical_expr(expr, schema)?,
+            ))),
 
             SQLExpr::IsNotNull(ref expr) => Ok(Expr::IsNotNull(Box::new(
-                self.sql_expr_to_logical_expr(expr)?,
+                self.sql_expr_to_logical_expr(expr, schema)?,
             ))),
 
             SQLExpr::UnaryOp { ref op, ref expr } => match op {
                 UnaryOperator::Not => {
-                    Ok(Expr::Not(Box::new(self.sql_expr_to_logical_expr(expr)?)))
+                    match self.sql_expr_to_logical_expr(expr, schema)? {
+                        Expr::Exists { subquery, negated } => Ok(Expr::Exists {
+                            subquery,
+                            negated: !negated,
+                        }),
+                        expr => Ok(Expr::Not(Box::new(expr))),
+                    }
                 }
-                UnaryOperator::Plus => Ok(self.sql_expr_to_logical_expr(expr)?),
+                UnaryOperator::Plus => Ok(self.sql_expr_to_logical_expr(expr, schema)?),
                 UnaryOperator::Minus => {
                     match expr.as_ref() {
                         // optimization: if it's a number literal, we applly the negative operator
@@ -725,7 +1173,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                                 })?)),
                         },
                         // not a literal, apply negative operator on expression
-                        _ => Ok(Expr::Negative(Box::new(self.sql_expr_to_logical_expr(expr)?))),
+                        _ => Ok(Expr::Negative(Box::new(self.sql_expr_to_logical_expr(expr, schema)?))),
                     }
                 }
             },
@@ -736,10 +1184,23 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 ref low,
                 ref high,
             } => Ok(Expr::Between {
-                expr: Box::new(self.sql_expr_to_logical_expr(&expr)?),
+                expr: Box::new(self.sql_expr_to_logical_expr(&expr, schema)?),
+                negated: *negated,
+                low: Box::new(self.sql_expr_to_logical_expr(&low, schema)?),
+                high: Box::new(self.sql_expr_to_logical_expr(&high, schema)?),
+            }),
+
+            SQLExpr::InList {
+                ref expr,
+                ref list,
+                ref negated,
+            } => Ok(Expr::InList {
+                expr: Box::new(self.sql_expr_to_logical_expr(&expr, schema)?),
+                list: list
+                    .iter()
+                    .map(|e| self.sql_expr_to_logical_expr(e, schema))
+                    .collect::<Result<Vec<_>>>()?,
                 negated: *negated,
-                low: Box::new(self.sql_expr_to_logical_expr(&low)?),
-                high: Box::new(self.sql_expr_to_logical_expr(&high)?),
             }),
 
             SQLExpr::BinaryOp {
@@ -770,21 +1231,26 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 }?;
 
                 Ok(Expr::BinaryExpr {
-                    left: Box::new(self.sql_expr_to_logical_expr(&left)?),
+                    left: Box::new(self.sql_expr_to_logical_expr(&left, schema)?),
                     op: operator,
-                    right: Box::new(self.sql_expr_to_logical_expr(&right)?),
+                    right: Box::new(self.sql_expr_to_logical_expr(&right, schema)?),
                 })
             }
 
             SQLExpr::Function(function) => {
                 let name: String = function.name.to_string();
 
-                // first, scalar built-in
+                // first, window functions
+                if let Some(window) = &function.over {
+                    return self.window_function_to_expr(&name, function, window, schema);
+                }
+
+                // next, scalar built-in
                 if let Ok(fun) = functions::BuiltinScalarFunction::from_str(&name) {
                     let args = function
                         .args
                         .iter()
-                        .map(|a| self.sql_expr_to_logical_expr(a))
+                        .map(|a| self.sql_expr_to_logical_expr(a, schema))
                         .collect::<Result<Vec<Expr>>>()?;
 
                     return Ok(Expr::ScalarFunction { fun, args });
@@ -792,28 +1258,14 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
                 // next, aggregate built-ins
                 if let Ok(fun) = aggregates::AggregateFunction::from_str(&name) {
-                    let args = if fun == aggregates::AggregateFunction::Count {
-                        function
-                            .args
-                            .iter()
-                            .map(|a| match a {
-                                SQLExpr::Value(Value::Number(_)) => Ok(lit(1_u8)),
-                                SQLExpr::Wildcard => Ok(lit(1_u8)),
-                                _ => self.sql_expr_to_logical_expr(a),
-                            })
-                            .collect::<Result<Vec<Expr>>>()?
-                    } else {
-                        function
-                            .args
-                            .iter()
-                            .map(|a| self.sql_expr_to_logical_expr(a))
-                            .collect::<Result<Vec<Expr>>>()?
-                    };
+                    let args = self.aggregate_fn_to_args(&fun, &function.args, schema)?;
 
+                    // the parser does not support an ORDER BY clause in the arguments
                     return Ok(Expr::AggregateFunction {
                         fun,
                         distinct: function.distinct,
                         args,
+                        order_by: vec![],
                     });
                 };
 
@@ -823,7 +1275,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                         let args = function
                             .args
                             .iter()
-                            .map(|a| self.sql_expr_to_logical_expr(a))
+                            .map(|a| self.sql_expr_to_logical_expr(a, schema))
                             .collect::<Result<Vec<Expr>>>()?;
 
                         Ok(Expr::ScalarUDF { fun: fm, args })
@@ -833,7 +1285,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                             let args = function
                                 .args
                                 .iter()
-                                .map(|a| self.sql_expr_to_logical_expr(a))
+                                .map(|a| self.sql_expr_to_logical_expr(a, schema))
                                 .collect::<Result<Vec<Expr>>>()?;
 
                             Ok(Expr::AggregateUDF { fun: fm, args })
@@ -846,7 +1298,44 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 }
             }
 
-            SQLExpr::Nested(e) => self.sql_expr_to_logical_expr(&e),
+            SQLExpr::Nested(e) => self.sql_expr_to_logical_expr(&e, schema),
+
+            SQLExpr::Exists(subquery) => Ok(Expr::Exists {
+                subquery: Subquery::new(self.subquery_to_plan(subquery, schema)?),
+                negated: false,
+            }),
+
+            SQLExpr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                let plan = self.subquery_to_plan(subquery, schema)?;
+                let num_columns = plan.schema().fields().len();
+                if num_columns != 1 {
+                    return Err(DataFusionError::Plan(format!(
+                        "IN subquery must return exactly one column, found {}",
+                        num_columns
+                    )));
+                }
+                Ok(Expr::InSubquery {
+                    expr: Box::new(self.sql_expr_to_logical_expr(expr, schema)?),
+                    subquery: Subquery::new(plan),
+                    negated: *negated,
+                })
+            }
+
+            SQLExpr::Subquery(subquery) => {
+                let plan = self.subquery_to_plan(subquery, schema)?;
+                let num_columns = plan.schema().fields().len();
+                if num_columns != 1 {
+                    return Err(DataFusionError::Plan(format!(
+                        "Scalar subquery must return exactly one column, found {}",
+                        num_columns
+                    )));
+                }
+                Ok(Expr::ScalarSubquery(Subquery::new(plan)))
+            }
 
             _ => Err(DataFusionError::NotImplemented(format!(
                 "Unsupported ast node {:?} in sqltorel",
@@ -854,6 +1343,92 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             ))),
         }
     }
+
+    /// Generate a relational expression from a function call with an `OVER` clause
+    fn window_function_to_expr(
+        &self,
+        name: &str,
+        function: &Function,
+        window: &WindowSpec,
+        schema: &DFSchema,
+    ) -> Result<Expr> {
+        if function.distinct {
+            return Err(DataFusionError::NotImplemented(format!(
+                "DISTINCT is not supported in window function {}",
+                name
+            )));
+        }
+
+        let partition_by = window
+            .partition_by
+            .iter()
+            .map(|e| self.sql_expr_to_logical_expr(e, schema))
+            .collect::<Result<Vec<Expr>>>()?;
+        let order_by = window
+            .order_by
+            .iter()
+            .map(|e| self.order_by_to_sort_expr(e, schema))
+            .collect::<Result<Vec<Expr>>>()?;
+        let window_frame: Option<WindowFrame> = window
+            .window_frame
+            .as_ref()
+            .map(|window_frame| window_frame.clone().try_into())
+            .transpose()?;
+
+        let fun = match window_functions::WindowFunction::from_str(name) {
+            Ok(fun) => fun,
+            Err(e) => {
+                return match self.schema_provider.get_aggregate_meta(name) {
+                    Some(_) => Err(DataFusionError::NotImplemented(format!(
+                        "User-defined aggregate function '{}' cannot be used as a window function",
+                        name
+                    ))),
+                    None => Err(e),
+                }
+            }
+        };
+
+        let args = match &fun {
+            window_functions::WindowFunction::AggregateFunction(aggr_fun) => {
+                self.aggregate_fn_to_args(aggr_fun, &function.args, schema)?
+            }
+            window_functions::WindowFunction::BuiltInWindowFunction(_) => function
+                .args
+                .iter()
+                .map(|a| self.sql_expr_to_logical_expr(a, schema))
+                .collect::<Result<Vec<Expr>>>()?,
+        };
+
+        Ok(Expr::WindowFunction {
+            fun,
+            args,
+            partition_by,
+            order_by,
+            window_frame,
+        })
+    }
+
+    /// Generate the argument expressions of a built-in aggregate function
+    fn aggregate_fn_to_args(
+        &self,
+        fun: &aggregates::AggregateFunction,
+        args: &[SQLExpr],
+        schema: &DFSchema,
+    ) -> Result<Vec<Expr>> {
+        if *fun == aggregates::AggregateFunction::Count {
+            args.iter()
+                .map(|a| match a {
+                    SQLExpr::Value(Value::Number(_)) => Ok(lit(1_u8)),
+                    SQLExpr::Wildcard => Ok(lit(1_u8)),
+                    _ => self.sql_expr_to_logical_expr(a, schema),
+                })
+                .collect::<Result<Vec<Expr>>>()
+        } else {
+            args.iter()
+                .map(|a| self.sql_expr_to_logical_expr(a, schema))
+                .collect::<Result<Vec<Expr>>>()
+        }
+    }
 }
 
 /// Remove join expressions from a filter expression
@@ -889,39 +1464,50 @@ fn remove_join_expressions(
     }
 }
 
-/// Parse equijoin ON condition which could be a single Eq or multiple conjunctive Eqs
+/// Splits the conjuncts of a join ON condition into the join keys, the equalities of a
+/// column of the `left` side with a column of the `right` side, and the other predicates
 ///
 /// Examples
 ///
 /// foo = bar
-/// foo = bar AND bar = baz AND ...
+/// foo = bar AND baz < qux AND ...
 ///
-fn extract_join_keys(expr: &Expr, accum: &mut Vec<(String, String)>) -> Result<()> {
+fn split_join_condition(
+    expr: &Expr,
+    left: &DFSchema,
+    right: &DFSchema,
+    keys: &mut Vec<(String, String)>,
+    filters: &mut Vec<Expr>,
+) {
     match expr {
-        Expr::BinaryExpr { left, op, right } => match op {
-            Operator::Eq => match (left.as_ref(), right.as_ref()) {
-                (Expr::Column(l), Expr::Column(r)) => {
-                    accum.push((l.to_owned(), r.to_owned()));
-                    Ok(())
-                }
-                other => Err(DataFusionError::SQL(ParserError(format!(
-                    "Unsupported expression '{:?}' in JOIN condition",
-                    other
-                )))),
-            },
-            Operator::And => {
-                extract_join_keys(left, accum)?;
-                extract_join_keys(right, accum)
+        Expr::BinaryExpr {
+            left: l,
+            op: Operator::And,
+            right: r,
+        } => {
+            split_join_condition(l, left, right, keys, filters);
+            split_join_condition(r, left, right, keys, filters);
+        }
+        Expr::BinaryExpr {
+            left: l,
+            op: Operator::Eq,
+            right: r,
+        } => match (l.as_ref(), r.as_ref()) {
+            (Expr::Column(l), Expr::Column(r))
+                if left.field_with_unqualified_name(l).is_ok()
+                    && right.field_with_unqualified_name(r).is_ok() =>
+            {
+                keys.push((l.to_owned(), r.to_owned()))
             }
-            other => Err(DataFusionError::SQL(ParserError(format!(
-                "Unsupported expression '{:?}' in JOIN condition",
-                other
-            )))),
+            (Expr::Column(l), Expr::Column(r))
+                if left.field_with_unqualified_name(r).is_ok()
+                    && right.field_with_unqualified_name(l).is_ok() =>
+            {
+                keys.push((r.to_owned(), l.to_owned()))
+            }
+            _ => filters.push(expr.clone()),
         },
-        other => Err(DataFusionError::SQL(ParserError(format!(
-            "Unsupported expression '{:?}' in JOIN condition",
-            other
-        )))),
+        _ => filters.push(expr.clone()),
     }
 }
 
@@ -972,6 +1558,7 @@ pub fn convert_data_type(sql: &SQLDataType) -> Result<DataType> {
 mod tests {
     use super::*;
     use crate::datasource::empty::EmptyTable;
+    use crate::optimizer::utils;
     use crate::{logical_plan::create_udf, sql::parser::DFParser};
     use functions::ScalarFunctionImplementation;
 
@@ -1412,6 +1999,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn select_aggregate_with_rollup() {
+        quick_test(
+            "SELECT state, age, MIN(salary) FROM person GROUP BY ROLLUP(state, age)",
+            "Aggregate: groupBy=[[#state, #age]], groupingSets=[[#state, #age], [#state], []], aggr=[[MIN(#salary)]]\
+             \n  TableScan: person projection=None",
+        );
+    }
+
+    #[test]
+    fn select_aggregate_with_cube_and_grouping() {
+        quick_test(
+            "SELECT id, state, age, GROUPING(state, age), MIN(salary) FROM person \
+             GROUP BY id, CUBE(state, age)",
+            "Aggregate: groupBy=[[#id, #state, #age]], groupingSets=[[#id, #state, #age], [#id, #state], [#id, #age], [#id]], aggr=[[GROUPING(#state, #age), MIN(#salary)]]\
+             \n  TableScan: person projection=None",
+        );
+    }
+
+    #[test]
+    fn select_aggregate_with_grouping_of_non_grouping_expression() {
+        let sql = "SELECT state, GROUPING(age) FROM person GROUP BY ROLLUP(state)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"The arguments of GROUPING must be grouping expressions, got #age\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn select_simple_aggregate_with_groupby_non_column_expression_selected_and_resolvable(
     ) {
@@ -1570,6 +2186,16 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn select_where_in_list() {
+        let sql =
+            "SELECT id FROM person WHERE state IN ('CO', 'NY') AND age NOT IN (20, 30)";
+        let expected = "Projection: #id\
+            \n  Filter: #state IN (Utf8(\"CO\"), Utf8(\"NY\")) And #age NOT IN (Int64(20), Int64(30))\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
     #[test]
     fn select_order_by() {
         let sql = "SELECT id FROM person ORDER BY id";
@@ -1644,6 +2270,56 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn select_aggregate_with_having() {
+        let sql =
+            "SELECT state, MAX(age) FROM person GROUP BY state HAVING MAX(age) > 30";
+        let expected = "Filter: #MAX(age) Gt Int64(30)\
+                        \n  Aggregate: groupBy=[[#state]], aggr=[[MAX(#age)]]\
+                        \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_aggregate_with_having_on_group_by_column() {
+        let sql = "SELECT state, MAX(age) FROM person GROUP BY state HAVING state = 'CO'";
+        let expected = "Filter: #state Eq Utf8(\"CO\")\
+                        \n  Aggregate: groupBy=[[#state]], aggr=[[MAX(#age)]]\
+                        \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_aggregate_with_having_not_in_select() {
+        let sql =
+            "SELECT state, SUM(age) FROM person GROUP BY state HAVING COUNT(*) > 10";
+        let expected = "Projection: #state, #SUM(age)\
+                        \n  Filter: #COUNT(UInt8(1)) Gt Int64(10)\
+                        \n    Aggregate: groupBy=[[#state]], aggr=[[SUM(#age), COUNT(UInt8(1))]]\
+                        \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_aggregate_with_having_non_aggregate_column() {
+        let sql = "SELECT state, MAX(age) FROM person GROUP BY state HAVING age > 30";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Having references non-aggregate values\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn select_having_without_aggregate() {
+        let sql = "SELECT id FROM person HAVING id > 1";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"HAVING clause requires a GROUP BY clause or an aggregate function\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn select_7480_1() {
         let sql = "SELECT c1, MIN(c12) FROM aggregate_test_100 GROUP BY c1, c13";
@@ -1698,6 +2374,20 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn create_external_table_json() {
+        let sql = "CREATE EXTERNAL TABLE t(c1 int) STORED AS NDJSON LOCATION 'foo.json'";
+        let expected = "CreateExternalTable: \"t\"";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn create_external_table_json_no_schema() {
+        let sql = "CREATE EXTERNAL TABLE t STORED AS NDJSON LOCATION 'foo.json'";
+        let expected = "CreateExternalTable: \"t\"";
+        quick_test(sql, expected);
+    }
+
     #[test]
     fn equijoin_explicit_syntax() {
         let sql = "SELECT id, order_id \
@@ -1726,6 +2416,332 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn full_outer_join() {
+        let sql = "SELECT id, order_id \
+            FROM person \
+            FULL OUTER JOIN orders \
+            ON id = customer_id";
+        let expected = "Projection: #id, #order_id\
+        \n  Join: id = customer_id\
+        \n    TableScan: person projection=None\
+        \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+        assert_eq!(Some(JoinType::Full), join_type(sql));
+    }
+
+    #[test]
+    fn left_join_with_filter() {
+        let sql = "SELECT id, order_id \
+            FROM person \
+            LEFT JOIN orders \
+            ON id = customer_id AND qty > 10";
+        let expected = "Projection: #id, #order_id\
+        \n  Join: id = customer_id, filter=#qty Gt Int64(10)\
+        \n    TableScan: person projection=None\
+        \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+        assert_eq!(Some(JoinType::Left), join_type(sql));
+    }
+
+    #[test]
+    fn join_without_keys() {
+        let sql = "SELECT id, order_id \
+            FROM person \
+            JOIN orders \
+            ON id < customer_id";
+        let expected = "Projection: #id, #order_id\
+        \n  Filter: #id Lt #customer_id\
+        \n    CrossJoin\
+        \n      TableScan: person projection=None\
+        \n      TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn cross_join() {
+        let sql = "SELECT id, order_id FROM person CROSS JOIN orders";
+        let expected = "Projection: #id, #order_id\
+        \n  CrossJoin\
+        \n    TableScan: person projection=None\
+        \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn in_subquery() {
+        let sql = "SELECT id FROM person \
+            WHERE state = 'CO' AND id IN (SELECT customer_id FROM orders)";
+        let expected = "Projection: #id\
+            \n  Filter: #state Eq Utf8(\"CO\") And #id IN (<subquery>)\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+        let expected = "Projection: #customer_id\
+            \n  TableScan: orders projection=None";
+        assert_eq!(expected, format!("{:?}", subquery_plan(sql)));
+
+        let sql =
+            "SELECT id FROM person WHERE id NOT IN (SELECT customer_id FROM orders)";
+        let expected = "Projection: #id\
+            \n  Filter: #id NOT IN (<subquery>)\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn in_subquery_with_many_columns() {
+        let sql = "SELECT id FROM person \
+            WHERE id IN (SELECT customer_id, order_id FROM orders)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"IN subquery must return exactly one column, found 2\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn correlated_exists() {
+        let sql = "SELECT id FROM person \
+            WHERE EXISTS (SELECT * FROM orders WHERE customer_id = id AND qty > 1)";
+        let expected = "Projection: #id\
+            \n  Filter: EXISTS (<subquery>)\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+        let expected = "Projection: #order_id, #customer_id, #o_item_id, #qty, #price\
+            \n  Filter: #customer_id Eq #id And #qty Gt Int64(1)\
+            \n    TableScan: orders projection=None";
+        assert_eq!(expected, format!("{:?}", subquery_plan(sql)));
+
+        let sql = "SELECT id FROM person \
+            WHERE NOT EXISTS (SELECT 1 FROM orders WHERE id = customer_id)";
+        let expected = "Projection: #id\
+            \n  Filter: NOT EXISTS (<subquery>)\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn uncorrelated_exists() {
+        let sql = "SELECT id FROM person WHERE EXISTS (SELECT * FROM orders)";
+        let expected = "Projection: #id\
+            \n  Filter: EXISTS (<subquery>)\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn subquery_column_does_not_exist() {
+        let sql = "SELECT id FROM person \
+            WHERE EXISTS (SELECT * FROM orders WHERE customer_id = doesnotexist)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert!(format!("{:?}", err)
+            .starts_with("Plan(\"Invalid identifier \\\'doesnotexist\\\'"));
+    }
+
+    #[test]
+    fn subquery_columns_shadow_outer_columns() {
+        // `id` of the subquery is not a reference to `person`
+        let sql = "SELECT id FROM person \
+            WHERE age IN (SELECT age FROM (SELECT id, age FROM person) WHERE id > 1)";
+        let expected = "Projection: #age\
+            \n  Filter: #id Gt Int64(1)\
+            \n    Projection: #id, #age\
+            \n      TableScan: person projection=None";
+        assert_eq!(expected, format!("{:?}", subquery_plan(sql)));
+    }
+
+    #[test]
+    fn qualified_column_names() {
+        let sql = "SELECT p.id, o.order_id \
+            FROM person AS p \
+            JOIN orders o ON p.id = o.customer_id \
+            WHERE p.age > 20";
+        let expected = "Projection: #id, #order_id\
+            \n  Filter: #age Gt Int64(20)\
+            \n    Join: id = customer_id\
+            \n      TableScan: person projection=None\
+            \n      TableScan: orders projection=None";
+        quick_test(sql, expected);
+
+        let sql = "SELECT person.id FROM person AS p";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Invalid qualifier \\\'person\\\'\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn subquery_with_qualified_outer_columns() {
+        // `q` renames the columns it shares with `p` for the subquery to reference both
+        let sql = "SELECT id FROM person p \
+            WHERE EXISTS (SELECT q.id FROM person AS q WHERE q.state = p.state AND age > p.age)";
+        let expected = "Projection: #q.id AS id\
+            \n  Filter: #q.state Eq #state And #q.age Gt #age\
+            \n    Projection: #id AS q.id, #first_name AS q.first_name, #last_name AS q.last_name, #age AS q.age, #state AS q.state, #salary AS q.salary, #birth_date AS q.birth_date\
+            \n      TableScan: person projection=None";
+        assert_eq!(expected, format!("{:?}", subquery_plan(sql)));
+    }
+
+    #[test]
+    fn scalar_subquery() {
+        let sql = "SELECT id, (SELECT MAX(qty) FROM orders WHERE customer_id = id) \
+            FROM person";
+        let expected = "Projection: #id, (<subquery>)\
+            \n  TableScan: person projection=None";
+        quick_test(sql, expected);
+        let expected = "Aggregate: groupBy=[[]], aggr=[[MAX(#qty)]]\
+            \n  Filter: #customer_id Eq #id\
+            \n    TableScan: orders projection=None";
+        assert_eq!(expected, format!("{:?}", subquery_plan(sql)));
+    }
+
+    #[test]
+    fn scalar_subquery_with_many_columns() {
+        let sql = "SELECT id FROM person \
+            WHERE age > (SELECT MIN(qty), MAX(qty) FROM orders)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Scalar subquery must return exactly one column, found 2\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn derived_table_with_column_aliases() {
+        let sql = "SELECT a FROM (SELECT id, first_name FROM person) AS p (a, b)";
+        let expected = "Projection: #a\
+            \n  Projection: #id AS a, #first_name AS b\
+            \n    Projection: #id, #first_name\
+            \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+
+        let sql = "SELECT a FROM (SELECT id, first_name FROM person) AS p (a)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Derived table returns 2 columns but 1 column aliases are given\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn union_all() {
+        let sql = "SELECT order_id FROM orders UNION ALL SELECT order_id FROM orders";
+        let expected = "Union\
+            \n  Projection: #order_id\
+            \n    TableScan: orders projection=None\
+            \n  Projection: #order_id\
+            \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn union_all_of_many_queries() {
+        let sql = "SELECT order_id FROM orders \
+                   UNION ALL SELECT customer_id FROM orders \
+                   UNION ALL SELECT id FROM person";
+        let expected = "Union\
+            \n  Projection: #order_id\
+            \n    TableScan: orders projection=None\
+            \n  Projection: #customer_id AS order_id\
+            \n    Projection: #customer_id\
+            \n      TableScan: orders projection=None\
+            \n  Projection: #id AS order_id\
+            \n    Projection: #id\
+            \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn union_distinct() {
+        let sql = "SELECT order_id, qty FROM orders UNION SELECT id, age FROM person";
+        let expected = "Aggregate: groupBy=[[#order_id, #qty]], aggr=[[]]\
+            \n  Union\
+            \n    Projection: #order_id, #qty\
+            \n      TableScan: orders projection=None\
+            \n    Projection: #id AS order_id, #age AS qty\
+            \n      Projection: #id, #age\
+            \n        TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn union_with_type_coercion() {
+        let sql = "SELECT qty FROM orders UNION ALL SELECT salary FROM person";
+        let expected = "Union\
+            \n  Projection: CAST(#qty AS Float64) AS qty\
+            \n    Projection: #qty\
+            \n      TableScan: orders projection=None\
+            \n  Projection: #salary AS qty\
+            \n    Projection: #salary\
+            \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn union_with_different_column_count() {
+        let sql = "SELECT order_id FROM orders UNION ALL SELECT id, age FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Set operations require the same number of columns on both sides, found 1 and 2\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn union_with_incompatible_types() {
+        let sql = "SELECT order_id FROM orders UNION ALL SELECT first_name FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Column order_id has incompatible types UInt32 and Utf8 in set operation\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn intersect() {
+        let sql = "SELECT order_id FROM orders INTERSECT SELECT customer_id FROM orders";
+        let expected = "Projection: #order_id\
+            \n  Filter: #__set_min_side Eq UInt8(1) And #__set_max_side Eq UInt8(2)\
+            \n    Aggregate: groupBy=[[#order_id]], aggr=[[MIN(#__set_side) AS __set_min_side, MAX(#__set_side) AS __set_max_side]]\
+            \n      Union\
+            \n        Projection: #order_id, UInt8(1) AS __set_side\
+            \n          Projection: #order_id\
+            \n            TableScan: orders projection=None\
+            \n        Projection: #order_id, UInt8(2) AS __set_side\
+            \n          Projection: #customer_id AS order_id\
+            \n            Projection: #customer_id\
+            \n              TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn except() {
+        let sql = "SELECT order_id FROM orders EXCEPT SELECT customer_id FROM orders";
+        let expected = "Projection: #order_id\
+            \n  Filter: #__set_max_side Eq UInt8(1)\
+            \n    Aggregate: groupBy=[[#order_id]], aggr=[[MAX(#__set_side) AS __set_max_side]]\
+            \n      Union\
+            \n        Projection: #order_id, UInt8(1) AS __set_side\
+            \n          Projection: #order_id\
+            \n            TableScan: orders projection=None\
+            \n        Projection: #order_id, UInt8(2) AS __set_side\
+            \n          Projection: #customer_id AS order_id\
+            \n            Projection: #customer_id\
+            \n              TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn intersect_all() {
+        let sql = "SELECT order_id FROM orders INTERSECT ALL SELECT id FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "NotImplemented(\"INTERSECT ALL is not supported\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn select_typedstring() {
         let sql = "SELECT date '2020-12-10' AS date FROM person";
@@ -1734,6 +2750,48 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn select_window_function() {
+        let sql =
+            "SELECT id, ROW_NUMBER() OVER (PARTITION BY state ORDER BY age DESC) FROM person";
+        let expected = "Projection: #id, #ROW_NUMBER() PARTITION BY [state] ORDER BY [age DESC NULLS FIRST]\
+            \n  Window: windowExpr=[[ROW_NUMBER() PARTITION BY [#state] ORDER BY [#age DESC NULLS FIRST]]]\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_window_function_with_frame() {
+        let sql = "SELECT SUM(age) OVER (ORDER BY id ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING) AS s \
+            FROM person";
+        let expected = "Projection: #SUM(age) ORDER BY [id ASC NULLS FIRST] ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING AS s\
+            \n  Window: windowExpr=[[SUM(#age) ORDER BY [#id ASC NULLS FIRST] ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING]]\
+            \n    TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_window_function_over_aggregate() {
+        let sql =
+            "SELECT state, RANK() OVER (ORDER BY MAX(age)) FROM person GROUP BY state";
+        let expected = "Projection: #state, #RANK() ORDER BY [MAX(age) ASC NULLS FIRST]\
+            \n  Window: windowExpr=[[RANK() ORDER BY [#MAX(age) ASC NULLS FIRST]]]\
+            \n    Aggregate: groupBy=[[#state]], aggr=[[MAX(#age)]]\
+            \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_window_function_invalid_frame() {
+        let sql = "SELECT ROW_NUMBER() OVER (ROWS BETWEEN UNBOUNDED FOLLOWING AND CURRENT ROW) \
+            FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Invalid window frame: start bound cannot be unbounded following\")",
+            format!("{:?}", err)
+        );
+    }
+
     fn logical_plan(sql: &str) -> Result<LogicalPlan> {
         let planner = SqlToRel::new(&MockContextProvider {});
         let result = DFParser::parse_sql(&sql);
@@ -1741,6 +2799,43 @@ mod tests {
         planner.statement_to_plan(&ast[0])
     }
 
+    /// Returns the type of the first join found in the logical plan of a query
+    fn join_type(sql: &str) -> Option<JoinType> {
+        fn find_join_type(plan: &LogicalPlan) -> Option<JoinType> {
+            match plan {
+                LogicalPlan::Join { join_type, .. } => Some(*join_type),
+                _ => utils::inputs(plan).into_iter().find_map(find_join_type),
+            }
+        }
+        find_join_type(&logical_plan(sql).unwrap())
+    }
+
+    /// Returns the plan of the first subquery found in the logical plan of a query
+    fn subquery_plan(sql: &str) -> LogicalPlan {
+        fn find_subquery(expr: &Expr) -> Option<LogicalPlan> {
+            match expr {
+                Expr::Exists { subquery, .. }
+                | Expr::InSubquery { subquery, .. }
+                | Expr::ScalarSubquery(subquery) => Some(subquery.plan.as_ref().clone()),
+                _ => utils::expr_sub_expressions(expr)
+                    .unwrap()
+                    .iter()
+                    .find_map(find_subquery),
+            }
+        }
+        fn find_subquery_in_plan(plan: &LogicalPlan) -> Option<LogicalPlan> {
+            utils::expressions(plan)
+                .iter()
+                .find_map(find_subquery)
+                .or_else(|| {
+                    utils::inputs(plan)
+                        .into_iter()
+                        .find_map(find_subquery_in_plan)
+                })
+        }
+        find_subquery_in_plan(&logical_plan(sql).unwrap()).unwrap()
+    }
+
     /// Create logical plan, write with formatter, compare to expected output
     fn quick_test(sql: &str, expected: &str) {
         let plan = logical_plan(sql).unwrap();
@@ -42,6 +42,14 @@ pub(crate) fn find_aggregate_exprs(exprs: &Vec<Expr>) -> Vec<Expr> {
     })
 }
 
+/// Collect all deeply nested `Expr::WindowFunction`. They are returned in order
+/// of occurrence (depth first), with duplicates omitted.
+pub(crate) fn find_window_exprs(exprs: &Vec<Expr>) -> Vec<Expr> {
+    find_exprs_in_exprs(exprs, &|nested_expr| {
+        matches!(nested_expr, Expr::WindowFunction { .. })
+    })
+}
+
 /// Collect all deeply nested `Expr::Column`'s. They are returned in order of
 /// appearance (depth first), with duplicates omitted.
 pub(crate) fn find_column_exprs(exprs: &Vec<Expr>) -> Vec<Expr> {
@@ -77,8 +85,24 @@ where
         vec![expr.clone()]
     } else {
         match expr {
-            Expr::AggregateFunction { args, .. } => find_exprs_in_exprs(&args, test_fn),
+            Expr::AggregateFunction { args, order_by, .. } => {
+                let mut matches = find_exprs_in_exprs(&args, test_fn);
+                matches.extend(find_exprs_in_exprs(&order_by, test_fn));
+                matches
+            }
             Expr::AggregateUDF { args, .. } => find_exprs_in_exprs(&args, test_fn),
+            Expr::WindowFunction {
+                args,
+                partition_by,
+                order_by,
+                ..
+            } => {
+                let mut matches = vec![];
+                matches.extend(find_exprs_in_exprs(&args, test_fn));
+                matches.extend(find_exprs_in_exprs(&partition_by, test_fn));
+                matches.extend(find_exprs_in_exprs(&order_by, test_fn));
+                matches
+            }
             Expr::Alias(nested_expr, _) => {
                 find_exprs_in_expr(nested_expr.as_ref(), test_fn)
             }
@@ -94,6 +118,16 @@ where
                 matches.extend(find_exprs_in_expr(high.as_ref(), test_fn));
                 matches
             }
+            Expr::InList {
+                expr: nested_expr,
+                list,
+                ..
+            } => {
+                let mut matches = vec![];
+                matches.extend(find_exprs_in_expr(nested_expr.as_ref(), test_fn));
+                matches.extend(find_exprs_in_exprs(&list, test_fn));
+                matches
+            }
             Expr::BinaryExpr { left, right, .. } => {
                 let mut matches = vec![];
                 matches.extend(find_exprs_in_expr(left.as_ref(), test_fn));
@@ -143,11 +177,17 @@ where
             Expr::Sort {
                 expr: nested_expr, ..
             } => find_exprs_in_expr(nested_expr.as_ref(), test_fn),
+            Expr::InSubquery {
+                expr: nested_expr, ..
+            } => find_exprs_in_expr(nested_expr.as_ref(), test_fn),
 
-            // These expressions don't nest other expressions.
+            // These expressions don't nest other expressions, the expressions of a
+            // subquery belong to its own plan.
             Expr::Column(_)
             | Expr::Literal(_)
             | Expr::ScalarVariable(_)
+            | Expr::Exists { .. }
+            | Expr::ScalarSubquery(_)
             | Expr::Wildcard => vec![],
         }
     };
@@ -247,6 +287,7 @@ where
                 fun,
                 args,
                 distinct,
+                order_by,
             } => Ok(Expr::AggregateFunction {
                 fun: fun.clone(),
                 args: args
@@ -254,6 +295,10 @@ where
                     .map(|e| clone_with_replacement(e, replacement_fn))
                     .collect::<Result<Vec<Expr>>>()?,
                 distinct: *distinct,
+                order_by: order_by
+                    .iter()
+                    .map(|e| clone_with_replacement(e, replacement_fn))
+                    .collect::<Result<Vec<Expr>>>()?,
             }),
             Expr::AggregateUDF { fun, args } => Ok(Expr::AggregateUDF {
                 fun: fun.clone(),
@@ -262,6 +307,28 @@ where
                     .map(|e| clone_with_replacement(e, replacement_fn))
                     .collect::<Result<Vec<Expr>>>()?,
             }),
+            Expr::WindowFunction {
+                fun,
+                args,
+                partition_by,
+                order_by,
+                window_frame,
+            } => Ok(Expr::WindowFunction {
+                fun: fun.clone(),
+                args: args
+                    .iter()
+                    .map(|e| clone_with_replacement(e, replacement_fn))
+                    .collect::<Result<Vec<Expr>>>()?,
+                partition_by: partition_by
+                    .iter()
+                    .map(|e| clone_with_replacement(e, replacement_fn))
+                    .collect::<Result<Vec<Expr>>>()?,
+                order_by: order_by
+                    .iter()
+                    .map(|e| clone_with_replacement(e, replacement_fn))
+                    .collect::<Result<Vec<Expr>>>()?,
+                window_frame: *window_frame,
+            }),
             Expr::Alias(nested_expr, alias_name) => Ok(Expr::Alias(
                 Box::new(clone_with_replacement(&**nested_expr, replacement_fn)?),
                 alias_name.clone(),
@@ -277,6 +344,18 @@ where
                 low: Box::new(clone_with_replacement(&**low, replacement_fn)?),
                 high: Box::new(clone_with_replacement(&**high, replacement_fn)?),
             }),
+            Expr::InList {
+                expr: nested_expr,
+                list,
+                negated,
+            } => Ok(Expr::InList {
+                expr: Box::new(clone_with_replacement(&**nested_expr, replacement_fn)?),
+                list: list
+                    .iter()
+                    .map(|e| clone_with_replacement(e, replacement_fn))
+                    .collect::<Result<Vec<Expr>>>()?,
+                negated: *negated,
+            }),
             Expr::BinaryExpr { left, right, op } => Ok(Expr::BinaryExpr {
                 left: Box::new(clone_with_replacement(&**left, replacement_fn)?),
                 op: op.clone(),
@@ -354,10 +433,21 @@ where
                 asc: *asc,
                 nulls_first: *nulls_first,
             }),
+            Expr::InSubquery {
+                expr: nested_expr,
+                subquery,
+                negated,
+            } => Ok(Expr::InSubquery {
+                expr: Box::new(clone_with_replacement(&**nested_expr, replacement_fn)?),
+                subquery: subquery.clone(),
+                negated: *negated,
+            }),
 
-            Expr::Column(_) | Expr::Literal(_) | Expr::ScalarVariable(_) => {
-                Ok(expr.clone())
-            }
+            Expr::Column(_)
+            | Expr::Literal(_)
+            | Expr::ScalarVariable(_)
+            | Expr::Exists { .. }
+            | Expr::ScalarSubquery(_) => Ok(expr.clone()),
             Expr::Wildcard => Ok(Expr::Wildcard),
         },
     }
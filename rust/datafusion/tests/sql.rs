@@ -21,20 +21,22 @@ use std::sync::Arc;
 extern crate arrow;
 extern crate datafusion;
 
-use arrow::{array::*, datatypes::TimeUnit};
+use arrow::{array::*, datatypes::DateUnit, datatypes::TimeUnit};
 use arrow::{datatypes::Int32Type, datatypes::Int64Type, record_batch::RecordBatch};
 use arrow::{
-    datatypes::{DataType, Field, Schema, SchemaRef},
+    datatypes::{DataType, Field, Int8Type, Schema, SchemaRef},
     util::display::array_value_to_string,
 };
 
 use datafusion::error::Result;
-use datafusion::execution::context::ExecutionContext;
-use datafusion::logical_plan::{LogicalPlan, ToDFSchema};
+use datafusion::execution::context::{ExecutionConfig, ExecutionContext};
+use datafusion::logical_plan::{array_agg, col, lit, Expr, LogicalPlan, ToDFSchema};
 use datafusion::prelude::create_udf;
 use datafusion::{
-    datasource::{csv::CsvReadOptions, MemTable},
-    physical_plan::collect,
+    datasource::{
+        csv::CsvReadOptions, ListingFileFormat, ListingTable, MemTable, NdJsonReadOptions,
+    },
+    physical_plan::{aggregates::AggregateFunction, collect},
 };
 
 #[tokio::test]
@@ -114,6 +116,21 @@ async fn parquet_query() {
     assert_eq!(expected, actual);
 }
 
+#[tokio::test]
+async fn parquet_query_with_filter() {
+    let mut ctx = ExecutionContext::new();
+    register_alltypes_parquet(&mut ctx);
+    // the filter is used to prune row groups, and still applied to the rows read
+    let sql = "SELECT id, bool_col FROM alltypes_plain WHERE id > 4 AND id < 7";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["5", "false"], vec!["6", "true"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT id FROM alltypes_plain WHERE id > 100";
+    let actual = execute(&mut ctx, sql).await;
+    assert!(actual.is_empty());
+}
+
 #[tokio::test]
 async fn parquet_single_nan_schema() {
     let mut ctx = ExecutionContext::new();
@@ -257,6 +274,66 @@ async fn csv_select_nested() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn csv_query_order_by_with_memory_limit() -> Result<()> {
+    let sql = "SELECT c1, c2, c3 FROM aggregate_test_100 ORDER BY c1, c2 DESC, c3";
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let expected = execute(&mut ctx, sql).await;
+    assert_eq!(expected.len(), 100);
+
+    // the input of the sort is spilled to disk batch by batch
+    let config = ExecutionConfig::new()
+        .with_batch_size(10)
+        .with_memory_limit(1);
+    let mut ctx = ExecutionContext::with_config(config);
+    register_aggregate_csv(&mut ctx)?;
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn csv_query_group_by_with_memory_limit() -> Result<()> {
+    let sql = "SELECT c1, c2, COUNT(c3), MIN(c4), AVG(c12) FROM aggregate_test_100 GROUP BY c1, c2 ORDER BY c1, c2";
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let expected = execute(&mut ctx, sql).await;
+    assert_eq!(expected.len(), 25);
+
+    // the states of the groups are spilled to disk batch by batch
+    let config = ExecutionConfig::new()
+        .with_batch_size(10)
+        .with_memory_limit(1);
+    let mut ctx = ExecutionContext::with_config(config);
+    register_aggregate_csv(&mut ctx)?;
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn csv_query_order_by_limit() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let sql = "SELECT c1, c9 FROM aggregate_test_100 ORDER BY c9 DESC";
+    let mut expected = execute(&mut ctx, sql).await;
+    expected.truncate(5);
+
+    // only the first rows of the sort are kept, over batches of 10 rows
+    let mut ctx =
+        ExecutionContext::with_config(ExecutionConfig::new().with_batch_size(10));
+    register_aggregate_csv(&mut ctx)?;
+    let sql = "SELECT c1, c9 FROM aggregate_test_100 ORDER BY c9 DESC LIMIT 5";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT c1, c9 FROM aggregate_test_100 ORDER BY c9 DESC LIMIT 0";
+    let actual = execute(&mut ctx, sql).await;
+    assert!(actual.is_empty());
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_count_star() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -344,6 +421,23 @@ async fn csv_query_group_by_int_min_max() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn csv_query_group_by_having() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let sql =
+        "SELECT c2, MAX(c12) FROM aggregate_test_100 GROUP BY c2 HAVING MIN(c12) < 0.05";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![
+        vec!["3", "0.9293883502480845"],
+        vec!["4", "0.9237877978193884"],
+        vec!["5", "0.9723580396501548"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_group_by_two_columns() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -583,6 +677,135 @@ async fn csv_query_count() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_variance_and_stddev() -> Result<()> {
+    let mut ctx = create_statistics_context()?;
+    let sql = "SELECT g, ROUND(VAR_SAMP(x) * 1000), ROUND(VAR_POP(x) * 1000), \
+        ROUND(STDDEV(x) * 1000), ROUND(STDDEV_POP(x) * 1000) FROM t GROUP BY g ORDER BY g";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["a", "1667", "1250", "1291", "1118"],
+        vec!["b", "50000", "25000", "7071", "5000"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_covariance_and_percentiles() -> Result<()> {
+    let mut ctx = create_statistics_context()?;
+    let sql = "SELECT g, ROUND(COVAR(x, y) * 1000), ROUND(COVAR_POP(x, y) * 1000), \
+        ROUND(CORR(x, y) * 1000), ROUND(MEDIAN(x) * 1000), \
+        ROUND(PERCENTILE_CONT(y, 0.25) * 1000) FROM t GROUP BY g ORDER BY g";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["a", "3667", "2750", "965", "2500", "3500"],
+        vec!["b", "10000", "5000", "1000", "15000", "1500"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT PERCENTILE_CONT(y, x) FROM t";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    assert!(ctx.create_physical_plan(&plan).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_approx_aggregates() -> Result<()> {
+    let mut ctx = create_statistics_context()?;
+    // with few values, the estimates are exact
+    let sql = "SELECT g, APPROX_DISTINCT(x), APPROX_DISTINCT(g), \
+        ROUND(APPROX_PERCENTILE_CONT(y, 0.25) * 1000) FROM t GROUP BY g ORDER BY g";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["a", "4", "1", "3500"], vec!["b", "2", "1", "1500"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn csv_query_approx_distinct() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let sql = "SELECT APPROX_DISTINCT(c1), COUNT(DISTINCT c1) FROM aggregate_test_100";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["5", "5"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_array_agg_distinct() -> Result<()> {
+    let mut ctx = create_statistics_context()?;
+    let sql = "SELECT g, ARRAY_AGG(DISTINCT x) FROM t GROUP BY g ORDER BY g";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["a", "[1, 2, 3, 4]"], vec!["b", "[10, 20]"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT STRING_AGG(g, g) FROM t";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    assert!(ctx.create_physical_plan(&plan).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_ordered_collection_aggregates() -> Result<()> {
+    let mut ctx = create_statistics_context()?;
+    // the values of both partitions are ordered once merged
+    let df = ctx
+        .table("t")?
+        .aggregate(
+            vec![col("g")],
+            vec![array_agg(col("y"), vec![col("x").sort(false, false)])],
+        )?
+        .sort(vec![col("g").sort(true, false)])?;
+    let actual = result_vec(&df.collect().await?);
+    let expected = vec![vec!["a", "[9, 5, 4, 2]"], vec!["b", "[3, 1]"]];
+    assert_eq!(expected, actual);
+
+    // the SQL parser does not support an ORDER BY clause in the arguments of a function
+    let string_agg = Expr::AggregateFunction {
+        fun: AggregateFunction::StringAgg,
+        distinct: false,
+        args: vec![col("g"), lit("-")],
+        order_by: vec![col("y").sort(false, false)],
+    };
+    let df = ctx.table("t")?.aggregate(vec![], vec![string_agg])?;
+    let actual = result_vec(&df.collect().await?);
+    assert_eq!(vec![vec!["a-a-a-b-a-b"]], actual);
+    Ok(())
+}
+
+/// A table of two partitions, each with rows of the groups `a` and `b`
+fn create_statistics_context() -> Result<ExecutionContext> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("g", DataType::Utf8, false),
+        Field::new("x", DataType::Int32, false),
+        Field::new("y", DataType::Float64, false),
+    ]));
+    let partition1 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "a", "b"])),
+            Arc::new(Int32Array::from(vec![1, 2, 10])),
+            Arc::new(Float64Array::from(vec![2.0, 4.0, 1.0])),
+        ],
+    )?;
+    let partition2 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "a", "b"])),
+            Arc::new(Int32Array::from(vec![3, 4, 20])),
+            Arc::new(Float64Array::from(vec![5.0, 9.0, 3.0])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![partition1], vec![partition2]])?;
+    ctx.register_table("t", Box::new(table));
+    Ok(ctx)
+}
+
 #[tokio::test]
 async fn csv_query_group_by_int_count() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -601,6 +824,173 @@ async fn csv_query_group_by_int_count() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_group_by_key_types() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("flag", DataType::Boolean, true),
+        Field::new("f", DataType::Float64, false),
+        Field::new("d", DataType::Date32(DateUnit::Day), false),
+        Field::new(
+            "ts",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            false,
+        ),
+        Field::new(
+            "s",
+            DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8)),
+            false,
+        ),
+        Field::new("v", DataType::Int32, false),
+    ]));
+    // the partitions encode the strings with different dictionaries
+    let partition1 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(BooleanArray::from(vec![Some(true), Some(false), None])),
+            Arc::new(Float64Array::from(vec![0.0, -0.0, f64::NAN])),
+            Arc::new(Date32Array::from(vec![18000, 18001, 18000])),
+            Arc::new(TimestampMillisecondArray::from(vec![1000, 2000, 1000])),
+            Arc::new(
+                vec!["a", "b", "a"]
+                    .into_iter()
+                    .collect::<DictionaryArray<Int8Type>>(),
+            ),
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+        ],
+    )?;
+    let partition2 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(BooleanArray::from(vec![Some(true), None, Some(false)])),
+            Arc::new(Float64Array::from(vec![1.5, -f64::NAN, 1.5])),
+            Arc::new(Date32Array::from(vec![18000, 18001, 18000])),
+            Arc::new(TimestampMillisecondArray::from(vec![3000, 2000, 1000])),
+            Arc::new(
+                vec!["c", "b", "a"]
+                    .into_iter()
+                    .collect::<DictionaryArray<Int8Type>>(),
+            ),
+            Arc::new(Int32Array::from(vec![4, 5, 6])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![partition1], vec![partition2]])?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", Box::new(table));
+
+    let cases = vec![
+        // NULL is a group of its own
+        (
+            "flag",
+            vec![vec!["NULL", "8"], vec!["false", "8"], vec!["true", "5"]],
+        ),
+        // -0.0 equals 0.0, and the NaNs equal each other
+        (
+            "f",
+            vec![vec!["0", "3"], vec!["1.5", "10"], vec!["NaN", "8"]],
+        ),
+        ("d", vec![vec!["18000", "14"], vec!["18001", "7"]]),
+        (
+            "ts",
+            vec![vec!["1000", "10"], vec!["2000", "7"], vec!["3000", "4"]],
+        ),
+        ("s", vec![vec!["a", "10"], vec!["b", "7"], vec!["c", "4"]]),
+    ];
+    for (key, expected) in cases {
+        let sql = format!("SELECT {}, SUM(v) FROM t GROUP BY {}", key, key);
+        let mut actual = execute(&mut ctx, &sql).await;
+        actual.sort();
+        assert_eq!(expected, actual, "GROUP BY {}", key);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_group_by_rollup_and_cube() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("region", DataType::Utf8, false),
+        Field::new("country", DataType::Utf8, false),
+        Field::new("sales", DataType::Int32, false),
+    ]));
+    let partition1 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["EU", "EU", "US"])),
+            Arc::new(StringArray::from(vec!["FR", "DE", "US"])),
+            Arc::new(Int32Array::from(vec![1, 2, 4])),
+        ],
+    )?;
+    let partition2 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["EU", "US"])),
+            Arc::new(StringArray::from(vec!["FR", "CA"])),
+            Arc::new(Int32Array::from(vec![8, 16])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![partition1], vec![partition2]])?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", Box::new(table));
+
+    // the subtotals of the regions and the grand total, told apart by GROUPING
+    let sql = "SELECT region, country, SUM(sales), GROUPING(region, country) FROM t \
+               GROUP BY ROLLUP(region, country)";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![
+        vec!["EU", "DE", "2", "0"],
+        vec!["EU", "FR", "9", "0"],
+        vec!["EU", "NULL", "11", "1"],
+        vec!["NULL", "NULL", "31", "3"],
+        vec!["US", "CA", "16", "0"],
+        vec!["US", "NULL", "20", "1"],
+        vec!["US", "US", "4", "0"],
+    ];
+    assert_eq!(expected, actual);
+
+    // the subtotals of the regions, of the countries and the grand total
+    let sql = "SELECT region, country, SUM(sales), GROUPING(country) FROM t \
+               GROUP BY CUBE(region, country)";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![
+        vec!["EU", "DE", "2", "0"],
+        vec!["EU", "FR", "9", "0"],
+        vec!["EU", "NULL", "11", "1"],
+        vec!["NULL", "CA", "16", "0"],
+        vec!["NULL", "DE", "2", "0"],
+        vec!["NULL", "FR", "9", "0"],
+        vec!["NULL", "NULL", "31", "1"],
+        vec!["NULL", "US", "4", "0"],
+        vec!["US", "CA", "16", "0"],
+        vec!["US", "NULL", "20", "1"],
+        vec!["US", "US", "4", "0"],
+    ];
+    assert_eq!(expected, actual);
+
+    // the subtotal rows can be filtered by GROUPING
+    let sql = "SELECT region, SUM(sales) FROM t GROUP BY ROLLUP(region, country) \
+               HAVING GROUPING(region, country) = 1";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![vec!["EU", "11"], vec!["US", "20"]];
+    assert_eq!(expected, actual);
+
+    // the grand total has a row even without input rows
+    let sql = "SELECT region, COUNT(*), SUM(sales), GROUPING(region) FROM t \
+               WHERE sales > 100 GROUP BY ROLLUP(region)";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["NULL", "0", "NULL", "1"]];
+    assert_eq!(expected, actual);
+
+    // a GROUP BY without grouping sets is a single one
+    let sql = "SELECT region, GROUPING(region) FROM t GROUP BY region";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![vec!["EU", "0"], vec!["US", "0"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_group_with_aliased_aggregate() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -942,6 +1332,89 @@ async fn csv_query_external_table_count() {
     assert_eq!(expected, actual);
 }
 
+#[tokio::test]
+async fn json_query_create_external_table() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    // the schema is inferred from both files of the directory
+    let df =
+        ctx.sql("CREATE EXTERNAL TABLE t STORED AS NDJSON LOCATION 'tests/jsons'")?;
+    assert!(df.collect().await?.is_empty());
+
+    let sql = "SELECT c, a, b FROM t ORDER BY a";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["true", "-10", "-3.5"],
+        vec!["false", "1", "2"],
+        vec!["false", "2", "0.6"],
+        vec!["true", "7", "-3.5"],
+        vec!["false", "100000000000000", "0.6"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT COUNT(d) FROM t";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["4"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_partitioned_listing_table() -> Result<()> {
+    let dir = tempfile::TempDir::new()?;
+    let files = vec![
+        ("day=2020-12-01/region=eu", "a\n1\n2\n"),
+        ("day=2020-12-01/region=us", "a\n3\n"),
+        ("day=2020-12-02/region=us", "a\n4\n5\n"),
+    ];
+    for (directory, content) in files {
+        let directory = dir.path().join(directory);
+        std::fs::create_dir_all(&directory)?;
+        std::fs::write(directory.join("part-0.csv"), content)?;
+    }
+
+    let mut ctx = ExecutionContext::new();
+    let format = ListingFileFormat::Csv {
+        has_header: true,
+        delimiter: b',',
+    };
+    let table = ListingTable::try_new(dir.path().to_str().unwrap(), format, None)?;
+    ctx.register_table("events", Box::new(table));
+
+    let sql =
+        "SELECT day, SUM(a) FROM events WHERE region = 'us' GROUP BY day ORDER BY day";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["2020-12-01", "3"], vec!["2020-12-02", "9"]];
+    assert_eq!(expected, actual);
+
+    // the filter on the partition column is applied by the table
+    let sql = "SELECT a FROM events WHERE region = 'us'";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = format!("{:?}", plan);
+    assert!(!plan.contains("Filter:"), "{}", plan);
+    Ok(())
+}
+
+#[tokio::test]
+async fn json_query_with_schema() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("d", DataType::Utf8, true),
+    ]);
+    ctx.register_json(
+        "t",
+        "tests/jsons/1.json",
+        NdJsonReadOptions::new().schema(&schema),
+    )?;
+
+    let sql = "SELECT d, a FROM t WHERE a > 0 ORDER BY a";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["4", "1"], vec!["text", "2"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_external_table_sum() {
     let mut ctx = ExecutionContext::new();
@@ -1030,72 +1503,207 @@ async fn case_when_else_with_base_expr() -> Result<()> {
     Ok(())
 }
 
-fn create_case_context() -> Result<ExecutionContext> {
-    let mut ctx = ExecutionContext::new();
-    let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Utf8, true)]));
-    let data = RecordBatch::try_new(
-        schema.clone(),
-        vec![Arc::new(StringArray::from(vec![
-            Some("a"),
-            Some("b"),
-            Some("c"),
-            None,
-        ]))],
-    )?;
-    let table = MemTable::try_new(schema, vec![vec![data]])?;
-    ctx.register_table("t1", Box::new(table));
-    Ok(ctx)
-}
-
 #[tokio::test]
-async fn equijoin() -> Result<()> {
-    let mut ctx = create_join_context("t1_id", "t2_id")?;
-    let sql =
-        "SELECT t1_id, t1_name, t2_name FROM t1 JOIN t2 ON t1_id = t2_id ORDER BY t1_id";
+async fn in_list_string() -> Result<()> {
+    let mut ctx = create_case_context()?;
+    let sql = "SELECT c1 FROM t1 WHERE c1 IN ('a', 'c')";
     let actual = execute(&mut ctx, sql).await;
-    let expected = vec![
-        vec!["11", "a", "z"],
-        vec!["22", "b", "y"],
-        vec!["44", "d", "x"],
-    ];
+    let expected = vec![vec!["a"], vec!["c"]];
     assert_eq!(expected, actual);
     Ok(())
 }
 
 #[tokio::test]
-async fn left_join() -> Result<()> {
-    let mut ctx = create_join_context("t1_id", "t2_id")?;
-    let sql =
-        "SELECT t1_id, t1_name, t2_name FROM t1 LEFT JOIN t2 ON t1_id = t2_id ORDER BY t1_id";
+async fn in_list_with_null() -> Result<()> {
+    let mut ctx = create_case_context()?;
+    let sql = "SELECT c1 IN ('a', NULL), c1 NOT IN ('a', NULL) FROM t1";
     let actual = execute(&mut ctx, sql).await;
     let expected = vec![
-        vec!["11", "a", "z"],
-        vec!["22", "b", "y"],
-        vec!["33", "c", "NULL"],
-        vec!["44", "d", "x"],
+        vec!["true", "false"],
+        vec!["NULL", "NULL"],
+        vec!["NULL", "NULL"],
+        vec!["NULL", "NULL"],
     ];
     assert_eq!(expected, actual);
     Ok(())
 }
 
 #[tokio::test]
-async fn right_join() -> Result<()> {
+async fn not_in_list_numeric() -> Result<()> {
     let mut ctx = create_join_context("t1_id", "t2_id")?;
-    let sql =
-        "SELECT t1_id, t1_name, t2_name FROM t1 RIGHT JOIN t2 ON t1_id = t2_id ORDER BY t1_id";
+    let sql = "SELECT t1_id FROM t1 WHERE t1_id NOT IN (11, 33)";
     let actual = execute(&mut ctx, sql).await;
-    let expected = vec![
-        vec!["NULL", "NULL", "w"],
-        vec!["11", "a", "z"],
-        vec!["22", "b", "y"],
-        vec!["44", "d", "x"],
-    ];
+    let expected = vec![vec!["22"], vec!["44"]];
     assert_eq!(expected, actual);
     Ok(())
 }
 
 #[tokio::test]
-async fn left_join_using() -> Result<()> {
+async fn constant_folding() -> Result<()> {
+    let mut ctx = create_case_context()?;
+    let sql = "SELECT c1, 1 + 2 FROM t1 WHERE c1 = 'a' AND NOT NOT 2 * 3 = 6";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["a", "3"]];
+    assert_eq!(expected, actual);
+
+    // the always true filter is removed
+    let sql = "SELECT c1 FROM t1 WHERE 1 < 2";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    assert!(!format!("{:?}", plan).contains("Filter"));
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["a"], vec!["b"], vec!["c"], vec!["NULL"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn limit_push_down() -> Result<()> {
+    let mut ctx = create_case_context()?;
+    let sql = "SELECT c1 FROM t1 LIMIT 2";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    assert!(format!("{:?}", plan).contains("limit=2"));
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["a"], vec!["b"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn common_subexpr_eliminate() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT SUM(t1_id + 1), MIN(t1_id + 1), MAX(t1_id + 1) + 1 FROM t1";
+    // `t1_id + 1` is computed once, below the aggregate
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    assert!(format!("{:?}", plan).contains("#t1_id Plus Int64(1) AS t1_id Plus Int64(1)"));
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["114", "12", "46"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+fn create_case_context() -> Result<ExecutionContext> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Utf8, true)]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(StringArray::from(vec![
+            Some("a"),
+            Some("b"),
+            Some("c"),
+            None,
+        ]))],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    ctx.register_table("t1", Box::new(table));
+    Ok(ctx)
+}
+
+#[tokio::test]
+async fn window_partition_by() -> Result<()> {
+    let mut ctx = create_window_context()?;
+    let sql = "SELECT c1, c2, \
+        ROW_NUMBER() OVER (PARTITION BY c1 ORDER BY c2), \
+        SUM(c2) OVER (PARTITION BY c1 ORDER BY c2), \
+        MAX(c2) OVER (PARTITION BY c1) \
+        FROM t ORDER BY c1, c2";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["a", "1", "1", "1", "5"],
+        vec!["a", "3", "2", "4", "5"],
+        vec!["a", "5", "3", "9", "5"],
+        vec!["b", "2", "1", "2", "4"],
+        vec!["b", "4", "2", "6", "4"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn window_frame_and_lag() -> Result<()> {
+    let mut ctx = create_window_context()?;
+    let sql = "SELECT c2, \
+        LAG(c2, 1, 0) OVER (ORDER BY c2), \
+        SUM(c2) OVER (ORDER BY c2 ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING) AS s \
+        FROM t ORDER BY c2";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["1", "0", "3"],
+        vec!["2", "1", "6"],
+        vec!["3", "2", "9"],
+        vec!["4", "3", "12"],
+        vec!["5", "4", "9"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+fn create_window_context() -> Result<ExecutionContext> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("c1", DataType::Utf8, false),
+        Field::new("c2", DataType::UInt32, false),
+    ]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "b", "a", "b", "a"])),
+            Arc::new(UInt32Array::from(vec![1, 2, 3, 4, 5])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    ctx.register_table("t", Box::new(table));
+    Ok(ctx)
+}
+
+#[tokio::test]
+async fn equijoin() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql =
+        "SELECT t1_id, t1_name, t2_name FROM t1 JOIN t2 ON t1_id = t2_id ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "a", "z"],
+        vec!["22", "b", "y"],
+        vec!["44", "d", "x"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn left_join() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql =
+        "SELECT t1_id, t1_name, t2_name FROM t1 LEFT JOIN t2 ON t1_id = t2_id ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "a", "z"],
+        vec!["22", "b", "y"],
+        vec!["33", "c", "NULL"],
+        vec!["44", "d", "x"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn right_join() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql =
+        "SELECT t1_id, t1_name, t2_name FROM t1 RIGHT JOIN t2 ON t1_id = t2_id ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["NULL", "NULL", "w"],
+        vec!["11", "a", "z"],
+        vec!["22", "b", "y"],
+        vec!["44", "d", "x"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn left_join_using() -> Result<()> {
     let mut ctx = create_join_context("id", "id")?;
     let sql = "SELECT id, t1_name, t2_name FROM t1 LEFT JOIN t2 USING (id) ORDER BY id";
     let actual = execute(&mut ctx, sql).await;
@@ -1109,6 +1717,484 @@ async fn left_join_using() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn union_all() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let sql = "SELECT c1 FROM aggregate_test_100 \
+               UNION ALL SELECT c1 FROM aggregate_test_100";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(200, actual.len());
+    Ok(())
+}
+
+#[tokio::test]
+async fn union_distinct() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let sql = "SELECT c1 FROM aggregate_test_100 \
+               UNION SELECT c1 FROM aggregate_test_100 ORDER BY c1";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["a"], vec!["b"], vec!["c"], vec!["d"], vec!["e"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn intersect() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let sql = "SELECT c2 FROM aggregate_test_100 WHERE c2 < 4 \
+               INTERSECT SELECT c2 FROM aggregate_test_100 WHERE c2 > 1 ORDER BY c2";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["2"], vec!["3"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn except() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let sql = "SELECT c2 FROM aggregate_test_100 WHERE c2 < 4 \
+               EXCEPT SELECT c2 FROM aggregate_test_100 WHERE c2 > 1";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["1"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn intersect_with_nulls() -> Result<()> {
+    let mut ctx = create_set_operation_context()?;
+    let sql = "SELECT a, b FROM s1 INTERSECT SELECT a, b FROM s2";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![vec!["NULL", "NULL"], vec!["NULL", "b"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn except_with_nulls() -> Result<()> {
+    let mut ctx = create_set_operation_context()?;
+    let sql = "SELECT a, b FROM s1 EXCEPT SELECT a, b FROM s2";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![vec!["1", "a"], vec!["2", "NULL"], vec!["3", "c"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT a, b FROM s2 EXCEPT SELECT a, b FROM s1";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![vec!["2", "x"], vec!["3", "d"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+fn create_set_operation_context() -> Result<ExecutionContext> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int32, true),
+        Field::new("b", DataType::Utf8, true),
+    ]));
+
+    let s1_data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![
+                Some(1),
+                None,
+                Some(2),
+                None,
+                Some(3),
+                None,
+            ])),
+            Arc::new(StringArray::from(vec![
+                Some("a"),
+                Some("b"),
+                None,
+                None,
+                Some("c"),
+                Some("b"),
+            ])),
+        ],
+    )?;
+    let s1_table = MemTable::try_new(schema.clone(), vec![vec![s1_data]])?;
+    ctx.register_table("s1", Box::new(s1_table));
+
+    let s2_data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![None, None, Some(3), Some(2)])),
+            Arc::new(StringArray::from(vec![
+                Some("b"),
+                None,
+                Some("d"),
+                Some("x"),
+            ])),
+        ],
+    )?;
+    let s2_table = MemTable::try_new(schema, vec![vec![s2_data]])?;
+    ctx.register_table("s2", Box::new(s2_table));
+
+    Ok(ctx)
+}
+
+#[tokio::test]
+async fn full_join() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id, t1_name, t2_id, t2_name \
+        FROM t1 FULL OUTER JOIN t2 ON t1_id = t2_id \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["NULL", "NULL", "55", "w"],
+        vec!["11", "a", "11", "z"],
+        vec!["22", "b", "22", "y"],
+        vec!["33", "c", "NULL", "NULL"],
+        vec!["44", "d", "44", "x"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn full_join_using() -> Result<()> {
+    let mut ctx = create_join_context("id", "id")?;
+    let sql =
+        "SELECT id, t1_name, t2_name FROM t1 FULL OUTER JOIN t2 USING (id) ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "a", "z"],
+        vec!["22", "b", "y"],
+        vec!["33", "c", "NULL"],
+        vec!["44", "d", "x"],
+        vec!["55", "NULL", "w"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn exists_subquery() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id, t1_name FROM t1 \
+        WHERE EXISTS (SELECT * FROM t2 WHERE t2_id = t1_id AND t2_name <> 'x') \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11", "a"], vec!["22", "b"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id, t1_name FROM t1 \
+        WHERE NOT EXISTS (SELECT * FROM t2 WHERE t2_id = t1_id AND t2_name <> 'x') \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["33", "c"], vec!["44", "d"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn exists_subquery_with_non_equality_correlation() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id FROM t1 \
+        WHERE EXISTS (SELECT * FROM t2 WHERE t2_id < t1_id) \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["22"], vec!["33"], vec!["44"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id FROM t1 \
+        WHERE NOT EXISTS (SELECT * FROM t2 WHERE t2_name <> 'x' AND t2_id >= t1_id) \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected: Vec<Vec<String>> = vec![];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id FROM t1 \
+        WHERE NOT EXISTS (SELECT * FROM t2 WHERE t2_id = t1_id AND t2_name > t1_name) \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["33"]];
+    assert_eq!(expected, actual);
+
+    // qualified names tell the columns of the subquery and of the query apart
+    let sql = "SELECT t1_id FROM t1 a \
+        WHERE EXISTS (SELECT * FROM t1 b WHERE b.t1_id > a.t1_id AND b.t1_name <> 'd') \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["22"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn uncorrelated_exists_subquery() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id FROM t1 \
+        WHERE EXISTS (SELECT * FROM t2 WHERE t2_id > 50) \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["22"], vec!["33"], vec!["44"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id FROM t1 WHERE EXISTS (SELECT * FROM t2 WHERE t2_id > 100)";
+    let actual = execute(&mut ctx, sql).await;
+    let expected: Vec<Vec<String>> = vec![];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id FROM t1 \
+        WHERE NOT EXISTS (SELECT * FROM t2 WHERE t2_id > 100) \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["11"], vec!["22"], vec!["33"], vec!["44"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn in_subquery() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id, t1_name FROM t1 \
+        WHERE t1_id IN (SELECT t2_id FROM t2) AND t1_id > 11 \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["22", "b"], vec!["44", "d"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id, t1_name FROM t1 \
+        WHERE t1_id NOT IN (SELECT t2_id FROM t2) \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["33", "c"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn not_in_subquery_with_nulls() -> Result<()> {
+    let mut ctx = create_set_operation_context()?;
+    // the subquery has a null: no value is known not to be in it
+    let sql = "SELECT a FROM s1 WHERE a NOT IN (SELECT a FROM s2)";
+    let actual = execute(&mut ctx, sql).await;
+    let expected: Vec<Vec<String>> = vec![];
+    assert_eq!(expected, actual);
+
+    // null values are not known not to be in the subquery
+    let sql = "SELECT a FROM s1 WHERE a NOT IN (SELECT a FROM s2 WHERE a IS NOT NULL)";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["1"]];
+    assert_eq!(expected, actual);
+
+    // every value, null or not, is not in an empty subquery
+    let sql = "SELECT a FROM s1 WHERE a NOT IN (SELECT a FROM s2 WHERE a > 10)";
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let expected = vec![
+        vec!["1"],
+        vec!["2"],
+        vec!["3"],
+        vec!["NULL"],
+        vec!["NULL"],
+        vec!["NULL"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn correlated_count_subquery() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id, (SELECT COUNT(*) FROM t2 WHERE t2_id = t1_id) AS n \
+        FROM t1 ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "1"],
+        vec!["22", "1"],
+        vec!["33", "0"],
+        vec!["44", "1"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn nested_in_subqueries() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id FROM t1 \
+        WHERE t1_id IN (\
+            SELECT t2_id FROM t2 \
+            WHERE t2_id IN (SELECT t1_id FROM t1 WHERE t1_name <> 'a')\
+        ) \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["22"], vec!["44"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn scalar_subquery() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id, (SELECT MAX(t2_id) FROM t2 WHERE t2_id = t1_id) AS t2_max \
+        FROM t1 \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "11"],
+        vec!["22", "22"],
+        vec!["33", "NULL"],
+        vec!["44", "44"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id FROM t1 \
+        WHERE t1_id > (SELECT MIN(t2_id) FROM t2 WHERE t2_name <> 'z') \
+        ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["33"], vec!["44"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn scalar_subquery_of_many_rows() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql =
+        "SELECT t1_id FROM t1 WHERE t1_id = (SELECT t2_id FROM t2 WHERE t2_id > 50)";
+    let actual = execute(&mut ctx, sql).await;
+    let expected: Vec<Vec<String>> = vec![];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id FROM t1 WHERE t1_id = (SELECT t2_id FROM t2)";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = ctx.create_physical_plan(&plan)?;
+    let err = collect(plan).await.unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("More than one row returned by a subquery used as an expression"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn derived_table_with_column_aliases() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT a, b FROM (SELECT t1_id, t1_name FROM t1) AS t (a, b) \
+        WHERE a > 22 \
+        ORDER BY a";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["33", "c"], vec!["44", "d"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn join_and_aggregate_repartitioned() -> Result<()> {
+    let config = ExecutionConfig::new()
+        .with_concurrency(4)
+        .with_repartition_joins(true)
+        .with_repartition_aggregations(true);
+    let mut ctx = create_join_context_with_config("t1_id", "t2_id", config)?;
+    let sql = "SELECT t1_name, COUNT(t2_id) \
+        FROM t1 LEFT JOIN t2 ON t1_id = t2_id \
+        GROUP BY t1_name \
+        ORDER BY t1_name";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["a", "1"],
+        vec!["b", "1"],
+        vec!["c", "0"],
+        vec!["d", "1"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn join_repartitioned_order_by() -> Result<()> {
+    let config = ExecutionConfig::new()
+        .with_concurrency(4)
+        .with_repartition_joins(true);
+    let mut ctx = create_join_context_with_config("t1_id", "t2_id", config)?;
+    let sql = "SELECT t1_id, t1_name, t2_name FROM t1 JOIN t2 ON t1_id = t2_id \
+        ORDER BY t1_id DESC";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["44", "d", "x"],
+        vec!["22", "b", "y"],
+        vec!["11", "a", "z"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT t1_id, t1_name, t2_name FROM t1 JOIN t2 ON t1_id = t2_id \
+        ORDER BY t1_id DESC LIMIT 2";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["44", "d", "x"], vec!["22", "b", "y"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn sort_merge_joins() -> Result<()> {
+    for concurrency in &[1, 4] {
+        let config = ExecutionConfig::new()
+            .with_concurrency(*concurrency)
+            .with_sort_merge_joins(true);
+        let mut ctx = create_join_context_with_config("t1_id", "t2_id", config)?;
+
+        let sql = "SELECT t1_id, t1_name, t2_name FROM t1 JOIN t2 ON t1_id = t2_id \
+            ORDER BY t1_id";
+        let actual = execute(&mut ctx, sql).await;
+        let expected = vec![
+            vec!["11", "a", "z"],
+            vec!["22", "b", "y"],
+            vec!["44", "d", "x"],
+        ];
+        assert_eq!(expected, actual);
+
+        let sql = "SELECT t1_id, t1_name, t2_id, t2_name \
+            FROM t1 FULL OUTER JOIN t2 ON t1_id = t2_id \
+            ORDER BY t1_id";
+        let actual = execute(&mut ctx, sql).await;
+        let expected = vec![
+            vec!["NULL", "NULL", "55", "w"],
+            vec!["11", "a", "11", "z"],
+            vec!["22", "b", "22", "y"],
+            vec!["33", "c", "NULL", "NULL"],
+            vec!["44", "d", "44", "x"],
+        ];
+        assert_eq!(expected, actual);
+
+        let sql = "SELECT t1_id, t1_name FROM t1 \
+            WHERE NOT EXISTS (SELECT * FROM t2 WHERE t2_id = t1_id) \
+            ORDER BY t1_id";
+        let actual = execute(&mut ctx, sql).await;
+        let expected = vec![vec!["33", "c"]];
+        assert_eq!(expected, actual);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn join_sorted_derived_tables() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    // both sides are sorted on the join keys, and merged without being hashed
+    let sql = "SELECT t1_id, t1_name, t2_name \
+        FROM (SELECT * FROM t1 ORDER BY t1_id DESC) AS a \
+        LEFT JOIN (SELECT * FROM t2 ORDER BY t2_id DESC) AS b ON t1_id = t2_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["44", "d", "x"],
+        vec!["33", "c", "NULL"],
+        vec!["22", "b", "y"],
+        vec!["11", "a", "z"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn equijoin_implicit_syntax() -> Result<()> {
     let mut ctx = create_join_context("t1_id", "t2_id")?;
@@ -1160,13 +2246,126 @@ async fn equijoin_implicit_syntax_reversed() -> Result<()> {
 
 #[tokio::test]
 async fn cartesian_join() -> Result<()> {
-    let ctx = create_join_context("t1_id", "t2_id")?;
-    let sql = "SELECT t1_id, t1_name, t2_name FROM t1, t2 ORDER BY t1_id";
-    let maybe_plan = ctx.create_logical_plan(&sql);
-    assert_eq!(
-        "This feature is not implemented: Cartesian joins are not supported",
-        &format!("{}", maybe_plan.err().unwrap())
-    );
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id, t2_id FROM t1, t2 WHERE t1_id < 22 ORDER BY t2_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "11"],
+        vec!["11", "22"],
+        vec!["11", "44"],
+        vec!["11", "55"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT COUNT(*) FROM t1, t2";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["16"]], actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn cross_join() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_name, t2_name FROM t1 CROSS JOIN t2 \
+               WHERE t1_id = 44 AND t2_id > 20 ORDER BY t2_name";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["d", "w"], vec!["d", "x"], vec!["d", "y"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn join_on_between() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id, t2_id FROM t1 JOIN t2 \
+               ON t2_id BETWEEN t1_id AND t1_id + 20 ORDER BY t1_id, t2_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "11"],
+        vec!["11", "22"],
+        vec!["22", "22"],
+        vec!["33", "44"],
+        vec!["44", "44"],
+        vec!["44", "55"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn left_join_with_filter() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    // the rows of t1 whose match does not satisfy the filter are padded with nulls
+    let sql = "SELECT t1_id, t1_name, t2_name FROM t1 LEFT JOIN t2 \
+               ON t1_id = t2_id AND t2_name <> 'y' ORDER BY t1_id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "a", "z"],
+        vec!["22", "b", "NULL"],
+        vec!["33", "c", "NULL"],
+        vec!["44", "d", "x"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn left_join_without_keys() -> Result<()> {
+    for concurrency in &[1, 4] {
+        let mut ctx = create_join_context_with_config(
+            "t1_id",
+            "t2_id",
+            ExecutionConfig::new().with_concurrency(*concurrency),
+        )?;
+        let sql = "SELECT t1_id, t2_id FROM t1 LEFT JOIN t2 \
+                   ON t2_id > t1_id + 20 ORDER BY t1_id, t2_id";
+        let actual = execute(&mut ctx, sql).await;
+        let expected = vec![
+            vec!["11", "44"],
+            vec!["11", "55"],
+            vec!["22", "44"],
+            vec!["22", "55"],
+            vec!["33", "55"],
+            vec!["44", "NULL"],
+        ];
+        assert_eq!(expected, actual);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn reordered_joins() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let t3_schema = Arc::new(Schema::new(vec![
+        Field::new("t3_id", DataType::UInt32, true),
+        Field::new("t3_value", DataType::Utf8, true),
+    ]));
+    let t3_data = RecordBatch::try_new(
+        t3_schema.clone(),
+        vec![
+            Arc::new(UInt32Array::from(vec![11, 44])),
+            Arc::new(StringArray::from(vec![Some("p"), Some("q")])),
+        ],
+    )?;
+    let t3_table = MemTable::try_new(t3_schema, vec![vec![t3_data]])?;
+    ctx.register_table("t3", Box::new(t3_table));
+
+    let sql = "SELECT * FROM t1 JOIN t2 ON t1_id = t2_id JOIN t3 ON t2_id = t3_id \
+        ORDER BY t1_id";
+    // the smallest table is joined first
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    assert!(format!("{:?}", plan).contains(
+        "Join: t2_id = t1_id\
+        \n        Join: t3_id = t2_id\
+        \n          TableScan: t3"
+    ));
+    // the columns are in the order of the query
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["11", "a", "11", "z", "11", "p"],
+        vec!["44", "d", "44", "x", "44", "q"],
+    ];
+    assert_eq!(expected, actual);
     Ok(())
 }
 
@@ -1174,7 +2373,15 @@ fn create_join_context(
     column_left: &str,
     column_right: &str,
 ) -> Result<ExecutionContext> {
-    let mut ctx = ExecutionContext::new();
+    create_join_context_with_config(column_left, column_right, ExecutionConfig::new())
+}
+
+fn create_join_context_with_config(
+    column_left: &str,
+    column_right: &str,
+    config: ExecutionConfig,
+) -> Result<ExecutionContext> {
+    let mut ctx = ExecutionContext::with_config(config);
 
     let t1_schema = Arc::new(Schema::new(vec![
         Field::new(column_left, DataType::UInt32, true),
@@ -1489,6 +2696,34 @@ async fn query_concat() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_substring() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Utf8, true)]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(StringArray::from(vec![
+            Some("datafusion"),
+            Some("ab"),
+            None,
+        ]))],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Box::new(table));
+    let sql =
+        "SELECT substring(c1 FROM 5 FOR 3), substr(c1, 2), substring(c1 FROM 0 FOR 2) \
+        FROM test";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["fus", "atafusion", "d"],
+        vec!["", "b", "a"],
+        vec!["NULL", "NULL", "NULL"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn query_array() -> Result<()> {
     let schema = Arc::new(Schema::new(vec![
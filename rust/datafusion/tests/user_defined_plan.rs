@@ -219,6 +219,7 @@ impl OptimizerRule for TopKOptimizerRule {
                 if let LogicalPlan::Sort {
                     ref expr,
                     ref input,
+                    ..
                 } = **input
                 {
                     if expr.len() == 1 {
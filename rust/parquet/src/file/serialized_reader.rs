@@ -137,6 +137,24 @@ impl<R: 'static + ChunkReader> SerializedFileReader<R> {
             metadata,
         })
     }
+
+    /// Filters row group metadata to only those row groups,
+    /// for which the predicate function returns true
+    pub fn filter_row_groups(
+        &mut self,
+        predicate: &dyn Fn(&RowGroupMetaData, usize) -> bool,
+    ) {
+        let mut filtered_row_groups = Vec::<RowGroupMetaData>::new();
+        for (i, row_group_metadata) in self.metadata.row_groups().iter().enumerate() {
+            if predicate(row_group_metadata, i) {
+                filtered_row_groups.push(row_group_metadata.clone());
+            }
+        }
+        self.metadata = ParquetMetaData::new(
+            self.metadata.file_metadata().clone(),
+            filtered_row_groups,
+        );
+    }
 }
 
 impl<R: 'static + ChunkReader> FileReader for SerializedFileReader<R> {
@@ -584,6 +602,22 @@ mod tests {
         assert_eq!(page_count, 2);
     }
 
+    #[test]
+    fn test_file_reader_filter_row_groups() {
+        let test_file = get_test_file("alltypes_plain.parquet");
+        let mut reader = SerializedFileReader::new(test_file).unwrap();
+
+        // test initial number of row groups
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 1);
+
+        // test filtering out all row groups
+        reader.filter_row_groups(&|_, _| false);
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 0);
+        assert_eq!(metadata.file_metadata().num_rows(), 8);
+    }
+
     #[test]
     fn test_file_reader_datapage_v2() {
         let test_file = get_test_file("datapage_v2.snappy.parquet");